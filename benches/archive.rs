@@ -0,0 +1,92 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Compares looking up names and prefixes in zero-copy archives with deserialising them first.
+//!
+//! Run with `cargo bench --features archive`.
+
+#![forbid(warnings)]
+#![deny(missing_docs, unsafe_code, unused)]
+
+use maidsafe_utilities::serialisation;
+use routing::{
+    archive::{self, Archive, ArchivedNames},
+    Prefix, XorName,
+};
+use std::time::{Duration, Instant};
+use unwrap::unwrap;
+
+const NAME_COUNT: usize = 100_000;
+const LOOKUPS: usize = 1_000;
+const ITERATIONS: u32 = 20;
+
+fn measure<F: FnMut()>(label: &str, mut f: F) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let per_iteration = start.elapsed() / ITERATIONS;
+    println!("{:<40} {:>10} us", label, micros(per_iteration));
+}
+
+fn micros(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000 + u64::from(duration.subsec_micros())
+}
+
+fn main() {
+    let mut names: Vec<XorName> = (0..NAME_COUNT).map(|_| rand::random()).collect();
+    names.sort();
+    let targets: Vec<XorName> = names
+        .iter()
+        .step_by(NAME_COUNT / LOOKUPS)
+        .cloned()
+        .collect();
+
+    let serialised = unwrap!(serialisation::serialise(&names));
+    let archived = archive::archive_names(&names);
+    println!(
+        "{} names: serialised {} bytes, archived {} bytes",
+        NAME_COUNT,
+        serialised.len(),
+        archived.len()
+    );
+
+    measure("names: deserialise, then look up", || {
+        let names: Vec<XorName> = unwrap!(serialisation::deserialise(&serialised));
+        assert!(targets
+            .iter()
+            .all(|target| names.binary_search(target).is_ok()));
+    });
+    measure("names: look up in archive", || {
+        let names = unwrap!(ArchivedNames::new(&archived));
+        assert!(targets.iter().all(|target| names.contains(target)));
+    });
+
+    let prefixes: Vec<Prefix<XorName>> = names
+        .iter()
+        .take(LOOKUPS)
+        .enumerate()
+        .map(|(i, name)| Prefix::new(i % 64, *name))
+        .collect();
+    let serialised: Vec<_> = prefixes
+        .iter()
+        .map(|prefix| unwrap!(serialisation::serialise(prefix)))
+        .collect();
+    let archived: Vec<_> = prefixes.iter().map(Archive::archive).collect();
+
+    measure("prefixes: deserialise", || {
+        for bytes in &serialised {
+            let _: Prefix<XorName> = unwrap!(serialisation::deserialise(bytes));
+        }
+    });
+    measure("prefixes: read from archive", || {
+        for bytes in &archived {
+            let _ = unwrap!(archive::unarchive_prefix(bytes));
+        }
+    });
+}
@@ -0,0 +1,80 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Measures the time and size overhead of encrypting hop messages.
+//!
+//! Run with `cargo bench --features mock_base --bench hop_encryption`.
+
+#![forbid(warnings)]
+#![deny(missing_docs, unsafe_code, unused)]
+
+use maidsafe_utilities::serialisation::serialise;
+use routing::{EncryptedHopMessage, FullId, HopMessage};
+use std::time::{Duration, Instant};
+use unwrap::unwrap;
+
+const ITERATIONS: u32 = 100;
+
+fn measure<F: FnMut()>(mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn micros(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000 + u64::from(duration.subsec_micros())
+}
+
+fn main() {
+    let sender = FullId::new();
+    let recipients: Vec<_> = (0..8).map(|_| FullId::new()).collect();
+
+    for &payload_len in &[1024, 64 * 1024, 1024 * 1024] {
+        let hop_msg = unwrap!(HopMessage::put_idata(&sender, payload_len));
+        let plain_len = unwrap!(serialise(&hop_msg)).len();
+        let plain = measure(|| {
+            let _ = unwrap!(serialise(&hop_msg));
+        });
+
+        for &recipient_count in &[1, 8] {
+            let targets = &recipients[..recipient_count];
+            let encrypt = measure(|| {
+                let encrypted = unwrap!(EncryptedHopMessage::new(
+                    &hop_msg,
+                    &sender,
+                    targets.iter().map(FullId::public_id)
+                ));
+                let _ = unwrap!(serialise(&encrypted));
+            });
+
+            let encrypted = unwrap!(EncryptedHopMessage::new(
+                &hop_msg,
+                &sender,
+                targets.iter().map(FullId::public_id)
+            ));
+            let decrypt = measure(|| {
+                let _ = unwrap!(encrypted.open(&targets[0]));
+            });
+
+            let encrypted_len = unwrap!(serialise(&encrypted)).len();
+            println!(
+                "{:>7} byte payload, {} recipients: serialise {:>6} us, encrypt {:>6} us, \
+                 decrypt {:>6} us, {} bytes on the wire instead of {}",
+                payload_len,
+                recipient_count,
+                micros(plain),
+                micros(encrypt),
+                micros(decrypt),
+                encrypted_len,
+                plain_len
+            );
+        }
+    }
+}
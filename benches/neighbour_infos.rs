@@ -0,0 +1,87 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Compares the targeted and the full cleaning of the neighbour infos for a section with many
+//! neighbours, and a pass of the periodic compaction.
+//!
+//! Run with `cargo bench --features mock_base --bench neighbour_infos`.
+
+#![forbid(warnings)]
+#![deny(missing_docs, unsafe_code, unused)]
+
+use routing::{NetworkGenerator, Prefix, XorName, Xorable, MIN_SECTION_SIZE};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use unwrap::unwrap;
+
+fn micros(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000 + u64::from(duration.subsec_micros())
+}
+
+// Returns `count` distinct prefixes of length `bit_count` which are all neighbours of the prefix
+// of `bit_count / 2` zeros.
+fn many_neighbour_prefixes(bit_count: usize, count: usize) -> Vec<Prefix<XorName>> {
+    let our_len = bit_count / 2;
+    (0..count)
+        .map(|i| {
+            let flipped = i % our_len;
+            let extension = i / our_len;
+            let name = (our_len..bit_count)
+                .fold(XorName::default().with_bit(flipped, true), |name, bit| {
+                    name.with_bit(bit, (extension >> (bit - our_len)) & 1 == 1)
+                });
+            Prefix::new(bit_count, name)
+        })
+        .collect()
+}
+
+fn main() {
+    let our_pfx = unwrap!(Prefix::from_str("00000000"));
+
+    for &count in &[64, 256, 1024] {
+        let prefixes = many_neighbour_prefixes(16, count);
+        let generator = NetworkGenerator::new(
+            MIN_SECTION_SIZE,
+            Some((our_pfx, 8))
+                .into_iter()
+                .chain(prefixes.iter().map(|pfx| (*pfx, 1))),
+        );
+        let our_id = *unwrap!(unwrap!(generator
+            .section_infos()
+            .find(|info| *info.prefix() == our_pfx))
+        .members()
+        .iter()
+        .next());
+        let mut chain = unwrap!(generator.chain(&our_id));
+
+        let start = Instant::now();
+        for pfx in &prefixes {
+            chain.clean_neighbour_infos(Some(pfx));
+        }
+        let targeted = start.elapsed();
+
+        let start = Instant::now();
+        for _ in &prefixes {
+            chain.clean_neighbour_infos(None);
+        }
+        let full = start.elapsed();
+
+        let start = Instant::now();
+        chain.compact_neighbour_infos();
+        let compaction = start.elapsed();
+
+        assert_eq!(chain.other_prefixes().len(), count);
+        println!(
+            "{:>4} neighbours: targeted {:>8} us, full scan {:>8} us, compaction batch {:>6} us",
+            count,
+            micros(targeted),
+            micros(full),
+            micros(compaction)
+        );
+    }
+}
@@ -80,7 +80,7 @@ mod unnamed {
     use maidsafe_utilities::SeededRng;
     use rand::distributions::{IndependentSample, Range};
     use rand::{random, thread_rng, Rng, ThreadRng};
-    use routing::{MutableData, Value, MIN_SECTION_SIZE};
+    use routing::{AppId, MutableData, Value, MIN_SECTION_SIZE};
     use safe_crypto::PublicSignKey;
     use std::collections::BTreeMap;
     use std::io::Write;
@@ -312,7 +312,7 @@ mod unnamed {
 
     fn gen_mutable_data<R: Rng>(rng: &mut R, owner: PublicSignKey) -> MutableData {
         let name = rng.gen();
-        let tag = rng.gen_range(10_000, 20_000);
+        let tag = AppId::from_name("ci_test").tag(rng.gen());
 
         let num_entries = rng.gen_range(0, 10);
         let mut entries = BTreeMap::new();
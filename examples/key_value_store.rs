@@ -82,7 +82,7 @@ mod unnamed {
     use maidsafe_utilities::log;
     use maidsafe_utilities::serialisation::{deserialise, serialise};
     use maidsafe_utilities::thread::{self, Joiner};
-    use routing::{MutableData, Value, XorName};
+    use routing::{AppId, MutableData, Value, XorName};
     use safe_crypto;
     use std::io::{self, Write};
     use std::iter;
@@ -115,7 +115,7 @@ Options:
   network discovery patterns to use, or which seed nodes to use.
 ";
 
-    const TAG: u64 = 10_000;
+    const APP_NAME: &str = "key_value_store";
     const KEY: &[u8] = &[];
 
     #[derive(Debug, Deserialize)]
@@ -214,7 +214,10 @@ Options:
         /// Get data from the network.
         pub fn get(&mut self, what: &str) {
             let name = Self::calculate_key_name(what);
-            match self.example_client.get_mdata_value(name, TAG, KEY.to_vec()) {
+            match self
+                .example_client
+                .get_mdata_value(name, Self::tag(), KEY.to_vec())
+            {
                 Ok(value) => {
                     let content = unwrap!(deserialise::<String>(&value.content));
                     println!("Got value {:?} on key {:?}", content, what);
@@ -236,7 +239,7 @@ Options:
 
             let data = unwrap!(MutableData::new(
                 name,
-                TAG,
+                Self::tag(),
                 Default::default(),
                 entries,
                 owners,
@@ -246,6 +249,11 @@ Options:
             }
         }
 
+        /// The type tag of our data, in the block of tags owned by this application.
+        fn tag() -> u64 {
+            AppId::from_name(APP_NAME).tag(0)
+        }
+
         fn calculate_key_name(key: &str) -> XorName {
             XorName(safe_crypto::hash(key.as_bytes()))
         }
@@ -173,6 +173,50 @@ impl ExampleClient {
         recv_response!(self, ListMDataEntries, name, msg_id)
     }
 
+    /// Send a `ListMDataEntriesRange` request to the network and return the page of entries
+    /// and the key the next page starts at.
+    ///
+    /// This is a blocking call and will wait indefinitely for the response.
+    #[allow(unused)]
+    pub fn list_mdata_entries_range(
+        &mut self,
+        name: XorName,
+        tag: u64,
+        start_key: Vec<u8>,
+        limit: u64,
+    ) -> Result<(BTreeMap<Vec<u8>, Value>, Option<Vec<u8>>), ClientError> {
+        let msg_id = MessageId::new();
+        unwrap!(self.client.list_mdata_entries_range(
+            Authority::NaeManager(name),
+            name,
+            tag,
+            start_key,
+            limit,
+            msg_id,
+        ));
+        recv_response!(self, ListMDataEntriesRange, name, msg_id)
+    }
+
+    /// Fetch all entries of the given MutableData in pages of at most `page_size` entries.
+    ///
+    /// This is a blocking call and will wait indefinitely for the responses.
+    #[allow(unused)]
+    pub fn list_mdata_entries_paged(
+        &mut self,
+        name: XorName,
+        tag: u64,
+        page_size: u64,
+    ) -> Result<BTreeMap<Vec<u8>, Value>, ClientError> {
+        let mut entries = BTreeMap::new();
+        let mut start_key = Some(Vec::new());
+        while let Some(key) = start_key.take() {
+            let (page, next_key) = self.list_mdata_entries_range(name, tag, key, page_size)?;
+            entries.extend(page);
+            start_key = next_key;
+        }
+        Ok(entries)
+    }
+
     /// Send a `GetMDataValue` request to the network and return the data received in
     /// the response.
     ///
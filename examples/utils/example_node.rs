@@ -128,6 +128,15 @@ impl ExampleNode {
             Request::ListMDataEntries { name, tag, msg_id } => {
                 self.handle_list_mdata_entries_request(src, dst, name, tag, msg_id)
             }
+            Request::ListMDataEntriesRange {
+                name,
+                tag,
+                start_key,
+                limit,
+                msg_id,
+            } => self.handle_list_mdata_entries_range_request(
+                src, dst, name, tag, &start_key, limit, msg_id,
+            ),
             Request::GetMDataValue {
                 name,
                 tag,
@@ -301,6 +310,38 @@ impl ExampleNode {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
+    fn handle_list_mdata_entries_range_request(
+        &mut self,
+        src: Authority<XorName>,
+        dst: Authority<XorName>,
+        name: XorName,
+        tag: u64,
+        start_key: &[u8],
+        limit: u64,
+        msg_id: MessageId,
+    ) {
+        match (src, dst) {
+            (src @ Authority::Client { .. }, dst @ Authority::NaeManager(_)) => {
+                let res = if let Some(data) = self.mdata_store.get(&(name, tag)) {
+                    Ok(data.entries_range(start_key, limit as usize))
+                } else {
+                    trace!(
+                        "{:?} ListMDataEntriesRange request failed for {:?}.",
+                        self.get_debug_name(),
+                        (name, tag)
+                    );
+                    Err(ClientError::NoSuchData)
+                };
+
+                unwrap!(self
+                    .node
+                    .send_list_mdata_entries_range_response(dst, src, res, msg_id))
+            }
+            (src, dst) => unreachable!("Wrong Src and Dest Authority {:?} - {:?}", src, dst),
+        }
+    }
+
     fn handle_get_mdata_value_request(
         &mut self,
         src: Authority<XorName>,
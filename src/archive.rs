@@ -0,0 +1,371 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Zero-copy archives of names, prefixes, section infos and proof chains.
+//!
+//! An archive is a fixed-layout byte encoding which can be read in place, e.g. from a memory-mapped
+//! file, without deserialising it first. The `Archived*` types are views into such a buffer: they
+//! validate its length once, and then read each field directly from the bytes when accessed.
+//!
+//! Section infos and proof chains also carry their regular serialisation after the fixed part, so
+//! the full value can still be restored via `unarchive` when needed.
+
+use crate::{
+    chain::{SectionInfo, SectionProofChain},
+    error::RoutingError,
+    sha3::Digest256,
+    Prefix, XorName, XOR_NAME_LEN,
+};
+use maidsafe_utilities::serialisation;
+use std::cmp::Ordering;
+
+/// The size of an archived `Prefix<XorName>`: the bit count followed by the name.
+pub const ARCHIVED_PREFIX_LEN: usize = 2 + XOR_NAME_LEN;
+
+const VERSION_LEN: usize = 8;
+const COUNT_LEN: usize = 4;
+const DIGEST_LEN: usize = 32;
+/// Size of the fixed part of an archived `SectionInfo` preceding the member names.
+const SECTION_INFO_HEADER_LEN: usize = VERSION_LEN + ARCHIVED_PREFIX_LEN + DIGEST_LEN + COUNT_LEN;
+/// Size of each archived key info in a `SectionProofChain`.
+const KEY_INFO_LEN: usize = VERSION_LEN + ARCHIVED_PREFIX_LEN;
+
+/// A value that can be written in the archive format.
+pub trait Archive {
+    /// Appends the archived form of `self` to `out`.
+    fn archive_to(&self, out: &mut Vec<u8>);
+
+    /// Returns the archived form of `self`.
+    fn archive(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.archive_to(&mut out);
+        out
+    }
+}
+
+impl Archive for XorName {
+    fn archive_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.0);
+    }
+}
+
+impl Archive for Prefix<XorName> {
+    fn archive_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&u16_to_bytes(self.bit_count() as u16));
+        self.name().archive_to(out);
+    }
+}
+
+impl Archive for SectionInfo {
+    fn archive_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&u64_to_bytes(*self.version()));
+        self.prefix().archive_to(out);
+        out.extend_from_slice(self.hash());
+        let names: Vec<_> = self.members().iter().map(|id| *id.name()).collect();
+        out.extend_from_slice(&u32_to_bytes(names.len() as u32));
+        out.extend(archive_names(&names));
+        // Serialising a `SectionInfo` can't fail.
+        out.extend(serialisation::serialise(self).unwrap_or_default());
+    }
+}
+
+impl Archive for SectionProofChain {
+    fn archive_to(&self, out: &mut Vec<u8>) {
+        let key_infos: Vec<_> = self.all_key_infos().collect();
+        out.extend_from_slice(&u32_to_bytes(key_infos.len() as u32));
+        for key_info in key_infos {
+            out.extend_from_slice(&u64_to_bytes(*key_info.version()));
+            key_info.prefix().archive_to(out);
+        }
+        out.extend(serialisation::serialise(self).unwrap_or_default());
+    }
+}
+
+/// Returns the archive of an index of `names`, which are sorted and deduplicated so that
+/// `ArchivedNames::contains` can use a binary search.
+pub fn archive_names<'a, I: IntoIterator<Item = &'a XorName>>(names: I) -> Vec<u8> {
+    let mut names: Vec<_> = names.into_iter().collect();
+    names.sort();
+    names.dedup();
+    let mut out = Vec::with_capacity(names.len() * XOR_NAME_LEN);
+    for name in names {
+        name.archive_to(&mut out);
+    }
+    out
+}
+
+/// Reads an archived `XorName`.
+pub fn unarchive_name(bytes: &[u8]) -> Result<XorName, RoutingError> {
+    if bytes.len() != XOR_NAME_LEN {
+        return Err(RoutingError::InvalidArchive);
+    }
+    let mut name = [0; XOR_NAME_LEN];
+    name.copy_from_slice(bytes);
+    Ok(XorName(name))
+}
+
+/// Reads an archived `Prefix<XorName>`.
+pub fn unarchive_prefix(bytes: &[u8]) -> Result<Prefix<XorName>, RoutingError> {
+    if bytes.len() != ARCHIVED_PREFIX_LEN {
+        return Err(RoutingError::InvalidArchive);
+    }
+    let bit_count = u16::from(bytes[0]) | u16::from(bytes[1]) << 8;
+    let name = unarchive_name(&bytes[2..])?;
+    Ok(Prefix::new(bit_count as usize, name))
+}
+
+/// A view of an archived, sorted index of names.
+#[derive(Clone, Copy, Debug)]
+pub struct ArchivedNames<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ArchivedNames<'a> {
+    /// Returns a view of the names archived by `archive_names`.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, RoutingError> {
+        if bytes.len() % XOR_NAME_LEN != 0 {
+            return Err(RoutingError::InvalidArchive);
+        }
+        Ok(Self { bytes })
+    }
+
+    /// Returns the number of names.
+    pub fn len(&self) -> usize {
+        self.bytes.len() / XOR_NAME_LEN
+    }
+
+    /// Returns whether there are no names.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Returns the name at `index`.
+    pub fn get(&self, index: usize) -> Option<XorName> {
+        if index >= self.len() {
+            return None;
+        }
+        unarchive_name(self.name_bytes(index)).ok()
+    }
+
+    /// Returns whether the index contains `name`, without decoding the other names.
+    pub fn contains(&self, name: &XorName) -> bool {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.name_bytes(mid).cmp(&name.0[..]) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return true,
+            }
+        }
+        false
+    }
+
+    /// Returns an iterator over the names, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = XorName> + 'a {
+        let bytes = self.bytes;
+        bytes
+            .chunks(XOR_NAME_LEN)
+            .filter_map(|chunk| unarchive_name(chunk).ok())
+    }
+
+    fn name_bytes(&self, index: usize) -> &'a [u8] {
+        let bytes = self.bytes;
+        &bytes[index * XOR_NAME_LEN..(index + 1) * XOR_NAME_LEN]
+    }
+}
+
+/// A view of an archived `SectionInfo`.
+#[derive(Clone, Copy, Debug)]
+pub struct ArchivedSectionInfo<'a> {
+    bytes: &'a [u8],
+    members: ArchivedNames<'a>,
+}
+
+impl<'a> ArchivedSectionInfo<'a> {
+    /// Returns a view of the section info archived in `bytes`.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, RoutingError> {
+        if bytes.len() < SECTION_INFO_HEADER_LEN {
+            return Err(RoutingError::InvalidArchive);
+        }
+        let count = u32_from_bytes(&bytes[SECTION_INFO_HEADER_LEN - COUNT_LEN..]) as usize;
+        let members_end = SECTION_INFO_HEADER_LEN + count * XOR_NAME_LEN;
+        if bytes.len() < members_end {
+            return Err(RoutingError::InvalidArchive);
+        }
+        let members = ArchivedNames::new(&bytes[SECTION_INFO_HEADER_LEN..members_end])?;
+        Ok(Self { bytes, members })
+    }
+
+    /// Returns the section version.
+    pub fn version(&self) -> u64 {
+        u64_from_bytes(self.bytes)
+    }
+
+    /// Returns the section prefix.
+    pub fn prefix(&self) -> Result<Prefix<XorName>, RoutingError> {
+        unarchive_prefix(&self.bytes[VERSION_LEN..VERSION_LEN + ARCHIVED_PREFIX_LEN])
+    }
+
+    /// Returns the hash of the section info.
+    pub fn hash(&self) -> Digest256 {
+        let start = VERSION_LEN + ARCHIVED_PREFIX_LEN;
+        let mut hash = [0; DIGEST_LEN];
+        hash.copy_from_slice(&self.bytes[start..start + DIGEST_LEN]);
+        hash
+    }
+
+    /// Returns the names of the section's members.
+    pub fn member_names(&self) -> ArchivedNames<'a> {
+        self.members
+    }
+
+    /// Deserialises the complete section info.
+    pub fn unarchive(&self) -> Result<SectionInfo, RoutingError> {
+        let start = SECTION_INFO_HEADER_LEN + self.members.len() * XOR_NAME_LEN;
+        Ok(serialisation::deserialise(&self.bytes[start..])?)
+    }
+}
+
+/// A view of an archived `SectionProofChain`.
+#[derive(Clone, Copy, Debug)]
+pub struct ArchivedProofChain<'a> {
+    bytes: &'a [u8],
+    len: usize,
+}
+
+impl<'a> ArchivedProofChain<'a> {
+    /// Returns a view of the proof chain archived in `bytes`.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, RoutingError> {
+        if bytes.len() < COUNT_LEN {
+            return Err(RoutingError::InvalidArchive);
+        }
+        let len = u32_from_bytes(bytes) as usize;
+        if bytes.len() < COUNT_LEN + len * KEY_INFO_LEN {
+            return Err(RoutingError::InvalidArchive);
+        }
+        Ok(Self { bytes, len })
+    }
+
+    /// Returns the number of keys in the chain, including the genesis key.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the chain holds no keys. An archived chain always has a genesis key.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the version and prefix of the section which held the key at `index`.
+    pub fn key_info(&self, index: usize) -> Option<(u64, Prefix<XorName>)> {
+        if index >= self.len {
+            return None;
+        }
+        let start = COUNT_LEN + index * KEY_INFO_LEN;
+        let version = u64_from_bytes(&self.bytes[start..]);
+        let prefix = unarchive_prefix(&self.bytes[start + VERSION_LEN..start + KEY_INFO_LEN]);
+        prefix.ok().map(|prefix| (version, prefix))
+    }
+
+    /// Deserialises the complete proof chain.
+    pub fn unarchive(&self) -> Result<SectionProofChain, RoutingError> {
+        let start = COUNT_LEN + self.len * KEY_INFO_LEN;
+        Ok(serialisation::deserialise(&self.bytes[start..])?)
+    }
+}
+
+fn u16_to_bytes(value: u16) -> [u8; 2] {
+    [value as u8, (value >> 8) as u8]
+}
+
+fn u32_to_bytes(value: u32) -> [u8; COUNT_LEN] {
+    let mut bytes = [0; COUNT_LEN];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = (value >> (8 * i)) as u8;
+    }
+    bytes
+}
+
+fn u64_to_bytes(value: u64) -> [u8; VERSION_LEN] {
+    let mut bytes = [0; VERSION_LEN];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = (value >> (8 * i)) as u8;
+    }
+    bytes
+}
+
+fn u32_from_bytes(bytes: &[u8]) -> u32 {
+    bytes[..COUNT_LEN]
+        .iter()
+        .enumerate()
+        .fold(0, |value, (i, byte)| value | u32::from(*byte) << (8 * i))
+}
+
+fn u64_from_bytes(bytes: &[u8]) -> u64 {
+    bytes[..VERSION_LEN]
+        .iter()
+        .enumerate()
+        .fold(0, |value, (i, byte)| value | u64::from(*byte) << (8 * i))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chain::SectionKeyInfo, id::FullId, network_params::NetworkParams};
+    use std::collections::BTreeSet;
+    use std::str::FromStr;
+    use unwrap::unwrap;
+
+    #[test]
+    fn names_and_prefixes() {
+        let names: Vec<XorName> = (0..20).map(|_| rand::random()).collect();
+        let archive = archive_names(&names);
+        let index = unwrap!(ArchivedNames::new(&archive));
+        assert_eq!(index.len(), names.len());
+        assert!(names.iter().all(|name| index.contains(name)));
+        assert!(!index.contains(&rand::random()));
+        let mut sorted = names.clone();
+        sorted.sort();
+        assert_eq!(index.iter().collect::<Vec<_>>(), sorted);
+        assert!(ArchivedNames::new(&archive[1..]).is_err());
+
+        let prefix = unwrap!(Prefix::from_str("1011"));
+        assert_eq!(unwrap!(unarchive_prefix(&prefix.archive())), prefix);
+    }
+
+    #[test]
+    fn section_info_and_proof_chain() {
+        let prefix = unwrap!(Prefix::from_str("01"));
+        let members: BTreeSet<_> = (0..5)
+            .map(|_| *FullId::within_range(&prefix.range_inclusive()).public_id())
+            .collect();
+        let sec_info = unwrap!(SectionInfo::new(members.clone(), prefix, None));
+
+        let archive = sec_info.archive();
+        let archived = unwrap!(ArchivedSectionInfo::new(&archive));
+        assert_eq!(archived.version(), *sec_info.version());
+        assert_eq!(unwrap!(archived.prefix()), prefix);
+        assert_eq!(archived.hash(), *sec_info.hash());
+        assert_eq!(archived.member_names().len(), members.len());
+        assert!(members
+            .iter()
+            .all(|id| archived.member_names().contains(id.name())));
+        assert_eq!(unwrap!(archived.unarchive()), sec_info);
+        assert!(ArchivedSectionInfo::new(&archive[..SECTION_INFO_HEADER_LEN]).is_err());
+
+        let key_info = SectionKeyInfo::from_section_info(&sec_info, NetworkParams::default());
+        let chain = SectionProofChain::from_genesis(key_info);
+        let archive = chain.archive();
+        let archived = unwrap!(ArchivedProofChain::new(&archive));
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived.key_info(0), Some((*sec_info.version(), prefix)));
+        assert_eq!(archived.key_info(1), None);
+        assert_eq!(unwrap!(archived.unarchive()), chain);
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+/// The maximum number of bits set per inserted digest.
+const MAX_HASH_COUNT: usize = 16;
+
+/// A bloom filter of 32-byte digests, which forgets old digests by keeping two generations of
+/// bits: once `capacity` digests were inserted into the current generation, the previous one is
+/// dropped and a new one started.
+///
+/// The digests are expected to be cryptographic hashes already, so their bytes are used as the
+/// hash values directly.
+pub struct BloomFilter {
+    current: Vec<u64>,
+    previous: Vec<u64>,
+    bit_count: usize,
+    hash_count: usize,
+    capacity: usize,
+    inserted: usize,
+}
+
+impl BloomFilter {
+    /// Creates a filter using `bytes` bytes for both generations, with each generation sized for
+    /// `capacity` digests.
+    pub fn new(bytes: usize, capacity: usize) -> Self {
+        let words = (bytes / 16).max(1);
+        let bit_count = words * 64;
+        let capacity = capacity.max(1);
+        // The false positive rate is lowest when the number of bits set per digest is
+        // `ln(2) * bits / digests`.
+        let hash_count = ((bit_count as f64 / capacity as f64) * 2f64.ln()).round() as usize;
+        BloomFilter {
+            current: vec![0; words],
+            previous: vec![0; words],
+            bit_count,
+            hash_count: hash_count.max(1).min(MAX_HASH_COUNT),
+            capacity,
+            inserted: 0,
+        }
+    }
+
+    /// Returns whether `digest` may have been inserted into one of the two generations. `false`
+    /// means it definitely wasn't.
+    pub fn contains(&self, digest: &[u8; 32]) -> bool {
+        let contained_in = |bits: &[u64]| {
+            self.bit_indices(digest)
+                .all(|index| bits[index / 64] & (1 << (index % 64)) != 0)
+        };
+        contained_in(&self.current) || contained_in(&self.previous)
+    }
+
+    /// Inserts `digest` into the current generation, starting a new one first if it is full.
+    pub fn insert(&mut self, digest: &[u8; 32]) {
+        if self.inserted >= self.capacity {
+            self.previous = std::mem::replace(&mut self.current, vec![0; self.previous.len()]);
+            self.inserted = 0;
+        }
+        for index in self.bit_indices(digest).collect::<Vec<_>>() {
+            self.current[index / 64] |= 1 << (index % 64);
+        }
+        self.inserted += 1;
+    }
+
+    // Derives the bit indices of `digest` by double hashing with two 64-bit words of it.
+    fn bit_indices<'a>(&'a self, digest: &[u8; 32]) -> impl Iterator<Item = usize> + 'a {
+        let first = word(&digest[0..8]);
+        let second = word(&digest[8..16]) | 1;
+        (0..self.hash_count as u64).map(move |i| {
+            (first.wrapping_add(i.wrapping_mul(second)) % self.bit_count as u64) as usize
+        })
+    }
+}
+
+fn word(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0, |word, byte| (word << 8) | u64::from(*byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{self, Rng};
+
+    #[test]
+    fn contains_inserted_and_forgets_old_generations() {
+        let mut filter = BloomFilter::new(1024, 10);
+        let digests: Vec<[u8; 32]> = (0..30).map(|_| rand::thread_rng().gen()).collect();
+
+        for digest in &digests[..10] {
+            filter.insert(digest);
+        }
+        assert!(digests[..10].iter().all(|digest| filter.contains(digest)));
+
+        // The first ten are kept in the previous generation while the next ten are inserted...
+        for digest in &digests[10..20] {
+            filter.insert(digest);
+        }
+        assert!(digests[..20].iter().all(|digest| filter.contains(digest)));
+
+        // ... and dropped afterwards.
+        for digest in &digests[20..] {
+            filter.insert(digest);
+        }
+        assert!(digests[10..].iter().all(|digest| filter.contains(digest)));
+        assert!(digests[..10].iter().any(|digest| !filter.contains(digest)));
+    }
+}
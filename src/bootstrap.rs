@@ -0,0 +1,246 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{error::RoutingError, quic_p2p::NodeInfo, storage::Storage};
+use maidsafe_utilities::serialisation;
+use std::fmt::{self, Debug, Formatter};
+use std::net::SocketAddr;
+
+/// The storage namespace the bootstrap cache is saved in.
+const BOOTSTRAP_NAMESPACE: &str = "bootstrap";
+/// The key the bootstrap contacts are saved under.
+const CONTACTS_KEY: &[u8] = b"contacts";
+
+/// The number of consecutive failed connections after which a contact is moved behind all others.
+pub const MAX_CONTACT_FAILURES: u32 = 3;
+/// The number of contacts, in order of priority, a node bootstraps off.
+pub const BOOTSTRAP_CONTACTS_TRIED: usize = 8;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Contact {
+    node_info: NodeInfo,
+    failures: u32,
+}
+
+/// The contacts a node bootstraps off, in order of priority.
+///
+/// Contacts can be added, removed and prioritised at runtime through `Node::bootstrap_cache_mut`.
+/// The node records the outcome of its connections to them, and a contact failing
+/// `MAX_CONTACT_FAILURES` times in a row is demoted behind all others. Only the first
+/// `BOOTSTRAP_CONTACTS_TRIED` contacts are passed to the transport when the node is created, so
+/// demoted contacts are only tried on later starts, once the ones ahead of them failed too.
+///
+/// A cache opened with `Cache::open` is kept in the given `Storage` and saved whenever a contact
+/// is demoted or the node bootstrapped, so it survives restarts.
+#[derive(Default)]
+pub struct Cache {
+    contacts: Vec<Contact>,
+    storage: Option<Box<dyn Storage>>,
+}
+
+impl Cache {
+    /// Creates an empty cache which isn't persisted.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens the cache kept in `storage`, or an empty one if `storage` doesn't contain any yet.
+    pub fn open(storage: Box<dyn Storage>) -> Result<Self, RoutingError> {
+        let contacts = match storage.get(BOOTSTRAP_NAMESPACE, CONTACTS_KEY)? {
+            Some(bytes) => serialisation::deserialise(&bytes)?,
+            None => Vec::new(),
+        };
+        Ok(Self {
+            contacts,
+            storage: Some(storage),
+        })
+    }
+
+    /// Writes the cache back to its storage. Does nothing if it wasn't opened with `Cache::open`.
+    pub fn save(&mut self) -> Result<(), RoutingError> {
+        if let Some(ref mut storage) = self.storage {
+            storage.put(
+                BOOTSTRAP_NAMESPACE,
+                CONTACTS_KEY,
+                serialisation::serialise(&self.contacts)?,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Adds `node_info` behind all other contacts. Returns `false` if it's known already.
+    pub fn insert(&mut self, node_info: NodeInfo) -> bool {
+        if self.position(&node_info.peer_addr).is_some() {
+            return false;
+        }
+        self.contacts.push(Contact {
+            node_info,
+            failures: 0,
+        });
+        true
+    }
+
+    /// Removes the contact at `peer_addr`, returning it if it was present.
+    pub fn remove(&mut self, peer_addr: &SocketAddr) -> Option<NodeInfo> {
+        self.position(peer_addr)
+            .map(|index| self.contacts.remove(index).node_info)
+    }
+
+    /// Moves the contact at `peer_addr` ahead of all others and forgets its failures. Returns
+    /// `false` if it's unknown.
+    pub fn prioritise(&mut self, peer_addr: &SocketAddr) -> bool {
+        match self.position(peer_addr) {
+            Some(index) => {
+                let mut contact = self.contacts.remove(index);
+                contact.failures = 0;
+                self.contacts.insert(0, contact);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the contacts in order of priority.
+    pub fn contacts(&self) -> impl Iterator<Item = &NodeInfo> {
+        self.contacts.iter().map(|contact| &contact.node_info)
+    }
+
+    /// Returns the contacts a node should bootstrap off.
+    pub fn bootstrap_contacts(&self) -> impl Iterator<Item = &NodeInfo> {
+        self.contacts().take(BOOTSTRAP_CONTACTS_TRIED)
+    }
+
+    /// Returns the number of contacts.
+    pub fn len(&self) -> usize {
+        self.contacts.len()
+    }
+
+    /// Returns whether there are no contacts.
+    pub fn is_empty(&self) -> bool {
+        self.contacts.is_empty()
+    }
+
+    /// Records that we connected to `peer_addr`, clearing its failures.
+    pub(crate) fn record_success(&mut self, peer_addr: &SocketAddr) {
+        if let Some(index) = self.position(peer_addr) {
+            self.contacts[index].failures = 0;
+        }
+    }
+
+    /// Records that the connection to `peer_addr` failed. Returns whether it was demoted.
+    pub(crate) fn record_failure(&mut self, peer_addr: &SocketAddr) -> bool {
+        let index = match self.position(peer_addr) {
+            Some(index) => index,
+            None => return false,
+        };
+        self.contacts[index].failures += 1;
+        if self.contacts[index].failures < MAX_CONTACT_FAILURES {
+            return false;
+        }
+
+        let mut contact = self.contacts.remove(index);
+        contact.failures = 0;
+        self.contacts.push(contact);
+        true
+    }
+
+    fn position(&self, peer_addr: &SocketAddr) -> Option<usize> {
+        self.contacts
+            .iter()
+            .position(|contact| contact.node_info.peer_addr == *peer_addr)
+    }
+}
+
+impl Debug for Cache {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("Cache")
+            .field("contacts", &self.contacts)
+            .field("persisted", &self.storage.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::FileStorage;
+    use rand;
+    use std::{env, fs};
+    use unwrap::unwrap;
+
+    fn node_info(port: u16) -> NodeInfo {
+        NodeInfo {
+            peer_addr: unwrap!(format!("127.0.0.1:{}", port).parse()),
+            peer_cert_der: vec![],
+        }
+    }
+
+    fn ports(cache: &Cache) -> Vec<u16> {
+        cache.contacts().map(|info| info.peer_addr.port()).collect()
+    }
+
+    #[test]
+    fn add_remove_and_prioritise() {
+        let mut cache = Cache::new();
+        assert!(cache.insert(node_info(1)));
+        assert!(cache.insert(node_info(2)));
+        assert!(cache.insert(node_info(3)));
+        assert!(!cache.insert(node_info(2)));
+        assert_eq!(ports(&cache), vec![1, 2, 3]);
+
+        assert!(cache.prioritise(&node_info(3).peer_addr));
+        assert_eq!(ports(&cache), vec![3, 1, 2]);
+        assert_eq!(cache.remove(&node_info(1).peer_addr), Some(node_info(1)));
+        assert_eq!(cache.remove(&node_info(1).peer_addr), None);
+        assert!(!cache.prioritise(&node_info(1).peer_addr));
+        assert_eq!(ports(&cache), vec![3, 2]);
+    }
+
+    #[test]
+    fn unreachable_contacts_are_demoted() {
+        let mut cache = Cache::new();
+        for port in 1..=BOOTSTRAP_CONTACTS_TRIED as u16 + 1 {
+            let _ = cache.insert(node_info(port));
+        }
+        let first = node_info(1).peer_addr;
+
+        // A success in between resets the count...
+        assert!(!cache.record_failure(&first));
+        cache.record_success(&first);
+        for _ in 1..MAX_CONTACT_FAILURES {
+            assert!(!cache.record_failure(&first));
+        }
+        assert_eq!(ports(&cache)[0], 1);
+
+        // ...but consecutive failures move the contact behind all others.
+        assert!(cache.record_failure(&first));
+        assert_eq!(ports(&cache)[0], 2);
+        assert!(cache
+            .bootstrap_contacts()
+            .all(|info| info.peer_addr != first));
+        assert_eq!(cache.contacts().last(), Some(&node_info(1)));
+    }
+
+    #[test]
+    fn saved_cache_survives_reopening() {
+        let root = env::temp_dir().join(format!("routing-bootstrap-{}", rand::random::<u64>()));
+        let open = || unwrap!(Cache::open(Box::new(unwrap!(FileStorage::open(&root)))));
+
+        let mut cache = open();
+        assert!(cache.is_empty());
+        let _ = cache.insert(node_info(1));
+        let _ = cache.insert(node_info(2));
+        assert!(cache.prioritise(&node_info(2).peer_addr));
+        unwrap!(cache.save());
+
+        assert_eq!(ports(&open()), vec![2, 1]);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
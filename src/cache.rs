@@ -6,7 +6,11 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use crate::data::ImmutableData;
 use crate::messages::{Request, Response};
+use crate::xor_name::XorName;
+use lru_time_cache::LruCache;
+use std::cell::RefCell;
 
 /// A cache that stores `Response`s keyed by `Requests`. Should be implemented
 /// by layers above routing.
@@ -28,3 +32,84 @@ impl Cache for NullCache {
     }
     fn put(&self, _: Response) {}
 }
+
+/// A cache of the `ImmutableData` in the `GetIData` responses relayed by a node, keyed by name, so
+/// popular chunks are served by the nearest hop instead of their managers. Once `capacity` chunks
+/// are cached, the least recently used one is dropped.
+///
+/// Enabled by setting `Config::relay_cache_size`, unless the node is given a cache of its own.
+pub struct IDataCache(RefCell<LruCache<XorName, ImmutableData>>);
+
+impl IDataCache {
+    /// Creates a cache holding at most `capacity` chunks.
+    pub fn new(capacity: usize) -> Self {
+        IDataCache(RefCell::new(LruCache::with_capacity(capacity)))
+    }
+}
+
+impl Cache for IDataCache {
+    fn get(&self, request: &Request) -> Option<Response> {
+        if let Request::GetIData { ref name, msg_id } = *request {
+            self.0
+                .borrow_mut()
+                .get(name)
+                .map(|data| Response::GetIData {
+                    res: Ok(data.clone()),
+                    msg_id,
+                })
+        } else {
+            None
+        }
+    }
+
+    fn put(&self, response: Response) {
+        if let Response::GetIData { res: Ok(data), .. } = response {
+            let _ = self.0.borrow_mut().insert(*data.name(), data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MessageId;
+
+    #[test]
+    fn idata_cache_evicts_least_recently_used() {
+        let cache = IDataCache::new(2);
+        let chunks: Vec<_> = (0..3u8).map(|i| ImmutableData::new(vec![i; 10])).collect();
+        let get = |data: &ImmutableData| {
+            let request = Request::GetIData {
+                name: *data.name(),
+                msg_id: MessageId::new(),
+            };
+            match cache.get(&request) {
+                Some(Response::GetIData {
+                    res: Ok(cached),
+                    msg_id,
+                }) => {
+                    assert_eq!(msg_id, *request.message_id());
+                    Some(cached)
+                }
+                _ => None,
+            }
+        };
+
+        for data in &chunks[..2] {
+            cache.put(Response::GetIData {
+                res: Ok(data.clone()),
+                msg_id: MessageId::new(),
+            });
+        }
+        assert_eq!(get(&chunks[0]), Some(chunks[0].clone()));
+
+        // The first chunk was used more recently, so the second one is dropped.
+        cache.put(Response::GetIData {
+            res: Ok(chunks[2].clone()),
+            msg_id: MessageId::new(),
+        });
+        assert_eq!(get(&chunks[0]), Some(chunks[0].clone()));
+        assert_eq!(get(&chunks[1]), None);
+        assert_eq!(get(&chunks[2]), Some(chunks[2].clone()));
+    }
+}
@@ -9,9 +9,12 @@
 //! Types emulating the BLS functionality until proper BLS lands
 use super::{ProofSet, SectionInfo};
 use crate::{
+    error::RoutingError,
     id::{FullId, PublicId},
-    QUORUM_DENOMINATOR, QUORUM_NUMERATOR,
+    network_params::NetworkParams,
+    xor_name::XorName,
 };
+use safe_crypto::{PublicEncryptKey, SymmetricKey};
 use std::{collections::BTreeMap, fmt};
 
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone, Hash, Serialize, Deserialize)]
@@ -35,6 +38,32 @@ pub struct Signature {
     sigs: BTreeMap<PublicId, SignatureShare>,
 }
 
+/// A message encrypted to a section's `PublicKey`.
+///
+/// The message is encrypted with a fresh symmetric key, which is encrypted to each member of the
+/// section using an ephemeral key pair. Unlike with real BLS, any single member can decrypt it,
+/// without combining decryption shares from more than `threshold` members.
+#[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Hash, Serialize, Deserialize)]
+pub struct Ciphertext {
+    sender: PublicEncryptKey,
+    keys: BTreeMap<XorName, Vec<u8>>,
+    payload: Vec<u8>,
+}
+
+impl Ciphertext {
+    /// Decrypts the message, if `full_id` is one of the members it was encrypted to.
+    #[cfg(feature = "node")]
+    pub fn decrypt(&self, full_id: &FullId) -> Result<Vec<u8>, RoutingError> {
+        let encrypted_key = self
+            .keys
+            .get(full_id.public_id().name())
+            .ok_or(RoutingError::InvalidMessage)?;
+        let shared_secret = full_id.encrypting_private_key().shared_secret(&self.sender);
+        let key: SymmetricKey = shared_secret.decrypt(encrypted_key)?;
+        Ok(key.decrypt(&self.payload)?)
+    }
+}
+
 impl Signature {
     pub fn from_proof_set(proofs: ProofSet) -> Self {
         Self { sigs: proofs.sigs }
@@ -62,8 +91,9 @@ impl PublicKeyShare {
 }
 
 impl PublicKeySet {
-    pub fn from_section_info(sec_info: SectionInfo) -> Self {
-        let threshold = sec_info.members().len() * QUORUM_NUMERATOR / QUORUM_DENOMINATOR;
+    pub fn from_section_info(sec_info: SectionInfo, network_params: NetworkParams) -> Self {
+        // More than `threshold` signatures must be a quorum of the section.
+        let threshold = network_params.quorum_size(sec_info.members().len()) - 1;
         Self {
             threshold,
             sec_info,
@@ -99,8 +129,11 @@ impl PublicKeySet {
 }
 
 impl PublicKey {
-    pub fn from_section_info(sec_info: &SectionInfo) -> Self {
-        PublicKey(PublicKeySet::from_section_info(sec_info.clone()))
+    pub fn from_section_info(sec_info: &SectionInfo, network_params: NetworkParams) -> Self {
+        PublicKey(PublicKeySet::from_section_info(
+            sec_info.clone(),
+            network_params,
+        ))
     }
 
     pub fn verify<M: AsRef<[u8]>>(&self, sig: &Signature, msg: M) -> bool {
@@ -115,6 +148,31 @@ impl PublicKey {
     }
 }
 
+impl PublicKey {
+    /// Encrypts `msg` so that the members of the section can decrypt it.
+    #[cfg(feature = "client")]
+    pub fn encrypt(&self, msg: &[u8]) -> Result<Ciphertext, RoutingError> {
+        let (sender, secret_key) = safe_crypto::gen_encrypt_keypair();
+        let key = SymmetricKey::new();
+        let payload = key.encrypt(&msg.to_vec())?;
+        let keys = self
+            .0
+            .sec_info
+            .members()
+            .iter()
+            .map(|pub_id| {
+                let shared_secret = secret_key.shared_secret(pub_id.encrypting_public_key());
+                Ok((*pub_id.name(), shared_secret.encrypt(&key)?))
+            })
+            .collect::<Result<_, RoutingError>>()?;
+        Ok(Ciphertext {
+            sender,
+            keys,
+            payload,
+        })
+    }
+}
+
 impl fmt::Debug for PublicKey {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         write!(formatter, "BLS-PublicKey({:?})", self.0.sec_info)
@@ -127,8 +185,8 @@ impl fmt::Debug for PublicKey {
 pub(super) struct BlsPublicKeyForSectionKeyInfo(PublicKey);
 
 impl BlsPublicKeyForSectionKeyInfo {
-    pub fn from_section_info(sec_info: &SectionInfo) -> Self {
-        Self(PublicKey::from_section_info(sec_info))
+    pub fn from_section_info(sec_info: &SectionInfo, network_params: NetworkParams) -> Self {
+        Self(PublicKey::from_section_info(sec_info, network_params))
     }
 
     pub fn key(&self) -> &PublicKey {
@@ -153,7 +211,7 @@ mod test {
         let ids: Vec<_> = (0..size).map(|_| FullId::new()).collect();
         let pub_ids = ids.iter().map(|full_id| *full_id.public_id()).collect();
         let sec_info = unwrap!(SectionInfo::new(pub_ids, Default::default(), None));
-        let pk_set = PublicKeySet::from_section_info(sec_info);
+        let pk_set = PublicKeySet::from_section_info(sec_info, NetworkParams::default());
 
         (pk_set, ids.into_iter().map(SecretKeyShare).collect())
     }
@@ -188,4 +246,17 @@ mod test {
 
         assert!(pk_set.public_key().verify(&sig, &data));
     }
+
+    #[test]
+    #[cfg(all(feature = "client", feature = "node"))]
+    fn test_encryption() {
+        let (pk_set, sk_shares) = gen_section(4);
+        let data = [1u8, 2, 3, 4, 5, 6];
+
+        let ciphertext = unwrap!(pk_set.public_key().encrypt(&data));
+        for sk_share in &sk_shares {
+            assert_eq!(unwrap!(ciphertext.decrypt(&sk_share.0)), data);
+        }
+        assert!(ciphertext.decrypt(&FullId::new()).is_err());
+    }
 }
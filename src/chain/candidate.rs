@@ -6,10 +6,23 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::OnlinePayload;
-use crate::{id::PublicId, utils::LogIdent, utils::XorTargetInterval};
+use super::{ExpectCandidatePayload, OnlinePayload};
+use crate::{
+    id::PublicId,
+    routing_table::Authority,
+    time::{Duration, Instant},
+    utils::LogIdent,
+    utils::XorTargetInterval,
+    xor_name::XorName,
+};
 use log::LogLevel;
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, VecDeque};
+
+/// The maximum number of candidates waiting for the current one to complete.
+pub const MAX_QUEUED_CANDIDATES: usize = 8;
+/// The maximum number of queued candidates which joined via the same proxy, so a single proxy
+/// can't crowd out everyone else.
+const MAX_QUEUED_CANDIDATES_PER_PROXY: usize = 2;
 
 /// A candidate (if any) may be in different stages of the resource proof process.
 /// When we consensus to accept them for resource proof, move to `AcceptedForResourceProof`
@@ -138,3 +151,148 @@ impl Candidate {
         }
     }
 }
+
+/// The candidates our section agreed to accept once the current one completes, in the order the
+/// `ExpectCandidate` events accumulated.
+#[derive(Default)]
+pub struct CandidateQueue {
+    candidates: VecDeque<QueuedCandidate>,
+}
+
+struct QueuedCandidate {
+    vote: ExpectCandidatePayload,
+    queued_at: Instant,
+    purge_voted: bool,
+}
+
+impl CandidateQueue {
+    /// Appends `vote` to the queue. Returns `false` if the queue is full, or if too many of the
+    /// queued candidates joined via the same proxy.
+    pub fn push(&mut self, vote: ExpectCandidatePayload) -> bool {
+        if self.candidates.len() >= MAX_QUEUED_CANDIDATES {
+            return false;
+        }
+        let proxy = proxy_name(&vote.old_client_auth);
+        let same_proxy = self
+            .candidates
+            .iter()
+            .filter(|queued| proxy_name(&queued.vote.old_client_auth) == proxy)
+            .count();
+        if same_proxy >= MAX_QUEUED_CANDIDATES_PER_PROXY {
+            return false;
+        }
+        self.candidates.push_back(QueuedCandidate {
+            vote,
+            queued_at: Instant::now(),
+            purge_voted: false,
+        });
+        true
+    }
+
+    /// Removes and returns the candidate which has waited longest.
+    pub fn pop(&mut self) -> Option<ExpectCandidatePayload> {
+        self.candidates.pop_front().map(|queued| queued.vote)
+    }
+
+    /// Removes and returns the candidate with the given old ID, if it is queued.
+    pub fn remove(&mut self, old_public_id: &PublicId) -> Option<ExpectCandidatePayload> {
+        let index = self
+            .candidates
+            .iter()
+            .position(|queued| queued.vote.old_public_id == *old_public_id)?;
+        self.candidates.remove(index).map(|queued| queued.vote)
+    }
+
+    /// Returns whether the candidate with the given old ID is queued.
+    pub fn contains(&self, old_public_id: &PublicId) -> bool {
+        self.candidates
+            .iter()
+            .any(|queued| queued.vote.old_public_id == *old_public_id)
+    }
+
+    /// Returns the old IDs of the candidates which have waited longer than `timeout`, unless they
+    /// were returned before. They stay queued until our section agrees to purge them.
+    pub fn take_expired(&mut self, timeout: Duration) -> Vec<PublicId> {
+        self.candidates
+            .iter_mut()
+            .filter(|queued| !queued.purge_voted && queued.queued_at.elapsed() > timeout)
+            .map(|queued| {
+                queued.purge_voted = true;
+                queued.vote.old_public_id
+            })
+            .collect()
+    }
+
+    /// Forgets all queued candidates.
+    pub fn clear(&mut self) {
+        self.candidates.clear()
+    }
+
+    /// Returns the number of queued candidates.
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// Returns whether no candidates are queued.
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+}
+
+/// The name candidates are grouped by for fair admission: their proxy's if they are clients,
+/// otherwise their own.
+fn proxy_name(old_client_auth: &Authority<XorName>) -> XorName {
+    match *old_client_auth {
+        Authority::Client {
+            proxy_node_name, ..
+        } => proxy_node_name,
+        ref auth => auth.name(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{constants::QUEUED_CANDIDATE_TIMEOUT, id::FullId, types::MessageId};
+
+    fn vote(proxy_node_name: XorName) -> ExpectCandidatePayload {
+        let old_public_id = *FullId::new().public_id();
+        ExpectCandidatePayload {
+            old_public_id,
+            old_client_auth: Authority::Client {
+                client_id: old_public_id,
+                proxy_node_name,
+            },
+            message_id: MessageId::new(),
+            dst_name: rand::random(),
+        }
+    }
+
+    #[test]
+    fn queue_is_bounded_and_fair() {
+        let mut queue = CandidateQueue::default();
+        let busy_proxy = rand::random();
+        let first = vote(busy_proxy);
+        assert!(queue.push(first.clone()));
+        assert!(queue.push(vote(busy_proxy)));
+        assert!(!queue.push(vote(busy_proxy)));
+
+        let others: Vec<_> = (2..MAX_QUEUED_CANDIDATES)
+            .map(|_| vote(rand::random()))
+            .collect();
+        for other in &others {
+            assert!(queue.push(other.clone()));
+        }
+        assert!(!queue.push(vote(rand::random())));
+        assert_eq!(queue.len(), MAX_QUEUED_CANDIDATES);
+
+        assert!(queue.contains(&others[0].old_public_id));
+        assert_eq!(
+            queue.remove(&others[0].old_public_id),
+            Some(others[0].clone())
+        );
+        assert!(!queue.contains(&others[0].old_public_id));
+        assert_eq!(queue.pop(), Some(first));
+        assert!(queue.take_expired(QUEUED_CANDIDATE_TIMEOUT).is_empty());
+    }
+}
@@ -7,30 +7,69 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
-    candidate::Candidate,
-    shared_state::{PrefixChange, SectionKeyInfo, SharedState},
-    GenesisPfxInfo, NetworkEvent, OnlinePayload, Proof, ProofSet, SectionInfo, SectionProofChain,
+    candidate::{Candidate, CandidateQueue},
+    event_cache::EventCache,
+    fork::ForkEvidence,
+    join_filter::JoinFilter,
+    journal::{EventJournal, JournalEntry},
+    membership::MembershipStatement,
+    peer_score::{Misbehaviour, PeerScores},
+    replay,
+    shared_state::{PendingUpgrade, PrefixChange, SectionKeyInfo, SharedState},
+    snapshot::{ChainSnapshot, VersionedSnapshot, SNAPSHOT_KEY, SNAPSHOT_NAMESPACE},
+    stability_guard::StabilityGuard,
+    vote_timeout::{PendingEvent, PendingVote, VoteTimeout, DEFAULT_VOTE_TTL},
+    AckMessagePayload, ExpectCandidatePayload, GenesisPfxInfo, NetworkEvent, OnlinePayload, Proof,
+    ProofSet, SectionInfo, SectionProofChain,
 };
 use crate::{
+    client_manager::Accounts,
+    constants::SPLIT_BUFFER,
     error::RoutingError,
     id::PublicId,
+    network_params::NetworkParams,
+    parsec,
     routing_table::{Authority, Error},
     sha3::Digest256,
+    storage::{Storage, WriteBatch},
+    time::{Duration, Instant},
     utils::LogIdent,
     utils::XorTargetInterval,
     Prefix, XorName, Xorable,
 };
 use itertools::Itertools;
 use log::LogLevel;
-use std::cmp::Ordering;
+use maidsafe_utilities::serialisation;
+use std::cmp::{self, Ordering};
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{self, Debug, Display, Formatter};
 use std::iter;
 use std::mem;
+use std::ops::Bound;
+
+/// Maximum number of neighbour infos checked by one call to `Chain::compact_neighbour_infos`.
+const NEIGHBOUR_COMPACTION_BATCH: usize = 32;
+
+/// Returns whether the neighbour info `newer` makes `older` obsolete: they are compatible and
+/// `newer` has a higher version.
+/// DO NOT SUPPORT MERGE: Not consider newer if the older one was extension (split).
+fn supersedes(
+    (newer_pfx, newer_info): (&Prefix<XorName>, &SectionInfo),
+    (older_pfx, older_info): (&Prefix<XorName>, &SectionInfo),
+) -> bool {
+    newer_pfx.is_compatible(older_pfx)
+        && newer_info.version() > older_info.version()
+        && !older_pfx.is_extension_of(newer_pfx)
+}
 
-/// Amount added to `min_section_size` when deciding whether a bucket split can happen. This helps
-/// protect against rapid splitting and merging in the face of moderate churn.
-const SPLIT_BUFFER: usize = 1;
+/// Returns the genesis of a journal starting from `state`.
+fn checkpoint_genesis(state: &SharedState) -> Result<GenesisPfxInfo, RoutingError> {
+    Ok(GenesisPfxInfo {
+        first_info: state.our_info().clone(),
+        first_state_serialized: state.get_genesis_related_info()?,
+        latest_info: Default::default(),
+    })
+}
 
 /// Returns the delivery group size based on the section size `n`
 pub fn delivery_group_size(n: usize) -> usize {
@@ -38,10 +77,50 @@ pub fn delivery_group_size(n: usize) -> usize {
     (n + 2) / 3
 }
 
+/// Which members of the destination sections `Chain::targets` delivers a message to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum DeliveryScope {
+    /// All members, e.g. for client data, which any member can relay or serve.
+    AllMembers,
+    /// Only the elders, for messages which are only acted on by voting, such as section infos.
+    /// This keeps consensus traffic independent of the number of adults in a section.
+    Elders,
+}
+
+/// A coarse logical clock derived from the section versions, e.g. to expire caches or schedule
+/// periodic duties.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct NetworkEpoch {
+    /// The version of our section. It advances with every change to the section and is agreed on
+    /// by all its elders, so it can be used to act in lockstep with the rest of the section.
+    pub section: u64,
+    /// The highest version of all the sections we know. Versions increase along each section's
+    /// history across splits and merges, so this approximates how far the whole network has
+    /// progressed.
+    pub network: u64,
+}
+
+/// A change to the neighbours we hold an inferred info for, reported by
+/// `Chain::take_neighbour_changes`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NeighbourChange {
+    /// Neighbour `from` split into sections including `prefix`, whose own info we haven't received
+    /// yet. We use the info of `from` for `prefix` until it arrives.
+    Inferred {
+        /// The prefix of the section we inferred.
+        prefix: Prefix<XorName>,
+        /// The prefix of the section it split off.
+        from: Prefix<XorName>,
+    },
+    /// We no longer hold an inferred info for `prefix`: its own info arrived, or it was superseded
+    /// or isn't our neighbour anymore.
+    Converged(Prefix<XorName>),
+}
+
 /// Data chain.
 pub struct Chain {
-    /// Minimum number of nodes we consider acceptable in a section
-    min_sec_size: usize,
+    /// The parameters of the network, such as the minimum section size and the quorum.
+    network_params: NetworkParams,
     /// This node's public ID.
     our_id: PublicId,
     /// The shared state of the section.
@@ -52,27 +131,67 @@ pub struct Chain {
     /// A map containing network events that have not been handled yet, together with their proofs
     /// that have been collected so far. We are still waiting for more proofs, or to reach a state
     /// where we can handle the event.
-    // FIXME: Purge votes that are older than a given period.
+    /// Entries are dropped by `purge_stale_votes` once they are older than `vote_ttl`.
     chain_accumulator: BTreeMap<NetworkEvent, ProofSet>,
+    /// Tracks how long the events in `chain_accumulator` have been waiting for more proofs.
+    vote_timeout: VoteTimeout,
+    /// How long an event may wait in `chain_accumulator` before its votes are dropped.
+    vote_ttl: Duration,
     /// Events that were handled: Further incoming proofs for these can be ignored.
     completed_events: BTreeSet<NetworkEvent>,
     /// Pending events whose handling has been deferred due to an ongoing split or merge.
-    event_cache: BTreeSet<NetworkEvent>,
+    event_cache: EventCache,
     /// Current consensused candidate.
     candidate: Candidate,
+    /// The candidates waiting for the current one to complete.
+    candidate_queue: CandidateQueue,
+    /// The last neighbour prefix checked by `compact_neighbour_infos`, if it didn't reach the end.
+    compaction_cursor: Option<Prefix<XorName>>,
+    /// Neighbour infos conflicting with the one we hold for the same prefix and version, which is
+    /// included. We don't route through these sections until a successor or a key from a proof
+    /// chain tells which info is genuine.
+    disputed_infos: BTreeMap<Prefix<XorName>, BTreeSet<SectionInfo>>,
+    /// Neighbours for which our section accepted several infos with the same version. We don't
+    /// accept any successors of a forked info until a key from a proof chain tells which branch
+    /// is genuine.
+    forks: BTreeMap<Prefix<XorName>, ForkEvidence>,
+    /// Forks which have new branches not yet reported via `take_new_forks`.
+    unreported_forks: BTreeSet<Prefix<XorName>>,
+    /// Neighbours we hold the info of the section they split off for, until their own arrives.
+    inferred_neighbours: BTreeSet<Prefix<XorName>>,
+    /// Changes to `inferred_neighbours` not yet reported via `take_neighbour_changes`.
+    neighbour_changes: Vec<NeighbourChange>,
+    /// Withholds non-membership events while too much of our section changed recently, if set.
+    stability_guard: Option<StabilityGuard>,
+    /// When our section last changed.
+    last_change: Option<Instant>,
+    /// Misbehaviour of the peers voting in our section.
+    peer_scores: PeerScores,
+    /// Every how many versions of our section info a member is relocated, if at all.
+    relocation_interval: Option<u64>,
+    /// The age from which a node joins our section as an adult rather than an infant, if there
+    /// are infants at all.
+    adult_age: Option<u8>,
+    /// Every event accumulated since the chain was created or restored.
+    journal: EventJournal,
 }
 
 #[allow(clippy::len_without_is_empty)]
 impl Chain {
     /// Returns the minimum section size.
     pub fn min_sec_size(&self) -> usize {
-        self.min_sec_size
+        self.network_params.min_section_size
+    }
+
+    /// Returns the parameters of the network.
+    pub fn network_params(&self) -> &NetworkParams {
+        &self.network_params
     }
 
     /// Returns the number of nodes which need to exist in each subsection of a given section to
     /// allow it to be split.
     pub fn min_split_size(&self) -> usize {
-        self.min_sec_size + SPLIT_BUFFER
+        self.min_sec_size() + SPLIT_BUFFER
     }
 
     /// Collects prefixes of all sections known by the routing table into a `BTreeSet`.
@@ -85,18 +204,44 @@ impl Chain {
     }
 
     /// Create a new chain given genesis information
-    pub fn new(min_sec_size: usize, our_id: PublicId, gen_info: GenesisPfxInfo) -> Self {
+    pub fn new(network_params: NetworkParams, our_id: PublicId, gen_info: GenesisPfxInfo) -> Self {
         // TODO validate `gen_info` to contain adequate proofs
-        let is_member = gen_info.first_info.members().contains(&our_id);
+        let state = SharedState::new(gen_info.first_info.clone(), network_params);
+        let journal = EventJournal::new(network_params, our_id, gen_info);
+        Self::with_state(network_params, our_id, state, journal)
+    }
+
+    fn with_state(
+        network_params: NetworkParams,
+        our_id: PublicId,
+        state: SharedState,
+        journal: EventJournal,
+    ) -> Self {
+        let is_member = state.our_info().members().contains(&our_id);
         Self {
-            min_sec_size,
+            network_params,
             our_id,
-            state: SharedState::new(gen_info.first_info),
+            state,
             is_member,
             chain_accumulator: Default::default(),
+            vote_timeout: Default::default(),
+            vote_ttl: DEFAULT_VOTE_TTL,
             completed_events: Default::default(),
             event_cache: Default::default(),
             candidate: Candidate::None,
+            candidate_queue: CandidateQueue::default(),
+            compaction_cursor: None,
+            disputed_infos: BTreeMap::new(),
+            forks: BTreeMap::new(),
+            unreported_forks: BTreeSet::new(),
+            inferred_neighbours: BTreeSet::new(),
+            neighbour_changes: Vec::new(),
+            stability_guard: None,
+            last_change: None,
+            peer_scores: Default::default(),
+            relocation_interval: None,
+            adult_age: None,
+            journal,
         }
     }
 
@@ -118,6 +263,65 @@ impl Chain {
         self.state.get_genesis_related_info()
     }
 
+    /// Writes our section's history and our knowledge of the other sections to `storage`, so
+    /// they can be restored with `Chain::restore` after a restart.
+    ///
+    /// This is a checkpoint of the journal: as the snapshot contains the effect of every event
+    /// recorded so far, the journal is truncated to start from it. The snapshot and the truncated
+    /// journal replace the previous ones atomically, so a crash while saving leaves them intact.
+    pub fn save(&mut self, storage: &mut dyn Storage) -> Result<(), RoutingError> {
+        let snapshot = VersionedSnapshot::V6(ChainSnapshot::new(
+            self.network_params,
+            self.our_id,
+            &self.state,
+        ));
+        let mut batch = WriteBatch::new();
+        let _ = batch.put(
+            SNAPSHOT_NAMESPACE,
+            SNAPSHOT_KEY,
+            serialisation::serialise(&snapshot)?,
+        );
+        self.journal
+            .truncate(checkpoint_genesis(&self.state)?, storage, &mut batch)?;
+        storage.write(batch)
+    }
+
+    /// Writes the events accumulated since the last call, or since the last `Chain::save`, to
+    /// `storage`, so `Chain::restore` can reapply them on top of the last snapshot.
+    pub fn save_journal(&mut self, storage: &mut dyn Storage) -> Result<(), RoutingError> {
+        self.journal.save(storage)
+    }
+
+    /// Restores a chain saved by `Chain::save`, migrating snapshots written in an older format,
+    /// and reapplies the events saved by `Chain::save_journal` since. Returns `None` if `storage`
+    /// doesn't contain a snapshot.
+    ///
+    /// Votes, candidates and any split or merge in progress aren't restored: the node catches up
+    /// on those once it rejoins its section's consensus.
+    pub fn restore(storage: &dyn Storage) -> Result<Option<Self>, RoutingError> {
+        let bytes = match storage.get(SNAPSHOT_NAMESPACE, SNAPSHOT_KEY)? {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+        let snapshot = serialisation::deserialise::<VersionedSnapshot>(&bytes)?.into_latest();
+        let network_params = snapshot.network_params;
+        let our_id = snapshot.our_id;
+        let state = snapshot.into_shared_state();
+        let genesis = checkpoint_genesis(&state)?;
+        let journal = EventJournal::new(network_params, our_id, genesis.clone());
+        let mut chain = Self::with_state(network_params, our_id, state, journal);
+
+        // Only a journal truncated together with this snapshot starts from its state.
+        if let Some(saved_journal) = EventJournal::restore(storage)? {
+            if *saved_journal.genesis() == genesis {
+                replay::apply_entries(&mut chain, saved_journal.entries())?;
+                chain.journal = saved_journal;
+            }
+        }
+
+        Ok(Some(chain))
+    }
+
     /// Handles an accumulated parsec Observation for membership mutation.
     ///
     /// The provided proofs wouldn't be validated against the mapped NetworkEvent as they're
@@ -156,6 +360,7 @@ impl Chain {
             return Ok(());
         }
 
+        self.vote_timeout.insert(event);
         if self
             .chain_accumulator
             .insert(event.clone(), proof_set)
@@ -190,13 +395,22 @@ impl Chain {
             return Ok(());
         }
 
+        let voter = *proof.pub_id();
+        if self.contradicts_section_info_vote(event, &voter) {
+            warn!(
+                "{} {} voted for contradictory infos of our section: {:?}",
+                self, voter, event
+            );
+            self.record_misbehaviour(voter, Misbehaviour::ContradictorySectionInfo);
+        }
+
+        self.vote_timeout.insert(event);
         if !self
             .chain_accumulator
             .entry(event.clone())
             .or_insert_with(ProofSet::new)
             .add_proof(proof)
         {
-            // TODO: If detecting duplicate vote from peer, penalise.
             log_or_panic!(
                 LogLevel::Warn,
                 "{} Duplicate proof for {:?} in chain accumulator. {:?}",
@@ -204,18 +418,228 @@ impl Chain {
                 event,
                 self.chain_accumulator
             );
+            self.record_misbehaviour(voter, Misbehaviour::DuplicateProof);
         }
         Ok(())
     }
 
+    /// Returns whether `voter` already voted for a different info of our section with the same
+    /// version as `event`.
+    fn contradicts_section_info_vote(&self, event: &NetworkEvent, voter: &PublicId) -> bool {
+        let sec_info = match event.section_info() {
+            Some(sec_info) if sec_info.prefix().matches(self.our_id.name()) => sec_info,
+            _ => return false,
+        };
+        self.chain_accumulator.iter().any(|(other_event, proofs)| {
+            other_event.section_info().map_or(false, |other_info| {
+                other_info != sec_info
+                    && other_info.prefix() == sec_info.prefix()
+                    && other_info.version() == sec_info.version()
+                    && proofs.contains_id(voter)
+            })
+        })
+    }
+
+    /// Records misbehaviour of `pub_id` in voting. Once its score crosses the threshold, it is
+    /// returned by `take_malicious_peers`.
+    pub fn record_misbehaviour(&mut self, pub_id: PublicId, misbehaviour: Misbehaviour) {
+        if self.peer_scores.record(pub_id, misbehaviour) {
+            warn!(
+                "{} {} crossed the misbehaviour threshold with a score of {}.",
+                self,
+                pub_id,
+                self.peer_scores.score(&pub_id)
+            );
+        }
+    }
+
+    /// Sets the misbehaviour score at which a peer is considered malicious.
+    pub fn set_misbehaviour_threshold(&mut self, threshold: u64) {
+        self.peer_scores.set_threshold(threshold);
+    }
+
+    /// Returns the misbehaviour scores of all peers that misbehaved in voting.
+    pub fn peer_scores(&self) -> &BTreeMap<PublicId, u64> {
+        self.peer_scores.scores()
+    }
+
+    /// Returns the members of our section whose misbehaviour score crossed the threshold since
+    /// the last call, so we can vote them offline.
+    pub fn take_malicious_peers(&mut self) -> Vec<PublicId> {
+        let our_info = self.state.our_info();
+        self.peer_scores
+            .take_malicious()
+            .into_iter()
+            .filter(|pub_id| our_info.members().contains(pub_id))
+            .collect()
+    }
+
+    /// Returns the members which became elders with our latest section info, and the ones which
+    /// stopped being elders but are still members. Nodes which joined or left aren't included.
+    pub fn our_elder_changes(&self) -> (BTreeSet<PublicId>, BTreeSet<PublicId>) {
+        let new_info = self.our_info();
+        let old_info = match self.state.our_infos.iter().rev().nth(1) {
+            Some((old_info, _)) => old_info,
+            None => return (BTreeSet::new(), BTreeSet::new()),
+        };
+        let new_elders = new_info.elders(&self.network_params);
+        let old_elders = old_info.elders(&self.network_params);
+        let promoted = new_elders
+            .difference(&old_elders)
+            .filter(|pub_id| old_info.members().contains(pub_id))
+            .cloned()
+            .collect();
+        let demoted = old_elders
+            .difference(&new_elders)
+            .filter(|pub_id| new_info.members().contains(pub_id))
+            .cloned()
+            .collect();
+        (promoted, demoted)
+    }
+
+    /// Returns the members which joined our section with our latest section info.
+    pub fn our_new_members(&self) -> BTreeSet<PublicId> {
+        let old_info = match self.state.our_infos.iter().rev().nth(1) {
+            Some((old_info, _)) => old_info,
+            None => return BTreeSet::new(),
+        };
+        self.our_info()
+            .members()
+            .difference(old_info.members())
+            .cloned()
+            .collect()
+    }
+
+    /// Sets after how many churn events, i.e. versions of our section info, a member of our
+    /// section is relocated to another one. Relocation is disabled if `None`.
+    pub fn set_relocation_interval(&mut self, interval: Option<u64>) {
+        self.relocation_interval = interval.filter(|&interval| interval > 0);
+    }
+
+    /// Returns the member to relocate if our current section info completes another relocation
+    /// interval. This is the oldest member, with ties broken by closeness to the hash of the
+    /// section info, so all elders pick the same one. Nobody is relocated while a split or merge
+    /// is in progress, or if it would leave our section below the minimum size.
+    pub fn relocation_candidate(&self) -> Option<PublicId> {
+        let interval = self.relocation_interval?;
+        let our_info = self.our_info();
+        let version = *our_info.version();
+        if self.state.change != PrefixChange::None
+            || version == 0
+            || version % interval != 0
+            || our_info.members().len() <= self.min_sec_size()
+        {
+            return None;
+        }
+
+        let tie_breaker = XorName(*our_info.hash());
+        our_info
+            .members()
+            .iter()
+            .max_by(|lhs, rhs| {
+                lhs.age()
+                    .cmp(&rhs.age())
+                    .then_with(|| tie_breaker.cmp_distance(rhs.name(), lhs.name()))
+            })
+            .cloned()
+    }
+
+    /// Sets the age from which nodes join our section as adults. Younger ones join as infants, which
+    /// aren't part of our section info. If `None`, every node joins as an adult.
+    pub fn set_adult_age(&mut self, adult_age: Option<u8>) {
+        self.adult_age = adult_age;
+    }
+
+    /// Returns whether the candidate `pub_id` should join our section as an infant: it is younger
+    /// than the adult age, and our section already has enough adults without it.
+    pub fn joins_as_infant(&self, pub_id: &PublicId) -> bool {
+        self.adult_age
+            .map_or(false, |adult_age| pub_id.age() < adult_age)
+            && self.state.new_info.members().len() >= self.min_sec_size()
+    }
+
+    /// Returns the members of our section which aren't elders, excluding the infants.
+    pub fn our_adults(&self) -> BTreeSet<PublicId> {
+        let our_info = self.our_info();
+        our_info
+            .members()
+            .difference(&our_info.elders(&self.network_params))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the infants of our section.
+    pub fn our_infants(&self) -> &BTreeSet<PublicId> {
+        &self.state.our_infants
+    }
+
+    /// Returns the resource proof difficulty our section agreed to challenge candidates with.
+    pub fn join_difficulty(&self) -> u8 {
+        self.state.join_difficulty
+    }
+
+    /// Returns the joining nodes our section agreed to allow or refuse.
+    pub fn join_filter(&self) -> &JoinFilter {
+        &self.state.join_filter
+    }
+
+    /// Returns the protocol upgrade our section agreed to, unless our section info reached its
+    /// activation version already.
+    pub fn pending_upgrade(&self) -> Option<&PendingUpgrade> {
+        self.state
+            .upgrade
+            .as_ref()
+            .filter(|upgrade| *self.our_info().version() < upgrade.activation_version)
+    }
+
+    /// Returns whether protocol `version` is active: our section agreed to upgrade to it or a
+    /// later one, and our section info reached the activation version.
+    pub fn is_upgrade_active(&self, version: u16) -> bool {
+        self.state.upgrade.map_or(false, |upgrade| {
+            upgrade.version >= version && *self.our_info().version() >= upgrade.activation_version
+        })
+    }
+
+    /// Returns whether an upgrade to protocol `version` is newer than the one we agreed to, so
+    /// we should vote for it.
+    pub fn is_new_upgrade(&self, version: u16) -> bool {
+        self.state
+            .upgrade
+            .map_or(true, |upgrade| version > upgrade.version)
+    }
+
+    /// Returns the balances of the client accounts our section manages.
+    pub fn accounts(&self) -> &Accounts {
+        &self.state.accounts
+    }
+
+    /// Returns the sequence number our section agreed on for its latest user message from `src` to
+    /// `dst`.
+    pub fn msg_seq(&self, src: &Authority<XorName>, dst: &Authority<XorName>) -> u64 {
+        self.state.msg_seqs.get(&(*src, *dst)).cloned().unwrap_or(0)
+    }
+
+    /// Returns whether `pub_id` is an infant of our section.
+    pub fn is_infant(&self, pub_id: &PublicId) -> bool {
+        self.state.our_infants.contains(pub_id)
+    }
+
     /// Returns the next accumulated event.
     ///
     /// If the event is a `SectionInfo` or `NeighbourInfo`, it also updates the corresponding
     /// containers.
     pub fn poll(&mut self) -> Result<Option<NetworkEvent>, RoutingError> {
+        let prefix = *self.our_prefix();
+        let version = *self.our_info().version();
+        in_span!("chain_poll", prefix = prefix, version = version; self.poll_accumulated())
+    }
+
+    fn poll_accumulated(&mut self) -> Result<Option<NetworkEvent>, RoutingError> {
+        let is_unstable = self.instability().is_some();
         let opt_event_proofs = self
             .chain_accumulator
             .iter()
+            .filter(|&(event, _)| !is_unstable || event.is_membership_change())
             .find(|&(event, proofs)| self.is_valid_transition(event, proofs))
             .map(|(event, proofs)| (event.clone(), proofs.clone()));
         let (event, proofs) = match opt_event_proofs {
@@ -226,6 +650,9 @@ impl Chain {
             log_or_panic!(LogLevel::Warn, "Duplicate insert in completed events.");
         }
         let _ = self.chain_accumulator.remove(&event);
+        self.vote_timeout.complete(&event);
+        self.journal.append(event.clone(), proofs.clone());
+        trace!("Accumulated {:?}", event);
 
         match event {
             NetworkEvent::SectionInfo(ref sec_info) => {
@@ -258,17 +685,219 @@ impl Chain {
                 // TODO: Check that the section is known and not already merged.
                 let _ = self.state.merging.insert(digest);
             }
+            NetworkEvent::AddInfant(ref pub_id, _) => {
+                if self.our_prefix().matches(pub_id.name()) {
+                    let _ = self.state.our_infants.insert(*pub_id);
+                    // Infants don't wait for a new section info to complete joining.
+                    self.candidate.reset_if_member_of(&self.state.our_infants);
+                }
+            }
+            NetworkEvent::RemoveInfant(ref pub_id) => {
+                let _ = self.state.our_infants.remove(pub_id);
+            }
+            NetworkEvent::SetJoinDifficulty(difficulty) => {
+                self.state.join_difficulty = difficulty;
+            }
+            NetworkEvent::Allow(id) => self.state.join_filter.allow(id),
+            NetworkEvent::Deny(id) => self.state.join_filter.deny(id),
+            NetworkEvent::UpgradeSignal {
+                version,
+                activation_version,
+            } => {
+                if self.is_new_upgrade(version) {
+                    self.state.upgrade = Some(PendingUpgrade {
+                        version,
+                        activation_version,
+                    });
+                }
+            }
+            NetworkEvent::AccountCredit(ref payload) => {
+                self.state.accounts.credit(payload.account, payload.amount);
+            }
+            NetworkEvent::AccountDebit(ref payload) => {
+                self.state.accounts.debit(payload.account, payload.amount);
+            }
+            NetworkEvent::SequenceMessage(ref payload) => {
+                let route = (payload.src, payload.dst);
+                let seq = self.state.msg_seqs.get(&route).map_or(0, |seq| seq + 1);
+                let _ = self.state.msg_seqs.insert(route, seq);
+            }
             NetworkEvent::AddElder(_, _)
             | NetworkEvent::RemoveElder(_)
             | NetworkEvent::Online(_)
             | NetworkEvent::Offline(_)
             | NetworkEvent::ExpectCandidate(_)
             | NetworkEvent::PurgeCandidate(_)
-            | NetworkEvent::SendAckMessage(_) => (),
+            | NetworkEvent::SendAckMessage(_)
+            | NetworkEvent::Relocate(_)
+            | NetworkEvent::Promote(_)
+            | NetworkEvent::Demote(_)
+            | NetworkEvent::Refresh(_)
+            | NetworkEvent::FailedAudits(_) => (),
         }
         Ok(Some(event))
     }
 
+    /// Returns the record of every event accumulated since this chain was created or restored.
+    pub fn journal(&self) -> &EventJournal {
+        &self.journal
+    }
+
+    /// Applies an event from a journal, which was accumulated with the recorded proofs before.
+    /// Returns whether it was still valid in our current state.
+    pub(super) fn reapply(&mut self, entry: &JournalEntry) -> Result<bool, RoutingError> {
+        let event = entry.event();
+        let _ = self
+            .chain_accumulator
+            .insert(event.clone(), entry.proofs().clone());
+        let _ = self.poll()?;
+        Ok(!self.chain_accumulator.contains_key(event))
+    }
+
+    pub(super) fn into_shared_state(self) -> SharedState {
+        self.state
+    }
+
+    /// Returns the events which have been waiting in the accumulator for longer than expected,
+    /// given our section size and how long events took to accumulate so far, together with the
+    /// number of valid votes they have and the number they need. Each event is returned only once.
+    pub fn stalled_votes(&mut self) -> Vec<(NetworkEvent, usize, usize)> {
+        let our_info = self.state.our_info();
+        let stalled = self
+            .vote_timeout
+            .take_stalled(our_info.quorum_size(&self.network_params));
+        stalled
+            .into_iter()
+            .filter_map(|event| {
+                let have = self.valid_votes(self.chain_accumulator.get(&event)?);
+                let need = self.votes_needed(&event);
+                Some((event, have, need))
+            })
+            .collect()
+    }
+
+    /// Sets how long an event may wait for more votes before `purge_stale_votes` drops it.
+    pub fn set_vote_ttl(&mut self, ttl: Duration) {
+        self.vote_ttl = ttl;
+    }
+
+    /// Drops the votes for events which have been waiting in the accumulator for longer than the
+    /// vote TTL, or which are infos of our section superseded by our current one and so can never
+    /// be handled. Returns the number of events dropped.
+    pub fn purge_stale_votes(&mut self) -> usize {
+        let our_version = *self.state.our_info().version();
+        let our_id = self.our_id;
+        let superseded: Vec<_> = self
+            .chain_accumulator
+            .keys()
+            .filter(|event| {
+                event.section_info().map_or(false, |info| {
+                    info.prefix().matches(our_id.name()) && *info.version() <= our_version
+                })
+            })
+            .cloned()
+            .collect();
+        for event in &superseded {
+            self.vote_timeout.remove(event);
+        }
+
+        let expired = self.vote_timeout.take_expired(self.vote_ttl);
+        let mut count = 0;
+        for event in superseded.iter().chain(&expired) {
+            if self.chain_accumulator.remove(event).is_some() {
+                count += 1;
+            }
+        }
+        if count > 0 {
+            debug!("{} Purged {} stale votes.", self, count);
+        }
+        count
+    }
+
+    /// Returns the number of our events which were evicted from the cache of events voted for
+    /// during a split or merge since the last call, because the cache was full.
+    pub fn take_evicted_events(&mut self) -> usize {
+        self.event_cache.take_evicted()
+    }
+
+    /// Returns the events we voted for which haven't accumulated yet, with how long ago the first
+    /// vote for them arrived.
+    pub fn pending_votes(&self) -> Vec<PendingVote> {
+        self.chain_accumulator
+            .iter()
+            .filter(|(_, proofs)| proofs.contains_id(&self.our_id))
+            .map(|(event, proofs)| PendingVote {
+                event: event.descriptor(),
+                age: self.vote_timeout.age(event).unwrap_or_default(),
+                have: self.valid_votes(proofs),
+                need: self.votes_needed(event),
+            })
+            .collect()
+    }
+
+    /// Returns all events waiting in the accumulator for more proofs, with the elders of our
+    /// section which haven't voted for them yet. Useful for debugging sections which got stuck.
+    pub fn pending_events(&self) -> Vec<PendingEvent> {
+        let our_elders = self.state.our_info().elders(&self.network_params);
+        self.chain_accumulator
+            .iter()
+            .map(|(event, proofs)| PendingEvent {
+                event: event.descriptor(),
+                age: self.vote_timeout.age(event).unwrap_or_default(),
+                proofs: self.valid_votes(proofs),
+                need: self.votes_needed(event),
+                missing: our_elders
+                    .iter()
+                    .filter(|member| !proofs.contains_id(member))
+                    .cloned()
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Returns a statement of how long `member` has been part of our section, or `None` if it
+    /// isn't a current member.
+    ///
+    /// The statement only goes back as far as the infos of our section this node holds, as
+    /// returned by `our_history_slice`, so `since_version` may be later than the version the
+    /// member actually joined at.
+    pub fn membership_statement(&self, member: &PublicId) -> Option<MembershipStatement> {
+        let our_info = self.state.our_info();
+        if !our_info.members().contains(member) {
+            return None;
+        }
+        let first_info = self
+            .state
+            .our_infos()
+            .rev()
+            .take_while(|info| info.members().contains(member))
+            .last()
+            .unwrap_or(our_info);
+        Some(MembershipStatement {
+            member: *member,
+            prefix: *our_info.prefix(),
+            version: *our_info.version(),
+            since_version: *first_info.version(),
+            // A node's age goes up by one with each relocation.
+            relocations: u32::from(member.age()),
+        })
+    }
+
+    /// Returns the number of proofs in `proofs` from current elders of our section.
+    fn valid_votes(&self, proofs: &ProofSet) -> usize {
+        let our_elders = self.state.our_info().elders(&self.network_params);
+        proofs.ids().filter(|id| our_elders.contains(id)).count()
+    }
+
+    /// Returns the number of votes `event` needs to accumulate.
+    fn votes_needed(&self, event: &NetworkEvent) -> usize {
+        let our_info = self.state.our_info();
+        match *event {
+            NetworkEvent::SendAckMessage(_) => our_info.elders(&self.network_params).len(),
+            _ => our_info.quorum_size(&self.network_params),
+        }
+    }
+
     /// Adds a member to our section, creating a new `SectionInfo` in the process.
     /// If we need to split also returns an additional sibling `SectionInfo`.
     /// Should not be called while a pfx change is in progress.
@@ -335,7 +964,7 @@ impl Chain {
             Some(&self.state.new_info),
         )?;
 
-        if self.state.new_info.members().len() < self.min_sec_size {
+        if self.state.new_info.members().len() < self.min_sec_size() {
             // set to merge state to prevent extending chain any further.
             // We'd still not Vote for OurMerge until we've updated our_infos
             self.state.change = PrefixChange::Merging;
@@ -362,7 +991,7 @@ impl Chain {
     /// Returns `true` if we should merge.
     pub fn should_vote_for_merge(&self) -> bool {
         self.state
-            .should_vote_for_merge(self.min_sec_size, self.neighbour_infos())
+            .should_vote_for_merge(self.min_sec_size(), self.neighbour_infos())
     }
 
     /// Check inside the `neighbour_infos` failing which inside the chain accumulator if we have a
@@ -370,11 +999,12 @@ impl Chain {
     pub fn is_valid_neighbour_info(&self, sec_info: &SectionInfo, proofs: &ProofSet) -> bool {
         self.compatible_neighbour_info(sec_info)
             .map_or(false, |n_info| {
-                n_info == sec_info || n_info.proves_successor(sec_info, proofs)
+                n_info == sec_info
+                    || n_info.proves_successor(&self.network_params, sec_info, proofs)
+            })
+            || self.signed_events().any(|ni_event| {
+                ni_event.proves_successor_info(&self.network_params, sec_info, proofs)
             })
-            || self
-                .signed_events()
-                .any(|ni_event| ni_event.proves_successor_info(sec_info, proofs))
     }
 
     /// Finalises a split or merge - creates a `GenesisPfxInfo` for the new graph and returns the
@@ -386,7 +1016,8 @@ impl Chain {
 
         let completed_events = mem::replace(&mut self.completed_events, Default::default());
         let chain_acc = mem::replace(&mut self.chain_accumulator, Default::default());
-        let event_cache = mem::replace(&mut self.event_cache, Default::default());
+        self.vote_timeout.clear();
+        let event_cache = self.event_cache.take_all();
         let merges = mem::replace(&mut self.state.merging, Default::default())
             .into_iter()
             .map(NetworkEvent::NeighbourMerge);
@@ -442,6 +1073,35 @@ impl Chain {
         self.state.our_info_by_hash(hash)
     }
 
+    /// Returns the infos of our section with versions from `from_version` to `to_version`
+    /// inclusive which we still hold, oldest first. Each comes with the signatures of the elders
+    /// of its predecessor who accepted it, so together they prove who was a member of our section
+    /// at each of these versions. The first info of our chain has no signatures.
+    pub fn our_history_slice(
+        &self,
+        from_version: u64,
+        to_version: u64,
+    ) -> Vec<(&SectionInfo, &ProofSet)> {
+        self.state
+            .our_infos
+            .iter()
+            .filter(|(sec_info, _)| {
+                from_version <= *sec_info.version() && *sec_info.version() <= to_version
+            })
+            .map(|(sec_info, proofs)| (sec_info, proofs))
+            .collect()
+    }
+
+    /// Returns the info of our section with the given version, if we still hold it, with the
+    /// signatures of the elders of its predecessor who accepted it.
+    pub fn section_info_at(&self, version: u64) -> Option<(&SectionInfo, &ProofSet)> {
+        self.state
+            .our_infos
+            .iter()
+            .find(|(sec_info, _)| *sec_info.version() == version)
+            .map(|(sec_info, proofs)| (sec_info, proofs))
+    }
+
     /// If we are a member of the section yet. We consider ourselves to be one after we receive a
     /// `SectionInfo` block that contains us. After that we are expected to be involved in futher
     /// votings.
@@ -454,18 +1114,47 @@ impl Chain {
         self.state.neighbour_infos.values()
     }
 
+    /// Returns a summary of the chain state for crash reports. It contains only prefixes, versions
+    /// and counts: no member IDs, keys or proofs.
+    pub fn redacted_snapshot(&self) -> String {
+        let mut snapshot = format!(
+            "our_prefix: {:?}, our_version: {}, change: {:?}, is_member: {}, members: {}\n\
+             accumulated: {}, completed: {}, cached: {}, candidate: {}\n",
+            self.our_prefix(),
+            self.state.our_version(),
+            self.state.change,
+            self.is_member,
+            self.our_info().members().len(),
+            self.chain_accumulator.len(),
+            self.completed_events.len(),
+            self.event_cache.len(),
+            !self.candidate.is_none(),
+        );
+        for (pfx, sec_info) in &self.state.neighbour_infos {
+            snapshot.push_str(&format!(
+                "neighbour: {:?}, version: {}, members: {}\n",
+                pfx,
+                sec_info.version(),
+                sec_info.members().len()
+            ));
+        }
+        snapshot
+    }
+
     /// Return prefixes of all our neighbours
     pub fn other_prefixes(&self) -> BTreeSet<Prefix<XorName>> {
         self.state.neighbour_infos.keys().cloned().collect()
     }
 
     /// Checks if given `PublicId` is a valid peer by checking if we have them as a member of self
-    /// section or neighbours.
+    /// section, including the infants, or neighbours.
     pub fn is_peer_valid(&self, pub_id: &PublicId) -> bool {
-        self.neighbour_infos()
-            .chain(iter::once(self.state.our_info()))
-            .chain(iter::once(&self.state.new_info))
-            .any(|si| si.members().contains(pub_id))
+        self.is_infant(pub_id)
+            || self
+                .neighbour_infos()
+                .chain(iter::once(self.state.our_info()))
+                .chain(iter::once(&self.state.new_info))
+                .any(|si| si.members().contains(pub_id))
     }
 
     /// Returns a set of valid peers we should be connected to.
@@ -474,6 +1163,7 @@ impl Chain {
             .chain(iter::once(self.state.our_info()))
             .flat_map(SectionInfo::members)
             .chain(self.state.new_info.members())
+            .chain(&self.state.our_infants)
             .collect()
     }
 
@@ -535,6 +1225,55 @@ impl Chain {
         self.our_prefix().is_neighbour(sec_info.prefix()) && self.is_new(sec_info)
     }
 
+    /// Returns the section list to gossip to an elder of the section with `dst_prefix`: the part
+    /// of our history its section may not know yet, and the version of its key we know.
+    pub fn section_list_for(&self, dst_prefix: &Prefix<XorName>) -> (SectionProofChain, u64) {
+        let their_version = self
+            .state
+            .their_keys
+            .values()
+            .filter(|info| info.prefix().is_compatible(dst_prefix))
+            .map(|info| *info.version())
+            .max()
+            .unwrap_or(0);
+        (
+            self.prove(&Authority::PrefixSection(*dst_prefix)),
+            their_version,
+        )
+    }
+
+    /// Returns the votes which bring `their_keys` and `their_knowledge` up to date with a section
+    /// list gossiped by an elder of the section with `src_prefix`. The keys are only trusted if
+    /// `proof_chain` is valid, belongs to that section and starts from a key we know.
+    pub fn section_list_votes(
+        &self,
+        src_prefix: &Prefix<XorName>,
+        proof_chain: &SectionProofChain,
+        their_version: u64,
+    ) -> Vec<NetworkEvent> {
+        let mut votes = Vec::new();
+        let key_info = proof_chain.last_public_key_info();
+        if key_info.prefix().is_compatible(src_prefix)
+            && proof_chain.validate()
+            && self.check_trust(proof_chain)
+            && self.state.is_new_their_key(key_info)
+        {
+            votes.push(NetworkEvent::TheirKeyInfo(key_info.clone()));
+        }
+        if self.state.is_new_their_knowledge(src_prefix, their_version) {
+            votes.push(NetworkEvent::AckMessage(AckMessagePayload {
+                src_prefix: *src_prefix,
+                ack_version: their_version,
+            }));
+        }
+        votes
+    }
+
+    /// Returns whether `key_info` is newer than the key we know for its section.
+    pub fn is_new_their_key(&self, key_info: &SectionKeyInfo) -> bool {
+        self.state.is_new_their_key(key_info)
+    }
+
     /// Returns the index of the public key in our_history that will be trusted by the target
     /// Authority
     fn proving_index(&self, target: &Authority<XorName>) -> u64 {
@@ -549,10 +1288,25 @@ impl Chain {
     /// Provide a SectionProofChain that proves the given signature to the section with a given
     /// prefix
     pub fn prove(&self, target: &Authority<XorName>) -> SectionProofChain {
-        let first_index = self.proving_index(target);
+        self.prove_from_version(self.proving_index(target))
+    }
+
+    /// Provide a SectionProofChain of our keys starting with the one of the given version, or with
+    /// the oldest one we still have if it is older.
+    pub fn prove_from_version(&self, version: u64) -> SectionProofChain {
+        let first_index = version.saturating_sub(self.state.our_history.first_version());
         self.state.our_history.slice_from(first_index as usize)
     }
 
+    /// Trims our section's proof chain to the oldest key still needed by the sections we know of,
+    /// and to a hard cap. Returns the number of keys dropped.
+    ///
+    /// This happens automatically whenever our section info or other sections' knowledge of it
+    /// changes; calling it explicitly is only needed after restoring an older state.
+    pub fn compact_our_history(&mut self) -> usize {
+        self.state.compact_our_history()
+    }
+
     /// Returns `true` if the given `NetworkEvent` is already accumulated and can be skipped.
     fn should_skip_accumulator(&self, event: &NetworkEvent) -> bool {
         // FIXME: may also need to handle non SI votes to not get handled multiple times
@@ -566,13 +1320,11 @@ impl Chain {
             return true;
         }
 
-        // we can skip neighbour infos we've already accumulated
-        if self
-            .state
-            .neighbour_infos
-            .iter()
-            .any(|(pfx, sec_info)| pfx == si.prefix() && sec_info.version() >= si.version())
-        {
+        // we can skip neighbour infos we've already accumulated, but not a different info for the
+        // version we hold, which would be a fork
+        if self.state.neighbour_infos.iter().any(|(pfx, sec_info)| {
+            pfx == si.prefix() && (sec_info.version() > si.version() || sec_info == si)
+        }) {
             return true;
         }
 
@@ -586,26 +1338,44 @@ impl Chain {
     fn is_valid_transition(&self, network_event: &NetworkEvent, proofs: &ProofSet) -> bool {
         match *network_event {
             NetworkEvent::SectionInfo(ref info) => {
-                // Reject any info we have a newer compatible info for.
+                // Reject any info we have a newer compatible info for. A different info for the
+                // same neighbour and version is a fork, which is let through to be recorded.
                 let is_newer = |i: &SectionInfo| {
                     info.prefix().is_compatible(i.prefix()) && i.version() >= info.version()
                 };
+                let is_fork = |i: &SectionInfo| {
+                    i.prefix() == info.prefix() && i.version() == info.version() && i != info
+                };
                 if self
                     .compatible_neighbour_info(info)
                     .into_iter()
+                    .filter(|i| !is_fork(i))
                     .chain(iter::once(self.our_info()))
                     .any(is_newer)
                 {
                     return false;
                 }
 
+                // Don't extend a forked neighbour until we know which branch is genuine.
+                if self.forks.values().any(|fork| {
+                    fork.prefix().is_compatible(info.prefix()) && fork.version() < *info.version()
+                }) {
+                    return false;
+                }
+
                 // Ensure our infos is forming an unbroken sequence.
-                if info.prefix().matches(self.our_id.name()) {
-                    return info.is_successor_of(self.our_info())
-                        && self.our_info().is_quorum(proofs);
+                if info.prefix().matches(self.our_id.name())
+                    && !info.is_successor_of(self.our_info())
+                {
+                    return false;
                 }
 
-                self.our_info().is_quorum(proofs)
+                // Only verify the signatures once there are enough of them, as this is the
+                // expensive part.
+                let observation: parsec::Observation<NetworkEvent, PublicId> =
+                    parsec::Observation::OpaquePayload(network_event.clone());
+                self.our_info().is_quorum(&self.network_params, proofs)
+                    && proofs.verify_all_batched(&observation)
             }
 
             NetworkEvent::AddElder(_, _)
@@ -615,17 +1385,34 @@ impl Chain {
             | NetworkEvent::ExpectCandidate(_)
             | NetworkEvent::PurgeCandidate(_)
             | NetworkEvent::TheirKeyInfo(_)
-            | NetworkEvent::AckMessage(_) => {
-                self.state.change == PrefixChange::None && self.our_info().is_quorum(proofs)
+            | NetworkEvent::AckMessage(_)
+            | NetworkEvent::Relocate(_)
+            | NetworkEvent::Promote(_)
+            | NetworkEvent::Demote(_)
+            | NetworkEvent::AddInfant(_, _)
+            | NetworkEvent::RemoveInfant(_)
+            | NetworkEvent::Refresh(_)
+            | NetworkEvent::SetJoinDifficulty(_)
+            | NetworkEvent::Allow(_)
+            | NetworkEvent::Deny(_)
+            | NetworkEvent::UpgradeSignal { .. }
+            | NetworkEvent::AccountCredit(_)
+            | NetworkEvent::AccountDebit(_)
+            | NetworkEvent::SequenceMessage(_)
+            | NetworkEvent::FailedAudits(_) => {
+                self.state.change == PrefixChange::None
+                    && self.our_info().is_quorum(&self.network_params, proofs)
             }
             NetworkEvent::SendAckMessage(_) => {
                 // We may not reach consensus if malicious peer, but when we do we know all our
                 // nodes have updated `their_keys`.
                 self.state.change == PrefixChange::None
-                    && self.our_info().is_total_consensus(proofs)
+                    && self
+                        .our_info()
+                        .is_total_consensus(&self.network_params, proofs)
             }
             NetworkEvent::OurMerge | NetworkEvent::NeighbourMerge(_) => {
-                self.our_info().is_quorum(proofs)
+                self.our_info().is_quorum(&self.network_params, proofs)
             }
         }
     }
@@ -677,7 +1464,7 @@ impl Chain {
             );
         }
         if self.our_id == *sender_id {
-            let _ = self.event_cache.insert(net_event.clone());
+            self.event_cache.insert(net_event.clone());
         }
         Ok(())
     }
@@ -723,24 +1510,38 @@ impl Chain {
         let pfx = *sec_info.prefix();
         if pfx.matches(self.our_id.name()) {
             let is_new_member = !self.is_member && sec_info.members().contains(&self.our_id);
-            self.state.push_our_new_info(sec_info, proofs);
+            self.state
+                .push_our_new_info(sec_info, proofs, self.network_params);
+            self.state.accounts.retain_matching(&pfx);
+            self.state
+                .msg_seqs
+                .retain(|(src, _), _| pfx.matches(&src.name()));
+            self.last_change = Some(Instant::now());
 
             if is_new_member {
                 self.is_member = true;
             }
             self.check_and_clean_neighbour_infos(None);
         } else {
-            let ppfx = sec_info.prefix().popped();
-            let spfx = sec_info.prefix().sibling();
             let new_sec_info_version = *sec_info.version();
             let sec_info = self
                 .state
                 .our_infos()
                 .rev()
-                .find(|our_info| our_info.is_quorum(&proofs))
+                .find(|our_info| our_info.is_quorum(&self.network_params, &proofs))
                 .map(|_| sec_info)
                 .ok_or(RoutingError::InvalidMessage)?;
 
+            if self.conflicts_with_held_info(&sec_info) {
+                // Keep the info we hold, but don't trust either until the conflict is resolved.
+                let _ = self.check_conflicting_info(&sec_info);
+                self.record_fork(sec_info, proofs);
+                return Ok(());
+            }
+
+            if self.inferred_neighbours.remove(&pfx) {
+                self.neighbour_changes.push(NeighbourChange::Converged(pfx));
+            }
             if let Some(old_sec_info) = self.state.neighbour_infos.insert(pfx, sec_info) {
                 if *old_sec_info.version() > new_sec_info_version {
                     log_or_panic!(
@@ -752,27 +1553,69 @@ impl Chain {
                 }
             }
 
-            // If we just split an existing neighbour and we also need its sibling,
-            // add the sibling prefix with the parent prefix sigs.
-            if let Some(ssec_info) = self
-                .state
-                .neighbour_infos
-                .get(&ppfx)
-                .filter(|psec_info| {
-                    *psec_info.version() < new_sec_info_version
-                        && self.our_prefix().is_neighbour(&spfx)
-                        && !self.state.neighbour_infos.contains_key(&spfx)
-                })
-                .cloned()
-            {
-                let _ = self.state.neighbour_infos.insert(spfx, ssec_info);
-            }
+            // If we just split an existing neighbour and we also need its sibling(s), add them
+            // with the ancestor's info.
+            self.add_split_siblings(pfx, new_sec_info_version);
 
             self.check_and_clean_neighbour_infos(Some(&pfx));
         }
+        self.prune_disputes();
+
+        let known_prefixes = self.prefixes();
+        self.state.fold_their_state_forward(&known_prefixes);
         Ok(())
     }
 
+    /// Handles a neighbour info for `pfx` which is the result of one or more splits of a neighbour
+    /// we still hold an older info for: every sibling created by those splits which is our
+    /// neighbour and which we have no info for yet gets the ancestor's info until its own arrives.
+    ///
+    /// This also covers the case where our own section and the neighbour split in the same round,
+    /// in which `pfx` itself may not be our neighbour anymore while its sibling still is.
+    fn add_split_siblings(&mut self, pfx: Prefix<XorName>, version: u64) {
+        let (ancestor_pfx, ancestor_info) = match self
+            .state
+            .neighbour_infos
+            .iter()
+            .find(|&(other_pfx, other_info)| {
+                pfx.is_extension_of(other_pfx) && *other_info.version() < version
+            })
+            .map(|(other_pfx, other_info)| (*other_pfx, other_info.clone()))
+        {
+            Some(ancestor) => ancestor,
+            None => return,
+        };
+
+        let mut child_pfx = pfx;
+        while child_pfx.bit_count() > ancestor_pfx.bit_count() {
+            let spfx = child_pfx.sibling();
+            let is_known = self
+                .state
+                .neighbour_infos
+                .keys()
+                .any(|other_pfx| *other_pfx != ancestor_pfx && other_pfx.is_compatible(&spfx));
+            if self.our_prefix().is_neighbour(&spfx) && !is_known {
+                trace!(
+                    "{} Inferred neighbour {:?} from the split of {:?} into {:?}",
+                    self,
+                    spfx,
+                    ancestor_pfx,
+                    pfx
+                );
+                let _ = self
+                    .state
+                    .neighbour_infos
+                    .insert(spfx, ancestor_info.clone());
+                let _ = self.inferred_neighbours.insert(spfx);
+                self.neighbour_changes.push(NeighbourChange::Inferred {
+                    prefix: spfx,
+                    from: ancestor_pfx,
+                });
+            }
+            child_pfx = child_pfx.popped();
+        }
+    }
+
     /// Inserts the `version` of our own section into `their_knowledge` for `pfx`.
     pub fn update_their_knowledge(&mut self, prefix: Prefix<XorName>, version: u64) {
         trace!(
@@ -793,24 +1636,190 @@ impl Chain {
             key_info,
         );
         self.state.update_their_keys(key_info);
+        self.resolve_dispute_with_key(key_info);
     }
 
-    /// Returns whether we should split into two sections.
-    fn should_split(&self, members: &BTreeSet<PublicId>) -> Result<bool, RoutingError> {
-        if self.state.change != PrefixChange::None || self.should_vote_for_merge() {
-            return Ok(false);
+    /// Checks `sec_info` against the neighbour info we hold for the same prefix and version. If
+    /// they differ, both are recorded as disputed and we stop routing through that section until
+    /// the dispute is resolved. Returns the hashes and member names of all the conflicting infos
+    /// for the prefix if `sec_info` is new evidence.
+    pub fn check_conflicting_info(
+        &mut self,
+        sec_info: &SectionInfo,
+    ) -> Option<Vec<(Digest256, BTreeSet<XorName>)>> {
+        if !self.conflicts_with_held_info(sec_info) {
+            return None;
         }
-
-        let new_size = members
+        let held = self.state.neighbour_infos.get(sec_info.prefix())?.clone();
+        let disputed = self
+            .disputed_infos
+            .entry(*sec_info.prefix())
+            .or_insert_with(|| iter::once(held).collect());
+        if !disputed.insert(sec_info.clone()) {
+            return None;
+        }
+        let evidence: Vec<_> = disputed
             .iter()
-            .filter(|id| {
-                self.our_id.name().common_prefix(id.name()) > self.our_prefix().bit_count()
-            })
-            .count();
-        let min_split_size = self.min_split_size();
-        // If either of the two new sections will not contain enough entries, return `false`.
-        Ok(new_size >= min_split_size && members.len() >= min_split_size + new_size)
-    }
+            .map(|info| (*info.hash(), info.member_names()))
+            .collect();
+        warn!(
+            "{} Received {} conflicting infos for {:?} version {}.",
+            self,
+            evidence.len(),
+            sec_info.prefix(),
+            sec_info.version()
+        );
+        Some(evidence)
+    }
+
+    /// Records `sec_info` as a branch of a fork of the neighbour info we hold for the same prefix
+    /// and version, both having been accepted by our section.
+    fn record_fork(&mut self, sec_info: SectionInfo, proofs: ProofSet) {
+        let pfx = *sec_info.prefix();
+        let is_new = match self.forks.get_mut(&pfx) {
+            Some(fork) => fork.add_branch(sec_info, proofs),
+            None => {
+                let held = match self.state.neighbour_infos.get(&pfx) {
+                    Some(held) => held.clone(),
+                    None => return,
+                };
+                let held_proofs = self.accepted_proofs(&held);
+                let fork = ForkEvidence::new((held, held_proofs), (sec_info, proofs));
+                let _ = self.forks.insert(pfx, fork);
+                true
+            }
+        };
+        if is_new {
+            error!(
+                "{} Detected a fork of {:?} at version {}.",
+                self,
+                pfx,
+                self.forks[&pfx].version()
+            );
+            let _ = self.unreported_forks.insert(pfx);
+        }
+    }
+
+    /// Returns the proofs `sec_info` accumulated with, if it is in our journal.
+    fn accepted_proofs(&self, sec_info: &SectionInfo) -> ProofSet {
+        self.journal
+            .entries()
+            .iter()
+            .rev()
+            .find(|entry| match *entry.event() {
+                NetworkEvent::SectionInfo(ref info) => info == sec_info,
+                _ => false,
+            })
+            .map(|entry| entry.proofs().clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns the forks of neighbouring sections detected or extended since the last call.
+    pub fn take_new_forks(&mut self) -> Vec<ForkEvidence> {
+        let unreported = mem::replace(&mut self.unreported_forks, BTreeSet::new());
+        unreported
+            .into_iter()
+            .filter_map(|pfx| self.forks.get(&pfx).cloned())
+            .collect()
+    }
+
+    /// Returns the changes to the neighbours we hold an inferred info for since the last call.
+    pub fn take_neighbour_changes(&mut self) -> Vec<NeighbourChange> {
+        let dropped: Vec<_> = self
+            .inferred_neighbours
+            .iter()
+            .filter(|pfx| !self.state.neighbour_infos.contains_key(pfx))
+            .cloned()
+            .collect();
+        for pfx in dropped {
+            let _ = self.inferred_neighbours.remove(&pfx);
+            self.neighbour_changes.push(NeighbourChange::Converged(pfx));
+        }
+        mem::replace(&mut self.neighbour_changes, Vec::new())
+    }
+
+    /// Returns whether we hold conflicting infos for the neighbour with the given prefix.
+    pub fn is_disputed(&self, pfx: &Prefix<XorName>) -> bool {
+        self.disputed_infos.contains_key(pfx)
+    }
+
+    /// Returns whether `sec_info` has the same prefix and version as the neighbour info we hold,
+    /// but differs from it.
+    fn conflicts_with_held_info(&self, sec_info: &SectionInfo) -> bool {
+        self.state
+            .neighbour_infos
+            .get(sec_info.prefix())
+            .map_or(false, |held| {
+                held.version() == sec_info.version() && held != sec_info
+            })
+    }
+
+    /// If `key_info` belongs to one of the infos of a dispute, that info is the one the section
+    /// signed its messages with: adopts it and drops the dispute.
+    fn resolve_dispute_with_key(&mut self, key_info: &SectionKeyInfo) {
+        let network_params = self.network_params;
+        let genuine = self
+            .disputed_infos
+            .get(key_info.prefix())
+            .and_then(|infos| {
+                infos.iter().find(|info| {
+                    SectionKeyInfo::from_section_info(info, network_params) == *key_info
+                })
+            })
+            .cloned();
+        let genuine = match genuine {
+            Some(info) => info,
+            None => return,
+        };
+        info!(
+            "{} Resolved conflicting infos for {:?} with their key.",
+            self,
+            genuine.prefix()
+        );
+        let _ = self.disputed_infos.remove(genuine.prefix());
+        let _ = self.forks.remove(genuine.prefix());
+        let _ = self
+            .state
+            .neighbour_infos
+            .insert(*genuine.prefix(), genuine);
+    }
+
+    /// Drops disputes for sections we have since accepted a newer info for, or removed, and forks
+    /// of sections which aren't our neighbours anymore.
+    fn prune_disputes(&mut self) {
+        let neighbour_infos = &self.state.neighbour_infos;
+        self.forks
+            .retain(|pfx, _| neighbour_infos.contains_key(pfx));
+        let resolved: Vec<_> = self
+            .disputed_infos
+            .iter()
+            .filter(|(pfx, infos)| {
+                let version = infos.iter().next().map(SectionInfo::version);
+                neighbour_infos.get(*pfx).map(SectionInfo::version) != version
+            })
+            .map(|(pfx, _)| *pfx)
+            .collect();
+        for pfx in resolved {
+            let _ = self.disputed_infos.remove(&pfx);
+        }
+    }
+
+    /// Returns whether we should split into two sections.
+    fn should_split(&self, members: &BTreeSet<PublicId>) -> Result<bool, RoutingError> {
+        if self.state.change != PrefixChange::None || self.should_vote_for_merge() {
+            return Ok(false);
+        }
+
+        let new_size = members
+            .iter()
+            .filter(|id| {
+                self.our_id.name().common_prefix(id.name()) > self.our_prefix().bit_count()
+            })
+            .count();
+        let min_split_size = self.min_split_size();
+        // If either of the two new sections will not contain enough entries, return `false`.
+        Ok(new_size >= min_split_size && members.len() >= min_split_size + new_size)
+    }
 
     /// Splits our section and generates new section infos for the child sections.
     fn split_self(
@@ -834,43 +1843,94 @@ impl Chain {
         Ok((our_new_info, other_info))
     }
 
-    /// Update our version which has signed the neighbour infos to whichever latest version
-    /// possible.
+    /// Removes neighbour infos which are no longer needed.
     ///
-    /// If we want to do for a particular `NeighbourInfo` then supply that else we will go over the
-    /// entire list.
-    fn check_and_clean_neighbour_infos(&mut self, _for_pfx: Option<&Prefix<XorName>>) {
-        // Remove invalid neighbour pfx, older version of compatible pfx.
-        let to_remove: Vec<Prefix<XorName>> = self
-            .state
-            .neighbour_infos
-            .iter()
-            .filter_map(|(pfx, sec_info)| {
-                if !self.our_prefix().is_neighbour(pfx) {
-                    // we just split making old neighbour no longer needed
-                    return Some(*pfx);
-                }
-
-                // Remove older compatible neighbour prefixes.
-                // DO NOT SUPPORT MERGE: Not consider newer if the older one was extension (split).
-                let is_newer = |(other_pfx, other_sec_info): (&Prefix<XorName>, &SectionInfo)| {
-                    other_pfx.is_compatible(pfx)
-                        && other_sec_info.version() > sec_info.version()
-                        && !pfx.is_extension_of(other_pfx)
+    /// With `for_pfx` set, only the changes caused by inserting the info for `for_pfx` are
+    /// considered: the map is assumed to have been clean before. Otherwise, e.g. after our own
+    /// prefix changed, all entries are checked.
+    fn check_and_clean_neighbour_infos(&mut self, for_pfx: Option<&Prefix<XorName>>) {
+        let to_remove: Vec<Prefix<XorName>> = match for_pfx {
+            Some(pfx) => {
+                let sec_info = match self.state.neighbour_infos.get(pfx) {
+                    Some(sec_info) => sec_info,
+                    None => return,
                 };
-
-                if self.state.neighbour_infos.iter().any(is_newer) {
-                    return Some(*pfx);
+                // Only entries compatible with `pfx` can be superseded by its info.
+                let mut to_remove: Vec<_> = self
+                    .state
+                    .neighbour_infos
+                    .iter()
+                    .filter(|&(other_pfx, other_sec_info)| {
+                        supersedes((pfx, sec_info), (other_pfx, other_sec_info))
+                    })
+                    .map(|(other_pfx, _)| *other_pfx)
+                    .collect();
+                if self.is_neighbour_deadwood(pfx, sec_info) {
+                    to_remove.push(*pfx);
                 }
-
-                None
-            })
-            .collect();
+                to_remove
+            }
+            None => self
+                .state
+                .neighbour_infos
+                .iter()
+                .filter(|&(pfx, sec_info)| self.is_neighbour_deadwood(pfx, sec_info))
+                .map(|(pfx, _)| *pfx)
+                .collect(),
+        };
         for pfx in to_remove {
             let _ = self.state.neighbour_infos.remove(&pfx);
         }
     }
 
+    /// Checks up to `NEIGHBOUR_COMPACTION_BATCH` neighbour infos for deadwood, continuing where
+    /// the previous call stopped, and removes what is no longer needed. Called periodically so
+    /// that large neighbour maps get cleaned without a full scan on every change.
+    pub fn compact_neighbour_infos(&mut self) {
+        let batch: Vec<Prefix<XorName>> = {
+            let range = match self.compaction_cursor {
+                Some(cursor) => self
+                    .state
+                    .neighbour_infos
+                    .range((Bound::Excluded(cursor), Bound::Unbounded)),
+                None => self.state.neighbour_infos.range(..),
+            };
+            range
+                .take(NEIGHBOUR_COMPACTION_BATCH)
+                .map(|(pfx, _)| *pfx)
+                .collect()
+        };
+        self.compaction_cursor = if batch.len() < NEIGHBOUR_COMPACTION_BATCH {
+            None
+        } else {
+            batch.last().cloned()
+        };
+
+        for pfx in batch {
+            let is_deadwood = self
+                .state
+                .neighbour_infos
+                .get(&pfx)
+                .map_or(false, |sec_info| self.is_neighbour_deadwood(&pfx, sec_info));
+            if is_deadwood {
+                trace!("{} Compacted neighbour info of {:?}", self, pfx);
+                let _ = self.state.neighbour_infos.remove(&pfx);
+            }
+        }
+    }
+
+    /// Returns whether the neighbour info `sec_info` stored for `pfx` is no longer needed: either
+    /// `pfx` isn't our neighbour anymore, or another entry supersedes it.
+    fn is_neighbour_deadwood(&self, pfx: &Prefix<XorName>, sec_info: &SectionInfo) -> bool {
+        // We just split making old neighbour no longer needed.
+        !self.our_prefix().is_neighbour(pfx)
+            || self
+                .state
+                .neighbour_infos
+                .iter()
+                .any(|newer| supersedes(newer, (pfx, sec_info)))
+    }
+
     /// Returns all network events that we have signed but haven't accumulated yet.
     fn signed_events(&self) -> impl Iterator<Item = &NetworkEvent> {
         self.chain_accumulator
@@ -891,6 +1951,15 @@ impl Chain {
         )))
     }
 
+    /// Returns whether the info we hold for the section matching `name` has at least the given
+    /// `version`. Also returns `true` if we don't know that section, as we then only relay
+    /// towards it.
+    pub fn knows_section_version(&self, name: &XorName, version: u64) -> bool {
+        self.all_sections()
+            .find(|&(pfx, _)| pfx.matches(name))
+            .map_or(true, |(_, sec_info)| *sec_info.version() >= version)
+    }
+
     /// Finds the `count` names closest to `name` in the whole routing table.
     fn closest_known_names(
         &self,
@@ -976,10 +2045,10 @@ impl Chain {
 
     /// Returns the prefix of the closest non-empty section to `name`, regardless of whether `name`
     /// belongs in that section or not, and the section itself.
-    fn closest_section(&self, name: &XorName) -> (Prefix<XorName>, BTreeSet<XorName>) {
+    fn closest_section(&self, name: &XorName) -> (Prefix<XorName>, &SectionInfo) {
         let mut best_pfx = *self.our_prefix();
         let mut best_si = self.our_info();
-        for (pfx, sec_info) in &self.state.neighbour_infos {
+        for (pfx, sec_info) in self.trusted_neighbour_infos() {
             // TODO: Remove the first check after verifying that section infos are never empty.
             if !sec_info.members().is_empty()
                 && best_pfx.cmp_distance(&pfx, name) == Ordering::Greater
@@ -988,14 +2057,22 @@ impl Chain {
                 best_si = sec_info;
             }
         }
-        (best_pfx, best_si.member_names())
+        (best_pfx, best_si)
+    }
+
+    /// Returns the neighbour infos which aren't disputed, i.e. the ones we route through.
+    fn trusted_neighbour_infos(&self) -> impl Iterator<Item = (&Prefix<XorName>, &SectionInfo)> {
+        self.state
+            .neighbour_infos
+            .iter()
+            .filter(move |(pfx, _)| !self.disputed_infos.contains_key(*pfx))
     }
 
     /// Returns the known sections sorted by the distance from a given XorName.
-    fn closest_sections(&self, name: &XorName) -> Vec<(Prefix<XorName>, BTreeSet<XorName>)> {
-        let mut result = vec![(*self.our_prefix(), self.our_info().member_names())];
-        for (pfx, sec_info) in &self.state.neighbour_infos {
-            result.push((*pfx, sec_info.member_names()));
+    fn closest_sections(&self, name: &XorName) -> Vec<(Prefix<XorName>, &SectionInfo)> {
+        let mut result = vec![(*self.our_prefix(), self.our_info())];
+        for (pfx, sec_info) in self.trusted_neighbour_infos() {
+            result.push((*pfx, sec_info));
         }
         result.sort_by(|lhs, rhs| lhs.0.cmp_distance(&rhs.0, name));
         result
@@ -1028,24 +2105,38 @@ impl Chain {
     ///     - if our name *is* the destination, returns an empty set; otherwise
     ///     - if the destination name is an entry in the routing table, returns it; otherwise
     ///     - returns the `N/3` closest members of the RT to the target
+    ///
+    /// With `DeliveryScope::Elders`, only the elders of each section are considered as "members".
     pub fn targets(
         &self,
         dst: &Authority<XorName>,
         connected_peers: &[&XorName],
+        scope: DeliveryScope,
     ) -> Result<(Vec<XorName>, usize), Error> {
         // FIXME: only filtering for now to match RT.
         // should confirm if needed esp after msg_relay changes.
         let is_connected = |target_name: &XorName| connected_peers.contains(&target_name);
+        let names = |sec_info: &SectionInfo| match scope {
+            DeliveryScope::AllMembers => sec_info.member_names(),
+            DeliveryScope::Elders => sec_info
+                .elders(&self.network_params)
+                .iter()
+                .map(|pub_id| *pub_id.name())
+                .collect(),
+        };
 
         let candidates = |target_name: &XorName| {
             let filtered_sections =
                 self.closest_sections(target_name)
                     .into_iter()
-                    .map(|(prefix, members)| {
+                    .map(|(prefix, sec_info)| {
                         (
                             prefix,
-                            members.len(),
-                            members.into_iter().filter(is_connected).collect::<Vec<_>>(),
+                            sec_info.members().len(),
+                            names(sec_info)
+                                .into_iter()
+                                .filter(is_connected)
+                                .collect::<Vec<_>>(),
                         )
                     });
 
@@ -1053,7 +2144,8 @@ impl Chain {
             let mut nodes_to_send = Vec::new();
             for (idx, (prefix, len, connected)) in filtered_sections.enumerate() {
                 nodes_to_send.extend(connected.into_iter());
-                dg_size = delivery_group_size(len);
+                // Only the section's elders are counted, as only they take part in consensus.
+                dg_size = delivery_group_size(cmp::min(len, self.min_sec_size()));
 
                 if &prefix == self.our_prefix() {
                     // Send to all connected targets so they can forward the message
@@ -1092,11 +2184,15 @@ impl Chain {
             Authority::ClientManager(ref target_name)
             | Authority::NaeManager(ref target_name)
             | Authority::NodeManager(ref target_name)
+            | Authority::Group {
+                name: ref target_name,
+                ..
+            }
             | Authority::Section(ref target_name) => {
-                let (prefix, section) = self.closest_section(target_name);
+                let (prefix, sec_info) = self.closest_section(target_name);
                 if &prefix == self.our_prefix() {
                     // Exclude our name since we don't need to send to ourself
-                    let mut section = section.clone();
+                    let mut section = names(sec_info);
                     let _ = section.remove(&self.our_id().name());
 
                     // FIXME: only doing this for now to match RT.
@@ -1125,9 +2221,7 @@ impl Chain {
                     };
 
                     let targets = Iterator::flatten(
-                        self.all_sections()
-                            .filter_map(is_compatible)
-                            .map(SectionInfo::member_names),
+                        self.all_sections().filter_map(is_compatible).map(&names),
                     )
                     .filter(is_connected)
                     .filter(|name| name != self.our_id().name())
@@ -1156,6 +2250,7 @@ impl Chain {
             Authority::ClientManager(ref name)
             | Authority::NaeManager(ref name)
             | Authority::NodeManager(ref name)
+            | Authority::Group { ref name, .. }
             | Authority::Section(ref name) => self.our_prefix().matches(name),
             Authority::PrefixSection(ref prefix) => self.our_prefix().is_compatible(prefix),
         }
@@ -1193,6 +2288,55 @@ impl Chain {
         (network_size.ceil() as u64, is_exact)
     }
 
+    /// Returns the current epoch of our section and the estimate for the network.
+    pub fn network_epoch(&self) -> NetworkEpoch {
+        let section = *self.state.our_info().version();
+        let network = self
+            .all_sections()
+            .map(|(_, sec_info)| *sec_info.version())
+            .max()
+            .unwrap_or(section);
+        NetworkEpoch { section, network }
+    }
+
+    /// Sets the guard against acting on the agreement of a section that changed too much recently,
+    /// or disables it if `None`.
+    pub fn set_stability_guard(&mut self, guard: Option<StabilityGuard>) {
+        self.stability_guard = guard;
+    }
+
+    /// Returns the number of current members of our section that joined within the stability
+    /// guard's window, and the section size, if these exceed the allowed fraction and our section
+    /// changed within the guard's hold time. While this is the case, only membership events are
+    /// returned by `poll`.
+    pub fn instability(&self) -> Option<(usize, usize)> {
+        let guard = self.stability_guard?;
+        if self.last_change?.elapsed() >= guard.hold() {
+            return None;
+        }
+
+        let our_info = self.state.our_info();
+        let version = *our_info.version();
+        let mut infos = self.state.our_infos();
+        let oldest = infos.next()?;
+        let baseline = infos
+            .rev()
+            .find(|info| *info.version() + guard.window <= version)
+            .unwrap_or(oldest);
+
+        let size = our_info.members().len();
+        let joined = our_info
+            .members()
+            .iter()
+            .filter(|id| !baseline.members().contains(id))
+            .count();
+        if guard.is_exceeded(joined, size) {
+            Some((joined, size))
+        } else {
+            None
+        }
+    }
+
     /// Return a minimum length prefix, favouring our prefix if it is one of the shortest.
     pub fn min_len_prefix(&self) -> Prefix<XorName> {
         *iter::once(self.our_prefix())
@@ -1246,9 +2390,55 @@ impl Chain {
         self.candidate.old_public_id()
     }
 
+    /// Queues a candidate our section agreed to accept while another one is being processed.
+    /// Returns `false` if there is no room for it.
+    pub fn queue_candidate(&mut self, vote: ExpectCandidatePayload) -> bool {
+        self.candidate_queue.push(vote)
+    }
+
+    /// Returns whether the candidate with the given old ID is queued.
+    pub fn is_candidate_queued(&self, old_pub_id: &PublicId) -> bool {
+        self.candidate_queue.contains(old_pub_id)
+    }
+
+    /// Takes the next queued candidate, if no candidate is being processed.
+    pub fn take_next_candidate(&mut self) -> Option<ExpectCandidatePayload> {
+        if self.has_resource_proof_candidate() {
+            return None;
+        }
+        self.candidate_queue.pop()
+    }
+
+    /// Removes the candidate with the given old ID from the queue, and returns it if it was there.
+    pub fn remove_queued_candidate(
+        &mut self,
+        old_pub_id: &PublicId,
+    ) -> Option<ExpectCandidatePayload> {
+        self.candidate_queue.remove(old_pub_id)
+    }
+
+    /// Returns the old IDs of the queued candidates which have waited too long, so we can vote to
+    /// purge them. Each one is only returned once.
+    pub fn take_expired_queued_candidates(&mut self) -> Vec<PublicId> {
+        self.candidate_queue
+            .take_expired(self.network_params.timeouts.queued_candidate)
+    }
+
+    /// Forgets all queued candidates.
+    pub fn clear_queued_candidates(&mut self) {
+        self.candidate_queue.clear()
+    }
+
     /// Logs info about ongoing candidate state, if any.
     pub fn show_candidate_status(&self, log_ident: &LogIdent) {
-        self.candidate.show_status(log_ident)
+        self.candidate.show_status(log_ident);
+        if !self.candidate_queue.is_empty() {
+            trace!(
+                "{} Shared Candidate Status - {} candidates queued.",
+                log_ident,
+                self.candidate_queue.len()
+            );
+        }
     }
 }
 
@@ -1294,6 +2484,11 @@ impl Display for Chain {
 
 #[cfg(any(test, feature = "mock_base"))]
 impl Chain {
+    /// Returns whether every block of our section's history is signed by the key before it.
+    pub fn validate_our_history(&self) -> bool {
+        self.state.our_history.validate()
+    }
+
     /// Returns the members of the section with the given prefix (if it exists)
     pub fn get_section(&self, pfx: &Prefix<XorName>) -> Option<&SectionInfo> {
         if self.our_prefix() == pfx {
@@ -1302,32 +2497,69 @@ impl Chain {
             self.state.neighbour_infos.get(pfx)
         }
     }
+
+    pub(super) fn state_mut(&mut self) -> &mut SharedState {
+        &mut self.state
+    }
 }
 
 #[cfg(feature = "mock_base")]
 impl Chain {
+    /// Removes the neighbour infos made redundant by inserting the info for `pfx`, or checks all
+    /// of them if `pfx` is `None`. Exposed to benchmark the two against each other.
+    pub fn clean_neighbour_infos(&mut self, pfx: Option<&Prefix<XorName>>) {
+        self.check_and_clean_neighbour_infos(pfx)
+    }
+
     /// Returns their_knowledge
     pub fn get_their_knowldege(&self) -> &BTreeMap<Prefix<XorName>, u64> {
         &self.state.get_their_knowledge()
     }
-}
 
-#[cfg(test)]
-impl Chain {
-    pub fn validate_our_history(&self) -> bool {
-        self.state.our_history.validate()
+    /// Returns the IDs of the nodes which the events still waiting in the accumulator are about.
+    pub fn pending_event_subjects(&self) -> BTreeSet<PublicId> {
+        self.chain_accumulator
+            .keys()
+            .filter_map(|event| match *event {
+                NetworkEvent::AddElder(ref pub_id, _)
+                | NetworkEvent::RemoveElder(ref pub_id)
+                | NetworkEvent::Offline(ref pub_id)
+                | NetworkEvent::AddInfant(ref pub_id, _)
+                | NetworkEvent::RemoveInfant(ref pub_id) => Some(*pub_id),
+                NetworkEvent::Online(ref payload) => Some(payload.new_public_id),
+                NetworkEvent::Relocate(ref payload) => Some(payload.pub_id),
+                NetworkEvent::Promote(ref payload) | NetworkEvent::Demote(ref payload) => {
+                    Some(payload.pub_id)
+                }
+                _ => None,
+            })
+            .collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::super::{GenesisPfxInfo, Proof, ProofSet, SectionInfo};
-    use super::Chain;
+    use super::super::journal::{JOURNAL_HEADER_KEY, JOURNAL_NAMESPACE};
+    use super::super::replay;
+    use super::super::snapshot::{
+        ChainSnapshotV1, VersionedSnapshot, SNAPSHOT_KEY, SNAPSHOT_NAMESPACE,
+    };
+    use super::super::{
+        AckMessagePayload, GenesisPfxInfo, NetworkEvent, Proof, ProofSet, SectionInfo,
+        SectionKeyInfo, StabilityGuard,
+    };
+    use super::{Chain, DeliveryScope, NeighbourChange};
     use crate::id::{FullId, PublicId};
-    use crate::{Prefix, XorName, MIN_SECTION_SIZE};
+    use crate::network_params::NetworkParams;
+    use crate::parsec;
+    use crate::routing_table::Authority;
+    use crate::storage::{MemoryStorage, Storage};
+    use crate::{Prefix, XorName, Xorable, MIN_SECTION_SIZE};
+    use maidsafe_utilities::serialisation;
     use rand::{thread_rng, Rng};
     use serde::Serialize;
     use std::collections::{BTreeSet, HashMap};
+    use std::iter;
     use std::str::FromStr;
     use unwrap::unwrap;
 
@@ -1420,7 +2652,11 @@ mod tests {
             latest_info: Default::default(),
         };
 
-        let mut chain = Chain::new(min_sec_size, *our_id.public_id(), genesis_info);
+        let network_params = NetworkParams {
+            min_section_size: min_sec_size,
+            ..NetworkParams::default()
+        };
+        let mut chain = Chain::new(network_params, *our_id.public_id(), genesis_info);
 
         for neighbour_info in sections_iter {
             let proofs = gen_proofs(&full_ids, &our_members, &neighbour_info);
@@ -1448,6 +2684,208 @@ mod tests {
         assert!(chain.get_section(&Prefix::from_str("").unwrap()).is_none());
     }
 
+    #[test]
+    fn conflicting_neighbour_infos() {
+        let neighbour_pfx = unwrap!(Prefix::from_str("01"));
+        let (mut chain, mut full_ids) = gen_chain(
+            MIN_SECTION_SIZE,
+            vec![
+                (unwrap!(Prefix::from_str("00")), 8),
+                (neighbour_pfx, 8),
+                (unwrap!(Prefix::from_str("1")), 8),
+            ],
+        );
+        let our_members = chain.our_info().members().clone();
+        let neighbour_info = unwrap!(chain.get_section(&neighbour_pfx)).clone();
+        let (genuine, ids) = gen_section_info(SecInfoGen::Add(&neighbour_info));
+        full_ids.extend(ids);
+        let (forged, _) = gen_section_info(SecInfoGen::Add(&neighbour_info));
+
+        let proofs = gen_proofs(&full_ids, &our_members, &genuine);
+        unwrap!(chain.add_section_info(genuine.clone(), proofs));
+        assert!(!chain.is_disputed(&neighbour_pfx));
+        assert_eq!(chain.check_conflicting_info(&genuine), None);
+
+        let infos = unwrap!(chain.check_conflicting_info(&forged));
+        assert_eq!(infos.len(), 2);
+        assert!(infos.contains(&(*forged.hash(), forged.member_names())));
+        assert_eq!(chain.check_conflicting_info(&forged), None);
+        assert!(chain.is_disputed(&neighbour_pfx));
+
+        // Messages for the disputed section are routed via another one.
+        let name = neighbour_pfx.substituted_in(thread_rng().gen());
+        assert_ne!(chain.closest_section(&name).0, neighbour_pfx);
+
+        // The forged info accumulating doesn't replace the one we hold.
+        let proofs = gen_proofs(&full_ids, &our_members, &forged);
+        unwrap!(chain.add_section_info(forged.clone(), proofs));
+        assert_eq!(chain.get_section(&neighbour_pfx), Some(&genuine));
+
+        // A key from the section's proof chain tells which info is genuine.
+        chain.update_their_keys(&SectionKeyInfo::from_section_info(
+            &genuine,
+            *chain.network_params(),
+        ));
+        assert!(!chain.is_disputed(&neighbour_pfx));
+        assert_eq!(chain.closest_section(&name).0, neighbour_pfx);
+
+        // A successor resolves a dispute, too.
+        let _ = chain.check_conflicting_info(&forged);
+        assert!(chain.is_disputed(&neighbour_pfx));
+        let (successor, ids) = gen_section_info(SecInfoGen::Add(&genuine));
+        full_ids.extend(ids);
+        let proofs = gen_proofs(&full_ids, &our_members, &successor);
+        unwrap!(chain.add_section_info(successor, proofs));
+        assert!(!chain.is_disputed(&neighbour_pfx));
+    }
+
+    #[test]
+    fn elder_only_targets() {
+        let size = MIN_SECTION_SIZE + 3;
+        let (chain, _) = gen_chain(
+            MIN_SECTION_SIZE,
+            vec![
+                (unwrap!(Prefix::from_str("0")), size),
+                (unwrap!(Prefix::from_str("1")), size),
+            ],
+        );
+        let all_names: Vec<_> = chain
+            .all_sections()
+            .flat_map(|(_, sec_info)| sec_info.member_names())
+            .collect();
+        let connected: Vec<_> = all_names.iter().collect();
+        let elder_names = |pfx: &str| -> BTreeSet<_> {
+            unwrap!(chain.get_section(&unwrap!(Prefix::from_str(pfx))))
+                .elders(chain.network_params())
+                .iter()
+                .map(|pub_id| *pub_id.name())
+                .collect()
+        };
+
+        for &pfx in &["0", "1"] {
+            let dst = Authority::Section(
+                unwrap!(Prefix::from_str(pfx)).substituted_in(thread_rng().gen()),
+            );
+            let (all, _) = unwrap!(chain.targets(&dst, &connected, DeliveryScope::AllMembers));
+            let (elders, dg_size) = unwrap!(chain.targets(&dst, &connected, DeliveryScope::Elders));
+            assert!(elders.len() < all.len());
+            assert!(elders.len() >= dg_size);
+            assert!(elders.iter().all(|name| elder_names(pfx).contains(name)));
+        }
+    }
+
+    #[test]
+    fn forked_neighbour_infos() {
+        let neighbour_pfx = unwrap!(Prefix::from_str("01"));
+        let (mut chain, mut full_ids) = gen_chain(
+            MIN_SECTION_SIZE,
+            vec![
+                (unwrap!(Prefix::from_str("00")), 8),
+                (neighbour_pfx, 8),
+                (unwrap!(Prefix::from_str("1")), 8),
+            ],
+        );
+        let vote =
+            |chain: &mut Chain, full_ids: &HashMap<PublicId, FullId>, sec_info: &SectionInfo| {
+                let event = NetworkEvent::SectionInfo(sec_info.clone());
+                let observation: parsec::Observation<NetworkEvent, PublicId> =
+                    parsec::Observation::OpaquePayload(event.clone());
+                for pub_id in chain.our_info().members().clone() {
+                    let full_id = unwrap!(full_ids.get(&pub_id));
+                    let proof = unwrap!(Proof::new(
+                        pub_id,
+                        full_id.signing_private_key(),
+                        &observation
+                    ));
+                    unwrap!(chain.handle_opaque_event(&event, proof));
+                }
+                unwrap!(chain.poll())
+            };
+        let neighbour_info = unwrap!(chain.get_section(&neighbour_pfx)).clone();
+        let (first, ids) = gen_section_info(SecInfoGen::Add(&neighbour_info));
+        full_ids.extend(ids);
+        let (second, ids) = gen_section_info(SecInfoGen::Add(&neighbour_info));
+        full_ids.extend(ids);
+
+        assert!(vote(&mut chain, &full_ids, &first).is_some());
+        assert!(chain.take_new_forks().is_empty());
+
+        // Our section accepting a second info for the same version is a fork.
+        assert!(vote(&mut chain, &full_ids, &second).is_some());
+        let forks = chain.take_new_forks();
+        assert_eq!(forks.len(), 1);
+        assert_eq!(*forks[0].prefix(), neighbour_pfx);
+        assert_eq!(forks[0].version(), *first.version());
+        let infos = forks[0].infos();
+        assert!(infos.contains(&(*first.hash(), first.member_names())));
+        assert!(infos.contains(&(*second.hash(), second.member_names())));
+        assert!(forks[0]
+            .branches()
+            .iter()
+            .all(|(_, proofs)| chain.our_info().is_quorum(chain.network_params(), proofs)));
+        assert!(chain.take_new_forks().is_empty());
+        assert_eq!(chain.get_section(&neighbour_pfx), Some(&first));
+        assert!(chain.is_disputed(&neighbour_pfx));
+
+        // Neither branch is extended while we don't know which one is genuine.
+        let (successor, ids) = gen_section_info(SecInfoGen::Add(&second));
+        full_ids.extend(ids);
+        assert_eq!(vote(&mut chain, &full_ids, &successor), None);
+        assert_eq!(chain.get_section(&neighbour_pfx), Some(&first));
+
+        // Once a key from the section's proof chain tells us, the successor is accepted.
+        chain.update_their_keys(&SectionKeyInfo::from_section_info(
+            &second,
+            *chain.network_params(),
+        ));
+        assert!(!chain.is_disputed(&neighbour_pfx));
+        assert_eq!(
+            unwrap!(chain.poll()),
+            Some(NetworkEvent::SectionInfo(successor.clone()))
+        );
+        assert_eq!(chain.get_section(&neighbour_pfx), Some(&successor));
+    }
+
+    #[test]
+    fn proving_index_after_neighbour_split() {
+        let parent_pfx = unwrap!(Prefix::from_str("1"));
+        let (mut chain, full_ids) = gen_chain(
+            MIN_SECTION_SIZE,
+            vec![
+                (unwrap!(Prefix::from_str("00")), 8),
+                (unwrap!(Prefix::from_str("01")), 8),
+                (parent_pfx, 8),
+            ],
+        );
+        let our_members = chain.our_info().members().clone();
+        chain.update_their_knowledge(parent_pfx, 2);
+
+        let child_pfx = unwrap!(Prefix::from_str("10"));
+        let sibling_pfx = child_pfx.sibling();
+        let parent_info = unwrap!(chain.get_section(&parent_pfx)).clone();
+        let (child_info, _) = gen_section_info(SecInfoGen::New(child_pfx, 8));
+        let child_info = unwrap!(SectionInfo::new(
+            child_info.members().clone(),
+            child_pfx,
+            Some(&parent_info)
+        ));
+        let proofs = gen_proofs(&full_ids, &our_members, &child_info);
+        unwrap!(chain.add_section_info(child_info, proofs));
+
+        // The parent's entry is replaced by ones for its children.
+        assert!(!chain.state.their_knowledge.contains_key(&parent_pfx));
+        let index = |chain: &Chain, pfx: Prefix<XorName>| {
+            chain.proving_index(&Authority::Section(pfx.substituted_in(thread_rng().gen())))
+        };
+        assert_eq!(index(&chain, child_pfx), 2);
+        assert_eq!(index(&chain, sibling_pfx), 2);
+
+        chain.update_their_knowledge(child_pfx, 4);
+        assert_eq!(index(&chain, child_pfx), 4);
+        assert_eq!(index(&chain, sibling_pfx), 2);
+        assert_eq!(index(&chain, unwrap!(Prefix::from_str("01"))), 0);
+    }
+
     fn check_infos_for_duplication(chain: &Chain) {
         let mut prefixes: Vec<Prefix<XorName>> = vec![];
         for info in chain.neighbour_infos() {
@@ -1462,6 +2900,216 @@ mod tests {
         }
     }
 
+    #[test]
+    fn save_and_restore() {
+        let (mut chain, _) = gen_chain(
+            MIN_SECTION_SIZE,
+            vec![
+                (unwrap!(Prefix::from_str("00")), 8),
+                (unwrap!(Prefix::from_str("01")), 8),
+                (unwrap!(Prefix::from_str("1")), 8),
+            ],
+        );
+        let mut storage = MemoryStorage::new();
+        assert!(unwrap!(Chain::restore(&storage)).is_none());
+        unwrap!(chain.save(&mut storage));
+        let restored = unwrap!(unwrap!(Chain::restore(&storage)));
+
+        assert_eq!(restored.our_id(), chain.our_id());
+        assert_eq!(restored.network_params(), chain.network_params());
+        assert_eq!(restored.is_member(), chain.is_member());
+        assert_eq!(restored.state, chain.state);
+    }
+
+    #[test]
+    fn restore_migrates_old_snapshots() {
+        let (chain, _) = gen_chain(
+            MIN_SECTION_SIZE + 1,
+            vec![(unwrap!(Prefix::from_str("")), MIN_SECTION_SIZE + 1)],
+        );
+        let snapshot = VersionedSnapshot::V1(ChainSnapshotV1 {
+            min_sec_size: chain.min_sec_size(),
+            our_id: *chain.our_id(),
+            our_infos: chain.state.our_infos.clone(),
+            our_history: chain.state.our_history.clone(),
+            neighbour_infos: chain.state.neighbour_infos.clone(),
+            their_keys: chain.state.their_keys.clone(),
+            their_knowledge: chain.state.their_knowledge.clone(),
+            their_recent_keys: chain.state.their_recent_keys.clone(),
+        });
+        let mut storage = MemoryStorage::new();
+        unwrap!(storage.put(
+            SNAPSHOT_NAMESPACE,
+            SNAPSHOT_KEY,
+            unwrap!(serialisation::serialise(&snapshot)),
+        ));
+
+        let restored = unwrap!(unwrap!(Chain::restore(&storage)));
+        assert_eq!(restored.network_params(), chain.network_params());
+        assert_eq!(restored.state, chain.state);
+    }
+
+    #[test]
+    fn pending_votes_lists_our_unaccumulated_votes() {
+        let (mut chain, full_ids) = gen_chain(
+            MIN_SECTION_SIZE,
+            vec![(Prefix::from_str("").unwrap(), MIN_SECTION_SIZE + 2)],
+        );
+        let our_id = *chain.our_id();
+        let other_id = *unwrap!(full_ids.keys().find(|id| **id != our_id));
+        let proof = |pub_id: &PublicId, event: &NetworkEvent| {
+            let full_id = unwrap!(full_ids.get(pub_id));
+            unwrap!(Proof::new(*pub_id, full_id.signing_private_key(), event))
+        };
+
+        // Only voted for by another member: not ours to report.
+        let theirs = NetworkEvent::Offline(our_id);
+        unwrap!(chain.handle_opaque_event(&theirs, proof(&other_id, &theirs)));
+        assert!(chain.pending_votes().is_empty());
+
+        let ours = NetworkEvent::Offline(other_id);
+        unwrap!(chain.handle_opaque_event(&ours, proof(&our_id, &ours)));
+        unwrap!(chain.handle_opaque_event(&ours, proof(&other_id, &ours)));
+        let pending = chain.pending_votes();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].event, ours.descriptor());
+        // Only the elders' votes count.
+        let our_elders = chain.our_info().elders(chain.network_params());
+        let have = [our_id, other_id]
+            .iter()
+            .filter(|id| our_elders.contains(id))
+            .count();
+        assert_eq!(pending[0].have, have);
+        assert_eq!(
+            pending[0].need,
+            chain.our_info().quorum_size(chain.network_params())
+        );
+    }
+
+    #[test]
+    fn pending_events_lists_missing_voters() {
+        let (mut chain, full_ids) = gen_chain(
+            MIN_SECTION_SIZE,
+            vec![(Prefix::from_str("").unwrap(), MIN_SECTION_SIZE + 2)],
+        );
+        let our_id = *chain.our_id();
+        let our_elders = chain.our_info().elders(chain.network_params());
+        let other_id = *unwrap!(our_elders.iter().find(|id| **id != our_id));
+        let full_id = unwrap!(full_ids.get(&other_id));
+
+        // Voted for by another elder only: still reported, with the other elders as the missing
+        // voters.
+        let event = NetworkEvent::Offline(our_id);
+        let proof = unwrap!(Proof::new(other_id, full_id.signing_private_key(), &event));
+        unwrap!(chain.handle_opaque_event(&event, proof));
+
+        let pending = chain.pending_events();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].event, event.descriptor());
+        assert_eq!(pending[0].proofs, 1);
+        let expected_missing: BTreeSet<_> = our_elders
+            .iter()
+            .filter(|id| **id != other_id)
+            .cloned()
+            .collect();
+        assert_eq!(pending[0].missing, expected_missing);
+    }
+
+    #[test]
+    fn purge_stale_votes_drops_superseded_infos() {
+        let (mut chain, full_ids) = gen_chain(
+            MIN_SECTION_SIZE,
+            vec![(Prefix::from_str("").unwrap(), MIN_SECTION_SIZE)],
+        );
+        let our_id = *chain.our_id();
+        let proof = |event: &NetworkEvent| {
+            let full_id = unwrap!(full_ids.get(&our_id));
+            unwrap!(Proof::new(our_id, full_id.signing_private_key(), event))
+        };
+
+        // Two competing successors of our info: once one of them is accumulated, the votes for
+        // the other can never be handled.
+        let (added_info, _) = gen_section_info(SecInfoGen::Add(chain.our_info()));
+        let (removed_info, _) = gen_section_info(SecInfoGen::Remove(chain.our_info()));
+        let superseded = NetworkEvent::SectionInfo(added_info);
+        unwrap!(chain.handle_opaque_event(&superseded, proof(&superseded)));
+        let offline = NetworkEvent::Offline(our_id);
+        unwrap!(chain.handle_opaque_event(&offline, proof(&offline)));
+        assert_eq!(chain.purge_stale_votes(), 0);
+
+        let proofs = gen_proofs(&full_ids, chain.our_info().members(), &removed_info);
+        unwrap!(chain.add_section_info(removed_info, proofs));
+        assert_eq!(chain.purge_stale_votes(), 1);
+        assert_eq!(chain.pending_votes().len(), 1);
+        assert_eq!(chain.purge_stale_votes(), 0);
+    }
+
+    #[test]
+    fn contradictory_section_info_votes_are_penalised() {
+        let (mut chain, full_ids) = gen_chain(
+            MIN_SECTION_SIZE,
+            vec![(Prefix::from_str("").unwrap(), MIN_SECTION_SIZE)],
+        );
+        let our_id = *chain.our_id();
+        let other_id = *unwrap!(full_ids.keys().find(|id| **id != our_id));
+        let proof = |event: &NetworkEvent| {
+            let full_id = unwrap!(full_ids.get(&other_id));
+            unwrap!(Proof::new(other_id, full_id.signing_private_key(), event))
+        };
+
+        let (added_info, _) = gen_section_info(SecInfoGen::Add(chain.our_info()));
+        let (removed_info, _) = gen_section_info(SecInfoGen::Remove(chain.our_info()));
+        let added = NetworkEvent::SectionInfo(added_info);
+        let removed = NetworkEvent::SectionInfo(removed_info);
+
+        unwrap!(chain.handle_opaque_event(&added, proof(&added)));
+        assert!(chain.peer_scores().is_empty());
+        assert!(chain.take_malicious_peers().is_empty());
+
+        // Both infos claim the same version, so an honest member can't have voted for both.
+        unwrap!(chain.handle_opaque_event(&removed, proof(&removed)));
+        assert!(chain.peer_scores().contains_key(&other_id));
+        assert_eq!(chain.take_malicious_peers(), vec![other_id]);
+        assert!(chain.take_malicious_peers().is_empty());
+    }
+
+    #[test]
+    fn membership_statement_tracks_history() {
+        let (mut chain, full_ids) = gen_chain(
+            MIN_SECTION_SIZE,
+            vec![(Prefix::from_str("").unwrap(), MIN_SECTION_SIZE)],
+        );
+        let founder = *chain.our_id();
+        let first_info = chain.our_info().clone();
+
+        // A node which was relocated twice joins.
+        let joined = *FullId::within_range(&first_info.prefix().range_inclusive())
+            .with_age(2)
+            .public_id();
+        let mut members = first_info.members().clone();
+        let _ = members.insert(joined);
+        let second_info = unwrap!(SectionInfo::new(
+            members,
+            *first_info.prefix(),
+            Some(&first_info)
+        ));
+        let proofs = gen_proofs(&full_ids, first_info.members(), &second_info);
+        unwrap!(chain.add_section_info(second_info.clone(), proofs));
+
+        let statement = unwrap!(chain.membership_statement(&founder));
+        assert_eq!(statement.member, founder);
+        assert_eq!(statement.version, *second_info.version());
+        assert_eq!(statement.since_version, *first_info.version());
+        assert_eq!(statement.relocations, 0);
+
+        let statement = unwrap!(chain.membership_statement(&joined));
+        assert_eq!(statement.since_version, *second_info.version());
+        assert_eq!(statement.relocations, 2);
+
+        let stranger = *FullId::new().public_id();
+        assert_eq!(chain.membership_statement(&stranger), None);
+    }
+
     #[test]
     fn neighbour_info_cleaning() {
         let mut rng = thread_rng();
@@ -1487,4 +3135,551 @@ mod tests {
             check_infos_for_duplication(&chain);
         }
     }
+
+    fn gen_split_info(
+        chain: &Chain,
+        full_ids: &mut HashMap<PublicId, FullId>,
+        parent_pfx: Prefix<XorName>,
+        pfx: Prefix<XorName>,
+    ) -> (SectionInfo, ProofSet) {
+        let parent_info = unwrap!(chain.get_section(&parent_pfx)).clone();
+        let (info, new_ids) = gen_section_info(SecInfoGen::New(pfx, 4));
+        full_ids.extend(new_ids);
+        let info = unwrap!(SectionInfo::new(
+            info.members().clone(),
+            pfx,
+            Some(&parent_info)
+        ));
+        let proofs = gen_proofs(full_ids, chain.our_info().members(), &info);
+        (info, proofs)
+    }
+
+    #[test]
+    fn neighbour_split_twice_infers_all_siblings() {
+        let p_0 = Prefix::from_str("0").unwrap();
+        let p_1 = Prefix::from_str("1").unwrap();
+        let (mut chain, mut full_ids) = gen_chain(MIN_SECTION_SIZE, vec![(p_0, 8), (p_1, 8)]);
+
+        // We only learn about `110` after `1` has split twice.
+        let p_110 = Prefix::from_str("110").unwrap();
+        let (info, proofs) = gen_split_info(&chain, &mut full_ids, p_1, p_110);
+        unwrap!(chain.add_section_info(info, proofs));
+
+        let expected: BTreeSet<_> = vec!["10", "110", "111"]
+            .into_iter()
+            .map(|pfx| unwrap!(Prefix::from_str(pfx)))
+            .collect();
+        assert_eq!(chain.other_prefixes(), expected);
+        let inferred: BTreeSet<_> = chain
+            .take_neighbour_changes()
+            .into_iter()
+            .map(|change| match change {
+                NeighbourChange::Inferred { prefix, from } => {
+                    assert_eq!(from, p_1);
+                    prefix
+                }
+                NeighbourChange::Converged(prefix) => panic!("Unexpected convergence {:?}", prefix),
+            })
+            .collect();
+        let expected_inferred: BTreeSet<_> = vec!["10", "111"]
+            .into_iter()
+            .map(|pfx| unwrap!(Prefix::from_str(pfx)))
+            .collect();
+        assert_eq!(inferred, expected_inferred);
+    }
+
+    #[test]
+    fn our_split_and_neighbour_split_in_same_round() {
+        let p_00 = Prefix::from_str("00").unwrap();
+        let p_01 = Prefix::from_str("01").unwrap();
+        let p_1 = Prefix::from_str("1").unwrap();
+        let (mut chain, mut full_ids) =
+            gen_chain(MIN_SECTION_SIZE, vec![(p_01, 8), (p_00, 8), (p_1, 8)]);
+
+        // The neighbour still considers us to be `0` and tells us about its half we no longer
+        // neighbour. We need to keep covering the half which is still our neighbour.
+        let p_10 = Prefix::from_str("10").unwrap();
+        let (info, proofs) = gen_split_info(&chain, &mut full_ids, p_1, p_10);
+        unwrap!(chain.add_section_info(info, proofs));
+
+        let expected: BTreeSet<_> = vec!["00", "11"]
+            .into_iter()
+            .map(|pfx| unwrap!(Prefix::from_str(pfx)))
+            .collect();
+        assert_eq!(chain.other_prefixes(), expected);
+        let p_11 = Prefix::from_str("11").unwrap();
+        assert_eq!(
+            chain.take_neighbour_changes(),
+            vec![NeighbourChange::Inferred {
+                prefix: p_11,
+                from: p_1,
+            }]
+        );
+
+        // Once the actual info of `11` arrives, it replaces the inferred one.
+        let (info, proofs) = gen_split_info(&chain, &mut full_ids, p_11, p_11);
+        unwrap!(chain.add_section_info(info.clone(), proofs));
+        assert_eq!(chain.other_prefixes(), expected);
+        assert_eq!(chain.get_section(&p_11), Some(&info));
+        assert_eq!(
+            chain.take_neighbour_changes(),
+            vec![NeighbourChange::Converged(p_11)]
+        );
+    }
+
+    #[test]
+    fn knows_section_version() {
+        let p_00 = Prefix::from_str("00").unwrap();
+        let p_01 = Prefix::from_str("01").unwrap();
+        let p_1 = Prefix::from_str("1").unwrap();
+        let (chain, _) = gen_chain(MIN_SECTION_SIZE, vec![(p_00, 8), (p_01, 8)]);
+
+        let our_version = *chain.our_info().version();
+        let name = p_00.substituted_in(rand::random());
+        assert!(chain.knows_section_version(&name, our_version));
+        assert!(!chain.knows_section_version(&name, our_version + 1));
+
+        let neighbour_version = *unwrap!(chain.get_section(&p_01)).version();
+        let name = p_01.substituted_in(rand::random());
+        assert!(chain.knows_section_version(&name, neighbour_version));
+        assert!(!chain.knows_section_version(&name, neighbour_version + 1));
+
+        // We don't know the section at all, so we relay towards it regardless of its version.
+        let name = p_1.substituted_in(rand::random());
+        assert!(chain.knows_section_version(&name, neighbour_version + 1));
+    }
+
+    #[test]
+    fn network_epoch() {
+        let p_00 = Prefix::from_str("00").unwrap();
+        let p_01 = Prefix::from_str("01").unwrap();
+        let p_1 = Prefix::from_str("1").unwrap();
+        let (mut chain, full_ids) =
+            gen_chain(MIN_SECTION_SIZE, vec![(p_00, 8), (p_01, 8), (p_1, 8)]);
+
+        let epoch = chain.network_epoch();
+        assert_eq!(epoch.section, *chain.our_info().version());
+        assert_eq!(epoch.network, epoch.section);
+
+        // A neighbour's change advances the network estimate, but not our section's epoch.
+        let (new_info, _) = gen_section_info(SecInfoGen::Remove(unwrap!(chain.get_section(&p_1))));
+        let proofs = gen_proofs(&full_ids, chain.our_info().members(), &new_info);
+        unwrap!(chain.add_section_info(new_info.clone(), proofs));
+
+        let new_epoch = chain.network_epoch();
+        assert_eq!(new_epoch.section, epoch.section);
+        assert_eq!(new_epoch.network, *new_info.version());
+        assert!(new_epoch.network > epoch.network);
+    }
+
+    #[test]
+    fn stability_guard_withholds_events_after_rapid_churn() {
+        let (mut chain, mut full_ids) = gen_chain(
+            MIN_SECTION_SIZE,
+            vec![(Prefix::from_str("").unwrap(), MIN_SECTION_SIZE)],
+        );
+        for _ in 0..4 {
+            let (new_info, new_ids) = gen_section_info(SecInfoGen::Add(chain.our_info()));
+            let proofs = gen_proofs(&full_ids, chain.our_info().members(), &new_info);
+            full_ids.extend(new_ids);
+            unwrap!(chain.add_section_info(new_info, proofs));
+        }
+        let size = MIN_SECTION_SIZE + 4;
+        assert_eq!(chain.our_info().members().len(), size);
+
+        let guard = StabilityGuard {
+            window: 4,
+            max_joined_numerator: 1,
+            max_joined_denominator: 4,
+            hold_secs: 60 * 60,
+        };
+        chain.set_stability_guard(Some(guard));
+        assert_eq!(chain.instability(), Some((4, size)));
+
+        let members = chain.our_info().members().clone();
+        let vote = |chain: &mut Chain, event: &NetworkEvent| {
+            for pub_id in &members {
+                let full_id = unwrap!(full_ids.get(pub_id));
+                let proof = unwrap!(Proof::new(*pub_id, full_id.signing_private_key(), event));
+                unwrap!(chain.handle_opaque_event(event, proof));
+            }
+        };
+        let ack = NetworkEvent::AckMessage(AckMessagePayload {
+            src_prefix: Prefix::from_str("1").unwrap(),
+            ack_version: 1,
+        });
+        let offline = NetworkEvent::Offline(*unwrap!(members.iter().next()));
+        vote(&mut chain, &ack);
+        vote(&mut chain, &offline);
+
+        // Membership changes still go through, everything else waits for the section to settle.
+        assert_eq!(unwrap!(chain.poll()), Some(offline));
+        assert_eq!(unwrap!(chain.poll()), None);
+
+        // A lower threshold isn't exceeded by the newcomers.
+        chain.set_stability_guard(Some(StabilityGuard {
+            max_joined_denominator: 2,
+            ..guard
+        }));
+        assert_eq!(chain.instability(), None);
+
+        // Neither is the guard applied once its hold time after the last change has passed.
+        chain.set_stability_guard(Some(StabilityGuard {
+            hold_secs: 0,
+            ..guard
+        }));
+        assert_eq!(chain.instability(), None);
+        assert_eq!(unwrap!(chain.poll()), Some(ack));
+    }
+
+    #[test]
+    fn elders_are_the_oldest_members() {
+        let (mut chain, full_ids) = gen_chain(
+            MIN_SECTION_SIZE,
+            vec![(Prefix::from_str("").unwrap(), MIN_SECTION_SIZE)],
+        );
+        let old_elders = chain.our_info().elders(chain.network_params());
+        assert_eq!(old_elders, *chain.our_info().members());
+
+        // A node older than everyone joins: it is an elder right away, and the youngest elder with
+        // the highest name is demoted.
+        let old_id = FullId::within_range(&chain.our_prefix().range_inclusive()).with_age(3);
+        let mut members = chain.our_info().members().clone();
+        let _ = members.insert(*old_id.public_id());
+        let new_info = unwrap!(SectionInfo::new(
+            members,
+            *chain.our_prefix(),
+            Some(chain.our_info())
+        ));
+        let proofs = gen_proofs(&full_ids, chain.our_info().members(), &new_info);
+        unwrap!(chain.add_section_info(new_info, proofs));
+
+        let new_elders = chain.our_info().elders(chain.network_params());
+        assert_eq!(new_elders.len(), MIN_SECTION_SIZE);
+        assert!(new_elders.contains(old_id.public_id()));
+        assert_eq!(
+            chain.our_info().quorum_size(chain.network_params()),
+            chain.network_params().quorum_size(MIN_SECTION_SIZE)
+        );
+
+        let demoted_id = *unwrap!(old_elders.iter().max_by_key(|pub_id| *pub_id.name()));
+        let (promoted, demoted) = chain.our_elder_changes();
+        assert!(promoted.is_empty());
+        assert_eq!(demoted, iter::once(demoted_id).collect());
+    }
+
+    #[test]
+    fn relocation_candidate_is_due_every_interval() {
+        let (mut chain, mut full_ids) = gen_chain(
+            MIN_SECTION_SIZE,
+            vec![(Prefix::from_str("").unwrap(), MIN_SECTION_SIZE + 1)],
+        );
+        let mut add_member = |chain: &mut Chain| {
+            let (new_info, new_ids) = gen_section_info(SecInfoGen::Add(chain.our_info()));
+            let proofs = gen_proofs(&full_ids, chain.our_info().members(), &new_info);
+            full_ids.extend(new_ids);
+            unwrap!(chain.add_section_info(new_info, proofs));
+        };
+
+        add_member(&mut chain);
+        assert_eq!(chain.relocation_candidate(), None);
+
+        chain.set_relocation_interval(Some(2));
+        assert_eq!(chain.relocation_candidate(), None);
+        add_member(&mut chain);
+        let candidate = unwrap!(chain.relocation_candidate());
+        assert!(chain.our_info().members().contains(&candidate));
+        add_member(&mut chain);
+        assert_eq!(chain.relocation_candidate(), None);
+
+        chain.set_relocation_interval(Some(0));
+        add_member(&mut chain);
+        assert_eq!(chain.relocation_candidate(), None);
+    }
+
+    #[test]
+    fn infants_join_without_new_section_info() {
+        let (mut chain, full_ids) = gen_chain(
+            MIN_SECTION_SIZE,
+            vec![(Prefix::from_str("").unwrap(), MIN_SECTION_SIZE)],
+        );
+        let vote = |chain: &mut Chain, event: &NetworkEvent| {
+            for pub_id in chain.our_info().members().clone() {
+                let full_id = unwrap!(full_ids.get(&pub_id));
+                let proof = unwrap!(Proof::new(pub_id, full_id.signing_private_key(), event));
+                unwrap!(chain.handle_opaque_event(event, proof));
+            }
+        };
+
+        let infant_id = *FullId::new().public_id();
+        assert!(!chain.joins_as_infant(&infant_id));
+        chain.set_adult_age(Some(1));
+        assert!(chain.joins_as_infant(&infant_id));
+        assert!(!chain.joins_as_infant(FullId::new().with_age(1).public_id()));
+
+        let version = *chain.our_info().version();
+        let add = NetworkEvent::AddInfant(
+            infant_id,
+            Authority::Client {
+                client_id: infant_id,
+                proxy_node_name: rand::random(),
+            },
+        );
+        vote(&mut chain, &add);
+        assert_eq!(unwrap!(chain.poll()), Some(add));
+        assert_eq!(*chain.our_info().version(), version);
+        assert_eq!(*chain.our_infants(), iter::once(infant_id).collect());
+        assert!(chain.our_adults().is_empty());
+        assert!(chain.is_peer_valid(&infant_id));
+
+        let mut storage = MemoryStorage::new();
+        unwrap!(chain.save(&mut storage));
+        let restored = unwrap!(unwrap!(Chain::restore(&storage)));
+        assert_eq!(restored.our_infants(), chain.our_infants());
+
+        let remove = NetworkEvent::RemoveInfant(infant_id);
+        vote(&mut chain, &remove);
+        assert_eq!(unwrap!(chain.poll()), Some(remove));
+        assert_eq!(*chain.our_info().version(), version);
+        assert!(chain.our_infants().is_empty());
+    }
+
+    #[test]
+    fn upgrades_activate_at_the_agreed_version() {
+        let (mut chain, full_ids) = gen_chain(
+            MIN_SECTION_SIZE,
+            vec![(Prefix::from_str("").unwrap(), MIN_SECTION_SIZE)],
+        );
+        let vote = |chain: &mut Chain, event: &NetworkEvent| {
+            for pub_id in chain.our_info().members().clone() {
+                let full_id = unwrap!(full_ids.get(&pub_id));
+                let proof = unwrap!(Proof::new(pub_id, full_id.signing_private_key(), event));
+                unwrap!(chain.handle_opaque_event(event, proof));
+            }
+        };
+
+        let version = *chain.our_info().version();
+        assert!(chain.is_new_upgrade(1));
+        let upgrade = NetworkEvent::UpgradeSignal {
+            version: 3,
+            activation_version: version + 1,
+        };
+        vote(&mut chain, &upgrade);
+        assert_eq!(unwrap!(chain.poll()), Some(upgrade));
+        assert_eq!(
+            chain.pending_upgrade(),
+            Some(&PendingUpgrade {
+                version: 3,
+                activation_version: version + 1,
+            })
+        );
+        assert!(!chain.is_upgrade_active(3));
+        assert!(!chain.is_new_upgrade(3));
+
+        // Older upgrades are ignored.
+        let older = NetworkEvent::UpgradeSignal {
+            version: 2,
+            activation_version: version,
+        };
+        vote(&mut chain, &older);
+        assert_eq!(unwrap!(chain.poll()), Some(older));
+        assert!(!chain.is_upgrade_active(2));
+
+        let mut storage = MemoryStorage::new();
+        unwrap!(chain.save(&mut storage));
+        let restored = unwrap!(unwrap!(Chain::restore(&storage)));
+        assert_eq!(restored.pending_upgrade(), chain.pending_upgrade());
+
+        let (new_info, _) = gen_section_info(SecInfoGen::Add(chain.our_info()));
+        let proofs = gen_proofs(&full_ids, chain.our_info().members(), &new_info);
+        unwrap!(chain.add_section_info(new_info, proofs));
+        assert_eq!(chain.pending_upgrade(), None);
+        assert!(chain.is_upgrade_active(2));
+        assert!(chain.is_upgrade_active(3));
+        assert!(!chain.is_upgrade_active(4));
+    }
+
+    #[test]
+    /// Has all our elders vote for `event` and accumulates it.
+    fn accumulate(chain: &mut Chain, full_ids: &HashMap<PublicId, FullId>, event: NetworkEvent) {
+        // Section infos are only accumulated with signatures of the parsec observation.
+        let observation: parsec::Observation<NetworkEvent, PublicId> =
+            parsec::Observation::OpaquePayload(event.clone());
+        for pub_id in chain.our_info().members().clone() {
+            let full_id = unwrap!(full_ids.get(&pub_id));
+            let proof = unwrap!(Proof::new(
+                pub_id,
+                full_id.signing_private_key(),
+                &observation
+            ));
+            unwrap!(chain.handle_opaque_event(&event, proof));
+        }
+        assert_eq!(unwrap!(chain.poll()), Some(event));
+    }
+
+    #[test]
+    fn replaying_the_journal_rebuilds_the_state() {
+        let (mut chain, mut full_ids) = gen_chain(
+            MIN_SECTION_SIZE,
+            vec![(Prefix::from_str("").unwrap(), MIN_SECTION_SIZE)],
+        );
+
+        for _ in 0..2 {
+            let (new_info, new_ids) = gen_section_info(SecInfoGen::Add(chain.our_info()));
+            accumulate(&mut chain, &full_ids, NetworkEvent::SectionInfo(new_info));
+            full_ids.extend(new_ids);
+        }
+        let infant_id = *FullId::new().public_id();
+        let client_auth = Authority::ManagedNode(*infant_id.name());
+        accumulate(
+            &mut chain,
+            &full_ids,
+            NetworkEvent::AddInfant(infant_id, client_auth),
+        );
+        accumulate(
+            &mut chain,
+            &full_ids,
+            NetworkEvent::AckMessage(AckMessagePayload {
+                src_prefix: Prefix::from_str("1").unwrap(),
+                ack_version: 1,
+            }),
+        );
+
+        assert_eq!(chain.journal().len(), 4);
+        assert_eq!(unwrap!(replay::replay(chain.journal())), chain.state);
+    }
+
+    #[test]
+    fn journal_is_saved_and_truncated_at_checkpoints() {
+        let (mut chain, mut full_ids) = gen_chain(
+            MIN_SECTION_SIZE,
+            vec![(Prefix::from_str("").unwrap(), MIN_SECTION_SIZE)],
+        );
+        let mut storage = MemoryStorage::new();
+        unwrap!(chain.save(&mut storage));
+
+        let (new_info, new_ids) = gen_section_info(SecInfoGen::Add(chain.our_info()));
+        accumulate(&mut chain, &full_ids, NetworkEvent::SectionInfo(new_info));
+        full_ids.extend(new_ids);
+        unwrap!(chain.save_journal(&mut storage));
+        let ack = AckMessagePayload {
+            src_prefix: Prefix::from_str("1").unwrap(),
+            ack_version: 1,
+        };
+        accumulate(&mut chain, &full_ids, NetworkEvent::AckMessage(ack));
+        unwrap!(chain.save_journal(&mut storage));
+
+        // The header and both events.
+        assert_eq!(unwrap!(storage.keys(JOURNAL_NAMESPACE)).len(), 3);
+        let restored = unwrap!(unwrap!(Chain::restore(&storage)));
+        assert_eq!(restored.state, chain.state);
+        assert_eq!(restored.journal(), chain.journal());
+
+        unwrap!(chain.save(&mut storage));
+        assert!(chain.journal().is_empty());
+        assert_eq!(
+            unwrap!(storage.keys(JOURNAL_NAMESPACE)),
+            vec![JOURNAL_HEADER_KEY.to_vec()]
+        );
+        let restored = unwrap!(unwrap!(Chain::restore(&storage)));
+        assert_eq!(restored.state, chain.state);
+        assert!(restored.journal().is_empty());
+    }
+
+    #[test]
+    fn history_slices_prove_past_membership() {
+        let (mut chain, mut full_ids) = gen_chain(
+            MIN_SECTION_SIZE,
+            vec![(Prefix::from_str("").unwrap(), MIN_SECTION_SIZE)],
+        );
+        let first_version = *chain.our_info().version();
+        for _ in 0..3 {
+            let (new_info, new_ids) = gen_section_info(SecInfoGen::Add(chain.our_info()));
+            let proofs = gen_proofs(&full_ids, chain.our_info().members(), &new_info);
+            unwrap!(chain.add_section_info(new_info, proofs));
+            full_ids.extend(new_ids);
+        }
+
+        let slice = chain.our_history_slice(first_version + 1, first_version + 2);
+        assert_eq!(slice.len(), 2);
+        assert_eq!(*slice[0].0.version(), first_version + 1);
+        assert_eq!(*slice[1].0.version(), first_version + 2);
+        assert_eq!(chain.our_history_slice(first_version, 100).len(), 4);
+        assert!(chain.our_history_slice(first_version + 4, 100).is_empty());
+
+        // Each info is signed by a quorum of its predecessor.
+        for version in first_version + 1..=first_version + 3 {
+            let (prev_info, _) = unwrap!(chain.section_info_at(version - 1));
+            let (sec_info, proofs) = unwrap!(chain.section_info_at(version));
+            assert_eq!(*sec_info.version(), version);
+            assert!(prev_info.is_quorum(chain.network_params(), proofs));
+        }
+        assert!(chain.section_info_at(first_version + 4).is_none());
+    }
+
+    /// Returns `count` prefixes of length `bit_count` which are neighbours of the prefix of
+    /// `bit_count / 2` zeros, by flipping one of its bits and extending it arbitrarily.
+    fn many_neighbour_prefixes(bit_count: usize, count: usize) -> Vec<Prefix<XorName>> {
+        let our_len = bit_count / 2;
+        (0..count)
+            .map(|i| {
+                let flipped = i % our_len;
+                let extension = i / our_len;
+                let name = (our_len..bit_count)
+                    .fold(XorName::default().with_bit(flipped, true), |name, bit| {
+                        name.with_bit(bit, (extension >> (bit - our_len)) & 1 == 1)
+                    });
+                Prefix::new(bit_count, name)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn targeted_cleaning_matches_full_scan() {
+        let mut rng = thread_rng();
+        let p_00 = Prefix::from_str("00").unwrap();
+        let p_01 = Prefix::from_str("01").unwrap();
+        let p_10 = Prefix::from_str("10").unwrap();
+        let (mut chain, mut full_ids) =
+            gen_chain(MIN_SECTION_SIZE, vec![(p_00, 8), (p_01, 8), (p_10, 8)]);
+        for _ in 0..100 {
+            let (new_info, new_ids) = {
+                let old_info: Vec<_> = chain.neighbour_infos().collect();
+                let info = rng.choose(&old_info).expect("neighbour infos");
+                gen_section_info(SecInfoGen::Add(info))
+            };
+            full_ids.extend(new_ids);
+            let proofs = gen_proofs(&full_ids, chain.our_info().members(), &new_info);
+            unwrap!(chain.add_section_info(new_info, proofs));
+
+            let targeted = chain.other_prefixes();
+            chain.check_and_clean_neighbour_infos(None);
+            assert_eq!(targeted, chain.other_prefixes());
+        }
+    }
+
+    #[test]
+    fn compaction_removes_deadwood_incrementally() {
+        let p_0 = Prefix::from_str("00000000").unwrap();
+        let p_1 = Prefix::from_str("1").unwrap();
+        let (mut chain, _) = gen_chain(MIN_SECTION_SIZE, vec![(p_0, 8), (p_1, 8)]);
+
+        // Prefixes which differ from ours in two bits are not our neighbours.
+        let deadwood = many_neighbour_prefixes(16, 3 * super::NEIGHBOUR_COMPACTION_BATCH)
+            .into_iter()
+            .map(|pfx| pfx.with_flipped_bit(7));
+        for pfx in deadwood {
+            let (info, _) = gen_section_info(SecInfoGen::New(pfx, 1));
+            let _ = chain.state.neighbour_infos.insert(pfx, info);
+        }
+
+        chain.compact_neighbour_infos();
+        assert!(chain.other_prefixes().len() > 1);
+
+        for _ in 0..3 {
+            chain.compact_neighbour_infos();
+        }
+        let expected: BTreeSet<_> = iter::once(p_1).collect();
+        assert_eq!(chain.other_prefixes(), expected);
+    }
 }
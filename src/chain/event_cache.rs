@@ -0,0 +1,202 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::NetworkEvent;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    mem,
+};
+
+/// Maximum number of our own events cached while a split or merge is in progress.
+pub const MAX_CACHED_EVENTS: usize = 1024;
+
+/// How important it is to keep a cached event. When the cache is full, events of the lowest
+/// priority are evicted first.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum Priority {
+    /// Acknowledgements, which are sent again with the next section change anyway.
+    Ack,
+    /// Changes to our own or other sections' infos and keys, and to the state our section keeps.
+    SectionInfo,
+    /// Nodes joining or leaving, which the section can't make progress without.
+    Membership,
+}
+
+impl Priority {
+    fn of(event: &NetworkEvent) -> Self {
+        match *event {
+            NetworkEvent::AddElder(..)
+            | NetworkEvent::RemoveElder(_)
+            | NetworkEvent::Online(_)
+            | NetworkEvent::Offline(_)
+            | NetworkEvent::ExpectCandidate(_)
+            | NetworkEvent::PurgeCandidate(_)
+            | NetworkEvent::Relocate(_)
+            | NetworkEvent::Promote(_)
+            | NetworkEvent::Demote(_)
+            | NetworkEvent::AddInfant(_, _)
+            | NetworkEvent::RemoveInfant(_)
+            | NetworkEvent::SetJoinDifficulty(_)
+            | NetworkEvent::Allow(_)
+            | NetworkEvent::Deny(_) => Priority::Membership,
+            NetworkEvent::OurMerge
+            | NetworkEvent::NeighbourMerge(_)
+            | NetworkEvent::SectionInfo(_)
+            | NetworkEvent::TheirKeyInfo(_)
+            | NetworkEvent::Refresh(_)
+            | NetworkEvent::UpgradeSignal { .. }
+            | NetworkEvent::AccountCredit(_)
+            | NetworkEvent::AccountDebit(_)
+            | NetworkEvent::FailedAudits(_) => Priority::SectionInfo,
+            NetworkEvent::AckMessage(_)
+            | NetworkEvent::SendAckMessage(_)
+            | NetworkEvent::SequenceMessage(_) => Priority::Ack,
+        }
+    }
+}
+
+/// The events we voted for while a split or merge was in progress, to be voted for again once it
+/// is finalised.
+///
+/// The cache is bounded so a prolonged prefix change can't exhaust memory. Once it is full, a new
+/// event evicts one of lower priority, or is dropped itself if there is none.
+pub struct EventCache {
+    events: BTreeMap<Priority, BTreeSet<NetworkEvent>>,
+    len: usize,
+    capacity: usize,
+    /// The number of events evicted since the last call to `take_evicted`.
+    evicted: usize,
+}
+
+#[allow(clippy::len_without_is_empty)]
+impl EventCache {
+    /// Creates a cache holding at most `capacity` events.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            events: BTreeMap::new(),
+            len: 0,
+            capacity,
+            evicted: 0,
+        }
+    }
+
+    /// Adds `event` to the cache, evicting an event of lower priority if it is full.
+    pub fn insert(&mut self, event: NetworkEvent) {
+        let priority = Priority::of(&event);
+        if self
+            .events
+            .get(&priority)
+            .map_or(false, |events| events.contains(&event))
+        {
+            return;
+        }
+
+        if self.len >= self.capacity {
+            let lowest = self
+                .events
+                .iter()
+                .find(|(_, events)| !events.is_empty())
+                .map(|(priority, _)| *priority);
+            match lowest {
+                Some(lowest) if lowest < priority => self.evict(lowest),
+                _ => {
+                    self.evicted += 1;
+                    return;
+                }
+            }
+        }
+
+        let _ = self
+            .events
+            .entry(priority)
+            .or_insert_with(BTreeSet::new)
+            .insert(event);
+        self.len += 1;
+    }
+
+    /// Returns the number of cached events.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the number of events evicted since the last call, and resets it.
+    pub fn take_evicted(&mut self) -> usize {
+        let evicted = self.evicted;
+        self.evicted = 0;
+        evicted
+    }
+
+    /// Removes and returns all cached events, in order of decreasing priority.
+    pub fn take_all(&mut self) -> Vec<NetworkEvent> {
+        self.len = 0;
+        let events = mem::replace(&mut self.events, BTreeMap::new());
+        events
+            .into_iter()
+            .rev()
+            .flat_map(|(_, events)| events)
+            .collect()
+    }
+
+    fn evict(&mut self, priority: Priority) {
+        let evicted = self.events.get_mut(&priority).and_then(|events| {
+            let event = events.iter().next_back().cloned()?;
+            let _ = events.remove(&event);
+            Some(event)
+        });
+        if evicted.is_some() {
+            self.len -= 1;
+            self.evicted += 1;
+        }
+    }
+}
+
+impl Default for EventCache {
+    fn default() -> Self {
+        Self::new(MAX_CACHED_EVENTS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chain::AckMessagePayload, id::FullId};
+
+    fn ack() -> NetworkEvent {
+        NetworkEvent::AckMessage(AckMessagePayload {
+            src_prefix: Default::default(),
+            ack_version: rand::random(),
+        })
+    }
+
+    #[test]
+    fn evicts_lowest_priority_first() {
+        let mut cache = EventCache::new(3);
+        let offline = NetworkEvent::Offline(*FullId::new().public_id());
+        cache.insert(ack());
+        cache.insert(NetworkEvent::OurMerge);
+        cache.insert(ack());
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.take_evicted(), 0);
+
+        // A membership event evicts an ack...
+        cache.insert(offline.clone());
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.take_evicted(), 1);
+
+        // ...but another ack doesn't displace anything more important.
+        cache.insert(ack());
+        cache.insert(ack());
+        assert_eq!(cache.take_evicted(), 2);
+
+        let events = cache.take_all();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0], offline);
+        assert_eq!(events[1], NetworkEvent::OurMerge);
+        assert_eq!(cache.len(), 0);
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{ProofSet, SectionInfo};
+use crate::{routing_table::Prefix, sha3::Digest256, xor_name::XorName};
+use hex_fmt::HexFmt;
+use std::collections::BTreeSet;
+use std::fmt::{self, Debug, Formatter};
+
+/// Proof that the chain of a neighbouring section forked: several infos for the same prefix and
+/// version, each of which was accepted by a quorum of our section.
+///
+/// A section never signs two successors of the same info, so either some of its elders signed
+/// conflicting infos or our section was made to accept a forged one. The proofs are the
+/// signatures of the elders of our section who voted for each info, so the evidence can be
+/// audited offline.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForkEvidence {
+    prefix: Prefix<XorName>,
+    version: u64,
+    branches: Vec<(SectionInfo, ProofSet)>,
+}
+
+impl ForkEvidence {
+    pub(crate) fn new(first: (SectionInfo, ProofSet), second: (SectionInfo, ProofSet)) -> Self {
+        Self {
+            prefix: *first.0.prefix(),
+            version: *first.0.version(),
+            branches: vec![first, second],
+        }
+    }
+
+    /// Adds another branch. Returns `false` if we already had it.
+    #[cfg(feature = "node")]
+    pub(crate) fn add_branch(&mut self, sec_info: SectionInfo, proofs: ProofSet) -> bool {
+        if self.branches.iter().any(|(info, _)| *info == sec_info) {
+            return false;
+        }
+        self.branches.push((sec_info, proofs));
+        true
+    }
+
+    /// Returns the prefix of the forked section.
+    pub fn prefix(&self) -> &Prefix<XorName> {
+        &self.prefix
+    }
+
+    /// Returns the version the branches share.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the hash and member names of the info of each branch.
+    pub fn infos(&self) -> Vec<(Digest256, BTreeSet<XorName>)> {
+        self.branches
+            .iter()
+            .map(|(info, _)| (*info.hash(), info.member_names()))
+            .collect()
+    }
+
+    /// Returns the info of each branch, with the proofs our section accepted it with. These are
+    /// empty if the info was accepted before our chain was restored.
+    pub fn branches(&self) -> &[(SectionInfo, ProofSet)] {
+        &self.branches
+    }
+}
+
+impl Debug for ForkEvidence {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        let hashes: Vec<_> = self
+            .branches
+            .iter()
+            .map(|(info, _)| HexFmt(info.hash()))
+            .collect();
+        write!(
+            formatter,
+            "ForkEvidence {{ prefix: {:?}, version: {}, hashes: {:?} }}",
+            self.prefix, self.version, hashes
+        )
+    }
+}
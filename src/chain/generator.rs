@@ -0,0 +1,185 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{Chain, GenesisPfxInfo, SectionInfo, SectionKeyInfo};
+use crate::{
+    id::{FullId, PublicId},
+    network_params::NetworkParams,
+    Prefix, XorName,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use unwrap::unwrap;
+
+/// Builds the `Chain`s of the nodes of a network with a given topology directly, without
+/// simulating how the network grew into it. Useful for benchmarks and tests which need large
+/// networks.
+///
+/// All sections are at version 0 and everyone knows everyone else's keys.
+pub struct NetworkGenerator {
+    min_section_size: usize,
+    sections: BTreeMap<Prefix<XorName>, SectionInfo>,
+    full_ids: HashMap<PublicId, FullId>,
+    /// For each section, the version of it each other section has acknowledged.
+    their_knowledge: BTreeMap<Prefix<XorName>, BTreeMap<Prefix<XorName>, u64>>,
+}
+
+impl NetworkGenerator {
+    /// Generates the given sections, each with the given number of members with random names
+    /// matching its prefix. Panics if any two of the prefixes are compatible.
+    pub fn new<I>(min_section_size: usize, sections: I) -> Self
+    where
+        I: IntoIterator<Item = (Prefix<XorName>, usize)>,
+    {
+        let mut infos = BTreeMap::new();
+        let mut full_ids = HashMap::new();
+        for (pfx, size) in sections {
+            if let Some(other) = infos
+                .keys()
+                .find(|other: &&Prefix<XorName>| other.is_compatible(&pfx))
+            {
+                panic!("Section prefixes {:?} and {:?} overlap.", pfx, other);
+            }
+
+            let mut members = BTreeSet::new();
+            for _ in 0..size {
+                let full_id = FullId::within_range(&pfx.range_inclusive());
+                let _ = members.insert(*full_id.public_id());
+                let _ = full_ids.insert(*full_id.public_id(), full_id);
+            }
+            let info = unwrap!(SectionInfo::new(members, pfx, None));
+            let _ = infos.insert(pfx, info);
+        }
+
+        NetworkGenerator {
+            min_section_size,
+            sections: infos,
+            full_ids,
+            their_knowledge: BTreeMap::new(),
+        }
+    }
+
+    /// Records that the section with prefix `their_pfx` has acknowledged `version` of the section
+    /// with prefix `our_pfx`.
+    pub fn with_knowledge(
+        mut self,
+        our_pfx: Prefix<XorName>,
+        their_pfx: Prefix<XorName>,
+        version: u64,
+    ) -> Self {
+        let _ = self
+            .their_knowledge
+            .entry(our_pfx)
+            .or_insert_with(BTreeMap::new)
+            .insert(their_pfx, version);
+        self
+    }
+
+    /// Returns the infos of all generated sections.
+    pub fn section_infos(&self) -> impl Iterator<Item = &SectionInfo> {
+        self.sections.values()
+    }
+
+    /// Returns the full ID of the generated node `pub_id`.
+    pub fn full_id(&self, pub_id: &PublicId) -> Option<&FullId> {
+        self.full_ids.get(pub_id)
+    }
+
+    /// Returns the chain of the node `our_id`, or `None` if it isn't one of the generated nodes.
+    pub fn chain(&self, our_id: &PublicId) -> Option<Chain> {
+        let our_info = self
+            .sections
+            .values()
+            .find(|info| info.members().contains(our_id))?;
+        let gen_info = GenesisPfxInfo {
+            first_info: our_info.clone(),
+            first_state_serialized: Vec::new(),
+            latest_info: Default::default(),
+        };
+        let network_params = NetworkParams {
+            min_section_size: self.min_section_size,
+            ..NetworkParams::default()
+        };
+        let mut chain = Chain::new(network_params, *our_id, gen_info);
+
+        let state = chain.state_mut();
+        state.neighbour_infos = self
+            .sections
+            .iter()
+            .filter(|(pfx, _)| pfx.is_neighbour(our_info.prefix()))
+            .map(|(pfx, info)| (*pfx, info.clone()))
+            .collect();
+        state.their_keys = self
+            .sections
+            .iter()
+            .map(|(pfx, info)| {
+                (
+                    *pfx,
+                    SectionKeyInfo::from_section_info(info, network_params),
+                )
+            })
+            .collect();
+        if let Some(knowledge) = self.their_knowledge.get(our_info.prefix()) {
+            state.their_knowledge = knowledge.clone();
+        }
+
+        Some(chain)
+    }
+
+    /// Returns the chains of all generated nodes.
+    pub fn chains(&self) -> Vec<Chain> {
+        self.sections
+            .values()
+            .flat_map(SectionInfo::members)
+            .filter_map(|pub_id| self.chain(pub_id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::verify_chain_invariant;
+    use std::str::FromStr;
+
+    #[test]
+    fn generated_chains_satisfy_invariant() {
+        let min_section_size = 3;
+        let prefixes = ["000", "001", "01", "10", "110", "111"];
+        let generator = NetworkGenerator::new(
+            min_section_size,
+            prefixes
+                .iter()
+                .map(|pfx| (unwrap!(Prefix::from_str(pfx)), min_section_size)),
+        )
+        .with_knowledge(
+            unwrap!(Prefix::from_str("01")),
+            unwrap!(Prefix::from_str("000")),
+            0,
+        );
+
+        let chains = generator.chains();
+        assert_eq!(chains.len(), prefixes.len() * min_section_size);
+        verify_chain_invariant(&chains, min_section_size);
+
+        let pub_id = *unwrap!(chains[0].our_info().members().iter().next());
+        assert!(generator.full_id(&pub_id).is_some());
+        assert_eq!(generator.section_infos().count(), prefixes.len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn overlapping_prefixes() {
+        let _ = NetworkGenerator::new(
+            3,
+            vec![
+                (unwrap!(Prefix::from_str("0")), 3),
+                (unwrap!(Prefix::from_str("01")), 3),
+            ],
+        );
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::id::PublicId;
+#[cfg(feature = "node")]
+use std::collections::BTreeSet;
+use std::net::IpAddr;
+
+/// Identifies joining nodes in our section's allow and deny lists.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum JoinerId {
+    /// The ID the node joins with.
+    Node(PublicId),
+    /// The address the node connects from.
+    Ip(IpAddr),
+}
+
+/// The allow and deny lists of joining nodes our section agreed on.
+///
+/// A node is refused if it is deny-listed. Once anything is allow-listed, only allow-listed nodes
+/// may join, which is how private networks restrict their membership.
+#[cfg(feature = "node")]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JoinFilter {
+    allowed: BTreeSet<JoinerId>,
+    denied: BTreeSet<JoinerId>,
+}
+
+#[cfg(feature = "node")]
+impl JoinFilter {
+    /// Allows `id` to join, taking it off the deny list.
+    pub(crate) fn allow(&mut self, id: JoinerId) {
+        let _ = self.denied.remove(&id);
+        let _ = self.allowed.insert(id);
+    }
+
+    /// Refuses `id`, taking it off the allow list.
+    pub(crate) fn deny(&mut self, id: JoinerId) {
+        let _ = self.allowed.remove(&id);
+        let _ = self.denied.insert(id);
+    }
+
+    /// Returns whether a node with any of the given IDs may join.
+    pub fn is_allowed<'a, I>(&self, ids: I) -> bool
+    where
+        I: IntoIterator<Item = &'a JoinerId> + Clone,
+    {
+        if ids.clone().into_iter().any(|id| self.denied.contains(id)) {
+            return false;
+        }
+        self.allowed.is_empty() || ids.into_iter().any(|id| self.allowed.contains(id))
+    }
+
+    /// Returns the allow-listed IDs.
+    pub fn allowed(&self) -> &BTreeSet<JoinerId> {
+        &self.allowed
+    }
+
+    /// Returns the deny-listed IDs.
+    pub fn denied(&self) -> &BTreeSet<JoinerId> {
+        &self.denied
+    }
+}
+
+#[cfg(all(test, feature = "node"))]
+mod tests {
+    use super::*;
+    use crate::id::FullId;
+    use unwrap::unwrap;
+
+    #[test]
+    fn allow_and_deny() {
+        let mut filter = JoinFilter::default();
+        let node = JoinerId::Node(*FullId::new().public_id());
+        let other = JoinerId::Node(*FullId::new().public_id());
+        let ip = JoinerId::Ip(unwrap!("10.0.0.1".parse()));
+        assert!(filter.is_allowed(&[node, ip]));
+
+        filter.deny(ip);
+        assert!(!filter.is_allowed(&[node, ip]));
+        assert!(filter.is_allowed(&[other]));
+
+        // Once anyone is allow-listed, everyone else is refused.
+        filter.allow(node);
+        assert!(filter.is_allowed(&[node]));
+        assert!(!filter.is_allowed(&[other]));
+        // Deny-listing takes precedence.
+        assert!(!filter.is_allowed(&[node, ip]));
+
+        filter.allow(ip);
+        assert!(filter.is_allowed(&[node, ip]));
+        filter.deny(node);
+        assert!(!filter.allowed().contains(&node));
+        assert!(filter.denied().contains(&node));
+    }
+}
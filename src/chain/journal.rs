@@ -0,0 +1,181 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{GenesisPfxInfo, NetworkEvent, ProofSet};
+use crate::{
+    error::RoutingError,
+    id::PublicId,
+    network_params::NetworkParams,
+    storage::{Storage, WriteBatch},
+};
+use maidsafe_utilities::serialisation;
+use std::fmt::{self, Debug, Formatter};
+
+/// The `Storage` namespace of the journal.
+pub const JOURNAL_NAMESPACE: &str = "journal";
+/// The key of the state the journal starts from. The entries are stored under their big-endian
+/// index, which never collides with it as it isn't 8 bytes long.
+pub const JOURNAL_HEADER_KEY: &[u8] = b"header";
+
+/// An accumulated network event, with the proofs it accumulated with.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    event: NetworkEvent,
+    proofs: ProofSet,
+}
+
+impl JournalEntry {
+    pub(crate) fn event(&self) -> &NetworkEvent {
+        &self.event
+    }
+
+    pub(crate) fn proofs(&self) -> &ProofSet {
+        &self.proofs
+    }
+}
+
+/// An append-only record of every network event accumulated by our section, in the order they
+/// were applied to the chain, starting from the genesis of the chain or its last checkpoint, i.e.
+/// the last snapshot written by `Chain::save`.
+///
+/// Each entry carries the signatures of the elders who voted for it, so the journal can be audited
+/// offline, and `replay` rebuilds the shared state from it, e.g. to find where two nodes forked.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventJournal {
+    network_params: NetworkParams,
+    our_id: PublicId,
+    genesis: GenesisPfxInfo,
+    entries: Vec<JournalEntry>,
+    /// The number of entries already written to storage.
+    #[serde(skip)]
+    saved_len: usize,
+}
+
+impl EventJournal {
+    pub(crate) fn new(
+        network_params: NetworkParams,
+        our_id: PublicId,
+        genesis: GenesisPfxInfo,
+    ) -> Self {
+        Self {
+            network_params,
+            our_id,
+            genesis,
+            entries: Vec::new(),
+            saved_len: 0,
+        }
+    }
+
+    /// Restores a journal written by `EventJournal::save`. Returns `None` if `storage` doesn't
+    /// contain one.
+    pub(crate) fn restore(storage: &dyn Storage) -> Result<Option<Self>, RoutingError> {
+        let header = match storage.get(JOURNAL_NAMESPACE, JOURNAL_HEADER_KEY)? {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+        let (network_params, our_id, genesis) = serialisation::deserialise(&header)?;
+        let mut journal = Self::new(network_params, our_id, genesis);
+        while let Some(entry) = storage.get(JOURNAL_NAMESPACE, &entry_key(journal.len()))? {
+            journal.entries.push(serialisation::deserialise(&entry)?);
+        }
+        journal.saved_len = journal.entries.len();
+        Ok(Some(journal))
+    }
+
+    /// Writes the entries appended since the last call to `storage`.
+    pub(crate) fn save(&mut self, storage: &mut dyn Storage) -> Result<(), RoutingError> {
+        let mut batch = WriteBatch::new();
+        self.save_to_batch(&mut batch)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        storage.write(batch)
+    }
+
+    /// Drops all entries and restarts the journal from `genesis`, the state the entries led to.
+    /// Adds replacing the journal in `storage` to `batch`, so the caller can write it atomically
+    /// together with the matching snapshot.
+    pub(crate) fn truncate(
+        &mut self,
+        genesis: GenesisPfxInfo,
+        storage: &dyn Storage,
+        batch: &mut WriteBatch,
+    ) -> Result<(), RoutingError> {
+        for key in storage.keys(JOURNAL_NAMESPACE)? {
+            let _ = batch.delete(JOURNAL_NAMESPACE, &key);
+        }
+        self.genesis = genesis;
+        self.entries.clear();
+        self.saved_len = 0;
+        self.save_to_batch(batch)
+    }
+
+    fn save_to_batch(&mut self, batch: &mut WriteBatch) -> Result<(), RoutingError> {
+        if self.saved_len == 0 {
+            let header = (&self.network_params, &self.our_id, &self.genesis);
+            let _ = batch.put(
+                JOURNAL_NAMESPACE,
+                JOURNAL_HEADER_KEY,
+                serialisation::serialise(&header)?,
+            );
+        }
+        for (index, entry) in self.entries.iter().enumerate().skip(self.saved_len) {
+            let _ = batch.put(
+                JOURNAL_NAMESPACE,
+                &entry_key(index),
+                serialisation::serialise(entry)?,
+            );
+        }
+        self.saved_len = self.entries.len();
+        Ok(())
+    }
+
+    pub(crate) fn append(&mut self, event: NetworkEvent, proofs: ProofSet) {
+        self.entries.push(JournalEntry { event, proofs });
+    }
+
+    pub(crate) fn network_params(&self) -> NetworkParams {
+        self.network_params
+    }
+
+    /// Returns the ID of the node which kept this journal.
+    pub fn our_id(&self) -> &PublicId {
+        &self.our_id
+    }
+
+    pub(crate) fn genesis(&self) -> &GenesisPfxInfo {
+        &self.genesis
+    }
+
+    pub(crate) fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Returns the number of events recorded.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether no events have been recorded yet.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+fn entry_key(index: usize) -> [u8; 8] {
+    (index as u64).to_be_bytes()
+}
+
+impl Debug for EventJournal {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter
+            .debug_list()
+            .entries(self.entries.iter().map(JournalEntry::event))
+            .finish()
+    }
+}
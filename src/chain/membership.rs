@@ -0,0 +1,28 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{id::PublicId, routing_table::Prefix, xor_name::XorName};
+
+/// A statement by a section about how long one of its members has been part of it.
+///
+/// It is sent to the member as a section-signed message, so systems outside this crate (e.g.
+/// rewards or node ageing) can rely on it without trusting the member itself.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct MembershipStatement {
+    /// The member the statement is about.
+    pub member: PublicId,
+    /// The prefix of the section making the statement.
+    pub prefix: Prefix<XorName>,
+    /// The version of the section info the statement was made at.
+    pub version: u64,
+    /// The version of the first section info the member was part of without interruption, as far
+    /// back as the section still holds its infos.
+    pub since_version: u64,
+    /// The number of times the member was relocated, i.e. its age.
+    pub relocations: u32,
+}
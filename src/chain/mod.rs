@@ -6,30 +6,67 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-// The `chain` submodule contains the `Chain` implementation, which we reexport here.
+// The `chain` submodule contains the `Chain` implementation, which we reexport here. Only nodes
+// keep a chain; clients just need the section infos, proofs and events they exchange with nodes.
 pub(crate) mod bls_emu;
+#[cfg(feature = "node")]
 mod candidate;
+#[cfg(feature = "node")]
 #[allow(clippy::module_inception)]
 mod chain;
+#[cfg(feature = "node")]
+mod event_cache;
+mod fork;
+#[cfg(all(feature = "node", any(test, feature = "mock_base")))]
+mod generator;
+mod join_filter;
+#[cfg(feature = "node")]
+mod journal;
+mod membership;
 mod network_event;
+#[cfg(feature = "node")]
+mod peer_score;
 mod proof;
+#[cfg(feature = "node")]
+pub mod replay;
 mod section_info;
 mod shared_state;
-#[cfg(any(test, feature = "mock_base"))]
+#[cfg(feature = "node")]
+mod snapshot;
+mod stability_guard;
+#[cfg(all(feature = "node", any(test, feature = "mock_base")))]
 mod test_utils;
+#[cfg(feature = "node")]
+mod vote_timeout;
 
-#[cfg(any(test, feature = "mock_base"))]
-pub use self::test_utils::verify_chain_invariant;
+#[cfg(feature = "node")]
 pub use self::{
-    chain::{delivery_group_size, Chain, PrefixChangeOutcome},
+    chain::{
+        delivery_group_size, Chain, DeliveryScope, NeighbourChange, NetworkEpoch,
+        PrefixChangeOutcome,
+    },
+    join_filter::JoinFilter,
+    journal::EventJournal,
+    peer_score::Misbehaviour,
+    shared_state::{PendingUpgrade, PrefixChange},
+    vote_timeout::{PendingEvent, PendingVote},
+};
+pub use self::{
+    fork::ForkEvidence,
+    join_filter::JoinerId,
+    membership::MembershipStatement,
     network_event::{
-        AckMessagePayload, ExpectCandidatePayload, NetworkEvent, OnlinePayload,
-        SendAckMessagePayload,
+        AccountPayload, AckMessagePayload, AuditPayload, ElderChangePayload, EventDescriptor,
+        ExpectCandidatePayload, NetworkEvent, NetworkEventKind, OnlinePayload, RefreshPayload,
+        RelocatePayload, SendAckMessagePayload, SequencePayload,
     },
     proof::{Proof, ProofSet},
     section_info::SectionInfo,
-    shared_state::{PrefixChange, SectionKeyInfo, SectionProofChain},
+    shared_state::{SectionKeyInfo, SectionProofChain},
+    stability_guard::StabilityGuard,
 };
+#[cfg(all(feature = "node", any(test, feature = "mock_base")))]
+pub use self::{generator::NetworkGenerator, test_utils::verify_chain_invariant};
 use std::fmt::{self, Debug, Formatter};
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
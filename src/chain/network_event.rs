@@ -6,8 +6,9 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{ProofSet, SectionInfo, SectionKeyInfo};
+use super::{JoinerId, ProofSet, SectionInfo, SectionKeyInfo};
 use crate::id::PublicId;
+use crate::network_params::NetworkParams;
 use crate::parsec;
 use crate::routing_table::Prefix;
 use crate::sha3::Digest256;
@@ -15,7 +16,10 @@ use crate::types::MessageId;
 use crate::{Authority, RoutingError, XorName};
 use hex_fmt::HexFmt;
 use maidsafe_utilities::serialisation::serialise;
-use std::fmt::{self, Debug, Formatter};
+use std::{
+    collections::BTreeSet,
+    fmt::{self, Debug, Formatter},
+};
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct ExpectCandidatePayload {
@@ -39,6 +43,32 @@ pub struct OnlinePayload {
     pub client_auth: Authority<XorName>,
 }
 
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct RelocatePayload {
+    /// The member to relocate.
+    pub pub_id: PublicId,
+    /// The version of our section info that made the relocation due. A member whose relocation
+    /// failed can be chosen again later.
+    pub version: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct ElderChangePayload {
+    /// The member which became or stopped being an elder.
+    pub pub_id: PublicId,
+    /// The version of our section info that made the change.
+    pub version: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct RefreshPayload {
+    /// The version of our section info which added the new members.
+    pub version: u64,
+    /// The members which joined our section with that version, and need to be sent the data they
+    /// are now responsible for.
+    pub new_members: BTreeSet<PublicId>,
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct AckMessagePayload {
     /// The prefix of our section when we acknowledge their SectionInfo of version ack_version.
@@ -55,6 +85,34 @@ pub struct SendAckMessagePayload {
     pub ack_version: u64,
 }
 
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct AccountPayload {
+    /// The name of the client account, which our section manages.
+    pub account: XorName,
+    /// The number of mutations credited or debited.
+    pub amount: u64,
+    /// Distinguishes the event from others for the same account and amount.
+    pub msg_id: MessageId,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct SequencePayload {
+    /// Our section's authority sending the message.
+    pub src: Authority<XorName>,
+    /// The message's destination.
+    pub dst: Authority<XorName>,
+    /// The hash of the message's content and priority.
+    pub digest: Digest256,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct AuditPayload {
+    /// The node or data manager group which failed too many audits of data it should hold.
+    pub auditee: Authority<XorName>,
+    /// The version of our section info when the failures were reported.
+    pub version: u64,
+}
+
 /// Routing Network events
 // TODO: Box `SectionInfo`?
 #[allow(clippy::large_enum_variant)]
@@ -88,13 +146,69 @@ pub enum NetworkEvent {
 
     // Voted for sending AckMessage (Require 100% consensus)
     SendAckMessage(SendAckMessagePayload),
+
+    /// Voted for relocating the given member to another section, once our section has seen
+    /// enough churn since the last relocation.
+    Relocate(RelocatePayload),
+
+    /// Voted for a member which became one of our elders by being among the oldest.
+    Promote(ElderChangePayload),
+    /// Voted for an elder which stayed a member, but is no longer among the oldest.
+    Demote(ElderChangePayload),
+
+    /// Add a candidate which is too young to be an adult as an infant once we agreed to add it.
+    /// Infants aren't part of our `SectionInfo`, so this doesn't create a new one.
+    AddInfant(PublicId, Authority<XorName>),
+    /// Remove an infant once we agreed to remove the peer.
+    RemoveInfant(PublicId),
+
+    /// Voted for new members of our section, once our section info including them accumulated, so
+    /// the data held by our section is republished to them.
+    Refresh(RefreshPayload),
+
+    /// Voted for the resource proof difficulty our section challenges candidates with, as it
+    /// adapts to the rate of joins.
+    SetJoinDifficulty(u8),
+
+    /// Voted for allowing the given node to join our section.
+    Allow(JoinerId),
+    /// Voted for refusing the given node when it tries to join our section.
+    Deny(JoinerId),
+
+    /// Voted for upgrading to protocol `version` once our section info reaches
+    /// `activation_version`, either on our operators' request or because a neighbouring section
+    /// agreed to it.
+    UpgradeSignal {
+        version: u16,
+        activation_version: u64,
+    },
+
+    /// Voted for allowing a client account to perform more mutations, on a vault's request.
+    AccountCredit(AccountPayload),
+    /// Voted for charging a client account for a mutation request we let through.
+    AccountDebit(AccountPayload),
+
+    /// Voted for a user message our section sends, so all elders give it the next sequence number
+    /// of its route in the order the votes accumulate.
+    SequenceMessage(SequencePayload),
+
+    /// Voted for a node or group which failed `MAX_FAILED_AUDITS` data audits, as reported by our
+    /// vaults or by the section which audited it.
+    FailedAudits(AuditPayload),
 }
 
 impl NetworkEvent {
     /// Checks if the given `SectionInfo` is a valid successor of `self`.
-    pub fn proves_successor_info(&self, their_si: &SectionInfo, proofs: &ProofSet) -> bool {
+    pub fn proves_successor_info(
+        &self,
+        params: &NetworkParams,
+        their_si: &SectionInfo,
+        proofs: &ProofSet,
+    ) -> bool {
         match *self {
-            NetworkEvent::SectionInfo(ref self_si) => self_si.proves_successor(their_si, proofs),
+            NetworkEvent::SectionInfo(ref self_si) => {
+                self_si.proves_successor(params, their_si, proofs)
+            }
             _ => false,
         }
     }
@@ -107,6 +221,135 @@ impl NetworkEvent {
         }
     }
 
+    /// Returns whether this event changes the membership of a section. These are still handled
+    /// while our section is unstable, as they are needed for it to settle.
+    pub fn is_membership_change(&self) -> bool {
+        match *self {
+            NetworkEvent::AddElder(_, _)
+            | NetworkEvent::RemoveElder(_)
+            | NetworkEvent::Online(_)
+            | NetworkEvent::Offline(_)
+            | NetworkEvent::SectionInfo(_)
+            | NetworkEvent::ExpectCandidate(_)
+            | NetworkEvent::PurgeCandidate(_)
+            | NetworkEvent::Relocate(_)
+            | NetworkEvent::Promote(_)
+            | NetworkEvent::Demote(_)
+            | NetworkEvent::AddInfant(_, _)
+            | NetworkEvent::RemoveInfant(_) => true,
+            NetworkEvent::OurMerge
+            | NetworkEvent::NeighbourMerge(_)
+            | NetworkEvent::TheirKeyInfo(_)
+            | NetworkEvent::AckMessage(_)
+            | NetworkEvent::SendAckMessage(_)
+            | NetworkEvent::Refresh(_)
+            | NetworkEvent::SetJoinDifficulty(_)
+            | NetworkEvent::Allow(_)
+            | NetworkEvent::Deny(_)
+            | NetworkEvent::UpgradeSignal { .. }
+            | NetworkEvent::AccountCredit(_)
+            | NetworkEvent::AccountDebit(_)
+            | NetworkEvent::SequenceMessage(_)
+            | NetworkEvent::FailedAudits(_) => false,
+        }
+    }
+
+    /// Returns the kind of this event with the fields identifying it, for reporting it to users
+    /// of the library without exposing its full payload.
+    pub fn descriptor(&self) -> EventDescriptor {
+        let mut descriptor = EventDescriptor::new(self.kind());
+        match *self {
+            NetworkEvent::AddElder(ref id, _)
+            | NetworkEvent::RemoveElder(ref id)
+            | NetworkEvent::Offline(ref id)
+            | NetworkEvent::PurgeCandidate(ref id)
+            | NetworkEvent::AddInfant(ref id, _)
+            | NetworkEvent::RemoveInfant(ref id)
+            | NetworkEvent::Allow(JoinerId::Node(ref id))
+            | NetworkEvent::Deny(JoinerId::Node(ref id)) => descriptor.pub_id = Some(*id),
+            NetworkEvent::Online(ref payload) => descriptor.pub_id = Some(payload.new_public_id),
+            NetworkEvent::ExpectCandidate(ref payload) => {
+                descriptor.pub_id = Some(payload.old_public_id);
+                descriptor.name = Some(payload.dst_name);
+            }
+            NetworkEvent::SectionInfo(ref info) => {
+                descriptor.prefix = Some(*info.prefix());
+                descriptor.version = Some(*info.version());
+            }
+            NetworkEvent::TheirKeyInfo(ref key_info) => {
+                descriptor.prefix = Some(*key_info.prefix());
+                descriptor.version = Some(*key_info.version());
+            }
+            NetworkEvent::AckMessage(ref payload) => {
+                descriptor.prefix = Some(payload.src_prefix);
+                descriptor.version = Some(payload.ack_version);
+            }
+            NetworkEvent::SendAckMessage(ref payload) => {
+                descriptor.prefix = Some(payload.ack_prefix);
+                descriptor.version = Some(payload.ack_version);
+            }
+            NetworkEvent::Relocate(ref payload) => {
+                descriptor.pub_id = Some(payload.pub_id);
+                descriptor.version = Some(payload.version);
+            }
+            NetworkEvent::Promote(ref payload) | NetworkEvent::Demote(ref payload) => {
+                descriptor.pub_id = Some(payload.pub_id);
+                descriptor.version = Some(payload.version);
+            }
+            NetworkEvent::Refresh(ref payload) => descriptor.version = Some(payload.version),
+            NetworkEvent::UpgradeSignal {
+                activation_version, ..
+            } => descriptor.version = Some(activation_version),
+            NetworkEvent::AccountCredit(ref payload) | NetworkEvent::AccountDebit(ref payload) => {
+                descriptor.name = Some(payload.account)
+            }
+            NetworkEvent::SequenceMessage(ref payload) => {
+                descriptor.name = Some(payload.dst.name())
+            }
+            NetworkEvent::FailedAudits(ref payload) => {
+                descriptor.name = Some(payload.auditee.name());
+                descriptor.version = Some(payload.version);
+            }
+            NetworkEvent::OurMerge
+            | NetworkEvent::NeighbourMerge(_)
+            | NetworkEvent::SetJoinDifficulty(_)
+            | NetworkEvent::Allow(JoinerId::Ip(_))
+            | NetworkEvent::Deny(JoinerId::Ip(_)) => (),
+        }
+        descriptor
+    }
+
+    fn kind(&self) -> NetworkEventKind {
+        match *self {
+            NetworkEvent::AddElder(_, _) => NetworkEventKind::AddElder,
+            NetworkEvent::RemoveElder(_) => NetworkEventKind::RemoveElder,
+            NetworkEvent::Online(_) => NetworkEventKind::Online,
+            NetworkEvent::Offline(_) => NetworkEventKind::Offline,
+            NetworkEvent::OurMerge => NetworkEventKind::OurMerge,
+            NetworkEvent::NeighbourMerge(_) => NetworkEventKind::NeighbourMerge,
+            NetworkEvent::SectionInfo(_) => NetworkEventKind::SectionInfo,
+            NetworkEvent::ExpectCandidate(_) => NetworkEventKind::ExpectCandidate,
+            NetworkEvent::PurgeCandidate(_) => NetworkEventKind::PurgeCandidate,
+            NetworkEvent::TheirKeyInfo(_) => NetworkEventKind::TheirKeyInfo,
+            NetworkEvent::AckMessage(_) => NetworkEventKind::AckMessage,
+            NetworkEvent::SendAckMessage(_) => NetworkEventKind::SendAckMessage,
+            NetworkEvent::Relocate(_) => NetworkEventKind::Relocate,
+            NetworkEvent::Promote(_) => NetworkEventKind::Promote,
+            NetworkEvent::Demote(_) => NetworkEventKind::Demote,
+            NetworkEvent::AddInfant(_, _) => NetworkEventKind::AddInfant,
+            NetworkEvent::RemoveInfant(_) => NetworkEventKind::RemoveInfant,
+            NetworkEvent::Refresh(_) => NetworkEventKind::Refresh,
+            NetworkEvent::SetJoinDifficulty(_) => NetworkEventKind::SetJoinDifficulty,
+            NetworkEvent::Allow(_) => NetworkEventKind::Allow,
+            NetworkEvent::Deny(_) => NetworkEventKind::Deny,
+            NetworkEvent::UpgradeSignal { .. } => NetworkEventKind::UpgradeSignal,
+            NetworkEvent::AccountCredit(_) => NetworkEventKind::AccountCredit,
+            NetworkEvent::AccountDebit(_) => NetworkEventKind::AccountDebit,
+            NetworkEvent::SequenceMessage(_) => NetworkEventKind::SequenceMessage,
+            NetworkEvent::FailedAudits(_) => NetworkEventKind::FailedAudits,
+        }
+    }
+
     /// Convert `NetworkEvent` into a Parsec Observation
     pub fn into_obs(self) -> Result<parsec::Observation<NetworkEvent, PublicId>, RoutingError> {
         Ok(match self {
@@ -154,6 +397,125 @@ impl Debug for NetworkEvent {
             NetworkEvent::SendAckMessage(ref payload) => {
                 write!(formatter, "SendAckMessage({:?})", payload)
             }
+            NetworkEvent::Relocate(ref payload) => write!(
+                formatter,
+                "Relocate({}, v{})",
+                payload.pub_id, payload.version
+            ),
+            NetworkEvent::Promote(ref payload) => write!(
+                formatter,
+                "Promote({}, v{})",
+                payload.pub_id, payload.version
+            ),
+            NetworkEvent::Demote(ref payload) => {
+                write!(
+                    formatter,
+                    "Demote({}, v{})",
+                    payload.pub_id, payload.version
+                )
+            }
+            NetworkEvent::AddInfant(ref id, _) => write!(formatter, "AddInfant({}, _)", id),
+            NetworkEvent::RemoveInfant(ref id) => write!(formatter, "RemoveInfant({})", id),
+            NetworkEvent::Refresh(ref payload) => write!(
+                formatter,
+                "Refresh({:?}, v{})",
+                payload.new_members, payload.version
+            ),
+            NetworkEvent::SetJoinDifficulty(difficulty) => {
+                write!(formatter, "SetJoinDifficulty({})", difficulty)
+            }
+            NetworkEvent::Allow(ref id) => write!(formatter, "Allow({:?})", id),
+            NetworkEvent::Deny(ref id) => write!(formatter, "Deny({:?})", id),
+            NetworkEvent::UpgradeSignal {
+                version,
+                activation_version,
+            } => write!(
+                formatter,
+                "UpgradeSignal(v{} at v{})",
+                version, activation_version
+            ),
+            NetworkEvent::AccountCredit(ref payload) => write!(
+                formatter,
+                "AccountCredit({}, {})",
+                payload.account, payload.amount
+            ),
+            NetworkEvent::AccountDebit(ref payload) => write!(
+                formatter,
+                "AccountDebit({}, {})",
+                payload.account, payload.amount
+            ),
+            NetworkEvent::SequenceMessage(ref payload) => write!(
+                formatter,
+                "SequenceMessage({:?} -> {:?}, {:.14?})",
+                payload.src,
+                payload.dst,
+                HexFmt(&payload.digest)
+            ),
+            NetworkEvent::FailedAudits(ref payload) => write!(
+                formatter,
+                "FailedAudits({:?}, v{})",
+                payload.auditee, payload.version
+            ),
+        }
+    }
+}
+
+/// The kind of a network event, named after its `NetworkEvent` variant.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum NetworkEventKind {
+    AddElder,
+    RemoveElder,
+    Online,
+    Offline,
+    OurMerge,
+    NeighbourMerge,
+    SectionInfo,
+    ExpectCandidate,
+    PurgeCandidate,
+    TheirKeyInfo,
+    AckMessage,
+    SendAckMessage,
+    Relocate,
+    Promote,
+    Demote,
+    AddInfant,
+    RemoveInfant,
+    Refresh,
+    SetJoinDifficulty,
+    Allow,
+    Deny,
+    UpgradeSignal,
+    AccountCredit,
+    AccountDebit,
+    SequenceMessage,
+    FailedAudits,
+}
+
+/// Identifies a network event by its kind and key fields, as reported in `Event::VoteStalled` and
+/// by `Node::pending_votes`. Fields which don't apply to the kind of event are `None`.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct EventDescriptor {
+    /// The kind of the event.
+    pub kind: NetworkEventKind,
+    /// The node the event is about.
+    pub pub_id: Option<PublicId>,
+    /// The name of the account, auditee or destination the event is about.
+    pub name: Option<XorName>,
+    /// The prefix of the section the event is about.
+    pub prefix: Option<Prefix<XorName>>,
+    /// The version of the section info the event refers to.
+    pub version: Option<u64>,
+}
+
+impl EventDescriptor {
+    fn new(kind: NetworkEventKind) -> Self {
+        EventDescriptor {
+            kind,
+            pub_id: None,
+            name: None,
+            prefix: None,
+            version: None,
         }
     }
 }
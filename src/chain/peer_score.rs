@@ -0,0 +1,123 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::id::PublicId;
+use std::collections::{BTreeMap, BTreeSet};
+use std::mem;
+
+/// Score at which a peer is considered malicious and voted offline, unless configured otherwise.
+pub const DEFAULT_MISBEHAVIOUR_THRESHOLD: u64 = 10;
+
+/// A kind of misbehaviour observed in the votes of a section member.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Misbehaviour {
+    /// The peer voted for the same event more than once.
+    DuplicateProof,
+    /// The peer's vote wasn't correctly signed.
+    InvalidSignature,
+    /// The peer voted for two different infos of our section with the same version.
+    ContradictorySectionInfo,
+    /// The peer failed `MAX_FAILED_AUDITS` audits of data it should hold.
+    FailedAudits,
+}
+
+impl Misbehaviour {
+    /// The score added for one occurrence. A duplicate proof can result from a resent message, so
+    /// it takes several before we act, whereas contradictory votes can't be honest. Failed audits
+    /// are only reported once they reached their own limit.
+    pub fn penalty(self) -> u64 {
+        match self {
+            Misbehaviour::DuplicateProof => 1,
+            Misbehaviour::InvalidSignature => 5,
+            Misbehaviour::ContradictorySectionInfo | Misbehaviour::FailedAudits => {
+                DEFAULT_MISBEHAVIOUR_THRESHOLD
+            }
+        }
+    }
+}
+
+/// Tracks the misbehaviour of peers voting in our section.
+pub struct PeerScores {
+    /// The accumulated penalties of each peer that misbehaved.
+    scores: BTreeMap<PublicId, u64>,
+    /// The score at which a peer is considered malicious.
+    threshold: u64,
+    /// Peers which crossed the threshold and haven't been taken by `take_malicious` yet.
+    malicious: BTreeSet<PublicId>,
+}
+
+impl PeerScores {
+    /// Sets the score at which a peer is considered malicious.
+    pub fn set_threshold(&mut self, threshold: u64) {
+        self.threshold = threshold;
+    }
+
+    /// Records an occurrence of `misbehaviour` by `pub_id`. Returns `true` if this made the peer
+    /// cross the threshold.
+    pub fn record(&mut self, pub_id: PublicId, misbehaviour: Misbehaviour) -> bool {
+        let score = self.scores.entry(pub_id).or_insert(0);
+        let was_malicious = *score >= self.threshold;
+        *score += misbehaviour.penalty();
+        if !was_malicious && *score >= self.threshold {
+            let _ = self.malicious.insert(pub_id);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the score of `pub_id`, which is zero if it never misbehaved.
+    pub fn score(&self, pub_id: &PublicId) -> u64 {
+        self.scores.get(pub_id).cloned().unwrap_or(0)
+    }
+
+    /// Returns the scores of all peers that misbehaved.
+    pub fn scores(&self) -> &BTreeMap<PublicId, u64> {
+        &self.scores
+    }
+
+    /// Returns the peers which crossed the threshold since the last call.
+    pub fn take_malicious(&mut self) -> BTreeSet<PublicId> {
+        mem::replace(&mut self.malicious, BTreeSet::new())
+    }
+}
+
+impl Default for PeerScores {
+    fn default() -> Self {
+        Self {
+            scores: BTreeMap::new(),
+            threshold: DEFAULT_MISBEHAVIOUR_THRESHOLD,
+            malicious: BTreeSet::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::FullId;
+    use std::iter;
+
+    #[test]
+    fn crossing_threshold_is_reported_once() {
+        let pub_id = *FullId::new().public_id();
+        let mut scores = PeerScores::default();
+        scores.set_threshold(3);
+
+        assert!(!scores.record(pub_id, Misbehaviour::DuplicateProof));
+        assert!(!scores.record(pub_id, Misbehaviour::DuplicateProof));
+        assert_eq!(scores.score(&pub_id), 2);
+        assert!(scores.take_malicious().is_empty());
+
+        assert!(scores.record(pub_id, Misbehaviour::DuplicateProof));
+        assert!(!scores.record(pub_id, Misbehaviour::InvalidSignature));
+        assert_eq!(scores.score(&pub_id), 8);
+        assert_eq!(scores.take_malicious(), iter::once(pub_id).collect());
+        assert!(scores.take_malicious().is_empty());
+    }
+}
@@ -10,11 +10,16 @@ use crate::error::Result;
 use crate::id::PublicId;
 use itertools::Itertools;
 use maidsafe_utilities::serialisation;
+use rayon::prelude::*;
 use safe_crypto::{SecretSignKey, Signature};
 use serde::Serialize;
 use std::collections::BTreeMap;
 use std::fmt::{self, Debug, Formatter};
 
+/// The smallest proof set `ProofSet::verify_all_batched` verifies in parallel. Below this, the
+/// overhead of distributing the work outweighs the gain.
+const MIN_PARALLEL_VERIFICATION_LEN: usize = 8;
+
 /// Proof as provided by a close group member. This struct should be ordered by age then `PublicKey`
 #[derive(Serialize, Deserialize, PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Hash)]
 pub struct Proof {
@@ -97,6 +102,21 @@ impl ProofSet {
         }
     }
 
+    /// Validates `payload` against all signatures in a single batched pass: the payload is
+    /// serialised once, and the signatures are verified in parallel if there are enough of them.
+    pub fn verify_all_batched<S: Serialize>(&self, payload: &S) -> bool {
+        let data = match serialisation::serialise(payload) {
+            Ok(data) => data,
+            Err(_) => return false,
+        };
+        if self.sigs.len() < MIN_PARALLEL_VERIFICATION_LEN {
+            return self.validate_signatures_for_bytes(&data);
+        }
+        self.sigs
+            .par_iter()
+            .all(|(id, sig)| id.signing_public_key().verify_detached(sig, &data))
+    }
+
     /// Validates `data` against all signatures.
     fn validate_signatures_for_bytes(&self, data: &[u8]) -> bool {
         let validate =
@@ -138,7 +158,7 @@ impl Debug for ProofSet {
 #[cfg(test)]
 mod tests {
     use super::super::NetworkEvent;
-    use super::Proof;
+    use super::{Proof, ProofSet, MIN_PARALLEL_VERIFICATION_LEN};
     use crate::id::FullId;
     use safe_crypto;
     use unwrap::unwrap;
@@ -153,6 +173,24 @@ mod tests {
         assert!(proof.validate_signature(&payload));
     }
 
+    #[test]
+    fn batched_verification() {
+        unwrap!(safe_crypto::init());
+        let payload = NetworkEvent::OurMerge;
+        let mut proofs = ProofSet::new();
+        for _ in 0..2 * MIN_PARALLEL_VERIFICATION_LEN {
+            let full_id = FullId::new();
+            let proof = unwrap!(Proof::new(
+                *full_id.public_id(),
+                full_id.signing_private_key(),
+                &payload
+            ));
+            assert!(proofs.add_proof(proof));
+            assert!(proofs.verify_all_batched(&payload));
+        }
+        assert!(proofs.validate_signatures(&payload));
+    }
+
     #[test]
     #[ignore] // Enable once sig checks are enabled
     fn bad_construction() {
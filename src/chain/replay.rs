@@ -0,0 +1,57 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Rebuilding the shared state of a section from an `EventJournal`.
+
+use super::{journal::JournalEntry, shared_state::SharedState, Chain, EventJournal, NetworkEvent};
+use crate::error::RoutingError;
+use std::collections::BTreeSet;
+
+/// Rebuilds the shared state of the chain which kept `journal`, by applying its events in order to
+/// the state the journal started from.
+///
+/// Besides the events themselves, this applies the changes our elders make in response to them,
+/// i.e. adding and removing members and finalising splits, so the result matches the state of the
+/// original chain. Fails with `RoutingError::Chain` if an event doesn't apply, which means the
+/// journal was tampered with or the original chain diverged from the rules.
+pub fn replay(journal: &EventJournal) -> Result<SharedState, RoutingError> {
+    let genesis = journal.genesis();
+    let mut chain = Chain::new(journal.network_params(), *journal.our_id(), genesis.clone());
+    chain.handle_genesis_event(&BTreeSet::new(), &genesis.first_state_serialized)?;
+    apply_entries(&mut chain, journal.entries())?;
+    Ok(chain.into_shared_state())
+}
+
+/// Applies the journal `entries` to `chain`, which must be in the state they started from.
+pub(super) fn apply_entries(
+    chain: &mut Chain,
+    entries: &[JournalEntry],
+) -> Result<(), RoutingError> {
+    for (index, entry) in entries.iter().enumerate() {
+        let old_pfx = *chain.our_prefix();
+        if !chain.reapply(entry)? {
+            debug!("Journal event {} doesn't apply: {:?}", index, entry.event());
+            return Err(RoutingError::Chain);
+        }
+
+        match *entry.event() {
+            NetworkEvent::AddElder(pub_id, _) => {
+                let _ = chain.add_member(pub_id)?;
+            }
+            NetworkEvent::RemoveElder(pub_id) => {
+                let _ = chain.remove_member(pub_id)?;
+            }
+            NetworkEvent::SectionInfo(_) if chain.our_prefix().is_extension_of(&old_pfx) => {
+                let _ = chain.finalise_prefix_change()?;
+            }
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
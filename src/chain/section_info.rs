@@ -9,11 +9,12 @@
 use super::{NetworkEvent, ProofSet};
 use crate::error::RoutingError;
 use crate::id::PublicId;
+use crate::network_params::NetworkParams;
 use crate::parsec;
 use crate::routing_table::Prefix;
 use crate::sha3::Digest256;
 use crate::XorName;
-use crate::{QUORUM_DENOMINATOR, QUORUM_NUMERATOR};
+use itertools::Itertools;
 use maidsafe_utilities::serialisation;
 use safe_crypto;
 use serde::de::Error as SerdeDeError;
@@ -27,7 +28,7 @@ use std::fmt::{self, Debug, Display, Formatter};
 /// node being added or removed, or the section splitting or merging.
 #[derive(Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct SectionInfo {
-    /// The complete list of the section's members' IDs.
+    /// The complete list of the section's members' IDs, which also carry their ages.
     members: BTreeSet<PublicId>,
     /// The section version. This increases monotonically whenever the section changes.
     /// Thus `SectionInfo`s with compatible prefixes always have different versions.
@@ -91,6 +92,31 @@ impl SectionInfo {
         self.members.iter().map(PublicId::name).cloned().collect()
     }
 
+    /// Returns the section's elders: its `min_section_size` oldest members, with ties between
+    /// members of the same age going to the lower name. Only the elders' votes count towards
+    /// consensus in the section.
+    pub fn elders(&self, params: &NetworkParams) -> BTreeSet<PublicId> {
+        if self.members.len() <= params.min_section_size {
+            return self.members.clone();
+        }
+        self.members
+            .iter()
+            .sorted_by(|lhs, rhs| {
+                rhs.age()
+                    .cmp(&lhs.age())
+                    .then_with(|| lhs.name().cmp(rhs.name()))
+            })
+            .into_iter()
+            .take(params.min_section_size)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns whether `pub_id` is one of the section's elders.
+    pub fn is_elder(&self, params: &NetworkParams, pub_id: &PublicId) -> bool {
+        self.elders(params).contains(pub_id)
+    }
+
     pub fn version(&self) -> &u64 {
         &self.version
     }
@@ -107,15 +133,24 @@ impl SectionInfo {
         &self.hash
     }
 
-    /// Returns `true` if the proofs are from a quorum of this section.
-    pub fn is_quorum(&self, proofs: &ProofSet) -> bool {
-        proofs.ids().filter(|id| self.members.contains(id)).count() * QUORUM_DENOMINATOR
-            > self.members.len() * QUORUM_NUMERATOR
+    /// Returns `true` if the proofs are from a quorum of this section's elders.
+    pub fn is_quorum(&self, params: &NetworkParams, proofs: &ProofSet) -> bool {
+        let elders = self.elders(params);
+        params.is_quorum(
+            proofs.ids().filter(|id| elders.contains(id)).count(),
+            elders.len(),
+        )
+    }
+
+    /// Returns the number of elder signatures needed to reach quorum in this section.
+    pub fn quorum_size(&self, params: &NetworkParams) -> usize {
+        params.quorum_size(cmp::min(self.members.len(), params.min_section_size))
     }
 
-    /// Returns `true` if the proofs are from all members of this section.
-    pub fn is_total_consensus(&self, proofs: &ProofSet) -> bool {
-        proofs.ids().filter(|id| self.members.contains(id)).count() == self.members.len()
+    /// Returns `true` if the proofs are from all elders of this section.
+    pub fn is_total_consensus(&self, params: &NetworkParams, proofs: &ProofSet) -> bool {
+        let elders = self.elders(params);
+        proofs.ids().filter(|id| elders.contains(id)).count() == elders.len()
     }
 
     /// Returns `true` if `self` is a successor of `other_info`, according to its hash.
@@ -125,16 +160,26 @@ impl SectionInfo {
 
     /// Returns `true` if the `proofs` are a quorum of `self` and valid signatures of
     /// `other_event`.
-    pub fn proves(&self, other_info: &SectionInfo, proofs: &ProofSet) -> bool {
+    pub fn proves(
+        &self,
+        params: &NetworkParams,
+        other_info: &SectionInfo,
+        proofs: &ProofSet,
+    ) -> bool {
         let other_event: parsec::Observation<NetworkEvent, PublicId> =
             parsec::Observation::OpaquePayload(NetworkEvent::SectionInfo(other_info.clone()));
-        self.is_quorum(proofs) && proofs.validate_signatures(&other_event)
+        self.is_quorum(params, proofs) && proofs.validate_signatures(&other_event)
     }
 
     /// Returns `true` if the `proofs` are a quorum of `self` and valid signatures of
     /// `other_event`, and if `other_info` is a valid successor of `self`.
-    pub fn proves_successor(&self, other_info: &SectionInfo, proofs: &ProofSet) -> bool {
-        other_info.is_successor_of(self) && self.proves(other_info, proofs)
+    pub fn proves_successor(
+        &self,
+        params: &NetworkParams,
+        other_info: &SectionInfo,
+        proofs: &ProofSet,
+    ) -> bool {
+        other_info.is_successor_of(self) && self.proves(params, other_info, proofs)
     }
 
     /// To NetworkEvent::SectionInfo event
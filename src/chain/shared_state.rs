@@ -6,26 +6,52 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{bls_emu::BlsPublicKeyForSectionKeyInfo, NetworkEvent, ProofSet, SectionInfo};
+use super::{bls_emu::BlsPublicKeyForSectionKeyInfo, NetworkEvent, SectionInfo};
+#[cfg(feature = "node")]
+use super::{JoinFilter, ProofSet};
+#[cfg(feature = "node")]
 use crate::{
-    error::RoutingError, id::PublicId, sha3::Digest256, BlsPublicKey, BlsSignature, Prefix, XorName,
+    client_manager::Accounts, error::RoutingError, routing_table::Authority, sha3::Digest256,
 };
+use crate::{
+    id::PublicId, network_params::NetworkParams, BlsPublicKey, BlsSignature, Prefix, XorName,
+};
+#[cfg(feature = "node")]
 use itertools::Itertools;
+#[cfg(feature = "node")]
 use log::LogLevel;
 use maidsafe_utilities::serialisation;
+#[cfg(feature = "node")]
 use std::{
+    cmp,
     collections::{BTreeMap, BTreeSet, VecDeque},
+    mem,
+};
+use std::{
     fmt::{self, Debug, Formatter},
-    iter, mem,
+    iter,
 };
+#[cfg(feature = "node")]
 use unwrap::unwrap;
 
 // Number of recent keys we keep: i.e how many other section churns we can handle before a
 // message send with a previous version of a section is no longer trusted.
 // With low churn rate, a ad hoc 10 should be big enough to avoid losing messages.
+#[cfg(feature = "node")]
 const MAX_THEIR_RECENT_KEYS: usize = 10;
 
+/// The most keys we keep in our section's proof chain, even if other sections still need older
+/// ones to verify our messages.
+#[cfg(feature = "node")]
+pub const MAX_OUR_HISTORY_LEN: u64 = 128;
+/// When the cap forces us to drop keys, the chain is cut at a key whose version is a multiple of
+/// this. Sections which fell that far behind can then resync from a predictable checkpoint, and
+/// the cut happens only once every so many versions rather than on every push.
+#[cfg(feature = "node")]
+const OUR_HISTORY_CHECKPOINT_INTERVAL: u64 = 32;
+
 /// Section state that is shared among all elders of a section via Parsec consensus.
+#[cfg(feature = "node")]
 #[derive(Debug, PartialEq, Eq)]
 pub struct SharedState {
     /// The new self section info, that doesn't necessarily have a full set of signatures yet.
@@ -54,11 +80,25 @@ pub struct SharedState {
     pub their_knowledge: BTreeMap<Prefix<XorName>, u64>,
     /// Recent keys removed from their_keys
     pub their_recent_keys: VecDeque<(Prefix<XorName>, SectionKeyInfo)>,
+    /// Members of our section too young to be adults. They aren't part of our section info, so
+    /// they can join and leave without the section signing a new one.
+    pub our_infants: BTreeSet<PublicId>,
+    /// The resource proof difficulty our section agreed to challenge candidates with.
+    pub join_difficulty: u8,
+    /// The joining nodes our section agreed to allow or refuse.
+    pub join_filter: JoinFilter,
+    /// The latest protocol upgrade our section agreed to.
+    pub upgrade: Option<PendingUpgrade>,
+    /// The balances of the client accounts our section manages.
+    pub accounts: Accounts,
+    /// The sequence number of the latest user message our section agreed to send on each route.
+    pub msg_seqs: BTreeMap<(Authority<XorName>, Authority<XorName>), u64>,
 }
 
+#[cfg(feature = "node")]
 impl SharedState {
-    pub fn new(section_info: SectionInfo) -> Self {
-        let pk_info = SectionKeyInfo::from_section_info(&section_info);
+    pub fn new(section_info: SectionInfo, network_params: NetworkParams) -> Self {
+        let pk_info = SectionKeyInfo::from_section_info(&section_info, network_params);
         let our_history = SectionProofChain::from_genesis(pk_info);
         let their_key_info = our_history.last_public_key_info();
         let their_keys = iter::once((*their_key_info.prefix(), their_key_info.clone())).collect();
@@ -74,6 +114,12 @@ impl SharedState {
             their_keys,
             their_knowledge: Default::default(),
             their_recent_keys: Default::default(),
+            our_infants: Default::default(),
+            join_difficulty: 0,
+            join_filter: Default::default(),
+            upgrade: None,
+            accounts: Default::default(),
+            msg_seqs: Default::default(),
         }
     }
 
@@ -92,6 +138,12 @@ impl SharedState {
             their_keys,
             their_knowledge,
             their_recent_keys,
+            our_infants,
+            join_difficulty,
+            join_filter,
+            upgrade,
+            accounts,
+            msg_seqs,
         ) = serialisation::deserialise(related_info)?;
         if self.our_infos.len() != 1 {
             // Check nodes with a history before genesis match the genesis block:
@@ -143,6 +195,14 @@ impl SharedState {
                     their_recent_keys
                 );
             }
+            if self.our_infants != our_infants {
+                log_or_panic!(
+                    LogLevel::Error,
+                    "update_with_genesis_related_info different our_infants:\n{:?},\n{:?}",
+                    self.our_infants,
+                    our_infants
+                );
+            }
         }
         self.our_infos = our_infos;
         self.our_history = our_history;
@@ -150,6 +210,12 @@ impl SharedState {
         self.their_keys = their_keys;
         self.their_knowledge = their_knowledge;
         self.their_recent_keys = their_recent_keys;
+        self.our_infants = our_infants;
+        self.join_difficulty = join_difficulty;
+        self.join_filter = join_filter;
+        self.upgrade = upgrade;
+        self.accounts = accounts;
+        self.msg_seqs = msg_seqs;
 
         Ok(())
     }
@@ -162,6 +228,12 @@ impl SharedState {
             &self.their_keys,
             &self.their_knowledge,
             &self.their_recent_keys,
+            &self.our_infants,
+            self.join_difficulty,
+            &self.join_filter,
+            &self.upgrade,
+            &self.accounts,
+            &self.msg_seqs,
         ))?)
     }
 
@@ -241,16 +313,70 @@ impl SharedState {
         neighbour_infos.into_iter().any(needs_merge)
     }
 
-    pub fn push_our_new_info(&mut self, sec_info: SectionInfo, proofs: ProofSet) {
+    pub fn push_our_new_info(
+        &mut self,
+        sec_info: SectionInfo,
+        proofs: ProofSet,
+        network_params: NetworkParams,
+    ) {
         self.our_history
             .push(SectionProofBlock::from_sec_info_with_proofs(
                 &sec_info,
                 proofs.clone(),
+                network_params,
             ));
+        // After a split, the infants which went to our sibling aren't ours any more.
+        let our_prefix = *sec_info.prefix();
+        self.our_infants
+            .retain(|pub_id| our_prefix.matches(pub_id.name()));
         self.our_infos.push((sec_info, proofs));
 
         let key_info = self.our_history.last_public_key_info().clone();
         self.update_their_keys(&key_info);
+        let _ = self.compact_our_history();
+    }
+
+    /// Trims `our_history` to the oldest key that a section in `their_knowledge` still needs to
+    /// verify our messages, and to at most `MAX_OUR_HISTORY_LEN` keys. Returns the number of keys
+    /// dropped.
+    ///
+    /// This only depends on the shared state, so all elders compact their history identically.
+    pub fn compact_our_history(&mut self) -> usize {
+        let first_version = self.our_history.first_version();
+        let last_version = *self.our_history.last_public_key_info().version();
+        let mut new_first_version = self
+            .their_knowledge
+            .values()
+            .min()
+            .map_or(first_version, |&version| {
+                cmp::min(cmp::max(version, first_version), last_version)
+            });
+        if last_version - new_first_version >= MAX_OUR_HISTORY_LEN {
+            let min_first_version = last_version + 1 - MAX_OUR_HISTORY_LEN;
+            new_first_version = (min_first_version + OUR_HISTORY_CHECKPOINT_INTERVAL - 1)
+                / OUR_HISTORY_CHECKPOINT_INTERVAL
+                * OUR_HISTORY_CHECKPOINT_INTERVAL;
+        }
+        self.our_history.truncate_before(new_first_version)
+    }
+
+    /// Returns whether `key_info` is newer than the key we know for a compatible section, i.e.
+    /// whether our section should vote to update `their_keys` with it.
+    pub fn is_new_their_key(&self, key_info: &SectionKeyInfo) -> bool {
+        self.their_keys.values().any(|info| {
+            info.version() < key_info.version() && info.prefix().is_compatible(key_info.prefix())
+        })
+    }
+
+    /// Returns whether the section with `prefix` knowing our key of `version` is news to us, i.e.
+    /// whether our section should vote to update `their_knowledge` with it. Claims of knowing a
+    /// key newer than our current one are never news.
+    pub fn is_new_their_knowledge(&self, prefix: &Prefix<XorName>, version: u64) -> bool {
+        version <= self.our_version()
+            && !self
+                .their_knowledge
+                .iter()
+                .any(|(pfx, &known)| known >= version && pfx.is_compatible(prefix))
     }
 
     /// Updates the entry in `their_keys` for `prefix` to the latest known key; if a split
@@ -322,6 +448,15 @@ impl SharedState {
             }
         }
         let _ = self.their_knowledge.insert(prefix, version);
+        let _ = self.compact_our_history();
+    }
+
+    /// Replaces the entries in `their_keys` and `their_knowledge` for sections which have split
+    /// since, i.e. whose prefix has a strict extension in `known_prefixes`, with entries for the
+    /// sections that replaced them, carrying the old key or version forward.
+    pub fn fold_their_state_forward(&mut self, known_prefixes: &BTreeSet<Prefix<XorName>>) {
+        fold_forward(&mut self.their_keys, known_prefixes);
+        fold_forward(&mut self.their_knowledge, known_prefixes);
     }
 
     /// Returns the reference to their_keys and any recent keys we still hold.
@@ -338,7 +473,45 @@ impl SharedState {
     }
 }
 
+/// Replaces each entry whose prefix has a strict extension in `known_prefixes` with entries
+/// holding its value for those extensions and for the prefixes covering the rest of its range, as
+/// `update_their_knowledge` does when it learns about a split.
+#[cfg(feature = "node")]
+fn fold_forward<T: Clone>(
+    entries: &mut BTreeMap<Prefix<XorName>, T>,
+    known_prefixes: &BTreeSet<Prefix<XorName>>,
+) {
+    let is_split = |pfx: &Prefix<XorName>| {
+        known_prefixes
+            .iter()
+            .any(|known| known.is_extension_of(pfx))
+    };
+    let split: Vec<_> = entries
+        .keys()
+        .filter(|pfx| is_split(pfx))
+        .cloned()
+        .collect();
+
+    for old_pfx in split {
+        let value = match entries.remove(&old_pfx) {
+            Some(value) => value,
+            None => continue,
+        };
+        trace!("    folding {:?} forward", old_pfx);
+        let mut pending = vec![old_pfx.pushed(false), old_pfx.pushed(true)];
+        while let Some(pfx) = pending.pop() {
+            if is_split(&pfx) {
+                pending.push(pfx.pushed(false));
+                pending.push(pfx.pushed(true));
+            } else if !entries.keys().any(|other| other.is_compatible(&pfx)) {
+                let _ = entries.insert(pfx, value.clone());
+            }
+        }
+    }
+}
+
 /// The prefix-affecting change (split or merge) to our own section that is currently in progress.
+#[cfg(feature = "node")]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum PrefixChange {
     None,
@@ -347,12 +520,14 @@ pub enum PrefixChange {
 }
 
 /// Vec-like container that is guaranteed to contain at least one element.
-#[derive(PartialEq, Eq, Serialize, Deserialize)]
+#[cfg(feature = "node")]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct NonEmptyList<T> {
     head: Vec<T>,
     tail: T,
 }
 
+#[cfg(feature = "node")]
 impl<T> NonEmptyList<T> {
     pub fn new(first: T) -> Self {
         Self {
@@ -378,6 +553,7 @@ impl<T> NonEmptyList<T> {
     }
 }
 
+#[cfg(feature = "node")]
 impl<T> Debug for NonEmptyList<T>
 where
     T: Debug,
@@ -404,8 +580,13 @@ impl Debug for SectionProofBlock {
 }
 
 impl SectionProofBlock {
-    pub fn from_sec_info_with_proofs(sec_info: &SectionInfo, proofs: ProofSet) -> Self {
-        let key_info = SectionKeyInfo::from_section_info(sec_info);
+    #[cfg(feature = "node")]
+    pub fn from_sec_info_with_proofs(
+        sec_info: &SectionInfo,
+        proofs: ProofSet,
+        network_params: NetworkParams,
+    ) -> Self {
+        let key_info = SectionKeyInfo::from_section_info(sec_info, network_params);
         let sig = BlsSignature::from_proof_set(proofs);
         SectionProofBlock { key_info, sig }
     }
@@ -434,6 +615,7 @@ pub struct SectionProofChain {
 }
 
 impl SectionProofChain {
+    #[cfg(feature = "node")]
     pub fn from_genesis(key_info: SectionKeyInfo) -> Self {
         Self {
             genesis_key_info: key_info,
@@ -445,6 +627,12 @@ impl SectionProofChain {
         self.blocks.len()
     }
 
+    /// Returns the version of the first key in the chain.
+    #[cfg(feature = "node")]
+    pub fn first_version(&self) -> u64 {
+        *self.genesis_key_info.version()
+    }
+
     pub fn push(&mut self, block: SectionProofBlock) {
         self.blocks.push(block);
     }
@@ -475,6 +663,23 @@ impl SectionProofChain {
         iter::once(&self.genesis_key_info).chain(self.blocks.iter().map(|block| block.key_info()))
     }
 
+    /// Drops the keys older than `version`, so that the chain starts with the key of that version,
+    /// or with the last key if they are all older. Returns the number of keys dropped.
+    #[cfg(feature = "node")]
+    pub fn truncate_before(&mut self, version: u64) -> usize {
+        let count = cmp::min(
+            self.all_key_infos()
+                .take_while(|key_info| *key_info.version() < version)
+                .count(),
+            self.blocks.len(),
+        );
+        if count > 0 {
+            *self = self.slice_from(count);
+        }
+        count
+    }
+
+    #[cfg(feature = "node")]
     pub fn slice_from(&self, first_index: usize) -> SectionProofChain {
         if first_index == 0 || self.blocks.is_empty() {
             return self.clone();
@@ -497,6 +702,19 @@ impl SectionProofChain {
     }
 }
 
+/// A protocol upgrade the network agreed to, which each section activates once its own section
+/// info reaches `activation_version`.
+#[cfg(feature = "node")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PendingUpgrade {
+    /// The protocol version to upgrade to.
+    pub version: u16,
+    /// The version of the section info from which on the upgrade is active.
+    pub activation_version: u64,
+}
+
+/// The BLS public key of a section, together with the prefix and version of the section info it
+/// belongs to.
 #[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Hash, Serialize, Deserialize)]
 pub struct SectionKeyInfo {
     // Hold all the information that is signed. When switching to real BLS, SectionKeyInfo
@@ -505,24 +723,32 @@ pub struct SectionKeyInfo {
 }
 
 impl SectionKeyInfo {
-    pub fn from_section_info(sec_info: &SectionInfo) -> Self {
+    /// Returns the key of the section described by `sec_info`.
+    pub fn from_section_info(sec_info: &SectionInfo, network_params: NetworkParams) -> Self {
         Self {
-            key_info_holder: BlsPublicKeyForSectionKeyInfo::from_section_info(sec_info),
+            key_info_holder: BlsPublicKeyForSectionKeyInfo::from_section_info(
+                sec_info,
+                network_params,
+            ),
         }
     }
 
+    /// Returns the public key.
     pub fn key(&self) -> &BlsPublicKey {
         self.key_info_holder.key()
     }
 
+    /// Returns the prefix of the section.
     pub fn prefix(&self) -> &Prefix<XorName> {
         self.key_info_holder.internal_section_info().prefix()
     }
 
+    /// Returns the version of the section info the key belongs to.
     pub fn version(&self) -> &u64 {
         self.key_info_holder.internal_section_info().version()
     }
 
+    /// Returns the bytes the previous key of the section signs to prove this one.
     pub fn serialise_for_signature(&self) -> Option<Vec<u8>> {
         let payload_for_signature: parsec::Observation<NetworkEvent, PublicId> =
             parsec::Observation::OpaquePayload(NetworkEvent::SectionInfo(
@@ -544,7 +770,7 @@ impl Debug for SectionKeyInfo {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "node"))]
 mod test {
     use super::*;
     use crate::{chain::SectionInfo, FullId, Prefix, XorName};
@@ -585,7 +811,8 @@ mod test {
             .map(|(version, pfx_str)| {
                 let pfx = unwrap!(Prefix::<XorName>::from_str(pfx_str));
                 let sec_info = gen_section_info(pfx, version as u64);
-                let key_info = SectionKeyInfo::from_section_info(&sec_info);
+                let key_info =
+                    SectionKeyInfo::from_section_info(&sec_info, NetworkParams::default());
                 (key_info, sec_info)
             })
             .collect::<Vec<_>>();
@@ -599,7 +826,7 @@ mod test {
 
         let mut state = {
             let start_section = unwrap!(keys_to_update.first()).1.clone();
-            SharedState::new(start_section)
+            SharedState::new(start_section, NetworkParams::default())
         };
 
         //
@@ -627,6 +854,32 @@ mod test {
         assert_eq!(actual_keys, expected_keys);
     }
 
+    #[test]
+    fn section_list_merge_rules() {
+        let our_pfx = unwrap!(Prefix::from_str("0"));
+        let their_pfx = unwrap!(Prefix::from_str("1"));
+        let params = NetworkParams::default();
+        let their_key = |pfx, version| {
+            SectionKeyInfo::from_section_info(&gen_section_info(pfx, version), params)
+        };
+        let mut state = SharedState::new(gen_section_info(our_pfx, 3), params);
+        state.update_their_keys(&their_key(their_pfx, 1));
+
+        // Only keys newer than the one we know for a compatible section are news.
+        assert!(state.is_new_their_key(&their_key(their_pfx, 2)));
+        assert!(state.is_new_their_key(&their_key(unwrap!(Prefix::from_str("10")), 2)));
+        assert!(!state.is_new_their_key(&their_key(their_pfx, 1)));
+
+        // Knowledge of our keys is news unless we know of as recent knowledge, or it claims a key
+        // newer than ours.
+        assert!(state.is_new_their_knowledge(&their_pfx, 2));
+        assert!(!state.is_new_their_knowledge(&their_pfx, 4));
+        state.update_their_knowledge(their_pfx, 2);
+        assert!(!state.is_new_their_knowledge(&their_pfx, 2));
+        assert!(!state.is_new_their_knowledge(&their_pfx, 1));
+        assert!(state.is_new_their_knowledge(&their_pfx, 3));
+    }
+
     #[test]
     fn single_prefix_multiple_updates() {
         update_keys_and_check(
@@ -670,6 +923,67 @@ mod test {
         );
     }
 
+    #[test]
+    fn fold_split_sections_forward() {
+        let pfx = |pfx_str| unwrap!(Prefix::<XorName>::from_str(pfx_str));
+        let params = NetworkParams::default();
+        let mut state = SharedState::new(gen_section_info(pfx("0"), 0), params);
+        let key_info = SectionKeyInfo::from_section_info(&gen_section_info(pfx("1"), 3), params);
+        state.update_their_keys(&key_info);
+        state.update_their_knowledge(pfx("1"), 5);
+
+        let known_prefixes = vec![pfx("0"), pfx("10")].into_iter().collect();
+        state.fold_their_state_forward(&known_prefixes);
+        assert_eq!(
+            state.their_knowledge.keys().collect::<Vec<_>>(),
+            vec![&pfx("10"), &pfx("11")]
+        );
+
+        let known_prefixes = vec![pfx("0"), pfx("10"), pfx("110"), pfx("111")]
+            .into_iter()
+            .collect();
+        state.fold_their_state_forward(&known_prefixes);
+        let expected: BTreeMap<_, _> = vec![(pfx("10"), 5), (pfx("110"), 5), (pfx("111"), 5)]
+            .into_iter()
+            .collect();
+        assert_eq!(state.their_knowledge, expected);
+        assert_eq!(
+            state.their_keys.keys().collect::<Vec<_>>(),
+            vec![&pfx("0"), &pfx("10"), &pfx("110"), &pfx("111")]
+        );
+        assert!(state
+            .their_keys
+            .iter()
+            .filter(|(prefix, _)| prefix.bit_count() > 1)
+            .all(|(_, info)| *info == key_info));
+    }
+
+    #[test]
+    fn our_history_compaction() {
+        let pfx = |s: &str| unwrap!(Prefix::<XorName>::from_str(s));
+        let params = NetworkParams::default();
+        let mut state = SharedState::new(gen_section_info(pfx(""), 0), params);
+        for version in 1..=200 {
+            state.push_our_new_info(
+                gen_section_info(pfx(""), version),
+                ProofSet::default(),
+                params,
+            );
+        }
+
+        // Nobody acknowledged anything: only the cap applies, cutting at a checkpoint.
+        assert_eq!(state.our_history.first_version(), 96);
+        assert_eq!(state.our_history.blocks_len(), 104);
+        assert!(state.our_history.blocks_len() as u64 <= MAX_OUR_HISTORY_LEN);
+
+        // Keys older than any section's knowledge are dropped.
+        state.update_their_knowledge(pfx("0"), 150);
+        state.update_their_knowledge(pfx("1"), 180);
+        assert_eq!(state.our_history.first_version(), 150);
+        assert_eq!(*state.our_history.last_public_key_info().version(), 200);
+        assert_eq!(state.compact_our_history(), 0);
+    }
+
     #[test]
     fn multiple_split() {
         update_keys_and_check(
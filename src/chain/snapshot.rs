@@ -0,0 +1,285 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    shared_state::{NonEmptyList, PendingUpgrade, PrefixChange, SharedState},
+    JoinFilter, ProofSet, SectionInfo, SectionKeyInfo, SectionProofChain,
+};
+use crate::{
+    client_manager::Accounts, id::PublicId, network_params::NetworkParams, Prefix, XorName,
+};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// The `Storage` namespace chain snapshots are kept in.
+pub const SNAPSHOT_NAMESPACE: &str = "chain";
+/// The key of the latest snapshot.
+pub const SNAPSHOT_KEY: &[u8] = b"snapshot";
+
+/// The on-disk format of a `Chain` snapshot.
+///
+/// Each format version is a variant of this enum. A new version is added as a new variant rather
+/// than by changing an existing one, so snapshots written by an older version of this crate can
+/// still be read, and are migrated to the latest format by `into_latest`.
+#[derive(Serialize, Deserialize)]
+pub enum VersionedSnapshot {
+    /// The first version of the format.
+    V1(ChainSnapshotV1),
+    /// Stores all the network parameters instead of just the minimum section size.
+    V2(ChainSnapshotV2),
+    /// Stores the infants of our section.
+    V3(ChainSnapshotV3),
+    /// Stores the joining nodes our section allows or refuses.
+    V4(ChainSnapshotV4),
+    /// Stores the protocol upgrade our section agreed to.
+    V5(ChainSnapshotV5),
+    /// Stores the balances of the client accounts our section manages.
+    V6(ChainSnapshot),
+}
+
+impl VersionedSnapshot {
+    /// Migrates the snapshot to the latest format.
+    pub fn into_latest(self) -> ChainSnapshot {
+        match self {
+            VersionedSnapshot::V1(snapshot) => ChainSnapshotV5::from(ChainSnapshotV4::from(
+                ChainSnapshotV3::from(ChainSnapshotV2::from(snapshot)),
+            ))
+            .into(),
+            VersionedSnapshot::V2(snapshot) => {
+                ChainSnapshotV5::from(ChainSnapshotV4::from(ChainSnapshotV3::from(snapshot))).into()
+            }
+            VersionedSnapshot::V3(snapshot) => {
+                ChainSnapshotV5::from(ChainSnapshotV4::from(snapshot)).into()
+            }
+            VersionedSnapshot::V4(snapshot) => ChainSnapshotV5::from(snapshot).into(),
+            VersionedSnapshot::V5(snapshot) => snapshot.into(),
+            VersionedSnapshot::V6(snapshot) => snapshot,
+        }
+    }
+}
+
+/// The parts of a `Chain` which are persisted across restarts: our section's history and our
+/// knowledge of the other sections. Votes and other transient state aren't, as the node has to
+/// rejoin the section's consensus anyway.
+#[derive(Serialize, Deserialize)]
+pub struct ChainSnapshot {
+    pub network_params: NetworkParams,
+    pub our_id: PublicId,
+    pub our_infos: NonEmptyList<(SectionInfo, ProofSet)>,
+    pub our_history: SectionProofChain,
+    pub neighbour_infos: BTreeMap<Prefix<XorName>, SectionInfo>,
+    pub their_keys: BTreeMap<Prefix<XorName>, SectionKeyInfo>,
+    pub their_knowledge: BTreeMap<Prefix<XorName>, u64>,
+    pub their_recent_keys: VecDeque<(Prefix<XorName>, SectionKeyInfo)>,
+    pub our_infants: BTreeSet<PublicId>,
+    pub join_filter: JoinFilter,
+    pub upgrade: Option<PendingUpgrade>,
+    pub accounts: Accounts,
+}
+
+impl ChainSnapshot {
+    pub fn new(network_params: NetworkParams, our_id: PublicId, state: &SharedState) -> Self {
+        Self {
+            network_params,
+            our_id,
+            our_infos: state.our_infos.clone(),
+            our_history: state.our_history.clone(),
+            neighbour_infos: state.neighbour_infos.clone(),
+            their_keys: state.their_keys.clone(),
+            their_knowledge: state.their_knowledge.clone(),
+            their_recent_keys: state.their_recent_keys.clone(),
+            our_infants: state.our_infants.clone(),
+            join_filter: state.join_filter.clone(),
+            upgrade: state.upgrade,
+            accounts: state.accounts.clone(),
+        }
+    }
+
+    /// Returns the shared state this snapshot was taken of. A split or merge that was in progress
+    /// is abandoned, and will be restarted by the section if it is still needed. The join
+    /// difficulty starts at zero again until the section votes for another one, and the message
+    /// sequence numbers are learnt again from the section's genesis info when we rejoin it.
+    pub fn into_shared_state(self) -> SharedState {
+        SharedState {
+            new_info: self.our_infos.last().0.clone(),
+            our_infos: self.our_infos,
+            neighbour_infos: self.neighbour_infos,
+            change: PrefixChange::None,
+            split_cache: None,
+            merging: BTreeSet::new(),
+            our_history: self.our_history,
+            their_keys: self.their_keys,
+            their_knowledge: self.their_knowledge,
+            their_recent_keys: self.their_recent_keys,
+            our_infants: self.our_infants,
+            join_difficulty: 0,
+            join_filter: self.join_filter,
+            upgrade: self.upgrade,
+            accounts: self.accounts,
+            msg_seqs: BTreeMap::new(),
+        }
+    }
+}
+
+/// The fifth version of `ChainSnapshot`, from before client accounts were tracked.
+#[derive(Serialize, Deserialize)]
+pub struct ChainSnapshotV5 {
+    pub network_params: NetworkParams,
+    pub our_id: PublicId,
+    pub our_infos: NonEmptyList<(SectionInfo, ProofSet)>,
+    pub our_history: SectionProofChain,
+    pub neighbour_infos: BTreeMap<Prefix<XorName>, SectionInfo>,
+    pub their_keys: BTreeMap<Prefix<XorName>, SectionKeyInfo>,
+    pub their_knowledge: BTreeMap<Prefix<XorName>, u64>,
+    pub their_recent_keys: VecDeque<(Prefix<XorName>, SectionKeyInfo)>,
+    pub our_infants: BTreeSet<PublicId>,
+    pub join_filter: JoinFilter,
+    pub upgrade: Option<PendingUpgrade>,
+}
+
+impl From<ChainSnapshotV5> for ChainSnapshot {
+    fn from(snapshot: ChainSnapshotV5) -> Self {
+        Self {
+            network_params: snapshot.network_params,
+            our_id: snapshot.our_id,
+            our_infos: snapshot.our_infos,
+            our_history: snapshot.our_history,
+            neighbour_infos: snapshot.neighbour_infos,
+            their_keys: snapshot.their_keys,
+            their_knowledge: snapshot.their_knowledge,
+            their_recent_keys: snapshot.their_recent_keys,
+            our_infants: snapshot.our_infants,
+            join_filter: snapshot.join_filter,
+            upgrade: snapshot.upgrade,
+            accounts: Accounts::default(),
+        }
+    }
+}
+
+/// The fourth version of `ChainSnapshot`, from before protocol upgrades were coordinated.
+#[derive(Serialize, Deserialize)]
+pub struct ChainSnapshotV4 {
+    pub network_params: NetworkParams,
+    pub our_id: PublicId,
+    pub our_infos: NonEmptyList<(SectionInfo, ProofSet)>,
+    pub our_history: SectionProofChain,
+    pub neighbour_infos: BTreeMap<Prefix<XorName>, SectionInfo>,
+    pub their_keys: BTreeMap<Prefix<XorName>, SectionKeyInfo>,
+    pub their_knowledge: BTreeMap<Prefix<XorName>, u64>,
+    pub their_recent_keys: VecDeque<(Prefix<XorName>, SectionKeyInfo)>,
+    pub our_infants: BTreeSet<PublicId>,
+    pub join_filter: JoinFilter,
+}
+
+impl From<ChainSnapshotV4> for ChainSnapshotV5 {
+    fn from(snapshot: ChainSnapshotV4) -> Self {
+        Self {
+            network_params: snapshot.network_params,
+            our_id: snapshot.our_id,
+            our_infos: snapshot.our_infos,
+            our_history: snapshot.our_history,
+            neighbour_infos: snapshot.neighbour_infos,
+            their_keys: snapshot.their_keys,
+            their_knowledge: snapshot.their_knowledge,
+            their_recent_keys: snapshot.their_recent_keys,
+            our_infants: snapshot.our_infants,
+            join_filter: snapshot.join_filter,
+            upgrade: None,
+        }
+    }
+}
+
+/// The third version of `ChainSnapshot`, from before joining nodes could be allowed or refused.
+#[derive(Serialize, Deserialize)]
+pub struct ChainSnapshotV3 {
+    pub network_params: NetworkParams,
+    pub our_id: PublicId,
+    pub our_infos: NonEmptyList<(SectionInfo, ProofSet)>,
+    pub our_history: SectionProofChain,
+    pub neighbour_infos: BTreeMap<Prefix<XorName>, SectionInfo>,
+    pub their_keys: BTreeMap<Prefix<XorName>, SectionKeyInfo>,
+    pub their_knowledge: BTreeMap<Prefix<XorName>, u64>,
+    pub their_recent_keys: VecDeque<(Prefix<XorName>, SectionKeyInfo)>,
+    pub our_infants: BTreeSet<PublicId>,
+}
+
+impl From<ChainSnapshotV3> for ChainSnapshotV4 {
+    fn from(snapshot: ChainSnapshotV3) -> Self {
+        Self {
+            network_params: snapshot.network_params,
+            our_id: snapshot.our_id,
+            our_infos: snapshot.our_infos,
+            our_history: snapshot.our_history,
+            neighbour_infos: snapshot.neighbour_infos,
+            their_keys: snapshot.their_keys,
+            their_knowledge: snapshot.their_knowledge,
+            their_recent_keys: snapshot.their_recent_keys,
+            our_infants: snapshot.our_infants,
+            join_filter: JoinFilter::default(),
+        }
+    }
+}
+
+/// The second version of `ChainSnapshot`, from before infants were tracked.
+#[derive(Serialize, Deserialize)]
+pub struct ChainSnapshotV2 {
+    pub network_params: NetworkParams,
+    pub our_id: PublicId,
+    pub our_infos: NonEmptyList<(SectionInfo, ProofSet)>,
+    pub our_history: SectionProofChain,
+    pub neighbour_infos: BTreeMap<Prefix<XorName>, SectionInfo>,
+    pub their_keys: BTreeMap<Prefix<XorName>, SectionKeyInfo>,
+    pub their_knowledge: BTreeMap<Prefix<XorName>, u64>,
+    pub their_recent_keys: VecDeque<(Prefix<XorName>, SectionKeyInfo)>,
+}
+
+impl From<ChainSnapshotV2> for ChainSnapshotV3 {
+    fn from(snapshot: ChainSnapshotV2) -> Self {
+        Self {
+            network_params: snapshot.network_params,
+            our_id: snapshot.our_id,
+            our_infos: snapshot.our_infos,
+            our_history: snapshot.our_history,
+            neighbour_infos: snapshot.neighbour_infos,
+            their_keys: snapshot.their_keys,
+            their_knowledge: snapshot.their_knowledge,
+            their_recent_keys: snapshot.their_recent_keys,
+            our_infants: BTreeSet::new(),
+        }
+    }
+}
+
+/// The first version of `ChainSnapshot`, from before the quorum was configurable.
+#[derive(Serialize, Deserialize)]
+pub struct ChainSnapshotV1 {
+    pub min_sec_size: usize,
+    pub our_id: PublicId,
+    pub our_infos: NonEmptyList<(SectionInfo, ProofSet)>,
+    pub our_history: SectionProofChain,
+    pub neighbour_infos: BTreeMap<Prefix<XorName>, SectionInfo>,
+    pub their_keys: BTreeMap<Prefix<XorName>, SectionKeyInfo>,
+    pub their_knowledge: BTreeMap<Prefix<XorName>, u64>,
+    pub their_recent_keys: VecDeque<(Prefix<XorName>, SectionKeyInfo)>,
+}
+
+impl From<ChainSnapshotV1> for ChainSnapshotV2 {
+    fn from(snapshot: ChainSnapshotV1) -> Self {
+        Self {
+            network_params: NetworkParams {
+                min_section_size: snapshot.min_sec_size,
+                ..NetworkParams::default()
+            },
+            our_id: snapshot.our_id,
+            our_infos: snapshot.our_infos,
+            our_history: snapshot.our_history,
+            neighbour_infos: snapshot.neighbour_infos,
+            their_keys: snapshot.their_keys,
+            their_knowledge: snapshot.their_knowledge,
+            their_recent_keys: snapshot.their_recent_keys,
+        }
+    }
+}
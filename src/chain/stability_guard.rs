@@ -0,0 +1,52 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::time::Duration;
+
+/// Limits how much of our section may be replaced within a short span of its history before we
+/// stop acting on its agreement.
+///
+/// If a large part of the section joined recently, its newcomers could make up a quorum by
+/// themselves. While that is the case, only membership events (nodes joining, leaving, and new
+/// section infos) are handled; all other events keep accumulating until the section is stable.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct StabilityGuard {
+    /// The number of section versions the change is measured over.
+    pub window: u64,
+    /// The numerator of the largest fraction of the current members that may have joined within
+    /// the window.
+    pub max_joined_numerator: usize,
+    /// The denominator of that fraction.
+    pub max_joined_denominator: usize,
+    /// How long, in seconds, after the last change to our section the guard applies. This makes
+    /// sure a section which stops churning isn't frozen until its next change.
+    pub hold_secs: u64,
+}
+
+impl StabilityGuard {
+    /// Returns whether `joined` out of `size` members is more than the allowed fraction.
+    pub fn is_exceeded(&self, joined: usize, size: usize) -> bool {
+        joined * self.max_joined_denominator > size * self.max_joined_numerator
+    }
+
+    /// Returns how long after the last change to our section the guard applies.
+    pub fn hold(&self) -> Duration {
+        Duration::from_secs(self.hold_secs)
+    }
+}
+
+impl Default for StabilityGuard {
+    fn default() -> Self {
+        Self {
+            window: 4,
+            max_joined_numerator: 1,
+            max_joined_denominator: 3,
+            hold_secs: 5 * 60,
+        }
+    }
+}
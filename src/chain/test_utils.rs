@@ -73,6 +73,19 @@ fn verify_single_chain(chain: &Chain, min_section_size: usize) {
         );
     }
 
+    if let Some(info) = chain
+        .neighbour_infos()
+        .find(|info| chain.get_section(info.prefix()) != Some(info))
+    {
+        panic!(
+            "A neighbour info inferred from a split was never replaced by the actual info: \
+             {:?}; For ({:?}: {:?})",
+            info,
+            chain.our_id(),
+            chain.our_info().prefix(),
+        );
+    }
+
     for info in chain.neighbour_infos() {
         if let Some(name) = info
             .members()
@@ -0,0 +1,197 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{EventDescriptor, NetworkEvent};
+use crate::{
+    id::PublicId,
+    time::{Duration, Instant},
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Time after which a vote that still hasn't accumulated is dropped, unless configured otherwise.
+pub const DEFAULT_VOTE_TTL: Duration = Duration::from_secs(20 * 60);
+/// Lower bound for the time an event may sit in the accumulator before it is reported as stalled.
+const MIN_VOTE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Expected time it takes one more section member's vote to reach us, used until we have observed
+/// some actual accumulation times.
+const VOTE_TIME_PER_MEMBER: Duration = Duration::from_secs(2);
+/// Multiple of the expected accumulation time after which an event is considered stalled.
+const STALL_FACTOR: u32 = 3;
+/// Weight (out of `SAMPLE_WEIGHT_DENOMINATOR`) of a new sample in the moving average of the
+/// observed accumulation times.
+const SAMPLE_WEIGHT: u32 = 1;
+const SAMPLE_WEIGHT_DENOMINATOR: u32 = 4;
+
+/// A network event this node voted for which hasn't been accumulated by its section yet.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingVote {
+    /// The kind and key fields of the event.
+    pub event: EventDescriptor,
+    /// Time since the first vote for the event reached us.
+    pub age: Duration,
+    /// The number of valid votes the event has received so far.
+    pub have: usize,
+    /// The number of votes the event needs to accumulate.
+    pub need: usize,
+}
+
+/// A network event waiting in the chain accumulator for more proofs, whether or not this node
+/// voted for it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingEvent {
+    /// The kind and key fields of the event.
+    pub event: EventDescriptor,
+    /// Time since the first vote for the event reached us.
+    pub age: Duration,
+    /// The number of valid proofs collected so far.
+    pub proofs: usize,
+    /// The number of proofs the event needs to accumulate.
+    pub need: usize,
+    /// The members of our section which haven't voted for the event yet.
+    pub missing: BTreeSet<PublicId>,
+}
+
+/// Tracks how long events stay in the chain accumulator, and derives from the section size and
+/// the observed accumulation times how long we should wait before considering a vote stalled.
+#[derive(Default)]
+pub struct VoteTimeout {
+    /// When the first proof for each pending event arrived, and whether it was reported stalled.
+    first_seen: BTreeMap<NetworkEvent, (Instant, bool)>,
+    /// Exponential moving average of the time it took events to accumulate so far.
+    observed: Option<Duration>,
+}
+
+impl VoteTimeout {
+    /// Records the arrival of a proof for `event`. Only the first call per event has an effect.
+    pub fn insert(&mut self, event: &NetworkEvent) {
+        if !self.first_seen.contains_key(event) {
+            let _ = self
+                .first_seen
+                .insert(event.clone(), (Instant::now(), false));
+        }
+    }
+
+    /// Records that `event` got accumulated, feeding the time it took into the moving average.
+    pub fn complete(&mut self, event: &NetworkEvent) {
+        if let Some((first_seen, _)) = self.first_seen.remove(event) {
+            self.add_sample(first_seen.elapsed());
+        }
+    }
+
+    /// Forgets about `event` without accounting for it in the observed accumulation times.
+    pub fn remove(&mut self, event: &NetworkEvent) {
+        let _ = self.first_seen.remove(event);
+    }
+
+    /// Returns the time since the first proof for `event` arrived, if it is pending.
+    pub fn age(&self, event: &NetworkEvent) -> Option<Duration> {
+        self.first_seen
+            .get(event)
+            .map(|(first_seen, _)| first_seen.elapsed())
+    }
+
+    /// Forgets about all pending events, e.g. when the accumulator gets reset on prefix change.
+    pub fn clear(&mut self) {
+        self.first_seen.clear();
+    }
+
+    /// Removes and returns the pending events whose first proof arrived more than `ttl` ago.
+    pub fn take_expired(&mut self, ttl: Duration) -> Vec<NetworkEvent> {
+        let expired: Vec<_> = self
+            .first_seen
+            .iter()
+            .filter(|(_, (first_seen, _))| first_seen.elapsed() > ttl)
+            .map(|(event, _)| event.clone())
+            .collect();
+        for event in &expired {
+            let _ = self.first_seen.remove(event);
+        }
+        expired
+    }
+
+    /// Returns the pending events that have been waiting longer than the adaptive deadline for a
+    /// section needing `quorum` votes and that were not reported before. Each event is only
+    /// returned once.
+    pub fn take_stalled(&mut self, quorum: usize) -> Vec<NetworkEvent> {
+        let deadline = self.deadline(quorum);
+        self.first_seen
+            .iter_mut()
+            .filter(|(_, (first_seen, reported))| !*reported && first_seen.elapsed() > deadline)
+            .map(|(event, (_, reported))| {
+                *reported = true;
+                event.clone()
+            })
+            .collect()
+    }
+
+    /// Returns the time after which a pending event is considered stalled in a section needing
+    /// `quorum` votes.
+    pub fn deadline(&self, quorum: usize) -> Duration {
+        let expected = self
+            .observed
+            .unwrap_or_else(|| VOTE_TIME_PER_MEMBER * quorum as u32);
+        let timeout = expected * STALL_FACTOR;
+        if timeout < MIN_VOTE_TIMEOUT {
+            MIN_VOTE_TIMEOUT
+        } else {
+            timeout
+        }
+    }
+
+    fn add_sample(&mut self, sample: Duration) {
+        self.observed = Some(match self.observed {
+            None => sample,
+            Some(observed) => {
+                (observed * (SAMPLE_WEIGHT_DENOMINATOR - SAMPLE_WEIGHT) + sample * SAMPLE_WEIGHT)
+                    / SAMPLE_WEIGHT_DENOMINATOR
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadline_grows_with_quorum() {
+        let timeout = VoteTimeout::default();
+        assert_eq!(timeout.deadline(1), MIN_VOTE_TIMEOUT);
+        assert!(timeout.deadline(100) > timeout.deadline(10));
+        assert!(timeout.deadline(10) >= MIN_VOTE_TIMEOUT);
+    }
+
+    #[test]
+    fn deadline_follows_observed_accumulation_time() {
+        let mut timeout = VoteTimeout::default();
+        timeout.add_sample(Duration::from_secs(60));
+        assert_eq!(
+            timeout.deadline(100),
+            Duration::from_secs(60) * STALL_FACTOR
+        );
+
+        // A fast accumulation pulls the average down, but never below the lower bound.
+        for _ in 0..50 {
+            timeout.add_sample(Duration::from_millis(10));
+        }
+        assert_eq!(timeout.deadline(100), MIN_VOTE_TIMEOUT);
+    }
+
+    #[test]
+    fn expired_events_are_forgotten() {
+        let mut timeout = VoteTimeout::default();
+        let event = NetworkEvent::OurMerge;
+        timeout.insert(&event);
+
+        assert!(timeout.take_expired(DEFAULT_VOTE_TTL).is_empty());
+        assert!(timeout.age(&event).is_some());
+
+        timeout.remove(&event);
+        assert_eq!(timeout.age(&event), None);
+    }
+}
@@ -6,25 +6,38 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+#[cfg(feature = "data")]
+use crate::data::{DataMap, DataMapAssembler};
+#[cfg(feature = "mock_base")]
+use crate::event::Event;
 #[cfg(feature = "mock_base")]
 use crate::event_stream::{EventStepper, EventStream};
+#[cfg(feature = "messaging")]
+use crate::messaging::{HeaderQuery, Mailbox};
+#[cfg(not(feature = "mock_base"))]
+use crate::outbox::EventSender;
 use crate::{
     action::Action,
     cache::NullCache,
+    client_config::ClientConfig,
     config_handler::{self, Config},
-    data::{EntryAction, ImmutableData, MutableData, PermissionSet, User},
+    data::{
+        AccountPacket, AppendEntry, AppendOnlyData, EntryAction, ImmutableData, KeyPermissions,
+        MutableData, PermissionSet, User,
+    },
     error::{InterfaceError, RoutingError},
-    event::Event,
     id::{FullId, PublicId},
     messages::{Request, CLIENT_GET_PRIORITY, DEFAULT_PRIORITY},
+    network_params::NetworkParams,
     outbox::{EventBox, EventBuf},
     quic_p2p::OurType,
     routing_table::Authority,
+    session::SessionToken,
     state_machine::{State, StateMachine},
     states::{BootstrappingPeer, TargetState},
     types::MessageId,
     xor_name::XorName,
-    NetworkConfig, MIN_SECTION_SIZE,
+    NetworkConfig,
 };
 use crossbeam_channel as mpmc;
 #[cfg(not(feature = "mock_base"))]
@@ -40,6 +53,9 @@ use std::{
 #[cfg(not(feature = "mock_base"))]
 use unwrap::unwrap;
 
+#[cfg(not(feature = "mock_base"))]
+pub mod asynch;
+
 /// Interface for sending and receiving messages to and from a network of nodes in the role of a
 /// client.
 ///
@@ -66,28 +82,38 @@ impl Client {
         outbox: &mut dyn EventBox,
         mut network_config: NetworkConfig,
         config: Option<Config>,
-        msg_expiry_dur: Duration,
+        client_config: ClientConfig,
+        session: Option<SessionToken>,
     ) -> (mpmc::Sender<Action>, StateMachine) {
         let full_id = keys.unwrap_or_else(FullId::new);
         let config = config.unwrap_or_else(config_handler::get_config);
-        let dev_config = config.dev.unwrap_or_default();
-        let min_section_size = dev_config.min_section_size.unwrap_or(MIN_SECTION_SIZE);
+        let network_params = NetworkParams {
+            timeouts: *client_config.timeouts(),
+            ..NetworkParams::from_config(&config)
+        };
 
         network_config.our_type = OurType::Client;
+        let proxy_candidates = network_config.hard_coded_contacts.iter().cloned().collect();
 
         StateMachine::new(
-            move |action_sender, network_service, timer, _outbox2| {
+            move |action_sender, mut network_service, timer, _outbox2| {
+                network_service.set_send_ack_timeout(network_params.timeouts.send_ack);
                 State::BootstrappingPeer(BootstrappingPeer::new(
                     action_sender,
                     Box::new(NullCache),
-                    TargetState::Client { msg_expiry_dur },
+                    TargetState::Client {
+                        config: client_config,
+                        session,
+                        proxy_candidates,
+                    },
                     network_service,
                     full_id,
-                    min_section_size,
+                    network_params,
                     timer,
                 ))
             },
             network_config,
+            None,
             outbox,
         )
     }
@@ -117,6 +143,44 @@ impl Client {
         self.send_request(dst, request, DEFAULT_PRIORITY)
     }
 
+    /// Splits `content` into ImmutableData chunks and puts each of them to the network. Returns
+    /// the data map to fetch the content with, and the message IDs of the `PutIData` requests.
+    #[cfg(feature = "data")]
+    pub fn put_large_idata(
+        &mut self,
+        dst: Authority<XorName>,
+        content: &[u8],
+    ) -> Result<(DataMap, Vec<MessageId>), InterfaceError> {
+        let (data_map, chunks) = DataMap::split(content);
+        let mut msg_ids = Vec::with_capacity(chunks.len());
+        for chunk in chunks {
+            let msg_id = MessageId::new();
+            self.put_idata(dst, chunk, msg_id)?;
+            msg_ids.push(msg_id);
+        }
+
+        Ok((data_map, msg_ids))
+    }
+
+    /// Fetches the chunks `assembler` is waiting for, and returns their names and the message IDs
+    /// of the `GetIData` requests. The chunks in the responses are to be passed to
+    /// `DataMapAssembler::add_chunk`, and whenever that returns `true`, this has to be called
+    /// again to fetch the chunks listed in the nested data map it completed.
+    #[cfg(feature = "data")]
+    pub fn get_large_idata(
+        &mut self,
+        assembler: &DataMapAssembler,
+    ) -> Result<Vec<(XorName, MessageId)>, InterfaceError> {
+        let mut requests = Vec::new();
+        for name in assembler.wanted() {
+            let msg_id = MessageId::new();
+            self.get_idata(Authority::NaeManager(name), name, msg_id)?;
+            requests.push((name, msg_id));
+        }
+
+        Ok(requests)
+    }
+
     /// Fetches ImmutableData from the network by the given name.
     pub fn get_idata(
         &mut self,
@@ -201,6 +265,29 @@ impl Client {
         self.send_request(dst, request, CLIENT_GET_PRIORITY)
     }
 
+    /// Fetches at most `limit` entries of the provided MutableData, starting with the one with the
+    /// key `start_key` or the next greater key. The response includes the key to pass as
+    /// `start_key` to fetch the next page, if there are more entries.
+    pub fn list_mdata_entries_range(
+        &mut self,
+        dst: Authority<XorName>,
+        name: XorName,
+        tag: u64,
+        start_key: Vec<u8>,
+        limit: u64,
+        msg_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        let request = Request::ListMDataEntriesRange {
+            name: name,
+            tag: tag,
+            start_key: start_key,
+            limit: limit,
+            msg_id: msg_id,
+        };
+
+        self.send_request(dst, request, CLIENT_GET_PRIORITY)
+    }
+
     /// Fetches a list of keys of the provided MutableData
     /// Note: response to this request is unlikely to accumulate during churn.
     pub fn list_mdata_keys(
@@ -273,7 +360,9 @@ impl Client {
         self.send_request(dst, request, DEFAULT_PRIORITY)
     }
 
-    /// Updates `MutableData` entries in bulk
+    /// Updates `MutableData` entries in bulk. The actions are applied atomically: if any of them
+    /// is invalid, none is applied and the response carries
+    /// `ClientError::InvalidEntryActions`, with the error for each invalid action's key.
     pub fn mutate_mdata_entries(
         &mut self,
         dst: Authority<XorName>,
@@ -380,6 +469,120 @@ impl Client {
         self.send_request(dst, request, DEFAULT_PRIORITY)
     }
 
+    /// Sets the permissions on the entries whose keys start with `prefix`, or removes them if
+    /// `permissions` are empty
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_mdata_key_permissions(
+        &mut self,
+        dst: Authority<XorName>,
+        name: XorName,
+        tag: u64,
+        prefix: Vec<u8>,
+        permissions: KeyPermissions,
+        version: u64,
+        msg_id: MessageId,
+        requester: PublicSignKey,
+    ) -> Result<(), InterfaceError> {
+        let request = Request::SetMDataKeyPermissions {
+            name: name,
+            tag: tag,
+            prefix: prefix,
+            permissions: permissions,
+            version: version,
+            msg_id: msg_id,
+            requester: requester,
+        };
+
+        self.send_request(dst, request, DEFAULT_PRIORITY)
+    }
+
+    /// Asks to be notified of mutations of the entries of the given MutableData, which are raised
+    /// as `Event::MDataChanged`. The watch is renewed periodically, so it persists through churn
+    /// in the managing section, until `unwatch_mdata` is called.
+    pub fn watch_mdata(
+        &mut self,
+        name: XorName,
+        tag: u64,
+        msg_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        let request = Request::WatchMData {
+            name: name,
+            tag: tag,
+            msg_id: msg_id,
+        };
+
+        self.send_request(Authority::NaeManager(name), request, CLIENT_GET_PRIORITY)
+    }
+
+    /// Stops watching the given MutableData.
+    pub fn unwatch_mdata(
+        &mut self,
+        name: XorName,
+        tag: u64,
+        msg_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        let request = Request::UnwatchMData {
+            name: name,
+            tag: tag,
+            msg_id: msg_id,
+        };
+
+        self.send_request(Authority::NaeManager(name), request, CLIENT_GET_PRIORITY)
+    }
+
+    /// Lists the summaries of the headers in one of our mailboxes which match `query`, without
+    /// fetching the headers or messages.
+    #[cfg(feature = "messaging")]
+    pub fn list_mpid_headers(
+        &mut self,
+        dst: Authority<XorName>,
+        mailbox: Mailbox,
+        query: HeaderQuery,
+        msg_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        let request = Request::ListMpidHeaders {
+            mailbox: mailbox,
+            query: query,
+            msg_id: msg_id,
+        };
+
+        self.send_request(dst, request, CLIENT_GET_PRIORITY)
+    }
+
+    /// Deletes the named entries from one of our mailboxes.
+    #[cfg(feature = "messaging")]
+    pub fn delete_mpid_headers(
+        &mut self,
+        dst: Authority<XorName>,
+        mailbox: Mailbox,
+        names: BTreeSet<XorName>,
+        msg_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        let request = Request::DeleteMpidHeaders {
+            mailbox: mailbox,
+            names: names,
+            msg_id: msg_id,
+        };
+
+        self.send_request(dst, request, DEFAULT_PRIORITY)
+    }
+
+    /// Gets the number and total size of the entries in one of our mailboxes.
+    #[cfg(feature = "messaging")]
+    pub fn get_mpid_mailbox_usage(
+        &mut self,
+        dst: Authority<XorName>,
+        mailbox: Mailbox,
+        msg_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        let request = Request::GetMpidMailboxUsage {
+            mailbox: mailbox,
+            msg_id: msg_id,
+        };
+
+        self.send_request(dst, request, CLIENT_GET_PRIORITY)
+    }
+
     /// Sends an ownership transfer request
     pub fn change_mdata_owner(
         &mut self,
@@ -401,6 +604,111 @@ impl Client {
         self.send_request(dst, request, DEFAULT_PRIORITY)
     }
 
+    /// Creates a new `AppendOnlyData` in the network
+    pub fn put_adata(
+        &mut self,
+        dst: Authority<XorName>,
+        data: AppendOnlyData,
+        msg_id: MessageId,
+        requester: PublicSignKey,
+    ) -> Result<(), InterfaceError> {
+        let request = Request::PutAData {
+            data: data,
+            msg_id: msg_id,
+            requester: requester,
+        };
+
+        self.send_request(dst, request, DEFAULT_PRIORITY)
+    }
+
+    /// Fetches the entire AppendOnlyData
+    pub fn get_adata(
+        &mut self,
+        dst: Authority<XorName>,
+        name: XorName,
+        tag: u64,
+        msg_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        let request = Request::GetAData {
+            name: name,
+            tag: tag,
+            msg_id: msg_id,
+        };
+
+        self.send_request(dst, request, CLIENT_GET_PRIORITY)
+    }
+
+    /// Fetches the entries of the provided AppendOnlyData appended at or after `from_version`
+    pub fn get_adata_entries(
+        &mut self,
+        dst: Authority<XorName>,
+        name: XorName,
+        tag: u64,
+        from_version: u64,
+        msg_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        let request = Request::GetADataEntries {
+            name: name,
+            tag: tag,
+            from_version: from_version,
+            msg_id: msg_id,
+        };
+
+        self.send_request(dst, request, CLIENT_GET_PRIORITY)
+    }
+
+    /// Appends an owner-signed entry to the provided AppendOnlyData. The entry's version has to
+    /// be the current version of the data.
+    pub fn append_adata_entry(
+        &mut self,
+        dst: Authority<XorName>,
+        name: XorName,
+        tag: u64,
+        entry: AppendEntry,
+        msg_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        let request = Request::AppendADataEntry {
+            name: name,
+            tag: tag,
+            entry: entry,
+            msg_id: msg_id,
+        };
+
+        self.send_request(dst, request, DEFAULT_PRIORITY)
+    }
+
+    /// Fetches the `AccountPacket` with the given name, see
+    /// `AccountPacket::name_from_credentials`
+    pub fn get_account(
+        &mut self,
+        dst: Authority<XorName>,
+        name: XorName,
+        msg_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        let request = Request::GetAccount {
+            name: name,
+            msg_id: msg_id,
+        };
+
+        self.send_request(dst, request, CLIENT_GET_PRIORITY)
+    }
+
+    /// Stores the `AccountPacket`: a new one has to have version 0, otherwise it has to be the
+    /// successor of the stored packet.
+    pub fn update_account(
+        &mut self,
+        dst: Authority<XorName>,
+        data: AccountPacket,
+        msg_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        let request = Request::UpdateAccount {
+            data: data,
+            msg_id: msg_id,
+        };
+
+        self.send_request(dst, request, DEFAULT_PRIORITY)
+    }
+
     /// Fetches a list of authorised keys and version in MaidManager
     pub fn list_auth_keys_and_version(
         &mut self,
@@ -444,6 +752,29 @@ impl Client {
 
         self.send_request(dst, request, DEFAULT_PRIORITY)
     }
+
+    /// Asks our ClientManager `dst` to send the GET `request` to `target` on our behalf. The
+    /// answer arrives as a `Response::RelayedGet` with the message ID of `request`, and the
+    /// enclosed `RelayedResponse` can be checked against `target` and a key of its section we
+    /// trust with `RelayedResponse::verify`.
+    pub fn relayed_get(
+        &mut self,
+        dst: Authority<XorName>,
+        target: Authority<XorName>,
+        request: Request,
+    ) -> Result<(), InterfaceError> {
+        if !request.is_get() {
+            return Err(InterfaceError::InvalidState);
+        }
+
+        let request = Request::RelayedGet {
+            dst: target,
+            msg_id: *request.message_id(),
+            request: Box::new(request),
+        };
+
+        self.send_request(dst, request, CLIENT_GET_PRIORITY)
+    }
 }
 
 #[cfg(not(feature = "mock_base"))]
@@ -458,13 +789,65 @@ impl Client {
     /// Keys will be exchanged with the `ClientAuthority` so that communication with the network is
     /// cryptographically secure and uses section consensus. The restriction for the client name
     /// exists to ensure that the client cannot choose its `ClientAuthority`.
-    pub fn new(
-        event_sender: mpsc::Sender<Event>,
+    ///
+    /// Events are delivered through `event_sender`, e.g. an `mpsc::Sender<Event>` or a bounded
+    /// `crossbeam_channel::Sender<Event>`. See [`EventSender`](trait.EventSender.html).
+    pub fn new<S: EventSender + 'static>(
+        event_sender: S,
         keys: Option<FullId>,
         network_config: Option<NetworkConfig>,
         msg_expiry_dur: Duration,
+    ) -> Result<Client, RoutingError> {
+        let client_config = ClientConfig::builder()
+            .msg_expiry_dur(msg_expiry_dur)
+            .build();
+        Self::with_config(event_sender, keys, network_config, client_config)
+    }
+
+    /// Create a new `Client` like `new`, configured by `client_config`, e.g. to retry requests.
+    pub fn with_config<S: EventSender + 'static>(
+        event_sender: S,
+        keys: Option<FullId>,
+        network_config: Option<NetworkConfig>,
+        client_config: ClientConfig,
+    ) -> Result<Client, RoutingError> {
+        Self::start(event_sender, keys, network_config, client_config, None)
+    }
+
+    /// Create a new `Client` like `with_config`, resuming the session of `token`, which the
+    /// client with the given `keys` was issued in an `Event::SessionStarted`.
+    ///
+    /// The client reconnects without asking its ClientManager for a new token. If its proxy
+    /// refuses this one, e.g. because it is too old, a new one is raised in another
+    /// `Event::SessionStarted`.
+    pub fn resume<S: EventSender + 'static>(
+        event_sender: S,
+        keys: FullId,
+        token: SessionToken,
+        network_config: Option<NetworkConfig>,
+        client_config: ClientConfig,
+    ) -> Result<Client, RoutingError> {
+        if token.client_id() != Some(keys.public_id()) {
+            return Err(RoutingError::InvalidOwners);
+        }
+        Self::start(
+            event_sender,
+            Some(keys),
+            network_config,
+            client_config,
+            Some(token),
+        )
+    }
+
+    fn start<S: EventSender + 'static>(
+        event_sender: S,
+        keys: Option<FullId>,
+        network_config: Option<NetworkConfig>,
+        client_config: ClientConfig,
+        session: Option<SessionToken>,
     ) -> Result<Client, RoutingError> {
         safe_crypto::init()?; // enable shared global (i.e. safe to multithread now)
+        client_config.timeouts().validate()?;
 
         let (tx, rx) = mpsc::channel();
         let (get_action_sender_tx, get_action_sender_rx) = mpsc::channel();
@@ -478,11 +861,12 @@ impl Client {
                 &mut event_buffer,
                 network_config,
                 None,
-                msg_expiry_dur,
+                client_config,
+                session,
             );
 
-            for ev in event_buffer.take_all() {
-                unwrap!(event_sender.send(ev));
+            if !event_buffer.deliver_all(&event_sender) {
+                return;
             }
 
             unwrap!(get_action_sender_tx.send(action_sender));
@@ -490,11 +874,9 @@ impl Client {
             // Gather events from the state machine's event loop and proxy them over the
             // event_sender channel.
             while Ok(()) == machine.step(&mut event_buffer) {
-                for ev in event_buffer.take_all() {
-                    // If sending the event fails, terminate this thread.
-                    if event_sender.send(ev).is_err() {
-                        return;
-                    }
+                // If sending the events fails, terminate this thread.
+                if !event_buffer.deliver_all(&event_sender) {
+                    return;
                 }
             }
             // When there are no more events to process, terminate this thread.
@@ -546,6 +928,50 @@ impl Client {
         config: Config,
         msg_expiry_dur: Duration,
     ) -> Result<Client, RoutingError> {
+        let client_config = ClientConfig::builder()
+            .msg_expiry_dur(msg_expiry_dur)
+            .build();
+        Self::with_config(keys, network_config, config, client_config)
+    }
+
+    /// Create a new `Client` for testing with mock network, configured by `client_config`.
+    pub fn with_config(
+        keys: Option<FullId>,
+        network_config: Option<NetworkConfig>,
+        config: Config,
+        client_config: ClientConfig,
+    ) -> Result<Client, RoutingError> {
+        Self::start(keys, network_config, config, client_config, None)
+    }
+
+    /// Create a new `Client` for testing with mock network, resuming the session of `token`.
+    pub fn resume(
+        keys: FullId,
+        token: SessionToken,
+        network_config: Option<NetworkConfig>,
+        config: Config,
+        client_config: ClientConfig,
+    ) -> Result<Client, RoutingError> {
+        if token.client_id() != Some(keys.public_id()) {
+            return Err(RoutingError::InvalidOwners);
+        }
+        Self::start(
+            Some(keys),
+            network_config,
+            config,
+            client_config,
+            Some(token),
+        )
+    }
+
+    fn start(
+        keys: Option<FullId>,
+        network_config: Option<NetworkConfig>,
+        config: Config,
+        client_config: ClientConfig,
+        session: Option<SessionToken>,
+    ) -> Result<Client, RoutingError> {
+        client_config.timeouts().validate()?;
         let network_config = network_config.unwrap_or_default();
 
         let mut event_buffer = EventBuf::new();
@@ -554,7 +980,8 @@ impl Client {
             &mut event_buffer,
             network_config,
             Some(config),
-            msg_expiry_dur,
+            client_config,
+            session,
         );
 
         let (tx, rx) = mpsc::channel();
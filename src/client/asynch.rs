@@ -0,0 +1,624 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! A `Client` whose requests return futures resolving to their responses.
+//!
+//! The futures don't depend on a particular executor. Responses are matched to requests by their
+//! message ID as the routing client delivers them, and all other events are passed on to the
+//! event sender given to `Client::new`.
+
+use crate::{
+    client_config::ClientConfig,
+    client_error::ClientError,
+    data::{EntryAction, ImmutableData, MutableData, Value},
+    error::{InterfaceError, RoutingError},
+    event::Event,
+    id::FullId,
+    messages::{AccountInfo, Request, Response, CLIENT_GET_PRIORITY, DEFAULT_PRIORITY},
+    outbox::EventSender,
+    routing_table::Authority,
+    time::{Duration, Instant},
+    types::MessageId,
+    xor_name::XorName,
+    NetworkConfig,
+};
+use maidsafe_utilities::thread::{self, Joiner};
+use safe_crypto::PublicSignKey;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Display, Formatter},
+    future::Future,
+    pin::Pin,
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+};
+use unwrap::unwrap;
+
+/// How often requests are checked for having timed out.
+const TIMEOUT_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Errors a `ResponseFuture` can resolve to.
+#[derive(Debug)]
+pub enum Error {
+    /// The request couldn't be sent.
+    Interface(InterfaceError),
+    /// The network refused the request.
+    Client(ClientError),
+    /// No response arrived in time.
+    TimedOut,
+    /// The request wasn't answered in any of the attempts allowed by the `RetryPolicy`.
+    RequestFailed,
+    /// The client terminated before the response arrived.
+    Terminated,
+    /// The response doesn't match the request.
+    UnexpectedResponse,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Error::Interface(ref error) => write!(f, "Interface error: {:?}", error),
+            Error::Client(ref error) => write!(f, "Client error: {}", error),
+            Error::TimedOut => write!(f, "Request timed out"),
+            Error::RequestFailed => write!(f, "Request failed after retrying"),
+            Error::Terminated => write!(f, "Client terminated"),
+            Error::UnexpectedResponse => write!(f, "Unexpected response"),
+        }
+    }
+}
+
+struct Slot {
+    deadline: Instant,
+    result: Option<Result<Response, Error>>,
+    waker: Option<Waker>,
+}
+
+/// The requests waiting for their responses.
+#[derive(Clone, Default)]
+struct Pending(Arc<Mutex<HashMap<MessageId, Slot>>>);
+
+impl Pending {
+    fn insert(&self, msg_id: MessageId, deadline: Instant) {
+        let slot = Slot {
+            deadline,
+            result: None,
+            waker: None,
+        };
+        let _ = unwrap!(self.0.lock()).insert(msg_id, slot);
+    }
+
+    fn remove(&self, msg_id: &MessageId) {
+        let _ = unwrap!(self.0.lock()).remove(msg_id);
+    }
+
+    fn set_deadline(&self, msg_id: &MessageId, deadline: Instant) {
+        if let Some(slot) = unwrap!(self.0.lock()).get_mut(msg_id) {
+            slot.deadline = deadline;
+        }
+    }
+
+    // Completes the request `response` answers, or gives the response back if there is none.
+    fn resolve(&self, response: Response) -> Option<Response> {
+        let mut slots = unwrap!(self.0.lock());
+        match slots.get_mut(response.message_id()) {
+            Some(slot) if slot.result.is_none() => {
+                slot.complete(Ok(response));
+                None
+            }
+            _ => Some(response),
+        }
+    }
+
+    fn expire(&self, now: Instant) {
+        for slot in unwrap!(self.0.lock()).values_mut() {
+            if slot.result.is_none() && slot.deadline <= now {
+                slot.complete(Err(Error::TimedOut));
+            }
+        }
+    }
+
+    // Completes the request with the given message ID with `error`. Returns whether it was pending.
+    fn fail(&self, msg_id: &MessageId, error: Error) -> bool {
+        match unwrap!(self.0.lock()).get_mut(msg_id) {
+            Some(slot) if slot.result.is_none() => {
+                slot.complete(Err(error));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn terminate(&self) {
+        for slot in unwrap!(self.0.lock()).values_mut() {
+            if slot.result.is_none() {
+                slot.complete(Err(Error::Terminated));
+            }
+        }
+    }
+
+    fn poll(&self, msg_id: &MessageId, waker: &Waker) -> Poll<Result<Response, Error>> {
+        let mut slots = unwrap!(self.0.lock());
+        let result = match slots.get_mut(msg_id) {
+            Some(slot) => match slot.result.take() {
+                Some(result) => result,
+                None => {
+                    slot.waker = Some(waker.clone());
+                    return Poll::Pending;
+                }
+            },
+            // The slot is only gone once the future completed.
+            None => return Poll::Ready(Err(Error::Terminated)),
+        };
+        let _ = slots.remove(msg_id);
+        Poll::Ready(result)
+    }
+}
+
+impl Slot {
+    fn complete(&mut self, result: Result<Response, Error>) {
+        self.result = Some(result);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Receives the events of the routing client: completes the requests responses arrive for, and
+/// passes everything else on.
+struct Dispatcher<S> {
+    pending: Pending,
+    events: S,
+}
+
+impl<S: EventSender> EventSender for Dispatcher<S> {
+    fn deliver(&self, event: Event) -> Result<(), Event> {
+        match event {
+            Event::ResponseReceived { response, src, dst } => {
+                match self.pending.resolve(response) {
+                    None => Ok(()),
+                    Some(response) => {
+                        self.events
+                            .deliver(Event::ResponseReceived { response, src, dst })
+                    }
+                }
+            }
+            Event::RequestFailed { dst, msg_id } => {
+                if self.pending.fail(&msg_id, Error::RequestFailed) {
+                    Ok(())
+                } else {
+                    self.events.deliver(Event::RequestFailed { dst, msg_id })
+                }
+            }
+            Event::Terminated => {
+                self.pending.terminate();
+                self.events.deliver(Event::Terminated)
+            }
+            event => self.events.deliver(event),
+        }
+    }
+}
+
+/// A future resolving to the response to a request, or an error if it can't be sent, fails or
+/// times out. Dropping it cancels the request: a response arriving later is ignored.
+pub struct ResponseFuture<T> {
+    msg_id: MessageId,
+    pending: Pending,
+    extract: fn(Response) -> Option<Result<T, ClientError>>,
+    error: Option<Error>,
+}
+
+impl<T> ResponseFuture<T> {
+    /// Returns the message ID of the request.
+    pub fn message_id(&self) -> &MessageId {
+        &self.msg_id
+    }
+
+    /// Makes the request time out after `timeout` from now, instead of the client's default.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        self.pending
+            .set_deadline(&self.msg_id, Instant::now() + timeout);
+        self
+    }
+}
+
+impl<T> Future for ResponseFuture<T> {
+    type Output = Result<T, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        if let Some(error) = self.error.take() {
+            return Poll::Ready(Err(error));
+        }
+        let response = match self.pending.poll(&self.msg_id, cx.waker()) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(Err(error)) => return Poll::Ready(Err(error)),
+            Poll::Ready(Ok(response)) => response,
+        };
+        Poll::Ready(match (self.extract)(response) {
+            Some(Ok(value)) => Ok(value),
+            Some(Err(error)) => Err(Error::Client(error)),
+            None => Err(Error::UnexpectedResponse),
+        })
+    }
+}
+
+impl<T> Drop for ResponseFuture<T> {
+    fn drop(&mut self) {
+        self.pending.remove(&self.msg_id);
+    }
+}
+
+/// Interface for sending requests to the network as a client, with each request returning a
+/// future of its response. See the `routing::Client` for the underlying API.
+pub struct Client {
+    inner: Mutex<super::Client>,
+    pending: Pending,
+    timeout: Duration,
+    // Dropped before `_timeouts` is joined, which stops the timeout thread.
+    _stop_tx: mpsc::Sender<()>,
+    _timeouts: Joiner,
+}
+
+impl Client {
+    /// Creates a new client, connecting to the network like `routing::Client::new`.
+    ///
+    /// Responses resolve the futures of their requests, and all other events are delivered
+    /// through `event_sender`. Requests time out after `timeout`, unless overridden per request
+    /// with `ResponseFuture::timeout`.
+    pub fn new<S: EventSender + 'static>(
+        event_sender: S,
+        keys: Option<FullId>,
+        network_config: Option<NetworkConfig>,
+        msg_expiry_dur: Duration,
+        timeout: Duration,
+    ) -> Result<Client, RoutingError> {
+        let client_config = ClientConfig::builder()
+            .msg_expiry_dur(msg_expiry_dur)
+            .build();
+        Self::with_config(event_sender, keys, network_config, client_config, timeout)
+    }
+
+    /// Creates a new client like `new`, configured by `client_config`. With a `RetryPolicy`,
+    /// `timeout` should leave enough time for all attempts.
+    pub fn with_config<S: EventSender + 'static>(
+        event_sender: S,
+        keys: Option<FullId>,
+        network_config: Option<NetworkConfig>,
+        client_config: ClientConfig,
+        timeout: Duration,
+    ) -> Result<Client, RoutingError> {
+        let pending = Pending::default();
+        let dispatcher = Dispatcher {
+            pending: pending.clone(),
+            events: event_sender,
+        };
+        let inner = super::Client::with_config(dispatcher, keys, network_config, client_config)?;
+
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+        let timeout_pending = pending.clone();
+        let timeouts = thread::named("Client timeouts", move || loop {
+            match stop_rx.recv_timeout(TIMEOUT_CHECK_INTERVAL) {
+                Err(RecvTimeoutError::Timeout) => timeout_pending.expire(Instant::now()),
+                _ => break,
+            }
+        });
+
+        Ok(Client {
+            inner: Mutex::new(inner),
+            pending,
+            timeout,
+            _stop_tx: stop_tx,
+            _timeouts: timeouts,
+        })
+    }
+
+    /// Sends `request` to `dst` and returns the future of the response, whatever it is.
+    pub fn send(&self, dst: Authority<XorName>, request: Request) -> ResponseFuture<Response> {
+        let msg_id = *request.message_id();
+        let priority = if request.is_get() {
+            CLIENT_GET_PRIORITY
+        } else {
+            DEFAULT_PRIORITY
+        };
+        self.request(
+            msg_id,
+            |response| Some(Ok(response)),
+            |client| client.send_request(dst, request, priority),
+        )
+    }
+
+    /// Gets MAID account information.
+    pub fn get_account_info(&self, dst: Authority<XorName>) -> ResponseFuture<AccountInfo> {
+        let msg_id = MessageId::new();
+        self.request(
+            msg_id,
+            |response| match response {
+                Response::GetAccountInfo { res, .. } => Some(res),
+                _ => None,
+            },
+            |client| client.get_account_info(dst, msg_id),
+        )
+    }
+
+    /// Puts ImmutableData to the network.
+    pub fn put_idata(&self, dst: Authority<XorName>, data: ImmutableData) -> ResponseFuture<()> {
+        let msg_id = MessageId::new();
+        self.request(
+            msg_id,
+            |response| match response {
+                Response::PutIData { res, .. } => Some(res),
+                _ => None,
+            },
+            |client| client.put_idata(dst, data, msg_id),
+        )
+    }
+
+    /// Fetches ImmutableData from the network by the given name.
+    pub fn get_idata(&self, name: XorName) -> ResponseFuture<ImmutableData> {
+        let msg_id = MessageId::new();
+        self.request(
+            msg_id,
+            |response| match response {
+                Response::GetIData { res, .. } => Some(res),
+                _ => None,
+            },
+            |client| client.get_idata(Authority::NaeManager(name), name, msg_id),
+        )
+    }
+
+    /// Creates a new MutableData in the network.
+    pub fn put_mdata(
+        &self,
+        dst: Authority<XorName>,
+        data: MutableData,
+        requester: PublicSignKey,
+    ) -> ResponseFuture<()> {
+        let msg_id = MessageId::new();
+        self.request(
+            msg_id,
+            |response| match response {
+                Response::PutMData { res, .. } => Some(res),
+                _ => None,
+            },
+            |client| client.put_mdata(dst, data, msg_id, requester),
+        )
+    }
+
+    /// Fetches the entire MutableData.
+    pub fn get_mdata(&self, name: XorName, tag: u64) -> ResponseFuture<MutableData> {
+        let msg_id = MessageId::new();
+        self.request(
+            msg_id,
+            |response| match response {
+                Response::GetMData { res, .. } => Some(res),
+                _ => None,
+            },
+            |client| client.get_mdata(Authority::NaeManager(name), name, tag, msg_id),
+        )
+    }
+
+    /// Fetches the latest version of the MutableData.
+    pub fn get_mdata_version(&self, name: XorName, tag: u64) -> ResponseFuture<u64> {
+        let msg_id = MessageId::new();
+        self.request(
+            msg_id,
+            |response| match response {
+                Response::GetMDataVersion { res, .. } => Some(res),
+                _ => None,
+            },
+            |client| client.get_mdata_version(Authority::NaeManager(name), name, tag, msg_id),
+        )
+    }
+
+    /// Fetches a single value from the MutableData.
+    pub fn get_mdata_value(&self, name: XorName, tag: u64, key: Vec<u8>) -> ResponseFuture<Value> {
+        let msg_id = MessageId::new();
+        self.request(
+            msg_id,
+            |response| match response {
+                Response::GetMDataValue { res, .. } => Some(res),
+                _ => None,
+            },
+            |client| client.get_mdata_value(Authority::NaeManager(name), name, tag, key, msg_id),
+        )
+    }
+
+    /// Updates MutableData entries in bulk.
+    pub fn mutate_mdata_entries(
+        &self,
+        dst: Authority<XorName>,
+        name: XorName,
+        tag: u64,
+        actions: BTreeMap<Vec<u8>, EntryAction>,
+        requester: PublicSignKey,
+    ) -> ResponseFuture<()> {
+        let msg_id = MessageId::new();
+        self.request(
+            msg_id,
+            |response| match response {
+                Response::MutateMDataEntries { res, .. } => Some(res),
+                _ => None,
+            },
+            |client| client.mutate_mdata_entries(dst, name, tag, actions, msg_id, requester),
+        )
+    }
+
+    /// Calls `f` with the underlying client, e.g. to send requests without awaiting their
+    /// responses. Responses to those are delivered as events.
+    pub fn with_inner<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut super::Client) -> R,
+    {
+        f(&mut unwrap!(self.inner.lock()))
+    }
+
+    // Registers the request before sending it, so its response can't arrive unnoticed.
+    fn request<T, F>(
+        &self,
+        msg_id: MessageId,
+        extract: fn(Response) -> Option<Result<T, ClientError>>,
+        send: F,
+    ) -> ResponseFuture<T>
+    where
+        F: FnOnce(&mut super::Client) -> Result<(), InterfaceError>,
+    {
+        self.pending.insert(msg_id, Instant::now() + self.timeout);
+        let error = self.with_inner(send).err().map(Error::Interface);
+        ResponseFuture {
+            msg_id,
+            pending: self.pending.clone(),
+            extract,
+            error,
+        }
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        // Requests still awaited can't be answered any more.
+        self.pending.terminate();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        task::Wake,
+        thread::{self as std_thread, Thread},
+    };
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn poll_once<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+        let waker = Waker::from(Arc::new(ThreadWaker(std_thread::current())));
+        Pin::new(future).poll(&mut Context::from_waker(&waker))
+    }
+
+    fn future<T>(
+        pending: &Pending,
+        extract: fn(Response) -> Option<Result<T, ClientError>>,
+    ) -> ResponseFuture<T> {
+        let msg_id = MessageId::new();
+        pending.insert(msg_id, Instant::now() + Duration::from_secs(60));
+        ResponseFuture {
+            msg_id,
+            pending: pending.clone(),
+            extract,
+            error: None,
+        }
+    }
+
+    fn get_idata(response: Response) -> Option<Result<ImmutableData, ClientError>> {
+        match response {
+            Response::GetIData { res, .. } => Some(res),
+            _ => None,
+        }
+    }
+
+    fn received(response: Response) -> Event {
+        Event::ResponseReceived {
+            response,
+            src: Authority::NaeManager(rand::random()),
+            dst: Authority::NaeManager(rand::random()),
+        }
+    }
+
+    #[test]
+    fn responses_resolve_their_requests() {
+        let (events_tx, events_rx) = mpsc::channel();
+        let pending = Pending::default();
+        let dispatcher = Dispatcher {
+            pending: pending.clone(),
+            events: events_tx,
+        };
+
+        let data = ImmutableData::new(b"content".to_vec());
+        let mut success = future(&pending, get_idata);
+        let mut failure = future(&pending, get_idata);
+        assert!(poll_once(&mut success).is_pending());
+
+        unwrap!(dispatcher.deliver(received(Response::GetIData {
+            res: Ok(data.clone()),
+            msg_id: *success.message_id(),
+        })));
+        unwrap!(dispatcher.deliver(received(Response::PutIData {
+            res: Err(ClientError::NoSuchData),
+            msg_id: *failure.message_id(),
+        })));
+        match poll_once(&mut success) {
+            Poll::Ready(Ok(ref received)) if *received == data => (),
+            _ => panic!("Request not resolved"),
+        }
+        match poll_once(&mut failure) {
+            Poll::Ready(Err(Error::UnexpectedResponse)) => (),
+            _ => panic!("Unexpected response accepted"),
+        }
+
+        // Responses nobody waits for are passed on.
+        unwrap!(dispatcher.deliver(received(Response::GetIData {
+            res: Ok(data),
+            msg_id: MessageId::new(),
+        })));
+        unwrap!(dispatcher.deliver(Event::Connected));
+        assert_eq!(events_rx.try_iter().count(), 2);
+    }
+
+    #[test]
+    fn timeout_cancellation_and_termination() {
+        let (events_tx, events_rx) = mpsc::channel();
+        let pending = Pending::default();
+        let dispatcher = Dispatcher {
+            pending: pending.clone(),
+            events: events_tx,
+        };
+
+        let mut timed_out = future(&pending, get_idata).timeout(Duration::from_secs(0));
+        let mut terminated = future(&pending, get_idata);
+        pending.expire(Instant::now());
+        match poll_once(&mut timed_out) {
+            Poll::Ready(Err(Error::TimedOut)) => (),
+            _ => panic!("Request didn't time out"),
+        }
+        assert!(poll_once(&mut terminated).is_pending());
+
+        // A response to a cancelled request is passed on.
+        let cancelled = future(&pending, get_idata);
+        let msg_id = *cancelled.message_id();
+        drop(cancelled);
+        unwrap!(dispatcher.deliver(received(Response::GetIData {
+            res: Err(ClientError::NoSuchData),
+            msg_id,
+        })));
+        assert_eq!(events_rx.try_iter().count(), 1);
+
+        let mut failed = future(&pending, get_idata);
+        unwrap!(dispatcher.deliver(Event::RequestFailed {
+            dst: Authority::NaeManager(rand::random()),
+            msg_id: *failed.message_id(),
+        }));
+        match poll_once(&mut failed) {
+            Poll::Ready(Err(Error::RequestFailed)) => (),
+            _ => panic!("Request not failed"),
+        }
+
+        unwrap!(dispatcher.deliver(Event::Terminated));
+        match poll_once(&mut terminated) {
+            Poll::Ready(Err(Error::Terminated)) => (),
+            _ => panic!("Request not terminated"),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{network_params::Timeouts, time::Duration};
+
+/// Default time to live of the messages a client sends.
+pub const DEFAULT_MSG_EXPIRY_DUR: Duration = Duration::from_secs(60);
+
+/// How a client retries requests which couldn't be routed or weren't answered in time.
+///
+/// A retry is sent with the same `MessageId` as the original request, so vaults can recognise it
+/// as a duplicate, and only the first response to any of the attempts is delivered. GET requests
+/// are always safe to repeat; mutations are only retried if `retry_mutations` is set, as that
+/// relies on vaults ignoring a mutation they have already applied.
+///
+/// Nodes drop messages identical to ones they have already relayed, so a retry only gets through
+/// where the previous attempt was lost.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct RetryPolicy {
+    /// Maximum number of times a request is sent, including the first attempt.
+    pub max_attempts: u32,
+    /// How long to wait for a response before the attempt is considered failed.
+    pub response_timeout: Duration,
+    /// How long to wait before the first retry. Each further retry waits twice as long as the
+    /// previous one.
+    pub initial_backoff: Duration,
+    /// Upper bound on the wait before a retry.
+    pub max_backoff: Duration,
+    /// Whether to also retry requests which aren't GETs.
+    pub retry_mutations: bool,
+}
+
+impl RetryPolicy {
+    /// Returns the number of attempts allowed for a request, depending on whether it is a GET.
+    pub fn max_attempts(&self, is_get: bool) -> u32 {
+        if is_get || self.retry_mutations {
+            self.max_attempts.max(1)
+        } else {
+            1
+        }
+    }
+
+    /// Returns how long to wait before sending the given attempt, counting from 1 for the first
+    /// one.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let doublings = attempt.saturating_sub(2).min(31);
+        self.initial_backoff
+            .checked_mul(1 << doublings)
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            response_timeout: Duration::from_secs(30),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+            retry_mutations: false,
+        }
+    }
+}
+
+/// Configuration of a `Client`, created with `ClientConfig::builder`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClientConfig {
+    msg_expiry_dur: Duration,
+    retry_policy: Option<RetryPolicy>,
+    timeouts: Timeouts,
+}
+
+impl ClientConfig {
+    /// Creates a builder starting from the default configuration: messages expire after
+    /// `DEFAULT_MSG_EXPIRY_DUR`, requests aren't retried and the timeouts are the defaults.
+    pub fn builder() -> ClientConfigBuilder {
+        ClientConfigBuilder {
+            config: Self::default(),
+        }
+    }
+
+    /// Returns the time to live of the messages the client sends.
+    pub fn msg_expiry_dur(&self) -> Duration {
+        self.msg_expiry_dur
+    }
+
+    /// Returns the policy for retrying requests, if they are retried.
+    pub fn retry_policy(&self) -> Option<&RetryPolicy> {
+        self.retry_policy.as_ref()
+    }
+
+    /// Returns how long the client waits for its peers.
+    pub fn timeouts(&self) -> &Timeouts {
+        &self.timeouts
+    }
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            msg_expiry_dur: DEFAULT_MSG_EXPIRY_DUR,
+            retry_policy: None,
+            timeouts: Timeouts::default(),
+        }
+    }
+}
+
+/// A builder to configure a `Client`.
+pub struct ClientConfigBuilder {
+    config: ClientConfig,
+}
+
+impl ClientConfigBuilder {
+    /// Sets the time to live of the messages the client sends.
+    pub fn msg_expiry_dur(self, msg_expiry_dur: Duration) -> ClientConfigBuilder {
+        ClientConfigBuilder {
+            config: ClientConfig {
+                msg_expiry_dur,
+                ..self.config
+            },
+        }
+    }
+
+    /// Makes the client retry requests according to `policy`. Requests which fail even after all
+    /// attempts are reported by `Event::RequestFailed`.
+    pub fn retry_policy(self, policy: RetryPolicy) -> ClientConfigBuilder {
+        ClientConfigBuilder {
+            config: ClientConfig {
+                retry_policy: Some(policy),
+                ..self.config
+            },
+        }
+    }
+
+    /// Sets how long the client waits for its peers. Only the bootstrap and send ack timeouts
+    /// apply to clients. Creating the client fails with `RoutingError::InvalidNetworkParams` if they aren't
+    /// valid.
+    pub fn timeouts(self, timeouts: Timeouts) -> ClientConfigBuilder {
+        ClientConfigBuilder {
+            config: ClientConfig {
+                timeouts,
+                ..self.config
+            },
+        }
+    }
+
+    /// Returns the configuration.
+    pub fn build(self) -> ClientConfig {
+        self.config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+            ..RetryPolicy::default()
+        };
+        let backoffs: Vec<_> = (2..6)
+            .map(|attempt| policy.backoff(attempt).as_secs())
+            .collect();
+        assert_eq!(backoffs, vec![1, 2, 4, 5]);
+        assert_eq!(policy.backoff(100), Duration::from_secs(5));
+
+        assert_eq!(policy.max_attempts(true), 4);
+        assert_eq!(policy.max_attempts(false), 1);
+    }
+}
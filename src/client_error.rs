@@ -52,6 +52,8 @@ pub enum ClientError {
     /// Network error occurring at Vault level which has no bearing on clients, e.g. serialisation
     /// failure or database failure
     NetworkOther(String),
+    /// The client sent more requests than its proxy accepts; it should retry later
+    RateLimitExceeded,
 }
 
 impl<T: Into<String>> From<T> for ClientError {
@@ -87,6 +89,7 @@ impl Display for ClientError {
             ClientError::LowBalance => write!(f, "Insufficient account balance for this operation"),
             ClientError::NetworkFull => write!(f, "Network cannot store any further data"),
             ClientError::NetworkOther(ref error) => write!(f, "Error on Vault network: {}", error),
+            ClientError::RateLimitExceeded => write!(f, "Too many requests sent through the proxy"),
         }
     }
 }
@@ -112,6 +115,7 @@ impl Error for ClientError {
             ClientError::LowBalance => "Low account balance",
             ClientError::NetworkFull => "Network full",
             ClientError::NetworkOther(ref error) => error,
+            ClientError::RateLimitExceeded => "Rate limit exceeded",
         }
     }
 }
@@ -125,6 +129,9 @@ pub enum EntryError {
     EntryExists(u64),
     /// Invalid version when updating an entry. Contains the current entry version.
     InvalidSuccessor(u64),
+    /// The action isn't supported by the `MergeMode` of the data, e.g. deleting from a grow-only
+    /// set.
+    InvalidAction,
 }
 
 #[cfg(test)]
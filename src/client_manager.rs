@@ -0,0 +1,188 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    client_error::ClientError,
+    messages::{AccountInfo, Request},
+    routing_table::Prefix,
+    types::MessageId,
+    xor_name::XorName,
+};
+use std::collections::{BTreeMap, HashMap};
+
+/// The number of mutations an account may perform before our section credited or debited it.
+pub const DEFAULT_ACCOUNT_BALANCE: u64 = 1000;
+
+/// The balances of the client accounts managed by our section, as agreed via `AccountCredit` and
+/// `AccountDebit` events.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Accounts {
+    accounts: BTreeMap<XorName, AccountInfo>,
+}
+
+impl Accounts {
+    /// Returns the mutations the account `name` performed and may still perform.
+    pub fn get(&self, name: &XorName) -> AccountInfo {
+        self.accounts
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| AccountInfo {
+                mutations_done: 0,
+                mutations_available: DEFAULT_ACCOUNT_BALANCE,
+            })
+    }
+
+    /// Allows the account to perform `amount` more mutations.
+    pub(crate) fn credit(&mut self, name: XorName, amount: u64) {
+        let account = self.get(&name);
+        let _ = self.accounts.insert(
+            name,
+            AccountInfo {
+                mutations_done: account.mutations_done,
+                mutations_available: account.mutations_available.saturating_add(amount),
+            },
+        );
+    }
+
+    /// Records that the account performed `amount` mutations. Our section only agrees to debits
+    /// it charged against the balance, but they may still overdraw it if it was credited
+    /// concurrently, so the balance bottoms out at zero.
+    pub(crate) fn debit(&mut self, name: XorName, amount: u64) {
+        let account = self.get(&name);
+        let _ = self.accounts.insert(
+            name,
+            AccountInfo {
+                mutations_done: account.mutations_done.saturating_add(amount),
+                mutations_available: account.mutations_available.saturating_sub(amount),
+            },
+        );
+    }
+
+    /// Drops the accounts which aren't managed by the section with `prefix`, e.g. after a split.
+    pub(crate) fn retain_matching(&mut self, prefix: &Prefix<XorName>) {
+        self.accounts.retain(|name, _| prefix.matches(name));
+    }
+}
+
+/// Charges the mutation requests of the clients whose accounts our section manages.
+///
+/// A request is charged against the agreed balance less the requests we charged and our section
+/// hasn't agreed to debit yet, so a burst of requests can't overdraw an account while the debits
+/// accumulate.
+pub struct ClientManager {
+    /// The amounts we charged, by account and message ID, until their debit accumulates.
+    pending: HashMap<XorName, HashMap<MessageId, u64>>,
+}
+
+impl ClientManager {
+    pub fn new() -> Self {
+        Self {
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Returns the number of mutations `request` is charged. Only storing new data is charged.
+    pub fn cost(request: &Request) -> u64 {
+        match *request {
+            Request::PutIData { .. } | Request::PutMData { .. } | Request::Sealed { .. } => 1,
+            _ => 0,
+        }
+    }
+
+    /// Charges `amount` for the request `msg_id` to `account`. Returns whether it was newly
+    /// charged, so its debit needs to be voted for, or `ClientError::LowBalance` if the account
+    /// can't afford it.
+    pub fn try_charge(
+        &mut self,
+        accounts: &Accounts,
+        account: XorName,
+        msg_id: MessageId,
+        amount: u64,
+    ) -> Result<bool, ClientError> {
+        let pending = self.pending.entry(account).or_insert_with(HashMap::new);
+        if pending.contains_key(&msg_id) {
+            return Ok(false);
+        }
+
+        let charged: u64 = pending.values().sum();
+        let available = accounts.get(&account).mutations_available;
+        if charged.saturating_add(amount) > available {
+            if pending.is_empty() {
+                let _ = self.pending.remove(&account);
+            }
+            return Err(ClientError::LowBalance);
+        }
+
+        let _ = pending.insert(msg_id, amount);
+        Ok(true)
+    }
+
+    /// Forgets the charge for the request `msg_id` once our section agreed to debit it.
+    pub fn settle(&mut self, account: &XorName, msg_id: &MessageId) {
+        if let Some(pending) = self.pending.get_mut(account) {
+            let _ = pending.remove(msg_id);
+            if pending.is_empty() {
+                let _ = self.pending.remove(account);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn charges_are_limited_by_the_balance() {
+        let mut accounts = Accounts::default();
+        let mut client_manager = ClientManager::new();
+        let account = rand::random();
+        accounts.debit(account, DEFAULT_ACCOUNT_BALANCE - 2);
+
+        let first = MessageId::new();
+        assert_eq!(
+            client_manager.try_charge(&accounts, account, first, 1),
+            Ok(true)
+        );
+        // A request is only charged once.
+        assert_eq!(
+            client_manager.try_charge(&accounts, account, first, 1),
+            Ok(false)
+        );
+        let second = MessageId::new();
+        assert_eq!(
+            client_manager.try_charge(&accounts, account, second, 1),
+            Ok(true)
+        );
+        // The pending charges count against the balance before their debits accumulate.
+        assert_eq!(
+            client_manager.try_charge(&accounts, account, MessageId::new(), 1),
+            Err(ClientError::LowBalance)
+        );
+
+        accounts.debit(account, 1);
+        client_manager.settle(&account, &first);
+        accounts.debit(account, 1);
+        client_manager.settle(&account, &second);
+        assert_eq!(accounts.get(&account).mutations_available, 0);
+        assert_eq!(
+            accounts.get(&account).mutations_done,
+            DEFAULT_ACCOUNT_BALANCE
+        );
+        assert_eq!(
+            client_manager.try_charge(&accounts, account, MessageId::new(), 1),
+            Err(ClientError::LowBalance)
+        );
+
+        accounts.credit(account, 1);
+        assert_eq!(
+            client_manager.try_charge(&accounts, account, MessageId::new(), 1),
+            Ok(true)
+        );
+    }
+}
@@ -0,0 +1,93 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! The codec messages are encoded with on the wire.
+//!
+//! By default this is bincode, via `maidsafe_utilities::serialisation`. With the `cbor` feature,
+//! messages are encoded as CBOR instead, which has a canonical, self-describing form that vault
+//! implementations in other languages can decode without mirroring bincode's layout. All nodes of
+//! a network must be built with the same codec. A schema-based codec such as protobuf would need
+//! its own message definitions mapped to and from ours, so it isn't provided here.
+//!
+//! Only the wire encoding of `Message`s goes through the codec. Signed and hashed payloads are
+//! still serialised with bincode, so signatures don't depend on the codec.
+
+use maidsafe_utilities::serialisation;
+use serde::{de::DeserializeOwned, Serialize};
+use std::error::Error as StdError;
+use std::fmt::{self, Display, Formatter};
+
+/// An encoding of serde types into bytes and back.
+pub trait Codec {
+    /// Encodes `value`.
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, CodecError>;
+    /// Decodes a value of type `T` from `bytes`, which must contain nothing else.
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, CodecError>;
+}
+
+/// The bincode codec of `maidsafe_utilities::serialisation`.
+pub struct Bincode;
+
+impl Codec for Bincode {
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, CodecError> {
+        serialisation::serialise(value).map_err(|error| CodecError::Encode(error.to_string()))
+    }
+
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, CodecError> {
+        serialisation::deserialise(bytes).map_err(|error| CodecError::Decode(error.to_string()))
+    }
+}
+
+/// The CBOR codec, encoding enums as maps from the variant name to the content.
+#[cfg(feature = "cbor")]
+pub struct Cbor;
+
+#[cfg(feature = "cbor")]
+impl Codec for Cbor {
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, CodecError> {
+        serde_cbor::to_vec(value).map_err(|error| CodecError::Encode(error.to_string()))
+    }
+
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, CodecError> {
+        serde_cbor::from_slice(bytes).map_err(|error| CodecError::Decode(error.to_string()))
+    }
+}
+
+/// The codec this crate was built with.
+#[cfg(not(feature = "cbor"))]
+pub type WireCodec = Bincode;
+/// The codec this crate was built with.
+#[cfg(feature = "cbor")]
+pub type WireCodec = Cbor;
+
+/// An error encoding or decoding a value.
+#[derive(Debug)]
+pub enum CodecError {
+    /// The value couldn't be encoded.
+    Encode(String),
+    /// The bytes aren't an encoding of a value of the expected type.
+    Decode(String),
+}
+
+impl Display for CodecError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match *self {
+            CodecError::Encode(ref error) => write!(formatter, "Encoding failed: {}", error),
+            CodecError::Decode(ref error) => write!(formatter, "Decoding failed: {}", error),
+        }
+    }
+}
+
+impl StdError for CodecError {
+    fn description(&self) -> &str {
+        match *self {
+            CodecError::Encode(_) => "Encoding failed",
+            CodecError::Decode(_) => "Decoding failed",
+        }
+    }
+}
@@ -10,12 +10,12 @@
 //! concern itself with. Ideally this could be moved to a crate which vaults and clients use,
 //! later.
 
-/// Account packet that is used to provide an invitation code for registration.
-/// After successful registration it should be replaced with `AccountPacket::AccPkt`
+/// Content of an `AccountPacket` that is used to provide an invitation code for registration.
+/// After successful registration it should be replaced with `AccountContent::AccPkt`
 /// with the contents of `account_ciphertext` as soon as possible to prevent an
 /// invitation code leak.
 #[derive(Serialize, Deserialize)]
-pub enum AccountPacket {
+pub enum AccountContent {
     /// Account data with an invitation code that is used for registration.
     WithInvitation {
         /// Invitation code.
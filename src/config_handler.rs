@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::error::Result;
+use crate::{chain::StabilityGuard, error::Result, rate_limiter::RateLimits};
 use config_file_handler::{self, FileHandler};
 
 /// Configuration for routing
@@ -14,6 +14,8 @@ use config_file_handler::{self, FileHandler};
 pub struct Config {
     /// Developer options
     pub dev: Option<DevConfig>,
+    /// Caches this many of the immutable data chunks we relay, to serve later requests for them
+    pub relay_cache_size: Option<usize>,
 }
 
 /// Extra configuration options intended for developers
@@ -27,6 +29,20 @@ pub struct DevConfig {
     pub disable_resource_proof: bool,
     /// Overrides default `MIN_SECTION_SIZE`
     pub min_section_size: Option<usize>,
+    /// Enables the guard against quorum capture by nodes which joined recently
+    pub stability_guard: Option<StabilityGuard>,
+    /// Overrides how long, in seconds, votes may wait to accumulate before they are dropped
+    pub vote_ttl_secs: Option<u64>,
+    /// Overrides the misbehaviour score at which a peer is voted offline
+    pub misbehaviour_threshold: Option<u64>,
+    /// Relocates the oldest member of a section after this many churn events, if set
+    pub relocation_interval: Option<u64>,
+    /// Makes younger nodes join sections as infants, outside the section info, if set
+    pub adult_age: Option<u8>,
+    /// Overrides the limits on the requests each client may send through us as its proxy
+    pub client_rate_limits: Option<RateLimits>,
+    /// Overrides how many bytes are used to remember incoming messages to filter duplicates
+    pub message_filter_memory: Option<usize>,
 }
 
 /// Reads the routing config file and returns it or a default if this fails
@@ -0,0 +1,41 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use std::net::SocketAddr;
+
+/// An inbound connection a `ConnectionPolicy` decides on.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct InboundConnection {
+    /// The address the peer connects from.
+    pub peer_addr: SocketAddr,
+    /// Whether the peer connects as a client rather than as a node.
+    pub is_client: bool,
+    /// The number of connections we already have, established or waiting for the peer to identify
+    /// itself.
+    pub current_connections: usize,
+}
+
+/// Decides whether to accept inbound connections, before the peer gets to send us anything.
+///
+/// Implementations can filter by IP, limit the connection rate or the load, or keep deny-lists, to
+/// mitigate denial of service attacks. Connections to peers we already know about, e.g. those we
+/// initiated ourselves, aren't subject to the policy.
+pub trait ConnectionPolicy {
+    /// Returns whether to accept `connection`. Rejected connections are closed right away.
+    fn accept(&mut self, connection: &InboundConnection) -> bool;
+}
+
+/// The default `ConnectionPolicy`, accepting all connections.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AcceptAll;
+
+impl ConnectionPolicy for AcceptAll {
+    fn accept(&mut self, _connection: &InboundConnection) -> bool {
+        true
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Protocol constants, and getters for the values derived from them and the active `Config`.
+//!
+//! Crates built on routing should use these instead of keeping their own copies.
+
+use crate::config_handler::Config;
+use crate::time::Duration;
+
+pub use crate::data::{
+    MAX_IMMUTABLE_DATA_SIZE_IN_BYTES, MAX_MUTABLE_DATA_ENTRIES, MAX_MUTABLE_DATA_SIZE_IN_BYTES,
+};
+pub use crate::messaging::{MAX_BODY_SIZE, MAX_HEADER_METADATA_SIZE};
+
+/// Quorum is defined as having strictly greater than `QUORUM_NUMERATOR / QUORUM_DENOMINATOR`
+/// agreement; using only integer arithmetic a quorum can be checked with
+/// `votes * QUORUM_DENOMINATOR > voters * QUORUM_NUMERATOR`.
+///
+/// This is the default quorum, which a network can override through its `NetworkParams`.
+pub const QUORUM_NUMERATOR: usize = 2;
+/// See `QUORUM_NUMERATOR`.
+pub const QUORUM_DENOMINATOR: usize = 3;
+
+/// Default minimal section size.
+pub const MIN_SECTION_SIZE: usize = 3;
+
+/// Amount added to `min_section_size` when deciding whether a section split can happen. This
+/// helps protect against rapid splitting and merging in the face of moderate churn.
+pub const SPLIT_BUFFER: usize = 1;
+
+/// How often a client renews its `MutableData` watches. After churn, the renewals reach the new
+/// members of the managing section.
+pub const MDATA_WATCH_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Time after which vaults should drop a `MutableData` watch which wasn't renewed.
+pub const MDATA_WATCH_EXPIRY: Duration = Duration::from_secs(180);
+
+/// Time (in seconds) between accepting a new candidate (i.e. accumulating an `ExpectCandidate` in
+/// our section) and sending a `CandidateApproval` for this candidate. If the candidate cannot
+/// satisfy the proof of resource challenge within this time, no `CandidateApproval` is sent.
+pub const RESOURCE_PROOF_DURATION: Duration = Duration::from_secs(300);
+
+/// Time (in seconds) within which a message and a quorum of signatures need to arrive to
+/// accumulate.
+pub const ACCUMULATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default maximum time a new node will wait to receive `NodeApproval` after receiving a
+/// `RelocateResponse`. This covers the built-in delay of the process and also allows time for the
+/// message to accumulate and be sent via four different routes.
+pub(crate) const APPROVAL_TIMEOUT: Duration =
+    Duration::from_secs(RESOURCE_PROOF_DURATION.as_secs() + 2 * ACCUMULATION_TIMEOUT.as_secs());
+
+/// Default duration after which a candidate is considered as expired.
+/// Using a larger timeout to allow Online to accumulate via gossip.
+pub(crate) const CANDIDATE_EXPIRED_TIMEOUT: Duration =
+    Duration::from_secs(RESOURCE_PROOF_DURATION.as_secs() + 90);
+
+/// How long a candidate may wait in our section's queue by default. It gives up waiting for our
+/// `RelocateResponse` soon after, so we deny it explicitly instead, telling it when to retry.
+pub(crate) const QUEUED_CANDIDATE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Returns the minimal section size used with the given config: the `dev.min_section_size`
+/// override if set, otherwise `MIN_SECTION_SIZE`.
+pub fn min_section_size(config: &Config) -> usize {
+    config
+        .dev
+        .and_then(|dev| dev.min_section_size)
+        .unwrap_or(MIN_SECTION_SIZE)
+}
+
+/// Returns the number of nodes which need to exist in each half of a section for it to split,
+/// with the given config.
+pub fn min_split_size(config: &Config) -> usize {
+    min_section_size(config) + SPLIT_BUFFER
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_handler::DevConfig;
+
+    #[test]
+    fn getters_follow_config() {
+        let mut config = Config::default();
+        assert_eq!(min_section_size(&config), MIN_SECTION_SIZE);
+        assert_eq!(min_split_size(&config), MIN_SECTION_SIZE + SPLIT_BUFFER);
+
+        config.dev = Some(DevConfig {
+            min_section_size: Some(8),
+            ..DevConfig::default()
+        });
+        assert_eq!(min_section_size(&config), 8);
+        assert_eq!(min_split_size(&config), 8 + SPLIT_BUFFER);
+    }
+}
@@ -0,0 +1,274 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+#[cfg(feature = "node")]
+use crate::chain::Chain;
+use crate::event::Event;
+use log::{Log, LogMetadata, LogRecord};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::{self, Display, Write};
+use std::fs;
+use std::panic;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+/// Number of recent log lines kept for the crash file.
+const LOG_CAPACITY: usize = 256;
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+static LOG_WRAPPED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static STATE: RefCell<CrashState> = RefCell::new(CrashState::default());
+}
+
+/// What we know about the routing state of the current thread, for writing to the crash file.
+#[derive(Default)]
+struct CrashState {
+    chain_snapshot: Option<String>,
+    logs: VecDeque<String>,
+    event_counts: BTreeMap<String, u64>,
+    queued_events: usize,
+}
+
+/// Installs a panic hook which, before unwinding, writes a redacted snapshot of the chain, the
+/// most recent routing log lines and event queue statistics of the panicking thread to the file at
+/// `path`. The previously installed hook is run afterwards, so backtraces are still printed.
+///
+/// Until this is called, routing doesn't record any of this information. Routing's log lines are
+/// only recorded if the application's logger is wrapped in a `CrashLog`.
+pub fn install_panic_hook<P: Into<PathBuf>>(path: P) {
+    let path = path.into();
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let dump = crash_dump(info);
+        if let Err(error) = fs::write(&path, dump) {
+            eprintln!(
+                "Failed to write crash dump to {}: {}",
+                path.display(),
+                error
+            );
+        }
+        previous_hook(info);
+    }));
+    INSTALLED.store(true, Ordering::SeqCst);
+}
+
+/// Returns whether the panic hook is installed, i.e. whether the state should be recorded.
+pub fn is_enabled() -> bool {
+    INSTALLED.load(Ordering::Relaxed)
+}
+
+/// Records the current state of `chain` for the crash file.
+#[cfg(feature = "node")]
+pub fn record_chain(chain: &Chain) {
+    if is_enabled() {
+        let snapshot = chain.redacted_snapshot();
+        with_state(|state| state.chain_snapshot = Some(snapshot));
+    }
+}
+
+/// Adds a line to the log ring buffer of the crash file.
+pub fn record_log(line: String) {
+    if is_enabled() {
+        with_state(|state| {
+            if state.logs.len() == LOG_CAPACITY {
+                let _ = state.logs.pop_front();
+            }
+            state.logs.push_back(line);
+        });
+    }
+}
+
+/// Adds the line formatted from `args` to the log ring buffer. Used by `log_or_panic!`, so the
+/// line is only formatted if the panic hook is installed, and not at all if a `CrashLog` records it
+/// when it is logged.
+#[doc(hidden)]
+pub fn record_log_args(args: fmt::Arguments) {
+    if is_enabled() && !LOG_WRAPPED.load(Ordering::Relaxed) {
+        record_log(args.to_string());
+    }
+}
+
+/// A logger which adds routing's log lines to the log ring buffer of the crash file, and passes
+/// all records on to the logger it wraps.
+///
+/// To have them in the crash file, install it in place of the application's logger. Lines below
+/// the maximum log level are never logged, so they aren't recorded either.
+///
+/// ```no_run
+/// use log::{Log, LogLevelFilter, LogMetadata, LogRecord};
+/// use routing::CrashLog;
+///
+/// struct Stderr;
+///
+/// impl Log for Stderr {
+///     fn enabled(&self, metadata: &LogMetadata) -> bool {
+///         metadata.level() <= LogLevelFilter::Info
+///     }
+///
+///     fn log(&self, record: &LogRecord) {
+///         eprintln!("{} {}", record.level(), record.args());
+///     }
+/// }
+///
+/// routing::install_panic_hook("routing_crash.txt");
+/// log::set_logger(|max_level| {
+///     max_level.set(LogLevelFilter::Debug);
+///     Box::new(CrashLog::new(Stderr))
+/// })
+/// .unwrap();
+/// ```
+pub struct CrashLog<L> {
+    inner: L,
+}
+
+impl<L: Log> CrashLog<L> {
+    /// Wraps `inner`, which keeps receiving the records it is enabled for.
+    pub fn new(inner: L) -> Self {
+        LOG_WRAPPED.store(true, Ordering::SeqCst);
+        Self { inner }
+    }
+}
+
+impl<L: Log> Log for CrashLog<L> {
+    fn enabled(&self, metadata: &LogMetadata) -> bool {
+        self.inner.enabled(metadata) || (is_enabled() && is_routing_target(metadata.target()))
+    }
+
+    fn log(&self, record: &LogRecord) {
+        if is_enabled() && is_routing_target(record.target()) {
+            record_log(format!("{:<5} {}", record.level(), record.args()));
+        }
+        if self.inner.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+    }
+}
+
+fn is_routing_target(target: &str) -> bool {
+    target == "routing" || target.starts_with("routing::")
+}
+
+/// Records that `event` was queued for the user, with `queued` events now waiting to be taken.
+pub fn record_event(event: &Event, queued: usize) {
+    if is_enabled() {
+        let debug = format!("{:?}", event);
+        let name = debug
+            .split(|c: char| c == '(' || c == ' ')
+            .next()
+            .unwrap_or("")
+            .to_string();
+        with_state(|state| {
+            *state.event_counts.entry(name).or_insert(0) += 1;
+            state.queued_events = queued;
+        });
+    }
+}
+
+fn with_state<F: FnOnce(&mut CrashState)>(f: F) {
+    // Never panic from here, as this may run while we're already panicking.
+    let _ = STATE.try_with(|state| {
+        if let Ok(mut state) = state.try_borrow_mut() {
+            f(&mut state)
+        }
+    });
+}
+
+fn crash_dump(panic: &dyn Display) -> String {
+    let mut dump = String::new();
+    let _ = writeln!(dump, "Routing crash dump");
+    let _ = writeln!(
+        dump,
+        "Thread: {}",
+        thread::current().name().unwrap_or("<unnamed>")
+    );
+    let _ = writeln!(dump, "Panic: {}", panic);
+
+    let _ = STATE.try_with(|state| {
+        let state = match state.try_borrow() {
+            Ok(state) => state,
+            Err(_) => {
+                let _ = writeln!(dump, "\nRouting state unavailable.");
+                return;
+            }
+        };
+
+        let _ = writeln!(dump, "\n== Chain ==");
+        let _ = writeln!(
+            dump,
+            "{}",
+            state
+                .chain_snapshot
+                .as_ref()
+                .map_or("<no chain recorded>", String::as_str)
+        );
+
+        let _ = writeln!(dump, "\n== Event queue ==");
+        let _ = writeln!(dump, "Queued events: {}", state.queued_events);
+        for (name, count) in &state.event_counts {
+            let _ = writeln!(dump, "{}: {}", name, count);
+        }
+
+        let _ = writeln!(dump, "\n== Recent log ==");
+        for line in &state.logs {
+            let _ = writeln!(dump, "{}", line);
+        }
+    });
+
+    dump
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Records the state while alive, as if the panic hook was installed.
+    struct Installed;
+
+    impl Installed {
+        fn new() -> Self {
+            INSTALLED.store(true, Ordering::SeqCst);
+            Installed
+        }
+    }
+
+    impl Drop for Installed {
+        fn drop(&mut self) {
+            INSTALLED.store(false, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn dump_contains_recorded_state() {
+        let _installed = Installed::new();
+        for i in 0..LOG_CAPACITY + 1 {
+            record_log(format!("line {}", i));
+        }
+        record_event(&Event::TimerTicked, 1);
+        record_event(&Event::TimerTicked, 2);
+
+        let dump = crash_dump(&"test panic");
+        assert!(dump.contains("Panic: test panic"));
+        assert!(dump.contains("<no chain recorded>"));
+        assert!(dump.contains("Queued events: 2"));
+        assert!(dump.contains("Event::TimerTicked: 2"));
+        assert!(!dump.contains("line 0\n"));
+        assert!(dump.contains(&format!("line {}\n", LOG_CAPACITY)));
+    }
+
+    #[test]
+    fn only_routing_targets_are_recorded() {
+        assert!(is_routing_target("routing"));
+        assert!(is_routing_target("routing::states::elder"));
+        assert!(!is_routing_target("routing_vault"));
+        assert!(!is_routing_target("parsec"));
+    }
+}
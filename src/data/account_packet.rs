@@ -0,0 +1,209 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::client_error::ClientError;
+use crate::xor_name::XorName;
+use hex_fmt::HexFmt;
+use maidsafe_utilities::serialisation;
+use safe_crypto::{PublicSignKey, SecretSignKey, Signature};
+use std::fmt::{self, Debug, Formatter};
+
+/// Maximum allowed size for a serialised Account Packet.
+pub const MAX_ACCOUNT_PACKET_SIZE_IN_BYTES: u64 = 64 * 1024;
+
+/// The encrypted login state of a user, stored under a name only the user's credentials lead to.
+///
+/// Every write replaces the whole packet with one signed by the same owner, with the version
+/// bumped by one. The first write has version 0. Data managers check writes with
+/// `validate_successor`.
+#[derive(Hash, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct AccountPacket {
+    /// Network address, derived from the credentials
+    name: XorName,
+    /// Incremented on every write
+    version: u64,
+    /// The login state, encrypted by the user
+    content: Vec<u8>,
+    /// The key allowed to write the packet
+    owner: PublicSignKey,
+    /// The owner's signature of the name, version and content
+    signature: Signature,
+}
+
+impl AccountPacket {
+    /// Returns the name of the account packet of the user with the given credentials.
+    ///
+    /// Both the locator and the password go into the name, so knowing a user's locator isn't
+    /// enough to find their packet.
+    pub fn name_from_credentials(locator: &[u8], password: &[u8]) -> XorName {
+        let mut combined = Vec::with_capacity(8 + locator.len() + password.len());
+        combined.extend_from_slice(&(locator.len() as u64).to_be_bytes());
+        combined.extend_from_slice(locator);
+        combined.extend_from_slice(password);
+        XorName(safe_crypto::hash(&combined))
+    }
+
+    /// Creates the packet with the given `version`, signed with the owner's key pair.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        name: XorName,
+        version: u64,
+        content: Vec<u8>,
+        owner: PublicSignKey,
+        secret_key: &SecretSignKey,
+    ) -> Result<AccountPacket, ClientError> {
+        let signature = secret_key.sign_detached(&signed_bytes(&name, version, &content));
+        let packet = AccountPacket {
+            name: name,
+            version: version,
+            content: content,
+            owner: owner,
+            signature: signature,
+        };
+
+        packet.validate()?;
+        Ok(packet)
+    }
+
+    /// Creates the packet replacing this one with the given content.
+    pub fn successor(
+        &self,
+        content: Vec<u8>,
+        secret_key: &SecretSignKey,
+    ) -> Result<AccountPacket, ClientError> {
+        AccountPacket::new(self.name, self.version + 1, content, self.owner, secret_key)
+    }
+
+    /// Validate this data.
+    pub fn validate(&self) -> Result<(), ClientError> {
+        if !self.owner.verify_detached(
+            &self.signature,
+            &signed_bytes(&self.name, self.version, &self.content),
+        ) {
+            return Err(ClientError::AccessDenied);
+        }
+
+        if !self.validate_size() {
+            return Err(ClientError::DataTooLarge);
+        }
+
+        Ok(())
+    }
+
+    /// Checks that `next` may replace this packet: it has to be valid, have the same name and
+    /// owner, and the next version.
+    pub fn validate_successor(&self, next: &AccountPacket) -> Result<(), ClientError> {
+        next.validate()?;
+        if next.name != self.name || next.owner != self.owner {
+            return Err(ClientError::AccessDenied);
+        }
+        if next.version != self.version + 1 {
+            return Err(ClientError::InvalidSuccessor(self.version));
+        }
+        Ok(())
+    }
+
+    /// Returns the name.
+    pub fn name(&self) -> &XorName {
+        &self.name
+    }
+
+    /// Returns the version.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the encrypted content.
+    pub fn content(&self) -> &[u8] {
+        &self.content
+    }
+
+    /// Returns the owner key.
+    pub fn owner(&self) -> &PublicSignKey {
+        &self.owner
+    }
+
+    /// Return the size of this data after serialisation.
+    pub fn serialised_size(&self) -> u64 {
+        serialisation::serialised_size(self)
+    }
+
+    /// Return true if the size is valid
+    pub fn validate_size(&self) -> bool {
+        self.serialised_size() <= MAX_ACCOUNT_PACKET_SIZE_IN_BYTES
+    }
+}
+
+impl Debug for AccountPacket {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "AccountPacket {{ name: {}, version: {}, content: {:.14} }}",
+            self.name,
+            self.version,
+            HexFmt(&self.content)
+        )
+    }
+}
+
+fn signed_bytes(name: &XorName, version: u64, content: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(name.0.len() + 8 + content.len());
+    bytes.extend_from_slice(&name.0);
+    bytes.extend_from_slice(&version.to_be_bytes());
+    bytes.extend_from_slice(content);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use safe_crypto::gen_sign_keypair;
+    use unwrap::unwrap;
+
+    #[test]
+    fn successors() {
+        let (owner, owner_sk) = gen_sign_keypair();
+        let (other, other_sk) = gen_sign_keypair();
+        let name = AccountPacket::name_from_credentials(b"locator", b"password");
+        assert_ne!(
+            name,
+            AccountPacket::name_from_credentials(b"locatorpass", b"word")
+        );
+
+        let first = unwrap!(AccountPacket::new(name, 0, vec![1], owner, &owner_sk));
+        let second = unwrap!(first.successor(vec![2], &owner_sk));
+        assert_eq!(second.version(), 1);
+        unwrap!(first.validate_successor(&second));
+
+        // The version has to be bumped by exactly one...
+        match second.validate_successor(&first) {
+            Err(ClientError::InvalidSuccessor(1)) => (),
+            x => panic!("Unexpected {:?}", x),
+        }
+        // ...and only the owner can write.
+        let forged = unwrap!(AccountPacket::new(name, 1, vec![3], other, &other_sk));
+        match first.validate_successor(&forged) {
+            Err(ClientError::AccessDenied) => (),
+            x => panic!("Unexpected {:?}", x),
+        }
+        match AccountPacket::new(name, 1, vec![3], owner, &other_sk) {
+            Err(ClientError::AccessDenied) => (),
+            x => panic!("Unexpected {:?}", x),
+        }
+    }
+
+    #[test]
+    fn max_size() {
+        let (owner, owner_sk) = gen_sign_keypair();
+        let content = vec![0; MAX_ACCOUNT_PACKET_SIZE_IN_BYTES as usize];
+        match AccountPacket::new(rand::random(), 0, content, owner, &owner_sk) {
+            Err(ClientError::DataTooLarge) => (),
+            x => panic!("Unexpected {:?}", x),
+        }
+    }
+}
@@ -0,0 +1,315 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::client_error::ClientError;
+use crate::xor_name::XorName;
+use hex_fmt::HexFmt;
+use maidsafe_utilities::serialisation;
+use safe_crypto::{PublicSignKey, SecretSignKey, Signature};
+use std::collections::BTreeSet;
+use std::fmt::{self, Debug, Formatter};
+
+/// Maximum allowed size for a serialised Append Only Data (AD) to grow to
+pub const MAX_APPEND_ONLY_DATA_SIZE_IN_BYTES: u64 = 1024 * 1024;
+
+/// An entry of `AppendOnlyData`, signed by one of its owners.
+///
+/// The signature covers the name and type tag of the data and the version of the entry, so an
+/// entry can't be replayed into another data or at another position.
+#[derive(Clone, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct AppendEntry {
+    content: Vec<u8>,
+    version: u64,
+    signer: PublicSignKey,
+    signature: Signature,
+}
+
+impl AppendEntry {
+    /// Creates the entry with the given `version` of the data with the given `name` and `tag`,
+    /// signed with the owner's key pair.
+    pub fn new(
+        name: XorName,
+        tag: u64,
+        version: u64,
+        content: Vec<u8>,
+        signer: PublicSignKey,
+        secret_key: &SecretSignKey,
+    ) -> Self {
+        let signature = secret_key.sign_detached(&signed_bytes(&name, tag, version, &content));
+        AppendEntry {
+            content: content,
+            version: version,
+            signer: signer,
+            signature: signature,
+        }
+    }
+
+    /// Returns the content.
+    pub fn content(&self) -> &[u8] {
+        &self.content
+    }
+
+    /// Returns the version of the data this entry was appended at, i.e. its index.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the key of the owner who signed this entry.
+    pub fn signer(&self) -> &PublicSignKey {
+        &self.signer
+    }
+
+    /// Returns whether the signature is valid for the data with the given `name` and `tag`.
+    pub fn verify(&self, name: &XorName, tag: u64) -> bool {
+        self.signer.verify_detached(
+            &self.signature,
+            &signed_bytes(name, tag, self.version, &self.content),
+        )
+    }
+}
+
+impl Debug for AppendEntry {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "AppendEntry {{ version: {}, content: {:.14} }}",
+            self.version,
+            HexFmt(&self.content)
+        )
+    }
+}
+
+fn signed_bytes(name: &XorName, tag: u64, version: u64, content: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(name.0.len() + 16 + content.len());
+    bytes.extend_from_slice(&name.0);
+    bytes.extend_from_slice(&tag.to_be_bytes());
+    bytes.extend_from_slice(&version.to_be_bytes());
+    bytes.extend_from_slice(content);
+    bytes
+}
+
+/// A log of entries which can only ever be appended to, by its owner.
+///
+/// Unlike `MutableData`, entries can't be updated or deleted and there are no permissions. The
+/// version of the data is the number of its entries, and each entry has to be appended with the
+/// current version, so concurrent appends can't silently interleave.
+#[derive(Hash, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct AppendOnlyData {
+    /// Network address
+    name: XorName,
+    /// Type tag
+    tag: u64,
+    /// The entries, in the order they were appended
+    entries: Vec<AppendEntry>,
+    /// Contains a set of owners which are allowed to append entries.
+    /// Currently limited to one owner to disallow multisig.
+    owners: BTreeSet<PublicSignKey>,
+}
+
+impl AppendOnlyData {
+    /// Creates a new, empty AppendOnlyData
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        name: XorName,
+        tag: u64,
+        owners: BTreeSet<PublicSignKey>,
+    ) -> Result<AppendOnlyData, ClientError> {
+        let ad = AppendOnlyData {
+            name: name,
+            tag: tag,
+            entries: Vec::new(),
+            owners: owners,
+        };
+
+        ad.validate()?;
+        Ok(ad)
+    }
+
+    /// Validate this data.
+    pub fn validate(&self) -> Result<(), ClientError> {
+        if self.owners.len() > 1 {
+            return Err(ClientError::InvalidOwners);
+        }
+
+        let valid_entries = self.entries.iter().enumerate().all(|(index, entry)| {
+            entry.version == index as u64
+                && self.owners.contains(&entry.signer)
+                && entry.verify(&self.name, self.tag)
+        });
+        if !valid_entries {
+            return Err(ClientError::AccessDenied);
+        }
+
+        if !self.validate_size() {
+            return Err(ClientError::DataTooLarge);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the name.
+    pub fn name(&self) -> &XorName {
+        &self.name
+    }
+
+    /// Returns the type tag of this AppendOnlyData.
+    pub fn tag(&self) -> u64 {
+        self.tag
+    }
+
+    /// Returns the current version, i.e. the number of entries.
+    pub fn version(&self) -> u64 {
+        self.entries.len() as u64
+    }
+
+    /// Returns the owner keys.
+    pub fn owners(&self) -> &BTreeSet<PublicSignKey> {
+        &self.owners
+    }
+
+    /// Returns the entry with the given version.
+    pub fn get(&self, version: u64) -> Option<&AppendEntry> {
+        self.entries.get(version as usize)
+    }
+
+    /// Returns all entries.
+    pub fn entries(&self) -> &[AppendEntry] {
+        &self.entries
+    }
+
+    /// Returns the entries appended at `version` or later.
+    pub fn entries_since(&self, version: u64) -> &[AppendEntry] {
+        let start = (version as usize).min(self.entries.len());
+        &self.entries[start..]
+    }
+
+    /// Appends the given entry, which has to be signed by an owner and have the current version.
+    pub fn append(&mut self, entry: AppendEntry) -> Result<(), ClientError> {
+        if !self.owners.contains(&entry.signer) || !entry.verify(&self.name, self.tag) {
+            return Err(ClientError::AccessDenied);
+        }
+        if entry.version != self.version() {
+            return Err(ClientError::InvalidSuccessor(self.version()));
+        }
+
+        self.entries.push(entry);
+        if !self.validate_size() {
+            let _ = self.entries.pop();
+            return Err(ClientError::DataTooLarge);
+        }
+
+        Ok(())
+    }
+
+    /// Appends the entry without performing any validation. Returns whether it was appended,
+    /// i.e. whether it had the current version.
+    pub fn append_without_validation(&mut self, entry: AppendEntry) -> bool {
+        if entry.version != self.version() {
+            return false;
+        }
+        self.entries.push(entry);
+        true
+    }
+
+    /// Return the size of this data after serialisation.
+    pub fn serialised_size(&self) -> u64 {
+        serialisation::serialised_size(self)
+    }
+
+    /// Return true if the size is valid
+    pub fn validate_size(&self) -> bool {
+        self.serialised_size() <= MAX_APPEND_ONLY_DATA_SIZE_IN_BYTES
+    }
+}
+
+impl Debug for AppendOnlyData {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "AppendOnlyData {{ name: {}, tag: {}, version: {}, owners: {:?} }}",
+            self.name(),
+            self.tag,
+            self.version(),
+            self.owners
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use safe_crypto::gen_sign_keypair;
+    use std::iter;
+    use unwrap::unwrap;
+
+    #[test]
+    fn append() {
+        let (owner, owner_sk) = gen_sign_keypair();
+        let (other, other_sk) = gen_sign_keypair();
+        let name = rand::random();
+        let tag = 10_000;
+        let mut ad = unwrap!(AppendOnlyData::new(name, tag, iter::once(owner).collect()));
+
+        let entry = |version, signer, secret_key| {
+            AppendEntry::new(name, tag, version, b"log line".to_vec(), signer, secret_key)
+        };
+
+        unwrap!(ad.append(entry(0, owner, &owner_sk)));
+        unwrap!(ad.append(entry(1, owner, &owner_sk)));
+        assert_eq!(ad.version(), 2);
+        assert_eq!(unwrap!(ad.get(1)).content(), b"log line");
+        assert_eq!(ad.entries_since(1).len(), 1);
+        assert!(ad.entries_since(5).is_empty());
+
+        // Only owners can append.
+        match ad.append(entry(2, other, &other_sk)) {
+            Err(ClientError::AccessDenied) => (),
+            x => panic!("Unexpected {:?}", x),
+        }
+        // The signature has to match the signer...
+        match ad.append(entry(2, owner, &other_sk)) {
+            Err(ClientError::AccessDenied) => (),
+            x => panic!("Unexpected {:?}", x),
+        }
+        // ...and the data.
+        let wrong_data = AppendEntry::new(rand::random(), tag, 2, vec![], owner, &owner_sk);
+        match ad.append(wrong_data) {
+            Err(ClientError::AccessDenied) => (),
+            x => panic!("Unexpected {:?}", x),
+        }
+        // Entries have to be appended at the current version.
+        match ad.append(entry(1, owner, &owner_sk)) {
+            Err(ClientError::InvalidSuccessor(2)) => (),
+            x => panic!("Unexpected {:?}", x),
+        }
+        assert_eq!(ad.version(), 2);
+        unwrap!(ad.validate());
+    }
+
+    #[test]
+    fn max_size() {
+        let (owner, owner_sk) = gen_sign_keypair();
+        let name = rand::random();
+        let mut ad = unwrap!(AppendOnlyData::new(name, 0, iter::once(owner).collect()));
+
+        let content = vec![0; MAX_APPEND_ONLY_DATA_SIZE_IN_BYTES as usize / 2];
+        unwrap!(ad.append(AppendEntry::new(
+            name,
+            0,
+            0,
+            content.clone(),
+            owner,
+            &owner_sk
+        )));
+        match ad.append(AppendEntry::new(name, 0, 1, content, owner, &owner_sk)) {
+            Err(ClientError::DataTooLarge) => (),
+            x => panic!("Unexpected {:?}", x),
+        }
+        assert_eq!(ad.version(), 1);
+    }
+}
@@ -0,0 +1,251 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::DataId;
+use crate::client_error::ClientError;
+use crate::messages::Request;
+use crate::routing_table::Authority;
+use crate::sha3::Digest256;
+use crate::time::{Duration, Instant};
+use crate::types::MessageId;
+use crate::xor_name::XorName;
+use rand;
+use safe_crypto;
+use std::collections::BTreeMap;
+
+/// Number of failed audits after which the audited authority is considered malicious.
+pub const MAX_FAILED_AUDITS: usize = 3;
+/// How long the audited authority has to answer a challenge before it counts as failed.
+pub const AUDIT_TIMEOUT: Duration = Duration::from_secs(60);
+/// Maximum number of challenges awaiting a response. Issuing another one drops the oldest,
+/// without counting it against its auditee, which may simply not have had time to answer.
+pub const MAX_PENDING_AUDITS: usize = 1024;
+
+/// Returns the proof that the holder of `content` has to send back for the given challenge
+/// `nonce`: the hash of `nonce || content`.
+pub fn storage_proof(nonce: &[u8], content: &[u8]) -> Digest256 {
+    let mut bytes = Vec::with_capacity(nonce.len() + content.len());
+    bytes.extend_from_slice(nonce);
+    bytes.extend_from_slice(content);
+    safe_crypto::hash(&bytes)
+}
+
+/// The result of checking the response to a `ChallengeData` request.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AuditOutcome {
+    /// The audited authority proved it holds the data.
+    Passed(Authority<XorName>),
+    /// The proof was wrong or missing. Contains the audited authority and the number of audits it
+    /// failed so far.
+    Failed(Authority<XorName>, usize),
+}
+
+/// Keeps track of the `ChallengeData` requests we sent and of the audits each authority failed.
+///
+/// Challenges not answered within `AUDIT_TIMEOUT` fail on the next call to `expire`, and at most
+/// `MAX_PENDING_AUDITS` of them are kept. Authorities which failed at least `MAX_FAILED_AUDITS`
+/// audits are returned by `offenders`, and are reported to our section by passing the tracker to
+/// `Node::report_audit_offenders`.
+#[derive(Default)]
+pub struct DataAudit {
+    /// Challenges awaiting a response: the audited authority, the expected proof and when the
+    /// challenge was issued.
+    pending: BTreeMap<MessageId, (Authority<XorName>, Digest256, Instant)>,
+    /// Number of failed audits per audited authority.
+    failures: BTreeMap<Authority<XorName>, usize>,
+}
+
+impl DataAudit {
+    /// Creates a new, empty audit tracker.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Creates a `ChallengeData` request for the chunk `data_id` with the given `content`, to be
+    /// sent to `auditee`. The expected proof is remembered until the response arrives.
+    pub fn challenge(
+        &mut self,
+        auditee: Authority<XorName>,
+        data_id: DataId,
+        content: &[u8],
+    ) -> Request {
+        if self.pending.len() >= MAX_PENDING_AUDITS {
+            let oldest = self
+                .pending
+                .iter()
+                .min_by_key(|&(_, &(_, _, issued))| issued)
+                .map(|(msg_id, _)| *msg_id);
+            if let Some(msg_id) = oldest {
+                let _ = self.pending.remove(&msg_id);
+            }
+        }
+
+        let nonce: [u8; 32] = rand::random();
+        let msg_id = MessageId::new();
+        let _ = self.pending.insert(
+            msg_id,
+            (auditee, storage_proof(&nonce, content), Instant::now()),
+        );
+        Request::ChallengeData {
+            data_id,
+            nonce,
+            msg_id,
+        }
+    }
+
+    /// Checks the proof received in a `ProveData` response. Returns `None` if we didn't send a
+    /// challenge with this `msg_id` or already handled its response.
+    pub fn handle_proof(
+        &mut self,
+        msg_id: &MessageId,
+        proof: Result<Digest256, ClientError>,
+    ) -> Option<AuditOutcome> {
+        let (auditee, expected, _) = self.pending.remove(msg_id)?;
+        if proof.ok() == Some(expected) {
+            Some(AuditOutcome::Passed(auditee))
+        } else {
+            Some(self.fail(auditee))
+        }
+    }
+
+    /// Counts the challenge with the given `msg_id` as failed, e.g. because it was not answered
+    /// in time.
+    pub fn handle_timeout(&mut self, msg_id: &MessageId) -> Option<AuditOutcome> {
+        let (auditee, _, _) = self.pending.remove(msg_id)?;
+        Some(self.fail(auditee))
+    }
+
+    /// Counts every challenge not answered within `AUDIT_TIMEOUT` as failed.
+    pub fn expire(&mut self) -> Vec<AuditOutcome> {
+        let expired: Vec<_> = self
+            .pending
+            .iter()
+            .filter(|&(_, &(_, _, issued))| issued.elapsed() >= AUDIT_TIMEOUT)
+            .map(|(msg_id, _)| *msg_id)
+            .collect();
+        expired
+            .iter()
+            .filter_map(|msg_id| self.handle_timeout(msg_id))
+            .collect()
+    }
+
+    /// Returns the number of challenges awaiting a response.
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns the authorities which failed at least `MAX_FAILED_AUDITS` audits.
+    pub fn offenders(&self) -> impl Iterator<Item = &Authority<XorName>> {
+        self.failures
+            .iter()
+            .filter(|&(_, count)| *count >= MAX_FAILED_AUDITS)
+            .map(|(auditee, _)| auditee)
+    }
+
+    /// Forgets the failed audits of `auditee`, e.g. once it has been dealt with.
+    pub fn forgive(&mut self, auditee: &Authority<XorName>) {
+        let _ = self.failures.remove(auditee);
+    }
+
+    fn fail(&mut self, auditee: Authority<XorName>) -> AuditOutcome {
+        let count = self.failures.entry(auditee).or_insert(0);
+        *count += 1;
+        AuditOutcome::Failed(auditee, *count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fake_clock::FakeClock;
+
+    fn response_proof(request: &Request, content: &[u8]) -> (MessageId, Digest256) {
+        match *request {
+            Request::ChallengeData { nonce, msg_id, .. } => {
+                (msg_id, storage_proof(&nonce, content))
+            }
+            _ => panic!("Unexpected request {:?}", request),
+        }
+    }
+
+    #[test]
+    fn failed_audits_accumulate() {
+        let mut audit = DataAudit::new();
+        let auditee = Authority::NaeManager(rand::random());
+        let content = b"chunk content";
+        let data_id = DataId::Immutable(rand::random());
+
+        let request = audit.challenge(auditee, data_id, content);
+        let (msg_id, proof) = response_proof(&request, content);
+        assert_eq!(
+            audit.handle_proof(&msg_id, Ok(proof)),
+            Some(AuditOutcome::Passed(auditee))
+        );
+        // A response can only be handled once.
+        assert_eq!(audit.handle_proof(&msg_id, Ok(proof)), None);
+
+        for count in 1..MAX_FAILED_AUDITS {
+            let request = audit.challenge(auditee, data_id, content);
+            let (msg_id, wrong_proof) = response_proof(&request, b"other content");
+            assert_eq!(
+                audit.handle_proof(&msg_id, Ok(wrong_proof)),
+                Some(AuditOutcome::Failed(auditee, count))
+            );
+            assert_eq!(audit.offenders().count(), 0);
+        }
+
+        let request = audit.challenge(auditee, data_id, content);
+        let (msg_id, _) = response_proof(&request, content);
+        assert_eq!(
+            audit.handle_timeout(&msg_id),
+            Some(AuditOutcome::Failed(auditee, MAX_FAILED_AUDITS))
+        );
+        assert_eq!(audit.offenders().collect::<Vec<_>>(), vec![&auditee]);
+
+        audit.forgive(&auditee);
+        assert_eq!(audit.offenders().count(), 0);
+    }
+
+    #[test]
+    fn unanswered_challenges_fail() {
+        let mut audit = DataAudit::new();
+        let auditee = Authority::ManagedNode(rand::random());
+        let data_id = DataId::Immutable(rand::random());
+
+        let _ = audit.challenge(auditee, data_id, b"content");
+        assert!(audit.expire().is_empty());
+        FakeClock::advance_time(AUDIT_TIMEOUT.as_secs() * 1000 + 1);
+        assert_eq!(audit.expire(), vec![AuditOutcome::Failed(auditee, 1)]);
+        assert_eq!(audit.pending_count(), 0);
+    }
+
+    #[test]
+    fn challenges_beyond_the_limit_drop_the_oldest() {
+        let mut audit = DataAudit::new();
+        let auditee = Authority::ManagedNode(rand::random());
+        let data_id = DataId::Immutable(rand::random());
+
+        let oldest = audit.challenge(auditee, data_id, b"content");
+        FakeClock::advance_time(1);
+        for _ in 0..MAX_PENDING_AUDITS {
+            let _ = audit.challenge(auditee, data_id, b"content");
+            FakeClock::advance_time(1);
+        }
+        assert_eq!(audit.pending_count(), MAX_PENDING_AUDITS);
+
+        // The dropped challenge doesn't count against the auditee, and its late answer is ignored.
+        let (msg_id, proof) = response_proof(&oldest, b"content");
+        assert_eq!(audit.handle_proof(&msg_id, Ok(proof)), None);
+        assert_eq!(audit.handle_timeout(&msg_id), None);
+        FakeClock::advance_time(AUDIT_TIMEOUT.as_secs() * 1000);
+        let outcomes = audit.expire();
+        assert_eq!(outcomes.len(), MAX_PENDING_AUDITS);
+        assert!(outcomes.contains(&AuditOutcome::Failed(auditee, MAX_PENDING_AUDITS)));
+        assert!(!outcomes.contains(&AuditOutcome::Failed(auditee, MAX_PENDING_AUDITS + 1)));
+    }
+}
@@ -0,0 +1,154 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::Value;
+use crate::client_error::ClientError;
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+/// `MutableData` type tags whose entries are last-writer-wins registers.
+pub const LWW_REGISTER_TAGS: Range<u64> = 15_000..16_000;
+/// `MutableData` type tags whose entries are grow-only sets.
+pub const GROW_ONLY_SET_TAGS: Range<u64> = 16_000..17_000;
+
+/// How concurrent mutations of the entries of a `MutableData` are reconciled. The mode is
+/// determined by the type tag.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum MergeMode {
+    /// Every mutation must carry the entry's current version plus one, so of concurrent mutations
+    /// only the first one applied succeeds.
+    Versioned,
+    /// Every entry is a last-writer-wins register: of two values, the one with the greater
+    /// `entry_version` wins, and ties are broken by comparing the contents. Writers are expected
+    /// to use timestamps as versions.
+    LwwRegister,
+    /// Every entry is a grow-only set of byte strings, created with `grow_only_set_value`.
+    /// Merging two values yields the union of their elements. Entries can't be deleted.
+    GrowOnlySet,
+}
+
+impl MergeMode {
+    /// Returns the merge mode of `MutableData` with the type tag `tag`.
+    pub fn of_tag(tag: u64) -> Self {
+        if LWW_REGISTER_TAGS.start <= tag && tag < LWW_REGISTER_TAGS.end {
+            MergeMode::LwwRegister
+        } else if GROW_ONLY_SET_TAGS.start <= tag && tag < GROW_ONLY_SET_TAGS.end {
+            MergeMode::GrowOnlySet
+        } else {
+            MergeMode::Versioned
+        }
+    }
+
+    /// Merges two values of the same entry. For the conflict-free modes this is commutative,
+    /// associative and idempotent, so replicas receiving the same values in any order converge.
+    ///
+    /// In `Versioned` mode, the value with the greater version wins and `current` is kept on a
+    /// tie. Fails if a grow-only set value is malformed.
+    pub fn merge(self, current: &Value, new: &Value) -> Result<Value, ClientError> {
+        match self {
+            MergeMode::Versioned => {
+                if new.entry_version > current.entry_version {
+                    Ok(new.clone())
+                } else {
+                    Ok(current.clone())
+                }
+            }
+            MergeMode::LwwRegister => {
+                let order = (current.entry_version, &current.content)
+                    .cmp(&(new.entry_version, &new.content));
+                if order == Ordering::Less {
+                    Ok(new.clone())
+                } else {
+                    Ok(current.clone())
+                }
+            }
+            MergeMode::GrowOnlySet => {
+                let mut elements = grow_only_set_elements(current)?;
+                elements.extend(grow_only_set_elements(new)?);
+                grow_only_set_value(&elements)
+            }
+        }
+    }
+}
+
+/// Returns the value of a grow-only set entry holding `elements`. Its version is the number of
+/// elements.
+pub fn grow_only_set_value(elements: &BTreeSet<Vec<u8>>) -> Result<Value, ClientError> {
+    Ok(Value {
+        content: serialise(elements).map_err(|_| ClientError::InvalidOperation)?,
+        entry_version: elements.len() as u64,
+    })
+}
+
+/// Returns the elements of a grow-only set entry.
+pub fn grow_only_set_elements(value: &Value) -> Result<BTreeSet<Vec<u8>>, ClientError> {
+    deserialise(&value.content).map_err(|_| ClientError::InvalidOperation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{self, Rng};
+    use unwrap::unwrap;
+
+    fn random_value<R: Rng>(mode: MergeMode, rng: &mut R) -> Value {
+        match mode {
+            MergeMode::GrowOnlySet => {
+                let elements = (0..rng.gen_range(0, 4))
+                    .map(|_| vec![rng.gen_range(0, 8)])
+                    .collect();
+                unwrap!(grow_only_set_value(&elements))
+            }
+            // Few distinct versions and contents, so ties are frequent.
+            _ => Value {
+                content: vec![rng.gen_range(0, 3)],
+                entry_version: rng.gen_range(0, 3),
+            },
+        }
+    }
+
+    #[test]
+    fn conflict_free_merges_converge() {
+        let mut rng = rand::thread_rng();
+        for &mode in &[MergeMode::LwwRegister, MergeMode::GrowOnlySet] {
+            for _ in 0..1000 {
+                let a = random_value(mode, &mut rng);
+                let b = random_value(mode, &mut rng);
+                let c = random_value(mode, &mut rng);
+                let merge = |x: &Value, y: &Value| unwrap!(mode.merge(x, y));
+
+                // Commutative
+                assert_eq!(merge(&a, &b), merge(&b, &a));
+                // Associative
+                assert_eq!(merge(&merge(&a, &b), &c), merge(&a, &merge(&b, &c)));
+                // Idempotent
+                assert_eq!(merge(&a, &a), a);
+                assert_eq!(merge(&merge(&a, &b), &b), merge(&a, &b));
+            }
+        }
+    }
+
+    #[test]
+    fn merge_modes_by_tag() {
+        assert_eq!(MergeMode::of_tag(0), MergeMode::Versioned);
+        assert_eq!(
+            MergeMode::of_tag(LWW_REGISTER_TAGS.start),
+            MergeMode::LwwRegister
+        );
+        assert_eq!(
+            MergeMode::of_tag(GROW_ONLY_SET_TAGS.end - 1),
+            MergeMode::GrowOnlySet
+        );
+        assert_eq!(
+            MergeMode::of_tag(GROW_ONLY_SET_TAGS.end),
+            MergeMode::Versioned
+        );
+    }
+}
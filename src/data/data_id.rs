@@ -0,0 +1,31 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::xor_name::XorName;
+
+/// Identifier of a chunk which can be audited.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum DataId {
+    /// `ImmutableData` with the given name.
+    Immutable(XorName),
+    /// `MutableData` with the given name and type tag.
+    Mutable(XorName, u64),
+    /// `AppendOnlyData` with the given name and type tag.
+    AppendOnly(XorName, u64),
+}
+
+impl DataId {
+    /// Returns the name of the chunk.
+    pub fn name(&self) -> &XorName {
+        match *self {
+            DataId::Immutable(ref name)
+            | DataId::Mutable(ref name, _)
+            | DataId::AppendOnly(ref name, _) => name,
+        }
+    }
+}
@@ -0,0 +1,300 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::ImmutableData;
+use crate::xor_name::XorName;
+use maidsafe_utilities::serialisation;
+use safe_crypto;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display, Formatter};
+use std::mem;
+use unwrap::unwrap;
+
+/// Maximum size of the content of a single chunk. Together with the serialisation overhead, this
+/// stays within `MAX_IMMUTABLE_DATA_SIZE_IN_BYTES`.
+pub const MAX_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Maximum number of chunk names listed in a single data map. A data map listing more chunks is
+/// itself stored as chunks, so that it stays small enough to be passed around.
+const MAX_DATA_MAP_CHUNKS: usize = 1024;
+
+/// Describes how to assemble content larger than `MAX_CHUNK_SIZE` from `ImmutableData` chunks.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum DataMap {
+    /// The content is the concatenation of the chunks with the given names.
+    Chunks {
+        /// Size of the content in bytes
+        size: u64,
+        /// Names of the chunks, in order
+        chunks: Vec<XorName>,
+    },
+    /// The content is described by a serialised `DataMap`, which is assembled from the chunks
+    /// listed in the contained one.
+    Nested(Box<DataMap>),
+}
+
+impl DataMap {
+    /// Splits `content` into chunks, and returns them together with the data map to assemble it
+    /// from them.
+    pub fn split(content: &[u8]) -> (DataMap, Vec<ImmutableData>) {
+        split_with(content, MAX_CHUNK_SIZE, MAX_DATA_MAP_CHUNKS)
+    }
+
+    /// Returns the number of levels of nested data maps which have to be fetched before the
+    /// chunks of the content.
+    pub fn depth(&self) -> usize {
+        match *self {
+            DataMap::Chunks { .. } => 0,
+            DataMap::Nested(ref inner) => inner.depth() + 1,
+        }
+    }
+}
+
+fn split_with(
+    content: &[u8],
+    chunk_size: usize,
+    max_chunks: usize,
+) -> (DataMap, Vec<ImmutableData>) {
+    let mut chunks: Vec<_> = content
+        .chunks(chunk_size)
+        .map(|chunk| ImmutableData::new(chunk.to_vec()))
+        .collect();
+    let data_map = DataMap::Chunks {
+        size: content.len() as u64,
+        chunks: chunks.iter().map(|chunk| *chunk.name()).collect(),
+    };
+    if chunks.len() <= max_chunks {
+        return (data_map, chunks);
+    }
+
+    let (inner, map_chunks) = split_with(
+        &unwrap!(serialisation::serialise(&data_map)),
+        chunk_size,
+        max_chunks,
+    );
+    chunks.extend(map_chunks);
+    (DataMap::Nested(Box::new(inner)), chunks)
+}
+
+/// Errors when assembling content from the chunks of a `DataMap`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DataMapError {
+    /// The content of the chunk doesn't hash to the name of any chunk we are waiting for.
+    UnexpectedChunk(XorName),
+    /// The assembled content doesn't have the size given in the data map.
+    SizeMismatch,
+    /// The content of a nested data map couldn't be parsed.
+    InvalidDataMap,
+}
+
+impl Display for DataMapError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            DataMapError::UnexpectedChunk(ref name) => write!(f, "Unexpected chunk {}", name),
+            DataMapError::SizeMismatch => write!(f, "Assembled content has the wrong size"),
+            DataMapError::InvalidDataMap => write!(f, "Nested data map is invalid"),
+        }
+    }
+}
+
+/// Assembles content from the chunks listed in a `DataMap`, as they arrive in any order.
+///
+/// Each chunk is checked against the names in the data map before it is used. Once the chunks of
+/// a nested data map are all there, the assembler moves on to the chunks listed in it. The content
+/// itself can be taken in pieces with `take_ready` while the remaining chunks are still fetched.
+pub struct DataMapAssembler {
+    /// The number of nested data maps still to be assembled before the content.
+    depth: usize,
+    size: u64,
+    chunks: Vec<XorName>,
+    /// The chunks received ahead of the ones before them, by index.
+    received: BTreeMap<usize, Vec<u8>>,
+    /// The index of the first chunk not yet assembled.
+    next: usize,
+    assembled: u64,
+    /// The assembled bytes not yet taken.
+    ready: Vec<u8>,
+}
+
+impl DataMapAssembler {
+    /// Creates an assembler for the content described by `data_map`.
+    pub fn new(data_map: DataMap) -> Self {
+        let mut assembler = DataMapAssembler {
+            depth: 0,
+            size: 0,
+            chunks: Vec::new(),
+            received: BTreeMap::new(),
+            next: 0,
+            assembled: 0,
+            ready: Vec::new(),
+        };
+        assembler.start(data_map);
+        assembler
+    }
+
+    /// Returns the names of the chunks which are still missing from the current level.
+    pub fn wanted(&self) -> BTreeSet<XorName> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .skip(self.next)
+            .filter(|(index, _)| !self.received.contains_key(index))
+            .map(|(_, name)| *name)
+            .collect()
+    }
+
+    /// Adds a received chunk. Returns `true` if this completed a nested data map, in which case
+    /// the chunks listed in it are now `wanted`.
+    pub fn add_chunk(&mut self, chunk: &ImmutableData) -> Result<bool, DataMapError> {
+        let name = XorName(safe_crypto::hash(chunk.value()));
+        let indices: Vec<_> = (self.next..self.chunks.len())
+            .filter(|index| self.chunks[*index] == name && !self.received.contains_key(index))
+            .collect();
+        if indices.is_empty() {
+            return Err(DataMapError::UnexpectedChunk(name));
+        }
+        for index in indices {
+            let _ = self.received.insert(index, chunk.value().to_vec());
+        }
+
+        while let Some(content) = self.received.remove(&self.next) {
+            self.assembled += content.len() as u64;
+            self.ready.extend(content);
+            self.next += 1;
+        }
+        if self.assembled > self.size {
+            return Err(DataMapError::SizeMismatch);
+        }
+        if self.next < self.chunks.len() {
+            return Ok(false);
+        }
+        if self.assembled != self.size {
+            return Err(DataMapError::SizeMismatch);
+        }
+        if self.depth == 0 {
+            return Ok(false);
+        }
+
+        self.depth -= 1;
+        let serialised_map = mem::replace(&mut self.ready, Vec::new());
+        let data_map = serialisation::deserialise(&serialised_map)
+            .map_err(|_| DataMapError::InvalidDataMap)?;
+        self.start(data_map);
+        Ok(true)
+    }
+
+    /// Removes and returns the part of the content assembled so far.
+    pub fn take_ready(&mut self) -> Vec<u8> {
+        if self.depth > 0 {
+            return Vec::new();
+        }
+        mem::replace(&mut self.ready, Vec::new())
+    }
+
+    /// Returns whether all chunks of the content have been added.
+    pub fn is_complete(&self) -> bool {
+        self.depth == 0 && self.next == self.chunks.len()
+    }
+
+    fn start(&mut self, mut data_map: DataMap) {
+        loop {
+            match data_map {
+                DataMap::Nested(inner) => {
+                    self.depth += 1;
+                    data_map = *inner;
+                }
+                DataMap::Chunks { size, chunks } => {
+                    self.size = size;
+                    self.chunks = chunks;
+                    break;
+                }
+            }
+        }
+        self.received.clear();
+        self.next = 0;
+        self.assembled = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{self, Rng};
+
+    fn assemble(data_map: DataMap, chunks: &[ImmutableData]) -> Vec<u8> {
+        let chunks: BTreeMap<_, _> = chunks.iter().map(|chunk| (*chunk.name(), chunk)).collect();
+        let mut assembler = DataMapAssembler::new(data_map);
+        let mut content = Vec::new();
+        while !assembler.is_complete() {
+            // Add the wanted chunks in reverse order, so they arrive ahead of their predecessors.
+            for name in assembler.wanted().into_iter().rev() {
+                if unwrap!(assembler.add_chunk(chunks[&name])) {
+                    break;
+                }
+                content.extend(assembler.take_ready());
+            }
+        }
+        content.extend(assembler.take_ready());
+        content
+    }
+
+    #[test]
+    fn split_and_assemble() {
+        let mut rng = rand::thread_rng();
+        let content: Vec<u8> = rng.gen_iter().take(1000).collect();
+
+        let (data_map, chunks) = split_with(&content, 64, 100);
+        assert_eq!(data_map.depth(), 0);
+        assert_eq!(chunks.len(), 16);
+        assert_eq!(assemble(data_map, &chunks), content);
+
+        let (data_map, chunks) = split_with(&[], 64, 100);
+        assert!(chunks.is_empty());
+        assert!(assemble(data_map, &chunks).is_empty());
+    }
+
+    #[test]
+    fn nested_data_map() {
+        let mut rng = rand::thread_rng();
+        let content: Vec<u8> = rng.gen_iter().take(1000).collect();
+
+        let (data_map, chunks) = split_with(&content, 64, 4);
+        assert!(data_map.depth() > 1);
+        assert_eq!(assemble(data_map, &chunks), content);
+    }
+
+    #[test]
+    fn repeated_chunks() {
+        let content = vec![0; 100];
+        let (data_map, chunks) = split_with(&content, 10, 100);
+        let mut assembler = DataMapAssembler::new(data_map);
+        assert_eq!(assembler.wanted().len(), 1);
+        assert!(!unwrap!(assembler.add_chunk(&chunks[0])));
+        assert!(assembler.is_complete());
+        assert_eq!(assembler.take_ready(), content);
+    }
+
+    #[test]
+    fn unexpected_chunk() {
+        let (data_map, chunks) = split_with(b"some content", 4, 100);
+        let mut assembler = DataMapAssembler::new(data_map);
+        let other = ImmutableData::new(b"other".to_vec());
+        assert_eq!(
+            assembler.add_chunk(&other),
+            Err(DataMapError::UnexpectedChunk(*other.name()))
+        );
+
+        // A chunk which was already added isn't wanted again.
+        assert!(!unwrap!(assembler.add_chunk(&chunks[0])));
+        assert_eq!(
+            assembler.add_chunk(&chunks[0]),
+            Err(DataMapError::UnexpectedChunk(*chunks[0].name()))
+        );
+        assert_eq!(assembler.take_ready(), b"some");
+    }
+}
@@ -7,41 +7,51 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::xor_name::XorName;
+use bytes::Bytes;
+use lazycell::AtomicLazyCell;
 use maidsafe_utilities::serialisation;
 use safe_crypto;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
 use std::fmt::{self, Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use unwrap::unwrap;
 
 /// Maximum allowed size for a serialised Immutable Data (ID) to grow to
 pub const MAX_IMMUTABLE_DATA_SIZE_IN_BYTES: u64 = 1024 * 1024 + 10 * 1024;
 
 /// An immutable chunk of data.
 ///
-/// Note that the `name` member is omitted when serialising `ImmutableData` and is calculated from
-/// the `value` when deserialising.
-#[derive(Hash, Clone, Eq, PartialEq, Ord, PartialOrd)]
+/// Note that the `name` member is omitted when serialising `ImmutableData`. It is calculated from
+/// the `value` when first needed, so nodes only relaying a chunk never hash it. The `value` is
+/// shared rather than copied when the chunk is cloned.
 pub struct ImmutableData {
-    name: XorName,
-    value: Vec<u8>,
+    name: AtomicLazyCell<XorName>,
+    value: Bytes,
 }
 
 impl ImmutableData {
     /// Creates a new instance of `ImmutableData`
-    pub fn new(value: Vec<u8>) -> ImmutableData {
+    pub fn new<T: Into<Bytes>>(value: T) -> ImmutableData {
         ImmutableData {
-            name: XorName(safe_crypto::hash(&value)),
-            value: value,
+            name: AtomicLazyCell::new(),
+            value: value.into(),
         }
     }
 
     /// Returns the value
-    pub fn value(&self) -> &Vec<u8> {
+    pub fn value(&self) -> &Bytes {
         &self.value
     }
 
     /// Returns name ensuring invariant.
     pub fn name(&self) -> &XorName {
-        &self.name
+        if let Some(name) = self.name.get() {
+            return name;
+        }
+        // If another thread filled the cell in the meantime, it holds the same name.
+        let _ = self.name.fill(XorName(safe_crypto::hash(&self.value)));
+        unwrap!(self.name.get())
     }
 
     /// Returns size of contained value.
@@ -60,6 +70,47 @@ impl ImmutableData {
     }
 }
 
+impl Clone for ImmutableData {
+    fn clone(&self) -> ImmutableData {
+        let name = AtomicLazyCell::new();
+        if let Some(known) = self.name.get() {
+            let _ = name.fill(*known);
+        }
+        ImmutableData {
+            name,
+            value: self.value.clone(),
+        }
+    }
+}
+
+// The name is a function of the value, so only the value needs comparing. Chunks are still ordered
+// by name first.
+impl PartialEq for ImmutableData {
+    fn eq(&self, other: &ImmutableData) -> bool {
+        self.value == other.value
+    }
+}
+
+impl Eq for ImmutableData {}
+
+impl Hash for ImmutableData {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state)
+    }
+}
+
+impl Ord for ImmutableData {
+    fn cmp(&self, other: &ImmutableData) -> Ordering {
+        (self.name(), &self.value).cmp(&(other.name(), &other.value))
+    }
+}
+
+impl PartialOrd for ImmutableData {
+    fn partial_cmp(&self, other: &ImmutableData) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Serialize for ImmutableData {
     fn serialize<S: Serializer>(&self, serialiser: S) -> Result<S::Ok, S::Error> {
         self.value.serialize(serialiser)
@@ -68,7 +119,7 @@ impl Serialize for ImmutableData {
 
 impl<'de> Deserialize<'de> for ImmutableData {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ImmutableData, D::Error> {
-        let value: Vec<u8> = Deserialize::deserialize(deserializer)?;
+        let value: Bytes = Deserialize::deserialize(deserializer)?;
         Ok(ImmutableData::new(value))
     }
 }
@@ -106,7 +157,16 @@ mod tests {
         let value = rng.gen_iter().take(len).collect();
         let immutable_data = ImmutableData::new(value);
         let serialised = unwrap!(serialisation::serialise(&immutable_data));
-        let parsed = unwrap!(serialisation::deserialise(&serialised));
+        let parsed: ImmutableData = unwrap!(serialisation::deserialise(&serialised));
         assert_eq!(immutable_data, parsed);
+        assert_eq!(immutable_data.name(), parsed.name());
+    }
+
+    #[test]
+    fn clones_share_the_value() {
+        let immutable_data = ImmutableData::new(vec![1; 100]);
+        let clone = immutable_data.clone();
+        assert_eq!(immutable_data.value().as_ptr(), clone.value().as_ptr());
+        assert_eq!(immutable_data.name(), clone.name());
     }
 }
@@ -6,13 +6,39 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+mod account_packet;
+mod append_only_data;
+#[cfg(feature = "data")]
+mod audit;
+mod crdt;
+mod data_id;
+#[cfg(feature = "data")]
+mod data_map;
 mod immutable_data;
 mod mutable_data;
+#[cfg(feature = "data")]
+mod tag_registry;
 
+pub use self::account_packet::{AccountPacket, MAX_ACCOUNT_PACKET_SIZE_IN_BYTES};
+pub use self::append_only_data::{AppendEntry, AppendOnlyData, MAX_APPEND_ONLY_DATA_SIZE_IN_BYTES};
+#[cfg(feature = "data")]
+pub use self::audit::{
+    storage_proof, AuditOutcome, DataAudit, AUDIT_TIMEOUT, MAX_FAILED_AUDITS, MAX_PENDING_AUDITS,
+};
+pub use self::crdt::{
+    grow_only_set_elements, grow_only_set_value, MergeMode, GROW_ONLY_SET_TAGS, LWW_REGISTER_TAGS,
+};
+pub use self::data_id::DataId;
+#[cfg(feature = "data")]
+pub use self::data_map::{DataMap, DataMapAssembler, DataMapError, MAX_CHUNK_SIZE};
 pub use self::immutable_data::{ImmutableData, MAX_IMMUTABLE_DATA_SIZE_IN_BYTES};
 pub use self::mutable_data::{
-    Action, EntryAction, EntryActions, MutableData, PermissionSet, User, Value,
-    MAX_MUTABLE_DATA_ENTRIES, MAX_MUTABLE_DATA_SIZE_IN_BYTES,
+    Action, Delegation, EntryAction, EntryActions, KeyPermissions, MutableData, PermissionSet,
+    User, Value, MAX_MUTABLE_DATA_ENTRIES, MAX_MUTABLE_DATA_SIZE_IN_BYTES,
+};
+#[cfg(feature = "data")]
+pub use self::tag_registry::{
+    AppId, TagError, TagRegistry, FIRST_APP_TAG, RESERVED_TAGS, TAGS_PER_APP,
 };
 
 use lazy_static::lazy_static;
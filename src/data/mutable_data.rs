@@ -6,6 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use super::{grow_only_set_elements, MergeMode};
 use crate::client_error::{ClientError, EntryError};
 use crate::xor_name::XorName;
 use maidsafe_utilities::serialisation;
@@ -40,6 +41,8 @@ pub struct MutableData {
     /// Contains a set of owners which are allowed to mutate permissions.
     /// Currently limited to one owner to disallow multisig.
     owners: BTreeSet<PublicSignKey>,
+    /// Maps a key prefix to the permissions on the entries whose keys start with it
+    key_permissions: BTreeMap<Vec<u8>, KeyPermissions>,
 }
 
 /// A value in `MutableData`
@@ -150,6 +153,72 @@ impl Rand for PermissionSet {
     }
 }
 
+/// Permissions on the entries whose keys start with a given prefix.
+///
+/// Where they allow or deny an action, they take precedence over the permissions on the whole
+/// data, and over the ones of shorter prefixes.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct KeyPermissions {
+    /// Permissions of each user on the matching entries.
+    pub permissions: BTreeMap<User, PermissionSet>,
+    /// Rights on the matching entries delegated to other keys for a limited time.
+    pub delegations: BTreeMap<PublicSignKey, Delegation>,
+}
+
+impl KeyPermissions {
+    /// Returns whether no permissions or delegations are set.
+    pub fn is_empty(&self) -> bool {
+        self.permissions.is_empty() && self.delegations.is_empty()
+    }
+
+    fn is_allowed(&self, requester: PublicSignKey, action: Action, version: u64) -> Option<bool> {
+        let delegated = self
+            .delegations
+            .get(&requester)
+            .map_or(false, |delegation| {
+                delegation.is_valid_at(version)
+                    && delegation.permissions.is_allowed(action).unwrap_or(false)
+            });
+        if delegated {
+            return Some(true);
+        }
+        self.permissions
+            .get(&User::Key(requester))
+            .and_then(|perms| perms.is_allowed(action))
+            .or_else(|| {
+                self.permissions
+                    .get(&User::Anyone)
+                    .and_then(|perms| perms.is_allowed(action))
+            })
+    }
+}
+
+/// Rights delegated to a key until the data reaches a given version. Only `Action::Insert` and
+/// `Action::Update` can be delegated.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Delegation {
+    /// The delegated rights.
+    pub permissions: PermissionSet,
+    /// The last version of the data at which the delegation is valid. Any change of permissions
+    /// or owners past it makes the delegation expire.
+    pub expiry_version: u64,
+}
+
+impl Delegation {
+    /// Returns whether the delegation is still valid at the given version of the data.
+    pub fn is_valid_at(&self, version: u64) -> bool {
+        version <= self.expiry_version
+    }
+
+    fn is_delegable(&self) -> bool {
+        self.permissions.is_allowed(Action::Delete).is_none()
+            && self
+                .permissions
+                .is_allowed(Action::ManagePermissions)
+                .is_none()
+    }
+}
+
 /// Action performed on a single entry: insert, update or delete.
 #[derive(Hash, Debug, Eq, PartialEq, Clone, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum EntryAction {
@@ -227,6 +296,7 @@ impl MutableData {
             permissions: permissions,
             version: 0,
             owners: owners,
+            key_permissions: BTreeMap::new(),
         };
 
         md.validate()?;
@@ -259,6 +329,7 @@ impl MutableData {
             permissions: self.permissions.clone(),
             version: self.version,
             owners: self.owners.clone(),
+            key_permissions: self.key_permissions.clone(),
         }
     }
 
@@ -302,17 +373,71 @@ impl MutableData {
         &self.data
     }
 
+    /// Returns at most `limit` entries, starting with the one with the key `start_key` or the next
+    /// greater key, together with the key the following page starts at, if there are more
+    /// entries.
+    pub fn entries_range(
+        &self,
+        start_key: &[u8],
+        limit: usize,
+    ) -> (BTreeMap<Vec<u8>, Value>, Option<Vec<u8>>) {
+        let mut range = self.data.range(start_key.to_vec()..);
+        let entries = range
+            .by_ref()
+            .take(limit)
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        let next_key = range.next().map(|(key, _)| key.clone());
+        (entries, next_key)
+    }
+
     /// Removes and returns all entries
     pub fn take_entries(&mut self) -> BTreeMap<Vec<u8>, Value> {
         mem::replace(&mut self.data, BTreeMap::new())
     }
 
-    /// Mutates entries (key + value pairs) in bulk
+    /// Mutates entries (key + value pairs) in bulk, if `requester` is allowed to perform all
+    /// `actions`. See [`validate_and_apply()`](#method.validate_and_apply).
     pub fn mutate_entries(
         &mut self,
         actions: BTreeMap<Vec<u8>, EntryAction>,
         requester: PublicSignKey,
     ) -> Result<(), ClientError> {
+        let is_allowed = |key: &Vec<u8>, action: &EntryAction| {
+            let action = match *action {
+                EntryAction::Insert(_) => Action::Insert,
+                EntryAction::Update(_) => Action::Update,
+                EntryAction::Delete(_) => Action::Delete,
+            };
+            self.is_entry_action_allowed(requester, key, action)
+        };
+        if actions.iter().any(|(key, action)| !is_allowed(key, action)) {
+            return Err(ClientError::AccessDenied);
+        }
+
+        self.validate_and_apply(actions)
+    }
+
+    /// Applies the batch of `actions` atomically: either all of them succeed or none is applied.
+    ///
+    /// Each update or delete must carry the entry's current version plus one, i.e. it only
+    /// succeeds if the entry is still at the version the requester expects. If any action is
+    /// invalid, `ClientError::InvalidEntryActions` maps the key of every invalid action to its
+    /// error. Permissions aren't checked; use [`mutate_entries()`](#method.mutate_entries) for
+    /// requests from clients.
+    ///
+    /// For data with a conflict-free [`merge_mode()`](#method.merge_mode), versions aren't
+    /// checked: each action's value is merged into the entry instead, and a delete merges in an
+    /// empty value with the given version.
+    pub fn validate_and_apply(
+        &mut self,
+        actions: BTreeMap<Vec<u8>, EntryAction>,
+    ) -> Result<(), ClientError> {
+        let mode = self.merge_mode();
+        if mode != MergeMode::Versioned {
+            return self.merge_actions(mode, actions);
+        }
+
         // Deconstruct actions into inserts, updates, and deletes
         let (insert, update, delete) = actions.into_iter().fold(
             (BTreeMap::new(), BTreeMap::new(), BTreeMap::new()),
@@ -332,13 +457,6 @@ impl MutableData {
             },
         );
 
-        if (!insert.is_empty() && !self.is_action_allowed(requester, Action::Insert))
-            || (!update.is_empty() && !self.is_action_allowed(requester, Action::Update))
-            || (!delete.is_empty() && !self.is_action_allowed(requester, Action::Delete))
-        {
-            return Err(ClientError::AccessDenied);
-        }
-
         let mut new_data = self.data.clone();
         let mut errors = BTreeMap::new();
 
@@ -404,6 +522,60 @@ impl MutableData {
             return Err(ClientError::InvalidEntryActions(errors));
         }
 
+        self.replace_entries(new_data)
+    }
+
+    /// Returns how concurrent mutations of the entries are reconciled, as determined by the type
+    /// tag.
+    pub fn merge_mode(&self) -> MergeMode {
+        MergeMode::of_tag(self.tag)
+    }
+
+    /// Merges the entries of another replica of this data into ours, according to the
+    /// [`merge_mode()`](#method.merge_mode). For conflict-free modes, replicas which merged the
+    /// same entries end up equal regardless of the order they were merged in.
+    pub fn merge_entries(&mut self, entries: &BTreeMap<Vec<u8>, Value>) -> Result<(), ClientError> {
+        let mode = self.merge_mode();
+        let mut new_data = self.data.clone();
+        for (key, value) in entries {
+            merge_entry(mode, &mut new_data, key.clone(), value)?;
+        }
+        self.replace_entries(new_data)
+    }
+
+    fn merge_actions(
+        &mut self,
+        mode: MergeMode,
+        actions: BTreeMap<Vec<u8>, EntryAction>,
+    ) -> Result<(), ClientError> {
+        let mut new_data = self.data.clone();
+        let mut errors = BTreeMap::new();
+
+        for (key, action) in actions {
+            let value = match action {
+                EntryAction::Insert(value) | EntryAction::Update(value) => value,
+                EntryAction::Delete(_) if mode == MergeMode::GrowOnlySet => {
+                    let _ = errors.insert(key, EntryError::InvalidAction);
+                    continue;
+                }
+                EntryAction::Delete(version) => Value {
+                    content: Vec::new(),
+                    entry_version: version,
+                },
+            };
+            if merge_entry(mode, &mut new_data, key.clone(), &value).is_err() {
+                let _ = errors.insert(key, EntryError::InvalidAction);
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(ClientError::InvalidEntryActions(errors));
+        }
+
+        self.replace_entries(new_data)
+    }
+
+    fn replace_entries(&mut self, new_data: BTreeMap<Vec<u8>, Value>) -> Result<(), ClientError> {
         if new_data.len() > MAX_MUTABLE_DATA_ENTRIES as usize {
             return Err(ClientError::TooManyEntries);
         }
@@ -563,6 +735,71 @@ impl MutableData {
         true
     }
 
+    /// Gets the permissions on entries by key prefix.
+    pub fn key_permissions(&self) -> &BTreeMap<Vec<u8>, KeyPermissions> {
+        &self.key_permissions
+    }
+
+    /// Sets the permissions on the entries whose keys start with `prefix`. Empty permissions
+    /// remove the ones set for that prefix before.
+    pub fn set_key_permissions(
+        &mut self,
+        prefix: Vec<u8>,
+        permissions: KeyPermissions,
+        version: u64,
+        requester: PublicSignKey,
+    ) -> Result<(), ClientError> {
+        if !self.is_action_allowed(requester, Action::ManagePermissions) {
+            return Err(ClientError::AccessDenied);
+        }
+        if version != self.version + 1 {
+            return Err(ClientError::InvalidSuccessor(self.version));
+        }
+        if !permissions
+            .delegations
+            .values()
+            .all(Delegation::is_delegable)
+        {
+            return Err(ClientError::InvalidOperation);
+        }
+
+        let prev = if permissions.is_empty() {
+            self.key_permissions.remove(&prefix)
+        } else {
+            self.key_permissions.insert(prefix.clone(), permissions)
+        };
+        if !self.validate_size() {
+            // Serialised data size limit is exceeded
+            let _ = match prev {
+                None => self.key_permissions.remove(&prefix),
+                Some(perms) => self.key_permissions.insert(prefix, perms),
+            };
+            return Err(ClientError::DataTooLarge);
+        }
+        self.version = version;
+        Ok(())
+    }
+
+    /// Set key permissions without performing any validation.
+    pub fn set_key_permissions_without_validation(
+        &mut self,
+        prefix: Vec<u8>,
+        permissions: KeyPermissions,
+        version: u64,
+    ) -> bool {
+        if version <= self.version {
+            return false;
+        }
+
+        let _ = if permissions.is_empty() {
+            self.key_permissions.remove(&prefix)
+        } else {
+            self.key_permissions.insert(prefix, permissions)
+        };
+        self.version = version;
+        true
+    }
+
     /// Change owner of the mutable data.
     pub fn change_owner(
         &mut self,
@@ -611,6 +848,24 @@ impl MutableData {
         }
     }
 
+    fn is_entry_action_allowed(
+        &self,
+        requester: PublicSignKey,
+        key: &[u8],
+        action: Action,
+    ) -> bool {
+        if self.owners.contains(&requester) {
+            return true;
+        }
+        self.key_permissions
+            .iter()
+            .rev()
+            .filter(|(prefix, _)| key.starts_with(prefix))
+            .filter_map(|(_, perms)| perms.is_allowed(requester, action, self.version))
+            .next()
+            .unwrap_or_else(|| self.is_action_allowed(requester, action))
+    }
+
     fn is_action_allowed(&self, requester: PublicSignKey, action: Action) -> bool {
         if self.owners.contains(&requester) {
             return true;
@@ -624,6 +879,28 @@ impl MutableData {
     }
 }
 
+// Merges `value` into the entry at `key` of `data`, or inserts it if there is no such entry.
+fn merge_entry(
+    mode: MergeMode,
+    data: &mut BTreeMap<Vec<u8>, Value>,
+    key: Vec<u8>,
+    value: &Value,
+) -> Result<(), ClientError> {
+    match data.entry(key) {
+        Entry::Occupied(mut entry) => {
+            let merged = mode.merge(entry.get(), value)?;
+            let _ = entry.insert(merged);
+        }
+        Entry::Vacant(entry) => {
+            if mode == MergeMode::GrowOnlySet {
+                let _ = grow_only_set_elements(value)?;
+            }
+            let _ = entry.insert(value.clone());
+        }
+    }
+    Ok(())
+}
+
 impl Debug for MutableData {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         // TODO(nbaksalyar): write all other fields
@@ -642,6 +919,7 @@ impl Debug for MutableData {
 mod tests {
     use super::*;
     use crate::client_error::ClientError;
+    use crate::data::{grow_only_set_value, GROW_ONLY_SET_TAGS, LWW_REGISTER_TAGS};
     use rand;
     use safe_crypto::gen_sign_keypair;
     use std::collections::{BTreeMap, BTreeSet};
@@ -755,6 +1033,122 @@ mod tests {
         assert!(!unwrap!(user2.is_allowed(Action::ManagePermissions)));
     }
 
+    #[test]
+    fn validate_and_apply_atomically() {
+        let mut md = unwrap!(MutableData::new(
+            rand::random(),
+            0,
+            BTreeMap::new(),
+            BTreeMap::new(),
+            BTreeSet::new()
+        ));
+        let actions = EntryActions::new()
+            .insert(b"a".to_vec(), b"1".to_vec(), 0)
+            .insert(b"b".to_vec(), b"1".to_vec(), 0);
+        unwrap!(md.validate_and_apply(actions.into()));
+
+        // One valid and two invalid actions: nothing is applied and both errors are reported.
+        let actions = EntryActions::new()
+            .update(b"a".to_vec(), b"2".to_vec(), 1)
+            .update(b"b".to_vec(), b"2".to_vec(), 5)
+            .delete(b"c".to_vec(), 1);
+        match md.validate_and_apply(actions.into()) {
+            Err(ClientError::InvalidEntryActions(errors)) => {
+                let expected = vec![
+                    (b"b".to_vec(), EntryError::InvalidSuccessor(0)),
+                    (b"c".to_vec(), EntryError::NoSuchEntry),
+                ];
+                assert_eq!(errors, expected.into_iter().collect());
+            }
+            result => panic!("Unexpected {:?}", result),
+        }
+        assert_eq!(unwrap!(md.get(b"a")).content, b"1".to_vec());
+        assert_eq!(md.version(), 0);
+    }
+
+    #[test]
+    fn conflict_free_mutations_converge() {
+        let new_md = |tag| {
+            unwrap!(MutableData::new(
+                rand::random(),
+                tag,
+                BTreeMap::new(),
+                BTreeMap::new(),
+                BTreeSet::new()
+            ))
+        };
+
+        // Two clients write the same register concurrently; replicas apply them in either order.
+        let first = EntryActions::new().update(b"key".to_vec(), b"first".to_vec(), 5);
+        let second = EntryActions::new().update(b"key".to_vec(), b"second".to_vec(), 7);
+        let mut md0 = new_md(LWW_REGISTER_TAGS.start);
+        let mut md1 = md0.clone();
+        unwrap!(md0.validate_and_apply(first.clone().into()));
+        unwrap!(md0.validate_and_apply(second.clone().into()));
+        unwrap!(md1.validate_and_apply(second.into()));
+        unwrap!(md1.validate_and_apply(first.into()));
+        assert_eq!(md0.entries(), md1.entries());
+        assert_eq!(unwrap!(md0.get(b"key")).content, b"second".to_vec());
+
+        // Grow-only sets are merged into their union, and can't be deleted from.
+        let set = |elements: &[&[u8]]| {
+            unwrap!(grow_only_set_value(
+                &elements.iter().map(|element| element.to_vec()).collect()
+            ))
+        };
+        let mut md0 = new_md(GROW_ONLY_SET_TAGS.start);
+        let mut md1 = md0.clone();
+        let _ = md0.data.insert(b"key".to_vec(), set(&[b"a", b"b"]));
+        let _ = md1.data.insert(b"key".to_vec(), set(&[b"b", b"c"]));
+        let md0_entries = md0.entries().clone();
+        unwrap!(md0.merge_entries(md1.entries()));
+        unwrap!(md1.merge_entries(&md0_entries));
+        assert_eq!(md0.entries(), md1.entries());
+        assert_eq!(unwrap!(md0.get(b"key")).clone(), set(&[b"a", b"b", b"c"]));
+
+        let delete = EntryActions::new().delete(b"key".to_vec(), 10);
+        match md0.validate_and_apply(delete.into()) {
+            Err(ClientError::InvalidEntryActions(errors)) => {
+                assert_eq!(
+                    errors.get(&b"key".to_vec()),
+                    Some(&EntryError::InvalidAction)
+                );
+            }
+            result => panic!("Unexpected {:?}", result),
+        }
+    }
+
+    #[test]
+    fn entries_range() {
+        let val = Value {
+            content: b"123".to_vec(),
+            entry_version: 0,
+        };
+        let data = (0..10u8).map(|i| (vec![i * 2], val.clone())).collect();
+        let md = unwrap!(MutableData::new(
+            rand::random(),
+            0,
+            BTreeMap::new(),
+            data,
+            BTreeSet::new()
+        ));
+
+        let (page, next_key) = md.entries_range(&[], 4);
+        assert_eq!(
+            page.keys().cloned().collect::<Vec<_>>(),
+            vec![vec![0], vec![2], vec![4], vec![6]]
+        );
+        assert_eq!(next_key, Some(vec![8]));
+
+        // A start key between entries starts at the next greater one.
+        let (page, next_key) = md.entries_range(&[13], 4);
+        assert_eq!(
+            page.keys().cloned().collect::<Vec<_>>(),
+            vec![vec![14], vec![16], vec![18]]
+        );
+        assert_eq!(next_key, None);
+    }
+
     #[test]
     fn max_entries_limit() {
         let to_vec_of_u8 = |i: u64| vec![(i >> 24) as u8, (i >> 16) as u8, (i >> 8) as u8, i as u8];
@@ -1051,4 +1445,86 @@ mod tests {
         assert!(md.get(&[0]).is_some());
         assert!(md.get(&[1]).is_none());
     }
+
+    #[test]
+    fn key_permissions() {
+        let (owner, _) = gen_sign_keypair();
+        let (pk1, _) = gen_sign_keypair();
+        let (pk2, _) = gen_sign_keypair();
+
+        let mut md = unwrap!(MutableData::new(
+            rand::random(),
+            0,
+            BTreeMap::new(),
+            BTreeMap::new(),
+            iter::once(owner).collect(),
+        ));
+        let insert = |key: &[u8]| -> BTreeMap<_, _> {
+            EntryActions::new().insert(key.to_vec(), vec![1], 0).into()
+        };
+
+        // pk1 may insert under "logs/", except under "logs/private/".
+        let mut logs = KeyPermissions::default();
+        let _ = logs
+            .permissions
+            .insert(User::Key(pk1), PermissionSet::new().allow(Action::Insert));
+        assert_err!(
+            md.set_key_permissions(b"logs/".to_vec(), logs.clone(), 1, pk1),
+            ClientError::AccessDenied
+        );
+        unwrap!(md.set_key_permissions(b"logs/".to_vec(), logs, 1, owner));
+        let mut private = KeyPermissions::default();
+        let _ = private
+            .permissions
+            .insert(User::Anyone, PermissionSet::new().deny(Action::Insert));
+        unwrap!(md.set_key_permissions(b"logs/private/".to_vec(), private, 2, owner));
+
+        assert!(md.mutate_entries(insert(b"logs/1"), pk1).is_ok());
+        assert_err!(
+            md.mutate_entries(insert(b"logs/private/1"), pk1),
+            ClientError::AccessDenied
+        );
+        assert_err!(
+            md.mutate_entries(insert(b"other"), pk1),
+            ClientError::AccessDenied
+        );
+        assert_err!(
+            md.mutate_entries(insert(b"logs/2"), pk2),
+            ClientError::AccessDenied
+        );
+
+        // Only insert and update rights can be delegated.
+        let mut delegated = KeyPermissions::default();
+        let _ = delegated.delegations.insert(
+            pk2,
+            Delegation {
+                permissions: PermissionSet::new().allow(Action::Delete),
+                expiry_version: 3,
+            },
+        );
+        assert_err!(
+            md.set_key_permissions(b"logs/".to_vec(), delegated.clone(), 3, owner),
+            ClientError::InvalidOperation
+        );
+
+        // pk2 may insert under "logs/" until the data is past version 3.
+        let _ = delegated.delegations.insert(
+            pk2,
+            Delegation {
+                permissions: PermissionSet::new().allow(Action::Insert),
+                expiry_version: 3,
+            },
+        );
+        unwrap!(md.set_key_permissions(b"logs/".to_vec(), delegated, 3, owner));
+        assert!(md.mutate_entries(insert(b"logs/2"), pk2).is_ok());
+        unwrap!(md.set_user_permissions(User::Key(pk1), PermissionSet::new(), 4, owner));
+        assert_err!(
+            md.mutate_entries(insert(b"logs/3"), pk2),
+            ClientError::AccessDenied
+        );
+
+        // Empty permissions remove the prefix.
+        unwrap!(md.set_key_permissions(b"logs/".to_vec(), KeyPermissions::default(), 5, owner));
+        assert_eq!(md.key_permissions().len(), 1);
+    }
 }
@@ -0,0 +1,218 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::client_error::ClientError;
+use crate::{TYPE_TAG_DNS_PACKET, TYPE_TAG_SESSION_PACKET};
+use safe_crypto;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+
+/// `MutableData` type tags reserved for data types defined by the network itself.
+pub const RESERVED_TAGS: Range<u64> = 0..15_000;
+/// The first type tag allocated to applications. Each application owns a block of
+/// `TAGS_PER_APP` consecutive tags above this.
+pub const FIRST_APP_TAG: u64 = 1 << 32;
+/// The number of type tags each application can use.
+pub const TAGS_PER_APP: u64 = 1 << 16;
+
+/// Identifier of an application, derived from its name.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct AppId(pub u32);
+
+impl AppId {
+    /// Returns the identifier of the application called `name`.
+    pub fn from_name(name: &str) -> Self {
+        let hash = safe_crypto::hash(name.as_bytes());
+        AppId(
+            u32::from(hash[0]) << 24
+                | u32::from(hash[1]) << 16
+                | u32::from(hash[2]) << 8
+                | u32::from(hash[3]),
+        )
+    }
+
+    /// Returns the `MutableData` type tag for the application's data type `type_tag`.
+    pub fn tag(self, type_tag: u16) -> u64 {
+        FIRST_APP_TAG + u64::from(self.0) * TAGS_PER_APP + u64::from(type_tag)
+    }
+
+    /// Returns the application and its data type a `MutableData` type tag belongs to, if any.
+    pub fn from_tag(tag: u64) -> Option<(AppId, u16)> {
+        if tag < FIRST_APP_TAG {
+            return None;
+        }
+        let offset = tag - FIRST_APP_TAG;
+        let app_id = offset / TAGS_PER_APP;
+        if app_id > u64::from(u32::max_value()) {
+            return None;
+        }
+        Some((AppId(app_id as u32), (offset % TAGS_PER_APP) as u16))
+    }
+}
+
+/// Errors when registering applications in a `TagRegistry`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TagError {
+    /// The name of the application maps to the same `AppId` as the contained, registered one.
+    AppIdCollision(String),
+    /// No application with that name is registered.
+    UnknownApp,
+}
+
+impl Display for TagError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            TagError::AppIdCollision(ref name) => {
+                write!(f, "Application ID already used by {:?}", name)
+            }
+            TagError::UnknownApp => write!(f, "Application not registered"),
+        }
+    }
+}
+
+/// Maps applications to the blocks of `MutableData` type tags they own, and tells which tags
+/// clients may write to.
+///
+/// Applications get their own tags via `app_tag` instead of picking arbitrary ones, so they can't
+/// collide with each other or with the tags in `RESERVED_TAGS`. Vaults call `check_client_write`
+/// before accepting a client's mutation to keep clients from writing to the network's own data.
+#[derive(Clone, Debug)]
+pub struct TagRegistry {
+    apps: BTreeMap<AppId, String>,
+    client_writable: BTreeSet<u64>,
+}
+
+impl TagRegistry {
+    /// Creates a registry with no applications, in which clients may only write to the reserved
+    /// tags for the data they own, such as their session packet.
+    pub fn new() -> Self {
+        TagRegistry {
+            apps: BTreeMap::new(),
+            client_writable: vec![TYPE_TAG_SESSION_PACKET, TYPE_TAG_DNS_PACKET]
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Registers the application called `name` and returns its ID. Registering the same name
+    /// again is a no-op.
+    pub fn register_app(&mut self, name: &str) -> Result<AppId, TagError> {
+        let app_id = AppId::from_name(name);
+        match self.apps.get(&app_id) {
+            Some(registered) if registered != name => {
+                return Err(TagError::AppIdCollision(registered.clone()));
+            }
+            Some(_) => (),
+            None => {
+                let _ = self.apps.insert(app_id, name.to_string());
+            }
+        }
+        Ok(app_id)
+    }
+
+    /// Returns the type tag for data type `type_tag` of the registered application `name`.
+    pub fn app_tag(&self, name: &str, type_tag: u16) -> Result<u64, TagError> {
+        let app_id = AppId::from_name(name);
+        match self.apps.get(&app_id) {
+            Some(registered) if registered == name => Ok(app_id.tag(type_tag)),
+            _ => Err(TagError::UnknownApp),
+        }
+    }
+
+    /// Returns the name of the registered application owning `tag`, if any.
+    pub fn app_name(&self, tag: u64) -> Option<&str> {
+        let (app_id, _) = AppId::from_tag(tag)?;
+        self.apps.get(&app_id).map(String::as_str)
+    }
+
+    /// Allows clients to write data with the reserved type tag `tag`.
+    pub fn allow_client_writes(&mut self, tag: u64) {
+        let _ = self.client_writable.insert(tag);
+    }
+
+    /// Returns whether `tag` is reserved for data types defined by the network.
+    pub fn is_reserved(tag: u64) -> bool {
+        RESERVED_TAGS.start <= tag && tag < RESERVED_TAGS.end
+    }
+
+    /// Checks whether a client may create or mutate data with type tag `tag`.
+    pub fn check_client_write(&self, tag: u64) -> Result<(), ClientError> {
+        if Self::is_reserved(tag) && !self.client_writable.contains(&tag) {
+            Err(ClientError::AccessDenied)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for TagRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn app_tags() {
+        let mut registry = TagRegistry::new();
+        assert_eq!(registry.app_tag("example", 0), Err(TagError::UnknownApp));
+
+        let app_id = unwrap!(registry.register_app("example"));
+        assert_eq!(registry.register_app("example"), Ok(app_id));
+
+        let tag = unwrap!(registry.app_tag("example", 7));
+        assert!(tag >= FIRST_APP_TAG);
+        assert!(!TagRegistry::is_reserved(tag));
+        assert_eq!(AppId::from_tag(tag), Some((app_id, 7)));
+        assert_eq!(registry.app_name(tag), Some("example"));
+
+        let max_tag = AppId(u32::max_value()).tag(u16::max_value());
+        assert_eq!(
+            AppId::from_tag(max_tag),
+            Some((AppId(u32::max_value()), u16::max_value()))
+        );
+        assert_eq!(AppId::from_tag(10_000), None);
+        assert_eq!(registry.app_name(10_000), None);
+    }
+
+    #[test]
+    fn app_id_collision() {
+        let mut registry = TagRegistry::new();
+        let app_id = unwrap!(registry.register_app("first"));
+
+        // Simulate a second application whose name hashes to the same ID.
+        let _ = registry.apps.insert(app_id, "second".to_string());
+        assert_eq!(
+            registry.register_app("first"),
+            Err(TagError::AppIdCollision("second".to_string()))
+        );
+        assert_eq!(registry.app_tag("first", 0), Err(TagError::UnknownApp));
+    }
+
+    #[test]
+    fn client_writes() {
+        let mut registry = TagRegistry::new();
+        assert_eq!(registry.check_client_write(TYPE_TAG_SESSION_PACKET), Ok(()));
+        assert_eq!(
+            registry.check_client_write(10_000),
+            Err(ClientError::AccessDenied)
+        );
+        assert_eq!(registry.check_client_write(RESERVED_TAGS.end), Ok(()));
+        assert_eq!(
+            registry.check_client_write(AppId::from_name("example").tag(0)),
+            Ok(())
+        );
+
+        registry.allow_client_writes(10_000);
+        assert_eq!(registry.check_client_write(10_000), Ok(()));
+    }
+}
@@ -7,7 +7,9 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::routing_table::Error as RoutingTableError;
-use crate::{action::Action, event::Event, id::PublicId, quic_p2p, types::MessageId};
+use crate::{
+    action::Action, codec::CodecError, event::Event, id::PublicId, quic_p2p, types::MessageId,
+};
 use config_file_handler::Error as ConfigFileHandlerError;
 use crossbeam_channel as mpmc;
 use maidsafe_utilities::serialisation;
@@ -35,6 +37,10 @@ pub enum InterfaceError {
     MpmcSendEventError(mpmc::SendError<Event>),
     /// Error while trying to send an action to a multiple-producer-multiple-consumer channel
     MpmcSendActionError(mpmc::SendError<Action>),
+    /// A middleware rejected the message.
+    Rejected,
+    /// Our outgoing queues are filling up. The message wasn't sent; the caller should retry later.
+    Busy,
 }
 
 impl From<mpsc::RecvError> for InterfaceError {
@@ -149,6 +155,14 @@ pub enum RoutingError {
     UntrustedMessage,
     /// Crypto related error.
     Crypto(safe_crypto::Error),
+    /// An archive is truncated or otherwise malformed.
+    InvalidArchive,
+    /// The network parameters don't give a safe quorum.
+    InvalidNetworkParams,
+    /// A peer sent a message in, or only supports, a protocol version outside our supported window.
+    UnsupportedProtocolVersion(u16),
+    /// A message couldn't be encoded or decoded with the wire codec.
+    Codec(CodecError),
 }
 
 impl From<RoutingTableError> for RoutingError {
@@ -187,6 +201,12 @@ impl From<mpsc::SendError<Event>> for RoutingError {
     }
 }
 
+impl From<CodecError> for RoutingError {
+    fn from(error: CodecError) -> RoutingError {
+        RoutingError::Codec(error)
+    }
+}
+
 impl From<serialisation::SerialisationError> for RoutingError {
     fn from(error: serialisation::SerialisationError) -> RoutingError {
         RoutingError::SerialisationError(error)
@@ -205,6 +225,35 @@ impl From<safe_crypto::Error> for RoutingError {
     }
 }
 
+quick_error! {
+    /// The reason a section gave for refusing to let a node join it.
+    #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+    pub enum JoinDenialReason {
+        /// The section doesn't accept more nodes. Sections no longer send it, but it keeps its
+        /// place so the other reasons keep their wire encoding.
+        #[deprecated(note = "sections refuse with `Throttled` instead")]
+        SectionFull {
+            description("Section full")
+            display("The section doesn't accept more nodes.")
+        }
+        /// The section is busy with other joining nodes.
+        Throttled {
+            description("Section busy")
+            display("The section is busy with other joining nodes.")
+        }
+        /// The section doesn't allow this node to join.
+        DenyListed {
+            description("Node deny-listed")
+            display("The section doesn't allow this node to join.")
+        }
+        /// The node failed the resource proof challenge.
+        InvalidResourceProof {
+            description("Invalid resource proof")
+            display("The node's resource proof was invalid.")
+        }
+    }
+}
+
 quick_error! {
     #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
     pub enum BootstrapResponseError {
@@ -221,5 +270,9 @@ quick_error! {
             display("The chosen proxy node already has connections to the maximum number of \
                      clients allowed per proxy.")
         }
+        ShuttingDown {
+            description("Proxy is shutting down")
+            display("The chosen proxy node is leaving the network.")
+        }
     }
 }
@@ -6,10 +6,19 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use crate::chain::{EventDescriptor, ForkEvidence, MembershipStatement};
+use crate::data::EntryAction;
+use crate::error::JoinDenialReason;
 use crate::messages::{Request, Response};
 use crate::routing_table::Authority;
 use crate::routing_table::Prefix;
+use crate::session::SessionToken;
+use crate::sha3::Digest256;
+use crate::time::Duration;
+use crate::types::MessageId;
 use crate::xor_name::XorName;
+use hex_fmt::HexFmt;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{self, Debug, Formatter};
 
 /// An Event raised by a `Node` or `Client` via its event sender.
@@ -19,7 +28,10 @@ use std::fmt::{self, Debug, Formatter};
 ///
 /// `Request` and `Response` events from section authorities are only raised once the quorum has
 /// been reached, i.e. enough members of the section have sent the same message.
-#[derive(Clone, Eq, PartialEq)]
+///
+/// Events can be serialised, e.g. to hand them to a worker in another process. Variants are encoded
+/// by their position, so new ones must only ever be added at the end.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 // FIXME - See https://maidsafe.atlassian.net/browse/MAID-2026 for info on removing this exclusion.
 #[allow(clippy::large_enum_variant)]
 pub enum Event {
@@ -50,15 +62,191 @@ pub enum Event {
     /// Our own section requires merged with others, resulting in the included `Prefix` for our new
     /// section.
     SectionMerged(Prefix<XorName>),
-    /// The client has successfully connected to a proxy node on the network.
+    /// The client has successfully connected to a proxy node on the network. Raised again once
+    /// the client failed over to another proxy after losing its previous one.
     Connected,
     /// Disconnected or failed to connect - restart required.
     RestartRequired,
     /// Startup failed - terminate.
     Terminated,
+    /// A network event our section voted for has not reached quorum within the time expected for
+    /// the current section size.
+    VoteStalled {
+        /// The kind and key fields of the stalled network event.
+        event: EventDescriptor,
+        /// The number of valid votes the event has received so far.
+        have: usize,
+        /// The number of votes the event needs to accumulate.
+        need: usize,
+    },
     // TODO: Find a better solution for periodic tasks.
     /// This event is sent periodically every time Routing sends the `Heartbeat` messages.
     TimerTicked,
+    /// A section message arrived before some of the ones its source section sent to the same
+    /// destination earlier, or after we stopped waiting for it. Messages arriving early are held
+    /// back for a short while to restore their order, so this only signals that it may not be.
+    OutOfOrderDetected {
+        /// The source authority of the messages.
+        src: Authority<XorName>,
+        /// The destination authority of the messages.
+        dst: Authority<XorName>,
+        /// The sequence number of the next message we expected.
+        expected: u64,
+        /// The sequence number of the message received instead.
+        received: u64,
+    },
+    /// The section we tried to join refused us. Without `retry_after` the node terminates;
+    /// otherwise it asks again once `retry_after` has passed.
+    JoinDenied {
+        /// Why we were refused.
+        reason: JoinDenialReason,
+        /// How long the section expects the reason to persist.
+        retry_after: Option<Duration>,
+    },
+    /// We received more than one info for the same version of a neighbouring section, so at least
+    /// one of them is forged. We don't route through that section until we learn which is genuine.
+    ConflictingSectionInfo {
+        /// The prefix of the section.
+        prefix: Prefix<XorName>,
+        /// The version the infos claim.
+        version: u64,
+        /// The hash and member names of each of the conflicting infos.
+        infos: Vec<(Digest256, BTreeSet<XorName>)>,
+    },
+    /// Our section's signed statement of how long we have been a member, requested via
+    /// `Node::request_membership_statement`.
+    MembershipStatement(MembershipStatement),
+    /// Section `from` asks ours to take over responsibility for the names in `range`. Once our
+    /// vaults are ready, e.g. hold the data, they confirm via `Node::confirm_handoff`. Until
+    /// `HandoffCommit`, section `from` remains responsible for the range.
+    HandoffPrepare {
+        /// The range of names whose responsibility is transferred.
+        range: Prefix<XorName>,
+        /// The section handing off the range.
+        from: Prefix<XorName>,
+        /// The identifier of the handoff.
+        handoff_id: MessageId,
+    },
+    /// Responsibility for the names in `range` has passed from section `from` to section `to`.
+    /// Raised in both sections.
+    HandoffCommit {
+        /// The range of names whose responsibility is transferred.
+        range: Prefix<XorName>,
+        /// The section which handed off the range.
+        from: Prefix<XorName>,
+        /// The section which took over the range.
+        to: Prefix<XorName>,
+        /// The identifier of the handoff.
+        handoff_id: MessageId,
+    },
+    /// Too much of our section joined recently, so only membership changes are being agreed on
+    /// until it is stable again. Raised on every tick while this lasts.
+    SectionUnstable {
+        /// The number of current members which joined recently.
+        joined: usize,
+        /// The size of our section.
+        size: usize,
+    },
+    /// Votes for events which didn't accumulate in time, or never can, have been dropped.
+    StaleVotesPurged {
+        /// The number of events whose votes were dropped.
+        count: usize,
+    },
+    /// The section managing our client account changed its key, or its prefix due to a split or
+    /// merge. The new key is proven by a chain of signatures leading from the previous one.
+    ManagerSectionChanged {
+        /// The prefix of the section.
+        prefix: Prefix<XorName>,
+        /// The version of the section's new key.
+        version: u64,
+        /// The hash of the section's new public key.
+        key: Digest256,
+    },
+    /// Events we voted for during a long split or merge have been dropped, because too many of
+    /// them were waiting for it to finish.
+    CachedEventsEvicted {
+        /// The number of events dropped.
+        count: usize,
+    },
+    /// Entries of a `MutableData` we watch were mutated. See `Client::watch_mdata`.
+    MDataChanged {
+        /// The name of the data.
+        name: XorName,
+        /// The type tag of the data.
+        tag: u64,
+        /// The mutations performed.
+        actions: BTreeMap<Vec<u8>, EntryAction>,
+    },
+    /// A request was given up on, as it couldn't be sent or wasn't answered in any of the attempts
+    /// allowed by the client's `RetryPolicy`.
+    RequestFailed {
+        /// The destination of the request.
+        dst: Authority<XorName>,
+        /// The message ID of the request.
+        msg_id: MessageId,
+    },
+    /// We became one of the elders of our section, as we are now among its oldest members.
+    Promoted,
+    /// We stopped being one of the elders of our section, but are still a member.
+    Demoted,
+    /// New members joined our section. The data we hold for our section should be offered to each
+    /// of them with `Node::send_data_inventory`.
+    RefreshRequired {
+        /// The prefix of our section.
+        prefix: Prefix<XorName>,
+        /// The names of the new members.
+        new_members: BTreeSet<XorName>,
+    },
+    /// A member of our section listed data it holds for our section. The data we are missing can
+    /// be requested with `Node::request_republish`.
+    DataInventory {
+        /// The name of the member holding the data.
+        src: XorName,
+        /// The names of the data.
+        names: BTreeSet<XorName>,
+    },
+    /// A new member of our section asked for data to be republished to it. Requests are raised in
+    /// small batches, so the republishing doesn't cause a spike in bandwidth.
+    RepublishRequested {
+        /// The name of the new member.
+        dst: XorName,
+        /// The names of the data.
+        names: BTreeSet<XorName>,
+    },
+    /// Our section accepted more than one info for the same version of a neighbouring section,
+    /// i.e. that section's chain forked. We don't accept any successors of these infos until we
+    /// learn which one is genuine. Raised again whenever another branch is found.
+    ForkDetected(ForkEvidence),
+    /// Our ClientManager section issued us a session token. Passing it to `Client::resume` on a
+    /// later start resumes the session instead of asking for a new one.
+    SessionStarted(SessionToken),
+    /// A neighbouring section split, but we only received the info of some of its halves, e.g.
+    /// because our section split in the same round. Until the info of `prefix` arrives, messages
+    /// to it are routed via the members of `from`, the section it split off.
+    NeighbourInferred {
+        /// The prefix of the neighbour whose info we are missing.
+        prefix: Prefix<XorName>,
+        /// The prefix of the section it split off.
+        from: Prefix<XorName>,
+    },
+    /// We no longer rely on an inferred info for the neighbour with this prefix, as reported by
+    /// `NeighbourInferred`: its own info arrived, or it isn't our neighbour anymore.
+    NeighbourConverged(Prefix<XorName>),
+    /// Our section acknowledged that we are going offline after `Node::shutdown`, or didn't do so
+    /// in time. The node has terminated and can be dropped.
+    ShutdownComplete,
+    /// The destination section of a request we sent, or which our section sent, confirmed that it
+    /// was delivered.
+    MessageDelivered {
+        /// The identifier of the delivered request.
+        msg_id: MessageId,
+    },
+    /// Our section agreed that it failed `MAX_FAILED_AUDITS` data audits as the data manager group
+    /// `auditee`, so data it should hold is missing and needs to be republished.
+    DataAuditFailed {
+        /// The group of our section which failed the audits.
+        auditee: Authority<XorName>,
+    },
 }
 
 impl Debug for Event {
@@ -95,7 +283,358 @@ impl Debug for Event {
             Event::Connected => write!(formatter, "Event::Connected"),
             Event::RestartRequired => write!(formatter, "Event::RestartRequired"),
             Event::Terminated => write!(formatter, "Event::Terminated"),
+            Event::VoteStalled {
+                ref event,
+                have,
+                need,
+            } => write!(
+                formatter,
+                "Event::VoteStalled {{ event: {:?}, have: {}, need: {} }}",
+                event, have, need
+            ),
+            Event::OutOfOrderDetected {
+                ref src,
+                ref dst,
+                expected,
+                received,
+            } => write!(
+                formatter,
+                "Event::OutOfOrderDetected {{ src: {:?}, dst: {:?}, expected: {}, received: {} }}",
+                src, dst, expected, received
+            ),
             Event::TimerTicked => write!(formatter, "Event::TimerTicked"),
+            Event::JoinDenied {
+                reason,
+                retry_after,
+            } => write!(
+                formatter,
+                "Event::JoinDenied {{ reason: {:?}, retry_after: {:?} }}",
+                reason, retry_after
+            ),
+            Event::ConflictingSectionInfo {
+                ref prefix,
+                version,
+                ref infos,
+            } => write!(
+                formatter,
+                "Event::ConflictingSectionInfo {{ prefix: {:?}, version: {}, infos: {:?} }}",
+                prefix, version, infos
+            ),
+            Event::MembershipStatement(ref statement) => {
+                write!(formatter, "Event::MembershipStatement({:?})", statement)
+            }
+            Event::HandoffPrepare {
+                ref range,
+                ref from,
+                ref handoff_id,
+            } => write!(
+                formatter,
+                "Event::HandoffPrepare {{ range: {:?}, from: {:?}, handoff_id: {:?} }}",
+                range, from, handoff_id
+            ),
+            Event::HandoffCommit {
+                ref range,
+                ref from,
+                ref to,
+                ref handoff_id,
+            } => write!(
+                formatter,
+                "Event::HandoffCommit {{ range: {:?}, from: {:?}, to: {:?}, handoff_id: {:?} }}",
+                range, from, to, handoff_id
+            ),
+            Event::SectionUnstable { joined, size } => write!(
+                formatter,
+                "Event::SectionUnstable {{ joined: {}, size: {} }}",
+                joined, size
+            ),
+            Event::StaleVotesPurged { count } => {
+                write!(formatter, "Event::StaleVotesPurged {{ count: {} }}", count)
+            }
+            Event::ManagerSectionChanged {
+                ref prefix,
+                version,
+                ref key,
+            } => write!(
+                formatter,
+                "Event::ManagerSectionChanged {{ prefix: {:?}, version: {}, key: {} }}",
+                prefix,
+                version,
+                HexFmt(key)
+            ),
+            Event::CachedEventsEvicted { count } => {
+                write!(
+                    formatter,
+                    "Event::CachedEventsEvicted {{ count: {} }}",
+                    count
+                )
+            }
+            Event::MDataChanged {
+                ref name,
+                tag,
+                ref actions,
+            } => write!(
+                formatter,
+                "Event::MDataChanged {{ name: {:?}, tag: {}, actions: {:?} }}",
+                name, tag, actions
+            ),
+            Event::RequestFailed {
+                ref dst,
+                ref msg_id,
+            } => write!(
+                formatter,
+                "Event::RequestFailed {{ dst: {:?}, msg_id: {:?} }}",
+                dst, msg_id
+            ),
+            Event::Promoted => write!(formatter, "Event::Promoted"),
+            Event::Demoted => write!(formatter, "Event::Demoted"),
+            Event::RefreshRequired {
+                ref prefix,
+                ref new_members,
+            } => write!(
+                formatter,
+                "Event::RefreshRequired {{ prefix: {:?}, new_members: {:?} }}",
+                prefix, new_members
+            ),
+            Event::DataInventory { ref src, ref names } => write!(
+                formatter,
+                "Event::DataInventory {{ src: {:?}, names: {} }}",
+                src,
+                names.len()
+            ),
+            Event::RepublishRequested { ref dst, ref names } => write!(
+                formatter,
+                "Event::RepublishRequested {{ dst: {:?}, names: {} }}",
+                dst,
+                names.len()
+            ),
+            Event::ForkDetected(ref evidence) => {
+                write!(formatter, "Event::ForkDetected({:?})", evidence)
+            }
+            Event::SessionStarted(ref token) => write!(
+                formatter,
+                "Event::SessionStarted {{ client_id: {:?} }}",
+                token.client_id()
+            ),
+            Event::NeighbourInferred {
+                ref prefix,
+                ref from,
+            } => write!(
+                formatter,
+                "Event::NeighbourInferred {{ prefix: {:?}, from: {:?} }}",
+                prefix, from
+            ),
+            Event::NeighbourConverged(ref prefix) => {
+                write!(formatter, "Event::NeighbourConverged({:?})", prefix)
+            }
+            Event::ShutdownComplete => write!(formatter, "Event::ShutdownComplete"),
+            Event::MessageDelivered { ref msg_id } => {
+                write!(
+                    formatter,
+                    "Event::MessageDelivered {{ msg_id: {:?} }}",
+                    msg_id
+                )
+            }
+            Event::DataAuditFailed { ref auditee } => write!(
+                formatter,
+                "Event::DataAuditFailed {{ auditee: {:?} }}",
+                auditee
+            ),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::{NetworkEvent, ProofSet, SectionInfo};
+    use crate::client_error::ClientError;
+    use crate::data::{ImmutableData, MAX_IMMUTABLE_DATA_SIZE_IN_BYTES};
+    use crate::id::FullId;
+    use crate::messages::{MessageContent, RoutingMessage, SignedRoutingMessage};
+    use crate::rate_limiter::RateLimits;
+    use maidsafe_utilities::serialisation::{deserialise, serialise};
+    use std::iter;
+
+    /// Upper bound on the bytes an event adds to the data it carries.
+    const MAX_EVENT_OVERHEAD: usize = 256;
+
+    fn all_events() -> Vec<Event> {
+        let request = Request::GetIData {
+            name: rand::random(),
+            msg_id: MessageId::new(),
+        };
+        let response = Response::GetIData {
+            res: Err(ClientError::NoSuchData),
+            msg_id: MessageId::new(),
+        };
+        let src = Authority::ClientManager(rand::random());
+        let dst = Authority::NaeManager(rand::random());
+        let prefix = unwrap!("101".parse());
+
+        // In declaration order, so the index of each event is its discriminant.
+        vec![
+            Event::RequestReceived { request, src, dst },
+            Event::ResponseReceived { response, src, dst },
+            Event::NodeAdded(rand::random()),
+            Event::NodeLost(rand::random()),
+            Event::SectionSplit(prefix),
+            Event::SectionMerged(prefix),
+            Event::Connected,
+            Event::RestartRequired,
+            Event::Terminated,
+            Event::VoteStalled {
+                event: NetworkEvent::Offline(*FullId::new().public_id()).descriptor(),
+                have: 2,
+                need: 5,
+            },
+            Event::TimerTicked,
+            Event::OutOfOrderDetected {
+                src,
+                dst,
+                expected: 3,
+                received: 5,
+            },
+            Event::JoinDenied {
+                reason: JoinDenialReason::Throttled,
+                retry_after: Some(Duration::from_secs(60)),
+            },
+            Event::ConflictingSectionInfo {
+                prefix,
+                version: 4,
+                infos: vec![([0; 32], BTreeSet::new()), ([1; 32], BTreeSet::new())],
+            },
+            Event::MembershipStatement(MembershipStatement {
+                member: *FullId::new().public_id(),
+                prefix,
+                version: 7,
+                since_version: 2,
+                relocations: 1,
+            }),
+            Event::HandoffPrepare {
+                range: prefix,
+                from: unwrap!("100".parse()),
+                handoff_id: MessageId::new(),
+            },
+            Event::HandoffCommit {
+                range: prefix,
+                from: unwrap!("100".parse()),
+                to: unwrap!("101".parse()),
+                handoff_id: MessageId::new(),
+            },
+            Event::SectionUnstable { joined: 3, size: 7 },
+            Event::StaleVotesPurged { count: 2 },
+            Event::ManagerSectionChanged {
+                prefix,
+                version: 5,
+                key: [7; 32],
+            },
+            Event::CachedEventsEvicted { count: 4 },
+            Event::MDataChanged {
+                name: rand::random(),
+                tag: 10_000,
+                actions: iter::once((b"key".to_vec(), EntryAction::Delete(2))).collect(),
+            },
+            Event::RequestFailed {
+                dst,
+                msg_id: MessageId::new(),
+            },
+            Event::Promoted,
+            Event::Demoted,
+            Event::RefreshRequired {
+                prefix,
+                new_members: iter::once(rand::random()).collect(),
+            },
+            Event::DataInventory {
+                src: rand::random(),
+                names: iter::once(rand::random()).collect(),
+            },
+            Event::RepublishRequested {
+                dst: rand::random(),
+                names: iter::once(rand::random()).collect(),
+            },
+            Event::ForkDetected(ForkEvidence::new(
+                (SectionInfo::default(), ProofSet::new()),
+                (SectionInfo::default(), ProofSet::new()),
+            )),
+            Event::SessionStarted(SessionToken::new(SignedRoutingMessage::insecure(
+                RoutingMessage {
+                    src,
+                    dst: Authority::Client {
+                        client_id: *FullId::new().public_id(),
+                        proxy_node_name: rand::random(),
+                    },
+                    content: MessageContent::SessionGranted {
+                        limits: RateLimits::default(),
+                        message_id: MessageId::new(),
+                    },
+                    dst_min_version: None,
+                },
+            ))),
+            Event::NeighbourInferred {
+                prefix,
+                from: unwrap!("10".parse()),
+            },
+            Event::NeighbourConverged(prefix),
+            Event::ShutdownComplete,
+            Event::MessageDelivered {
+                msg_id: MessageId::new(),
+            },
+            Event::DataAuditFailed {
+                auditee: Authority::NaeManager(rand::random()),
+            },
+        ]
+    }
+
+    fn discriminant(serialised: &[u8]) -> u32 {
+        unwrap!(deserialise(&serialised[..4]))
+    }
+
+    #[test]
+    fn serialisation_round_trip() {
+        for event in all_events() {
+            let serialised = unwrap!(serialise(&event));
+            let parsed: Event = unwrap!(deserialise(&serialised));
+            assert_eq!(parsed, event);
+        }
+    }
+
+    #[test]
+    fn stable_discriminants() {
+        for (index, event) in all_events().into_iter().enumerate() {
+            let serialised = unwrap!(serialise(&event));
+            assert_eq!(discriminant(&serialised) as usize, index, "{:?}", event);
+        }
+
+        let request = Request::RelayedGet {
+            dst: Authority::NaeManager(rand::random()),
+            request: Box::new(Request::GetAccountInfo(MessageId::new())),
+            msg_id: MessageId::new(),
+        };
+        assert_eq!(discriminant(&unwrap!(serialise(&request))), 22);
+
+        let response = Response::RelayedGet {
+            res: Err(ClientError::NoSuchData),
+            msg_id: MessageId::new(),
+        };
+        assert_eq!(discriminant(&unwrap!(serialise(&response))), 21);
+    }
+
+    #[test]
+    fn serialised_size() {
+        for event in all_events() {
+            assert!(unwrap!(serialise(&event)).len() <= MAX_EVENT_OVERHEAD);
+        }
+
+        let data = ImmutableData::new(vec![0; MAX_IMMUTABLE_DATA_SIZE_IN_BYTES as usize - 64]);
+        let event = Event::RequestReceived {
+            request: Request::PutIData {
+                data,
+                msg_id: MessageId::new(),
+            },
+            src: Authority::ClientManager(rand::random()),
+            dst: Authority::NaeManager(rand::random()),
+        };
+        let size = unwrap!(serialise(&event)).len();
+        assert!(size <= MAX_IMMUTABLE_DATA_SIZE_IN_BYTES as usize + MAX_EVENT_OVERHEAD);
+    }
+}
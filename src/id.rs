@@ -6,18 +6,28 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use crate::error::RoutingError;
 use crate::parsec;
+use crate::storage::Storage;
 use crate::xor_name::XorName;
+use maidsafe_utilities::serialisation;
 use safe_crypto;
 use safe_crypto::{
     gen_encrypt_keypair, gen_sign_keypair, PublicEncryptKey, PublicSignKey, SecretEncryptKey,
-    SecretSignKey, Signature,
+    SecretSignKey, Signature, SymmetricKey, SYMMETRIC_KEY_BYTES,
 };
 use serde::de::Deserialize;
 use serde::{Deserializer, Serialize, Serializer};
 use std::fmt::{self, Debug, Display, Formatter};
 use std::ops::RangeInclusive;
 
+/// The storage namespace the encrypted `FullId` is saved in.
+const ID_NAMESPACE: &str = "id";
+/// The key the encrypted `FullId` is saved under.
+const ID_KEY: &[u8] = b"full_id";
+/// The length of the random salt the key encrypting a saved `FullId` is derived with.
+const ID_SALT_BYTES: usize = 16;
+
 /// Network identity component containing name, and public and private keys.
 // FIXME Remove the Clone-ability
 #[derive(Clone)]
@@ -67,6 +77,13 @@ impl FullId {
         }
     }
 
+    /// Returns this `FullId` with the age of its public ID set to `age`. A node's age is one more
+    /// than that of the ID it relocated from.
+    pub fn with_age(mut self, age: u8) -> FullId {
+        self.public_id.age = age;
+        self
+    }
+
     /// Returns public ID reference.
     pub fn public_id(&self) -> &PublicId {
         &self.public_id
@@ -86,6 +103,67 @@ impl FullId {
     pub fn encrypting_private_key(&self) -> &SecretEncryptKey {
         &self.private_encrypt_key
     }
+
+    /// Writes the keys and age to `storage`, encrypted with a key derived from `passphrase`, so a
+    /// restarted node can reuse its identity via `NodeBuilder::full_id`. Replaces any ID saved
+    /// before.
+    pub fn save_encrypted(
+        &self,
+        storage: &mut dyn Storage,
+        passphrase: &[u8],
+    ) -> Result<(), RoutingError> {
+        let salt: [u8; ID_SALT_BYTES] = rand::random();
+        let key = id_key(passphrase, &salt)?;
+        let keys = (
+            self.public_id.age,
+            &self.public_id.public_encrypt_key,
+            &self.private_encrypt_key,
+            &self.public_id.public_sign_key,
+            &self.private_sign_key,
+        );
+        let ciphertext = key.encrypt(&keys)?;
+        storage.put(
+            ID_NAMESPACE,
+            ID_KEY,
+            serialisation::serialise(&(salt, ciphertext))?,
+        )
+    }
+
+    /// Reads a `FullId` written by `save_encrypted`, or returns `None` if `storage` doesn't
+    /// contain any. Fails with `RoutingError::Crypto` if `passphrase` is wrong.
+    pub fn restore_encrypted(
+        storage: &dyn Storage,
+        passphrase: &[u8],
+    ) -> Result<Option<FullId>, RoutingError> {
+        let bytes = match storage.get(ID_NAMESPACE, ID_KEY)? {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+        let (salt, ciphertext): ([u8; ID_SALT_BYTES], Vec<u8>) =
+            serialisation::deserialise(&bytes)?;
+        let key = id_key(passphrase, &salt)?;
+        let (age, public_encrypt_key, private_encrypt_key, public_sign_key, private_sign_key): (
+            u8,
+            PublicEncryptKey,
+            SecretEncryptKey,
+            PublicSignKey,
+            SecretSignKey,
+        ) = key.decrypt(&ciphertext)?;
+
+        Ok(Some(
+            FullId::with_keys(
+                (public_encrypt_key, private_encrypt_key),
+                (public_sign_key, private_sign_key),
+            )
+            .with_age(age),
+        ))
+    }
+}
+
+fn id_key(passphrase: &[u8], salt: &[u8]) -> Result<SymmetricKey, RoutingError> {
+    let mut key = [0; SYMMETRIC_KEY_BYTES];
+    safe_crypto::derive_key_from_pw(passphrase, salt, None, &mut key)?;
+    Ok(SymmetricKey::from_bytes(key))
 }
 
 impl parsec::SecretId for FullId {
@@ -207,6 +285,7 @@ impl PublicId {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::MemoryStorage;
     use maidsafe_utilities::serialisation;
     use safe_crypto;
     use unwrap::unwrap;
@@ -231,6 +310,32 @@ mod tests {
         assert!(pub_id_1 < pub_id_2);
     }
 
+    #[test]
+    fn encrypted_save_and_restore() {
+        unwrap!(safe_crypto::init());
+
+        let mut storage = MemoryStorage::new();
+        assert!(unwrap!(FullId::restore_encrypted(&storage, b"passphrase")).is_none());
+
+        let full_id = FullId::new().with_age(5);
+        unwrap!(full_id.save_encrypted(&mut storage, b"passphrase"));
+
+        let restored = unwrap!(unwrap!(FullId::restore_encrypted(&storage, b"passphrase")));
+        assert_eq!(restored.public_id(), full_id.public_id());
+        assert_eq!(restored.public_id().age(), 5);
+        let signature = restored.signing_private_key().sign_detached(b"data");
+        assert!(full_id
+            .public_id()
+            .signing_public_key()
+            .verify_detached(&signature, b"data"));
+
+        match FullId::restore_encrypted(&storage, b"wrong") {
+            Err(RoutingError::Crypto(_)) => (),
+            Err(error) => panic!("Unexpected {:?}", error),
+            Ok(_) => panic!("Decrypted with the wrong passphrase"),
+        }
+    }
+
     #[test]
     fn serialisation() {
         unwrap!(safe_crypto::init());
@@ -0,0 +1,111 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::time::{Duration, Instant};
+use std::{cmp, collections::VecDeque};
+
+/// The period over which the joins to our section are counted.
+const JOIN_RATE_WINDOW: Duration = Duration::from_secs(10 * 60);
+/// The number of joins per window a section of any size accepts without extra work.
+const MIN_FREE_JOINS: usize = 2;
+/// The highest resource proof difficulty our section votes for.
+const MAX_JOIN_DIFFICULTY: u8 = 8;
+
+/// Tracks the recent joins to our section, to work out the resource proof difficulty candidates
+/// should be challenged with.
+///
+/// While joins arrive no faster than a section of our size can absorb, there is no extra work.
+/// Every doubling of the rate beyond that adds a leading zero bit to the proof, which doubles the
+/// expected work of the next candidates.
+pub struct JoinRate {
+    joins: VecDeque<Instant>,
+    /// The difficulty we last voted for, so we don't vote for it again while it accumulates.
+    voted: Option<u8>,
+}
+
+impl JoinRate {
+    pub fn new() -> Self {
+        Self {
+            joins: VecDeque::new(),
+            voted: None,
+        }
+    }
+
+    /// Records that a candidate joined our section now.
+    pub fn record_join(&mut self) {
+        self.joins.push_back(Instant::now());
+    }
+
+    /// Returns the difficulty our section should use given the joins during the last window.
+    fn difficulty(&mut self, section_size: usize) -> u8 {
+        while let Some(joined) = self.joins.front() {
+            if joined.elapsed() < JOIN_RATE_WINDOW {
+                break;
+            }
+            let _ = self.joins.pop_front();
+        }
+        difficulty_for(self.joins.len(), section_size)
+    }
+
+    /// Returns the difficulty to vote for if it differs from the `current` one agreed by our
+    /// section, unless we voted for it already.
+    pub fn vote(&mut self, section_size: usize, current: u8) -> Option<u8> {
+        let difficulty = self.difficulty(section_size);
+        if difficulty == current {
+            self.voted = None;
+            None
+        } else if self.voted == Some(difficulty) {
+            None
+        } else {
+            self.voted = Some(difficulty);
+            Some(difficulty)
+        }
+    }
+}
+
+fn difficulty_for(joins: usize, section_size: usize) -> u8 {
+    let mut limit = cmp::max(MIN_FREE_JOINS, section_size / 2);
+    let mut difficulty = 0;
+    while joins > limit && difficulty < MAX_JOIN_DIFFICULTY {
+        difficulty += 1;
+        limit *= 2;
+    }
+    difficulty
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn difficulty_grows_with_the_join_rate() {
+        assert_eq!(difficulty_for(0, 8), 0);
+        assert_eq!(difficulty_for(4, 8), 0);
+        assert_eq!(difficulty_for(5, 8), 1);
+        assert_eq!(difficulty_for(9, 8), 2);
+        // Larger sections absorb more joins.
+        assert_eq!(difficulty_for(9, 20), 0);
+        // Small sections still accept a few.
+        assert_eq!(difficulty_for(2, 1), 0);
+        assert_eq!(difficulty_for(10_000, 8), MAX_JOIN_DIFFICULTY);
+    }
+
+    #[test]
+    fn votes_only_for_changes() {
+        let mut join_rate = JoinRate::new();
+        assert_eq!(join_rate.vote(8, 0), None);
+        for _ in 0..5 {
+            join_rate.record_join();
+        }
+        assert_eq!(join_rate.vote(8, 0), Some(1));
+        assert_eq!(join_rate.vote(8, 0), None);
+        assert_eq!(join_rate.vote(8, 1), None);
+        // Once the section agreed, dropping back is voted for again.
+        assert_eq!(join_rate.vote(20, 1), Some(0));
+    }
+}
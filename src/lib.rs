@@ -61,6 +61,8 @@
 //!
 //! Messages can be sent using the methods of `client`, and received as `Event`s from the
 //! `receiver`.
+//! Alternatively, the requests of an `asynch::Client` return futures which resolve to their
+//! responses.
 //!
 //!
 //! ## Node creation
@@ -84,6 +86,25 @@
 //! that authority send the same message.
 //!
 //!
+//! # Features
+//!
+//! All of the following are enabled by default:
+//!
+//! - `client`: the `Client` API.
+//! - `node`: the `Node` API, together with the chain and consensus machinery only nodes run.
+//! - `messaging`: the `Client` and `Node` methods for MPID messaging. The types in `messaging` and
+//!   the MPID request and response variants are always built, so all peers agree on the wire
+//!   format whichever features they were built with.
+//! - `data`: the helpers vaults use to manage data, such as `DataAudit` and `TagRegistry`.
+//!
+//! Applications which only need the client API can depend on routing with
+//! `default-features = false, features = ["client"]`. At least one of `client` and `node` has to
+//! be enabled.
+//!
+//! The optional `archive` feature adds the zero-copy archive format in `archive`, for vaults
+//! persisting large indexes of names or proofs.
+//!
+//!
 //! # Sequence diagrams
 //!
 //! - [Bootstrapping](bootstrap.png)
@@ -147,40 +168,76 @@
     clippy::redundant_field_names
 )]
 
+#[cfg(not(feature = "tracing"))]
 #[macro_use]
 extern crate log;
+#[cfg(feature = "tracing")]
+#[macro_use(log, log_enabled)]
+extern crate log;
 #[macro_use]
 extern crate serde_derive;
+#[cfg(feature = "tracing")]
+#[macro_use]
+extern crate tracing;
+
+#[cfg(not(any(feature = "client", feature = "node")))]
+compile_error!("At least one of the `client` and `node` features has to be enabled.");
 
 // Needs to be before all other modules to make the macros available to them.
 #[macro_use]
 mod macros;
 
 mod action;
+mod bloom_filter;
 mod cache;
 mod chain;
+#[cfg(feature = "client")]
 mod client;
+mod client_config;
 mod client_error;
+#[cfg(feature = "node")]
+mod client_manager;
+mod codec;
 mod common_types;
 mod config_handler;
+mod connection_policy;
+mod crash_dump;
 mod data;
 mod error;
 mod event;
 mod event_stream;
 mod id;
+#[cfg(feature = "node")]
+mod join_rate;
 mod message_filter;
+#[cfg(feature = "node")]
+mod message_sequencer;
 mod messages;
+mod middleware;
+mod network_params;
 mod network_service;
+#[cfg(feature = "node")]
 mod node;
 mod outbox;
+#[cfg(feature = "node")]
 mod peer_manager;
 mod peer_map;
+mod rate_limiter;
+#[cfg(feature = "node")]
+mod refresh;
+#[cfg(feature = "client")]
+mod request_retry;
+#[cfg(feature = "node")]
 mod resource_prover;
 mod routing_message_filter;
 mod routing_table;
+mod section_contacts;
+mod session;
+#[cfg(feature = "node")]
 mod signature_accumulator;
 mod state_machine;
 mod states;
+mod storage;
 mod time;
 mod timer;
 mod types;
@@ -190,81 +247,127 @@ mod xor_name;
 /// Mocking utilities.
 #[cfg(feature = "mock_base")]
 pub mod mock;
+
+// Used by the exported `log_or_panic!` macro.
+#[doc(hidden)]
+pub use crate::crash_dump::record_log_args as __record_crash_log;
 pub(crate) mod parsec;
 
 /// SHA-3 type alias.
 pub mod sha3;
 
+/// Zero-copy archives of names, prefixes, section infos and proof chains.
+#[cfg(feature = "archive")]
+pub mod archive;
+/// The contacts nodes bootstrap off.
+pub mod bootstrap;
+/// Protocol constants
+pub mod constants;
 /// Messaging infrastructure
 pub mod messaging;
+pub mod metrics;
+/// The network transport abstraction.
+pub mod transport;
+/// XOR-metric containers
+pub mod xor_space;
 /// Structured Data Tag for Session Packet Type
 pub const TYPE_TAG_SESSION_PACKET: u64 = 0;
 /// Structured Data Tag for DNS Packet Type
 pub const TYPE_TAG_DNS_PACKET: u64 = 5;
-
-/// Quorum is defined as having strictly greater than `QUORUM_NUMERATOR / QUORUM_DENOMINATOR`
-/// agreement; using only integer arithmetic a quorum can be checked with
-/// `votes * QUORUM_DENOMINATOR > voters * QUORUM_NUMERATOR`.
-pub const QUORUM_NUMERATOR: usize = 2;
-/// See `QUORUM_NUMERATOR`.
-pub const QUORUM_DENOMINATOR: usize = 3;
-
-/// Default minimal section size.
-pub const MIN_SECTION_SIZE: usize = 3;
 /// Key of an account data in the account packet
 pub const ACC_LOGIN_ENTRY_KEY: &[u8] = b"Login";
 
+#[cfg(all(feature = "client", not(feature = "mock_base")))]
+pub use crate::client::asynch;
+#[cfg(feature = "client")]
+pub use crate::client::Client;
+#[cfg(feature = "data")]
+pub use crate::data::{
+    storage_proof, AppId, AuditOutcome, DataAudit, DataMap, DataMapAssembler, DataMapError,
+    TagError, TagRegistry, AUDIT_TIMEOUT, FIRST_APP_TAG, MAX_CHUNK_SIZE, MAX_FAILED_AUDITS,
+    MAX_PENDING_AUDITS, RESERVED_TAGS, TAGS_PER_APP,
+};
 #[cfg(feature = "mock_base")]
 use crate::mock::quic_p2p;
+#[cfg(feature = "node")]
+pub use crate::node::{Node, NodeBuilder};
 #[cfg(any(test, feature = "mock_base"))]
 pub use crate::routing_table::verify_network_invariant;
 pub use crate::{
-    cache::{Cache, NullCache},
-    chain::Chain,
-    client::Client,
+    cache::{Cache, IDataCache, NullCache},
+    chain::{
+        EventDescriptor, ForkEvidence, JoinerId, MembershipStatement, NetworkEventKind,
+        SectionKeyInfo, StabilityGuard,
+    },
+    client_config::{ClientConfig, ClientConfigBuilder, RetryPolicy, DEFAULT_MSG_EXPIRY_DUR},
     client_error::{ClientError, EntryError},
-    common_types::AccountPacket,
+    common_types::AccountContent,
     config_handler::{Config, DevConfig},
+    connection_policy::{AcceptAll, ConnectionPolicy, InboundConnection},
+    constants::{MIN_SECTION_SIZE, QUORUM_DENOMINATOR, QUORUM_NUMERATOR},
+    crash_dump::{install_panic_hook, CrashLog},
     data::{
-        Action, EntryAction, EntryActions, ImmutableData, MutableData, PermissionSet, User, Value,
+        grow_only_set_elements, grow_only_set_value, AccountPacket, Action, AppendEntry,
+        AppendOnlyData, DataId, Delegation, EntryAction, EntryActions, ImmutableData,
+        KeyPermissions, MergeMode, MutableData, PermissionSet, User, Value, GROW_ONLY_SET_TAGS,
+        LWW_REGISTER_TAGS, MAX_ACCOUNT_PACKET_SIZE_IN_BYTES, MAX_APPEND_ONLY_DATA_SIZE_IN_BYTES,
         MAX_IMMUTABLE_DATA_SIZE_IN_BYTES, MAX_MUTABLE_DATA_ENTRIES, MAX_MUTABLE_DATA_SIZE_IN_BYTES,
         NO_OWNER_PUB_KEY,
     },
-    error::{InterfaceError, RoutingError},
+    error::{InterfaceError, JoinDenialReason, RoutingError},
     event::Event,
     event_stream::EventStream,
     id::{FullId, PublicId},
-    messages::{AccountInfo, Request, Response},
-    node::{Node, NodeBuilder},
+    messages::{AccountInfo, RelayedResponse, Request, Response, SealedRequest, UserMessage},
+    middleware::{Direction, MessageContext, MessageMiddleware, MiddlewarePipeline, Verdict},
+    network_params::{NetworkParams, Timeouts},
+    outbox::EventSender,
+    rate_limiter::{RateLimit, RateLimits},
     routing_table::Error as RoutingTableError,
-    routing_table::{Authority, Prefix, RoutingTable, VersionedPrefix, Xorable},
+    routing_table::{
+        Authority, GroupKind, GroupKindError, GroupKindRegistry, Prefix, RoutingTable,
+        VersionedPrefix, Xorable,
+    },
+    section_contacts::SectionContacts,
+    session::SessionToken,
+    storage::{FileStorage, MemoryStorage, Storage, WriteBatch, WriteOp},
     types::MessageId,
     utils::XorTargetInterval,
     xor_name::{XorName, XorNameFromHexError, XOR_NAME_BITS, XOR_NAME_LEN},
 };
 #[cfg(feature = "mock_base")]
 pub use crate::{
-    chain::{delivery_group_size, verify_chain_invariant},
+    chain::{delivery_group_size, verify_chain_invariant, NetworkGenerator},
+    messages::{EncryptedHopMessage, HopMessage},
     peer_manager::test_consts,
 };
+#[cfg(feature = "node")]
+pub use crate::{
+    chain::{
+        Chain, DeliveryScope, EventJournal, JoinFilter, NetworkEpoch, PendingEvent, PendingUpgrade,
+        PendingVote,
+    },
+    client_manager::{Accounts, DEFAULT_ACCOUNT_BALANCE},
+};
 #[cfg(not(feature = "mock_base"))]
 use quic_p2p;
 
-// Format that can be sent between peers
+/// Format that can be sent between peers
 #[cfg(not(feature = "mock_serialise"))]
-pub(crate) type NetworkBytes = bytes::Bytes;
+pub type NetworkBytes = bytes::Bytes;
+/// Format that can be sent between peers
 #[cfg(feature = "mock_serialise")]
-pub(crate) type NetworkBytes = std::rc::Rc<crate::messages::Message>;
+pub type NetworkBytes = std::rc::Rc<crate::messages::Message>;
 
 pub use self::quic_p2p::Config as NetworkConfig;
 pub(crate) use self::{
     chain::bls_emu::{
-        PublicKey as BlsPublicKey, PublicKeySet as BlsPublicKeySet,
+        Ciphertext as BlsCiphertext, PublicKey as BlsPublicKey, PublicKeySet as BlsPublicKeySet,
         PublicKeyShare as BlsPublicKeyShare, Signature as BlsSignature,
         SignatureShare as BlsSignatureShare,
     },
     network_service::NetworkService,
-    quic_p2p::{Event as NetworkEvent, Peer as ConnectionInfo, QuicP2p},
+    quic_p2p::{Event as NetworkEvent, Peer as ConnectionInfo},
 };
 
 #[cfg(test)]
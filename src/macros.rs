@@ -14,10 +14,41 @@
 #[macro_export]
 macro_rules! log_or_panic {
     ($log_level:expr, $($arg:tt)*) => {
+        $crate::__record_crash_log(format_args!($($arg)*));
         if cfg!(feature = "mock_base") && !::std::thread::panicking() {
             panic!($($arg)*);
         } else {
-            log!($log_level, $($arg)*);
+            match $log_level {
+                ::log::LogLevel::Error => error!($($arg)*),
+                ::log::LogLevel::Warn => warn!($($arg)*),
+                ::log::LogLevel::Info => info!($($arg)*),
+                ::log::LogLevel::Debug => debug!($($arg)*),
+                ::log::LogLevel::Trace => trace!($($arg)*),
+            }
         }
     };
 }
+
+/// Evaluates the expression inside a `tracing` span with the given name and fields, which are
+/// recorded using their `Debug` implementation. Without the "tracing" feature this only evaluates
+/// the expression.
+///
+/// The expression is evaluated in a closure, so `return` and `?` inside it don't leave the
+/// enclosing function.
+///
+/// Example usage:
+/// `in_span!("handle_message", src = routing_msg.src; self.handle(routing_msg))`
+#[cfg(feature = "tracing")]
+macro_rules! in_span {
+    ($name:expr, $($field:ident = $value:expr),*; $body:expr) => {
+        trace_span!($name, $($field = ?$value),*).in_scope(|| $body)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! in_span {
+    ($name:expr, $($field:ident = $value:expr),*; $body:expr) => {{
+        $(let _ = &$value;)*
+        $body
+    }};
+}
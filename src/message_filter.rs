@@ -22,12 +22,15 @@ fn hash<T: Hash>(t: &T) -> u64 {
 /// A time based message filter that takes any generic type as a key and will drop keys after a
 /// time period (LRU Cache pattern).
 pub struct MessageFilter<Message> {
-    /// The number of times each message has been received so far, and the expiry timestamp.
-    count: HashMap<u64, (usize, Instant)>,
+    /// The number of times each message has been received so far, the expiry timestamp and the
+    /// number of records of the message in `timeout_queue`.
+    count: HashMap<u64, (usize, Instant, usize)>,
     /// A record of message hashes and the expiry timestamps of all insertions, ordered
     /// chronologically. The timestamps are out of date if the same hash has been inserted again.
     timeout_queue: VecDeque<(u64, Instant)>,
     time_to_live: Duration,
+    /// The maximum number of distinct messages kept, if limited.
+    capacity: Option<usize>,
     phantom: PhantomData<Message>,
 }
 
@@ -38,10 +41,22 @@ impl<Message: Hash> MessageFilter<Message> {
             count: HashMap::new(),
             timeout_queue: VecDeque::new(),
             time_to_live: time_to_live,
+            capacity: None,
             phantom: PhantomData,
         }
     }
 
+    /// Constructor for a time based `MessageFilter` which also keeps at most `capacity` messages.
+    pub fn with_expiry_duration_and_capacity(
+        time_to_live: Duration,
+        capacity: usize,
+    ) -> MessageFilter<Message> {
+        MessageFilter {
+            capacity: Some(capacity),
+            ..Self::with_expiry_duration(time_to_live)
+        }
+    }
+
     /// Adds a message to the filter.
     ///
     /// Removes any expired messages, then adds `message`, then removes enough older messages until
@@ -56,14 +71,25 @@ impl<Message: Hash> MessageFilter<Message> {
         let hash_code = hash(message);
         let expiry = Instant::now() + self.time_to_live;
         self.timeout_queue.push_back((hash_code, expiry));
-        match self.count.entry(hash_code) {
+        let count = match self.count.entry(hash_code) {
             Entry::Occupied(entry) => {
-                let &mut (ref mut c, ref mut t) = entry.into_mut();
+                let &mut (ref mut c, ref mut t, ref mut records) = entry.into_mut();
                 *t = expiry;
                 *c += 1;
+                *records += 1;
                 *c
             }
-            Entry::Vacant(entry) => entry.insert((1, expiry)).0,
+            Entry::Vacant(entry) => entry.insert((1, expiry, 1)).0,
+        };
+        self.remove_excess();
+        count
+    }
+
+    /// Removes `message` from the filter, as if it had never been added.
+    pub fn remove(&mut self, message: &Message) {
+        let hash_code = hash(message);
+        if self.count.remove(&hash_code).is_some() {
+            self.timeout_queue.retain(|&(code, _)| code != hash_code);
         }
     }
 
@@ -71,7 +97,7 @@ impl<Message: Hash> MessageFilter<Message> {
     #[cfg(test)]
     pub fn count(&self, message: &Message) -> usize {
         let hash_code = hash(message);
-        self.count.get(&hash_code).map_or(0, |&(count, _)| count)
+        self.count.get(&hash_code).map_or(0, |&(count, _, _)| count)
     }
 
     /// Removes any expired messages, then returns whether `message` exists in the filter or not.
@@ -81,6 +107,24 @@ impl<Message: Hash> MessageFilter<Message> {
         self.count.contains_key(&hash(message))
     }
 
+    fn remove_excess(&mut self) {
+        let capacity = match self.capacity {
+            Some(capacity) => capacity,
+            None => return,
+        };
+        while self.count.len() > capacity {
+            let (hash_code, _) = unwrap!(self.timeout_queue.pop_front());
+            if let Entry::Occupied(mut entry) = self.count.entry(hash_code) {
+                // Only remove the message with its latest record, i.e. if it has not been inserted
+                // again since.
+                entry.get_mut().2 -= 1;
+                if entry.get().2 == 0 {
+                    let _removed_pair = entry.remove_entry();
+                }
+            }
+        }
+    }
+
     fn remove_expired(&mut self) {
         let now = Instant::now();
         while self
@@ -89,9 +133,11 @@ impl<Message: Hash> MessageFilter<Message> {
             .map_or(false, |&(_, ref t)| *t <= now)
         {
             let (hash_code, _) = unwrap!(self.timeout_queue.pop_front());
-            if let Entry::Occupied(entry) = self.count.entry(hash_code) {
+            if let Entry::Occupied(mut entry) = self.count.entry(hash_code) {
                 if entry.get().1 <= now {
                     let _removed_pair = entry.remove_entry();
+                } else {
+                    entry.get_mut().2 -= 1;
                 }
             }
         }
@@ -186,6 +232,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn capacity() {
+        let capacity = 5;
+        let time_to_live = Duration::from_secs(99);
+        let mut msg_filter =
+            MessageFilter::<usize>::with_expiry_duration_and_capacity(time_to_live, capacity);
+
+        for i in 0..capacity {
+            assert_eq!(1, msg_filter.insert(&i));
+        }
+        // Re-inserting "0" makes "1" the oldest message, which is removed to make room for "5".
+        assert_eq!(2, msg_filter.insert(&0));
+        assert_eq!(1, msg_filter.insert(&capacity));
+        assert!(msg_filter.contains(&0));
+        assert!(!msg_filter.contains(&1));
+        assert!((2..=capacity).all(|index| msg_filter.contains(&index)));
+    }
+
+    #[test]
+    fn remove() {
+        let capacity = 3;
+        let time_to_live = Duration::from_secs(99);
+        let mut msg_filter =
+            MessageFilter::<usize>::with_expiry_duration_and_capacity(time_to_live, capacity);
+
+        assert_eq!(1, msg_filter.insert(&0));
+        assert_eq!(2, msg_filter.insert(&0));
+        msg_filter.remove(&0);
+        assert!(!msg_filter.contains(&0));
+
+        // Its old records don't count against the message once it is added again.
+        assert_eq!(1, msg_filter.insert(&0));
+        for i in 1..capacity {
+            assert_eq!(1, msg_filter.insert(&i));
+        }
+        assert!((0..capacity).all(|index| msg_filter.contains(&index)));
+    }
+
     #[test]
     fn add_duplicate() {
         let size = 10;
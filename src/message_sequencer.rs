@@ -0,0 +1,224 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::messages::UserMessage;
+use crate::routing_table::Authority;
+use crate::time::{Duration, Instant};
+use crate::xor_name::XorName;
+use std::collections::btree_map::{BTreeMap, Entry};
+
+/// Duration after which the sequence numbers of a pair of authorities which exchanged no messages
+/// are forgotten.
+const SEQUENCE_EXPIRY_DURATION: Duration = Duration::from_secs(60 * 20);
+/// Duration for which a message is held back waiting for the messages missing before it.
+pub const REORDER_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Route = (Authority<XorName>, Authority<XorName>);
+
+/// A gap or late message in the sequence of user messages from `src` to `dst`.
+#[derive(Debug, Eq, PartialEq)]
+pub struct OutOfOrder {
+    pub src: Authority<XorName>,
+    pub dst: Authority<XorName>,
+    pub expected: u64,
+    pub received: u64,
+}
+
+struct IncomingSequence {
+    next: u64,
+    held: BTreeMap<u64, (Instant, UserMessage)>,
+    last_received: Instant,
+}
+
+/// Restores the order of the sequenced user messages we receive.
+///
+/// The sending section agrees on the sequence number of each of its messages via a
+/// `SequenceMessage` vote and signs it as part of the message, so the numbers of a route are
+/// consecutive no matter which of its members relayed them to us.
+pub struct MessageSequencer {
+    incoming: BTreeMap<Route, IncomingSequence>,
+}
+
+impl MessageSequencer {
+    pub fn new() -> Self {
+        Self {
+            incoming: BTreeMap::new(),
+        }
+    }
+
+    /// Handles a message with sequence number `seq` and returns the messages which can now be
+    /// delivered, in order. A message arriving ahead of the ones before it is held back until they
+    /// arrive or `REORDER_TIMEOUT` elapses; this and a message arriving after that are reported.
+    pub fn receive(
+        &mut self,
+        src: Authority<XorName>,
+        dst: Authority<XorName>,
+        seq: u64,
+        msg: UserMessage,
+    ) -> (Vec<UserMessage>, Option<OutOfOrder>) {
+        let sequence = match self.incoming.entry((src, dst)) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                // The first message we see on this route, e.g. because we only just joined.
+                let _ = entry.insert(IncomingSequence {
+                    next: seq + 1,
+                    held: BTreeMap::new(),
+                    last_received: Instant::now(),
+                });
+                return (vec![msg], None);
+            }
+        };
+        sequence.last_received = Instant::now();
+
+        if seq == sequence.next {
+            sequence.next += 1;
+            let mut ready = vec![msg];
+            ready.extend(release_consecutive(sequence));
+            return (ready, None);
+        }
+
+        let out_of_order = OutOfOrder {
+            src,
+            dst,
+            expected: sequence.next,
+            received: seq,
+        };
+        if seq < sequence.next {
+            (vec![msg], Some(out_of_order))
+        } else {
+            let _ = sequence.held.insert(seq, (Instant::now(), msg));
+            (vec![], Some(out_of_order))
+        }
+    }
+
+    /// Gives up waiting for missing messages which held back others for longer than
+    /// `REORDER_TIMEOUT`, and returns the messages which can now be delivered, in order. Also
+    /// forgets routes we haven't received anything on for a while.
+    pub fn release_expired(
+        &mut self,
+    ) -> Vec<(Authority<XorName>, Authority<XorName>, UserMessage)> {
+        let mut released = Vec::new();
+        for (&(src, dst), sequence) in &mut self.incoming {
+            while let Some(seq) = first_expired(sequence) {
+                sequence.next = seq;
+                released.extend(
+                    release_consecutive(sequence)
+                        .into_iter()
+                        .map(|msg| (src, dst, msg)),
+                );
+            }
+        }
+
+        let stale_routes: Vec<_> = self
+            .incoming
+            .iter()
+            .filter(|(_, sequence)| {
+                sequence.held.is_empty()
+                    && sequence.last_received.elapsed() >= SEQUENCE_EXPIRY_DURATION
+            })
+            .map(|(route, _)| *route)
+            .collect();
+        for route in stale_routes {
+            let _ = self.incoming.remove(&route);
+        }
+        released
+    }
+}
+
+impl Default for MessageSequencer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn first_expired(sequence: &IncomingSequence) -> Option<u64> {
+    sequence
+        .held
+        .iter()
+        .next()
+        .filter(|(_, (held_at, _))| held_at.elapsed() >= REORDER_TIMEOUT)
+        .map(|(seq, _)| *seq)
+}
+
+fn release_consecutive(sequence: &mut IncomingSequence) -> Vec<UserMessage> {
+    let mut ready = Vec::new();
+    while let Some((_, msg)) = sequence.held.remove(&sequence.next) {
+        sequence.next += 1;
+        ready.push(msg);
+    }
+    ready
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::Request;
+    use crate::types::MessageId;
+    use fake_clock::FakeClock;
+    use rand;
+
+    fn msg(index: u64) -> UserMessage {
+        UserMessage::Request(Request::Refresh(vec![index as u8], MessageId::new()))
+    }
+
+    fn route() -> Route {
+        (
+            Authority::NaeManager(rand::random()),
+            Authority::ClientManager(rand::random()),
+        )
+    }
+
+    #[test]
+    fn reorders_messages() {
+        let mut sequencer = MessageSequencer::new();
+        let (src, dst) = route();
+
+        assert_eq!(sequencer.receive(src, dst, 5, msg(5)), (vec![msg(5)], None));
+        let (ready, out_of_order) = sequencer.receive(src, dst, 7, msg(7));
+        assert!(ready.is_empty());
+        assert_eq!(
+            out_of_order,
+            Some(OutOfOrder {
+                src,
+                dst,
+                expected: 6,
+                received: 7,
+            })
+        );
+        assert_eq!(
+            sequencer.receive(src, dst, 6, msg(6)),
+            (vec![msg(6), msg(7)], None)
+        );
+        assert_eq!(sequencer.receive(src, dst, 8, msg(8)), (vec![msg(8)], None));
+    }
+
+    #[test]
+    fn releases_held_messages_after_timeout() {
+        let mut sequencer = MessageSequencer::new();
+        let (src, dst) = route();
+
+        let _ = sequencer.receive(src, dst, 0, msg(0));
+        let _ = sequencer.receive(src, dst, 2, msg(2));
+        let _ = sequencer.receive(src, dst, 3, msg(3));
+        assert!(sequencer.release_expired().is_empty());
+
+        FakeClock::advance_time(REORDER_TIMEOUT.as_secs() * 1000 + 1000);
+        assert_eq!(
+            sequencer.release_expired(),
+            vec![(src, dst, msg(2)), (src, dst, msg(3))]
+        );
+
+        // The missing message arrives late: it's delivered, but reported.
+        let (ready, out_of_order) = sequencer.receive(src, dst, 1, msg(1));
+        assert_eq!(ready, vec![msg(1)]);
+        assert_eq!(
+            out_of_order.map(|out_of_order| out_of_order.expected),
+            Some(4)
+        );
+    }
+}
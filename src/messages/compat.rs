@@ -0,0 +1,155 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Versioning of the wire format, so that nodes of adjacent releases can form sections together
+//! during a rolling upgrade.
+//!
+//! A message is serialised behind a header holding `MAGIC` and the protocol version. Each node
+//! announces the versions it supports in its `Capabilities` and sends to a peer in the highest
+//! version both of them support. When the layout of `Message` changes, the protocol version is
+//! bumped, and the previous layout is kept here and converted to the current one when decoding,
+//! for as long as its version is within the supported window.
+//!
+//! Version 1 is the format from before the header was introduced: the bare serialised `Message`,
+//! which never starts with `MAGIC`. Its layout differs from the current one and no conversion is
+//! kept for it, so peers speaking only version 1 are refused.
+
+use super::Message;
+use crate::{
+    codec::{Codec, CodecError, WireCodec},
+    error::RoutingError,
+};
+use std::{cmp, ops::RangeInclusive};
+
+/// The protocol version spoken by this release.
+pub const PROTOCOL_VERSION: u16 = 2;
+/// The oldest protocol version this release can still decode and send.
+pub const MIN_PROTOCOL_VERSION: u16 = 2;
+/// The version of the format without a header, spoken by peers which don't announce a version.
+/// This release no longer supports it.
+pub const LEGACY_PROTOCOL_VERSION: u16 = 1;
+
+const MAGIC: &[u8] = b"SAFE";
+const HEADER_LEN: usize = 6;
+
+/// The protocol versions this release supports.
+pub fn supported_versions() -> RangeInclusive<u16> {
+    MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION
+}
+
+/// Returns the highest version we and a peer supporting `theirs` can both speak, or an error if
+/// we have none in common.
+pub fn negotiate(theirs: RangeInclusive<u16>) -> Result<u16, RoutingError> {
+    let version = cmp::min(PROTOCOL_VERSION, *theirs.end());
+    if version < cmp::max(MIN_PROTOCOL_VERSION, *theirs.start()) {
+        Err(RoutingError::UnsupportedProtocolVersion(*theirs.end()))
+    } else {
+        Ok(version)
+    }
+}
+
+/// Serialises `message` with the wire codec, in the format of protocol `version`.
+pub fn serialise(message: &Message, version: u16) -> Result<Vec<u8>, CodecError> {
+    let payload = WireCodec::encode(message)?;
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&[(version >> 8) as u8, version as u8]);
+    bytes.extend(payload);
+    Ok(bytes)
+}
+
+/// Deserialises a message in any supported protocol version, and returns it with its version.
+/// Fails with `UnsupportedProtocolVersion` if the version is outside our window.
+pub fn deserialise(bytes: &[u8]) -> Result<(u16, Message), RoutingError> {
+    let (version, payload) = split_header(bytes);
+    if !supported_versions().contains(&version) {
+        return Err(RoutingError::UnsupportedProtocolVersion(version));
+    }
+    // All supported versions share the current layout of `Message` so far.
+    Ok((version, WireCodec::decode(payload)?))
+}
+
+/// Returns the serialised `Message` without the header, if any.
+pub fn payload(bytes: &[u8]) -> &[u8] {
+    split_header(bytes).1
+}
+
+fn split_header(bytes: &[u8]) -> (u16, &[u8]) {
+    if bytes.len() >= HEADER_LEN && bytes.starts_with(MAGIC) {
+        let version = (u16::from(bytes[4]) << 8) | u16::from(bytes[5]);
+        (version, &bytes[HEADER_LEN..])
+    } else {
+        (LEGACY_PROTOCOL_VERSION, bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::FullId;
+    use crate::messages::{DirectMessage, SignedDirectMessage};
+    use unwrap::unwrap;
+
+    fn message() -> Message {
+        let content = DirectMessage::ParsecPoke(7);
+        Message::Direct(unwrap!(SignedDirectMessage::new(content, &FullId::new())))
+    }
+
+    #[test]
+    fn round_trip_in_all_supported_versions() {
+        let message = message();
+        for version in supported_versions() {
+            let bytes = unwrap!(serialise(&message, version));
+            let (decoded_version, decoded) = unwrap!(deserialise(&bytes));
+            assert_eq!(decoded_version, version);
+            assert_eq!(decoded, message);
+        }
+
+        assert_eq!(
+            payload(&unwrap!(serialise(&message, PROTOCOL_VERSION))),
+            &unwrap!(WireCodec::encode(&message))[..]
+        );
+    }
+
+    #[test]
+    fn legacy_messages_are_rejected() {
+        // Nodes before versioning sent the message without a header, in a layout we can't decode.
+        let legacy = unwrap!(WireCodec::encode(&message()));
+        match deserialise(&legacy) {
+            Err(RoutingError::UnsupportedProtocolVersion(version)) => {
+                assert_eq!(version, LEGACY_PROTOCOL_VERSION)
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn unsupported_versions_are_rejected() {
+        let bytes = unwrap!(serialise(&message(), PROTOCOL_VERSION + 1));
+        match deserialise(&bytes) {
+            Err(RoutingError::UnsupportedProtocolVersion(version)) => {
+                assert_eq!(version, PROTOCOL_VERSION + 1)
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn negotiation() {
+        assert!(negotiate(LEGACY_PROTOCOL_VERSION..=LEGACY_PROTOCOL_VERSION).is_err());
+        assert_eq!(
+            unwrap!(negotiate(LEGACY_PROTOCOL_VERSION..=PROTOCOL_VERSION)),
+            PROTOCOL_VERSION
+        );
+        assert_eq!(
+            unwrap!(negotiate(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION + 5)),
+            PROTOCOL_VERSION
+        );
+        assert!(negotiate(PROTOCOL_VERSION + 1..=PROTOCOL_VERSION + 2).is_err());
+    }
+}
@@ -7,11 +7,14 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    error::{BootstrapResponseError, RoutingError},
+    chain::SectionProofChain,
+    error::{BootstrapResponseError, JoinDenialReason, RoutingError},
     id::{FullId, PublicId},
-    messages::SignedRoutingMessage,
+    messages::{compat, SignedRoutingMessage, CONSENSUS_PRIORITY, RELOCATE_PRIORITY},
     parsec,
     routing_table::Authority,
+    session::SessionToken,
+    time::Duration,
     xor_name::XorName,
 };
 use maidsafe_utilities::serialisation::serialise;
@@ -20,6 +23,7 @@ use std::{
     fmt::{self, Debug, Formatter},
     hash::{Hash, Hasher},
     mem,
+    ops::RangeInclusive,
 };
 
 /// Direct message content.
@@ -83,6 +87,86 @@ pub enum DirectMessage {
     ParsecRequest(u64, parsec::Request),
     /// Parsec response message
     ParsecResponse(u64, parsec::Response),
+    /// Announces the optional protocol features we support. Sent once to each peer we exchange
+    /// direct messages with.
+    Capabilities(Capabilities),
+    /// Sent from a member of the section a candidate is joining to the candidate if it refuses to
+    /// approve it.
+    JoinDenied {
+        /// Why the candidate is refused.
+        reason: JoinDenialReason,
+        /// How long the reason is expected to persist.
+        retry_after: Option<Duration>,
+    },
+    /// Periodically sent from an elder to an elder of each neighbouring section, so the two
+    /// sections learn about each other's keys even if no other messages flow between them.
+    SectionList {
+        /// The sender's section's keys, starting from the latest one the recipient's section is
+        /// known to have.
+        proof_chain: SectionProofChain,
+        /// The version of the recipient's section key the sender's section knows.
+        their_version: u64,
+    },
+    /// Sent from a client to its proxy after bootstrapping, to resume the session of the token
+    /// instead of asking its ClientManager for a new one.
+    ResumeSession(SessionToken),
+    /// Sent from the proxy to a client in response to `ResumeSession`. If `false`, the token was
+    /// refused and the client has to ask for a new one.
+    ResumeSessionResponse(bool),
+}
+
+/// Optional protocol features supported by a node.
+///
+/// Serialised as a bit set so that nodes ignore features introduced after their version, rather
+/// than failing to parse the whole announcement. The top 32 bits hold the lowest and highest wire
+/// protocol version the node supports.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Capabilities(u64);
+
+impl Capabilities {
+    /// Accepts hop messages encrypted to it as `Message::EncryptedHop`.
+    const HOP_ENCRYPTION: u64 = 1;
+    const MIN_VERSION_SHIFT: u64 = 32;
+    const MAX_VERSION_SHIFT: u64 = 48;
+
+    /// The capabilities of this version of routing.
+    pub fn ours() -> Self {
+        Capabilities(
+            Self::HOP_ENCRYPTION
+                | (u64::from(compat::MIN_PROTOCOL_VERSION) << Self::MIN_VERSION_SHIFT)
+                | (u64::from(compat::PROTOCOL_VERSION) << Self::MAX_VERSION_SHIFT),
+        )
+    }
+
+    /// Whether hop messages to the peer can be encrypted.
+    pub fn hop_encryption(self) -> bool {
+        self.0 & Self::HOP_ENCRYPTION != 0
+    }
+
+    /// The wire protocol versions the peer supports. Peers which don't announce any only speak
+    /// the legacy version, which we don't support any more.
+    pub fn protocol_versions(self) -> RangeInclusive<u16> {
+        let max = (self.0 >> Self::MAX_VERSION_SHIFT) as u16;
+        if max == 0 {
+            compat::LEGACY_PROTOCOL_VERSION..=compat::LEGACY_PROTOCOL_VERSION
+        } else {
+            (self.0 >> Self::MIN_VERSION_SHIFT) as u16..=max
+        }
+    }
+}
+
+impl DirectMessage {
+    /// The priority this message is sent with: signatures take the priority of the message they
+    /// sign, resource proofs are bulk traffic and everything else keeps the network running.
+    pub fn priority(&self) -> u8 {
+        match *self {
+            DirectMessage::MessageSignature(ref msg) => msg.priority(),
+            DirectMessage::ResourceProof { .. }
+            | DirectMessage::ResourceProofResponse { .. }
+            | DirectMessage::ResourceProofResponseReceipt => RELOCATE_PRIORITY,
+            _ => CONSENSUS_PRIORITY,
+        }
+    }
 }
 
 impl Debug for DirectMessage {
@@ -121,6 +205,28 @@ impl Debug for DirectMessage {
             ParsecRequest(ref v, _) => write!(formatter, "ParsecRequest({}, _)", v),
             ParsecResponse(ref v, _) => write!(formatter, "ParsecResponse({}, _)", v),
             ParsecPoke(ref v) => write!(formatter, "ParsecPoke({})", v),
+            Capabilities(ref capabilities) => write!(formatter, "{:?}", capabilities),
+            JoinDenied {
+                reason,
+                retry_after,
+            } => write!(
+                formatter,
+                "JoinDenied {{ reason: {:?}, retry_after: {:?} }}",
+                reason, retry_after
+            ),
+            SectionList {
+                ref proof_chain,
+                their_version,
+            } => write!(
+                formatter,
+                "SectionList {{ {:?}, their_version: {} }}",
+                proof_chain.last_public_key_info(),
+                their_version
+            ),
+            ResumeSession(ref token) => write!(formatter, "ResumeSession({:?})", token.client_id()),
+            ResumeSessionResponse(resumed) => {
+                write!(formatter, "ResumeSessionResponse({})", resumed)
+            }
         }
     }
 }
@@ -172,6 +278,23 @@ impl Hash for DirectMessage {
                 leading_zero_bytes.hash(state);
             }
             ParsecPoke(version) => version.hash(state),
+            Capabilities(capabilities) => capabilities.hash(state),
+            JoinDenied {
+                reason,
+                retry_after,
+            } => {
+                reason.hash(state);
+                retry_after.hash(state);
+            }
+            SectionList {
+                ref proof_chain,
+                their_version,
+            } => {
+                proof_chain.hash(state);
+                their_version.hash(state);
+            }
+            ResumeSession(ref token) => token.hash(state),
+            ResumeSessionResponse(resumed) => resumed.hash(state),
             ParsecRequest(version, ref request) => {
                 version.hash(state);
                 // Fake hash via serialisation
@@ -276,3 +399,77 @@ mod implementation {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{Codec, WireCodec};
+    use hex::{FromHex, ToHex};
+    use unwrap::unwrap;
+
+    #[cfg(not(feature = "cbor"))]
+    const GOLDEN: &str = include_str!("golden/bincode.txt");
+    #[cfg(feature = "cbor")]
+    const GOLDEN: &str = include_str!("golden/cbor.txt");
+
+    fn cases() -> Vec<(&'static str, DirectMessage)> {
+        vec![
+            ("bootstrap_request", DirectMessage::BootstrapRequest),
+            (
+                "bootstrap_response_ok",
+                DirectMessage::BootstrapResponse(Ok(())),
+            ),
+            (
+                "bootstrap_response_err",
+                DirectMessage::BootstrapResponse(Err(BootstrapResponseError::TooFewPeers)),
+            ),
+            ("connection_response", DirectMessage::ConnectionResponse),
+            (
+                "resource_proof",
+                DirectMessage::ResourceProof {
+                    seed: vec![1, 2, 3],
+                    target_size: 4,
+                    difficulty: 5,
+                },
+            ),
+            (
+                "resource_proof_response_receipt",
+                DirectMessage::ResourceProofResponseReceipt,
+            ),
+            ("parsec_poke", DirectMessage::ParsecPoke(5)),
+            ("capabilities", DirectMessage::Capabilities(Capabilities(1))),
+            (
+                "join_denied",
+                DirectMessage::JoinDenied {
+                    reason: JoinDenialReason::Throttled,
+                    retry_after: Some(Duration::from_secs(2)),
+                },
+            ),
+        ]
+    }
+
+    // Any change to these encodings breaks compatibility with other implementations, so it must
+    // come with a protocol version bump and updated golden files.
+    #[test]
+    fn golden_encodings() {
+        let golden: Vec<(&str, &str)> = GOLDEN
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let mut parts = line.split_whitespace();
+                (unwrap!(parts.next()), unwrap!(parts.next()))
+            })
+            .collect();
+
+        let cases = cases();
+        assert_eq!(golden.len(), cases.len());
+        for ((name, message), (golden_name, golden_hex)) in cases.into_iter().zip(golden) {
+            assert_eq!(name, golden_name);
+            let encoded = unwrap!(WireCodec::encode(&message));
+            assert_eq!(encoded.to_hex(), golden_hex, "Encoding of {} changed", name);
+            let bytes: Vec<u8> = unwrap!(FromHex::from_hex(golden_hex));
+            let decoded: DirectMessage = unwrap!(WireCodec::decode(&bytes));
+            assert!(decoded == message, "Decoding of {} changed", name);
+        }
+    }
+}
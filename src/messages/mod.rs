@@ -6,22 +6,29 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+pub mod compat;
 mod direct;
 mod request;
 mod response;
+mod sealed;
 
 pub use self::{
-    direct::{DirectMessage, SignedDirectMessage},
+    direct::{Capabilities, DirectMessage, SignedDirectMessage},
     request::Request,
-    response::{AccountInfo, Response},
+    response::{AccountInfo, RelayedResponse, Response},
+    sealed::SealedRequest,
 };
+#[cfg(feature = "node")]
+use crate::chain::{Chain, DeliveryScope};
 use crate::{
-    chain::{Chain, GenesisPfxInfo, SectionInfo, SectionKeyInfo, SectionProofChain},
-    error::{Result, RoutingError},
+    chain::{GenesisPfxInfo, MembershipStatement, SectionInfo, SectionKeyInfo, SectionProofChain},
+    error::{JoinDenialReason, Result, RoutingError},
     event::Event,
     id::{FullId, PublicId},
+    rate_limiter::RateLimits,
     routing_table::{Authority, Prefix},
     sha3::Digest256,
+    time::Duration,
     types::MessageId,
     xor_name::XorName,
     BlsPublicKeySet, BlsPublicKeyShare, BlsSignature, BlsSignatureShare, XorTargetInterval,
@@ -29,13 +36,16 @@ use crate::{
 use hex_fmt::HexFmt;
 use log::LogLevel;
 use maidsafe_utilities::serialisation::serialise;
-use safe_crypto::{self, SecretSignKey, Signature};
+use safe_crypto::{self, SecretSignKey, Signature, SymmetricKey};
 use std::{
     collections::{BTreeMap, BTreeSet},
     fmt::{self, Debug, Display, Formatter},
     mem,
 };
 
+/// Messages the network's consensus depends on, such as acknowledgements, section infos and parsec
+/// gossip, have the highest priority: They are sent and relayed ahead of any data.
+pub const CONSENSUS_PRIORITY: u8 = 0;
 /// Get and refresh messages from nodes have a high priority: They relocate data under churn and are
 /// critical to prevent data loss.
 pub const RELOCATE_PRIORITY: u8 = 1;
@@ -57,6 +67,20 @@ pub enum Message {
     Direct(SignedDirectMessage),
     /// A message sent across the network (in transit)
     Hop(HopMessage),
+    /// A `HopMessage` encrypted to its next hop, sent instead of `Hop` to peers which announced
+    /// support for it.
+    EncryptedHop(EncryptedHopMessage),
+}
+
+impl Message {
+    /// The priority this message is queued and sent with. Lower numbers go first.
+    pub fn priority(&self) -> u8 {
+        match *self {
+            Message::Direct(ref msg) => msg.content().priority(),
+            Message::Hop(ref msg) => msg.priority,
+            Message::EncryptedHop(ref msg) => msg.priority(),
+        }
+    }
 }
 
 /// An individual hop message that represents a part of the route of a message in transit.
@@ -69,12 +93,102 @@ pub enum Message {
 pub struct HopMessage {
     /// Wrapped signed message.
     pub content: SignedRoutingMessage,
+    /// The priority the message is queued and relayed with, taken from its content.
+    pub priority: u8,
 }
 
 impl HopMessage {
     /// Wrap `content` for transmission to the next hop and sign it.
     pub fn new(content: SignedRoutingMessage) -> Result<HopMessage> {
-        Ok(HopMessage { content: content })
+        let priority = content.priority();
+        Ok(HopMessage { content, priority })
+    }
+
+    /// Returns a hop message from `sender` carrying a `PutIData` request for a chunk of
+    /// `payload_len` bytes. Useful for benchmarks.
+    #[cfg(any(test, feature = "mock_base"))]
+    pub fn put_idata(sender: &FullId, payload_len: usize) -> Result<HopMessage> {
+        let data = crate::data::ImmutableData::new((0..payload_len).map(|i| i as u8).collect());
+        let msg = RoutingMessage {
+            src: Authority::ManagedNode(*sender.public_id().name()),
+            dst: Authority::NaeManager(*data.name()),
+            content: MessageContent::UserMessage {
+                content: UserMessage::Request(Request::PutIData {
+                    data,
+                    msg_id: MessageId::new(),
+                }),
+                priority: DEFAULT_PRIORITY,
+                seq: None,
+            },
+            dst_min_version: None,
+        };
+        HopMessage::new(SignedRoutingMessage::single_source(msg, sender)?)
+    }
+}
+
+/// A `HopMessage` encrypted so that only its possible next hops can read it, independently of any
+/// security of the transport.
+///
+/// The message is encrypted once with a fresh symmetric key, and that key is encrypted to each
+/// recipient using the secret shared between the sender and the recipient. This way the same bytes
+/// can be resent to the next target if sending to one of them fails.
+#[cfg_attr(feature = "mock_serialise", derive(Clone))]
+#[derive(Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct EncryptedHopMessage {
+    sender: PublicId,
+    keys: BTreeMap<XorName, Vec<u8>>,
+    payload: Vec<u8>,
+    /// The priority of the encrypted message, in the clear so it can be queued without
+    /// decrypting it.
+    priority: u8,
+}
+
+impl EncryptedHopMessage {
+    /// Encrypts `msg` from `sender` so that any of the `recipients` can decrypt it.
+    pub fn new<'a, I>(msg: &HopMessage, sender: &FullId, recipients: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = &'a PublicId>,
+    {
+        let key = SymmetricKey::new();
+        let payload = key.encrypt(msg)?;
+        let keys = recipients
+            .into_iter()
+            .map(|pub_id| {
+                let shared_secret = sender
+                    .encrypting_private_key()
+                    .shared_secret(pub_id.encrypting_public_key());
+                Ok((*pub_id.name(), shared_secret.encrypt(&key)?))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(EncryptedHopMessage {
+            sender: *sender.public_id(),
+            keys,
+            payload,
+            priority: msg.priority,
+        })
+    }
+
+    /// Returns the priority of the encrypted message.
+    pub fn priority(&self) -> u8 {
+        self.priority
+    }
+
+    /// Decrypts the message, if `recipient` is one of the nodes it was encrypted to.
+    pub fn open(&self, recipient: &FullId) -> Result<HopMessage> {
+        let encrypted_key = self
+            .keys
+            .get(recipient.public_id().name())
+            .ok_or(RoutingError::InvalidMessage)?;
+        let shared_secret = recipient
+            .encrypting_private_key()
+            .shared_secret(self.sender.encrypting_public_key());
+        let key: SymmetricKey = shared_secret.decrypt(encrypted_key)?;
+        let msg: HopMessage = key.decrypt(&self.payload)?;
+        if msg.priority != self.priority {
+            return Err(RoutingError::InvalidMessage);
+        }
+        Ok(msg)
     }
 }
 
@@ -215,6 +329,7 @@ impl SignedRoutingMessage {
     }
 
     /// Creates a `SignedRoutingMessage` security metadata from a single source
+    #[cfg(feature = "node")]
     pub fn single_source(
         content: RoutingMessage,
         full_id: &FullId,
@@ -272,6 +387,7 @@ impl SignedRoutingMessage {
     }
 
     /// Checks if the message can be trusted according to the Chain
+    #[cfg(feature = "node")]
     pub fn check_trust(&self, chain: &Chain) -> bool {
         match self.security_metadata {
             SecurityMetadata::Full(ref security_metadata) => {
@@ -294,6 +410,32 @@ impl SignedRoutingMessage {
         }
     }
 
+    /// Replaces the proof chain of a fully signed message with `proof_chain`, which must end with
+    /// the same key. Returns whether it was replaced.
+    #[cfg(feature = "node")]
+    pub fn reprove(&mut self, proof_chain: SectionProofChain) -> bool {
+        match self.security_metadata {
+            SecurityMetadata::Full(ref mut security_metadata)
+                if security_metadata.last_public_key_info()
+                    == proof_chain.last_public_key_info() =>
+            {
+                security_metadata.proof = proof_chain;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the proof chain of the source section, if the message is fully signed by one.
+    pub fn proof_chain(&self) -> Option<&SectionProofChain> {
+        match self.security_metadata {
+            SecurityMetadata::None | SecurityMetadata::Partial(_) | SecurityMetadata::Single(_) => {
+                None
+            }
+            SecurityMetadata::Full(ref security_metadata) => Some(security_metadata.proof_chain()),
+        }
+    }
+
     /// Adds a proof if it is new, without validating it.
     pub fn add_signature_share(
         &mut self,
@@ -306,6 +448,7 @@ impl SignedRoutingMessage {
     }
 
     /// Adds all signatures from the given message, without validating them.
+    #[cfg(feature = "node")]
     pub fn add_signature_shares(&mut self, mut msg: SignedRoutingMessage) {
         if self.content.src.is_multiple() {
             if let (
@@ -365,6 +508,7 @@ impl SignedRoutingMessage {
     }
 
     /// Returns whether there are enough signatures from the sender.
+    #[cfg(feature = "node")]
     pub fn check_fully_signed(&mut self) -> bool {
         if !self.has_enough_sigs() {
             return false;
@@ -410,6 +554,7 @@ impl SignedRoutingMessage {
 
     // Returns a list of all invalid signatures (not from an expected key or not cryptographically
     // valid).
+    #[cfg(feature = "node")]
     fn find_invalid_sigs(&self, signed_bytes: &[u8]) -> Vec<BlsPublicKeyShare> {
         match self.security_metadata {
             SecurityMetadata::None | SecurityMetadata::Full(_) | SecurityMetadata::Single(_) => {
@@ -432,6 +577,7 @@ impl SignedRoutingMessage {
 
     // Returns true if there are enough signatures (note that this method does not verify the
     // signatures, it only counts them).
+    #[cfg(feature = "node")]
     fn has_enough_sigs(&self) -> bool {
         match &self.security_metadata {
             SecurityMetadata::None => !self.content.src.is_multiple(),
@@ -458,6 +604,10 @@ pub struct RoutingMessage {
     pub dst: Authority<XorName>,
     /// The message content
     pub content: MessageContent,
+    /// The minimum version of the destination section's info the message may be delivered to, if
+    /// `dst` is an `Authority::Section`. Relays only knowing an older version hold the message
+    /// until they learn about a recent enough one instead of delivering it to stale members.
+    pub dst_min_version: Option<u64>,
 }
 
 impl RoutingMessage {
@@ -589,6 +739,10 @@ pub enum MessageContent {
         content: UserMessage,
         /// The message priority.
         priority: u8,
+        /// Sequence number of the message among those sent from its section source authority to
+        /// its destination, used by the recipients to restore their order. `None` if the source
+        /// isn't a section.
+        seq: Option<u64>,
     },
     /// Approves the joining node as a routing node.
     ///
@@ -601,6 +755,151 @@ pub enum MessageContent {
         /// The version acknowledged.
         ack_version: u64,
     },
+    /// Refuses the relocation of a joining node.
+    ///
+    /// Sent from Group Y to the joining node instead of `RelocateResponse`.
+    JoinDenied {
+        /// Why the node is refused.
+        reason: JoinDenialReason,
+        /// How long the reason is expected to persist.
+        retry_after: Option<Duration>,
+        /// The identifier of the `Relocate` message this replies to.
+        message_id: MessageId,
+    },
+    /// Asks our section for a statement of how long we have been a member.
+    ///
+    /// Sent from a node to its own section.
+    MembershipStatementRequest {
+        /// The message's unique identifier.
+        message_id: MessageId,
+    },
+    /// The section-signed statement of how long the node has been a member.
+    ///
+    /// Sent from the section to the node in reply to `MembershipStatementRequest`.
+    MembershipStatementResponse {
+        /// The statement about the node.
+        statement: MembershipStatement,
+        /// The identifier of the `MembershipStatementRequest` message this replies to.
+        message_id: MessageId,
+    },
+    /// Asks another section to take over responsibility for a range of names.
+    ///
+    /// Sent from the section handing off the range to the section taking it over, which replies
+    /// with `HandoffPrepared` once its vaults are ready.
+    HandoffPrepare {
+        /// The range of names whose responsibility is transferred.
+        range: Prefix<XorName>,
+        /// The handoff's unique identifier.
+        handoff_id: MessageId,
+    },
+    /// Confirms that our section is ready to take over the range from `HandoffPrepare`.
+    ///
+    /// Sent from the section taking over the range to the one handing it off.
+    HandoffPrepared {
+        /// The range of names whose responsibility is transferred.
+        range: Prefix<XorName>,
+        /// The identifier of the handoff.
+        handoff_id: MessageId,
+    },
+    /// Completes the handoff: the recipients are now responsible for the range.
+    ///
+    /// Sent from the section handing off the range in reply to `HandoffPrepared`.
+    HandoffCommit {
+        /// The range of names whose responsibility is transferred.
+        range: Prefix<XorName>,
+        /// The identifier of the handoff.
+        handoff_id: MessageId,
+    },
+    /// Confirms that a request was delivered to its destination section. Unlike `AckMessage`,
+    /// which only updates the knowledge of the sections about each other, this goes back to the
+    /// original sender of the request, signed by the destination section.
+    ///
+    /// Sent from the destination of a request to its source.
+    DeliveryAck {
+        /// The identifier of the delivered request.
+        msg_id: MessageId,
+    },
+    /// Lists names of data the sender holds for our section, so a new member can request what it
+    /// is missing. Large inventories are split into several messages.
+    ///
+    /// Sent from a member of a section to a new member of it, after a `Refresh`.
+    DataInventory {
+        /// The names of the data.
+        names: BTreeSet<XorName>,
+    },
+    /// Asks the recipient to republish the data with the given names to the sender.
+    ///
+    /// Sent from a new member of a section in reply to a `DataInventory`.
+    RepublishRequest {
+        /// The names of the data.
+        names: BTreeSet<XorName>,
+    },
+    /// Returns a message whose proof chain didn't start from a key we know to its source, with
+    /// our keys and the version of its key we know. The source updates its knowledge of us and
+    /// resends the message with a proof starting from that version.
+    ///
+    /// Sent from a member of the destination section to the source of the bounced message.
+    Bounce {
+        /// The message we couldn't trust.
+        message: Box<SignedRoutingMessage>,
+        /// Our section's keys, starting from the latest one the source section is known to have.
+        proof_chain: SectionProofChain,
+        /// The version of the source section's key our section knows.
+        their_version: u64,
+    },
+    /// Tells a neighbouring section that ours agreed to upgrade the protocol, so it votes for the
+    /// same upgrade unless it knows of a newer one, and passes it on to its own neighbours.
+    ///
+    /// Sent from a section to its neighbours after accumulating a `NetworkEvent::UpgradeSignal`.
+    UpgradeSignal {
+        /// The protocol version to upgrade to.
+        version: u16,
+        /// The version of the section info from which on the upgrade is active.
+        activation_version: u64,
+    },
+    /// Asks the client's ClientManager section for a session token.
+    ///
+    /// Sent from a client to its ClientManager after bootstrapping, unless it resumes a session.
+    SessionRequest {
+        /// The message's unique identifier.
+        message_id: MessageId,
+    },
+    /// Grants the client a session, whose token is this message signed by the section.
+    ///
+    /// Sent from the ClientManager section in reply to `SessionRequest`.
+    SessionGranted {
+        /// The limits the client's requests are held to.
+        limits: RateLimits,
+        /// The identifier of the `SessionRequest` message this replies to.
+        message_id: MessageId,
+    },
+    /// Asks the section managing the client for the current key of the section managing `name`.
+    ///
+    /// Sent from a client which needs to seal a request to a section whose key it doesn't know.
+    SectionKeyRequest {
+        /// The name whose section's key is requested.
+        name: XorName,
+        /// The message's unique identifier.
+        message_id: MessageId,
+    },
+    /// The key of the requested section, vouched for by the client's manager section, which the
+    /// client already trusts.
+    ///
+    /// Sent from the client's manager section in reply to `SectionKeyRequest`.
+    SectionKeyResponse {
+        /// The latest key we know of the section managing the requested name.
+        key_info: SectionKeyInfo,
+        /// The identifier of the `SectionKeyRequest` message this replies to.
+        message_id: MessageId,
+    },
+    /// Tells the section managing `auditee` that it failed `MAX_FAILED_AUDITS` data audits.
+    ///
+    /// Sent from a section after accumulating a `NetworkEvent::FailedAudits` for an auditee it
+    /// doesn't manage.
+    FailedAudits {
+        /// The node or data manager group which failed the audits.
+        auditee: Authority<XorName>,
+    },
 }
 
 impl MessageContent {
@@ -608,7 +907,58 @@ impl MessageContent {
     pub fn priority(&self) -> u8 {
         match *self {
             MessageContent::UserMessage { priority, .. } => priority,
-            _ => 0,
+            MessageContent::DataInventory { .. } | MessageContent::RepublishRequest { .. } => {
+                RELOCATE_PRIORITY
+            }
+            _ => CONSENSUS_PRIORITY,
+        }
+    }
+
+    /// Which members of the destination section need this message. The ones only acted on by
+    /// voting go to the elders alone.
+    #[cfg(feature = "node")]
+    pub fn delivery_scope(&self) -> DeliveryScope {
+        match *self {
+            MessageContent::ExpectCandidate { .. }
+            | MessageContent::NeighbourInfo(_)
+            | MessageContent::Merge(_)
+            | MessageContent::AckMessage { .. }
+            | MessageContent::UpgradeSignal { .. }
+            | MessageContent::SessionRequest { .. }
+            | MessageContent::SectionKeyRequest { .. }
+            | MessageContent::FailedAudits { .. } => DeliveryScope::Elders,
+            _ => DeliveryScope::AllMembers,
+        }
+    }
+
+    /// The identifier of the message or of the exchange it is part of, if it has one.
+    pub fn message_id(&self) -> Option<&MessageId> {
+        match *self {
+            MessageContent::Relocate { ref message_id }
+            | MessageContent::ExpectCandidate { ref message_id, .. }
+            | MessageContent::RelocateResponse { ref message_id, .. }
+            | MessageContent::JoinDenied { ref message_id, .. }
+            | MessageContent::MembershipStatementRequest { ref message_id }
+            | MessageContent::MembershipStatementResponse { ref message_id, .. }
+            | MessageContent::SessionRequest { ref message_id }
+            | MessageContent::SessionGranted { ref message_id, .. }
+            | MessageContent::SectionKeyRequest { ref message_id, .. }
+            | MessageContent::SectionKeyResponse { ref message_id, .. } => Some(message_id),
+            MessageContent::ConnectionRequest { ref msg_id, .. }
+            | MessageContent::DeliveryAck { ref msg_id } => Some(msg_id),
+            MessageContent::HandoffPrepare { ref handoff_id, .. }
+            | MessageContent::HandoffPrepared { ref handoff_id, .. }
+            | MessageContent::HandoffCommit { ref handoff_id, .. } => Some(handoff_id),
+            MessageContent::UserMessage { ref content, .. } => Some(content.message_id()),
+            MessageContent::NeighbourInfo(_)
+            | MessageContent::Merge(_)
+            | MessageContent::NodeApproval(_)
+            | MessageContent::AckMessage { .. }
+            | MessageContent::DataInventory { .. }
+            | MessageContent::RepublishRequest { .. }
+            | MessageContent::Bounce { .. }
+            | MessageContent::UpgradeSignal { .. }
+            | MessageContent::FailedAudits { .. } => None,
         }
     }
 }
@@ -617,8 +967,20 @@ impl Debug for HopMessage {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         write!(
             formatter,
-            "HopMessage {{ content: {:?}, signature: .. }}",
-            self.content
+            "HopMessage {{ content: {:?}, priority: {}, signature: .. }}",
+            self.content, self.priority
+        )
+    }
+}
+
+impl Debug for EncryptedHopMessage {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "EncryptedHopMessage {{ sender: {:?}, recipients: {:?}, payload_len: {} }}",
+            self.sender,
+            self.keys.keys().collect::<Vec<_>>(),
+            self.payload.len()
         )
     }
 }
@@ -681,6 +1043,78 @@ impl Debug for MessageContent {
                 ref src_prefix,
                 ref ack_version,
             } => write!(formatter, "AckMessage({:?}, {})", src_prefix, ack_version),
+            JoinDenied {
+                ref reason,
+                ref retry_after,
+                ref message_id,
+            } => write!(
+                formatter,
+                "JoinDenied({:?}, {:?}, {:?})",
+                reason, retry_after, message_id
+            ),
+            MembershipStatementRequest { ref message_id } => {
+                write!(formatter, "MembershipStatementRequest({:?})", message_id)
+            }
+            MembershipStatementResponse {
+                ref statement,
+                ref message_id,
+            } => write!(
+                formatter,
+                "MembershipStatementResponse({:?}, {:?})",
+                statement, message_id
+            ),
+            HandoffPrepare {
+                ref range,
+                ref handoff_id,
+            } => write!(formatter, "HandoffPrepare({:?}, {:?})", range, handoff_id),
+            HandoffPrepared {
+                ref range,
+                ref handoff_id,
+            } => write!(formatter, "HandoffPrepared({:?}, {:?})", range, handoff_id),
+            HandoffCommit {
+                ref range,
+                ref handoff_id,
+            } => write!(formatter, "HandoffCommit({:?}, {:?})", range, handoff_id),
+            DeliveryAck { ref msg_id } => write!(formatter, "DeliveryAck({:?})", msg_id),
+            DataInventory { ref names } => {
+                write!(formatter, "DataInventory({} names)", names.len())
+            }
+            RepublishRequest { ref names } => {
+                write!(formatter, "RepublishRequest({} names)", names.len())
+            }
+            Bounce {
+                ref message,
+                their_version,
+                ..
+            } => write!(formatter, "Bounce({:?}, {})", message, their_version),
+            UpgradeSignal {
+                version,
+                activation_version,
+            } => write!(
+                formatter,
+                "UpgradeSignal(v{} at v{})",
+                version, activation_version
+            ),
+            SessionRequest { ref message_id } => {
+                write!(formatter, "SessionRequest({:?})", message_id)
+            }
+            SessionGranted {
+                ref limits,
+                ref message_id,
+            } => write!(formatter, "SessionGranted({:?}, {:?})", limits, message_id),
+            SectionKeyRequest {
+                ref name,
+                ref message_id,
+            } => write!(formatter, "SectionKeyRequest({}, {:?})", name, message_id),
+            SectionKeyResponse {
+                ref key_info,
+                ref message_id,
+            } => write!(
+                formatter,
+                "SectionKeyResponse({:?}, {:?})",
+                key_info, message_id
+            ),
+            FailedAudits { ref auditee } => write!(formatter, "FailedAudits({:?})", auditee),
         }
     }
 }
@@ -751,6 +1185,7 @@ mod tests {
     use crate::chain::SectionKeyInfo;
     use crate::data::ImmutableData;
     use crate::id::FullId;
+    use crate::network_params::NetworkParams;
     use crate::routing_table::{Authority, Prefix};
     use crate::types::MessageId;
     use crate::xor_name::XorName;
@@ -768,8 +1203,10 @@ mod tests {
             .into_iter()
             .collect();
         let dummy_sec_info = unwrap!(SectionInfo::new(pub_ids, prefix, None));
-        let dummy_pk_set = BlsPublicKeySet::from_section_info(dummy_sec_info.clone());
-        let dummy_key_info = SectionKeyInfo::from_section_info(&dummy_sec_info);
+        let network_params = NetworkParams::default();
+        let dummy_pk_set =
+            BlsPublicKeySet::from_section_info(dummy_sec_info.clone(), network_params);
+        let dummy_key_info = SectionKeyInfo::from_section_info(&dummy_sec_info, network_params);
         let dummy_proof = SectionProofChain::from_genesis(dummy_key_info);
 
         let msg = RoutingMessage {
@@ -781,6 +1218,7 @@ mod tests {
             content: MessageContent::Relocate {
                 message_id: MessageId::new(),
             },
+            dst_min_version: None,
         };
         let mut signed_msg = unwrap!(SignedRoutingMessage::new(
             msg.clone(),
@@ -823,7 +1261,9 @@ mod tests {
             content: MessageContent::UserMessage {
                 content: user_msg,
                 priority: 0,
+                seq: None,
             },
+            dst_min_version: None,
         };
 
         let src_section_nodes = vec![
@@ -837,8 +1277,9 @@ mod tests {
             prefix,
             None,
         ));
-        let pk_set = BlsPublicKeySet::from_section_info(src_section.clone());
-        let dummy_key_info = SectionKeyInfo::from_section_info(&src_section);
+        let network_params = NetworkParams::default();
+        let pk_set = BlsPublicKeySet::from_section_info(src_section.clone(), network_params);
+        let dummy_key_info = SectionKeyInfo::from_section_info(&src_section, network_params);
         let dummy_proof = SectionProofChain::from_genesis(dummy_key_info);
         let mut signed_msg = unwrap!(SignedRoutingMessage::new(
             msg,
@@ -875,4 +1316,32 @@ mod tests {
             .expect("no signatures")
             .contains_key(&BlsPublicKeyShare(*full_id_3.public_id())));
     }
+
+    #[test]
+    fn encrypted_hop_message() {
+        let sender = FullId::new();
+        let recipients = vec![FullId::new(), FullId::new()];
+        let other = FullId::new();
+        let hop_msg = unwrap!(HopMessage::put_idata(&sender, 100));
+
+        let encrypted = unwrap!(EncryptedHopMessage::new(
+            &hop_msg,
+            &sender,
+            recipients.iter().map(FullId::public_id)
+        ));
+        for recipient in &recipients {
+            assert_eq!(unwrap!(encrypted.open(recipient)), hop_msg);
+        }
+        assert!(encrypted.open(&other).is_err());
+        assert!(encrypted.open(&sender).is_err());
+
+        // The priority is readable without decrypting, but has to match the encrypted message.
+        assert_eq!(hop_msg.priority, DEFAULT_PRIORITY);
+        let mut encrypted = Message::EncryptedHop(encrypted);
+        assert_eq!(encrypted.priority(), DEFAULT_PRIORITY);
+        if let Message::EncryptedHop(ref mut encrypted) = encrypted {
+            encrypted.priority = CONSENSUS_PRIORITY;
+            assert!(encrypted.open(&recipients[0]).is_err());
+        }
+    }
 }
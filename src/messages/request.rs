@@ -6,13 +6,22 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::data::{EntryAction, ImmutableData, MutableData, PermissionSet, User};
+use crate::data::{
+    AccountPacket, AppendEntry, AppendOnlyData, DataId, EntryAction, ImmutableData, KeyPermissions,
+    MutableData, PermissionSet, User,
+};
+use crate::messages::{Response, SealedRequest};
+use crate::messaging::{HeaderQuery, Mailbox};
+use crate::routing_table::Authority;
 use crate::types::MessageId as MsgId;
 use crate::xor_name::XorName;
+use crate::ClientError;
 use safe_crypto::PublicSignKey;
 use std::collections::{BTreeMap, BTreeSet};
 
 /// Request message types
+///
+/// Variants are serialised by their position, so new ones must only ever be added at the end.
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum Request {
     /// Represents a refresh message sent between vaults. Vec<u8> is the message content.
@@ -119,7 +128,8 @@ pub enum Request {
         /// Unique message identifier
         msg_id: MsgId,
     },
-    /// Updates MutableData entries in bulk.
+    /// Updates MutableData entries in bulk. The actions are applied atomically: if any of them is
+    /// invalid, none is applied and the error maps the key of each invalid action to its error.
     MutateMDataEntries {
         /// Network identifier of MutableData
         name: XorName,
@@ -225,6 +235,185 @@ pub enum Request {
         /// Unique message identifier
         msg_id: MsgId,
     },
+
+    // --- Data audits between data managers ---
+    // ==========================
+    /// Challenges the recipient to prove it holds the given data by hashing it with `nonce`.
+    ChallengeData {
+        /// Identifier of the data to prove possession of
+        data_id: DataId,
+        /// Random value to prepend to the data content before hashing
+        nonce: [u8; 32],
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+
+    // --- Client to ClientManager ---
+    // ==========================
+    /// Asks the client's ClientManager to perform a GET from `dst` on the client's behalf and
+    /// forward the section-signed response, which the client can verify with
+    /// `RelayedResponse::verify`.
+    RelayedGet {
+        /// NaeManager the GET should be sent to
+        dst: Authority<XorName>,
+        /// GET request to send; its message ID must be `msg_id`
+        request: Box<Request>,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+
+    // --- AppendOnlyData ---
+    // ==========================
+    /// Creates a new AppendOnlyData in the network.
+    PutAData {
+        /// AppendOnlyData to be stored
+        data: AppendOnlyData,
+        /// Unique message identifier
+        msg_id: MsgId,
+        /// Requester public key
+        requester: PublicSignKey,
+    },
+    /// Fetches whole AppendOnlyData from the network.
+    GetAData {
+        /// Network identifier of AppendOnlyData
+        name: XorName,
+        /// Type tag
+        tag: u64,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+    /// Fetches the entries of AppendOnlyData appended at or after the given version.
+    GetADataEntries {
+        /// Network identifier of AppendOnlyData
+        name: XorName,
+        /// Type tag
+        tag: u64,
+        /// Version of the first entry to be fetched
+        from_version: u64,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+    /// Appends an entry, signed by an owner, to AppendOnlyData.
+    AppendADataEntry {
+        /// Network identifier of AppendOnlyData
+        name: XorName,
+        /// Type tag
+        tag: u64,
+        /// Entry to be appended, with the current version of AppendOnlyData
+        entry: AppendEntry,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+
+    // --- MutableData (continued) ---
+    // ==========================
+    /// Sets the permissions on the entries of MutableData whose keys start with the given prefix.
+    SetMDataKeyPermissions {
+        /// Network identifier of MutableData
+        name: XorName,
+        /// Type tag
+        tag: u64,
+        /// Prefix of the keys the permissions apply to
+        prefix: Vec<u8>,
+        /// Permissions to be set; empty ones remove the prefix
+        permissions: KeyPermissions,
+        /// Incremented version of MutableData
+        version: u64,
+        /// Unique message identifier
+        msg_id: MsgId,
+        /// Requester public key
+        requester: PublicSignKey,
+    },
+    /// Asks to be notified with `Response::MDataChanged` whenever the entries of MutableData are
+    /// mutated. Clients renew this every `MDATA_WATCH_REFRESH_INTERVAL`.
+    WatchMData {
+        /// Network identifier of MutableData
+        name: XorName,
+        /// Type tag
+        tag: u64,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+    /// Cancels a `WatchMData` request.
+    UnwatchMData {
+        /// Network identifier of MutableData
+        name: XorName,
+        /// Type tag
+        tag: u64,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+    /// A `PutIData` or `PutMData` request encrypted to the destination section. The section opens
+    /// it before delivering the request, so vaults never receive this variant.
+    Sealed {
+        /// The encrypted request
+        request: SealedRequest,
+        /// Unique message identifier, the same as the one of the encrypted request
+        msg_id: MsgId,
+    },
+
+    // --- MPID messaging ---
+    // ==========================
+    /// Lists the summaries of the headers in one of the client's mailboxes matching `query`.
+    ListMpidHeaders {
+        /// The mailbox to list
+        mailbox: Mailbox,
+        /// The criteria selecting the headers
+        query: HeaderQuery,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+    /// Deletes the named entries from one of the client's mailboxes.
+    DeleteMpidHeaders {
+        /// The mailbox to delete from
+        mailbox: Mailbox,
+        /// The names of the headers, which are also the names of the messages
+        names: BTreeSet<XorName>,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+    /// Gets the number and total size of the entries in one of the client's mailboxes.
+    GetMpidMailboxUsage {
+        /// The mailbox to query
+        mailbox: Mailbox,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+
+    // --- MutableData (continued) ---
+    // ==========================
+    /// Fetches at most `limit` entries of MutableData, starting with the one with the key
+    /// `start_key` or the next greater key.
+    ListMDataEntriesRange {
+        /// Network identifier of MutableData
+        name: XorName,
+        /// Type tag
+        tag: u64,
+        /// The key to start at; empty to start at the first entry
+        start_key: Vec<u8>,
+        /// The maximum number of entries to return
+        limit: u64,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+
+    // --- AccountPacket ---
+    // ==========================
+    /// Fetches an AccountPacket from the network.
+    GetAccount {
+        /// Network identifier of AccountPacket, see `AccountPacket::name_from_credentials`
+        name: XorName,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+    /// Stores an AccountPacket: creates it if it has version 0, otherwise replaces the stored
+    /// packet if it is its successor.
+    UpdateAccount {
+        /// AccountPacket to be stored
+        data: AccountPacket,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
 }
 
 impl Request {
@@ -250,12 +439,219 @@ impl Request {
             | SetMDataUserPermissions { ref msg_id, .. }
             | DeleteMDataUserPermissions { ref msg_id, .. }
             | ChangeMDataOwner { ref msg_id, .. }
+            | ChallengeData { ref msg_id, .. }
             | ListAuthKeysAndVersion(ref msg_id)
             | InsertAuthKey { ref msg_id, .. }
-            | DeleteAuthKey { ref msg_id, .. } => msg_id,
+            | DeleteAuthKey { ref msg_id, .. }
+            | RelayedGet { ref msg_id, .. }
+            | PutAData { ref msg_id, .. }
+            | GetAData { ref msg_id, .. }
+            | GetADataEntries { ref msg_id, .. }
+            | AppendADataEntry { ref msg_id, .. }
+            | SetMDataKeyPermissions { ref msg_id, .. }
+            | WatchMData { ref msg_id, .. }
+            | UnwatchMData { ref msg_id, .. }
+            | Sealed { ref msg_id, .. }
+            | ListMpidHeaders { ref msg_id, .. }
+            | DeleteMpidHeaders { ref msg_id, .. }
+            | GetMpidMailboxUsage { ref msg_id, .. }
+            | ListMDataEntriesRange { ref msg_id, .. }
+            | GetAccount { ref msg_id, .. }
+            | UpdateAccount { ref msg_id, .. } => msg_id,
+        }
+    }
+
+    /// Does this request only fetch data, without mutating anything?
+    pub fn is_get(&self) -> bool {
+        use crate::Request::*;
+        match *self {
+            GetAccountInfo(_)
+            | GetIData { .. }
+            | GetMData { .. }
+            | GetMDataVersion { .. }
+            | GetMDataShell { .. }
+            | ListMDataEntries { .. }
+            | ListMDataKeys { .. }
+            | ListMDataValues { .. }
+            | GetMDataValue { .. }
+            | ListMDataPermissions { .. }
+            | ListMDataUserPermissions { .. }
+            | ListAuthKeysAndVersion(_)
+            | GetAData { .. }
+            | GetADataEntries { .. }
+            | ListMpidHeaders { .. }
+            | GetMpidMailboxUsage { .. }
+            | ListMDataEntriesRange { .. }
+            | GetAccount { .. } => true,
+            Refresh(..)
+            | PutIData { .. }
+            | PutMData { .. }
+            | MutateMDataEntries { .. }
+            | SetMDataUserPermissions { .. }
+            | DeleteMDataUserPermissions { .. }
+            | ChangeMDataOwner { .. }
+            | ChallengeData { .. }
+            | InsertAuthKey { .. }
+            | DeleteAuthKey { .. }
+            | RelayedGet { .. }
+            | PutAData { .. }
+            | AppendADataEntry { .. }
+            | SetMDataKeyPermissions { .. }
+            | WatchMData { .. }
+            | UnwatchMData { .. }
+            | Sealed { .. }
+            | DeleteMpidHeaders { .. }
+            | UpdateAccount { .. } => false,
         }
     }
 
+    /// Returns the response refusing this request with `error`, or `None` if this request isn't
+    /// responded to or its response can't be determined, i.e. for `Refresh` and `Sealed`.
+    pub fn error_response(&self, error: ClientError) -> Option<Response> {
+        use crate::Request::*;
+        let msg_id = *self.message_id();
+        let response = match *self {
+            Refresh(..) | Sealed { .. } => return None,
+            GetAccountInfo(_) => Response::GetAccountInfo {
+                res: Err(error),
+                msg_id,
+            },
+            PutIData { .. } => Response::PutIData {
+                res: Err(error),
+                msg_id,
+            },
+            GetIData { .. } => Response::GetIData {
+                res: Err(error),
+                msg_id,
+            },
+            GetMData { .. } => Response::GetMData {
+                res: Err(error),
+                msg_id,
+            },
+            PutMData { .. } => Response::PutMData {
+                res: Err(error),
+                msg_id,
+            },
+            GetMDataVersion { .. } => Response::GetMDataVersion {
+                res: Err(error),
+                msg_id,
+            },
+            GetMDataShell { .. } => Response::GetMDataShell {
+                res: Err(error),
+                msg_id,
+            },
+            ListMDataEntries { .. } => Response::ListMDataEntries {
+                res: Err(error),
+                msg_id,
+            },
+            ListMDataKeys { .. } => Response::ListMDataKeys {
+                res: Err(error),
+                msg_id,
+            },
+            ListMDataValues { .. } => Response::ListMDataValues {
+                res: Err(error),
+                msg_id,
+            },
+            GetMDataValue { .. } => Response::GetMDataValue {
+                res: Err(error),
+                msg_id,
+            },
+            MutateMDataEntries { .. } => Response::MutateMDataEntries {
+                res: Err(error),
+                msg_id,
+            },
+            ListMDataPermissions { .. } => Response::ListMDataPermissions {
+                res: Err(error),
+                msg_id,
+            },
+            ListMDataUserPermissions { .. } => Response::ListMDataUserPermissions {
+                res: Err(error),
+                msg_id,
+            },
+            SetMDataUserPermissions { .. } => Response::SetMDataUserPermissions {
+                res: Err(error),
+                msg_id,
+            },
+            DeleteMDataUserPermissions { .. } => Response::DeleteMDataUserPermissions {
+                res: Err(error),
+                msg_id,
+            },
+            ChangeMDataOwner { .. } => Response::ChangeMDataOwner {
+                res: Err(error),
+                msg_id,
+            },
+            ChallengeData { .. } => Response::ProveData {
+                res: Err(error),
+                msg_id,
+            },
+            ListAuthKeysAndVersion(_) => Response::ListAuthKeysAndVersion {
+                res: Err(error),
+                msg_id,
+            },
+            InsertAuthKey { .. } => Response::InsertAuthKey {
+                res: Err(error),
+                msg_id,
+            },
+            DeleteAuthKey { .. } => Response::DeleteAuthKey {
+                res: Err(error),
+                msg_id,
+            },
+            RelayedGet { .. } => Response::RelayedGet {
+                res: Err(error),
+                msg_id,
+            },
+            PutAData { .. } => Response::PutAData {
+                res: Err(error),
+                msg_id,
+            },
+            GetAData { .. } => Response::GetAData {
+                res: Err(error),
+                msg_id,
+            },
+            GetADataEntries { .. } => Response::GetADataEntries {
+                res: Err(error),
+                msg_id,
+            },
+            AppendADataEntry { .. } => Response::AppendADataEntry {
+                res: Err(error),
+                msg_id,
+            },
+            SetMDataKeyPermissions { .. } => Response::SetMDataKeyPermissions {
+                res: Err(error),
+                msg_id,
+            },
+            WatchMData { .. } | UnwatchMData { .. } => Response::WatchMData {
+                res: Err(error),
+                msg_id,
+            },
+            ListMpidHeaders { .. } => Response::ListMpidHeaders {
+                res: Err(error),
+                msg_id,
+            },
+            DeleteMpidHeaders { .. } => Response::DeleteMpidHeaders {
+                res: Err(error),
+                msg_id,
+            },
+            GetMpidMailboxUsage { .. } => Response::GetMpidMailboxUsage {
+                res: Err(error),
+                msg_id,
+            },
+            ListMDataEntriesRange { .. } => Response::ListMDataEntriesRange {
+                res: Err(error),
+                msg_id,
+            },
+            GetAccount { .. } => Response::GetAccount {
+                res: Err(error),
+                msg_id,
+            },
+            UpdateAccount { .. } => Response::UpdateAccount {
+                res: Err(error),
+                msg_id,
+            },
+        };
+        Some(response)
+    }
+
     /// Is the response corresponding to this request cacheable?
     pub fn is_cacheable(&self) -> bool {
         if let Request::GetIData { .. } = *self {
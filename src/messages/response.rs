@@ -6,13 +6,25 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use super::{MessageContent, SignedRoutingMessage, UserMessage};
+use crate::chain::SectionKeyInfo;
 use crate::client_error::ClientError;
-use crate::data::{ImmutableData, MutableData, PermissionSet, User, Value};
+use crate::data::{
+    AccountPacket, AppendEntry, AppendOnlyData, EntryAction, ImmutableData, MutableData,
+    PermissionSet, User, Value,
+};
+use crate::error::RoutingError;
+use crate::messaging::{MailboxUsage, MpidHeaderInfo};
+use crate::routing_table::Authority;
+use crate::sha3::Digest256;
 use crate::types::MessageId as MsgId;
+use crate::xor_name::XorName;
 use safe_crypto::PublicSignKey;
 use std::collections::{BTreeMap, BTreeSet};
 
 /// Response message types
+///
+/// Variants are serialised by their position, so new ones must only ever be added at the end.
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub enum Response {
     /// Returns a success or failure status of account information retrieval.
@@ -174,6 +186,138 @@ pub enum Response {
         /// Unique message identifier
         msg_id: MsgId,
     },
+
+    // --- Data audits between data managers ---
+    // ==========================
+    /// Returns the proof of holding the challenged data: the hash of `nonce || content`.
+    ProveData {
+        /// Result of hashing the challenged data with the given nonce
+        res: Result<Digest256, ClientError>,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+
+    // --- ClientManager to Client ---
+    // ==========================
+    /// Returns the response to a `Request::RelayedGet`, as signed by the section it was fetched
+    /// from, or the reason the ClientManager refused to relay it.
+    RelayedGet {
+        /// Result of relaying the GET
+        res: Result<RelayedResponse, ClientError>,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+    // --- AppendOnlyData ---
+    // ==========================
+    /// Returns a success or failure status of putting AppendOnlyData to the network.
+    PutAData {
+        /// Result of putting AppendOnlyData to the network.
+        res: Result<(), ClientError>,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+    /// Returns a result of fetching AppendOnlyData from the network.
+    GetAData {
+        /// Result of fetching AppendOnlyData from the network.
+        res: Result<AppendOnlyData, ClientError>,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+    /// Returns the entries of AppendOnlyData appended at or after the requested version.
+    GetADataEntries {
+        /// Result of fetching the entries
+        res: Result<Vec<AppendEntry>, ClientError>,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+    /// Returns a success or failure status of appending an entry to AppendOnlyData.
+    AppendADataEntry {
+        /// Result of appending the entry
+        res: Result<(), ClientError>,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+
+    // --- MutableData (continued) ---
+    // ==========================
+    /// Returns a success or failure status of setting permissions for a key prefix.
+    SetMDataKeyPermissions {
+        /// Result of setting the key permissions
+        res: Result<(), ClientError>,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+    /// Returns a success or failure status of watching MutableData.
+    WatchMData {
+        /// Result of registering the watch
+        res: Result<(), ClientError>,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+    /// Notifies a client watching MutableData that its entries were mutated. Raised as
+    /// `Event::MDataChanged` by the client.
+    MDataChanged {
+        /// Network identifier of MutableData
+        name: XorName,
+        /// Type tag
+        tag: u64,
+        /// The mutations performed
+        actions: BTreeMap<Vec<u8>, EntryAction>,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+
+    // --- MPID messaging ---
+    // ==========================
+    /// Returns the summaries of the headers matching a `ListMpidHeaders` request.
+    ListMpidHeaders {
+        /// Result of listing the headers
+        res: Result<Vec<MpidHeaderInfo>, ClientError>,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+    /// Returns a success or failure status of deleting entries from a mailbox.
+    DeleteMpidHeaders {
+        /// Result of deleting the entries
+        res: Result<(), ClientError>,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+    /// Returns the number and total size of the entries in a mailbox.
+    GetMpidMailboxUsage {
+        /// Result of querying the mailbox
+        res: Result<MailboxUsage, ClientError>,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+
+    // --- MutableData (continued) ---
+    // ==========================
+    /// Returns a page of entries of MutableData, and the key the next page starts at if there are
+    /// more entries.
+    ListMDataEntriesRange {
+        /// Result of getting the page of entries
+        res: Result<(BTreeMap<Vec<u8>, Value>, Option<Vec<u8>>), ClientError>,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+
+    // --- AccountPacket ---
+    // ==========================
+    /// Returns a result of fetching AccountPacket from the network.
+    GetAccount {
+        /// Result of fetching AccountPacket from the network.
+        res: Result<AccountPacket, ClientError>,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
+    /// Returns a success or failure status of storing AccountPacket.
+    UpdateAccount {
+        /// Result of storing AccountPacket
+        res: Result<(), ClientError>,
+        /// Unique message identifier
+        msg_id: MsgId,
+    },
 }
 
 impl Response {
@@ -208,9 +352,24 @@ impl Response {
             | SetMDataUserPermissions { ref msg_id, .. }
             | DeleteMDataUserPermissions { ref msg_id, .. }
             | ChangeMDataOwner { ref msg_id, .. }
+            | ProveData { ref msg_id, .. }
             | ListAuthKeysAndVersion { ref msg_id, .. }
             | InsertAuthKey { ref msg_id, .. }
-            | DeleteAuthKey { ref msg_id, .. } => msg_id,
+            | DeleteAuthKey { ref msg_id, .. }
+            | RelayedGet { ref msg_id, .. }
+            | PutAData { ref msg_id, .. }
+            | GetAData { ref msg_id, .. }
+            | GetADataEntries { ref msg_id, .. }
+            | AppendADataEntry { ref msg_id, .. }
+            | SetMDataKeyPermissions { ref msg_id, .. }
+            | WatchMData { ref msg_id, .. }
+            | MDataChanged { ref msg_id, .. }
+            | ListMpidHeaders { ref msg_id, .. }
+            | DeleteMpidHeaders { ref msg_id, .. }
+            | GetMpidMailboxUsage { ref msg_id, .. }
+            | ListMDataEntriesRange { ref msg_id, .. }
+            | GetAccount { ref msg_id, .. }
+            | UpdateAccount { ref msg_id, .. } => msg_id,
         }
     }
 
@@ -224,6 +383,71 @@ impl Response {
     }
 }
 
+/// A response fetched by a ClientManager on behalf of a client, together with the signature of
+/// the section which sent it and the proof chain of that section's key.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct RelayedResponse {
+    signed_msg: Box<SignedRoutingMessage>,
+}
+
+impl RelayedResponse {
+    pub(crate) fn new(signed_msg: SignedRoutingMessage) -> Self {
+        Self {
+            signed_msg: Box::new(signed_msg),
+        }
+    }
+
+    /// Checks that the response was signed by the section responsible for `origin`, i.e. the
+    /// `dst` of the relayed request, and returns it.
+    ///
+    /// `trusted_key` is a key of that section the caller already trusts, e.g. the one a response
+    /// verified earlier was signed with. The response's proof chain has to lead from it to the key
+    /// the response is signed with.
+    pub fn verify(
+        &self,
+        origin: &Authority<XorName>,
+        trusted_key: &SectionKeyInfo,
+    ) -> Result<Response, RoutingError> {
+        let proof_chain = self
+            .signed_msg
+            .proof_chain()
+            .ok_or(RoutingError::FailedSignature)?;
+        if !proof_chain
+            .last_public_key_info()
+            .prefix()
+            .matches(&origin.name())
+        {
+            return Err(RoutingError::InvalidProvingSection);
+        }
+        if !proof_chain
+            .all_key_infos()
+            .any(|key_info| key_info == trusted_key)
+        {
+            return Err(RoutingError::UntrustedMessage);
+        }
+        self.signed_msg.check_integrity()?;
+
+        let routing_msg = self.signed_msg.routing_message();
+        if routing_msg.src != *origin {
+            return Err(RoutingError::InvalidSource);
+        }
+
+        match routing_msg.content {
+            MessageContent::UserMessage {
+                content: UserMessage::Response(ref response),
+                ..
+            } => Ok(response.clone()),
+            _ => Err(RoutingError::InvalidMessage),
+        }
+    }
+
+    /// Returns the key of the section the response is signed with. Once the response is verified,
+    /// it can be trusted to verify later responses from that section.
+    pub fn section_key_info(&self) -> Option<&SectionKeyInfo> {
+        self.signed_msg.source_section_key_info()
+    }
+}
+
 /// Account information
 #[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize, Debug)]
 pub struct AccountInfo {
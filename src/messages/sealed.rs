@@ -0,0 +1,130 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::Request;
+#[cfg(feature = "client")]
+use crate::chain::SectionKeyInfo;
+use crate::{error::Result, routing_table::Prefix, xor_name::XorName, BlsCiphertext};
+#[cfg(feature = "node")]
+use crate::{error::RoutingError, id::FullId, types::MessageId};
+#[cfg(feature = "node")]
+use maidsafe_utilities::serialisation::deserialise;
+#[cfg(feature = "client")]
+use maidsafe_utilities::serialisation::serialise;
+use std::fmt::{self, Debug, Formatter};
+
+/// A request encrypted to the key of the section it is sent to, so that only the members of that
+/// section can read it, whichever nodes relay it.
+///
+/// Only requests carrying data, i.e. `PutIData` and `PutMData`, are sealed. The destination
+/// section opens the request before delivering it, so vaults never see it sealed.
+#[derive(Clone, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct SealedRequest {
+    prefix: Prefix<XorName>,
+    version: u64,
+    ciphertext: BlsCiphertext,
+}
+
+impl SealedRequest {
+    /// Returns whether `request` carries data which should be sealed.
+    pub fn should_seal(request: &Request) -> bool {
+        match *request {
+            Request::PutIData { .. } | Request::PutMData { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Encrypts `request` to the section with the given key, and wraps it in a `Request::Sealed`
+    /// with the same message ID.
+    #[cfg(feature = "client")]
+    pub(crate) fn seal(request: &Request, key_info: &SectionKeyInfo) -> Result<Request> {
+        let ciphertext = key_info.key().encrypt(&serialise(request)?)?;
+        Ok(Request::Sealed {
+            request: SealedRequest {
+                prefix: *key_info.prefix(),
+                version: *key_info.version(),
+                ciphertext,
+            },
+            msg_id: *request.message_id(),
+        })
+    }
+
+    /// Decrypts the request, if `full_id` is a member of the section it was sealed to. The request
+    /// must have the message ID `msg_id` of the `Request::Sealed` it came in.
+    #[cfg(feature = "node")]
+    pub(crate) fn open(&self, full_id: &FullId, msg_id: &MessageId) -> Result<Request> {
+        let request: Request = deserialise(&self.ciphertext.decrypt(full_id)?)?;
+        if !Self::should_seal(&request) || request.message_id() != msg_id {
+            return Err(RoutingError::InvalidMessage);
+        }
+        Ok(request)
+    }
+
+    /// Returns the prefix of the section the request was sealed to.
+    pub fn prefix(&self) -> &Prefix<XorName> {
+        &self.prefix
+    }
+
+    /// Returns the version of the section key the request was sealed to.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+}
+
+impl Debug for SealedRequest {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "SealedRequest {{ prefix: {:?}, version: {}, .. }}",
+            self.prefix, self.version
+        )
+    }
+}
+
+#[cfg(all(test, feature = "client", feature = "node"))]
+mod tests {
+    use super::*;
+    use crate::chain::SectionInfo;
+    use crate::data::ImmutableData;
+    use crate::network_params::NetworkParams;
+    use unwrap::unwrap;
+
+    #[test]
+    fn seal_and_open() {
+        let members: Vec<_> = (0..4).map(|_| FullId::new()).collect();
+        let sec_info = unwrap!(SectionInfo::new(
+            members.iter().map(|full_id| *full_id.public_id()).collect(),
+            Prefix::default(),
+            None
+        ));
+        let key_info = SectionKeyInfo::from_section_info(&sec_info, NetworkParams::default());
+
+        let request = Request::PutIData {
+            data: ImmutableData::new(vec![1, 2, 3]),
+            msg_id: MessageId::new(),
+        };
+        assert!(SealedRequest::should_seal(&request));
+        let (sealed, msg_id) = match unwrap!(SealedRequest::seal(&request, &key_info)) {
+            Request::Sealed { request, msg_id } => (request, msg_id),
+            request => panic!("Unexpected {:?}", request),
+        };
+        assert_eq!(msg_id, *request.message_id());
+        assert_eq!(sealed.version(), *key_info.version());
+
+        for member in &members {
+            assert_eq!(unwrap!(sealed.open(member, &msg_id)), request);
+        }
+        assert!(sealed.open(&FullId::new(), &msg_id).is_err());
+        assert!(sealed.open(&members[0], &MessageId::new()).is_err());
+
+        assert!(!SealedRequest::should_seal(&Request::GetIData {
+            name: rand::random(),
+            msg_id: MessageId::new(),
+        }));
+    }
+}
@@ -19,6 +19,17 @@ pub enum Error {
     /// Used where the length of a [message's `body`](struct.MpidMessage.html#method.new) exceeds
     /// [`MAX_BODY_SIZE`](constant.MAX_BODY_SIZE.html).
     BodyTooLarge,
+    /// Used where a message or header is retrieved after its
+    /// [expiry time](struct.MpidHeader.html#method.expires_at).
+    Expired,
+    /// Used where a [multi-recipient message](struct.MpidMessage.html#method.new_multi) has more
+    /// than [`MAX_RECIPIENTS`](constant.MAX_RECIPIENTS.html) recipients.
+    TooManyRecipients,
+    /// Used where storing an entry would exceed the size limit of a
+    /// [mailbox](enum.Mailbox.html).
+    MailboxFull,
+    /// Error sealing or opening encrypted parts of a message.
+    Crypto(safe_crypto::Error),
     /// Serialisation error.
     Serialisation(SerialisationError),
 }
@@ -28,6 +39,10 @@ impl Display for Error {
         match *self {
             Error::MetadataTooLarge => write!(formatter, "Message header too large"),
             Error::BodyTooLarge => write!(formatter, "Message body too large"),
+            Error::Expired => write!(formatter, "Message expired"),
+            Error::TooManyRecipients => write!(formatter, "Too many message recipients"),
+            Error::MailboxFull => write!(formatter, "Mailbox full"),
+            Error::Crypto(ref error) => write!(formatter, "Crypto error: {}", error),
             Error::Serialisation(ref error) => write!(formatter, "Serialisation error: {}", error),
         }
     }
@@ -38,6 +53,10 @@ impl StdError for Error {
         match *self {
             Error::MetadataTooLarge => "Header too large",
             Error::BodyTooLarge => "Body too large",
+            Error::Expired => "Expired",
+            Error::TooManyRecipients => "Too many recipients",
+            Error::MailboxFull => "Mailbox full",
+            Error::Crypto(ref error) => error.description(),
             Error::Serialisation(ref error) => error.description(),
         }
     }
@@ -45,11 +64,18 @@ impl StdError for Error {
     fn cause(&self) -> Option<&dyn StdError> {
         match *self {
             Error::Serialisation(ref error) => Some(error),
+            Error::Crypto(ref error) => Some(error),
             _ => None,
         }
     }
 }
 
+impl From<safe_crypto::Error> for Error {
+    fn from(error: safe_crypto::Error) -> Error {
+        Error::Crypto(error)
+    }
+}
+
 impl From<SerialisationError> for Error {
     fn from(error: SerialisationError) -> Error {
         Error::Serialisation(error)
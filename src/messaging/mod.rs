@@ -12,16 +12,23 @@ pub const GUID_SIZE: usize = 16;
 pub const MAX_INBOX_SIZE: usize = 1 << 27;
 /// Maximum allowed outbox size for an account (128 MiB).
 pub const MAX_OUTBOX_SIZE: usize = 1 << 27;
+/// Maximum number of high priority messages from one sender which are listed as such in an inbox.
+/// Any further ones are listed with normal priority.
+pub const MAX_HIGH_PRIORITY_PER_SENDER: usize = 10;
 
 mod error;
 mod mpid_header;
+mod mpid_manager;
 mod mpid_message;
 mod mpid_message_wrapper;
+mod mpid_receipt;
 
 pub use self::error::Error;
-pub use self::mpid_header::{MpidHeader, MAX_HEADER_METADATA_SIZE};
-pub use self::mpid_message::{MpidMessage, MAX_BODY_SIZE};
+pub use self::mpid_header::{MpidHeader, MpidPriority, MAX_HEADER_METADATA_SIZE};
+pub use self::mpid_manager::{HeaderQuery, Mailbox, MailboxUsage, MpidHeaderInfo, MpidManager};
+pub use self::mpid_message::{MpidMessage, MAX_BODY_SIZE, MAX_RECIPIENTS};
 pub use self::mpid_message_wrapper::MpidMessageWrapper;
+pub use self::mpid_receipt::{MpidReceipt, ReceiptKind};
 
 #[cfg(test)]
 fn generate_random_bytes(size: usize) -> Vec<u8> {
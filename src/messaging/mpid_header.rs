@@ -16,14 +16,38 @@ use hex_fmt::HexFmt;
 use maidsafe_utilities::serialisation::serialise;
 use rand::{self, Rng};
 use safe_crypto;
-use safe_crypto::{PublicSignKey, SecretSignKey, Signature};
+use safe_crypto::{PublicEncryptKey, PublicSignKey, SecretEncryptKey, SecretSignKey, Signature};
 use std::fmt::{self, Debug, Formatter};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How urgently the recipient should be notified of a message.  Inboxes list headers by priority,
+/// highest first.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum MpidPriority {
+    /// Listed after all other messages.
+    Low,
+    /// The priority of messages created by `new()`.
+    Normal,
+    /// Listed before all other messages.  Only the first
+    /// [`MAX_HIGH_PRIORITY_PER_SENDER`](constant.MAX_HIGH_PRIORITY_PER_SENDER.html) high priority
+    /// messages from each sender in an inbox are listed as such.
+    High,
+}
+
+impl Default for MpidPriority {
+    fn default() -> Self {
+        MpidPriority::Normal
+    }
+}
 
 #[derive(PartialEq, Eq, Hash, Clone, Deserialize, Serialize)]
 struct Detail {
     sender: XorName,
     guid: [u8; GUID_SIZE],
     metadata: Vec<u8>,
+    /// Seconds since the Unix epoch after which the message may be dropped, if it expires.
+    expires_at: Option<u64>,
+    priority: MpidPriority,
 }
 
 /// Minimal information about a given message which can be used as a notification to the receiver.
@@ -54,17 +78,73 @@ impl MpidHeader {
         sender: XorName,
         metadata: Vec<u8>,
         secret_key: &SecretSignKey,
+    ) -> Result<MpidHeader, Error> {
+        Self::with_guid(
+            sender,
+            random_guid(),
+            metadata,
+            None,
+            MpidPriority::Normal,
+            secret_key,
+        )
+    }
+
+    /// Constructor for a header which expires at `expires_at`, after which vaults may drop the
+    /// message and retrieving it fails with `Error::Expired`. The expiry is covered by the
+    /// signature, and has a resolution of one second.
+    ///
+    /// See [`new()`](#method.new) for the other arguments.
+    pub fn with_expiry(
+        sender: XorName,
+        metadata: Vec<u8>,
+        expires_at: SystemTime,
+        secret_key: &SecretSignKey,
+    ) -> Result<MpidHeader, Error> {
+        let expires_at = expires_at
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        Self::with_guid(
+            sender,
+            random_guid(),
+            metadata,
+            Some(expires_at),
+            MpidPriority::Normal,
+            secret_key,
+        )
+    }
+
+    /// Constructor for a header with the given priority, which is covered by the signature.
+    ///
+    /// See [`new()`](#method.new) for the other arguments.
+    pub fn with_priority(
+        sender: XorName,
+        metadata: Vec<u8>,
+        priority: MpidPriority,
+        secret_key: &SecretSignKey,
+    ) -> Result<MpidHeader, Error> {
+        Self::with_guid(sender, random_guid(), metadata, None, priority, secret_key)
+    }
+
+    pub(super) fn with_guid(
+        sender: XorName,
+        guid: [u8; GUID_SIZE],
+        metadata: Vec<u8>,
+        expires_at: Option<u64>,
+        priority: MpidPriority,
+        secret_key: &SecretSignKey,
     ) -> Result<MpidHeader, Error> {
         if metadata.len() > MAX_HEADER_METADATA_SIZE {
             return Err(Error::MetadataTooLarge);
         }
 
-        let mut detail = Detail {
+        let detail = Detail {
             sender: sender,
-            guid: [0u8; GUID_SIZE],
+            guid: guid,
             metadata: metadata,
+            expires_at: expires_at,
+            priority: priority,
         };
-        rand::thread_rng().fill_bytes(&mut detail.guid);
 
         let encoded = serialise(&detail)?;
         Ok(MpidHeader {
@@ -88,7 +168,41 @@ impl MpidHeader {
         &self.detail.metadata
     }
 
-    /// The signature of `sender`, `guid` and `metadata`, created when calling `new()`.
+    /// Decrypts the metadata of a header belonging to a
+    /// [multi-recipient message](struct.MpidMessage.html#method.new_multi), which is sealed for
+    /// each recipient.  `recipient_key` is the recipient's secret encryption key and `sender_key`
+    /// the sender's public one.
+    pub fn open_metadata(
+        &self,
+        recipient_key: &SecretEncryptKey,
+        sender_key: &PublicEncryptKey,
+    ) -> Result<Vec<u8>, Error> {
+        let shared_secret = recipient_key.shared_secret(sender_key);
+        Ok(shared_secret.decrypt_bytes(&self.detail.metadata)?)
+    }
+
+    /// The time after which the message may be dropped, if it expires.
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.detail
+            .expires_at
+            .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    /// Returns `Error::Expired` if the message's expiry time has passed.
+    pub fn check_expiry(&self) -> Result<(), Error> {
+        match self.expires_at() {
+            Some(expires_at) if expires_at <= SystemTime::now() => Err(Error::Expired),
+            _ => Ok(()),
+        }
+    }
+
+    /// The priority of the message.
+    pub fn priority(&self) -> MpidPriority {
+        self.detail.priority
+    }
+
+    /// The signature of `sender`, `guid`, `metadata`, the expiry time and the priority, created
+    /// when calling `new()`.
     pub fn signature(&self) -> &Signature {
         &self.signature
     }
@@ -109,14 +223,23 @@ impl MpidHeader {
     }
 }
 
+fn random_guid() -> [u8; GUID_SIZE] {
+    let mut guid = [0u8; GUID_SIZE];
+    rand::thread_rng().fill_bytes(&mut guid);
+    guid
+}
+
 impl Debug for MpidHeader {
     fn fmt(&self, formatter: &mut Formatter) -> Result<(), fmt::Error> {
         write!(
             formatter,
-            "MpidHeader {{ sender: {:?}, guid: {:.14}, metadata: {:.14}, signature: {:.14} }}",
+            "MpidHeader {{ sender: {:?}, guid: {:.14}, metadata: {:.14}, expires_at: {:?}, \
+             priority: {:?}, signature: {:.14} }}",
             self.detail.sender,
             HexFmt(&self.detail.guid),
             HexFmt(&self.detail.metadata),
+            self.detail.expires_at,
+            self.detail.priority,
             HexFmt(&self.signature.into_bytes()[..])
         )
     }
@@ -168,4 +291,58 @@ mod tests {
         let name2 = unwrap!(header2.name());
         assert_ne!(name1, name2);
     }
+
+    #[test]
+    fn expiry() {
+        let (public_key, secret_key) = gen_sign_keypair();
+        let sender: XorName = rand::random();
+
+        let header = unwrap!(MpidHeader::new(sender, vec![], &secret_key));
+        assert!(header.expires_at().is_none());
+        unwrap!(header.check_expiry());
+
+        let later = SystemTime::now() + Duration::from_secs(60);
+        let header = unwrap!(MpidHeader::with_expiry(sender, vec![], later, &secret_key));
+        assert!(header.verify(&public_key));
+        assert!(header.expires_at().is_some());
+        unwrap!(header.check_expiry());
+
+        let earlier = SystemTime::now() - Duration::from_secs(60);
+        let mut header = unwrap!(MpidHeader::with_expiry(
+            sender,
+            vec![],
+            earlier,
+            &secret_key
+        ));
+        match header.check_expiry() {
+            Err(Error::Expired) => (),
+            result => panic!("Unexpected {:?}", result),
+        }
+
+        // The expiry is covered by the signature.
+        header.detail.expires_at = Some(unwrap!(later.duration_since(UNIX_EPOCH)).as_secs());
+        assert!(!header.verify(&public_key));
+    }
+
+    #[test]
+    fn priority() {
+        let (public_key, secret_key) = gen_sign_keypair();
+        let sender: XorName = rand::random();
+
+        let header = unwrap!(MpidHeader::new(sender, vec![], &secret_key));
+        assert_eq!(header.priority(), MpidPriority::Normal);
+
+        let mut header = unwrap!(MpidHeader::with_priority(
+            sender,
+            vec![],
+            MpidPriority::Low,
+            &secret_key
+        ));
+        assert_eq!(header.priority(), MpidPriority::Low);
+        assert!(header.verify(&public_key));
+
+        // The priority is covered by the signature.
+        header.detail.priority = MpidPriority::High;
+        assert!(!header.verify(&public_key));
+    }
 }
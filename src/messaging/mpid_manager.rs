@@ -0,0 +1,354 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    Error, MpidHeader, MpidMessage, MpidPriority, MAX_HIGH_PRIORITY_PER_SENDER, MAX_INBOX_SIZE,
+    MAX_OUTBOX_SIZE,
+};
+use crate::xor_name::XorName;
+use maidsafe_utilities::serialisation::serialise;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One of the two mailboxes of a client.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum Mailbox {
+    /// The headers of messages sent to the client.
+    Inbox,
+    /// The messages sent by the client which weren't deleted yet.
+    Outbox,
+}
+
+/// Criteria selecting headers in a mailbox.  Unset criteria match every header.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct HeaderQuery {
+    /// Only select headers of messages from this sender.
+    pub sender: Option<XorName>,
+    /// Only select headers stored at or after this time, in seconds since the Unix epoch.
+    pub stored_after: Option<u64>,
+    /// Only select headers stored before this time, in seconds since the Unix epoch.
+    pub stored_before: Option<u64>,
+}
+
+impl HeaderQuery {
+    /// Returns whether the header described by `info` matches all criteria.
+    pub fn matches(&self, info: &MpidHeaderInfo) -> bool {
+        self.sender.map_or(true, |sender| info.sender == sender)
+            && self
+                .stored_after
+                .map_or(true, |time| info.stored_at >= time)
+            && self
+                .stored_before
+                .map_or(true, |time| info.stored_at < time)
+    }
+}
+
+/// A summary of a header stored in a mailbox, which lets clients manage their mailboxes without
+/// fetching the headers or messages.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct MpidHeaderInfo {
+    /// The name of the header, which is also the name of the message.
+    pub name: XorName,
+    /// The sender of the message.
+    pub sender: XorName,
+    /// The number of bytes the entry counts towards the mailbox size.
+    pub size: u64,
+    /// When the entry was stored, in seconds since the Unix epoch.
+    pub stored_at: u64,
+    /// The message's expiry time, in seconds since the Unix epoch, if it expires.
+    pub expires_at: Option<u64>,
+    /// The priority the entry is listed with.  This is the message's priority, except for high
+    /// priority messages beyond a sender's
+    /// [cap](constant.MAX_HIGH_PRIORITY_PER_SENDER.html), which are listed with normal priority.
+    pub priority: MpidPriority,
+}
+
+/// The number of entries in a mailbox and their total size.
+#[derive(
+    Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize,
+)]
+pub struct MailboxUsage {
+    /// The number of entries.
+    pub count: u64,
+    /// The total size of the entries in bytes.
+    pub size: u64,
+}
+
+/// The inbox and outbox of a single client, as held by its MpidManagers.
+///
+/// The inbox holds the headers of messages sent to the client, the outbox the full messages the
+/// client sent.  Each is limited to [`MAX_INBOX_SIZE`](constant.MAX_INBOX_SIZE.html) and
+/// [`MAX_OUTBOX_SIZE`](constant.MAX_OUTBOX_SIZE.html) bytes respectively.
+#[derive(Default)]
+pub struct MpidManager {
+    inbox: BTreeMap<XorName, Entry<MpidHeader>>,
+    outbox: BTreeMap<XorName, Entry<MpidMessage>>,
+    arrivals: u64,
+}
+
+struct Entry<T> {
+    item: T,
+    info: MpidHeaderInfo,
+    // The number of entries stored before this one, to list entries in order of arrival.
+    arrival: u64,
+}
+
+impl MpidManager {
+    /// Creates a manager with empty mailboxes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores a message sent by the client in its outbox and returns the message's name.
+    ///
+    /// Returns `Error::MailboxFull` if this would exceed the outbox size limit.
+    pub fn put_message(&mut self, message: MpidMessage) -> Result<XorName, Error> {
+        let info = header_info(message.header(), serialise(&message)?.len())?;
+        if self.usage(Mailbox::Outbox).size + info.size > MAX_OUTBOX_SIZE as u64 {
+            return Err(Error::MailboxFull);
+        }
+        let name = info.name;
+        let entry = self.entry(message, info);
+        let _ = self.outbox.insert(name, entry);
+        Ok(name)
+    }
+
+    /// Stores the header of a message sent to the client in its inbox and returns its name.
+    ///
+    /// If the inbox already holds `MAX_HIGH_PRIORITY_PER_SENDER` high priority headers from the
+    /// same sender, a further high priority header is listed with normal priority.
+    ///
+    /// Returns `Error::MailboxFull` if this would exceed the inbox size limit.
+    pub fn put_header(&mut self, header: MpidHeader) -> Result<XorName, Error> {
+        let mut info = header_info(&header, serialise(&header)?.len())?;
+        if self.usage(Mailbox::Inbox).size + info.size > MAX_INBOX_SIZE as u64 {
+            return Err(Error::MailboxFull);
+        }
+        if info.priority == MpidPriority::High {
+            let high_priority_count = self
+                .inbox
+                .values()
+                .filter(|entry| {
+                    entry.info.sender == info.sender && entry.info.priority == MpidPriority::High
+                })
+                .count();
+            if high_priority_count >= MAX_HIGH_PRIORITY_PER_SENDER {
+                info.priority = MpidPriority::Normal;
+            }
+        }
+        let name = info.name;
+        let entry = self.entry(header, info);
+        let _ = self.inbox.insert(name, entry);
+        Ok(name)
+    }
+
+    /// Returns the named message from the outbox, or `Error::Expired` if it has expired.
+    pub fn get_message(&self, name: &XorName) -> Result<Option<&MpidMessage>, Error> {
+        match self.outbox.get(name) {
+            Some(entry) => entry.item.check_expiry().map(|()| Some(&entry.item)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the summaries of the headers in `mailbox` matching `query`, ordered by priority,
+    /// highest first, and then by arrival, oldest first.
+    pub fn list(&self, mailbox: Mailbox, query: &HeaderQuery) -> Vec<MpidHeaderInfo> {
+        let mut entries: Vec<_> = self
+            .infos(mailbox)
+            .filter(|&(info, _)| query.matches(info))
+            .collect();
+        entries.sort_by_key(|&(info, arrival)| (Reverse(info.priority), arrival));
+        entries.into_iter().map(|(info, _)| info.clone()).collect()
+    }
+
+    /// Deletes the named entries from `mailbox` and returns the names of those which existed.
+    pub fn delete(&mut self, mailbox: Mailbox, names: &BTreeSet<XorName>) -> BTreeSet<XorName> {
+        names
+            .iter()
+            .filter(|name| match mailbox {
+                Mailbox::Inbox => self.inbox.remove(name).is_some(),
+                Mailbox::Outbox => self.outbox.remove(name).is_some(),
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the number and total size of the entries in `mailbox`.
+    pub fn usage(&self, mailbox: Mailbox) -> MailboxUsage {
+        self.infos(mailbox)
+            .fold(MailboxUsage::default(), |usage, (info, _)| MailboxUsage {
+                count: usage.count + 1,
+                size: usage.size + info.size,
+            })
+    }
+
+    /// Deletes all expired entries from both mailboxes.
+    pub fn remove_expired(&mut self) {
+        let now = now_secs();
+        let is_live = |info: &MpidHeaderInfo| info.expires_at.map_or(true, |time| time > now);
+        self.inbox.retain(|_, entry| is_live(&entry.info));
+        self.outbox.retain(|_, entry| is_live(&entry.info));
+    }
+
+    fn entry<T>(&mut self, item: T, info: MpidHeaderInfo) -> Entry<T> {
+        self.arrivals += 1;
+        Entry {
+            item,
+            info,
+            arrival: self.arrivals,
+        }
+    }
+
+    // Returns the summaries and arrival numbers of the entries in `mailbox`.
+    fn infos<'a>(
+        &'a self,
+        mailbox: Mailbox,
+    ) -> Box<dyn Iterator<Item = (&'a MpidHeaderInfo, u64)> + 'a> {
+        match mailbox {
+            Mailbox::Inbox => Box::new(
+                self.inbox
+                    .values()
+                    .map(|entry| (&entry.info, entry.arrival)),
+            ),
+            Mailbox::Outbox => Box::new(
+                self.outbox
+                    .values()
+                    .map(|entry| (&entry.info, entry.arrival)),
+            ),
+        }
+    }
+}
+
+fn header_info(header: &MpidHeader, size: usize) -> Result<MpidHeaderInfo, Error> {
+    let expires_at = header.expires_at().map(|time| {
+        time.duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0)
+    });
+    Ok(MpidHeaderInfo {
+        name: header.name()?,
+        sender: *header.sender(),
+        size: size as u64,
+        stored_at: now_secs(),
+        expires_at,
+        priority: header.priority(),
+    })
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+    use safe_crypto::gen_sign_keypair;
+    use std::time::Duration;
+    use unwrap::unwrap;
+
+    #[test]
+    fn list_delete_and_usage() {
+        let (_, secret_key) = gen_sign_keypair();
+        let alice: XorName = rand::random();
+        let bob: XorName = rand::random();
+        let mut manager = MpidManager::new();
+
+        let from_alice =
+            unwrap!(manager.put_header(unwrap!(MpidHeader::new(alice, vec![], &secret_key))));
+        let from_bob =
+            unwrap!(manager.put_header(unwrap!(MpidHeader::new(bob, vec![], &secret_key))));
+        assert_eq!(manager.usage(Mailbox::Inbox).count, 2);
+        assert_eq!(manager.usage(Mailbox::Outbox), MailboxUsage::default());
+
+        let query = HeaderQuery {
+            sender: Some(alice),
+            ..HeaderQuery::default()
+        };
+        let infos = manager.list(Mailbox::Inbox, &query);
+        assert_eq!(infos.len(), 1);
+        assert_eq!(infos[0].name, from_alice);
+
+        let query = HeaderQuery {
+            stored_before: Some(now_secs() - 60),
+            ..HeaderQuery::default()
+        };
+        assert!(manager.list(Mailbox::Inbox, &query).is_empty());
+
+        let names = vec![from_bob, rand::random()].into_iter().collect();
+        let deleted = manager.delete(Mailbox::Inbox, &names);
+        assert_eq!(deleted, Some(from_bob).into_iter().collect());
+        assert_eq!(manager.usage(Mailbox::Inbox).count, 1);
+    }
+
+    #[test]
+    fn priority_ordering_and_cap() {
+        let (_, secret_key) = gen_sign_keypair();
+        let alice: XorName = rand::random();
+        let bob: XorName = rand::random();
+        let mut manager = MpidManager::new();
+        let mut put = |sender, priority| {
+            let header = unwrap!(MpidHeader::with_priority(
+                sender,
+                vec![],
+                priority,
+                &secret_key
+            ));
+            unwrap!(manager.put_header(header))
+        };
+
+        let low = put(alice, MpidPriority::Low);
+        let normal = put(alice, MpidPriority::Normal);
+        let high: Vec<_> = (0..MAX_HIGH_PRIORITY_PER_SENDER)
+            .map(|_| put(alice, MpidPriority::High))
+            .collect();
+        // Alice exceeded her cap, but Bob didn't.
+        let alice_excess = put(alice, MpidPriority::High);
+        let bob_high = put(bob, MpidPriority::High);
+
+        let listed: Vec<_> = manager
+            .list(Mailbox::Inbox, &HeaderQuery::default())
+            .into_iter()
+            .map(|info| info.name)
+            .collect();
+        let expected: Vec<_> = high
+            .into_iter()
+            .chain(vec![bob_high, normal, alice_excess, low])
+            .collect();
+        assert_eq!(listed, expected);
+    }
+
+    #[test]
+    fn expired_messages() {
+        let (_, secret_key) = gen_sign_keypair();
+        let sender: XorName = rand::random();
+        let mut manager = MpidManager::new();
+
+        let message = unwrap!(MpidMessage::with_expiry(
+            sender,
+            vec![],
+            rand::random(),
+            vec![],
+            SystemTime::now() - Duration::from_secs(1),
+            &secret_key,
+        ));
+        let name = unwrap!(manager.put_message(message));
+        match manager.get_message(&name) {
+            Err(Error::Expired) => (),
+            result => panic!("Unexpected {:?}", result),
+        }
+
+        manager.remove_expired();
+        assert!(unwrap!(manager.get_message(&name)).is_none());
+        assert_eq!(manager.usage(Mailbox::Outbox), MailboxUsage::default());
+    }
+}
@@ -10,17 +10,26 @@
 /// bytes).
 pub const MAX_BODY_SIZE: usize = 102_400 - 512 - super::MAX_HEADER_METADATA_SIZE;
 
-use super::{Error, MpidHeader};
+/// Maximum allowed number of recipients of a
+/// [multi-recipient message](struct.MpidMessage.html#method.new_multi) (32).
+pub const MAX_RECIPIENTS: usize = 32;
+
+use super::{Error, MpidHeader, MpidPriority, GUID_SIZE};
 use crate::xor_name::XorName;
 use hex_fmt::HexFmt;
 use maidsafe_utilities::serialisation::serialise;
-use safe_crypto::{PublicSignKey, SecretSignKey, Signature};
+use rand::{self, Rng};
+use safe_crypto::{
+    self, PublicEncryptKey, PublicSignKey, SecretEncryptKey, SecretSignKey, Signature,
+};
 use std::fmt::{self, Debug, Formatter};
+use std::time::SystemTime;
 
 #[derive(PartialEq, Eq, Hash, Clone, Deserialize, Serialize)]
 struct Detail {
     recipient: XorName,
     body: Vec<u8>,
+    encrypted: bool,
 }
 
 /// A full message including header and body which can be sent to or retrieved from the network.
@@ -59,10 +68,136 @@ impl MpidMessage {
         }
 
         let header = MpidHeader::new(sender, metadata, secret_key)?;
+        Self::with_header(header, recipient, body, false, secret_key)
+    }
+
+    /// Constructor for a message which expires at `expires_at`, after which vaults may drop it and
+    /// retrieving it fails with `Error::Expired`.
+    ///
+    /// See [`new()`](#method.new) for the other arguments, and
+    /// [MpidHeader::with_expiry()](struct.MpidHeader.html#method.with_expiry) for the expiry.
+    pub fn with_expiry(
+        sender: XorName,
+        metadata: Vec<u8>,
+        recipient: XorName,
+        body: Vec<u8>,
+        expires_at: SystemTime,
+        secret_key: &SecretSignKey,
+    ) -> Result<MpidMessage, Error> {
+        if body.len() > MAX_BODY_SIZE {
+            return Err(Error::BodyTooLarge);
+        }
+
+        let header = MpidHeader::with_expiry(sender, metadata, expires_at, secret_key)?;
+        Self::with_header(header, recipient, body, false, secret_key)
+    }
 
+    /// Constructor for a message with the given priority.
+    ///
+    /// See [`new()`](#method.new) for the other arguments, and
+    /// [MpidHeader::with_priority()](struct.MpidHeader.html#method.with_priority) for the priority.
+    pub fn with_priority(
+        sender: XorName,
+        metadata: Vec<u8>,
+        recipient: XorName,
+        body: Vec<u8>,
+        priority: MpidPriority,
+        secret_key: &SecretSignKey,
+    ) -> Result<MpidMessage, Error> {
+        if body.len() > MAX_BODY_SIZE {
+            return Err(Error::BodyTooLarge);
+        }
+
+        let header = MpidHeader::with_priority(sender, metadata, priority, secret_key)?;
+        Self::with_header(header, recipient, body, false, secret_key)
+    }
+
+    /// Constructor for a message sent to several recipients at once.
+    ///
+    /// Returns one copy of the message per recipient, each with the same `body` but its own
+    /// header: `metadata` is sealed for each recipient using `sender_encrypt_key` and the
+    /// recipient's public encryption key, and can be read back using
+    /// [MpidHeader::open_metadata()](struct.MpidHeader.html#method.open_metadata).  Sealing adds
+    /// some overhead, so the usable `metadata` is somewhat shorter than
+    /// [`MAX_HEADER_METADATA_SIZE`](constant.MAX_HEADER_METADATA_SIZE.html).
+    ///
+    /// The copies' GUIDs are derived from a common random GUID and the recipient's name, so every
+    /// copy and its header have a distinct [`name()`](#method.name) and can be stored in and
+    /// deleted from each recipient's inbox independently.
+    ///
+    /// An error will be returned if there are more than
+    /// [`MAX_RECIPIENTS`](constant.MAX_RECIPIENTS.html) recipients, if sealing the metadata fails,
+    /// or for any of the reasons given for [`new()`](#method.new).
+    pub fn new_multi(
+        sender: XorName,
+        sender_encrypt_key: &SecretEncryptKey,
+        metadata: &[u8],
+        recipients: &[(XorName, PublicEncryptKey)],
+        body: Vec<u8>,
+        secret_key: &SecretSignKey,
+    ) -> Result<Vec<MpidMessage>, Error> {
+        if recipients.len() > MAX_RECIPIENTS {
+            return Err(Error::TooManyRecipients);
+        }
+        if body.len() > MAX_BODY_SIZE {
+            return Err(Error::BodyTooLarge);
+        }
+
+        let base_guid: [u8; GUID_SIZE] = rand::thread_rng().gen();
+        recipients
+            .iter()
+            .map(|&(recipient, ref recipient_key)| {
+                let shared_secret = sender_encrypt_key.shared_secret(recipient_key);
+                let sealed_metadata = shared_secret.encrypt_bytes(metadata)?;
+                let header = MpidHeader::with_guid(
+                    sender,
+                    recipient_guid(&base_guid, &recipient),
+                    sealed_metadata,
+                    None,
+                    MpidPriority::Normal,
+                    secret_key,
+                )?;
+                Self::with_header(header, recipient, body.clone(), false, secret_key)
+            })
+            .collect()
+    }
+
+    /// Constructor for a message whose body is encrypted to `recipient_key`, the recipient's
+    /// public encryption key, so only the recipient can read it using
+    /// [`decrypt_body()`](#method.decrypt_body).  The signature covers the encrypted body.
+    ///
+    /// The encrypted body must not exceed [`MAX_BODY_SIZE`](constant.MAX_BODY_SIZE.html), so the
+    /// plaintext `body` is limited to `MAX_BODY_SIZE` less the encryption overhead.
+    ///
+    /// See [`new()`](#method.new) for the other arguments.
+    pub fn new_encrypted(
+        sender: XorName,
+        metadata: Vec<u8>,
+        recipient: XorName,
+        recipient_key: &PublicEncryptKey,
+        body: &[u8],
+        secret_key: &SecretSignKey,
+    ) -> Result<MpidMessage, Error> {
+        let encrypted_body = recipient_key.anonymously_encrypt_bytes(body);
+        if encrypted_body.len() > MAX_BODY_SIZE {
+            return Err(Error::BodyTooLarge);
+        }
+
+        let header = MpidHeader::new(sender, metadata, secret_key)?;
+        Self::with_header(header, recipient, encrypted_body, true, secret_key)
+    }
+
+    fn with_header(
+        header: MpidHeader,
+        recipient: XorName,
+        body: Vec<u8>,
+        encrypted: bool,
+        secret_key: &SecretSignKey,
+    ) -> Result<MpidMessage, Error> {
         let detail = Detail {
             recipient: recipient,
             body: body,
+            encrypted: encrypted,
         };
 
         let recipient_and_body = serialise(&detail)?;
@@ -83,11 +218,36 @@ impl MpidMessage {
         &self.detail.recipient
     }
 
-    /// Arbitrary, user-supplied data representing the main portion of the message.
+    /// Arbitrary, user-supplied data representing the main portion of the message.  This is the
+    /// ciphertext if the message [is encrypted](#method.is_encrypted).
     pub fn body(&self) -> &Vec<u8> {
         &self.detail.body
     }
 
+    /// Whether the body was encrypted to the recipient when calling `new_encrypted()`.
+    pub fn is_encrypted(&self) -> bool {
+        self.detail.encrypted
+    }
+
+    /// Returns the plaintext body, decrypting it using the recipient's encryption keys if the
+    /// message [is encrypted](#method.is_encrypted).
+    pub fn decrypt_body(
+        &self,
+        public_key: &PublicEncryptKey,
+        secret_key: &SecretEncryptKey,
+    ) -> Result<Vec<u8>, Error> {
+        if !self.detail.encrypted {
+            return Ok(self.detail.body.clone());
+        }
+        Ok(secret_key.anonymously_decrypt_bytes(&self.detail.body, public_key)?)
+    }
+
+    /// Returns `Error::Expired` if the message's expiry time has passed. Vaults call this when the
+    /// message is retrieved, so stale messages are never delivered.
+    pub fn check_expiry(&self) -> Result<(), Error> {
+        self.header.check_expiry()
+    }
+
     /// The name of the message, equivalent to the
     /// [`MpidHeader::name()`](../struct.MpidHeader.html#method.name).  As per that getter, this is
     /// relatively expensive, so its use should be minimised.
@@ -107,14 +267,25 @@ impl MpidMessage {
     }
 }
 
+// The GUID of the copy of a multi-recipient message sent to `recipient`.
+fn recipient_guid(base_guid: &[u8; GUID_SIZE], recipient: &XorName) -> [u8; GUID_SIZE] {
+    let mut input = base_guid.to_vec();
+    input.extend_from_slice(&recipient.0);
+    let mut guid = [0u8; GUID_SIZE];
+    guid.copy_from_slice(&safe_crypto::hash(&input)[..GUID_SIZE]);
+    guid
+}
+
 impl Debug for MpidMessage {
     fn fmt(&self, formatter: &mut Formatter) -> Result<(), fmt::Error> {
         write!(
             formatter,
-            "MpidMessage {{ header: {:?}, recipient: {:?}, body: {:.14}, signature: {:.14} }}",
+            "MpidMessage {{ header: {:?}, recipient: {:?}, body: {:.14}, encrypted: {}, \
+             signature: {:.14} }}",
             self.header,
             self.detail.recipient,
             HexFmt(&self.detail.body),
+            self.detail.encrypted,
             HexFmt(&self.signature.into_bytes()[..])
         )
     }
@@ -126,7 +297,9 @@ mod tests {
     use crate::messaging;
     use crate::xor_name::XorName;
     use rand;
-    use safe_crypto::gen_sign_keypair;
+    use safe_crypto::{gen_encrypt_keypair, gen_sign_keypair};
+    use std::collections::BTreeSet;
+    use std::time::Duration;
     use unwrap::unwrap;
 
     #[test]
@@ -172,4 +345,132 @@ mod tests {
         let (rand_public_key, _) = gen_sign_keypair();
         assert!(!message.verify(&rand_public_key));
     }
+
+    #[test]
+    fn multi() {
+        let (public_key, secret_key) = gen_sign_keypair();
+        let (sender_public_encrypt_key, sender_encrypt_key) = gen_encrypt_keypair();
+        let sender: XorName = rand::random();
+        let metadata = messaging::generate_random_bytes(32);
+        let body = messaging::generate_random_bytes(1024);
+        let recipient_keys: Vec<_> = (0..3).map(|_| gen_encrypt_keypair()).collect();
+        let recipients: Vec<_> = recipient_keys
+            .iter()
+            .map(|&(ref public_key, _)| (rand::random(), *public_key))
+            .collect();
+
+        let messages = unwrap!(MpidMessage::new_multi(
+            sender,
+            &sender_encrypt_key,
+            &metadata,
+            &recipients,
+            body.clone(),
+            &secret_key,
+        ));
+        assert_eq!(messages.len(), recipients.len());
+
+        let names: BTreeSet<_> = messages
+            .iter()
+            .map(|message| unwrap!(message.name()))
+            .collect();
+        assert_eq!(names.len(), messages.len());
+
+        for ((message, recipient), (_, recipient_key)) in
+            messages.iter().zip(&recipients).zip(&recipient_keys)
+        {
+            assert!(message.verify(&public_key));
+            assert_eq!(*message.recipient(), recipient.0);
+            assert_eq!(*message.body(), body);
+            assert_ne!(*message.header().metadata(), metadata);
+            let opened = unwrap!(message
+                .header()
+                .open_metadata(recipient_key, &sender_public_encrypt_key));
+            assert_eq!(opened, metadata);
+        }
+
+        // The metadata is sealed for each recipient separately.
+        assert!(messages[0]
+            .header()
+            .open_metadata(&recipient_keys[1].1, &sender_public_encrypt_key)
+            .is_err());
+
+        let too_many: Vec<_> = (0..MAX_RECIPIENTS + 1)
+            .map(|_| (rand::random(), recipients[0].1))
+            .collect();
+        match MpidMessage::new_multi(
+            sender,
+            &sender_encrypt_key,
+            &metadata,
+            &too_many,
+            body,
+            &secret_key,
+        ) {
+            Err(messaging::Error::TooManyRecipients) => (),
+            result => panic!("Unexpected {:?}", result),
+        }
+    }
+
+    #[test]
+    fn encrypted() {
+        let (public_key, secret_key) = gen_sign_keypair();
+        let (recipient_public_key, recipient_secret_key) = gen_encrypt_keypair();
+        let sender: XorName = rand::random();
+        let recipient: XorName = rand::random();
+        let body = messaging::generate_random_bytes(1024);
+
+        let message = unwrap!(MpidMessage::new_encrypted(
+            sender,
+            vec![],
+            recipient,
+            &recipient_public_key,
+            &body,
+            &secret_key,
+        ));
+        assert!(message.verify(&public_key));
+        assert!(message.is_encrypted());
+        assert_ne!(*message.body(), body);
+        let decrypted = unwrap!(message.decrypt_body(&recipient_public_key, &recipient_secret_key));
+        assert_eq!(decrypted, body);
+
+        let (other_public_key, other_secret_key) = gen_encrypt_keypair();
+        assert!(message
+            .decrypt_body(&other_public_key, &other_secret_key)
+            .is_err());
+
+        // The size limit applies to the encrypted body.
+        let body = messaging::generate_random_bytes(MAX_BODY_SIZE);
+        match MpidMessage::new_encrypted(
+            sender,
+            vec![],
+            recipient,
+            &recipient_public_key,
+            &body,
+            &secret_key,
+        ) {
+            Err(messaging::Error::BodyTooLarge) => (),
+            result => panic!("Unexpected {:?}", result),
+        }
+    }
+
+    #[test]
+    fn expiry() {
+        let (public_key, secret_key) = gen_sign_keypair();
+        let sender: XorName = rand::random();
+        let recipient: XorName = rand::random();
+        let expires_at = SystemTime::now() - Duration::from_secs(1);
+
+        let message = unwrap!(MpidMessage::with_expiry(
+            sender,
+            vec![],
+            recipient,
+            vec![],
+            expires_at,
+            &secret_key,
+        ));
+        assert!(message.verify(&public_key));
+        match message.check_expiry() {
+            Err(messaging::Error::Expired) => (),
+            result => panic!("Unexpected {:?}", result),
+        }
+    }
 }
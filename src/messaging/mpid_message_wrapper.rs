@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{MpidHeader, MpidMessage};
+use super::{MpidHeader, MpidMessage, MpidReceipt};
 use crate::xor_name::XorName;
 
 /// A serialisable wrapper to allow multiplexing all MPID message types and actions via a single
@@ -43,4 +43,10 @@ pub enum MpidMessageWrapper {
     /// Sent by a receiving Client to the sender's MpidManagers to delete the named message's header
     /// from the sender's outbox.
     DeleteHeader(XorName),
+    /// Sent by a receiving Client to the sender's MpidManagers once it has retrieved or read the
+    /// message, to record the receipt in the sender's outbox.
+    PutReceipt(MpidReceipt),
+    /// Sent by the sender's MpidManagers to the sender, if online, to forward a receipt for one of
+    /// its messages.
+    Receipt(MpidReceipt),
 }
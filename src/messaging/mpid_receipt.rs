@@ -0,0 +1,184 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{Error, MpidHeader};
+use crate::xor_name::XorName;
+use hex_fmt::HexFmt;
+use maidsafe_utilities::serialisation::serialise;
+use safe_crypto::{PublicSignKey, SecretSignKey, Signature};
+use std::fmt::{self, Debug, Formatter};
+
+/// What a receipt confirms about a message.
+///
+/// A message for which the sender holds no receipt has only been stored in its outbox.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum ReceiptKind {
+    /// The recipient retrieved the message from the network.
+    Delivered,
+    /// The recipient read the message.
+    Read,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Deserialize, Serialize)]
+struct Detail {
+    message_name: XorName,
+    sender: XorName,
+    recipient: XorName,
+    kind: ReceiptKind,
+}
+
+/// A confirmation signed by the recipient of a message, routed back to the sender's outbox
+/// authority.
+#[derive(PartialEq, Eq, Hash, Clone, Deserialize, Serialize)]
+pub struct MpidReceipt {
+    detail: Detail,
+    signature: Signature,
+}
+
+impl MpidReceipt {
+    /// Constructor.
+    ///
+    /// `header` is the header of the retrieved message, which identifies the message and its
+    /// sender.
+    ///
+    /// `recipient` is the name of the receiver of the message, who signs the receipt with
+    /// `secret_key`.
+    ///
+    /// An error will be returned if serialisation fails while deriving the message name or during
+    /// the signing process.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        header: &MpidHeader,
+        recipient: XorName,
+        kind: ReceiptKind,
+        secret_key: &SecretSignKey,
+    ) -> Result<MpidReceipt, Error> {
+        let detail = Detail {
+            message_name: header.name()?,
+            sender: *header.sender(),
+            recipient: recipient,
+            kind: kind,
+        };
+
+        let encoded = serialise(&detail)?;
+        Ok(MpidReceipt {
+            detail: detail,
+            signature: secret_key.sign_detached(&encoded),
+        })
+    }
+
+    /// The name of the message this receipt is for.
+    pub fn message_name(&self) -> &XorName {
+        &self.detail.message_name
+    }
+
+    /// The name of the original creator of the message, whose outbox the receipt is routed to.
+    pub fn sender(&self) -> &XorName {
+        &self.detail.sender
+    }
+
+    /// The name of the receiver of the message, who created the receipt.
+    pub fn recipient(&self) -> &XorName {
+        &self.detail.recipient
+    }
+
+    /// What the receipt confirms about the message.
+    pub fn kind(&self) -> ReceiptKind {
+        self.detail.kind
+    }
+
+    /// The recipient's signature of the other fields, created when calling `new()`.
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// Validates the receipt's signature against the recipient's `PublicSignKey`.
+    pub fn verify(&self, public_key: &PublicSignKey) -> bool {
+        match serialise(&self.detail) {
+            Ok(encoded) => public_key.verify_detached(&self.signature, &encoded),
+            Err(_) => false,
+        }
+    }
+
+    /// Returns whether this receipt is for the message with the given header, and is validly
+    /// signed by the recipient.
+    pub fn verify_for(&self, header: &MpidHeader, public_key: &PublicSignKey) -> bool {
+        match header.name() {
+            Ok(name) => {
+                name == self.detail.message_name
+                    && *header.sender() == self.detail.sender
+                    && self.verify(public_key)
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+impl Debug for MpidReceipt {
+    fn fmt(&self, formatter: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(
+            formatter,
+            "MpidReceipt {{ message_name: {:?}, sender: {:?}, recipient: {:?}, kind: {:?}, \
+             signature: {:.14} }}",
+            self.detail.message_name,
+            self.detail.sender,
+            self.detail.recipient,
+            self.detail.kind,
+            HexFmt(&self.signature.into_bytes()[..])
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messaging::MpidMessage;
+    use rand;
+    use safe_crypto::gen_sign_keypair;
+    use unwrap::unwrap;
+
+    #[test]
+    fn full() {
+        let (_, sender_secret_key) = gen_sign_keypair();
+        let (recipient_public_key, recipient_secret_key) = gen_sign_keypair();
+        let sender: XorName = rand::random();
+        let recipient: XorName = rand::random();
+        let message = unwrap!(MpidMessage::new(
+            sender,
+            vec![],
+            recipient,
+            vec![],
+            &sender_secret_key,
+        ));
+        let other_message = unwrap!(MpidMessage::new(
+            sender,
+            vec![],
+            recipient,
+            vec![],
+            &sender_secret_key,
+        ));
+
+        let receipt = unwrap!(MpidReceipt::new(
+            message.header(),
+            recipient,
+            ReceiptKind::Read,
+            &recipient_secret_key,
+        ));
+        assert_eq!(*receipt.message_name(), unwrap!(message.name()));
+        assert_eq!(*receipt.sender(), sender);
+        assert_eq!(*receipt.recipient(), recipient);
+        assert_eq!(receipt.kind(), ReceiptKind::Read);
+
+        // Check verify functions with a valid and invalid key, and the wrong message.
+        assert!(receipt.verify(&recipient_public_key));
+        assert!(receipt.verify_for(message.header(), &recipient_public_key));
+        let (rand_public_key, _) = gen_sign_keypair();
+        assert!(!receipt.verify(&rand_public_key));
+        assert!(!receipt.verify_for(other_message.header(), &recipient_public_key));
+    }
+}
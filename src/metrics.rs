@@ -0,0 +1,326 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Counters and gauges describing a running node, and an HTTP exporter serving them in the
+//! Prometheus text format.
+
+use std::fmt::Write as FmtWrite;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How long the exporter sleeps when no scrape is waiting, before checking whether to stop.
+const EXPORTER_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How long the exporter waits for a scraper to send its request or read the response.
+const EXPORTER_IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Metrics of a node. They are updated by the node as it runs and can be read from any thread.
+///
+/// Counters only ever increase; a rate such as accumulated events per second is derived from two
+/// samples, e.g. with Prometheus' `rate()`.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    section_size: AtomicUsize,
+    prefix_bit_count: AtomicUsize,
+    accumulated_events: AtomicUsize,
+    message_queue_depth: AtomicUsize,
+    dropped_messages: AtomicUsize,
+    client_requests_served: AtomicUsize,
+    filtered_messages: AtomicUsize,
+    filter_false_positives: AtomicUsize,
+    relay_cache_hits: AtomicUsize,
+    relay_cache_misses: AtomicUsize,
+}
+
+impl Metrics {
+    /// Returns the number of members of our section, excluding infants.
+    pub fn section_size(&self) -> usize {
+        self.section_size.load(Ordering::Relaxed)
+    }
+
+    /// Returns the bit count of our section's prefix.
+    pub fn prefix_bit_count(&self) -> usize {
+        self.prefix_bit_count.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of network events our section accumulated since the node started.
+    pub fn accumulated_events(&self) -> usize {
+        self.accumulated_events.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of messages which were waiting for their targets the last time the
+    /// outgoing queue was flushed.
+    pub fn message_queue_depth(&self) -> usize {
+        self.message_queue_depth.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of outgoing messages dropped because the queue of their target was
+    /// full.
+    pub fn dropped_messages(&self) -> usize {
+        self.dropped_messages.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of client requests delivered to the vault since the node started.
+    pub fn client_requests_served(&self) -> usize {
+        self.client_requests_served.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of incoming messages checked against the duplicate filter.
+    pub fn filtered_messages(&self) -> usize {
+        self.filtered_messages.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of new incoming messages the duplicate filter's bloom filter wrongly
+    /// reported as seen, which then had to be looked up in its exact store.
+    pub fn filter_false_positives(&self) -> usize {
+        self.filter_false_positives.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of relayed `GetIData` requests answered from our cache.
+    pub fn relay_cache_hits(&self) -> usize {
+        self.relay_cache_hits.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of relayed `GetIData` requests whose data wasn't in our cache.
+    pub fn relay_cache_misses(&self) -> usize {
+        self.relay_cache_misses.load(Ordering::Relaxed)
+    }
+
+    #[cfg(feature = "node")]
+    pub(crate) fn set_section(&self, size: usize, prefix_bit_count: usize) {
+        self.section_size.store(size, Ordering::Relaxed);
+        self.prefix_bit_count
+            .store(prefix_bit_count, Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "node")]
+    pub(crate) fn inc_accumulated_events(&self) {
+        let _ = self.accumulated_events.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_message_queue_depth(&self, depth: usize) {
+        self.message_queue_depth.store(depth, Ordering::Relaxed);
+    }
+
+    pub(crate) fn inc_dropped_messages(&self) {
+        let _ = self.dropped_messages.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "node")]
+    pub(crate) fn inc_client_requests_served(&self) {
+        let _ = self.client_requests_served.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "node")]
+    pub(crate) fn set_filter_stats(&self, filtered: usize, false_positives: usize) {
+        self.filtered_messages.store(filtered, Ordering::Relaxed);
+        self.filter_false_positives
+            .store(false_positives, Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "node")]
+    pub(crate) fn inc_relay_cache_lookups(&self, hit: bool) {
+        let counter = if hit {
+            &self.relay_cache_hits
+        } else {
+            &self.relay_cache_misses
+        };
+        let _ = counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let metrics = [
+            (
+                "routing_section_size",
+                "gauge",
+                "Number of members of our section, excluding infants.",
+                self.section_size(),
+            ),
+            (
+                "routing_prefix_bit_count",
+                "gauge",
+                "Bit count of our section's prefix.",
+                self.prefix_bit_count(),
+            ),
+            (
+                "routing_accumulated_events_total",
+                "counter",
+                "Network events accumulated by our section.",
+                self.accumulated_events(),
+            ),
+            (
+                "routing_message_queue_depth",
+                "gauge",
+                "Messages waiting for their targets at the last flush of the outgoing queue.",
+                self.message_queue_depth(),
+            ),
+            (
+                "routing_dropped_messages_total",
+                "counter",
+                "Outgoing messages dropped because the queue of their target was full.",
+                self.dropped_messages(),
+            ),
+            (
+                "routing_client_requests_served_total",
+                "counter",
+                "Client requests delivered to the vault.",
+                self.client_requests_served(),
+            ),
+            (
+                "routing_filtered_messages_total",
+                "counter",
+                "Incoming messages checked against the duplicate filter.",
+                self.filtered_messages(),
+            ),
+            (
+                "routing_filter_false_positives_total",
+                "counter",
+                "New messages the duplicate filter's bloom filter wrongly reported as seen.",
+                self.filter_false_positives(),
+            ),
+            (
+                "routing_relay_cache_hits_total",
+                "counter",
+                "Relayed GetIData requests answered from our cache.",
+                self.relay_cache_hits(),
+            ),
+            (
+                "routing_relay_cache_misses_total",
+                "counter",
+                "Relayed GetIData requests whose data wasn't in our cache.",
+                self.relay_cache_misses(),
+            ),
+        ];
+
+        let mut output = String::new();
+        for (name, kind, help, value) in &metrics {
+            let _ = writeln!(output, "# HELP {} {}", name, help);
+            let _ = writeln!(output, "# TYPE {} {}", name, kind);
+            let _ = writeln!(output, "{} {}", name, value);
+        }
+        output
+    }
+}
+
+/// Serves a node's `Metrics` over HTTP in a background thread, answering every request with the
+/// rendered metrics. The thread is stopped when the exporter is dropped.
+pub struct MetricsExporter {
+    local_addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MetricsExporter {
+    /// Starts serving `metrics` on `addr`.
+    pub fn start(addr: SocketAddr, metrics: Arc<Metrics>) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let local_addr = listener.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let handle = thread::Builder::new()
+            .name("routing-metrics".to_string())
+            .spawn(move || {
+                while !thread_stop.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        Ok((stream, _)) => {
+                            if let Err(error) = serve_scrape(stream, &metrics) {
+                                debug!("Failed to serve metrics: {:?}", error);
+                            }
+                        }
+                        Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => {
+                            thread::sleep(EXPORTER_POLL_INTERVAL)
+                        }
+                        Err(error) => {
+                            warn!("Metrics exporter failed to accept: {:?}", error);
+                            thread::sleep(EXPORTER_POLL_INTERVAL)
+                        }
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            local_addr,
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /// Returns the address the exporter is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for MetricsExporter {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn serve_scrape(mut stream: TcpStream, metrics: &Metrics) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(EXPORTER_IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(EXPORTER_IO_TIMEOUT))?;
+
+    // The request itself doesn't matter, but it has to be read before responding.
+    let mut request = [0; 1024];
+    let _ = stream.read(&mut request)?;
+
+    let body = metrics.render();
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unwrap::unwrap;
+
+    #[test]
+    fn exporter_serves_rendered_metrics() {
+        let metrics = Arc::new(Metrics::default());
+        metrics.set_section(8, 2);
+        metrics.inc_accumulated_events();
+        metrics.inc_accumulated_events();
+        metrics.inc_client_requests_served();
+        metrics.inc_dropped_messages();
+
+        let rendered = metrics.render();
+        assert!(rendered.contains("\nrouting_section_size 8\n"));
+        assert!(rendered.contains("\nrouting_prefix_bit_count 2\n"));
+        assert!(rendered.contains("# TYPE routing_accumulated_events_total counter\n"));
+        assert!(rendered.contains("\nrouting_accumulated_events_total 2\n"));
+        assert!(rendered.contains("\nrouting_client_requests_served_total 1\n"));
+        assert!(rendered.contains("\nrouting_dropped_messages_total 1\n"));
+
+        let exporter = unwrap!(MetricsExporter::start(
+            unwrap!("127.0.0.1:0".parse()),
+            Arc::clone(&metrics)
+        ));
+        let mut stream = unwrap!(TcpStream::connect(exporter.local_addr()));
+        unwrap!(stream.write_all(b"GET /metrics HTTP/1.1\r\n\r\n"));
+        let mut response = String::new();
+        let _ = unwrap!(stream.read_to_string(&mut response));
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with(&rendered));
+    }
+}
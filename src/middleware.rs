@@ -0,0 +1,123 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{messages::UserMessage, routing_table::Authority, xor_name::XorName};
+
+/// Whether a user message is being received or sent by the node.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Direction {
+    /// The message is addressed to us, and about to be raised as an event.
+    Incoming,
+    /// The message is being sent on behalf of the embedding application.
+    Outgoing,
+}
+
+/// A user message passing through the middleware pipeline.
+#[derive(Clone, Copy, Debug)]
+pub struct MessageContext<'a> {
+    /// Whether the message is incoming or outgoing.
+    pub direction: Direction,
+    /// The source authority.
+    pub src: Authority<XorName>,
+    /// The destination authority.
+    pub dst: Authority<XorName>,
+    /// The request or response.
+    pub content: &'a UserMessage,
+}
+
+/// What happens to a message after a middleware has processed it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Verdict {
+    /// Pass the message on to the next middleware, and finally handle it.
+    Continue,
+    /// Drop the message. The remaining middleware doesn't see it.
+    Drop,
+}
+
+/// A step every user message a node receives or sends passes through.
+///
+/// Middleware can implement cross-cutting policies such as rate limiting, auditing, metrics or
+/// custom filters. They are registered via `NodeBuilder::middleware` and run in the order they
+/// were added.
+pub trait MessageMiddleware {
+    /// Processes `msg`, returning whether it should continue through the pipeline.
+    fn process(&mut self, msg: &MessageContext) -> Verdict;
+}
+
+/// An ordered list of middleware.
+#[derive(Default)]
+pub struct MiddlewarePipeline {
+    layers: Vec<Box<dyn MessageMiddleware>>,
+}
+
+impl MiddlewarePipeline {
+    /// Appends `middleware` to the end of the pipeline.
+    pub fn push(&mut self, middleware: Box<dyn MessageMiddleware>) {
+        self.layers.push(middleware);
+    }
+
+    /// Passes `msg` through the middleware in order, stopping at the first which drops it.
+    pub fn process(&mut self, msg: &MessageContext) -> Verdict {
+        for layer in &mut self.layers {
+            if layer.process(msg) == Verdict::Drop {
+                return Verdict::Drop;
+            }
+        }
+        Verdict::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{messages::Request, types::MessageId};
+    use std::{cell::RefCell, rc::Rc};
+
+    struct Recorder {
+        name: &'static str,
+        log: Rc<RefCell<Vec<&'static str>>>,
+        verdict: Verdict,
+    }
+
+    impl MessageMiddleware for Recorder {
+        fn process(&mut self, _msg: &MessageContext) -> Verdict {
+            self.log.borrow_mut().push(self.name);
+            self.verdict
+        }
+    }
+
+    #[test]
+    fn runs_in_order_until_dropped() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut pipeline = MiddlewarePipeline::default();
+        for &(name, verdict) in &[
+            ("metrics", Verdict::Continue),
+            ("filter", Verdict::Drop),
+            ("audit", Verdict::Continue),
+        ] {
+            pipeline.push(Box::new(Recorder {
+                name,
+                log: log.clone(),
+                verdict,
+            }));
+        }
+
+        let content = UserMessage::Request(Request::GetIData {
+            name: rand::random(),
+            msg_id: MessageId::new(),
+        });
+        let msg = MessageContext {
+            direction: Direction::Incoming,
+            src: Authority::ManagedNode(rand::random()),
+            dst: Authority::NaeManager(rand::random()),
+            content: &content,
+        };
+        assert_eq!(pipeline.process(&msg), Verdict::Drop);
+        assert_eq!(*log.borrow(), vec!["metrics", "filter"]);
+    }
+}
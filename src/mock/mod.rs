@@ -13,4 +13,7 @@ pub(crate) mod parsec;
 /// Mock version of Quic-P2P
 pub(crate) mod quic_p2p;
 
-pub use self::quic_p2p::Network;
+pub mod replay;
+pub mod visualiser;
+
+pub use self::quic_p2p::{FaultPolicy, Latency, Network};
@@ -11,11 +11,11 @@ mod node;
 #[cfg(test)]
 mod tests;
 
-pub use self::network::Network;
+pub use self::network::{FaultPolicy, Latency, Network};
 pub use quic_p2p::Token;
 
 use self::node::Node;
-use crate::NetworkBytes;
+use crate::{error::RoutingError, transport::Transport, NetworkBytes};
 use crossbeam_channel::Sender;
 use std::{cell::RefCell, collections::HashSet, iter, net::SocketAddr, rc::Rc};
 
@@ -109,6 +109,32 @@ impl QuicP2p {
     }
 }
 
+impl Transport for QuicP2p {
+    fn bootstrap(&mut self) {
+        self.bootstrap()
+    }
+
+    fn connect_to(&mut self, peer_info: NodeInfo) {
+        self.connect_to(peer_info)
+    }
+
+    fn disconnect_from(&mut self, peer_addr: SocketAddr) {
+        self.disconnect_from(peer_addr)
+    }
+
+    fn send(&mut self, peer: Peer, msg: NetworkBytes, token: Token) {
+        self.send(peer, msg, token)
+    }
+
+    fn our_connection_info(&mut self) -> Result<NodeInfo, RoutingError> {
+        Ok(self.our_connection_info()?)
+    }
+
+    fn is_hard_coded_contact(&self, node_info: &NodeInfo) -> bool {
+        self.is_hard_coded_contact(node_info)
+    }
+}
+
 #[cfg(test)]
 impl QuicP2p {
     fn addr(&self) -> SocketAddr {
@@ -121,7 +147,7 @@ impl QuicP2p {
 }
 
 /// Configuration for `QuicP2p`.
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct Config {
     /// Hard-coded contacts.
     pub hard_coded_contacts: HashSet<NodeInfo>,
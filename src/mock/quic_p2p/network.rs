@@ -7,9 +7,22 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{node::Node, OurType};
+#[cfg(not(feature = "mock_serialise"))]
+use crate::messages::compat;
 #[cfg(feature = "mock_parsec")]
 use crate::mock::parsec;
-use crate::NetworkBytes;
+use crate::{
+    client::Client,
+    error::RoutingError,
+    event_stream::EventStream,
+    mock::{
+        replay::{Capture, CapturedMessage},
+        visualiser::{self, TRACE_ENV_VAR},
+    },
+    node::Node as RoutingNode,
+    time::{Duration, Instant},
+    NetworkBytes, PublicId,
+};
 use fxhash::{FxHashMap, FxHashSet};
 use maidsafe_utilities::SeededRng;
 use rand::Rng;
@@ -17,7 +30,10 @@ use std::{
     cell::RefCell,
     cmp,
     collections::{hash_map::Entry, VecDeque},
-    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    env,
+    fs::OpenOptions,
+    io::Write,
+    net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4},
     rc::{Rc, Weak},
     sync::Once,
 };
@@ -25,6 +41,8 @@ use unwrap::unwrap;
 
 const IP_BASE: Ipv4Addr = Ipv4Addr::LOCALHOST;
 const PORT: u16 = 9999;
+/// Maximum number of poll rounds `Network::shutdown_and_verify` waits for the network to go quiet.
+const MAX_SHUTDOWN_ROUNDS: usize = 1000;
 
 static PRINT_SEED: Once = Once::new();
 
@@ -50,6 +68,15 @@ impl Network {
         #[cfg(feature = "mock_parsec")]
         parsec::init_mock();
 
+        let trace = env::var_os(TRACE_ENV_VAR).map(|path| {
+            let file = unwrap!(
+                OpenOptions::new().create(true).append(true).open(&path),
+                "Failed to open trace file {:?}:",
+                path
+            );
+            Box::new(file) as Box<dyn Write>
+        });
+
         Network(Rc::new(RefCell::new(Inner {
             min_section_size,
             rng,
@@ -57,6 +84,12 @@ impl Network {
             connections: Default::default(),
             used_ips: Default::default(),
             message_sent: false,
+            sent_messages: 0,
+            fault_policies: Default::default(),
+            default_fault_policy: FaultPolicy::default(),
+            trace,
+            trace_round: 0,
+            captures: Default::default(),
         })))
     }
 
@@ -81,6 +114,17 @@ impl Network {
         SocketAddr::V4(SocketAddrV4::new(ip, PORT))
     }
 
+    /// Generate new unique IPv6 socket addrs. Nodes can only reach peers of their own address
+    /// family.
+    pub fn gen_addr_v6(&self) -> SocketAddr {
+        match self.gen_addr() {
+            SocketAddr::V4(addr) => {
+                SocketAddr::new(IpAddr::V6(addr.ip().to_ipv6_compatible()), PORT)
+            }
+            addr => addr,
+        }
+    }
+
     /// Generate new socket address to be used by the next created `QuicP2p` instance and return it.
     pub fn gen_next_addr(&self) -> SocketAddr {
         let addr = self.gen_addr();
@@ -105,6 +149,57 @@ impl Network {
         }
     }
 
+    /// Returns the `(src, dst)` pairs of the connections which have packets queued, in a fixed
+    /// order. Together with `deliver_next`, this lets a test choose the order of deliveries itself
+    /// rather than leaving it to `poll`.
+    pub fn pending_connections(&self) -> Vec<(SocketAddr, SocketAddr)> {
+        let mut connections: Vec<_> = self
+            .0
+            .borrow()
+            .connections
+            .iter()
+            .filter(|(_, queue)| !queue.is_empty())
+            .map(|(connection, _)| (connection.src, connection.dst))
+            .collect();
+        connections.sort_by_key(|(src, dst)| (src.ip(), src.port(), dst.ip(), dst.port()));
+        connections
+    }
+
+    /// Delivers the oldest packet queued on the connection from `src` to `dst`. Returns `false`
+    /// if there is none.
+    pub fn deliver_next(&self, src: SocketAddr, dst: SocketAddr) -> bool {
+        let connection = Connection::new(src, dst);
+        let packet = self.0.borrow_mut().pop_oldest_packet(connection);
+        if let Some(packet) = packet {
+            self.process_packet(&connection, packet);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Applies `policy` to the messages sent from `src` to `dst` from now on. Messages already in
+    /// flight are not affected.
+    pub fn set_fault_policy(&self, src: SocketAddr, dst: SocketAddr, policy: FaultPolicy) {
+        let _ = self
+            .0
+            .borrow_mut()
+            .fault_policies
+            .insert(Connection::new(src, dst), policy);
+    }
+
+    /// Applies `policy` to the messages sent over all links without a policy of their own.
+    pub fn set_default_fault_policy(&self, policy: FaultPolicy) {
+        self.0.borrow_mut().default_fault_policy = policy;
+    }
+
+    /// Removes all fault policies. Delayed messages stay in flight until their delivery time.
+    pub fn clear_fault_policies(&self) {
+        let mut inner = self.0.borrow_mut();
+        inner.fault_policies.clear();
+        inner.default_fault_policy = FaultPolicy::default();
+    }
+
     /// Disconnect peer at `addr0` from the peer at `addr1`.
     pub fn disconnect(&self, addr0: &SocketAddr, addr1: &SocketAddr) {
         let node = self.0.borrow().find_node(addr0);
@@ -128,6 +223,32 @@ impl Network {
         self.0.borrow_mut().rng.new_rng()
     }
 
+    /// Writes the trace described in the `visualiser` module to `out`, instead of to the file
+    /// given by the `ROUTING_MOCK_TRACE` environment variable.
+    pub fn set_trace_output<W: Write + 'static>(&self, out: W) {
+        let mut inner = self.0.borrow_mut();
+        inner.trace = Some(Box::new(out));
+        inner.trace_round = 0;
+    }
+
+    /// Appends the state of `nodes` and the number of messages in flight to the trace, if it is
+    /// enabled. Call this after each poll round.
+    pub fn trace_round<'a, I>(&self, nodes: I)
+    where
+        I: IntoIterator<Item = &'a RoutingNode>,
+    {
+        let mut inner = self.0.borrow_mut();
+        let inner = &mut *inner;
+        let in_flight = inner.in_flight_messages();
+        if let Some(ref mut out) = inner.trace {
+            unwrap!(
+                visualiser::write_round(&mut **out, inner.trace_round, in_flight, nodes),
+                "Failed to write trace:"
+            );
+            inner.trace_round += 1;
+        }
+    }
+
     /// Return whether sent any message since previous query and reset the flag.
     pub fn reset_message_sent(&self) -> bool {
         let mut inner = self.0.borrow_mut();
@@ -136,13 +257,143 @@ impl Network {
         message_sent
     }
 
+    /// Returns the number of messages other than parsec gossip sent over the network so far, i.e.
+    /// the total number of hops they took.
+    pub fn sent_messages(&self) -> usize {
+        self.0.borrow().sent_messages
+    }
+
+    /// Delivers all queued messages and lets `nodes` and `clients` handle them until the network
+    /// goes quiet, then drops them all. Panics unless the simulation ended in a consistent state:
+    ///
+    /// * no messages are in flight or waiting for a connection to a node which is still alive,
+    /// * no node has events about departed nodes waiting in its chain accumulator,
+    /// * no mock node outlives the `Node` or `Client` it belongs to.
+    ///
+    /// Call this at the end of a test instead of just dropping the nodes. Tests using fault
+    /// policies should clear them and advance the clock past any delayed messages first.
+    pub fn shutdown_and_verify<N, C>(&self, nodes: N, clients: C)
+    where
+        N: IntoIterator<Item = RoutingNode>,
+        C: IntoIterator<Item = Client>,
+    {
+        let mut nodes: Vec<_> = nodes.into_iter().collect();
+        let mut clients: Vec<_> = clients.into_iter().collect();
+
+        let mut quiet = false;
+        for _ in 0..MAX_SHUTDOWN_ROUNDS {
+            self.poll();
+            let mut handled = false;
+            for node in &mut nodes {
+                handled = node.poll() || handled;
+            }
+            for client in &mut clients {
+                handled = client.poll() || handled;
+            }
+            if !handled && !self.reset_message_sent() {
+                quiet = true;
+                break;
+            }
+        }
+        assert!(
+            quiet,
+            "Network still busy after {} poll rounds.",
+            MAX_SHUTDOWN_ROUNDS
+        );
+
+        self.0.borrow().verify_no_unsent_messages();
+
+        let live_ids: FxHashSet<PublicId> =
+            nodes.iter().filter_map(|node| node.id().ok()).collect();
+        for node in &nodes {
+            let chain = if let Some(chain) = node.chain() {
+                chain
+            } else {
+                continue;
+            };
+            let departed: Vec<_> = chain
+                .pending_event_subjects()
+                .into_iter()
+                .filter(|id| !live_ids.contains(id))
+                .collect();
+            assert!(
+                departed.is_empty(),
+                "{} has pending events about departed nodes {:?}.",
+                chain,
+                departed
+            );
+        }
+
+        drop(nodes);
+        drop(clients);
+        // Deliver the disconnects of the dropped nodes.
+        self.poll();
+
+        let inner = self.0.borrow();
+        let leaked: Vec<_> = inner
+            .nodes
+            .iter()
+            .filter(|(_, node)| node.upgrade().is_some())
+            .map(|(addr, _)| *addr)
+            .collect();
+        assert!(leaked.is_empty(), "Leaked mock nodes at {:?}.", leaked);
+        assert_eq!(
+            inner.in_flight_messages(),
+            0,
+            "Messages left in flight after shutdown."
+        );
+    }
+
+    /// Starts recording the messages delivered to the node at `addr`, to be retrieved with
+    /// `take_capture`.
+    pub fn capture_messages_to(&self, addr: SocketAddr) {
+        let _ = self
+            .0
+            .borrow_mut()
+            .captures
+            .entry(addr)
+            .or_insert_with(Capture::new);
+    }
+
+    /// Stops recording the messages delivered to the node at `addr` and returns those recorded so
+    /// far.
+    pub fn take_capture(&self, addr: &SocketAddr) -> Capture {
+        self.0
+            .borrow_mut()
+            .captures
+            .remove(addr)
+            .unwrap_or_default()
+    }
+
+    /// Delivers the messages of `capture` to the node at `addr` in order, bypassing the random
+    /// delivery order of `poll`. Senders which aren't connected to the node connect to it first.
+    /// The node's replies to senders which don't exist in this network are dropped.
+    pub fn replay(&self, addr: &SocketAddr, capture: &Capture) -> Result<(), RoutingError> {
+        let node = unwrap!(self.find_node(addr), "No node at {}", addr);
+        for message in &capture.messages {
+            let msg = message.to_network_bytes()?;
+            let mut node = node.borrow_mut();
+            if !node.is_connected(&message.src) {
+                let src_type = if message.src_is_client {
+                    OurType::Client
+                } else {
+                    OurType::Node
+                };
+                node.receive_packet(message.src, Packet::ConnectRequest(src_type));
+            }
+            node.receive_packet(message.src, Packet::Message(msg, 0));
+        }
+        Ok(())
+    }
+
     fn pop_random_packet(&self) -> Option<(Connection, Packet)> {
         self.0.borrow_mut().pop_random_packet()
     }
 
     fn process_packet(&self, connection: &Connection, packet: Packet) {
-        let response = if let Some(dst) = self.find_node(&connection.dst) {
+        let response = if let Some(dst) = self.find_reachable_node(connection) {
             let msg = if let Packet::Message(ref msg, msg_id) = packet {
+                self.record(connection, msg);
                 Some(Packet::MessageSent(msg.clone(), msg_id))
             } else {
                 None
@@ -163,10 +414,32 @@ impl Network {
         }
     }
 
+    fn record(&self, connection: &Connection, msg: &NetworkBytes) {
+        let mut inner = self.0.borrow_mut();
+        if !inner.captures.contains_key(&connection.dst) {
+            return;
+        }
+        let src_is_client = inner
+            .find_node(&connection.src)
+            .map_or(false, |src| src.borrow().our_type() == OurType::Client);
+        let message = unwrap!(CapturedMessage::from_network_bytes(
+            connection.src,
+            src_is_client,
+            msg
+        ));
+        if let Some(capture) = inner.captures.get_mut(&connection.dst) {
+            capture.messages.push(message);
+        }
+    }
+
     fn find_node(&self, addr: &SocketAddr) -> Option<Rc<RefCell<Node>>> {
         self.0.borrow().find_node(addr)
     }
 
+    fn find_reachable_node(&self, connection: &Connection) -> Option<Rc<RefCell<Node>>> {
+        self.0.borrow().find_reachable_node(connection)
+    }
+
     fn send(&self, src: SocketAddr, dst: SocketAddr, packet: Packet) {
         self.0.borrow_mut().send(src, dst, packet)
     }
@@ -179,6 +452,13 @@ pub(super) struct Inner {
     connections: FxHashMap<Connection, Queue>,
     used_ips: FxHashSet<Ipv4Addr>,
     message_sent: bool,
+    sent_messages: usize,
+    fault_policies: FxHashMap<Connection, FaultPolicy>,
+    default_fault_policy: FaultPolicy,
+    trace: Option<Box<dyn Write>>,
+    trace_round: u64,
+    /// Messages delivered to the nodes whose traffic is being recorded.
+    captures: FxHashMap<SocketAddr, Capture>,
 }
 
 impl Inner {
@@ -200,12 +480,41 @@ impl Inner {
         // requires further polling.
         if !packet.is_parsec_gossip() {
             self.message_sent = true;
+            if let Packet::Message(..) = packet {
+                self.sent_messages += 1;
+            }
         }
 
+        let connection = Connection::new(src, dst);
+        let mut deliver_at = None;
+        if let Packet::Message(ref msg, msg_id) = packet {
+            let policy = self.fault_policy(&connection);
+            // Only consult the rng if there is a policy, so seeded tests without one replay the
+            // same way they used to.
+            if policy.drop_probability > 0.0 && self.rng.gen::<f64>() < policy.drop_probability {
+                // The message is lost on the way, but the sender doesn't know that.
+                let sent = Packet::MessageSent(msg.clone(), msg_id);
+                self.push(Connection::new(dst, src), sent, None);
+                return;
+            }
+            deliver_at = Some(Instant::now() + policy.latency.sample(&mut self.rng));
+        }
+
+        self.push(connection, packet, deliver_at)
+    }
+
+    fn push(&mut self, connection: Connection, packet: Packet, deliver_at: Option<Instant>) {
         self.connections
-            .entry(Connection::new(src, dst))
+            .entry(connection)
             .or_insert_with(Queue::new)
-            .push(packet)
+            .push(packet, deliver_at)
+    }
+
+    fn fault_policy(&self, connection: &Connection) -> FaultPolicy {
+        self.fault_policies
+            .get(connection)
+            .cloned()
+            .unwrap_or(self.default_fault_policy)
     }
 
     pub fn disconnect(&mut self, src: SocketAddr, dst: SocketAddr) {
@@ -216,11 +525,70 @@ impl Inner {
         self.nodes.get(addr).and_then(Weak::upgrade)
     }
 
+    // Returns the destination node of `connection`, unless it's of another address family than
+    // the source and so can't be reached from it.
+    fn find_reachable_node(&self, connection: &Connection) -> Option<Rc<RefCell<Node>>> {
+        if connection.src.is_ipv4() != connection.dst.is_ipv4() {
+            return None;
+        }
+        self.find_node(&connection.dst)
+    }
+
+    // Panics if any message is still queued, or waits for a connection to a node which is alive
+    // and reachable, and so should have accepted it.
+    fn verify_no_unsent_messages(&self) {
+        let in_flight = self.in_flight_messages();
+        assert_eq!(in_flight, 0, "{} messages still in flight.", in_flight);
+
+        for (addr, node) in &self.nodes {
+            let node = if let Some(node) = node.upgrade() {
+                node
+            } else {
+                continue;
+            };
+            let stuck: Vec<_> = node
+                .borrow()
+                .pending_message_dsts()
+                .filter(|dst| {
+                    self.find_reachable_node(&Connection::new(*addr, **dst))
+                        .is_some()
+                })
+                .cloned()
+                .collect();
+            assert!(
+                stuck.is_empty(),
+                "{} has messages waiting for connections to live nodes {:?}.",
+                addr,
+                stuck
+            );
+        }
+    }
+
+    fn in_flight_messages(&self) -> usize {
+        self.connections
+            .values()
+            .map(|queue| {
+                queue
+                    .0
+                    .iter()
+                    .filter(|queued| match queued.packet {
+                        Packet::Message(..) => true,
+                        _ => false,
+                    })
+                    .count()
+            })
+            .sum()
+    }
+
     fn pop_random_packet(&mut self) -> Option<(Connection, Packet)> {
+        let now = Instant::now();
         let connections: Vec<_> = self
             .connections
             .iter()
-            .filter(|(_, queue)| !queue.is_empty())
+            .filter(|(connection, queue)| {
+                let window = self.fault_policy(connection).reorder_window;
+                !queue.deliverable(window, now).is_empty()
+            })
             .map(|(connection, _)| connection)
             .collect();
 
@@ -235,9 +603,23 @@ impl Inner {
     }
 
     fn pop_packet(&mut self, connection: Connection) -> Option<Packet> {
+        let window = self.fault_policy(&connection).reorder_window;
         match self.connections.entry(connection) {
             Entry::Occupied(mut entry) => {
-                let packet = entry.get_mut().pop_random_msg(&mut self.rng);
+                let packet = entry.get_mut().pop_random_msg(window, &mut self.rng);
+                if entry.get().is_empty() {
+                    let _ = entry.remove_entry();
+                }
+                packet
+            }
+            Entry::Vacant(_) => None,
+        }
+    }
+
+    fn pop_oldest_packet(&mut self, connection: Connection) -> Option<Packet> {
+        match self.connections.entry(connection) {
+            Entry::Occupied(mut entry) => {
+                let packet = entry.get_mut().0.pop_front().map(|queued| queued.packet);
                 if entry.get().is_empty() {
                     let _ = entry.remove_entry();
                 }
@@ -256,11 +638,11 @@ impl Inner {
 
 // The 4-byte tags of `Message::Direct` and `DirectMessage::ParsecRequest`.
 // A serialised Parsec request message starts with these bytes.
-#[cfg(not(feature = "mock_serialise"))]
+#[cfg(not(any(feature = "mock_serialise", feature = "cbor")))]
 static PARSEC_REQ_MSG_TAGS: &[u8] = &[0, 0, 0, 0, 9, 0, 0, 0];
 // The 4-byte tags of `Message::Direct` and `DirectMessage::ParsecResponse`.
 // A serialised Parsec response message starts with these bytes.
-#[cfg(not(feature = "mock_serialise"))]
+#[cfg(not(any(feature = "mock_serialise", feature = "cbor")))]
 static PARSEC_RSP_MSG_TAGS: &[u8] = &[0, 0, 0, 0, 10, 0, 0, 0];
 
 #[derive(Debug)]
@@ -279,16 +661,36 @@ pub(super) enum Packet {
 
 impl Packet {
     // Returns `true` if this packet contains a Parsec request or response.
-    #[cfg(not(feature = "mock_serialise"))]
+    #[cfg(not(any(feature = "mock_serialise", feature = "cbor")))]
     pub fn is_parsec_gossip(&self) -> bool {
         match self {
-            Packet::Message(bytes, _) if bytes.len() >= 8 => {
-                &bytes[..8] == PARSEC_REQ_MSG_TAGS || &bytes[..8] == PARSEC_RSP_MSG_TAGS
+            Packet::Message(bytes, _) => {
+                let payload = compat::payload(bytes);
+                payload.len() >= 8
+                    && (&payload[..8] == PARSEC_REQ_MSG_TAGS
+                        || &payload[..8] == PARSEC_RSP_MSG_TAGS)
             }
             _ => false,
         }
     }
 
+    // CBOR doesn't put the variant at a fixed offset, so the message has to be decoded.
+    #[cfg(all(feature = "cbor", not(feature = "mock_serialise")))]
+    pub fn is_parsec_gossip(&self) -> bool {
+        use crate::messages::{DirectMessage, Message};
+
+        match self {
+            Packet::Message(bytes, _) => match compat::deserialise(bytes) {
+                Ok((_, Message::Direct(ref message))) => match message.content() {
+                    DirectMessage::ParsecRequest(..) | DirectMessage::ParsecResponse(..) => true,
+                    _ => false,
+                },
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
     #[cfg(feature = "mock_serialise")]
     pub fn is_parsec_gossip(&self) -> bool {
         use crate::messages::{DirectMessage, Message};
@@ -306,40 +708,132 @@ impl Packet {
     }
 }
 
-struct Queue(VecDeque<Packet>);
+struct Queue(VecDeque<QueuedPacket>);
+
+struct QueuedPacket {
+    packet: Packet,
+    // The time a message becomes deliverable, according to the fault policy of its link.
+    deliver_at: Option<Instant>,
+}
 
 impl Queue {
     fn new() -> Self {
         Queue(VecDeque::new())
     }
 
-    fn push(&mut self, packet: Packet) {
-        self.0.push_back(packet)
+    fn push(&mut self, packet: Packet, deliver_at: Option<Instant>) {
+        self.0.push_back(QueuedPacket { packet, deliver_at })
     }
 
     fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
 
-    // This function will pop random msg from the queue.
-    fn pop_random_msg(&mut self, rng: &mut SeededRng) -> Option<Packet> {
-        let first_non_msg_packet = self
-            .0
-            .iter()
-            .position(|packet| {
-                if let Packet::Message(_, _) = packet {
-                    false
+    // Returns the indices of the packets that can be delivered next: those of the leading
+    // messages (limited to `reorder_window`, if any) which are due, or the first packet if it
+    // isn't a message.
+    fn deliverable(&self, reorder_window: Option<usize>, now: Instant) -> Vec<usize> {
+        let is_msg = |queued: &QueuedPacket| {
+            if let Packet::Message(_, _) = queued.packet {
+                true
+            } else {
+                false
+            }
+        };
+        let candidates = if let Some(window) = reorder_window {
+            let leading_msgs = self.0.iter().take_while(|queued| is_msg(queued)).count();
+            cmp::min(leading_msgs, window)
+        } else {
+            self.0
+                .iter()
+                .position(|queued| !is_msg(queued))
+                .unwrap_or(0)
+        };
+
+        (0..cmp::min(cmp::max(candidates, 1), self.0.len()))
+            .filter(|&index| self.0[index].deliver_at.map_or(true, |time| time <= now))
+            .collect()
+    }
+
+    // This function will pop random msg from the queue, among the deliverable ones.
+    fn pop_random_msg(
+        &mut self,
+        reorder_window: Option<usize>,
+        rng: &mut SeededRng,
+    ) -> Option<Packet> {
+        let deliverable = self.deliverable(reorder_window, Instant::now());
+        let selected = *rng.choose(&deliverable)?;
+        self.0.remove(selected).map(|queued| queued.packet)
+    }
+}
+
+/// How the mock network interferes with the messages sent over a link. Other packets, like
+/// connection requests, are always delivered unchanged.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FaultPolicy {
+    /// The probability with which a message is lost. The sender still gets a `SentUserMessage`
+    /// event for it.
+    pub drop_probability: f64,
+    /// The time a message takes before it can be delivered. Messages delayed past the current
+    /// time stay in flight until `FakeClock` is advanced.
+    pub latency: Latency,
+    /// If set, only the first `reorder_window` of the messages queued on the link are candidates
+    /// for the next delivery, so `Some(1)` makes it FIFO. If `None`, the network reorders
+    /// messages as it always has.
+    pub reorder_window: Option<usize>,
+}
+
+/// The distribution of message latencies on a link.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Latency {
+    /// Messages are delivered immediately.
+    Zero,
+    /// Every message is delayed by the same time.
+    Fixed(Duration),
+    /// Delays are uniformly distributed between `min` and `max`.
+    Uniform {
+        /// The shortest delay.
+        min: Duration,
+        /// The longest delay.
+        max: Duration,
+    },
+    /// Delays are exponentially distributed with the given mean.
+    Exponential {
+        /// The mean delay.
+        mean: Duration,
+    },
+}
+
+impl Default for Latency {
+    fn default() -> Self {
+        Latency::Zero
+    }
+}
+
+impl Latency {
+    fn sample(&self, rng: &mut SeededRng) -> Duration {
+        match *self {
+            Latency::Zero => Duration::from_millis(0),
+            Latency::Fixed(delay) => delay,
+            Latency::Uniform { min, max } => {
+                if max <= min {
+                    min
                 } else {
-                    true
+                    Duration::from_millis(rng.gen_range(millis(min), millis(max) + 1))
                 }
-            })
-            .unwrap_or(0);
-
-        let selected = rng.gen_range(0, cmp::max(first_non_msg_packet, 1));
-        self.0.remove(selected)
+            }
+            Latency::Exponential { mean } => {
+                let sample = -(millis(mean) as f64) * (1.0 - rng.gen::<f64>()).ln();
+                Duration::from_millis(sample as u64)
+            }
+        }
     }
 }
 
+fn millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
+}
+
 #[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
 struct Connection {
     src: SocketAddr,
@@ -226,10 +226,19 @@ impl Node {
         self.bootstrap_cache.iter().cloned().collect()
     }
 
+    pub fn our_type(&self) -> OurType {
+        self.config.our_type
+    }
+
     pub fn is_connected(&self, addr: &SocketAddr) -> bool {
         self.peers.get(addr).is_some()
     }
 
+    /// Returns the addresses of the peers with messages waiting for a connection to them.
+    pub fn pending_message_dsts(&self) -> impl Iterator<Item = &SocketAddr> {
+        self.pending_messages.keys()
+    }
+
     fn fire_event(&self, event: Event) {
         let _ = self.event_tx.send(event);
     }
@@ -271,10 +280,6 @@ impl Node {
     pub fn addr(&self) -> &SocketAddr {
         &self.addr
     }
-
-    pub fn our_type(&self) -> OurType {
-        self.config.our_type
-    }
 }
 
 impl Drop for Node {
@@ -6,9 +6,16 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{Builder, Config, Event, Network, NodeInfo, OurType, Peer, QuicP2p};
-use crate::NetworkBytes;
+use super::{
+    Builder, Config, Event, FaultPolicy, Latency, Network, NodeInfo, OurType, Peer, QuicP2p,
+};
+use crate::{
+    time::Duration,
+    transport::{AddressFamilyPolicy, DualStack, Transport},
+    NetworkBytes,
+};
 use crossbeam_channel::{self as mpmc, Receiver, TryRecvError};
+use fake_clock::FakeClock;
 use fxhash::FxHashSet;
 use std::{iter, net::SocketAddr};
 use unwrap::unwrap;
@@ -316,6 +323,70 @@ fn send_multiple_messages_without_connecting_first() {
     expected_messages_received(msgs.to_vec(), received_messages);
 }
 
+#[test]
+fn connect_across_address_families() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut a = Agent::node(&network);
+    let b = Agent::node_v6(&network);
+
+    a.connect_to(b.addr());
+    network.poll();
+    a.expect_none();
+    b.expect_none();
+
+    let msg = gen_message();
+    a.send(b.addr(), msg, 0);
+    network.poll();
+    a.expect_none();
+    b.expect_none();
+}
+
+#[test]
+fn dual_stack_reaches_peers_of_both_families() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let a = Agent::node(&network);
+    let b = Agent::node_v6(&network);
+
+    let (tx, rx) = mpmc::unbounded();
+    let ipv4_addr = network.gen_next_addr();
+    let ipv4 = unwrap!(Builder::new(tx.clone()).with_config(Config::node()).build());
+    let ipv6_addr = network.gen_addr_v6();
+    network.set_next_addr(ipv6_addr);
+    let ipv6 = unwrap!(Builder::new(tx).with_config(Config::node()).build());
+    let mut dual_stack = DualStack::new(
+        Box::new(ipv4),
+        Box::new(ipv6),
+        AddressFamilyPolicy::PreferIpv6,
+    );
+
+    let endpoints: Vec<_> = unwrap!(dual_stack.our_endpoints())
+        .into_iter()
+        .map(|node_info| node_info.peer_addr)
+        .collect();
+    assert_eq!(endpoints, vec![ipv6_addr, ipv4_addr]);
+
+    // Each peer is reached over the transport of its own family.
+    let msg_a = gen_message();
+    let msg_b = gen_message();
+    dual_stack.send(Peer::node(a.addr()), msg_a.clone(), 0);
+    dual_stack.send(Peer::node(b.addr()), msg_b.clone(), 1);
+    network.poll();
+
+    a.expect_connected_to_node(&ipv4_addr);
+    a.expect_new_message(&ipv4_addr, &msg_a);
+    b.expect_connected_to_node(&ipv6_addr);
+    b.expect_new_message(&ipv6_addr, &msg_b);
+
+    let sent: FxHashSet<_> = rx
+        .try_iter()
+        .filter_map(|event| match event {
+            Event::SentUserMessage { peer_addr, .. } => Some(peer_addr),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(sent, [a.addr(), b.addr()].iter().cloned().collect());
+}
+
 #[test]
 fn our_connection_info_of_node() {
     let network = Network::new(MIN_SECTION_SIZE, None);
@@ -375,14 +446,117 @@ fn drop_disconnects() {
     b.expect_connection_failure(&a_addr);
 }
 
+#[test]
+fn dropped_messages_are_not_delivered() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut a = Agent::node(&network);
+    let mut b = Agent::node(&network);
+
+    establish_connection(&network, &mut a, &mut b);
+
+    let policy = FaultPolicy {
+        drop_probability: 1.0,
+        ..Default::default()
+    };
+    network.set_fault_policy(a.addr(), b.addr(), policy);
+
+    let msg = gen_message();
+    a.send(b.addr(), msg.clone(), 0);
+    network.poll();
+
+    a.expect_sent_message(&b.addr(), &msg, 0);
+    b.expect_none();
+
+    // The opposite direction is unaffected.
+    b.send(a.addr(), msg.clone(), 1);
+    network.poll();
+
+    b.expect_sent_message(&a.addr(), &msg, 1);
+    a.expect_new_message(&b.addr(), &msg);
+}
+
+#[test]
+fn delayed_messages_are_delivered_after_latency() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut a = Agent::node(&network);
+    let mut b = Agent::node(&network);
+
+    establish_connection(&network, &mut a, &mut b);
+
+    network.set_default_fault_policy(FaultPolicy {
+        latency: Latency::Fixed(Duration::from_secs(1)),
+        ..Default::default()
+    });
+
+    let msg = gen_message();
+    a.send(b.addr(), msg.clone(), 0);
+    network.poll();
+    b.expect_none();
+
+    FakeClock::advance_time(999);
+    network.poll();
+    b.expect_none();
+
+    FakeClock::advance_time(1);
+    network.poll();
+    b.expect_new_message(&a.addr(), &msg);
+    a.expect_sent_message(&b.addr(), &msg, 0);
+}
+
+#[test]
+fn reordering_is_limited_to_window() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut a = Agent::node(&network);
+    let mut b = Agent::node(&network);
+
+    establish_connection(&network, &mut a, &mut b);
+
+    let send_and_receive = |a: &mut Agent, window| {
+        network.set_fault_policy(
+            a.addr(),
+            b.addr(),
+            FaultPolicy {
+                reorder_window: Some(window),
+                ..Default::default()
+            },
+        );
+        let msgs: Vec<_> = (0..50).map(|_| gen_message()).collect();
+        for (token, msg) in msgs.iter().enumerate() {
+            a.send(b.addr(), msg.clone(), token as u64);
+        }
+        network.poll();
+
+        let mut received = Vec::new();
+        while let Ok(Event::NewMessage { msg, .. }) = b.rx.try_recv() {
+            received.push(unwrap!(msgs.iter().position(|sent| *sent == msg)));
+        }
+        received
+    };
+
+    // A window of one keeps the messages in order...
+    let received = send_and_receive(&mut a, 1);
+    assert_eq!(received, (0..50).collect::<Vec<_>>());
+
+    // ... while a larger one reorders them, but never delivers a message before all of those
+    // sent more than a window earlier.
+    let window = 3;
+    let received = send_and_receive(&mut a, window);
+    assert_eq!(received.len(), 50);
+    assert_ne!(received, (0..50).collect::<Vec<_>>());
+    for (position, index) in received.iter().enumerate() {
+        assert!(*index < position + window);
+    }
+}
+
 #[test]
 #[cfg(not(feature = "mock_serialise"))]
 fn packet_is_parsec_gossip() {
     use super::network::Packet;
     use crate::{
+        codec::{Codec, WireCodec},
         id::FullId,
         messages::{
-            DirectMessage, HopMessage, Message, MessageContent, RoutingMessage,
+            compat, DirectMessage, HopMessage, Message, MessageContent, RoutingMessage,
             SignedDirectMessage, SignedRoutingMessage,
         },
         parsec::{Request, Response},
@@ -390,13 +564,12 @@ fn packet_is_parsec_gossip() {
         types::MessageId,
     };
 
-    use maidsafe_utilities::serialisation;
     use serde::Serialize;
 
     let full_id = FullId::new();
 
     fn serialise<T: Serialize>(msg: &T) -> Vec<u8> {
-        unwrap!(serialisation::serialise(&msg))
+        unwrap!(WireCodec::encode(msg))
     }
 
     let make_message =
@@ -412,8 +585,8 @@ fn packet_is_parsec_gossip() {
         let repr = Vec::<u64>::new();
 
         (
-            unwrap!(serialisation::deserialise(&serialise(&repr))),
-            unwrap!(serialisation::deserialise(&serialise(&repr))),
+            unwrap!(WireCodec::decode(&serialise(&repr))),
+            unwrap!(WireCodec::decode(&serialise(&repr))),
         )
     };
 
@@ -423,6 +596,9 @@ fn packet_is_parsec_gossip() {
     ];
     for msg in &msgs {
         assert!(Packet::Message(NetworkBytes::from(serialise(msg)), 0).is_parsec_gossip());
+        // Also behind the header of the current protocol version.
+        let bytes = unwrap!(compat::serialise(msg, compat::PROTOCOL_VERSION));
+        assert!(Packet::Message(NetworkBytes::from(bytes), 0).is_parsec_gossip());
     }
 
     // No other direct message types contain a Parsec request or response.
@@ -441,6 +617,7 @@ fn packet_is_parsec_gossip() {
         content: MessageContent::Relocate {
             message_id: MessageId::new(),
         },
+        dst_min_version: None,
     };
     let msg = SignedRoutingMessage::insecure(msg);
     let msg = unwrap!(HopMessage::new(msg));
@@ -479,8 +656,17 @@ impl Agent {
         Self::with_config(network, Config::client())
     }
 
+    // Create new test agent who is a node listening on IPv6.
+    fn node_v6(network: &Network) -> Self {
+        Self::with_addr(network, network.gen_addr_v6(), Config::node())
+    }
+
     fn with_config(network: &Network, config: Config) -> Self {
-        let _ = network.gen_next_addr();
+        Self::with_addr(network, network.gen_addr(), config)
+    }
+
+    fn with_addr(network: &Network, addr: SocketAddr, config: Config) -> Self {
+        network.set_next_addr(addr);
         let (tx, rx) = mpmc::unbounded();
         let inner = unwrap!(Builder::new(tx).with_config(config).build());
 
@@ -0,0 +1,147 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Replay of captured message sequences into mock nodes.
+//!
+//! A `Capture` is a list of the routing payloads one node received, in order, together with their
+//! senders. It can be extracted from a packet capture of a live network, or recorded in a mock
+//! network via `Network::capture_messages_to`. `Network::replay` feeds it into a mock node, so a
+//! bug observed on a testnet can be turned into a deterministic test.
+
+use crate::{
+    error::RoutingError,
+    messages::{compat, Message},
+    NetworkBytes,
+};
+use maidsafe_utilities::serialisation;
+use std::net::SocketAddr;
+
+/// A routing message received by a node.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CapturedMessage {
+    /// The address of the sender.
+    pub src: SocketAddr,
+    /// Whether the sender connected to the node as a client.
+    pub src_is_client: bool,
+    /// The serialised routing message, as carried in a quic-p2p user message.
+    pub payload: Vec<u8>,
+}
+
+impl CapturedMessage {
+    /// Returns a description of the decoded message, or an error if the payload isn't a valid
+    /// routing message.
+    pub fn decode(&self) -> Result<String, RoutingError> {
+        decode_payload(&self.payload).map(|message| format!("{:?}", message))
+    }
+
+    pub(crate) fn from_network_bytes(
+        src: SocketAddr,
+        src_is_client: bool,
+        msg: &NetworkBytes,
+    ) -> Result<Self, RoutingError> {
+        #[cfg(not(feature = "mock_serialise"))]
+        let payload = msg.to_vec();
+
+        #[cfg(feature = "mock_serialise")]
+        let payload = serialisation::serialise(&**msg)?;
+
+        Ok(Self {
+            src,
+            src_is_client,
+            payload,
+        })
+    }
+
+    pub(crate) fn to_network_bytes(&self) -> Result<NetworkBytes, RoutingError> {
+        #[cfg(not(feature = "mock_serialise"))]
+        let result = Ok(NetworkBytes::from(self.payload.clone()));
+
+        #[cfg(feature = "mock_serialise")]
+        let result = decode_payload(&self.payload).map(NetworkBytes::new);
+
+        result
+    }
+}
+
+/// A sequence of messages received by a node, in the order they arrived.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Capture {
+    /// The captured messages.
+    pub messages: Vec<CapturedMessage>,
+}
+
+impl Capture {
+    /// Creates an empty capture.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a capture serialised by `to_bytes`, e.g. by a tool extracting the routing payloads
+    /// from a packet capture.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RoutingError> {
+        Ok(serialisation::deserialise(bytes)?)
+    }
+
+    /// Serialises the capture, e.g. to store it as a test fixture.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, RoutingError> {
+        Ok(serialisation::serialise(self)?)
+    }
+
+    /// Appends a message received from `src`.
+    pub fn push(&mut self, src: SocketAddr, src_is_client: bool, payload: Vec<u8>) {
+        self.messages.push(CapturedMessage {
+            src,
+            src_is_client,
+            payload,
+        })
+    }
+
+    /// Returns the number of captured messages.
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Returns whether no messages were captured.
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
+
+fn decode_payload(payload: &[u8]) -> Result<Message, RoutingError> {
+    compat::deserialise(payload).map(|(_, message)| message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::FullId;
+    use crate::messages::{DirectMessage, SignedDirectMessage};
+    use unwrap::unwrap;
+
+    #[test]
+    fn round_trip() {
+        let full_id = FullId::new();
+        let message = Message::Direct(unwrap!(SignedDirectMessage::new(
+            DirectMessage::BootstrapRequest,
+            &full_id
+        )));
+        let src: SocketAddr = unwrap!("127.0.0.1:9999".parse());
+
+        let mut capture = Capture::new();
+        capture.push(src, true, unwrap!(serialisation::serialise(&message)));
+        capture.push(src, false, vec![1, 2, 3]);
+
+        let parsed = unwrap!(Capture::from_bytes(&unwrap!(capture.to_bytes())));
+        assert_eq!(parsed, capture);
+        assert_eq!(
+            unwrap!(parsed.messages[0].decode()),
+            format!("{:?}", message)
+        );
+        assert!(parsed.messages[1].decode().is_err());
+    }
+}
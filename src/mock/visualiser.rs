@@ -0,0 +1,104 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Line-based trace of the mock network state, meant to be consumed by an external visualiser.
+//!
+//! The trace is enabled with `Network::set_trace_output`, or by setting the
+//! `ROUTING_MOCK_TRACE` environment variable to the path of the file to write it to. After each
+//! poll round passed to `Network::trace_round`, a block of lines separated by single spaces is
+//! appended:
+//!
+//! ```text
+//! ROUND <round> <in-flight>
+//! SECTION <prefix> <version> <member>...
+//! NODE <name> <prefix> <version>
+//! END
+//! ```
+//!
+//! * `<round>` counts the traced rounds of the network, starting at `0`.
+//! * `<in-flight>` is the number of messages queued in the network which weren't delivered yet.
+//! * There is one `SECTION` line per distinct section info held by any node as its own section's
+//!   info, sorted by prefix and version. `<member>`s are the names of its members.
+//! * There is one `NODE` line per traced node, giving the section info it considers current. Nodes
+//!   which are not members of a section yet have `-` as both `<prefix>` and `<version>`.
+//! * `<prefix>` is written as a string of bits, with `_` denoting the empty prefix.
+//! * Names are written in their short hex form, as in the logs.
+//!
+//! Consumers should ignore lines starting with unknown keywords, so that more can be added.
+
+use crate::{chain::SectionInfo, node::Node, Prefix, XorName};
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+/// Environment variable holding the path of the file to write the trace to.
+pub const TRACE_ENV_VAR: &str = "ROUTING_MOCK_TRACE";
+
+/// Writes the trace block of one poll round of `nodes` to `out`.
+pub(crate) fn write_round<'a, I>(
+    out: &mut dyn Write,
+    round: u64,
+    in_flight: usize,
+    nodes: I,
+) -> io::Result<()>
+where
+    I: IntoIterator<Item = &'a Node>,
+{
+    writeln!(out, "ROUND {} {}", round, in_flight)?;
+
+    let mut node_lines = Vec::new();
+    let mut sections = BTreeMap::new();
+    for node in nodes {
+        let name = match node.id() {
+            Ok(id) => *id.name(),
+            Err(_) => continue,
+        };
+        match node.chain().filter(|chain| chain.is_member()) {
+            Some(chain) => {
+                let info = chain.our_info();
+                node_lines.push(format!(
+                    "NODE {} {} {}",
+                    name,
+                    prefix_bits(info.prefix()),
+                    info.version()
+                ));
+                let _ = sections
+                    .entry((*info.prefix(), *info.version()))
+                    .or_insert_with(|| info.clone());
+            }
+            None => node_lines.push(format!("NODE {} - -", name)),
+        }
+    }
+
+    for ((prefix, version), info) in &sections {
+        write!(out, "SECTION {} {}", prefix_bits(prefix), version)?;
+        write_members(out, info)?;
+        writeln!(out)?;
+    }
+    for line in node_lines {
+        writeln!(out, "{}", line)?;
+    }
+    writeln!(out, "END")?;
+    out.flush()
+}
+
+fn write_members(out: &mut dyn Write, info: &SectionInfo) -> io::Result<()> {
+    for name in info.member_names() {
+        write!(out, " {}", name)?;
+    }
+    Ok(())
+}
+
+fn prefix_bits(prefix: &Prefix<XorName>) -> String {
+    if prefix.bit_count() == 0 {
+        "_".to_string()
+    } else {
+        format!("{:b}", prefix)
+    }
+}
@@ -0,0 +1,196 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    config_handler::Config,
+    constants::{
+        self, APPROVAL_TIMEOUT, CANDIDATE_EXPIRED_TIMEOUT, MIN_SECTION_SIZE,
+        QUEUED_CANDIDATE_TIMEOUT, QUORUM_DENOMINATOR, QUORUM_NUMERATOR, RESOURCE_PROOF_DURATION,
+    },
+    error::RoutingError,
+    time::Duration,
+};
+
+/// Parameters all nodes of a network have to agree on, and the timeouts a node uses.
+///
+/// The defaults are the values from `constants`. Testnets can run with different ones by passing
+/// them to `NodeBuilder::network_params`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct NetworkParams {
+    /// Minimum number of nodes we consider acceptable in a section.
+    pub min_section_size: usize,
+    /// Quorum is defined as having strictly greater than `quorum_numerator / quorum_denominator`
+    /// agreement.
+    pub quorum_numerator: usize,
+    /// See `quorum_numerator`.
+    pub quorum_denominator: usize,
+    /// How long the node waits for its peers. Unlike the other parameters, these don't need to
+    /// agree across the network, so they aren't saved with the chain either.
+    #[serde(skip)]
+    pub timeouts: Timeouts,
+}
+
+/// How long a node or client waits for its peers before it gives up on them.
+///
+/// Nodes take them from their `NetworkParams`, and can override them with `NodeBuilder::timeouts`.
+/// Clients take them from their `ClientConfig`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Timeouts {
+    /// How long to wait for the node we bootstrap off, or a client fails over to, to accept us.
+    pub bootstrap: Duration,
+    /// How long our section waits for its current candidate to be approved before purging it.
+    pub candidate_approval: Duration,
+    /// How long a joining node waits for its approval before it gives up and restarts.
+    pub node_approval: Duration,
+    /// How long a candidate waits in our section's queue for the current one to complete.
+    pub queued_candidate: Duration,
+    /// How long a message handed to the transport is waited for to be acknowledged as sent,
+    /// before it no longer counts against the peer's limit of messages in flight.
+    pub send_ack: Duration,
+    /// How long an elder waits for the response to a GET it relayed for one of its clients.
+    pub relayed_get_response: Duration,
+}
+
+impl Timeouts {
+    /// Checks that all timeouts are non-zero, and that candidates get at least
+    /// `RESOURCE_PROOF_DURATION` to be approved, as they can't pass their resource proof before.
+    pub fn validate(&self) -> Result<(), RoutingError> {
+        let durations = [
+            self.bootstrap,
+            self.candidate_approval,
+            self.node_approval,
+            self.queued_candidate,
+            self.send_ack,
+            self.relayed_get_response,
+        ];
+        if durations
+            .iter()
+            .any(|duration| *duration == Duration::from_secs(0))
+            || self.candidate_approval < RESOURCE_PROOF_DURATION
+            || self.node_approval < RESOURCE_PROOF_DURATION
+        {
+            return Err(RoutingError::InvalidNetworkParams);
+        }
+        Ok(())
+    }
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            bootstrap: Duration::from_secs(20),
+            candidate_approval: CANDIDATE_EXPIRED_TIMEOUT,
+            node_approval: APPROVAL_TIMEOUT,
+            queued_candidate: QUEUED_CANDIDATE_TIMEOUT,
+            send_ack: Duration::from_secs(30),
+            relayed_get_response: Duration::from_secs(2 * 60),
+        }
+    }
+}
+
+impl NetworkParams {
+    /// Returns the default parameters, with the minimal section size used with the given config.
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            min_section_size: constants::min_section_size(config),
+            ..Self::default()
+        }
+    }
+
+    /// Checks that the parameters are safe to run a network with: sections can't be empty, and a
+    /// quorum has to be more than two thirds of the voters, so that any two quorums share a
+    /// majority of honest voters as long as less than a third of them are malicious. The timeouts
+    /// have to be valid too.
+    pub fn validate(&self) -> Result<(), RoutingError> {
+        if self.min_section_size == 0
+            || self.quorum_numerator >= self.quorum_denominator
+            || self.quorum_numerator * 3 < self.quorum_denominator * 2
+        {
+            return Err(RoutingError::InvalidNetworkParams);
+        }
+        self.timeouts.validate()
+    }
+
+    /// Returns whether `votes` out of `voters` are a quorum.
+    pub fn is_quorum(&self, votes: usize, voters: usize) -> bool {
+        votes * self.quorum_denominator > voters * self.quorum_numerator
+    }
+
+    /// Returns the smallest number of votes which are a quorum out of `voters`.
+    pub fn quorum_size(&self, voters: usize) -> usize {
+        voters * self.quorum_numerator / self.quorum_denominator + 1
+    }
+}
+
+impl Default for NetworkParams {
+    fn default() -> Self {
+        Self {
+            min_section_size: MIN_SECTION_SIZE,
+            quorum_numerator: QUORUM_NUMERATOR,
+            quorum_denominator: QUORUM_DENOMINATOR,
+            timeouts: Timeouts::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validation() {
+        assert!(NetworkParams::default().validate().is_ok());
+
+        let params = |quorum_numerator, quorum_denominator| NetworkParams {
+            quorum_numerator,
+            quorum_denominator,
+            ..NetworkParams::default()
+        };
+        assert!(params(3, 4).validate().is_ok());
+        assert!(params(4, 6).validate().is_ok());
+        assert!(params(1, 2).validate().is_err());
+        assert!(params(3, 3).validate().is_err());
+        assert!(params(0, 0).validate().is_err());
+
+        let empty_sections = NetworkParams {
+            min_section_size: 0,
+            ..NetworkParams::default()
+        };
+        assert!(empty_sections.validate().is_err());
+
+        assert!(Timeouts::default().validate().is_ok());
+        let no_bootstrap_timeout = Timeouts {
+            bootstrap: Duration::from_secs(0),
+            ..Timeouts::default()
+        };
+        assert!(no_bootstrap_timeout.validate().is_err());
+        let impatient_section = NetworkParams {
+            timeouts: Timeouts {
+                candidate_approval: RESOURCE_PROOF_DURATION / 2,
+                ..Timeouts::default()
+            },
+            ..NetworkParams::default()
+        };
+        assert!(impatient_section.validate().is_err());
+    }
+
+    #[test]
+    fn quorum_size_is_smallest_quorum() {
+        let params = NetworkParams {
+            quorum_numerator: 3,
+            quorum_denominator: 4,
+            ..NetworkParams::default()
+        };
+        for voters in 1..50 {
+            let quorum = params.quorum_size(voters);
+            assert!(params.is_quorum(quorum, voters));
+            assert!(!params.is_quorum(quorum - 1, voters));
+            assert!(quorum >= NetworkParams::default().quorum_size(voters));
+        }
+    }
+}
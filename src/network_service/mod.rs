@@ -6,33 +6,61 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+mod outgoing_queue;
+mod peer_queues;
 mod sending_targets_cache;
 
 use crate::{
-    quic_p2p::{Builder, Error, Token},
+    bootstrap,
+    connection_policy::{AcceptAll, ConnectionPolicy, InboundConnection},
+    error::RoutingError,
+    messages::CONSENSUS_PRIORITY,
+    metrics::Metrics,
+    quic_p2p::{Builder, Token},
+    time::Duration,
+    transport::{Transport, TransportFactory},
     utils::LogIdent,
-    ConnectionInfo, NetworkBytes, NetworkConfig, NetworkEvent, QuicP2p,
+    ConnectionInfo, NetworkBytes, NetworkConfig, NetworkEvent,
+};
+#[cfg(feature = "node")]
+use crate::{
+    middleware::{MessageContext, MessageMiddleware, MiddlewarePipeline, Verdict},
+    quic_p2p::NodeInfo,
+    transport::AddressFamilyPolicy,
 };
 use crossbeam_channel::Sender;
 use std::net::SocketAddr;
+use std::sync::Arc;
 
+use outgoing_queue::OutgoingQueue;
+use peer_queues::PeerQueues;
 use sending_targets_cache::SendingTargetsCache;
 
 /// Struct that handles network operations: sending and receiving messages, as well as resending on
 /// failure.
 pub struct NetworkService {
-    quic_p2p: QuicP2p,
+    transport: Box<dyn Transport>,
     cache: SendingTargetsCache,
     next_msg_token: Token,
+    outgoing: OutgoingQueue<(Vec<ConnectionInfo>, usize, NetworkBytes)>,
+    peer_queues: PeerQueues<(ConnectionInfo, NetworkBytes)>,
+    connection_policy: Box<dyn ConnectionPolicy>,
+    #[cfg(feature = "node")]
+    middleware: MiddlewarePipeline,
+    metrics: Arc<Metrics>,
+    bootstrap_cache: bootstrap::Cache,
+    #[cfg(feature = "node")]
+    address_family_policy: AddressFamilyPolicy,
 }
 
 impl NetworkService {
-    pub fn service(&self) -> &QuicP2p {
-        &self.quic_p2p
+    #[cfg(feature = "node")]
+    pub fn service(&self) -> &dyn Transport {
+        &*self.transport
     }
 
-    pub fn service_mut(&mut self) -> &mut QuicP2p {
-        &mut self.quic_p2p
+    pub fn service_mut(&mut self) -> &mut dyn Transport {
+        &mut *self.transport
     }
 
     pub fn next_msg_token(&mut self) -> Token {
@@ -40,25 +68,148 @@ impl NetworkService {
         self.next_msg_token
     }
 
-    pub fn targets_cache_mut(&mut self) -> &mut SendingTargetsCache {
-        &mut self.cache
+    #[cfg(feature = "node")]
+    pub fn set_connection_policy(&mut self, policy: Box<dyn ConnectionPolicy>) {
+        self.connection_policy = policy;
+    }
+
+    /// Appends `middleware` to the pipeline user messages pass through.
+    #[cfg(feature = "node")]
+    pub fn add_middleware(&mut self, middleware: Box<dyn MessageMiddleware>) {
+        self.middleware.push(middleware);
+    }
+
+    /// Sets how long a message handed to the transport is waited for to be reported as sent.
+    pub fn set_send_ack_timeout(&mut self, timeout: Duration) {
+        self.peer_queues.set_in_flight_timeout(timeout);
+    }
+
+    /// Makes the node record its metrics in `metrics`.
+    #[cfg(feature = "node")]
+    pub fn set_metrics(&mut self, metrics: Arc<Metrics>) {
+        self.metrics = metrics;
+    }
+
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    #[cfg(feature = "node")]
+    pub fn set_address_family_policy(&mut self, policy: AddressFamilyPolicy) {
+        self.address_family_policy = policy;
+    }
+
+    /// Picks the endpoint to connect to among those a peer advertised: one of an address family
+    /// we listen on, as allowed and preferred by our policy.
+    #[cfg(feature = "node")]
+    pub fn select_endpoint(&mut self, endpoints: &[NodeInfo]) -> Option<NodeInfo> {
+        // Clients don't listen, so they can connect over any family.
+        let ours = self.transport.our_endpoints().unwrap_or_default();
+        self.address_family_policy
+            .select(endpoints, |addr| {
+                ours.is_empty()
+                    || ours
+                        .iter()
+                        .any(|our| our.peer_addr.is_ipv4() == addr.is_ipv4())
+            })
+            .cloned()
+    }
+
+    #[cfg(feature = "node")]
+    pub fn set_bootstrap_cache(&mut self, cache: bootstrap::Cache) {
+        self.bootstrap_cache = cache;
+    }
+
+    pub fn bootstrap_cache(&self) -> &bootstrap::Cache {
+        &self.bootstrap_cache
+    }
+
+    #[cfg(feature = "node")]
+    pub fn bootstrap_cache_mut(&mut self) -> &mut bootstrap::Cache {
+        &mut self.bootstrap_cache
+    }
+
+    /// Records that we connected to the node at `peer_addr`, saving the bootstrap cache if we
+    /// bootstrapped off it.
+    pub fn bootstrap_contact_reached(&mut self, peer_addr: &SocketAddr, bootstrapped: bool) {
+        self.bootstrap_cache.record_success(peer_addr);
+        if bootstrapped {
+            self.save_bootstrap_cache();
+        }
     }
 
+    /// Records that the connection to `peer_addr` failed, saving the bootstrap cache if that
+    /// demoted it.
+    pub fn bootstrap_contact_failed(&mut self, peer_addr: &SocketAddr) {
+        if self.bootstrap_cache.record_failure(peer_addr) {
+            debug!("Demoted unreachable bootstrap contact {}.", peer_addr);
+            self.save_bootstrap_cache();
+        }
+    }
+
+    fn save_bootstrap_cache(&mut self) {
+        if let Err(error) = self.bootstrap_cache.save() {
+            warn!("Failed to save the bootstrap cache: {:?}", error);
+        }
+    }
+
+    /// Passes the user message `msg` through the middleware pipeline.
+    #[cfg(feature = "node")]
+    pub fn process_user_message(&mut self, msg: &MessageContext) -> Verdict {
+        self.middleware.process(msg)
+    }
+
+    /// Asks the connection policy whether to accept the inbound connection from `conn_info`.
+    pub fn accept_connection(
+        &mut self,
+        conn_info: &ConnectionInfo,
+        current_connections: usize,
+    ) -> bool {
+        let is_client = match *conn_info {
+            ConnectionInfo::Client { .. } => true,
+            ConnectionInfo::Node { .. } => false,
+        };
+        self.connection_policy.accept(&InboundConnection {
+            peer_addr: conn_info.peer_addr(),
+            is_client,
+            current_connections,
+        })
+    }
+
+    /// Queues `msg` to be sent to the first `dg_size` of `conn_infos` with the given priority.
+    /// Nothing is sent until the next `flush_outgoing`.
     pub fn send_message_to_initial_targets(
         &mut self,
         conn_infos: Vec<ConnectionInfo>,
         dg_size: usize,
         msg: NetworkBytes,
+        priority: u8,
     ) {
-        let token = self.next_msg_token();
+        self.outgoing.push(priority, (conn_infos, dg_size, msg));
+    }
 
-        // initially only send to dg_size targets
-        for conn_info in conn_infos.iter().take(dg_size) {
-            // NetworkBytes is refcounted and cheap to clone.
-            self.quic_p2p.send(conn_info.clone(), msg.clone(), token);
+    /// Moves all queued messages to the queues of their targets, the ones with the lowest
+    /// priority number first, and sends as many of them as the targets' in-flight limits allow.
+    pub fn flush_outgoing(&mut self) {
+        let queued: Vec<_> = self.outgoing.drain().collect();
+        for (priority, (conn_infos, dg_size, msg)) in queued {
+            let token = self.next_msg_token();
+            self.cache
+                .insert_message(token, conn_infos.clone(), dg_size);
+
+            // initially only send to dg_size targets
+            for conn_info in conn_infos.into_iter().take(dg_size) {
+                // NetworkBytes is refcounted and cheap to clone.
+                self.send_to_peer(conn_info, msg.clone(), token, priority);
+            }
         }
+        self.metrics.set_message_queue_depth(self.peer_queues.len());
+    }
 
-        self.cache.insert_message(token, conn_infos, dg_size);
+    /// Returns whether the queue of any peer is filling up, in which case callers should hold
+    /// back new user messages.
+    pub fn is_congested(&self) -> bool {
+        self.peer_queues.is_congested()
     }
 
     pub fn send_message_to_next_target(
@@ -68,38 +219,131 @@ impl NetworkService {
         failed_tgt: SocketAddr,
         log_ident: LogIdent,
     ) {
+        let priority = self
+            .peer_queues
+            .complete(&failed_tgt, token)
+            .unwrap_or(CONSENSUS_PRIORITY);
         if let Some(tgt) = self.cache.target_failed(token, failed_tgt) {
             info!(
                 "{} Sending of message ID {} failed; resending...",
                 log_ident, token
             );
-            self.quic_p2p.send(tgt, msg, token);
+            self.send_to_peer(tgt, msg, token, priority);
+        }
+        self.send_queued(&failed_tgt);
+    }
+
+    /// Records that the message `token` was sent to `peer_addr`, and sends the next one waiting
+    /// for that peer.
+    pub fn message_sent(&mut self, peer_addr: SocketAddr, token: Token) {
+        let _ = self.peer_queues.complete(&peer_addr, token);
+        self.cache.target_succeeded(token, peer_addr);
+        self.send_queued(&peer_addr);
+    }
+
+    /// Passes the messages waiting for a peer we lost the connection to on to their next
+    /// targets, if they have any.
+    pub fn peer_disconnected(&mut self, peer_addr: SocketAddr) {
+        for (priority, token, (_, msg)) in self.peer_queues.take_waiting(&peer_addr) {
+            if let Some(tgt) = self.cache.target_failed(token, peer_addr) {
+                self.send_to_peer(tgt, msg, token, priority);
+            }
+        }
+        self.metrics.set_message_queue_depth(self.peer_queues.len());
+    }
+
+    // Queues `msg` for `conn_info` and sends it if the peer has room for more messages in flight.
+    // If the peer's queue is full, its least urgent message is passed on to its next target
+    // instead, if there is one.
+    fn send_to_peer(
+        &mut self,
+        conn_info: ConnectionInfo,
+        msg: NetworkBytes,
+        token: Token,
+        priority: u8,
+    ) {
+        let peer_addr = conn_info.peer_addr();
+        if let Some((dropped_priority, dropped_token, (_, dropped_msg))) =
+            self.peer_queues
+                .push(peer_addr, priority, token, (conn_info, msg))
+        {
+            debug!(
+                "Outgoing queue for {} is full; dropped message ID {}.",
+                peer_addr, dropped_token
+            );
+            self.metrics.inc_dropped_messages();
+            // This terminates, as every target is only tried a limited number of times.
+            if let Some(tgt) = self.cache.target_failed(dropped_token, peer_addr) {
+                self.send_to_peer(tgt, dropped_msg, dropped_token, dropped_priority);
+            }
+        }
+        self.send_queued(&peer_addr);
+    }
+
+    // Hands the messages waiting for `peer_addr` to quic-p2p, as far as its in-flight limit
+    // allows.
+    fn send_queued(&mut self, peer_addr: &SocketAddr) {
+        while let Some((token, (conn_info, msg))) = self.peer_queues.pop(peer_addr) {
+            self.transport.send(conn_info, msg, token);
         }
     }
 }
 
 pub struct NetworkBuilder {
-    quic_p2p: Builder,
+    event_tx: Sender<NetworkEvent>,
+    config: Option<NetworkConfig>,
+    transport: Option<TransportFactory>,
 }
 
 impl NetworkBuilder {
     pub fn new(event_tx: Sender<NetworkEvent>) -> Self {
         Self {
-            quic_p2p: Builder::new(event_tx),
+            event_tx,
+            config: None,
+            transport: None,
         }
     }
 
     pub fn with_config(self, config: NetworkConfig) -> Self {
         Self {
-            quic_p2p: self.quic_p2p.with_config(config),
+            config: Some(config),
+            ..self
         }
     }
 
-    pub fn build(self) -> Result<NetworkService, Error> {
+    /// Uses the transport created by `factory` instead of quic-p2p. The config is ignored then.
+    pub fn with_transport(self, factory: TransportFactory) -> Self {
+        Self {
+            transport: Some(factory),
+            ..self
+        }
+    }
+
+    pub fn build(self) -> Result<NetworkService, RoutingError> {
+        let transport = match self.transport {
+            Some(factory) => factory(self.event_tx)?,
+            None => {
+                let mut builder = Builder::new(self.event_tx);
+                if let Some(config) = self.config {
+                    builder = builder.with_config(config);
+                }
+                Box::new(builder.build()?)
+            }
+        };
+
         Ok(NetworkService {
-            quic_p2p: self.quic_p2p.build()?,
+            transport,
             cache: Default::default(),
             next_msg_token: 0,
+            outgoing: Default::default(),
+            peer_queues: Default::default(),
+            connection_policy: Box::new(AcceptAll),
+            #[cfg(feature = "node")]
+            middleware: Default::default(),
+            metrics: Default::default(),
+            bootstrap_cache: Default::default(),
+            #[cfg(feature = "node")]
+            address_family_policy: Default::default(),
         })
     }
 }
@@ -0,0 +1,98 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use std::collections::{BTreeMap, VecDeque};
+
+/// Messages waiting to be sent, with one lane per priority.
+///
+/// Lanes are drained in order of their priority number, lowest first, and each lane in the order
+/// its messages were queued. This way consensus traffic queued while handling an event goes out
+/// ahead of the bulk data queued alongside it.
+pub struct OutgoingQueue<T> {
+    lanes: BTreeMap<u8, VecDeque<T>>,
+}
+
+impl<T> OutgoingQueue<T> {
+    pub fn push(&mut self, priority: u8, item: T) {
+        self.lanes
+            .entry(priority)
+            .or_insert_with(VecDeque::new)
+            .push_back(item);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lanes.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lanes.values().map(VecDeque::len).sum()
+    }
+
+    /// Removes the message which should be sent next, with its priority.
+    pub fn pop(&mut self) -> Option<(u8, T)> {
+        let (&priority, lane) = self.lanes.iter_mut().next()?;
+        let item = lane.pop_front()?;
+        if lane.is_empty() {
+            let _ = self.lanes.remove(&priority);
+        }
+        Some((priority, item))
+    }
+
+    /// Removes the message which would be sent last, with its priority.
+    pub fn pop_last(&mut self) -> Option<(u8, T)> {
+        let (&priority, lane) = self.lanes.iter_mut().next_back()?;
+        let item = lane.pop_back()?;
+        if lane.is_empty() {
+            let _ = self.lanes.remove(&priority);
+        }
+        Some((priority, item))
+    }
+
+    /// Removes all queued messages, in the order they should be sent, with their priorities.
+    pub fn drain(&mut self) -> impl Iterator<Item = (u8, T)> {
+        let lanes = std::mem::replace(&mut self.lanes, BTreeMap::new());
+        lanes
+            .into_iter()
+            .flat_map(|(priority, lane)| lane.into_iter().map(move |item| (priority, item)))
+    }
+}
+
+impl<T> Default for OutgoingQueue<T> {
+    fn default() -> Self {
+        Self {
+            lanes: BTreeMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drains_by_priority_then_fifo() {
+        let mut queue = OutgoingQueue::default();
+        queue.push(3, "client get");
+        queue.push(0, "ack");
+        queue.push(2, "put");
+        queue.push(0, "section info vote");
+        queue.push(3, "another client get");
+
+        assert_eq!(
+            queue.drain().map(|(_, item)| item).collect::<Vec<_>>(),
+            vec![
+                "ack",
+                "section info vote",
+                "put",
+                "client get",
+                "another client get"
+            ]
+        );
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::outgoing_queue::OutgoingQueue;
+use crate::network_params::Timeouts;
+use crate::quic_p2p::Token;
+use crate::time::{Duration, Instant};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+/// The maximum number of messages handed to quic-p2p for one peer which it hasn't reported as sent
+/// or failed yet. Further messages wait in the peer's queue.
+pub const MAX_IN_FLIGHT_PER_PEER: usize = 32;
+/// The maximum number of messages waiting for one peer. When it is exceeded, the message which
+/// would be sent last is dropped.
+pub const MAX_QUEUED_PER_PEER: usize = 512;
+/// Once this many messages wait for any peer, new user messages are refused as busy.
+pub const CONGESTION_THRESHOLD: usize = MAX_QUEUED_PER_PEER / 2;
+
+/// Bounded queues of the messages waiting to be sent to each peer.
+///
+/// Only a few messages per peer are handed to quic-p2p at a time, so a slow peer doesn't make us
+/// buffer an unbounded amount of data for it. The rest wait here, by priority, so consensus
+/// traffic overtakes the bulk data waiting for the same peer.
+pub struct PeerQueues<T> {
+    peers: HashMap<SocketAddr, PeerQueue<T>>,
+    /// How long a message may be in flight before we stop counting it against the peer's limit.
+    /// quic-p2p doesn't report the fate of messages to peers it never connected to.
+    in_flight_timeout: Duration,
+}
+
+struct PeerQueue<T> {
+    /// The priorities of the messages handed to quic-p2p and when they were, by token.
+    in_flight: HashMap<Token, (u8, Instant)>,
+    waiting: OutgoingQueue<(Token, T)>,
+}
+
+impl<T> PeerQueues<T> {
+    /// Sets how long a message may be in flight before it no longer counts against the limit.
+    pub fn set_in_flight_timeout(&mut self, timeout: Duration) {
+        self.in_flight_timeout = timeout;
+    }
+
+    /// Queues `item` for `peer_addr`. If the peer's queue is full, drops the message which would
+    /// be sent last, which may be `item` itself, and returns its priority, token and content.
+    pub fn push(
+        &mut self,
+        peer_addr: SocketAddr,
+        priority: u8,
+        token: Token,
+        item: T,
+    ) -> Option<(u8, Token, T)> {
+        let peer = self.peers.entry(peer_addr).or_insert_with(|| PeerQueue {
+            in_flight: HashMap::new(),
+            waiting: OutgoingQueue::default(),
+        });
+        peer.waiting.push(priority, (token, item));
+        if peer.waiting.len() > MAX_QUEUED_PER_PEER {
+            peer.waiting
+                .pop_last()
+                .map(|(priority, (token, item))| (priority, token, item))
+        } else {
+            None
+        }
+    }
+
+    /// Takes the next message to hand to quic-p2p for `peer_addr`, unless none is waiting or too
+    /// many are in flight already.
+    pub fn pop(&mut self, peer_addr: &SocketAddr) -> Option<(Token, T)> {
+        let in_flight_timeout = self.in_flight_timeout;
+        let peer = self.peers.get_mut(peer_addr)?;
+        if peer.in_flight.len() >= MAX_IN_FLIGHT_PER_PEER {
+            peer.in_flight
+                .retain(|_, (_, handed_over)| handed_over.elapsed() < in_flight_timeout);
+            if peer.in_flight.len() >= MAX_IN_FLIGHT_PER_PEER {
+                return None;
+            }
+        }
+        let (priority, (token, item)) = peer.waiting.pop()?;
+        let _ = peer.in_flight.insert(token, (priority, Instant::now()));
+        Some((token, item))
+    }
+
+    /// Records that quic-p2p is done with the message `token` to `peer_addr`, successfully or
+    /// not. Returns the message's priority if it was in flight.
+    pub fn complete(&mut self, peer_addr: &SocketAddr, token: Token) -> Option<u8> {
+        let peer = self.peers.get_mut(peer_addr)?;
+        let priority = peer.in_flight.remove(&token).map(|(priority, _)| priority);
+        if peer.in_flight.is_empty() && peer.waiting.is_empty() {
+            let _ = self.peers.remove(peer_addr);
+        }
+        priority
+    }
+
+    /// Removes the messages waiting for `peer_addr`, with their priorities. The ones in flight
+    /// are still tracked until quic-p2p reports them as sent or failed.
+    pub fn take_waiting(&mut self, peer_addr: &SocketAddr) -> Vec<(u8, Token, T)> {
+        let peer = match self.peers.get_mut(peer_addr) {
+            Some(peer) => peer,
+            None => return Vec::new(),
+        };
+        let waiting: Vec<_> = peer
+            .waiting
+            .drain()
+            .map(|(priority, (token, item))| (priority, token, item))
+            .collect();
+        if peer.in_flight.is_empty() {
+            let _ = self.peers.remove(peer_addr);
+        }
+        waiting
+    }
+
+    /// Returns the number of messages waiting for all peers.
+    pub fn len(&self) -> usize {
+        self.peers.values().map(|peer| peer.waiting.len()).sum()
+    }
+
+    /// Returns whether the queue of any peer is filling up.
+    pub fn is_congested(&self) -> bool {
+        self.peers
+            .values()
+            .any(|peer| peer.waiting.len() >= CONGESTION_THRESHOLD)
+    }
+}
+
+impl<T> Default for PeerQueues<T> {
+    fn default() -> Self {
+        Self {
+            peers: HashMap::new(),
+            in_flight_timeout: Timeouts::default().send_ack,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unwrap::unwrap;
+
+    #[test]
+    fn in_flight_messages_are_bounded_per_peer() {
+        let mut queues = PeerQueues::default();
+        let slow: SocketAddr = unwrap!("127.0.0.1:1000".parse());
+        let fast: SocketAddr = unwrap!("127.0.0.1:2000".parse());
+        for token in 0..MAX_IN_FLIGHT_PER_PEER as Token + 1 {
+            assert!(queues.push(slow, 2, token, ()).is_none());
+            assert!(queues.pop(&slow).is_some() || token == MAX_IN_FLIGHT_PER_PEER as Token);
+        }
+        assert!(queues.pop(&slow).is_none());
+        assert_eq!(queues.len(), 1);
+
+        // The slow peer doesn't hold up others.
+        assert!(queues.push(fast, 2, 100, ()).is_none());
+        assert_eq!(queues.pop(&fast), Some((100, ())));
+
+        // A message completing makes room for the next one.
+        assert_eq!(queues.complete(&slow, 0), Some(2));
+        assert_eq!(queues.complete(&slow, 0), None);
+        assert_eq!(
+            queues.pop(&slow),
+            Some((MAX_IN_FLIGHT_PER_PEER as Token, ()))
+        );
+        assert_eq!(queues.len(), 0);
+    }
+
+    #[test]
+    fn full_queues_drop_the_least_urgent_message() {
+        let mut queues = PeerQueues::default();
+        let peer: SocketAddr = unwrap!("127.0.0.1:1000".parse());
+        for token in 0..MAX_QUEUED_PER_PEER as Token {
+            assert!(queues.push(peer, 3, token, token).is_none());
+            assert_eq!(
+                queues.is_congested(),
+                token as usize + 1 >= CONGESTION_THRESHOLD
+            );
+        }
+
+        // An urgent message displaces the newest bulk one...
+        let last = MAX_QUEUED_PER_PEER as Token - 1;
+        assert_eq!(queues.push(peer, 0, 1000, 1000), Some((3, last, last)));
+        // ... while further bulk messages are dropped themselves.
+        assert_eq!(queues.push(peer, 3, 1001, 1001), Some((3, 1001, 1001)));
+        assert_eq!(queues.pop(&peer), Some((1000, 1000)));
+
+        assert_eq!(queues.take_waiting(&peer).len(), MAX_QUEUED_PER_PEER - 1);
+        assert!(!queues.is_congested());
+    }
+}
@@ -6,31 +6,51 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+#[cfg(feature = "data")]
+use crate::data::DataAudit;
+#[cfg(feature = "messaging")]
+use crate::messaging::{MailboxUsage, MpidHeaderInfo};
 use crate::{
     action::Action,
-    cache::{Cache, NullCache},
+    bootstrap,
+    cache::{Cache, IDataCache, NullCache},
+    chain::{JoinerId, NetworkEpoch, PendingVote},
     client_error::ClientError,
     config_handler::{self, Config},
-    data::{EntryAction, ImmutableData, MutableData, PermissionSet, User, Value},
+    connection_policy::ConnectionPolicy,
+    data::{
+        AccountPacket, AppendEntry, AppendOnlyData, DataId, EntryAction, ImmutableData,
+        KeyPermissions, MutableData, PermissionSet, User, Value,
+    },
     error::{InterfaceError, RoutingError},
     event::Event,
     event_stream::{EventStepper, EventStream},
     id::{FullId, PublicId},
     messages::{
-        AccountInfo, Request, Response, UserMessage, CLIENT_GET_PRIORITY, DEFAULT_PRIORITY,
-        RELOCATE_PRIORITY,
+        AccountInfo, RelayedResponse, Request, Response, UserMessage, CLIENT_GET_PRIORITY,
+        DEFAULT_PRIORITY, RELOCATE_PRIORITY,
     },
-    outbox::{EventBox, EventBuf},
+    metrics::{Metrics, MetricsExporter},
+    middleware::MessageMiddleware,
+    network_params::{NetworkParams, Timeouts},
+    outbox::{EventBox, EventBuf, EventSender},
     quic_p2p::OurType,
-    routing_table::Authority,
+    routing_table::{Authority, Prefix},
+    section_contacts::SectionContacts,
+    sha3::Digest256,
     state_machine::{State, StateMachine},
     states::{self, BootstrappingPeer, TargetState},
+    transport::{AddressFamilyPolicy, DualStack, NetworkEvent, Transport, TransportFactory},
     types::MessageId,
     xor_name::XorName,
-    NetworkConfig, MIN_SECTION_SIZE,
+    NetworkConfig,
 };
 #[cfg(feature = "mock_base")]
-use crate::{utils::XorTargetInterval, Chain};
+use crate::{
+    chain::{PendingEvent, StabilityGuard},
+    utils::XorTargetInterval,
+    Chain,
+};
 use crossbeam_channel as mpmc;
 #[cfg(not(feature = "mock_base"))]
 use safe_crypto;
@@ -39,7 +59,8 @@ use safe_crypto::PublicSignKey;
 use std::fmt::{self, Display, Formatter};
 use std::{
     collections::{BTreeMap, BTreeSet},
-    sync::mpsc,
+    net::SocketAddr,
+    sync::{mpsc, Arc},
 };
 #[cfg(feature = "mock_base")]
 use unwrap::unwrap;
@@ -87,16 +108,32 @@ macro_rules! impl_response {
 
 /// A builder to configure and create a new `Node`.
 pub struct NodeBuilder {
-    cache: Box<dyn Cache>,
+    cache: Option<Box<dyn Cache>>,
     first: bool,
     config: Option<Config>,
     network_config: Option<NetworkConfig>,
+    event_sender: Option<Box<dyn EventSender>>,
+    connection_policy: Option<Box<dyn ConnectionPolicy>>,
+    middleware: Vec<Box<dyn MessageMiddleware>>,
+    network_params: Option<NetworkParams>,
+    metrics_addr: Option<SocketAddr>,
+    full_id: Option<FullId>,
+    section_contacts: Option<SectionContacts>,
+    bootstrap_cache: Option<bootstrap::Cache>,
+    transport: Option<TransportFactory>,
+    ipv6_network_config: Option<NetworkConfig>,
+    address_family_policy: Option<AddressFamilyPolicy>,
+    timeouts: Option<Timeouts>,
 }
 
 impl NodeBuilder {
-    /// Configures the node to use the given request cache.
+    /// Configures the node to use the given request cache, instead of the one configured by
+    /// `Config::relay_cache_size`.
     pub fn cache(self, cache: Box<dyn Cache>) -> NodeBuilder {
-        NodeBuilder { cache, ..self }
+        NodeBuilder {
+            cache: Some(cache),
+            ..self
+        }
     }
 
     /// Configures the node to start a new network instead of joining an existing one.
@@ -120,52 +157,255 @@ impl NodeBuilder {
         }
     }
 
+    /// The node will deliver its events through `sender`, e.g. a bounded
+    /// `crossbeam_channel::Sender<Event>`, instead of buffering them for `EventStream::next_ev`.
+    /// The node still needs to be driven by `EventStream::poll`, which returns `false` once the
+    /// receiving end is gone.
+    pub fn event_sender<S: EventSender + 'static>(self, sender: S) -> NodeBuilder {
+        NodeBuilder {
+            event_sender: Some(Box::new(sender)),
+            ..self
+        }
+    }
+
+    /// The node will consult `policy` before accepting inbound connections, instead of accepting
+    /// all of them.
+    pub fn connection_policy<P: ConnectionPolicy + 'static>(self, policy: P) -> NodeBuilder {
+        NodeBuilder {
+            connection_policy: Some(Box::new(policy)),
+            ..self
+        }
+    }
+
+    /// Appends `middleware` to the pipeline every user message the node receives or sends passes
+    /// through. Middleware runs in the order it was added, and a message dropped by one isn't seen
+    /// by the rest.
+    pub fn middleware<M: MessageMiddleware + 'static>(mut self, middleware: M) -> NodeBuilder {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+
+    /// The node will use the given network parameters rather than the defaults, including the
+    /// minimum section size configured in `config`. All nodes of a network have to use the same
+    /// parameters, so this is meant for testnets trying out different consensus thresholds.
+    pub fn network_params(self, params: NetworkParams) -> NodeBuilder {
+        NodeBuilder {
+            network_params: Some(params),
+            ..self
+        }
+    }
+
+    /// The node will serve its metrics over HTTP on `addr`, in the Prometheus text format. They
+    /// are available through `Node::metrics` either way.
+    pub fn metrics_exporter(self, addr: SocketAddr) -> NodeBuilder {
+        NodeBuilder {
+            metrics_addr: Some(addr),
+            ..self
+        }
+    }
+
+    /// The node will use `full_id`, e.g. one restored by `FullId::restore_encrypted`, rather
+    /// than generating new keys. Our section may still relocate it under a new name.
+    pub fn full_id(self, full_id: FullId) -> NodeBuilder {
+        NodeBuilder {
+            full_id: Some(full_id),
+            ..self
+        }
+    }
+
+    /// The node will bootstrap off the elders in `contacts`, as saved by a previous run, instead
+    /// of the hard-coded contacts of the network config. If none of them is reachable any more,
+    /// the node has to be created again without them.
+    pub fn section_contacts(self, contacts: SectionContacts) -> NodeBuilder {
+        NodeBuilder {
+            section_contacts: Some(contacts),
+            ..self
+        }
+    }
+
+    /// The node will bootstrap off the highest priority contacts in `cache`, e.g. one opened with
+    /// `bootstrap::Cache::open`, instead of the hard-coded contacts of the network config. The
+    /// node demotes the contacts it fails to reach, and the cache can be edited via
+    /// `Node::bootstrap_cache_mut`. Contacts given via `section_contacts` take precedence.
+    pub fn bootstrap_cache(self, cache: bootstrap::Cache) -> NodeBuilder {
+        NodeBuilder {
+            bootstrap_cache: Some(cache),
+            ..self
+        }
+    }
+
+    /// The node will run over the transport `factory` creates, given the channel the transport
+    /// reports its events through, instead of quic-p2p. The network config, section contacts and
+    /// bootstrap cache are ignored then, as the transport manages its own contacts.
+    pub fn transport<F>(self, factory: F) -> NodeBuilder
+    where
+        F: FnOnce(mpmc::Sender<NetworkEvent>) -> Result<Box<dyn Transport>, RoutingError> + 'static,
+    {
+        NodeBuilder {
+            transport: Some(Box::new(factory)),
+            ..self
+        }
+    }
+
+    /// The node will also listen on IPv6, as configured by `config`, e.g. with its `ip` set to
+    /// `::`. The node then advertises an endpoint per address family to its peers. The contacts
+    /// of the network config are split by family between the two listeners.
+    pub fn ipv6_network_config(self, config: NetworkConfig) -> NodeBuilder {
+        NodeBuilder {
+            ipv6_network_config: Some(config),
+            ..self
+        }
+    }
+
+    /// The node will connect to its peers over the address families `policy` allows, preferring
+    /// the one it prefers, rather than preferring IPv4.
+    pub fn address_family_policy(self, policy: AddressFamilyPolicy) -> NodeBuilder {
+        NodeBuilder {
+            address_family_policy: Some(policy),
+            ..self
+        }
+    }
+
+    /// The node will wait for its peers as long as `timeouts` say, instead of the timeouts of its
+    /// network params. Unlike the rest of the params, they don't need to agree across the network.
+    pub fn timeouts(self, timeouts: Timeouts) -> NodeBuilder {
+        NodeBuilder {
+            timeouts: Some(timeouts),
+            ..self
+        }
+    }
+
     /// Creates new `Node`.
     ///
+    /// Fails with `RoutingError::InvalidNetworkParams` if the network parameters are unsafe or the
+    /// timeouts invalid, and with `RoutingError::Io` if the metrics exporter can't be started.
+    ///
     /// It will automatically connect to the network in the same way a client does, but then
     /// request a new name and integrate itself into the network using the new name.
     ///
-    /// The initial `Node` object will have newly generated keys.
-    pub fn create(self) -> Result<Node, RoutingError> {
+    /// The initial `Node` object will have newly generated keys, unless given via `full_id`.
+    pub fn create(mut self) -> Result<Node, RoutingError> {
         // If we're not in a test environment where we might want to manually seed the crypto RNG
         // then seed randomly.
         #[cfg(not(feature = "mock_base"))]
         safe_crypto::init()?;
 
+        if let Some(ref params) = self.network_params {
+            params.validate()?;
+        }
+        if let Some(ref timeouts) = self.timeouts {
+            timeouts.validate()?;
+        }
+
+        let metrics = Arc::new(Metrics::default());
+        let metrics_exporter = match self.metrics_addr {
+            Some(addr) => Some(MetricsExporter::start(addr, Arc::clone(&metrics))?),
+            None => None,
+        };
+
         let mut ev_buffer = EventBuf::new();
+        let event_sender = self.event_sender.take();
 
         // start the handler for routing without a restriction to become a full node
-        let (_, machine) = self.make_state_machine(&mut ev_buffer);
+        let (_, machine) = self.make_state_machine(Arc::clone(&metrics), &mut ev_buffer);
         let (tx, rx) = mpsc::channel();
 
-        Ok(Node {
+        let mut node = Node {
             interface_result_tx: tx,
             interface_result_rx: rx,
             machine: machine,
             event_buffer: ev_buffer,
-        })
+            event_sender,
+            metrics,
+            _metrics_exporter: metrics_exporter,
+        };
+        let _ = node.deliver_events();
+        Ok(node)
     }
 
-    fn make_state_machine(self, outbox: &mut dyn EventBox) -> (mpmc::Sender<Action>, StateMachine) {
-        let full_id = FullId::new();
+    fn make_state_machine(
+        self,
+        metrics: Arc<Metrics>,
+        outbox: &mut dyn EventBox,
+    ) -> (mpmc::Sender<Action>, StateMachine) {
+        let full_id = self.full_id.unwrap_or_else(FullId::new);
         let config = self.config.unwrap_or_else(config_handler::get_config);
-        let dev_config = config.dev.unwrap_or_default();
-        let min_section_size = dev_config.min_section_size.unwrap_or(MIN_SECTION_SIZE);
+        let mut network_params = self
+            .network_params
+            .unwrap_or_else(|| NetworkParams::from_config(&config));
+        if let Some(timeouts) = self.timeouts {
+            network_params.timeouts = timeouts;
+        }
 
         let first = self.first;
-        let cache = self.cache;
+        let cache = self.cache.unwrap_or_else(|| match config.relay_cache_size {
+            Some(capacity) => Box::new(IDataCache::new(capacity)),
+            None => Box::new(NullCache),
+        });
+        let connection_policy = self.connection_policy;
+        let middleware = self.middleware;
+        let address_family_policy = self.address_family_policy.unwrap_or_default();
 
         let mut network_config = self.network_config.unwrap_or_default();
         network_config.our_type = OurType::Node;
+        let bootstrap_cache = self.bootstrap_cache.unwrap_or_default();
+        match self.section_contacts {
+            Some(ref contacts) if !contacts.contacts().is_empty() => {
+                network_config.hard_coded_contacts = contacts.contacts().iter().cloned().collect();
+            }
+            _ if !bootstrap_cache.is_empty() => {
+                network_config.hard_coded_contacts =
+                    bootstrap_cache.bootstrap_contacts().cloned().collect();
+            }
+            _ => (),
+        }
+
+        let transport = match (self.transport, self.ipv6_network_config) {
+            (Some(transport), _) => Some(transport),
+            (None, Some(mut ipv6_config)) => {
+                // Each family's transport bootstraps off the contacts of its own family.
+                ipv6_config.our_type = OurType::Node;
+                let (ipv6_contacts, ipv4_contacts) = network_config
+                    .hard_coded_contacts
+                    .drain()
+                    .partition(|contact| contact.peer_addr.is_ipv6());
+                ipv6_config.hard_coded_contacts.extend(ipv6_contacts);
+                let mut ipv4_config = network_config.clone();
+                ipv4_config.hard_coded_contacts = ipv4_contacts;
+
+                let factory = move |event_tx: mpmc::Sender<NetworkEvent>| {
+                    let dual_stack = DualStack::quic_p2p(
+                        event_tx,
+                        ipv4_config,
+                        ipv6_config,
+                        address_family_policy,
+                    )?;
+                    Ok(Box::new(dual_stack) as Box<dyn Transport>)
+                };
+                Some(Box::new(factory) as TransportFactory)
+            }
+            (None, None) => None,
+        };
 
         StateMachine::new(
-            move |action_sender, network_service, timer, outbox| {
+            move |action_sender, mut network_service, timer, outbox| {
+                if let Some(policy) = connection_policy {
+                    network_service.set_connection_policy(policy);
+                }
+                network_service.set_address_family_policy(address_family_policy);
+                network_service.set_send_ack_timeout(network_params.timeouts.send_ack);
+                network_service.set_metrics(metrics);
+                network_service.set_bootstrap_cache(bootstrap_cache);
+                for layer in middleware {
+                    network_service.add_middleware(layer);
+                }
+
                 if first {
                     states::Elder::first(
                         cache,
                         network_service,
                         full_id,
-                        min_section_size,
+                        network_params,
                         timer,
                         outbox,
                     )
@@ -178,12 +418,13 @@ impl NodeBuilder {
                         TargetState::RelocatingNode,
                         network_service,
                         full_id,
-                        min_section_size,
+                        network_params,
                         timer,
                     ))
                 }
             },
             network_config,
+            transport,
             outbox,
         )
     }
@@ -201,16 +442,31 @@ pub struct Node {
     interface_result_rx: mpsc::Receiver<Result<(), InterfaceError>>,
     machine: StateMachine,
     event_buffer: EventBuf,
+    event_sender: Option<Box<dyn EventSender>>,
+    metrics: Arc<Metrics>,
+    _metrics_exporter: Option<MetricsExporter>,
 }
 
 impl Node {
     /// Creates a new builder to configure and create a `Node`.
     pub fn builder() -> NodeBuilder {
         NodeBuilder {
-            cache: Box::new(NullCache),
+            cache: None,
             first: false,
             config: None,
             network_config: None,
+            event_sender: None,
+            connection_policy: None,
+            middleware: Vec::new(),
+            network_params: None,
+            metrics_addr: None,
+            full_id: None,
+            section_contacts: None,
+            bootstrap_cache: None,
+            transport: None,
+            ipv6_network_config: None,
+            address_family_policy: None,
+            timeouts: None,
         }
     }
 
@@ -318,6 +574,21 @@ impl Node {
         DEFAULT_PRIORITY
     );
 
+    /// Send a `SetMDataKeyPermissions` request.
+    impl_request!(
+        send_set_mdata_key_permissions_request,
+        SetMDataKeyPermissions {
+            name: XorName,
+            tag: u64,
+            prefix: Vec<u8>,
+            permissions: KeyPermissions,
+            version: u64,
+            msg_id: MessageId,
+            requester: PublicSignKey,
+        },
+        DEFAULT_PRIORITY
+    );
+
     /// Send a `ChangeMDataOwner` request.
     impl_request!(send_change_mdata_owner_request,
                   ChangeMDataOwner {
@@ -328,6 +599,51 @@ impl Node {
                       msg_id: MessageId,
                   }, DEFAULT_PRIORITY);
 
+    /// Send a `PutAData` request.
+    impl_request!(
+        send_put_adata_request,
+        PutAData {
+            data: AppendOnlyData,
+            msg_id: MessageId,
+            requester: PublicSignKey,
+        },
+        DEFAULT_PRIORITY
+    );
+
+    /// Send a `GetAData` request to `dst` to retrieve data from the network.
+    impl_request!(
+        send_get_adata_request,
+        GetAData {
+            name: XorName,
+            tag: u64,
+            msg_id: MessageId,
+        },
+        RELOCATE_PRIORITY
+    );
+
+    /// Send an `AppendADataEntry` request.
+    impl_request!(
+        send_append_adata_entry_request,
+        AppendADataEntry {
+            name: XorName,
+            tag: u64,
+            entry: AppendEntry,
+            msg_id: MessageId,
+        },
+        DEFAULT_PRIORITY
+    );
+
+    /// Send a `ChallengeData` request to audit that `dst` holds the given data.
+    impl_request!(
+        send_challenge_data_request,
+        ChallengeData {
+            data_id: DataId,
+            nonce: [u8; 32],
+            msg_id: MessageId,
+        },
+        DEFAULT_PRIORITY
+    );
+
     /// Send a `Refresh` request from `src` to `dst` to trigger churn.
     pub fn send_refresh_request(
         &mut self,
@@ -423,6 +739,14 @@ impl Node {
         CLIENT_GET_PRIORITY
     );
 
+    /// Respond to a `ListMDataEntriesRange` request.
+    impl_response!(
+        send_list_mdata_entries_range_response,
+        ListMDataEntriesRange,
+        (BTreeMap<Vec<u8>, Value>, Option<Vec<u8>>),
+        CLIENT_GET_PRIORITY
+    );
+
     /// Respond to a `ListMDataKeys` request.
     /// Note: this response is unlikely to accumulate during churn.
     impl_response!(
@@ -520,6 +844,66 @@ impl Node {
         DEFAULT_PRIORITY
     );
 
+    /// Respond to a `SetMDataKeyPermissions` request.
+    impl_response!(
+        send_set_mdata_key_permissions_response,
+        SetMDataKeyPermissions,
+        (),
+        DEFAULT_PRIORITY
+    );
+
+    /// Respond to a `WatchMData` request.
+    impl_response!(send_watch_mdata_response, WatchMData, (), DEFAULT_PRIORITY);
+
+    /// Respond to a `ListMpidHeaders` request.
+    #[cfg(feature = "messaging")]
+    impl_response!(
+        send_list_mpid_headers_response,
+        ListMpidHeaders,
+        Vec<MpidHeaderInfo>,
+        CLIENT_GET_PRIORITY
+    );
+
+    /// Respond to a `DeleteMpidHeaders` request.
+    #[cfg(feature = "messaging")]
+    impl_response!(
+        send_delete_mpid_headers_response,
+        DeleteMpidHeaders,
+        (),
+        DEFAULT_PRIORITY
+    );
+
+    /// Respond to a `GetMpidMailboxUsage` request.
+    #[cfg(feature = "messaging")]
+    impl_response!(
+        send_get_mpid_mailbox_usage_response,
+        GetMpidMailboxUsage,
+        MailboxUsage,
+        CLIENT_GET_PRIORITY
+    );
+
+    /// Notify a client watching MutableData `name` that its entries were mutated by `actions`.
+    /// For the notification to accumulate, all members of the section need to use the same
+    /// `msg_id`, e.g. one derived from the mutation request's ID.
+    pub fn send_mdata_changed_notification(
+        &mut self,
+        src: Authority<XorName>,
+        dst: Authority<XorName>,
+        name: XorName,
+        tag: u64,
+        actions: BTreeMap<Vec<u8>, EntryAction>,
+        msg_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        let msg = UserMessage::Response(Response::MDataChanged {
+            name: name,
+            tag: tag,
+            actions: actions,
+            msg_id: msg_id,
+        });
+
+        self.send_action(src, dst, msg, DEFAULT_PRIORITY)
+    }
+
     /// Respond to a `ChangeMDataOwner` request.
     impl_response!(
         send_change_mdata_owner_response,
@@ -528,6 +912,66 @@ impl Node {
         DEFAULT_PRIORITY
     );
 
+    /// Respond to a `PutAData` request.
+    impl_response!(send_put_adata_response, PutAData, (), DEFAULT_PRIORITY);
+
+    /// Respond to a `GetAData` request.
+    impl_response!(
+        send_get_adata_response,
+        GetAData,
+        AppendOnlyData,
+        CLIENT_GET_PRIORITY
+    );
+
+    /// Respond to a `GetADataEntries` request.
+    impl_response!(
+        send_get_adata_entries_response,
+        GetADataEntries,
+        Vec<AppendEntry>,
+        CLIENT_GET_PRIORITY
+    );
+
+    /// Respond to an `AppendADataEntry` request.
+    impl_response!(
+        send_append_adata_entry_response,
+        AppendADataEntry,
+        (),
+        DEFAULT_PRIORITY
+    );
+
+    /// Respond to a `GetAccount` request.
+    impl_response!(
+        send_get_account_response,
+        GetAccount,
+        AccountPacket,
+        CLIENT_GET_PRIORITY
+    );
+
+    /// Respond to an `UpdateAccount` request.
+    impl_response!(
+        send_update_account_response,
+        UpdateAccount,
+        (),
+        DEFAULT_PRIORITY
+    );
+
+    /// Respond to a `ChallengeData` request.
+    impl_response!(
+        send_prove_data_response,
+        ProveData,
+        Digest256,
+        DEFAULT_PRIORITY
+    );
+
+    /// Refuse a `RelayedGet` request. Successful responses are forwarded to the client by routing
+    /// once the GET sent on its behalf is answered.
+    impl_response!(
+        send_relayed_get_response,
+        RelayedGet,
+        RelayedResponse,
+        CLIENT_GET_PRIORITY
+    );
+
     /// Returns the first `count` names of the nodes in the routing table which are closest
     /// to the given one.
     pub fn close_group(&self, name: XorName, count: usize) -> Option<Vec<XorName>> {
@@ -539,11 +983,220 @@ impl Node {
         self.machine.current().id().ok_or(RoutingError::Terminated)
     }
 
+    /// Returns the network events this node voted for which haven't been accumulated by its
+    /// section yet, so the vault can decide whether to vote again or escalate.
+    pub fn pending_votes(&self) -> Vec<PendingVote> {
+        self.machine.current().pending_votes()
+    }
+
+    /// Returns the current epoch of our section and the estimate for the whole network, or `None`
+    /// if this node isn't a member of a section yet.
+    pub fn network_epoch(&self) -> Option<NetworkEpoch> {
+        self.machine.current().network_epoch()
+    }
+
+    /// Returns the misbehaviour scores of the peers which misbehaved when voting in our section.
+    /// Peers crossing the configured threshold are voted offline.
+    pub fn peer_scores(&self) -> BTreeMap<PublicId, u64> {
+        self.machine.current().peer_scores()
+    }
+
+    /// Returns the mutations the client account `account` performed and may still perform, as
+    /// agreed by our section, or `None` if our section doesn't manage it.
+    pub fn account_info(&self, account: &XorName) -> Option<AccountInfo> {
+        self.machine.current().account_info(account)
+    }
+
+    /// Returns the connection info of the elders of our section we are connected to, or `None` if
+    /// we aren't a member of a section yet. Save them with `SectionContacts::save` to rejoin the
+    /// section after a restart, see `NodeBuilder::section_contacts`.
+    pub fn section_contacts(&self) -> Option<SectionContacts> {
+        self.machine.current().section_contacts()
+    }
+
+    /// Returns the contacts this node bootstraps off, see `NodeBuilder::bootstrap_cache`.
+    pub fn bootstrap_cache(&self) -> Option<&bootstrap::Cache> {
+        self.machine.current().bootstrap_cache()
+    }
+
+    /// Returns the contacts this node bootstraps off, to add, remove or prioritise them. The
+    /// transport is configured when the node is created, so the changes take effect on the next
+    /// start, once persisted with `bootstrap::Cache::save`.
+    pub fn bootstrap_cache_mut(&mut self) -> Option<&mut bootstrap::Cache> {
+        self.machine.current_mut().bootstrap_cache_mut()
+    }
+
+    /// Asks our section for a signed statement of how long this node has been a member. It is
+    /// delivered as `Event::MembershipStatement` once enough members have signed it.
+    pub fn request_membership_statement(&mut self) -> Result<(), InterfaceError> {
+        // Make sure the state machine has processed any outstanding network events.
+        let _ = self.poll();
+        self.machine.current_mut().request_membership_statement()
+    }
+
+    /// Asks section `to` to take over responsibility for the names in `range`, e.g. after a split
+    /// or before a merge. All elders of our section need to call this with the same `handoff_id`.
+    ///
+    /// Once section `to` confirms it is ready, both sections raise `Event::HandoffCommit` and
+    /// responsibility for the range passes to section `to`.
+    pub fn start_handoff(
+        &mut self,
+        range: Prefix<XorName>,
+        to: Prefix<XorName>,
+        handoff_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        // Make sure the state machine has processed any outstanding network events.
+        let _ = self.poll();
+        self.machine
+            .current_mut()
+            .start_handoff(range, to, handoff_id)
+    }
+
+    /// Confirms that our section is ready to take over the range from the `Event::HandoffPrepare`
+    /// with the given `handoff_id`.
+    pub fn confirm_handoff(&mut self, handoff_id: MessageId) -> Result<(), InterfaceError> {
+        // Make sure the state machine has processed any outstanding network events.
+        let _ = self.poll();
+        self.machine.current_mut().confirm_handoff(handoff_id)
+    }
+
+    /// Lists the names of data we hold for our section to `dst`, a new member of it, in reply to
+    /// `Event::RefreshRequired`. Large inventories are split into several messages.
+    ///
+    /// `dst` receives them as `Event::DataInventory`.
+    pub fn send_data_inventory(
+        &mut self,
+        dst: XorName,
+        names: BTreeSet<XorName>,
+    ) -> Result<(), InterfaceError> {
+        // Make sure the state machine has processed any outstanding network events.
+        let _ = self.poll();
+        self.machine.current_mut().send_data_inventory(dst, names)
+    }
+
+    /// Asks `dst`, a member of our section which sent us an `Event::DataInventory`, to republish
+    /// the data with the given names to us.
+    ///
+    /// `dst` receives the request as `Event::RepublishRequested`, throttled so it doesn't cause a
+    /// spike in bandwidth.
+    pub fn request_republish(
+        &mut self,
+        dst: XorName,
+        names: BTreeSet<XorName>,
+    ) -> Result<(), InterfaceError> {
+        // Make sure the state machine has processed any outstanding network events.
+        let _ = self.poll();
+        self.machine.current_mut().request_republish(dst, names)
+    }
+
+    /// Fails the audits in `audit` which weren't answered in time, and votes for the nodes and
+    /// data manager groups which failed at least `MAX_FAILED_AUDITS` of them.
+    ///
+    /// Once our section agrees, the offenders are handed to the section managing them: a failing
+    /// node is voted offline like peers which misbehaved in voting, while a failing group raises
+    /// `Event::DataAuditFailed` in its section. Their failures are forgotten once reported.
+    #[cfg(feature = "data")]
+    pub fn report_audit_offenders(&mut self, audit: &mut DataAudit) -> Result<(), InterfaceError> {
+        let _ = audit.expire();
+        let offenders = audit
+            .offenders()
+            .filter(|auditee| match **auditee {
+                Authority::ManagedNode(_) | Authority::NaeManager(_) => true,
+                _ => false,
+            })
+            .cloned()
+            .collect();
+        // Make sure the state machine has processed any outstanding network events.
+        let _ = self.poll();
+        for auditee in self.machine.current_mut().report_failed_audits(offenders)? {
+            audit.forgive(&auditee);
+        }
+        Ok(())
+    }
+
+    /// Starts leaving the network gracefully: votes this node offline and, once the section has
+    /// accumulated any events already pending and agreed on our departure, raises
+    /// `Event::ShutdownComplete` and terminates. The node has to keep being polled until then.
+    ///
+    /// The clients this node is the proxy of are disconnected straight away so they fail over to
+    /// another proxy, and new clients are refused. If the section doesn't agree in time, the node
+    /// terminates anyway. Only elders hand over like this; any other node can simply be dropped.
+    pub fn shutdown(&mut self) -> Result<(), InterfaceError> {
+        // Make sure the state machine has processed any outstanding network events.
+        let _ = self.poll();
+        self.machine.current_mut().shutdown()
+    }
+
+    /// Votes for allowing `id` to join our section. Once a quorum of our elders voted for it, the
+    /// node is taken off the deny list, and if anything is allow-listed, only allow-listed nodes
+    /// can join. Only elders can vote.
+    pub fn allow_joiner(&mut self, id: JoinerId) -> Result<(), InterfaceError> {
+        // Make sure the state machine has processed any outstanding network events.
+        let _ = self.poll();
+        self.machine.current_mut().vote_for_join_filter(id, true)
+    }
+
+    /// Votes for refusing `id` when it tries to join our section, which takes effect once a quorum
+    /// of our elders voted for it. Only elders can vote.
+    pub fn deny_joiner(&mut self, id: JoinerId) -> Result<(), InterfaceError> {
+        // Make sure the state machine has processed any outstanding network events.
+        let _ = self.poll();
+        self.machine.current_mut().vote_for_join_filter(id, false)
+    }
+
+    /// Votes for upgrading the network to protocol `version` once each section's info reaches
+    /// `activation_version`. Once our section agrees, it passes the upgrade on to its neighbours,
+    /// which pass it on in turn. Check `Chain::is_upgrade_active` before using the features of the
+    /// new version. Only elders can vote.
+    pub fn signal_upgrade(
+        &mut self,
+        version: u16,
+        activation_version: u64,
+    ) -> Result<(), InterfaceError> {
+        // Make sure the state machine has processed any outstanding network events.
+        let _ = self.poll();
+        self.machine
+            .current_mut()
+            .signal_upgrade(version, activation_version)
+    }
+
+    /// Votes for allowing the client account `account` to perform `amount` more mutations, e.g.
+    /// once it paid for them. The elders have to vote with the same `msg_id` for their votes to
+    /// accumulate, and a different one for each credit. `PutIData` and `PutMData` requests are
+    /// refused with `ClientError::LowBalance` once an account runs out of mutations. Only elders
+    /// can vote.
+    pub fn credit_account(
+        &mut self,
+        account: XorName,
+        amount: u64,
+        msg_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        // Make sure the state machine has processed any outstanding network events.
+        let _ = self.poll();
+        self.machine
+            .current_mut()
+            .credit_account(account, amount, msg_id)
+    }
+
+    /// Returns the metrics of this node.
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
     /// Returns the minimum section size this vault is using.
     pub fn min_section_size(&self) -> usize {
         self.machine.current().min_section_size()
     }
 
+    /// Delivers the buffered events through the configured `EventSender`, if any. Returns `false`
+    /// if its receiving end is gone.
+    fn deliver_events(&mut self) -> bool {
+        match self.event_sender {
+            Some(ref sender) => self.event_buffer.deliver_all(&**sender),
+            None => true,
+        }
+    }
+
     fn send_action(
         &mut self,
         src: Authority<XorName>,
@@ -576,11 +1229,21 @@ impl EventStepper for Node {
     type Item = Event;
 
     fn produce_events(&mut self) -> Result<(), mpmc::RecvError> {
-        self.machine.step(&mut self.event_buffer)
+        self.machine.step(&mut self.event_buffer)?;
+        if self.deliver_events() {
+            Ok(())
+        } else {
+            Err(mpmc::RecvError)
+        }
     }
 
     fn try_produce_events(&mut self) -> Result<(), mpmc::TryRecvError> {
-        self.machine.try_step(&mut self.event_buffer)
+        self.machine.try_step(&mut self.event_buffer)?;
+        if self.deliver_events() {
+            Ok(())
+        } else {
+            Err(mpmc::TryRecvError::Disconnected)
+        }
     }
 
     fn pop_item(&mut self) -> Option<Event> {
@@ -595,6 +1258,12 @@ impl Node {
         self.machine.current().chain()
     }
 
+    /// Returns the events waiting in this node's chain accumulator for more proofs, with the
+    /// elders which haven't voted for them yet.
+    pub fn pending_events(&self) -> Vec<PendingEvent> {
+        self.chain().map_or_else(Vec::new, Chain::pending_events)
+    }
+
     /// Returns this node state.
     pub fn node_state(&self) -> Option<&crate::states::Elder> {
         self.machine.current().elder_state()
@@ -635,6 +1304,13 @@ impl Node {
             .map(|state| state.set_next_relocation_dst(dst));
     }
 
+    /// Sets the guard against quorum capture by recently joined nodes, or disables it if `None`.
+    pub fn set_stability_guard(&mut self, guard: Option<StabilityGuard>) {
+        let _ = self
+            .node_state_mut()
+            .map(|state| state.set_stability_guard(guard));
+    }
+
     /// Sets an interval to be used when a node is required to generate a new name.
     pub fn set_next_relocation_interval(&mut self, interval: Option<XorTargetInterval>) {
         let _ = self
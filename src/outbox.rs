@@ -11,10 +11,13 @@
 //! The design allows the separate traits to handle dispatching of each type of message to an
 //! object handling the appropriate types of message.
 
+use crate::crash_dump;
 use crate::event::Event;
+use crossbeam_channel;
 use std::collections::VecDeque;
 use std::default::Default;
 use std::mem;
+use std::sync::mpsc;
 
 /// An event dispatcher. Collects things to deliver and "sends".
 ///
@@ -25,6 +28,40 @@ pub trait EventBox {
     fn send_event(&mut self, event: Event);
 }
 
+/// A channel through which `Event`s are delivered to the embedder.
+///
+/// Implemented for the `std::sync::mpsc` and `crossbeam_channel` senders. With a bounded channel,
+/// delivery blocks until the consumer has caught up, which applies backpressure to routing. Other
+/// channels, e.g. an async one, can be used by implementing this for an adapter.
+pub trait EventSender: Send {
+    /// Delivers `event`, or gives it back if the receiving end is gone.
+    fn deliver(&self, event: Event) -> Result<(), Event>;
+}
+
+impl EventSender for mpsc::Sender<Event> {
+    fn deliver(&self, event: Event) -> Result<(), Event> {
+        self.send(event).map_err(|err| err.0)
+    }
+}
+
+impl EventSender for mpsc::SyncSender<Event> {
+    fn deliver(&self, event: Event) -> Result<(), Event> {
+        self.send(event).map_err(|err| err.0)
+    }
+}
+
+impl EventSender for crossbeam_channel::Sender<Event> {
+    fn deliver(&self, event: Event) -> Result<(), Event> {
+        self.send(event).map_err(|err| err.0)
+    }
+}
+
+impl<S: EventSender + ?Sized> EventSender for Box<S> {
+    fn deliver(&self, event: Event) -> Result<(), Event> {
+        (**self).deliver(event)
+    }
+}
+
 /// Implementor of `EventBox`; stores its events in a `VecDeque`.
 #[derive(Default)]
 pub struct EventBuf {
@@ -33,6 +70,7 @@ pub struct EventBuf {
 
 impl EventBox for EventBuf {
     fn send_event(&mut self, event: Event) {
+        crash_dump::record_event(&event, self.events.len() + 1);
         self.events.push_back(event)
     }
 }
@@ -52,6 +90,14 @@ impl EventBuf {
     pub fn take_all(&mut self) -> VecDeque<Event> {
         mem::replace(&mut self.events, Default::default())
     }
+
+    /// Delivers all stored events through `sender`, in order. Returns `false` if the receiving
+    /// end is gone, in which case the remaining events are dropped.
+    pub fn deliver_all(&mut self, sender: &dyn EventSender) -> bool {
+        self.take_all()
+            .into_iter()
+            .all(|event| sender.deliver(event).is_ok())
+    }
 }
 
 impl Drop for EventBuf {
@@ -61,3 +107,24 @@ impl Drop for EventBuf {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deliver_all_through_bounded_channel() {
+        let (sender, receiver) = crossbeam_channel::bounded(2);
+        let mut buf = EventBuf::new();
+        buf.send_event(Event::Connected);
+        buf.send_event(Event::TimerTicked);
+        assert!(buf.deliver_all(&sender));
+        assert_eq!(receiver.try_recv(), Ok(Event::Connected));
+        assert_eq!(receiver.try_recv(), Ok(Event::TimerTicked));
+
+        drop(receiver);
+        buf.send_event(Event::Terminated);
+        assert!(!buf.deliver_all(&sender));
+        assert!(buf.take_first().is_none());
+    }
+}
@@ -8,15 +8,14 @@
 
 #[cfg(feature = "mock_parsec")]
 use crate::mock::parsec as inner;
-use crate::{
-    chain::{self, GenesisPfxInfo},
-    id::{self, FullId},
-    messages::DirectMessage,
-    utils::LogIdent,
-};
+use crate::{chain, id};
+#[cfg(feature = "node")]
+use crate::{chain::GenesisPfxInfo, id::FullId, messages::DirectMessage, utils::LogIdent};
+#[cfg(feature = "node")]
 use log::LogLevel;
 #[cfg(not(feature = "mock_parsec"))]
 use parsec as inner;
+#[cfg(feature = "node")]
 use std::collections::{btree_map::Entry, BTreeMap};
 
 #[cfg(feature = "mock_parsec")]
@@ -26,15 +25,19 @@ pub use crate::mock::parsec::{
 #[cfg(not(feature = "mock_parsec"))]
 pub use parsec::{ConsensusMode, NetworkEvent, Observation, Proof, PublicId, SecretId};
 
+#[cfg(feature = "node")]
 pub type Block = inner::Block<chain::NetworkEvent, id::PublicId>;
+#[cfg(feature = "node")]
 pub type Parsec = inner::Parsec<chain::NetworkEvent, FullId>;
 pub type Request = inner::Request<chain::NetworkEvent, id::PublicId>;
 pub type Response = inner::Response<chain::NetworkEvent, id::PublicId>;
 
+#[cfg(feature = "node")]
 pub struct ParsecMap {
     map: BTreeMap<u64, Parsec>,
 }
 
+#[cfg(feature = "node")]
 impl ParsecMap {
     pub fn new(full_id: FullId, gen_pfx_info: &GenesisPfxInfo) -> Self {
         let mut map = BTreeMap::new();
@@ -171,6 +174,7 @@ impl ParsecMap {
 }
 
 /// Create Parsec instance.
+#[cfg(feature = "node")]
 fn create(full_id: FullId, gen_pfx_info: &GenesisPfxInfo) -> Parsec {
     if gen_pfx_info
         .first_info
@@ -10,7 +10,6 @@ use crate::{
     chain::OnlinePayload,
     error::RoutingError,
     id::PublicId,
-    resource_prover::RESOURCE_PROOF_DURATION,
     time::{Duration, Instant},
     utils::{LogIdent, XorTargetInterval},
     xor_name::XorName,
@@ -29,10 +28,6 @@ use std::{
 
 /// Time (in seconds) after which a joining node will get dropped from the map of joining nodes.
 const JOINING_NODE_TIMEOUT_SECS: u64 = 900;
-/// Duration after which a candidate is considered as expired.
-/// Using a larger timeout to allow Online to accumulate via gossip.
-const CANDIDATE_EXPIRED_TIMEOUT: Duration =
-    Duration::from_secs(RESOURCE_PROOF_DURATION.as_secs() + 90);
 /// Time (in seconds) after which the connection to a peer is considered failed.
 const CONNECTING_PEER_TIMEOUT_SECS: u64 = 150;
 /// Time (in seconds) the node waits for a peer to either become valid once connected to it or to
@@ -42,7 +37,8 @@ const CONNECTED_PEER_TIMEOUT_SECS: u64 = 120;
 #[cfg(feature = "mock_base")]
 #[doc(hidden)]
 pub mod test_consts {
-    pub const CANDIDATE_EXPIRED_TIMEOUT_SECS: u64 = super::CANDIDATE_EXPIRED_TIMEOUT.as_secs();
+    pub const CANDIDATE_EXPIRED_TIMEOUT_SECS: u64 =
+        crate::constants::CANDIDATE_EXPIRED_TIMEOUT.as_secs();
     pub const CONNECTING_PEER_TIMEOUT_SECS: u64 = super::CONNECTING_PEER_TIMEOUT_SECS;
     pub const CONNECTED_PEER_TIMEOUT_SECS: u64 = super::CONNECTED_PEER_TIMEOUT_SECS;
     pub const JOINING_NODE_TIMEOUT_SECS: u64 = super::JOINING_NODE_TIMEOUT_SECS;
@@ -139,6 +135,11 @@ impl Peer {
         }
     }
 
+    /// Returns whether the peer is a client we are the proxy of.
+    pub fn is_client(&self) -> bool {
+        self.client_ip().is_some()
+    }
+
     /// Returns whether the peer is our proxy node.
     fn is_proxy(&self) -> bool {
         self.state == PeerState::Proxy
@@ -201,7 +202,7 @@ enum Candidate {
 }
 
 impl Candidate {
-    fn is_expired(&self) -> bool {
+    fn is_expired(&self, timeout: Duration) -> bool {
         match self {
             Candidate::None => false,
             Candidate::AcceptedForResourceProof {
@@ -209,7 +210,7 @@ impl Candidate {
             }
             | Candidate::ResourceProof {
                 res_proof_start, ..
-            } => res_proof_start.elapsed() > CANDIDATE_EXPIRED_TIMEOUT,
+            } => res_proof_start.elapsed() > timeout,
         }
     }
 
@@ -483,9 +484,9 @@ impl PeerManager {
             .map(|(pub_id, _)| pub_id.name())
     }
 
-    /// Return old public id of expired candidate only once
-    pub fn expired_candidate_once(&mut self) -> bool {
-        if !self.candidate.has_expired_once() && self.candidate.is_expired() {
+    /// Return whether the candidate expired after `timeout`, only once
+    pub fn expired_candidate_once(&mut self, timeout: Duration) -> bool {
+        if !self.candidate.has_expired_once() && self.candidate.is_expired(timeout) {
             self.candidate.set_expired_once();
             true
         } else {
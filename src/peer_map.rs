@@ -6,8 +6,11 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{id::PublicId, quic_p2p::NodeInfo, ConnectionInfo};
-use std::{collections::HashMap, net::SocketAddr};
+use crate::{id::PublicId, messages::Capabilities, quic_p2p::NodeInfo, ConnectionInfo};
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+};
 
 /// This structure holds the bi-directional association between peers public id and their network
 /// connection info. This association can be create in two ways:
@@ -18,11 +21,16 @@ use std::{collections::HashMap, net::SocketAddr};
 ///    the network layer, call `connect`. Then when their public id is received, call `identify`.
 ///    This happens when the peer connects to us and then sends us a message which contains their
 ///    public id.
+///
+/// It also tracks the capabilities exchanged with each identified peer, which are forgotten with
+/// the connection.
 #[derive(Default)]
 pub struct PeerMap {
     forward: HashMap<PublicId, ConnectionInfo>,
     reverse: HashMap<SocketAddr, PublicId>,
     pending: HashMap<SocketAddr, PendingConnection>,
+    capabilities: HashMap<PublicId, Capabilities>,
+    announced: HashSet<PublicId>,
 }
 
 // TODO (quic-p2p): correctly handle these pathological scenarios:
@@ -56,6 +64,16 @@ impl PeerMap {
         }
     }
 
+    // Returns whether we know which peer is at `socket_addr`, e.g. because we connected to it.
+    pub fn is_known(&self, socket_addr: &SocketAddr) -> bool {
+        self.reverse.contains_key(socket_addr)
+    }
+
+    // Returns the number of connections, both identified and pending.
+    pub fn connection_count(&self) -> usize {
+        self.reverse.len() + self.pending.len()
+    }
+
     // Marks the connection as severed at the network layer. Returns the peers public id if the
     // connection has been associated with one.
     pub fn disconnect(&mut self, socket_addr: SocketAddr) -> Option<PublicId> {
@@ -63,6 +81,7 @@ impl PeerMap {
 
         if let Some(pub_id) = self.reverse.remove(&socket_addr) {
             let _ = self.forward.remove(&pub_id);
+            self.forget_capabilities(&pub_id);
             Some(pub_id)
         } else {
             None
@@ -96,12 +115,16 @@ impl PeerMap {
     pub fn remove(&mut self, pub_id: &PublicId) -> Option<ConnectionInfo> {
         let conn_info = self.forward.remove(pub_id)?;
         let _ = self.reverse.remove(&conn_info.peer_addr());
+        self.forget_capabilities(pub_id);
         Some(conn_info)
     }
 
     // Removes all peers. Returns an iterator over the connection infos of the removed peers.
+    #[cfg(feature = "node")]
     pub fn remove_all<'a>(&'a mut self) -> impl Iterator<Item = ConnectionInfo> + 'a {
         self.reverse.clear();
+        self.capabilities.clear();
+        self.announced.clear();
         self.forward.drain().map(|(_, conn_info)| conn_info).chain(
             self.pending
                 .drain()
@@ -114,9 +137,38 @@ impl PeerMap {
         self.forward.get(pub_id)
     }
 
+    // Get the public id of the peer at `socket_addr`, if it has been identified.
+    pub fn get_id(&self, socket_addr: &SocketAddr) -> Option<&PublicId> {
+        self.reverse.get(socket_addr)
+    }
+
+    #[cfg(feature = "node")]
     pub fn connected_ids(&self) -> impl Iterator<Item = &PublicId> {
         self.forward.keys()
     }
+
+    // Records the capabilities the peer announced. Ignored if we aren't connected to it.
+    pub fn set_capabilities(&mut self, pub_id: PublicId, capabilities: Capabilities) {
+        if self.forward.contains_key(&pub_id) {
+            let _ = self.capabilities.insert(pub_id, capabilities);
+        }
+    }
+
+    // Returns the capabilities the peer announced, or none if it didn't (yet).
+    pub fn capabilities(&self, pub_id: &PublicId) -> Capabilities {
+        self.capabilities.get(pub_id).cloned().unwrap_or_default()
+    }
+
+    // Returns `true` if we are connected to the peer and haven't announced our capabilities to it
+    // yet, and marks them as announced.
+    pub fn should_announce_capabilities(&mut self, pub_id: &PublicId) -> bool {
+        self.forward.contains_key(pub_id) && self.announced.insert(*pub_id)
+    }
+
+    fn forget_capabilities(&mut self, pub_id: &PublicId) {
+        let _ = self.capabilities.remove(pub_id);
+        let _ = self.announced.remove(pub_id);
+    }
 }
 
 enum PendingConnection {
@@ -189,6 +241,31 @@ mod tests {
         assert_eq!(peer_map.get_connection_info(&pub_id), Some(&conn_info));
     }
 
+    #[test]
+    fn capabilities_are_forgotten_on_disconnect() {
+        let mut peer_map = PeerMap::new();
+        let conn_info = conn_info("198.51.100.0:5555");
+        let pub_id = *FullId::new().public_id();
+
+        peer_map.set_capabilities(pub_id, Capabilities::ours());
+        assert!(!peer_map.should_announce_capabilities(&pub_id));
+        assert_eq!(peer_map.capabilities(&pub_id), Capabilities::default());
+
+        peer_map.connect(conn_info.clone());
+        peer_map.identify(pub_id, conn_info.peer_addr());
+        peer_map.set_capabilities(pub_id, Capabilities::ours());
+        assert!(peer_map.capabilities(&pub_id).hop_encryption());
+        assert!(peer_map.should_announce_capabilities(&pub_id));
+        assert!(!peer_map.should_announce_capabilities(&pub_id));
+
+        let _ = peer_map.disconnect(conn_info.peer_addr());
+        assert!(!peer_map.capabilities(&pub_id).hop_encryption());
+
+        peer_map.connect(conn_info.clone());
+        peer_map.identify(pub_id, conn_info.peer_addr());
+        assert!(peer_map.should_announce_capabilities(&pub_id));
+    }
+
     fn conn_info(addr: &str) -> ConnectionInfo {
         ConnectionInfo::Node {
             node_info: node_info(addr),
@@ -0,0 +1,234 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+#[cfg(feature = "node")]
+use crate::id::PublicId;
+#[cfg(feature = "node")]
+use crate::messages::Request;
+#[cfg(feature = "node")]
+use crate::time::{Duration, Instant};
+#[cfg(feature = "node")]
+use lru_time_cache::LruCache;
+#[cfg(feature = "node")]
+use std::collections::HashMap;
+
+/// Duration after which the buckets of a client which sent no requests are forgotten. Buckets are
+/// full again long before that, so forgetting them doesn't let a client exceed its limits.
+#[cfg(feature = "node")]
+const BUCKET_EXPIRY_DURATION: Duration = Duration::from_secs(60 * 10);
+
+/// The kinds of client requests which are limited separately.
+#[cfg(feature = "node")]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum RequestKind {
+    Put,
+    Get,
+    Mutation,
+}
+
+#[cfg(feature = "node")]
+impl RequestKind {
+    pub fn of(request: &Request) -> Self {
+        match *request {
+            Request::PutIData { .. }
+            | Request::PutMData { .. }
+            | Request::PutAData { .. }
+            | Request::Sealed { .. } => RequestKind::Put,
+            ref request if request.is_get() => RequestKind::Get,
+            _ => RequestKind::Mutation,
+        }
+    }
+}
+
+/// How many requests of one kind a client may send through its proxy.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// The number of requests a client may send at once.
+    pub burst: u32,
+    /// The number of requests per second a client may send in the long run.
+    pub per_sec: u32,
+}
+
+/// The limits for each kind of client request.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct RateLimits {
+    /// The limit for requests storing new data.
+    pub puts: RateLimit,
+    /// The limit for requests only fetching data.
+    pub gets: RateLimit,
+    /// The limit for requests changing existing data or accounts.
+    pub mutations: RateLimit,
+}
+
+#[cfg(feature = "node")]
+impl RateLimits {
+    fn of(&self, kind: RequestKind) -> RateLimit {
+        match kind {
+            RequestKind::Put => self.puts,
+            RequestKind::Get => self.gets,
+            RequestKind::Mutation => self.mutations,
+        }
+    }
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            puts: RateLimit {
+                burst: 20,
+                per_sec: 5,
+            },
+            gets: RateLimit {
+                burst: 100,
+                per_sec: 50,
+            },
+            mutations: RateLimit {
+                burst: 20,
+                per_sec: 5,
+            },
+        }
+    }
+}
+
+#[cfg(feature = "node")]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Token buckets limiting the requests each of our clients may send, per kind of request.
+#[cfg(feature = "node")]
+pub struct RateLimiter {
+    limits: RateLimits,
+    /// The limits granted to clients by their ClientManager, which replace ours for them.
+    client_limits: HashMap<PublicId, RateLimits>,
+    buckets: LruCache<(PublicId, RequestKind), Bucket>,
+}
+
+#[cfg(feature = "node")]
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> Self {
+        Self {
+            limits,
+            client_limits: HashMap::new(),
+            buckets: LruCache::with_expiry_duration(BUCKET_EXPIRY_DURATION),
+        }
+    }
+
+    /// Returns the limits of clients which weren't granted any by their ClientManager.
+    pub fn limits(&self) -> &RateLimits {
+        &self.limits
+    }
+
+    /// Holds the requests of `client_id` to the `limits` its ClientManager granted it.
+    pub fn set_client_limits(&mut self, client_id: PublicId, limits: RateLimits) {
+        let _ = self.client_limits.insert(client_id, limits);
+    }
+
+    /// Forgets the limits granted to `client_id`, once it disconnected.
+    pub fn remove_client(&mut self, client_id: &PublicId) {
+        let _ = self.client_limits.remove(client_id);
+    }
+
+    /// Takes a token for a request of the given kind from the client's bucket. Returns `false` if
+    /// the bucket is empty, i.e. the client exceeded its limit and the request must be refused.
+    pub fn try_acquire(&mut self, client_id: PublicId, kind: RequestKind) -> bool {
+        let limit = self
+            .client_limits
+            .get(&client_id)
+            .unwrap_or(&self.limits)
+            .of(kind);
+        let key = (client_id, kind);
+        if !self.buckets.contains_key(&key) {
+            let bucket = Bucket {
+                tokens: f64::from(limit.burst),
+                refilled_at: Instant::now(),
+            };
+            let _ = self.buckets.insert(key, bucket);
+        }
+        let bucket = match self.buckets.get_mut(&key) {
+            Some(bucket) => bucket,
+            None => return true,
+        };
+
+        let elapsed = bucket.refilled_at.elapsed();
+        let elapsed_secs = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) * 1e-9;
+        bucket.tokens =
+            (bucket.tokens + elapsed_secs * f64::from(limit.per_sec)).min(f64::from(limit.burst));
+        bucket.refilled_at = Instant::now();
+
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+}
+
+#[cfg(all(test, feature = "node"))]
+mod tests {
+    use super::*;
+    use crate::id::FullId;
+    use fake_clock::FakeClock;
+
+    #[test]
+    fn limits_bursts_and_refills() {
+        let limit = RateLimit {
+            burst: 3,
+            per_sec: 1,
+        };
+        let mut limiter = RateLimiter::new(RateLimits {
+            puts: limit,
+            gets: limit,
+            mutations: limit,
+        });
+        let client_id = *FullId::new().public_id();
+        let other_client_id = *FullId::new().public_id();
+
+        for _ in 0..3 {
+            assert!(limiter.try_acquire(client_id, RequestKind::Put));
+        }
+        assert!(!limiter.try_acquire(client_id, RequestKind::Put));
+
+        // Other kinds of requests and other clients have their own buckets.
+        assert!(limiter.try_acquire(client_id, RequestKind::Get));
+        assert!(limiter.try_acquire(other_client_id, RequestKind::Put));
+
+        FakeClock::advance_time(2000);
+        assert!(limiter.try_acquire(client_id, RequestKind::Put));
+        assert!(limiter.try_acquire(client_id, RequestKind::Put));
+        assert!(!limiter.try_acquire(client_id, RequestKind::Put));
+    }
+
+    #[test]
+    fn granted_limits_replace_ours() {
+        let mut limiter = RateLimiter::new(RateLimits::default());
+        let client_id = *FullId::new().public_id();
+        let limit = RateLimit {
+            burst: 1,
+            per_sec: 1,
+        };
+        limiter.set_client_limits(
+            client_id,
+            RateLimits {
+                puts: limit,
+                gets: limit,
+                mutations: limit,
+            },
+        );
+
+        assert!(limiter.try_acquire(client_id, RequestKind::Get));
+        assert!(!limiter.try_acquire(client_id, RequestKind::Get));
+
+        // Once forgotten, the client's limits are ours again.
+        limiter.remove_client(&client_id);
+        FakeClock::advance_time(1000);
+        assert!(limiter.try_acquire(client_id, RequestKind::Get));
+        assert!(limiter.try_acquire(client_id, RequestKind::Get));
+    }
+}
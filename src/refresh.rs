@@ -0,0 +1,164 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::time::Duration;
+use crate::xor_name::XorName;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// The maximum number of names in one `DataInventory` or `RepublishRequest` message.
+pub const MAX_INVENTORY_NAMES: usize = 1000;
+/// Interval between the batches of republish requests passed on to the user.
+pub const REPUBLISH_INTERVAL: Duration = Duration::from_secs(1);
+/// The maximum number of names passed on to the user per `REPUBLISH_INTERVAL`.
+const NAMES_PER_INTERVAL: usize = 100;
+/// The maximum number of names waiting to be passed on. Requests beyond that are dropped; the
+/// requester will be refreshed again with the next churn.
+const MAX_PENDING_NAMES: usize = 100 * MAX_INVENTORY_NAMES;
+
+/// Splits `names` into chunks small enough for one `DataInventory` or `RepublishRequest` message.
+pub fn inventory_chunks(names: BTreeSet<XorName>) -> Vec<BTreeSet<XorName>> {
+    let mut chunks = Vec::new();
+    let mut chunk = BTreeSet::new();
+    for name in names {
+        let _ = chunk.insert(name);
+        if chunk.len() == MAX_INVENTORY_NAMES {
+            chunks.push(chunk);
+            chunk = BTreeSet::new();
+        }
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+/// The data new members of our section asked us to republish to them, passed on to the user in
+/// small batches, so a churn event doesn't cause a spike in bandwidth.
+///
+/// Requesters are served in turn, so one with a large request doesn't starve the others.
+pub struct RepublishQueue {
+    pending: BTreeMap<XorName, BTreeSet<XorName>>,
+    order: VecDeque<XorName>,
+    len: usize,
+}
+
+impl RepublishQueue {
+    pub fn new() -> Self {
+        Self {
+            pending: BTreeMap::new(),
+            order: VecDeque::new(),
+            len: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Queues the names `requester` asked to be republished. Returns the number of names dropped
+    /// because the queue is full.
+    pub fn push(&mut self, requester: XorName, names: BTreeSet<XorName>) -> usize {
+        if !self.pending.contains_key(&requester) {
+            self.order.push_back(requester);
+        }
+        let queued = self.pending.entry(requester).or_insert_with(BTreeSet::new);
+        let mut dropped = 0;
+        for name in names {
+            if self.len >= MAX_PENDING_NAMES {
+                dropped += 1;
+            } else if queued.insert(name) {
+                self.len += 1;
+            }
+        }
+        if queued.is_empty() {
+            let _ = self.pending.remove(&requester);
+            self.order.retain(|name| *name != requester);
+        }
+        dropped
+    }
+
+    /// Takes the next batch of at most `NAMES_PER_INTERVAL` names, by requester.
+    pub fn pop_batch(&mut self) -> BTreeMap<XorName, BTreeSet<XorName>> {
+        let mut batch = BTreeMap::new();
+        let mut budget = NAMES_PER_INTERVAL;
+        while budget > 0 {
+            let requester = match self.order.pop_front() {
+                Some(requester) => requester,
+                None => break,
+            };
+            let queued = match self.pending.get_mut(&requester) {
+                Some(queued) => queued,
+                None => continue,
+            };
+            let names: Vec<_> = queued.iter().take(budget).cloned().collect();
+            for name in &names {
+                let _ = queued.remove(name);
+            }
+            budget -= names.len();
+            self.len -= names.len();
+            if queued.is_empty() {
+                let _ = self.pending.remove(&requester);
+            } else {
+                self.order.push_back(requester);
+            }
+            batch
+                .entry(requester)
+                .or_insert_with(BTreeSet::new)
+                .extend(names);
+        }
+        batch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+
+    fn random_names(count: usize) -> BTreeSet<XorName> {
+        (0..count).map(|_| rand::random()).collect()
+    }
+
+    #[test]
+    fn chunks() {
+        let chunks = inventory_chunks(random_names(2 * MAX_INVENTORY_NAMES + 1));
+        let lens: Vec<_> = chunks.iter().map(BTreeSet::len).collect();
+        assert_eq!(lens, vec![MAX_INVENTORY_NAMES, MAX_INVENTORY_NAMES, 1]);
+        assert!(inventory_chunks(BTreeSet::new()).is_empty());
+    }
+
+    #[test]
+    fn batches_are_throttled_and_shared() {
+        let mut queue = RepublishQueue::new();
+        let first: XorName = rand::random();
+        let second: XorName = rand::random();
+        let first_names = random_names(NAMES_PER_INTERVAL + 10);
+        let second_names = random_names(10);
+        assert_eq!(queue.push(first, first_names.clone()), 0);
+        assert_eq!(queue.push(second, second_names.clone()), 0);
+
+        // The first batch is full and serves the first requester only...
+        let batch = queue.pop_batch();
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[&first].len(), NAMES_PER_INTERVAL);
+
+        // ... while the second one serves both.
+        let batch = queue.pop_batch();
+        assert_eq!(batch[&second], second_names);
+        assert_eq!(batch[&first].len(), 10);
+        assert!(queue.is_empty());
+        assert!(queue.pop_batch().is_empty());
+
+        // Repeated names are only queued once, and the queue is bounded.
+        assert_eq!(queue.push(first, first_names.clone()), 0);
+        assert_eq!(queue.push(first, first_names), 0);
+        assert_eq!(queue.len, NAMES_PER_INTERVAL + 10);
+        let dropped = queue.push(second, random_names(MAX_PENDING_NAMES));
+        assert_eq!(dropped, NAMES_PER_INTERVAL + 10);
+    }
+}
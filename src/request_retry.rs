@@ -0,0 +1,275 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    client_config::RetryPolicy, messages::Request, routing_table::Authority, time::Duration,
+    timer::Timer, types::MessageId, xor_name::XorName,
+};
+use lru_time_cache::LruCache;
+use std::collections::BTreeMap;
+
+/// Duration for which the IDs of answered requests are remembered, so that responses to their
+/// other attempts are dropped.
+const ANSWERED_EXPIRY_DURATION: Duration = Duration::from_secs(60 * 10);
+
+/// What the timer of a pending request is waiting for.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Wait {
+    Response,
+    Backoff,
+}
+
+struct PendingRequest {
+    dst: Authority<XorName>,
+    request: Request,
+    priority: u8,
+    /// The number of the latest attempt, counting from 1.
+    attempt: u32,
+    token: u64,
+    wait: Wait,
+}
+
+/// What to do about a request after a failed attempt.
+#[derive(Debug)]
+pub enum RetryAction {
+    /// Send the request again.
+    Resend {
+        dst: Authority<XorName>,
+        request: Request,
+        priority: u8,
+    },
+    /// Give up on the request, as no attempts are left.
+    Fail {
+        dst: Authority<XorName>,
+        msg_id: MessageId,
+    },
+}
+
+/// Tracks the requests a client sent until they are answered, and decides when to retry them
+/// according to the `RetryPolicy`.
+pub struct RequestRetries {
+    policy: RetryPolicy,
+    requests: BTreeMap<MessageId, PendingRequest>,
+    tokens: BTreeMap<u64, MessageId>,
+    answered: LruCache<MessageId, ()>,
+}
+
+impl RequestRetries {
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self {
+            policy,
+            requests: BTreeMap::new(),
+            tokens: BTreeMap::new(),
+            answered: LruCache::with_expiry_duration(ANSWERED_EXPIRY_DURATION),
+        }
+    }
+
+    /// Returns the policy the requests are retried by.
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    /// Starts tracking `request`, which is about to be sent to `dst` for the first time.
+    pub fn insert(
+        &mut self,
+        dst: Authority<XorName>,
+        request: Request,
+        priority: u8,
+        timer: &Timer,
+    ) {
+        let msg_id = *request.message_id();
+        if self.requests.contains_key(&msg_id) {
+            return;
+        }
+        let token = timer.schedule(self.policy.response_timeout);
+        let _ = self.tokens.insert(token, msg_id);
+        let _ = self.requests.insert(
+            msg_id,
+            PendingRequest {
+                dst,
+                request,
+                priority,
+                attempt: 1,
+                token,
+                wait: Wait::Response,
+            },
+        );
+    }
+
+    /// Handles the response with the given message ID. Returns whether to deliver it, i.e. it
+    /// doesn't answer a request which was already answered.
+    pub fn handle_response(&mut self, msg_id: &MessageId) -> bool {
+        if let Some(pending) = self.requests.remove(msg_id) {
+            let _ = self.tokens.remove(&pending.token);
+            let _ = self.answered.insert(*msg_id, ());
+            true
+        } else {
+            !self.answered.contains_key(msg_id)
+        }
+    }
+
+    /// Returns whether the request with the given message ID has already been sent before.
+    pub fn is_retrying(&self, msg_id: &MessageId) -> bool {
+        self.requests
+            .get(msg_id)
+            .map_or(false, |pending| pending.attempt > 1)
+    }
+
+    /// Handles the latest attempt of the request failing to be sent.
+    pub fn handle_send_failure(
+        &mut self,
+        msg_id: &MessageId,
+        timer: &Timer,
+    ) -> Option<RetryAction> {
+        self.attempt_failed(msg_id, timer)
+    }
+
+    /// Handles a timeout, which either means a request wasn't answered in time or is due to be
+    /// retried. Returns `None` if the token isn't one of ours, or there is nothing to do yet.
+    pub fn handle_timeout(&mut self, token: u64, timer: &Timer) -> Option<RetryAction> {
+        let msg_id = self.tokens.remove(&token)?;
+        let wait = self.requests.get(&msg_id)?.wait;
+        match wait {
+            Wait::Response => self.attempt_failed(&msg_id, timer),
+            Wait::Backoff => {
+                let token = timer.schedule(self.policy.response_timeout);
+                let _ = self.tokens.insert(token, msg_id);
+                let pending = self.requests.get_mut(&msg_id)?;
+                pending.attempt += 1;
+                pending.token = token;
+                pending.wait = Wait::Response;
+                Some(RetryAction::Resend {
+                    dst: pending.dst,
+                    request: pending.request.clone(),
+                    priority: pending.priority,
+                })
+            }
+        }
+    }
+
+    // Schedules the next attempt, or gives up on the request if it was the last one.
+    fn attempt_failed(&mut self, msg_id: &MessageId, timer: &Timer) -> Option<RetryAction> {
+        let policy = self.policy;
+        let pending = self.requests.get_mut(msg_id)?;
+        let _ = self.tokens.remove(&pending.token);
+
+        if pending.attempt >= policy.max_attempts(pending.request.is_get()) {
+            let dst = pending.dst;
+            let _ = self.requests.remove(msg_id);
+            return Some(RetryAction::Fail {
+                dst,
+                msg_id: *msg_id,
+            });
+        }
+
+        pending.token = timer.schedule(policy.backoff(pending.attempt + 1));
+        pending.wait = Wait::Backoff;
+        let _ = self.tokens.insert(pending.token, *msg_id);
+        None
+    }
+}
+
+#[cfg(all(test, feature = "mock_base"))]
+mod tests {
+    use super::*;
+    use crate::{data::ImmutableData, time::Instant};
+    use crossbeam_channel as mpmc;
+    use fake_clock::FakeClock;
+    use unwrap::unwrap;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            response_timeout: Duration::from_secs(10),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(10),
+            retry_mutations: false,
+        }
+    }
+
+    fn advance(timer: &mut Timer, duration: Duration) -> Vec<u64> {
+        FakeClock::advance_time(duration.as_secs() * 1000 + 1);
+        timer.get_timed_out_tokens()
+    }
+
+    #[test]
+    fn retries_until_answered() {
+        let (action_tx, _action_rx) = mpmc::unbounded();
+        let mut timer = Timer::new(action_tx);
+        let mut retries = RequestRetries::new(policy());
+        let dst = Authority::NaeManager(rand::random());
+        let request = Request::GetIData {
+            name: rand::random(),
+            msg_id: MessageId::new(),
+        };
+        let msg_id = *request.message_id();
+        retries.insert(dst, request, 0, &timer);
+
+        // The response times out, and the request is resent after the backoff.
+        let start = Instant::now();
+        let tokens = advance(&mut timer, Duration::from_secs(10));
+        assert_eq!(tokens.len(), 1);
+        assert!(retries.handle_timeout(tokens[0], &timer).is_none());
+        let tokens = advance(&mut timer, Duration::from_secs(1));
+        match retries.handle_timeout(tokens[0], &timer) {
+            Some(RetryAction::Resend { request, .. }) => assert_eq!(*request.message_id(), msg_id),
+            action => panic!("Unexpected {:?}", action),
+        }
+        assert!(Instant::now() - start >= Duration::from_secs(11));
+
+        // Only the first response to any attempt is delivered.
+        assert!(retries.handle_response(&msg_id));
+        assert!(!retries.handle_response(&msg_id));
+        assert!(retries.handle_response(&MessageId::new()));
+        let tokens = advance(&mut timer, Duration::from_secs(10));
+        assert!(tokens
+            .into_iter()
+            .all(|token| retries.handle_timeout(token, &timer).is_none()));
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let (action_tx, _action_rx) = mpmc::unbounded();
+        let timer = Timer::new(action_tx);
+        let mut retries = RequestRetries::new(policy());
+        let dst = Authority::NaeManager(rand::random());
+
+        // Mutations are only sent once by default.
+        let put = Request::PutIData {
+            data: ImmutableData::new(vec![1, 2, 3]),
+            msg_id: MessageId::new(),
+        };
+        let put_id = *put.message_id();
+        retries.insert(dst, put, 0, &timer);
+        match retries.handle_send_failure(&put_id, &timer) {
+            Some(RetryAction::Fail { msg_id, .. }) => assert_eq!(msg_id, put_id),
+            action => panic!("Unexpected {:?}", action),
+        }
+
+        let get = Request::GetIData {
+            name: rand::random(),
+            msg_id: MessageId::new(),
+        };
+        let get_id = *get.message_id();
+        retries.insert(dst, get, 0, &timer);
+        for _ in 0..2 {
+            assert!(retries.handle_send_failure(&get_id, &timer).is_none());
+            let token = unwrap!(retries.requests.get(&get_id)).token;
+            match retries.handle_timeout(token, &timer) {
+                Some(RetryAction::Resend { .. }) => (),
+                action => panic!("Unexpected {:?}", action),
+            }
+        }
+        match retries.handle_send_failure(&get_id, &timer) {
+            Some(RetryAction::Fail { msg_id, .. }) => assert_eq!(msg_id, get_id),
+            action => panic!("Unexpected {:?}", action),
+        }
+        assert!(retries.requests.is_empty());
+        assert!(retries.tokens.is_empty());
+    }
+}
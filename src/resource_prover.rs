@@ -8,11 +8,11 @@
 
 use crate::{
     action::Action,
+    constants::RESOURCE_PROOF_DURATION,
     event::Event,
     id::PublicId,
     messages::DirectMessage,
     outbox::EventBox,
-    signature_accumulator::ACCUMULATION_TIMEOUT,
     state_machine::Transition,
     time::{Duration, Instant},
     timer::Timer,
@@ -30,15 +30,6 @@ use std::{
 };
 use unwrap::unwrap;
 
-/// Time (in seconds) between accepting a new candidate (i.e. accumulating an `ExpectCandidate` in
-/// our section) and sending a `CandidateApproval` for this candidate. If the candidate cannot
-/// satisfy the proof of resource challenge within this time, no `CandidateApproval` is sent.
-pub const RESOURCE_PROOF_DURATION: Duration = Duration::from_secs(300);
-/// Maximum time a new node will wait to receive `NodeApproval` after receiving a
-/// `RelocateResponse`. This covers the built-in delay of the process and also allows time for the
-/// message to accumulate and be sent via four different routes.
-const APPROVAL_TIMEOUT: Duration =
-    Duration::from_secs(RESOURCE_PROOF_DURATION.as_secs() + 2 * ACCUMULATION_TIMEOUT.as_secs());
 /// Interval between displaying info about ongoing approval progress, in seconds.
 const APPROVAL_PROGRESS_INTERVAL: Duration = Duration::from_secs(30);
 
@@ -63,14 +54,19 @@ pub struct ResourceProver {
 }
 
 impl ResourceProver {
-    /// Create an instance.
-    pub fn new(action_sender: mpmc::Sender<Action>, timer: Timer, challenger_count: usize) -> Self {
+    /// Create an instance, which waits for approval for `approval_timeout`.
+    pub fn new(
+        action_sender: mpmc::Sender<Action>,
+        timer: Timer,
+        challenger_count: usize,
+        approval_timeout: Duration,
+    ) -> Self {
         ResourceProver {
             action_sender: action_sender,
             get_approval_timer_token: None,
             approval_progress_timer_token: None,
             approval_expiry_time: Instant::now(),
-            approval_timeout: APPROVAL_TIMEOUT,
+            approval_timeout,
             challenger_count: challenger_count,
             response_parts: Default::default(),
             workers: Default::default(),
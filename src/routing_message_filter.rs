@@ -6,6 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use crate::bloom_filter::BloomFilter;
 use crate::id::PublicId;
 use crate::message_filter::MessageFilter;
 use crate::messages::RoutingMessage;
@@ -20,6 +21,10 @@ type Digest = [u8; 32];
 
 const INCOMING_EXPIRY_DURATION_SECS: u64 = 60 * 20;
 const OUTGOING_EXPIRY_DURATION_SECS: u64 = 60 * 10;
+/// The default number of bytes used to remember incoming messages.
+pub const DEFAULT_MEMORY_BUDGET: usize = 4 * 1024 * 1024;
+/// The approximate number of bytes the exact store uses per message.
+const BYTES_PER_EXACT_ENTRY: usize = 64;
 
 /// An enum representing a result of message filtering
 #[derive(Eq, PartialEq)]
@@ -31,36 +36,79 @@ pub enum FilteringResult {
 }
 
 // Structure to filter (throttle) incoming and outgoing `RoutingMessages`.
+//
+// Incoming messages pass a bloom filter first, which tells most new messages apart without a
+// lookup in the exact store behind it. The exact store has the final say for the messages the
+// bloom filter may have seen, so a false positive never drops a new message.
 pub struct RoutingMessageFilter {
+    incoming_bloom: BloomFilter,
     incoming: MessageFilter<Digest>,
     outgoing: LruCache<(Digest, PublicId), ()>,
+    lookups: usize,
+    false_positives: usize,
 }
 
 impl RoutingMessageFilter {
     pub fn new() -> Self {
+        Self::with_memory_budget(DEFAULT_MEMORY_BUDGET)
+    }
+
+    // Creates a filter remembering as many incoming messages as fit into `bytes` bytes, a quarter
+    // of which is used by the bloom filter and the rest by the exact store.
+    pub fn with_memory_budget(bytes: usize) -> Self {
         let incoming_duration = Duration::from_secs(INCOMING_EXPIRY_DURATION_SECS);
         let outgoing_duration = Duration::from_secs(OUTGOING_EXPIRY_DURATION_SECS);
+        let bloom_bytes = bytes / 4;
+        let capacity = ((bytes - bloom_bytes) / BYTES_PER_EXACT_ENTRY).max(1);
 
         RoutingMessageFilter {
-            incoming: MessageFilter::with_expiry_duration(incoming_duration),
+            incoming_bloom: BloomFilter::new(bloom_bytes, capacity),
+            incoming: MessageFilter::with_expiry_duration_and_capacity(incoming_duration, capacity),
             outgoing: LruCache::with_expiry_duration(outgoing_duration),
+            lookups: 0,
+            false_positives: 0,
         }
     }
 
-    // Filter incoming `RoutingMessage`. Return the number of times this specific message has been
-    // seen, including this time.
+    // Filter incoming `RoutingMessage`. Return whether this specific message has been seen
+    // before.
     pub fn filter_incoming(&mut self, msg: &RoutingMessage) -> FilteringResult {
         let hash = match hash(msg) {
             Some(hash) => hash,
             None => return FilteringResult::NewMessage,
         };
+        self.lookups += 1;
+        if !self.incoming_bloom.contains(&hash) {
+            self.incoming_bloom.insert(&hash);
+            let _ = self.incoming.insert(&hash);
+            return FilteringResult::NewMessage;
+        }
+
         if self.incoming.insert(&hash) > 1 {
             FilteringResult::KnownMessage
         } else {
+            self.false_positives += 1;
+            self.incoming_bloom.insert(&hash);
             FilteringResult::NewMessage
         }
     }
 
+    // Forgets an incoming `RoutingMessage`, so it is handled again if it is received again. The
+    // bloom filter can't forget it, but the exact store corrects it.
+    #[cfg(feature = "node")]
+    pub fn forget_incoming(&mut self, msg: &RoutingMessage) {
+        if let Some(hash) = hash(msg) {
+            self.incoming.remove(&hash);
+        }
+    }
+
+    // Returns the number of incoming messages filtered, and the number of them the bloom filter
+    // wrongly reported as seen.
+    #[cfg(feature = "node")]
+    pub fn incoming_stats(&self) -> (usize, usize) {
+        (self.lookups, self.false_positives)
+    }
+
     // Filter outgoing `RoutingMessage`. Return whether this specific message has been seen recently
     // (and thus should not be sent, due to deduplication).
     //
@@ -9,14 +9,135 @@
 use super::{Prefix, Xorable};
 use crate::id::PublicId;
 use crate::xor_name::XorName;
+use safe_crypto;
+use std::collections::BTreeMap;
 use std::fmt::{self, Binary, Debug, Display, Formatter};
 
+/// Number of `GroupKind` values reserved for the kinds defined by routing itself.
+const RESERVED_GROUP_KINDS: u32 = 16;
+
+/// The persona of a group authority, i.e. which role the group of nodes closest to a name acts in.
+#[derive(Serialize, Deserialize, PartialEq, PartialOrd, Eq, Ord, Clone, Copy, Hash, Debug)]
+pub struct GroupKind(pub u32);
+
+impl GroupKind {
+    /// Same as `Authority::ClientManager`.
+    pub const CLIENT_MANAGER: GroupKind = GroupKind(0);
+    /// Same as `Authority::NaeManager`.
+    pub const NAE_MANAGER: GroupKind = GroupKind(1);
+    /// Same as `Authority::NodeManager`.
+    pub const NODE_MANAGER: GroupKind = GroupKind(2);
+
+    /// Returns the kind for the persona called `name`. All nodes derive the same kind from the same
+    /// name, so they don't need to agree on a numbering.
+    pub fn from_name(name: &str) -> Self {
+        let hash = safe_crypto::hash(name.as_bytes());
+        GroupKind(
+            u32::from(hash[0]) << 24
+                | u32::from(hash[1]) << 16
+                | u32::from(hash[2]) << 8
+                | u32::from(hash[3]),
+        )
+    }
+
+    /// Returns whether this is one of the kinds defined by routing.
+    pub fn is_reserved(self) -> bool {
+        self.0 < RESERVED_GROUP_KINDS
+    }
+}
+
+/// Errors when registering personas in a `GroupKindRegistry`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GroupKindError {
+    /// The name of the persona maps to the same `GroupKind` as the contained, registered one.
+    KindCollision(String),
+    /// The name of the persona maps to a kind reserved for routing.
+    Reserved,
+    /// No persona with that name is registered.
+    UnknownKind,
+}
+
+impl Display for GroupKindError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            GroupKindError::KindCollision(ref name) => {
+                write!(f, "Group kind already used by {:?}", name)
+            }
+            GroupKindError::Reserved => write!(f, "Group kind reserved for routing"),
+            GroupKindError::UnknownKind => write!(f, "Group kind not registered"),
+        }
+    }
+}
+
+/// Maps the names of vault personas to the `GroupKind`s their group authorities use.
+///
+/// New personas (e.g. a reward manager) register here and address each other with
+/// `Authority::Group`, instead of needing their own `Authority` variant.
+#[derive(Clone, Debug)]
+pub struct GroupKindRegistry {
+    kinds: BTreeMap<GroupKind, String>,
+}
+
+impl GroupKindRegistry {
+    /// Creates a registry containing the kinds defined by routing.
+    pub fn new() -> Self {
+        GroupKindRegistry {
+            kinds: vec![
+                (GroupKind::CLIENT_MANAGER, "ClientManager".to_string()),
+                (GroupKind::NAE_MANAGER, "NaeManager".to_string()),
+                (GroupKind::NODE_MANAGER, "NodeManager".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    /// Registers the persona called `name` and returns its kind. Registering the same name again
+    /// is a no-op.
+    pub fn register(&mut self, name: &str) -> Result<GroupKind, GroupKindError> {
+        let kind = GroupKind::from_name(name);
+        if kind.is_reserved() {
+            return Err(GroupKindError::Reserved);
+        }
+        match self.kinds.get(&kind) {
+            Some(registered) if registered != name => {
+                return Err(GroupKindError::KindCollision(registered.clone()));
+            }
+            Some(_) => (),
+            None => {
+                let _ = self.kinds.insert(kind, name.to_string());
+            }
+        }
+        Ok(kind)
+    }
+
+    /// Returns the kind of the registered persona `name`.
+    pub fn kind(&self, name: &str) -> Result<GroupKind, GroupKindError> {
+        self.kinds
+            .iter()
+            .find(|&(_, registered)| registered == name)
+            .map(|(kind, _)| *kind)
+            .ok_or(GroupKindError::UnknownKind)
+    }
+
+    /// Returns the name of the persona with the given kind, if it is registered.
+    pub fn name(&self, kind: GroupKind) -> Option<&str> {
+        self.kinds.get(&kind).map(String::as_str)
+    }
+}
+
+impl Default for GroupKindRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// An entity that can act as a source or destination of a message.
 ///
 /// `Client` and `ManagedNode` are single-node authorities (i.e. no verification of messages from
 /// additional sources needed); other authorities require agreement by a quorum of some set.
-/// `NodeManager`, `ClientManager` and `NaeManager` use _group_ verification of messages: they
-/// require quorum agreement from the group of nodes closest to the source, while `Section` and
+/// `NodeManager`, `ClientManager`, `NaeManager` and `Group` use _group_ verification of messages:
+/// they require quorum agreement from the group of nodes closest to the source, while `Section` and
 /// `PrefixSection` use _section_ verification: the set from which a quorum is required is all
 /// members of the section (`Section`) or of all sections matching the prefix (`PrefixSection`).
 #[derive(Serialize, Deserialize, PartialEq, PartialOrd, Eq, Ord, Clone, Copy, Hash)]
@@ -44,6 +165,14 @@ pub enum Authority<N: Xorable + Clone + Copy + Binary + Default> {
         /// through.
         proxy_node_name: N,
     },
+    /// The group matching `name`, acting as the persona `kind`. Generalises `ClientManager`,
+    /// `NaeManager` and `NodeManager`, which are the groups of the reserved kinds.
+    Group {
+        /// The name the group is closest to.
+        name: N,
+        /// The persona the group acts as.
+        kind: GroupKind,
+    },
 }
 
 impl<N: Xorable + Clone + Copy + Binary + Default> Authority<N> {
@@ -54,7 +183,8 @@ impl<N: Xorable + Clone + Copy + Binary + Default> Authority<N> {
             | Authority::PrefixSection(_)
             | Authority::ClientManager(_)
             | Authority::NaeManager(_)
-            | Authority::NodeManager(_) => true,
+            | Authority::NodeManager(_)
+            | Authority::Group { .. } => true,
             Authority::ManagedNode(_) | Authority::Client { .. } => false,
         }
     }
@@ -66,7 +196,8 @@ impl<N: Xorable + Clone + Copy + Binary + Default> Authority<N> {
             | Authority::NaeManager(_)
             | Authority::Section(_)
             | Authority::PrefixSection(_)
-            | Authority::NodeManager(_) => false,
+            | Authority::NodeManager(_)
+            | Authority::Group { .. } => false,
             Authority::ManagedNode(_) | Authority::Client { .. } => true,
         }
     }
@@ -87,7 +218,8 @@ impl<N: Xorable + Clone + Copy + Binary + Default> Authority<N> {
             | Authority::NaeManager(ref name)
             | Authority::NodeManager(ref name)
             | Authority::Section(ref name)
-            | Authority::ManagedNode(ref name) => *name,
+            | Authority::ManagedNode(ref name)
+            | Authority::Group { ref name, .. } => *name,
             Authority::PrefixSection(ref prefix) => prefix.lower_bound(),
             Authority::Client {
                 ref proxy_node_name,
@@ -95,6 +227,32 @@ impl<N: Xorable + Clone + Copy + Binary + Default> Authority<N> {
             } => *proxy_node_name,
         }
     }
+
+    /// Returns the group kind if this is a group authority, including the legacy manager
+    /// variants.
+    pub fn group_kind(&self) -> Option<GroupKind> {
+        match *self {
+            Authority::ClientManager(_) => Some(GroupKind::CLIENT_MANAGER),
+            Authority::NaeManager(_) => Some(GroupKind::NAE_MANAGER),
+            Authority::NodeManager(_) => Some(GroupKind::NODE_MANAGER),
+            Authority::Group { kind, .. } => Some(kind),
+            Authority::Section(_)
+            | Authority::PrefixSection(_)
+            | Authority::ManagedNode(_)
+            | Authority::Client { .. } => None,
+        }
+    }
+
+    /// Returns the group authority of `kind` for `name`, using the legacy manager variants for the
+    /// reserved kinds so that peers which only know those understand it.
+    pub fn group(name: N, kind: GroupKind) -> Self {
+        match kind {
+            GroupKind::CLIENT_MANAGER => Authority::ClientManager(name),
+            GroupKind::NAE_MANAGER => Authority::NaeManager(name),
+            GroupKind::NODE_MANAGER => Authority::NodeManager(name),
+            kind => Authority::Group { name, kind },
+        }
+    }
 }
 
 impl Authority<XorName> {
@@ -105,7 +263,8 @@ impl Authority<XorName> {
             | Authority::NaeManager(_)
             | Authority::Section(_)
             | Authority::PrefixSection(_)
-            | Authority::NodeManager(_) => None,
+            | Authority::NodeManager(_)
+            | Authority::Group { .. } => None,
             Authority::ManagedNode(ref name) => Some(name),
             Authority::Client { ref client_id, .. } => Some(client_id.name()),
         }
@@ -134,6 +293,58 @@ impl<N: Xorable + Clone + Copy + Binary + Default + Display> Debug for Authority
                 client_id.name(),
                 proxy_node_name
             ),
+            Authority::Group { ref name, kind } => {
+                write!(formatter, "Group {{ name: {}, kind: {} }}", name, kind.0)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_groups() {
+        let name: XorName = rand::random();
+        for auth in &[
+            Authority::ClientManager(name),
+            Authority::NaeManager(name),
+            Authority::NodeManager(name),
+        ] {
+            let kind = unwrap!(auth.group_kind());
+            assert!(kind.is_reserved());
+            assert_eq!(Authority::group(name, kind), *auth);
         }
+
+        let kind = GroupKind::from_name("RewardManager");
+        let auth = Authority::group(name, kind);
+        assert_eq!(auth, Authority::Group { name, kind });
+        assert_eq!(auth.group_kind(), Some(kind));
+        assert_eq!(auth.name(), name);
+        assert!(auth.is_multiple());
+        assert_eq!(Authority::Section(name).group_kind(), None);
+    }
+
+    #[test]
+    fn register_kinds() {
+        let mut registry = GroupKindRegistry::new();
+        assert_eq!(registry.kind("NaeManager"), Ok(GroupKind::NAE_MANAGER));
+        assert_eq!(
+            registry.kind("RewardManager"),
+            Err(GroupKindError::UnknownKind)
+        );
+
+        let kind = unwrap!(registry.register("RewardManager"));
+        assert_eq!(registry.register("RewardManager"), Ok(kind));
+        assert_eq!(registry.kind("RewardManager"), Ok(kind));
+        assert_eq!(registry.name(kind), Some("RewardManager"));
+
+        // Simulate a second persona whose name hashes to the same kind.
+        let _ = registry.kinds.insert(kind, "OtherManager".to_string());
+        assert_eq!(
+            registry.register("RewardManager"),
+            Err(GroupKindError::KindCollision("OtherManager".to_string()))
+        );
     }
 }
@@ -103,12 +103,13 @@ mod network_tests;
 mod prefix;
 mod xorable;
 
-pub use self::authority::Authority;
+pub use self::authority::{Authority, GroupKind, GroupKindError, GroupKindRegistry};
 pub use self::error::Error;
 #[cfg(any(test, feature = "mock_base"))]
 pub use self::network_tests::verify_network_invariant;
 pub use self::prefix::{Prefix, VersionedPrefix, DEFAULT_PREFIX};
 pub use self::xorable::Xorable;
+use crate::constants::SPLIT_BUFFER;
 use itertools::Itertools;
 use log::LogLevel;
 use std::cmp::Ordering;
@@ -124,10 +125,6 @@ use unwrap::unwrap;
 pub type Sections<T> = BTreeMap<Prefix<T>, (u64, BTreeSet<T>)>;
 type SectionItem<'a, T> = (Prefix<T>, (u64, &'a BTreeSet<T>));
 
-// Amount added to `min_section_size` when deciding whether a bucket split can happen. This helps
-// protect against rapid splitting and merging in the face of moderate churn.
-const SPLIT_BUFFER: usize = 1;
-
 // Immutable iterator over the entries of a `RoutingTable`.
 pub struct Iter<'a, T: 'a + Binary + Clone + Copy + Default + Hash + Xorable> {
     inner: Box<dyn Iterator<Item = &'a T> + 'a>,
@@ -821,7 +818,11 @@ impl<T: Binary + Clone + Copy + Debug + Default + Hash + Xorable> RoutingTable<T
             }
             Authority::ClientManager(ref target_name)
             | Authority::NaeManager(ref target_name)
-            | Authority::NodeManager(ref target_name) => {
+            | Authority::NodeManager(ref target_name)
+            | Authority::Group {
+                name: ref target_name,
+                ..
+            } => {
                 if let Some(group) = self.other_closest_names(target_name, self.min_section_size) {
                     return Ok(group.into_iter().cloned().collect());
                 }
@@ -886,6 +887,7 @@ impl<T: Binary + Clone + Copy + Debug + Default + Hash + Xorable> RoutingTable<T
             Authority::ClientManager(ref name)
             | Authority::NaeManager(ref name)
             | Authority::NodeManager(ref name)
+            | Authority::Group { ref name, .. }
             | Authority::Section(ref name) => self.our_prefix.matches(name),
             Authority::PrefixSection(ref prefix) => self.our_prefix.is_compatible(prefix),
         }
@@ -326,8 +326,114 @@ impl FromStr for Prefix<XorName> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::{collection, prelude::*};
+    use std::collections::BTreeSet;
     use unwrap::unwrap;
 
+    /// The maximum length of generated prefixes. Short prefixes make compatible and neighbouring
+    /// ones likely.
+    const MAX_BITS: usize = 8;
+
+    fn arb_xor_name() -> impl Strategy<Value = XorName> {
+        any::<[u8; 32]>().prop_map(XorName)
+    }
+
+    fn arb_prefix() -> impl Strategy<Value = Prefix<XorName>> {
+        (0..=MAX_BITS, arb_xor_name()).prop_map(|(bit_count, name)| Prefix::new(bit_count, name))
+    }
+
+    fn arb_versioned_prefix() -> impl Strategy<Value = VersionedPrefix<XorName>> {
+        (arb_prefix(), any::<u64>()).prop_map(|(prefix, version)| prefix.with_version(version))
+    }
+
+    // A set of disjoint prefixes covering the whole name space, created by repeatedly splitting
+    // the prefix matching a random name.
+    fn arb_partition() -> impl Strategy<Value = BTreeSet<Prefix<XorName>>> {
+        collection::vec(arb_xor_name(), 0..16).prop_map(|names| {
+            let mut prefixes: BTreeSet<_> = vec![Prefix::default()].into_iter().collect();
+            for name in names {
+                let prefix = *unwrap!(prefixes.iter().find(|prefix| prefix.matches(&name)));
+                if prefix.bit_count() < MAX_BITS {
+                    let _ = prefixes.remove(&prefix);
+                    let _ = prefixes.insert(prefix.pushed(false));
+                    let _ = prefixes.insert(prefix.pushed(true));
+                }
+            }
+            prefixes
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn covered_by_is_monotone(
+            partition in arb_partition(),
+            others in collection::vec(arb_prefix(), 0..4),
+            prefix in arb_prefix(),
+        ) {
+            prop_assert!(prefix.is_covered_by(&partition));
+            let mut extended = partition.clone();
+            extended.extend(others);
+            prop_assert!(prefix.is_covered_by(&extended));
+        }
+
+        #[test]
+        fn partition_without_any_part_is_not_covering(partition in arb_partition()) {
+            prop_assume!(partition.len() > 1);
+            for part in &partition {
+                let rest: BTreeSet<_> = partition.iter().filter(|p| *p != part).cloned().collect();
+                prop_assert!(!Prefix::default().is_covered_by(&rest));
+                prop_assert!(!part.is_covered_by(&rest));
+            }
+        }
+
+        #[test]
+        fn sibling_is_neighbour(prefix in arb_prefix()) {
+            prop_assume!(!prefix.is_empty());
+            let sibling = prefix.sibling();
+            prop_assert!(prefix.is_neighbour(&sibling));
+            prop_assert!(sibling.is_neighbour(&prefix));
+            prop_assert!(!prefix.is_compatible(&sibling));
+            prop_assert_eq!(sibling.sibling(), prefix);
+            prop_assert_eq!(sibling.popped(), prefix.popped());
+        }
+
+        #[test]
+        fn substituted_in_matches_prefix(prefix in arb_prefix(), name in arb_xor_name()) {
+            let substituted = prefix.substituted_in(name);
+            prop_assert!(prefix.matches(&substituted));
+            prop_assert!(prefix.range_inclusive().contains(&substituted));
+            if prefix.matches(&name) {
+                prop_assert_eq!(substituted, name);
+            }
+        }
+
+        #[test]
+        fn pushed_extends_and_popped_restores(prefix in arb_prefix(), bit in any::<bool>()) {
+            let child = prefix.pushed(bit);
+            prop_assert!(child.is_extension_of(&prefix));
+            prop_assert!(child.is_compatible(&prefix));
+            prop_assert!(prefix.is_compatible(&child));
+            prop_assert_eq!(child.popped(), prefix);
+            prop_assert_eq!(child.sibling(), prefix.pushed(!bit));
+        }
+
+        #[test]
+        fn ordering_is_consistent_with_equality(a in arb_prefix(), b in arb_prefix()) {
+            prop_assert_eq!(a.cmp(&b) == Ordering::Equal, a == b);
+            prop_assert_eq!(a.cmp(&b), b.cmp(&a).reverse());
+            prop_assert_eq!(a.is_compatible(&b), b.is_compatible(&a));
+            prop_assert_eq!(a.is_neighbour(&b), b.is_neighbour(&a));
+        }
+
+        #[test]
+        fn versioned_prefix_round_trips(versioned in arb_versioned_prefix()) {
+            let (prefix, version): (Prefix<XorName>, u64) = versioned.into();
+            prop_assert_eq!(prefix, *versioned.prefix());
+            prop_assert_eq!(version, versioned.version());
+            prop_assert_eq!(prefix.with_version(version), versioned);
+        }
+    }
+
     #[test]
     fn prefix() {
         assert_eq!(
@@ -0,0 +1,97 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    chain::SectionInfo, error::RoutingError, peer_map::PeerMap, quic_p2p::NodeInfo,
+    routing_table::Prefix, storage::Storage, xor_name::XorName, ConnectionInfo,
+};
+use maidsafe_utilities::serialisation;
+
+/// The storage namespace the section contacts are saved in.
+pub const CONTACTS_NAMESPACE: &str = "contacts";
+/// The key the section contacts are saved under.
+const CONTACTS_KEY: &[u8] = b"section";
+
+/// The connection info of the elders of a node's section, as last known to it.
+///
+/// A node saves them with `Node::section_contacts` before it shuts down. Passing them to
+/// `NodeBuilder::section_contacts` on restart, together with the saved `FullId`, makes the node
+/// bootstrap off its old section instead of the hard-coded contacts of the network config.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectionContacts {
+    prefix: Prefix<XorName>,
+    contacts: Vec<NodeInfo>,
+}
+
+impl SectionContacts {
+    /// Collects the connection info of the members of `sec_info` we are connected to.
+    pub(crate) fn new(sec_info: &SectionInfo, peer_map: &PeerMap) -> Self {
+        let contacts = sec_info
+            .members()
+            .iter()
+            .filter_map(|pub_id| match peer_map.get_connection_info(pub_id) {
+                Some(ConnectionInfo::Node { node_info }) => Some(node_info.clone()),
+                _ => None,
+            })
+            .collect();
+        Self {
+            prefix: *sec_info.prefix(),
+            contacts,
+        }
+    }
+
+    /// Returns the prefix of the section the contacts belong to.
+    pub fn prefix(&self) -> &Prefix<XorName> {
+        &self.prefix
+    }
+
+    /// Returns the connection info of the elders.
+    pub fn contacts(&self) -> &[NodeInfo] {
+        &self.contacts
+    }
+
+    /// Writes the contacts to `storage`, replacing any saved before.
+    pub fn save(&self, storage: &mut dyn Storage) -> Result<(), RoutingError> {
+        storage.put(
+            CONTACTS_NAMESPACE,
+            CONTACTS_KEY,
+            serialisation::serialise(self)?,
+        )
+    }
+
+    /// Reads the contacts written by `save`, or returns `None` if `storage` doesn't contain any.
+    pub fn restore(storage: &dyn Storage) -> Result<Option<Self>, RoutingError> {
+        match storage.get(CONTACTS_NAMESPACE, CONTACTS_KEY)? {
+            Some(bytes) => Ok(Some(serialisation::deserialise(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+    use unwrap::unwrap;
+
+    #[test]
+    fn save_and_restore() {
+        let mut storage = MemoryStorage::new();
+        assert_eq!(unwrap!(SectionContacts::restore(&storage)), None);
+
+        let contacts = SectionContacts {
+            prefix: Prefix::default(),
+            contacts: vec![NodeInfo {
+                peer_addr: unwrap!("127.0.0.1:5483".parse()),
+                peer_cert_der: vec![1, 2, 3],
+            }],
+        };
+        unwrap!(contacts.save(&mut storage));
+        assert_eq!(unwrap!(SectionContacts::restore(&storage)), Some(contacts));
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+#[cfg(feature = "node")]
+use crate::chain::Chain;
+use crate::{
+    error::RoutingError,
+    id::PublicId,
+    messages::{MessageContent, SignedRoutingMessage},
+    rate_limiter::RateLimits,
+    routing_table::Authority,
+};
+
+/// A token the ClientManager section of a client issues to it after it bootstrapped, granting it
+/// the limits its requests are held to.
+///
+/// The token is the section-signed message it arrived in, so any node can check it. Passing it to
+/// `Client::resume` on a later start lets the client reconnect through any proxy without asking
+/// its ClientManager for a new token, and the proxy enforces the limits the section granted. A
+/// proxy refuses tokens signed with a key its section doesn't know any more, in which case the
+/// client is issued a new one.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct SessionToken {
+    signed_msg: Box<SignedRoutingMessage>,
+}
+
+impl SessionToken {
+    pub(crate) fn new(signed_msg: SignedRoutingMessage) -> Self {
+        Self {
+            signed_msg: Box::new(signed_msg),
+        }
+    }
+
+    /// Returns the ID of the client the token was issued to.
+    pub fn client_id(&self) -> Option<&PublicId> {
+        match self.signed_msg.routing_message().dst {
+            Authority::Client { ref client_id, .. } => Some(client_id),
+            _ => None,
+        }
+    }
+
+    /// Checks that the token was signed by the ClientManager section of its client, and returns
+    /// the client's ID and the limits granted to it.
+    ///
+    /// As with `RelayedResponse::verify`, this verifies the section signature and the consistency
+    /// of the proof chain, but not that the chain leads to a key the caller trusts.
+    pub fn verify(&self) -> Result<(PublicId, RateLimits), RoutingError> {
+        self.signed_msg.check_integrity()?;
+
+        let key_info = self
+            .signed_msg
+            .source_section_key_info()
+            .ok_or(RoutingError::FailedSignature)?;
+        let routing_msg = self.signed_msg.routing_message();
+        let client_id = match routing_msg.dst {
+            Authority::Client { client_id, .. } => client_id,
+            _ => return Err(RoutingError::InvalidDestination),
+        };
+        if routing_msg.src != Authority::ClientManager(*client_id.name()) {
+            return Err(RoutingError::InvalidSource);
+        }
+        if !key_info.prefix().matches(client_id.name()) {
+            return Err(RoutingError::InvalidProvingSection);
+        }
+
+        match routing_msg.content {
+            MessageContent::SessionGranted { limits, .. } => Ok((client_id, limits)),
+            _ => Err(RoutingError::InvalidMessage),
+        }
+    }
+
+    /// Returns whether the token's proof chain includes a key of the issuing section that `chain`
+    /// knows.
+    #[cfg(feature = "node")]
+    pub(crate) fn is_trusted(&self, chain: &Chain) -> bool {
+        self.signed_msg.check_trust(chain)
+    }
+}
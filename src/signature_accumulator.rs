@@ -7,17 +7,11 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    messages::SignedRoutingMessage,
-    sha3::Digest256,
-    time::{Duration, Instant},
+    constants::ACCUMULATION_TIMEOUT, messages::SignedRoutingMessage, sha3::Digest256, time::Instant,
 };
 use itertools::Itertools;
 use std::collections::HashMap;
 
-/// Time (in seconds) within which a message and a quorum of signatures need to arrive to
-/// accumulate.
-pub const ACCUMULATION_TIMEOUT: Duration = Duration::from_secs(30);
-
 #[derive(Default)]
 pub struct SignatureAccumulator {
     msgs: HashMap<Digest256, (SignedRoutingMessage, Instant)>,
@@ -80,6 +74,7 @@ mod tests {
             DirectMessage, MessageContent, RoutingMessage, SignedDirectMessage,
             SignedRoutingMessage,
         },
+        network_params::NetworkParams,
         routing_table::{Authority, Prefix},
         types::MessageId,
         BlsPublicKeySet,
@@ -109,11 +104,13 @@ mod tests {
                 content: MessageContent::Relocate {
                     message_id: MessageId::new(),
                 },
+                dst_min_version: None,
             };
             let prefix = Prefix::new(0, *unwrap!(all_ids.iter().next()).name());
             let sec_info = unwrap!(SectionInfo::new(all_ids, prefix, None));
-            let pk_set = BlsPublicKeySet::from_section_info(sec_info.clone());
-            let key_info = SectionKeyInfo::from_section_info(&sec_info);
+            let network_params = NetworkParams::default();
+            let pk_set = BlsPublicKeySet::from_section_info(sec_info.clone(), network_params);
+            let key_info = SectionKeyInfo::from_section_info(&sec_info, network_params);
             let proof = SectionProofChain::from_genesis(key_info);
             let signed_msg = unwrap!(SignedRoutingMessage::new(
                 routing_msg.clone(),
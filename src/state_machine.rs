@@ -6,25 +6,35 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+#[cfg(feature = "client")]
+use crate::states::Client;
+#[cfg(feature = "mock_base")]
+use crate::Chain;
+use crate::{
+    action::Action, id::PublicId, network_service::NetworkBuilder, outbox::EventBox,
+    states::common::Base, states::BootstrappingPeer, timer::Timer, transport::TransportFactory,
+    NetworkConfig, NetworkEvent, NetworkService,
+};
+#[cfg(feature = "node")]
 use crate::{
-    action::Action,
-    chain::{GenesisPfxInfo, SectionInfo},
-    id::{FullId, PublicId},
-    network_service::NetworkBuilder,
-    outbox::EventBox,
-    routing_table::Prefix,
-    states::common::Base,
-    states::{Adult, BootstrappingPeer, Client, Elder, ProvingNode, RelocatingNode},
-    timer::Timer,
+    bootstrap,
+    chain::{GenesisPfxInfo, JoinerId, NetworkEpoch, PendingVote, SectionInfo},
+    error::InterfaceError,
+    id::FullId,
+    messages::AccountInfo,
+    routing_table::{Authority, Prefix},
+    section_contacts::SectionContacts,
+    states::{Adult, Elder, ProvingNode, RelocatingNode},
+    types::MessageId,
     xor_name::XorName,
-    NetworkConfig, NetworkEvent, NetworkService, MIN_SECTION_SIZE,
+    MIN_SECTION_SIZE,
 };
-#[cfg(feature = "mock_base")]
-use crate::{routing_table::Authority, Chain};
 use crossbeam_channel as mpmc;
+#[cfg(feature = "node")]
 use log::LogLevel;
+#[cfg(feature = "node")]
+use std::collections::{BTreeMap, BTreeSet};
 use std::{
-    collections::BTreeSet,
     fmt::{self, Debug, Display, Formatter},
     mem,
 };
@@ -36,10 +46,15 @@ macro_rules! state_dispatch {
     ($self:expr, $state:pat => $expr:expr, Terminated => $term_expr:expr) => {
         match $self {
             State::BootstrappingPeer($state) => $expr,
+            #[cfg(feature = "client")]
             State::Client($state) => $expr,
+            #[cfg(feature = "node")]
             State::RelocatingNode($state) => $expr,
+            #[cfg(feature = "node")]
             State::ProvingNode($state) => $expr,
+            #[cfg(feature = "node")]
             State::Adult($state) => $expr,
+            #[cfg(feature = "node")]
             State::Elder($state) => $expr,
             State::Terminated => $term_expr,
         }
@@ -51,6 +66,9 @@ pub struct StateMachine {
     state: State,
     network_rx: mpmc::Receiver<NetworkEvent>,
     action_rx: mpmc::Receiver<Action>,
+    /// Passed on to the new state when an elder is relocated and has to bootstrap again.
+    #[cfg(feature = "node")]
+    action_tx: mpmc::Sender<Action>,
     is_running: bool,
     #[cfg(feature = "mock_base")]
     events: Vec<EventType>,
@@ -60,10 +78,15 @@ pub struct StateMachine {
 #[allow(clippy::large_enum_variant)]
 pub enum State {
     BootstrappingPeer(BootstrappingPeer),
+    #[cfg(feature = "client")]
     Client(Client),
+    #[cfg(feature = "node")]
     RelocatingNode(RelocatingNode),
+    #[cfg(feature = "node")]
     ProvingNode(ProvingNode),
+    #[cfg(feature = "node")]
     Adult(Adult),
+    #[cfg(feature = "node")]
     Elder(Elder),
     Terminated,
 }
@@ -116,25 +139,12 @@ impl State {
         )
     }
 
-    pub fn close_group(&self, name: XorName, count: usize) -> Option<Vec<XorName>> {
-        state_dispatch!(
-            *self,
-            ref state => state.close_group(name, count),
-            Terminated => None
-        )
-    }
-
-    pub fn min_section_size(&self) -> usize {
+    /// Sends the messages queued while handling the last event or call, by priority.
+    fn flush_outgoing(&mut self) {
         state_dispatch!(
             *self,
-            ref state => state.min_section_size(),
-            Terminated => {
-                log_or_panic!(
-                    LogLevel::Error,
-                    "Can't get min_section_size when Terminated."
-                );
-                MIN_SECTION_SIZE
-            }
+            ref mut state => state.network_service_mut().flush_outgoing(),
+            Terminated => ()
         )
     }
 
@@ -156,6 +166,237 @@ impl State {
     }
 }
 
+// The parts of the `Node` API which are forwarded to the current state.
+#[cfg(feature = "node")]
+impl State {
+    pub fn close_group(&self, name: XorName, count: usize) -> Option<Vec<XorName>> {
+        state_dispatch!(
+            *self,
+            ref state => state.close_group(name, count),
+            Terminated => None
+        )
+    }
+
+    pub fn pending_votes(&self) -> Vec<PendingVote> {
+        state_dispatch!(
+            *self,
+            ref state => state.pending_votes(),
+            Terminated => Vec::new()
+        )
+    }
+
+    pub fn network_epoch(&self) -> Option<NetworkEpoch> {
+        state_dispatch!(
+            *self,
+            ref state => state.network_epoch(),
+            Terminated => None
+        )
+    }
+
+    pub fn peer_scores(&self) -> BTreeMap<PublicId, u64> {
+        state_dispatch!(
+            *self,
+            ref state => state.peer_scores(),
+            Terminated => BTreeMap::new()
+        )
+    }
+
+    pub fn account_info(&self, account: &XorName) -> Option<AccountInfo> {
+        state_dispatch!(
+            *self,
+            ref state => state.account_info(account),
+            Terminated => None
+        )
+    }
+
+    pub fn bootstrap_cache(&self) -> Option<&bootstrap::Cache> {
+        state_dispatch!(
+            *self,
+            ref state => Some(state.network_service().bootstrap_cache()),
+            Terminated => None
+        )
+    }
+
+    pub fn bootstrap_cache_mut(&mut self) -> Option<&mut bootstrap::Cache> {
+        state_dispatch!(
+            *self,
+            ref mut state => Some(state.network_service_mut().bootstrap_cache_mut()),
+            Terminated => None
+        )
+    }
+
+    pub fn section_contacts(&self) -> Option<SectionContacts> {
+        state_dispatch!(
+            *self,
+            ref state => state.section_contacts(),
+            Terminated => None
+        )
+    }
+
+    pub fn request_membership_statement(&mut self) -> Result<(), InterfaceError> {
+        state_dispatch!(
+            *self,
+            ref mut state => {
+                let result = state.request_membership_statement();
+                state.network_service_mut().flush_outgoing();
+                result
+            },
+            Terminated => Err(InterfaceError::InvalidState)
+        )
+    }
+
+    pub fn shutdown(&mut self) -> Result<(), InterfaceError> {
+        state_dispatch!(
+            *self,
+            ref mut state => {
+                let result = state.shutdown();
+                state.network_service_mut().flush_outgoing();
+                result
+            },
+            Terminated => Err(InterfaceError::InvalidState)
+        )
+    }
+
+    pub fn signal_upgrade(
+        &mut self,
+        version: u16,
+        activation_version: u64,
+    ) -> Result<(), InterfaceError> {
+        state_dispatch!(
+            *self,
+            ref mut state => {
+                let result = state.signal_upgrade(version, activation_version);
+                state.network_service_mut().flush_outgoing();
+                result
+            },
+            Terminated => Err(InterfaceError::InvalidState)
+        )
+    }
+
+    pub fn credit_account(
+        &mut self,
+        account: XorName,
+        amount: u64,
+        msg_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        state_dispatch!(
+            *self,
+            ref mut state => {
+                let result = state.credit_account(account, amount, msg_id);
+                state.network_service_mut().flush_outgoing();
+                result
+            },
+            Terminated => Err(InterfaceError::InvalidState)
+        )
+    }
+
+    pub fn vote_for_join_filter(
+        &mut self,
+        id: JoinerId,
+        allow: bool,
+    ) -> Result<(), InterfaceError> {
+        state_dispatch!(
+            *self,
+            ref mut state => {
+                let result = state.vote_for_join_filter(id, allow);
+                state.network_service_mut().flush_outgoing();
+                result
+            },
+            Terminated => Err(InterfaceError::InvalidState)
+        )
+    }
+
+    pub fn start_handoff(
+        &mut self,
+        range: Prefix<XorName>,
+        to: Prefix<XorName>,
+        handoff_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        state_dispatch!(
+            *self,
+            ref mut state => {
+                let result = state.start_handoff(range, to, handoff_id);
+                state.network_service_mut().flush_outgoing();
+                result
+            },
+            Terminated => Err(InterfaceError::InvalidState)
+        )
+    }
+
+    pub fn confirm_handoff(&mut self, handoff_id: MessageId) -> Result<(), InterfaceError> {
+        state_dispatch!(
+            *self,
+            ref mut state => {
+                let result = state.confirm_handoff(handoff_id);
+                state.network_service_mut().flush_outgoing();
+                result
+            },
+            Terminated => Err(InterfaceError::InvalidState)
+        )
+    }
+
+    pub fn send_data_inventory(
+        &mut self,
+        dst: XorName,
+        names: BTreeSet<XorName>,
+    ) -> Result<(), InterfaceError> {
+        state_dispatch!(
+            *self,
+            ref mut state => {
+                let result = state.send_data_inventory(dst, names);
+                state.network_service_mut().flush_outgoing();
+                result
+            },
+            Terminated => Err(InterfaceError::InvalidState)
+        )
+    }
+
+    pub fn request_republish(
+        &mut self,
+        dst: XorName,
+        names: BTreeSet<XorName>,
+    ) -> Result<(), InterfaceError> {
+        state_dispatch!(
+            *self,
+            ref mut state => {
+                let result = state.request_republish(dst, names);
+                state.network_service_mut().flush_outgoing();
+                result
+            },
+            Terminated => Err(InterfaceError::InvalidState)
+        )
+    }
+
+    pub fn report_failed_audits(
+        &mut self,
+        offenders: BTreeSet<Authority<XorName>>,
+    ) -> Result<BTreeSet<Authority<XorName>>, InterfaceError> {
+        state_dispatch!(
+            *self,
+            ref mut state => {
+                let result = state.report_failed_audits(offenders);
+                state.network_service_mut().flush_outgoing();
+                result
+            },
+            Terminated => Err(InterfaceError::InvalidState)
+        )
+    }
+
+    pub fn min_section_size(&self) -> usize {
+        state_dispatch!(
+            *self,
+            ref state => state.min_section_size(),
+            Terminated => {
+                log_or_panic!(
+                    LogLevel::Error,
+                    "Can't get min_section_size when Terminated."
+                );
+                MIN_SECTION_SIZE
+            }
+        )
+    }
+}
+
 impl Display for State {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         state_dispatch!(
@@ -247,16 +488,19 @@ pub enum Transition {
     IntoBootstrapped {
         proxy_public_id: PublicId,
     },
-    // `RelocatingNode` state transitioning back to `Bootstrapping`.
+    // `RelocatingNode` or relocated `Elder` state transitioning back to `Bootstrapping`.
+    #[cfg(feature = "node")]
     IntoBootstrapping {
         new_id: FullId,
         our_section: (Prefix<XorName>, BTreeSet<PublicId>),
     },
     // `ProvingNode` state transitioning to `Adult`.
+    #[cfg(feature = "node")]
     IntoAdult {
         gen_pfx_info: GenesisPfxInfo,
     },
     // `Adult` state transition to `Elder`.
+    #[cfg(feature = "node")]
     IntoElder {
         sec_info: SectionInfo,
         old_pfx: Prefix<XorName>,
@@ -267,9 +511,12 @@ pub enum Transition {
 impl StateMachine {
     // Construct a new StateMachine by passing a function returning the initial state.
     #[allow(clippy::new_ret_no_self)]
+    // The network service uses the transport created by `transport` if given, and quic-p2p with
+    // `network_config` otherwise.
     pub fn new<F>(
         init_state: F,
         network_config: NetworkConfig,
+        transport: Option<TransportFactory>,
         outbox: &mut dyn EventBox,
     ) -> (mpmc::Sender<Action>, Self)
     where
@@ -278,12 +525,11 @@ impl StateMachine {
         let (network_tx, network_rx) = mpmc::unbounded();
         let (action_tx, action_rx) = mpmc::unbounded();
 
-        let network_service = unwrap!(
-            NetworkBuilder::new(network_tx)
-                .with_config(network_config)
-                .build(),
-            "Unable to start network service"
-        );
+        let mut network_builder = NetworkBuilder::new(network_tx).with_config(network_config);
+        if let Some(transport) = transport {
+            network_builder = network_builder.with_transport(transport);
+        }
+        let network_service = unwrap!(network_builder.build(), "Unable to start network service");
 
         let timer = Timer::new(action_tx.clone());
         let state = init_state(action_tx.clone(), network_service, timer, outbox);
@@ -295,6 +541,8 @@ impl StateMachine {
             state: state,
             network_rx,
             action_rx,
+            #[cfg(feature = "node")]
+            action_tx: action_tx.clone(),
             is_running: is_running,
             #[cfg(feature = "mock_base")]
             events: Vec::new(),
@@ -321,25 +569,36 @@ impl StateMachine {
                 State::BootstrappingPeer(src) => src.into_target_state(proxy_public_id, outbox),
                 _ => unreachable!(),
             }),
+            #[cfg(feature = "node")]
             IntoBootstrapping {
                 new_id,
                 our_section,
-            } => self.state.replace_with::<_, ()>(|state| match state {
-                State::RelocatingNode(src) => {
-                    Ok(src.into_bootstrapping(new_id, our_section, outbox))
-                }
-                _ => unreachable!(),
-            }),
+            } => {
+                let action_tx = self.action_tx.clone();
+                self.state.replace_with::<_, ()>(|state| match state {
+                    State::RelocatingNode(src) => {
+                        Ok(src.into_bootstrapping(new_id, our_section, outbox))
+                    }
+                    State::Elder(src) => {
+                        Ok(src.into_bootstrapping(new_id, our_section, action_tx, outbox))
+                    }
+                    _ => unreachable!(),
+                })
+            }
+            #[cfg(feature = "node")]
             IntoAdult { gen_pfx_info } => self.state.replace_with(|state| match state {
                 State::ProvingNode(src) => src.into_adult(gen_pfx_info, outbox),
                 _ => unreachable!(),
             }),
+            #[cfg(feature = "node")]
             IntoElder { sec_info, old_pfx } => self.state.replace_with(|state| match state {
                 State::Adult(src) => src.into_elder(sec_info, old_pfx, outbox),
                 _ => unreachable!(),
             }),
             Terminate => self.terminate(),
         }
+
+        self.state.flush_outgoing();
     }
 
     fn terminate(&mut self) {
@@ -377,7 +636,7 @@ impl StateMachine {
     }
 }
 
-#[cfg(not(feature = "mock_base"))]
+#[cfg(all(feature = "node", not(feature = "mock_base")))]
 impl StateMachine {
     /// Query for a result, or yield: Err(NothingAvailable), Err(Disconnected) or Err(Terminated).
     pub fn try_step(&mut self, outbox: &mut dyn EventBox) -> Result<(), mpmc::TryRecvError> {
@@ -16,19 +16,22 @@ use super::{
 use crate::{
     cache::Cache,
     chain::{
-        Chain, ExpectCandidatePayload, GenesisPfxInfo, OnlinePayload, SectionInfo, SectionKeyInfo,
-        SendAckMessagePayload,
+        AccountPayload, AuditPayload, Chain, ElderChangePayload, ExpectCandidatePayload,
+        GenesisPfxInfo, NetworkEpoch, OnlinePayload, PendingVote, RefreshPayload, SectionInfo,
+        SectionKeyInfo, SendAckMessagePayload, SequencePayload,
     },
     error::RoutingError,
     event::Event,
     id::{FullId, PublicId},
-    messages::{DirectMessage, HopMessage, RoutingMessage},
+    messages::{AccountInfo, DirectMessage, HopMessage, RoutingMessage},
+    network_params::NetworkParams,
     outbox::EventBox,
     parsec::ParsecMap,
     peer_manager::PeerManager,
     peer_map::PeerMap,
     routing_message_filter::RoutingMessageFilter,
     routing_table::{Authority, Prefix},
+    section_contacts::SectionContacts,
     state_machine::{State, Transition},
     time::{Duration, Instant},
     timer::Timer,
@@ -36,6 +39,7 @@ use crate::{
     NetworkService,
 };
 use itertools::Itertools;
+use std::collections::BTreeMap;
 use std::fmt::{self, Display, Formatter};
 
 const POKE_TIMEOUT: Duration = Duration::from_secs(60);
@@ -46,7 +50,7 @@ pub struct AdultDetails {
     pub event_backlog: Vec<Event>,
     pub full_id: FullId,
     pub gen_pfx_info: GenesisPfxInfo,
-    pub min_section_size: usize,
+    pub network_params: NetworkParams,
     pub msg_backlog: Vec<RoutingMessage>,
     pub peer_map: PeerMap,
     pub peer_mgr: PeerManager,
@@ -81,7 +85,7 @@ impl Adult {
 
         let parsec_map = ParsecMap::new(details.full_id.clone(), &details.gen_pfx_info);
         let chain = Chain::new(
-            details.min_section_size,
+            details.network_params,
             public_id,
             details.gen_pfx_info.clone(),
         );
@@ -212,6 +216,30 @@ impl Base for Adult {
         self.chain.min_sec_size()
     }
 
+    fn pending_votes(&self) -> Vec<PendingVote> {
+        self.chain.pending_votes()
+    }
+
+    fn network_epoch(&self) -> Option<NetworkEpoch> {
+        Some(self.chain.network_epoch())
+    }
+
+    fn peer_scores(&self) -> BTreeMap<PublicId, u64> {
+        self.chain.peer_scores().clone()
+    }
+
+    fn account_info(&self, account: &XorName) -> Option<AccountInfo> {
+        if self.chain.our_prefix().matches(account) {
+            Some(self.chain.accounts().get(account))
+        } else {
+            None
+        }
+    }
+
+    fn section_contacts(&self) -> Option<SectionContacts> {
+        Some(SectionContacts::new(self.chain.our_info(), &self.peer_map))
+    }
+
     fn peer_map(&self) -> &PeerMap {
         &self.peer_map
     }
@@ -389,6 +417,41 @@ impl Approved for Adult {
         Ok(())
     }
 
+    fn handle_relocate_event(&mut self, _: PublicId) -> Result<(), RoutingError> {
+        Ok(())
+    }
+
+    fn handle_promote_event(&mut self, _: ElderChangePayload, _: &mut dyn EventBox) {}
+
+    fn handle_demote_event(&mut self, _: ElderChangePayload, _: &mut dyn EventBox) {}
+
+    fn handle_add_infant_event(
+        &mut self,
+        _: PublicId,
+        _: Authority<XorName>,
+        _: &mut dyn EventBox,
+    ) -> Result<(), RoutingError> {
+        Ok(())
+    }
+
+    fn handle_remove_infant_event(
+        &mut self,
+        _: PublicId,
+        _: &mut dyn EventBox,
+    ) -> Result<(), RoutingError> {
+        Ok(())
+    }
+
+    fn handle_refresh_event(&mut self, _: RefreshPayload, _: &mut dyn EventBox) {}
+
+    fn handle_upgrade_signal_event(&mut self, _: u16, _: u64) {}
+
+    fn handle_account_debit_event(&mut self, _: AccountPayload) {}
+
+    fn handle_sequence_message_event(&mut self, _: SequencePayload) {}
+
+    fn handle_failed_audits_event(&mut self, _: AuditPayload, _: &mut dyn EventBox) {}
+
     fn handle_section_info_event(
         &mut self,
         sec_info: SectionInfo,
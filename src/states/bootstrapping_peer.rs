@@ -6,12 +6,16 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+#[cfg(feature = "client")]
+use super::client::{Client, ClientDetails};
+use super::common::Base;
+#[cfg(feature = "node")]
 use super::{
-    client::{Client, ClientDetails},
-    common::Base,
     proving_node::{ProvingNode, ProvingNodeDetails},
     relocating_node::{RelocatingNode, RelocatingNodeDetails},
 };
+#[cfg(feature = "node")]
+use crate::routing_table::Prefix;
 use crate::{
     action::Action,
     cache::Cache,
@@ -19,35 +23,41 @@ use crate::{
     event::Event,
     id::{FullId, PublicId},
     messages::{DirectMessage, HopMessage, Request, UserMessage},
+    network_params::NetworkParams,
     outbox::EventBox,
     peer_map::PeerMap,
     quic_p2p::NodeInfo,
     quic_p2p::Peer,
-    routing_table::{Authority, Prefix},
+    routing_table::Authority,
     state_machine::{State, Transition},
     timer::Timer,
     xor_name::XorName,
     NetworkService,
 };
+#[cfg(feature = "client")]
+use crate::{client_config::ClientConfig, session::SessionToken};
 use crossbeam_channel as mpmc;
+#[cfg(feature = "node")]
+use std::collections::BTreeSet;
 use std::{
-    collections::BTreeSet,
     fmt::{self, Display, Formatter},
     net::SocketAddr,
-    time::Duration,
 };
 
-// Time (in seconds) after which bootstrap is cancelled (and possibly retried).
-const BOOTSTRAP_TIMEOUT: Duration = Duration::from_secs(20);
-
 // State to transition into after bootstrap process is complete.
 // FIXME - See https://maidsafe.atlassian.net/browse/MAID-2026 for info on removing this exclusion.
 #[allow(clippy::large_enum_variant)]
 pub enum TargetState {
+    #[cfg(feature = "client")]
     Client {
-        msg_expiry_dur: Duration,
+        config: ClientConfig,
+        session: Option<SessionToken>,
+        // The nodes the client can fail over to if it loses its proxy.
+        proxy_candidates: Vec<NodeInfo>,
     },
+    #[cfg(feature = "node")]
     RelocatingNode,
+    #[cfg(feature = "node")]
     ProvingNode {
         old_full_id: FullId,
         our_section: (Prefix<XorName>, BTreeSet<PublicId>),
@@ -56,12 +66,16 @@ pub enum TargetState {
 
 // State of Client or Node while bootstrapping.
 pub struct BootstrappingPeer {
+    // Only handed over to the node states, a client has no use for it.
+    #[cfg_attr(not(feature = "node"), allow(dead_code))]
     action_sender: mpmc::Sender<Action>,
     bootstrap_connection: Option<(NodeInfo, u64)>,
+    // Only handed over to the node states, a client has no use for it.
+    #[cfg_attr(not(feature = "node"), allow(dead_code))]
     cache: Box<dyn Cache>,
     network_service: NetworkService,
     full_id: FullId,
-    min_section_size: usize,
+    network_params: NetworkParams,
     peer_map: PeerMap,
     target_state: TargetState,
     timer: Timer,
@@ -74,7 +88,7 @@ impl BootstrappingPeer {
         target_state: TargetState,
         mut network_service: NetworkService,
         full_id: FullId,
-        min_section_size: usize,
+        network_params: NetworkParams,
         timer: Timer,
     ) -> Self {
         network_service.service_mut().bootstrap();
@@ -84,7 +98,7 @@ impl BootstrappingPeer {
             cache: cache,
             network_service,
             full_id,
-            min_section_size,
+            network_params,
             timer: timer,
             bootstrap_connection: None,
             peer_map: PeerMap::new(),
@@ -98,27 +112,44 @@ impl BootstrappingPeer {
         outbox: &mut dyn EventBox,
     ) -> Result<State, RoutingError> {
         match self.target_state {
-            TargetState::Client { msg_expiry_dur } => {
+            #[cfg(feature = "client")]
+            TargetState::Client {
+                config,
+                session,
+                proxy_candidates,
+            } => {
+                let proxy_addr = self
+                    .bootstrap_connection
+                    .map(|(node_info, _)| node_info.peer_addr);
+                let proxy_candidates = proxy_candidates
+                    .into_iter()
+                    .filter(|node_info| Some(node_info.peer_addr) != proxy_addr)
+                    .collect();
                 Ok(State::Client(Client::from_bootstrapping(
                     ClientDetails {
                         network_service: self.network_service,
                         full_id: self.full_id,
-                        min_section_size: self.min_section_size,
-                        msg_expiry_dur,
+                        min_section_size: self.network_params.min_section_size,
+                        msg_expiry_dur: config.msg_expiry_dur(),
+                        retry_policy: config.retry_policy().cloned(),
                         peer_map: self.peer_map,
                         proxy_pub_id,
+                        proxy_candidates,
+                        bootstrap_timeout: self.network_params.timeouts.bootstrap,
+                        session,
                         timer: self.timer,
                     },
                     outbox,
                 )))
             }
+            #[cfg(feature = "node")]
             TargetState::RelocatingNode => {
                 let details = RelocatingNodeDetails {
                     action_sender: self.action_sender,
                     cache: self.cache,
                     network_service: self.network_service,
                     full_id: self.full_id,
-                    min_section_size: self.min_section_size,
+                    network_params: self.network_params,
                     peer_map: self.peer_map,
                     proxy_pub_id,
                     timer: self.timer,
@@ -131,6 +162,7 @@ impl BootstrappingPeer {
                         err
                     })
             }
+            #[cfg(feature = "node")]
             TargetState::ProvingNode {
                 old_full_id,
                 our_section,
@@ -141,7 +173,7 @@ impl BootstrappingPeer {
                     cache: self.cache,
                     network_service: self.network_service,
                     full_id: self.full_id,
-                    min_section_size: self.min_section_size,
+                    network_params: self.network_params,
                     old_full_id,
                     our_section,
                     peer_map: self.peer_map,
@@ -157,7 +189,7 @@ impl BootstrappingPeer {
     fn send_bootstrap_request(&mut self, dst: NodeInfo) {
         debug!("{} Sending BootstrapRequest to {}.", self, dst.peer_addr);
 
-        let token = self.timer.schedule(BOOTSTRAP_TIMEOUT);
+        let token = self.timer.schedule(self.network_params.timeouts.bootstrap);
         self.bootstrap_connection = Some((dst.clone(), token));
 
         let message =
@@ -204,7 +236,7 @@ impl Base for BootstrappingPeer {
     }
 
     fn min_section_size(&self) -> usize {
-        self.min_section_size
+        self.network_params.min_section_size
     }
 
     fn peer_map(&self) -> &PeerMap {
@@ -220,6 +252,7 @@ impl Base for BootstrappingPeer {
         _: Authority<XorName>,
         _: Request,
         _: u8,
+        _: &mut dyn EventBox,
     ) -> Result<(), InterfaceError> {
         warn!(
             "{} - Cannot handle ClientSendRequest - not bootstrapped.",
@@ -275,7 +308,22 @@ impl Base for BootstrappingPeer {
             // Established connection. Pending Validity checks
             self.send_bootstrap_request(node_info);
         } else {
-            warn!("{} Received more than one BootstrappedTo event", self);
+            match self.target_state {
+                #[cfg(feature = "client")]
+                TargetState::Client {
+                    ref mut proxy_candidates,
+                    ..
+                } => {
+                    if proxy_candidates
+                        .iter()
+                        .all(|candidate| candidate.peer_addr != node_info.peer_addr)
+                    {
+                        proxy_candidates.push(node_info);
+                    }
+                }
+                #[cfg(feature = "node")]
+                _ => warn!("{} Received more than one BootstrappedTo event", self),
+            }
         }
 
         Transition::Stay
@@ -292,6 +340,7 @@ impl Base for BootstrappingPeer {
         peer_addr: SocketAddr,
         _: &mut dyn EventBox,
     ) -> Transition {
+        self.network_service_mut().peer_disconnected(peer_addr);
         let _ = self.peer_map_mut().disconnect(peer_addr);
 
         if let Some((node_info, _)) = self.bootstrap_connection.as_ref() {
@@ -378,15 +427,21 @@ mod tests {
                     action_tx,
                     Box::new(NullCache),
                     TargetState::Client {
-                        msg_expiry_dur: Duration::from_secs(60),
+                        config: ClientConfig::default(),
+                        session: None,
+                        proxy_candidates: vec![],
                     },
                     network_service,
                     client_full_id,
-                    min_section_size,
+                    NetworkParams {
+                        min_section_size,
+                        ..NetworkParams::default()
+                    },
                     timer,
                 ))
             },
             config,
+            None,
             &mut client_outbox,
         )
         .1;
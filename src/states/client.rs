@@ -8,21 +8,36 @@
 
 use super::common::{proxied, Base, Bootstrapped, BootstrappedNotEstablished};
 use crate::{
+    chain::SectionKeyInfo,
+    client_config::RetryPolicy,
+    constants::MDATA_WATCH_REFRESH_INTERVAL,
     error::{InterfaceError, RoutingError},
     event::Event,
     id::{FullId, PublicId},
-    messages::{DirectMessage, HopMessage, MessageContent, Request, RoutingMessage, UserMessage},
+    messages::{
+        DirectMessage, HopMessage, MessageContent, Request, Response, RoutingMessage,
+        SealedRequest, SignedRoutingMessage, UserMessage, CLIENT_GET_PRIORITY,
+    },
     outbox::EventBox,
     peer_map::PeerMap,
+    quic_p2p::{NodeInfo, Peer},
+    request_retry::{RequestRetries, RetryAction},
     routing_message_filter::RoutingMessageFilter,
-    routing_table::Authority,
+    routing_table::{Authority, Prefix},
+    session::SessionToken,
     state_machine::Transition,
     time::{Duration, Instant},
     timer::Timer,
+    types::MessageId,
     xor_name::XorName,
     NetworkService,
 };
-use std::fmt::{self, Display, Formatter};
+use lru_time_cache::LruCache;
+use maidsafe_utilities::serialisation;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display, Formatter},
+};
 
 pub struct ClientDetails {
     pub network_service: NetworkService,
@@ -31,48 +46,158 @@ pub struct ClientDetails {
     pub msg_expiry_dur: Duration,
     pub peer_map: PeerMap,
     pub proxy_pub_id: PublicId,
+    pub proxy_candidates: Vec<NodeInfo>,
+    pub bootstrap_timeout: Duration,
+    pub retry_policy: Option<RetryPolicy>,
+    pub session: Option<SessionToken>,
     pub timer: Timer,
 }
 
 /// A node connecting a user to the network, as opposed to a routing / data storage node.
 ///
-/// Each client has a _proxy_: a node through which all requests are routed.
+/// Each client has a _proxy_: a node through which all requests are routed. If it loses its proxy,
+/// it bootstraps off the next of the other nodes it knew when it connected, registers with it as
+/// before and resends the requests which weren't answered yet.
 pub struct Client {
     network_service: NetworkService,
     full_id: FullId,
     min_section_size: usize,
     peer_map: PeerMap,
     proxy_pub_id: PublicId,
+    /// The nodes to fail over to if we lose our proxy, in the order they are tried.
+    proxy_candidates: Vec<NodeInfo>,
+    /// The candidate we are failing over to, and the token of the timeout of the attempt.
+    failover: Option<(NodeInfo, u64)>,
+    /// How long a proxy candidate has to accept us.
+    bootstrap_timeout: Duration,
+    /// Our session token, which we resume our session with after a failover.
+    session: Option<SessionToken>,
     routing_msg_filter: RoutingMessageFilter,
     timer: Timer,
     msg_expiry_dur: Duration,
+    /// The latest key of the section managing our account, proven by its messages to us.
+    manager_key: Option<SectionKeyInfo>,
+    /// The latest keys of the sections we received messages from, which requests carrying data are
+    /// sealed to.
+    section_keys: BTreeMap<Prefix<XorName>, SectionKeyInfo>,
+    /// The sealed requests not answered yet, which are sealed again if the key of their
+    /// destination changes. Requests to a section whose key we don't know yet are held here until
+    /// it proves its key.
+    sealed_requests: LruCache<MessageId, (Authority<XorName>, Request, u8)>,
+    /// The requests not answered yet which are safe to send again, which are resent through our
+    /// new proxy after a failover.
+    in_flight_requests: LruCache<MessageId, (Authority<XorName>, Request, u8)>,
+    /// The names and tags of the `MutableData` we watch.
+    watched_mdata: BTreeSet<(XorName, u64)>,
+    watch_refresh_token: u64,
+    /// The requests awaiting a response, if they are retried.
+    retries: Option<RequestRetries>,
 }
 
 impl Client {
     pub fn from_bootstrapping(details: ClientDetails, outbox: &mut dyn EventBox) -> Self {
-        let client = Client {
+        let watch_refresh_token = details.timer.schedule(MDATA_WATCH_REFRESH_INTERVAL);
+        let mut client = Client {
             network_service: details.network_service,
             full_id: details.full_id,
             min_section_size: details.min_section_size,
             peer_map: details.peer_map,
             proxy_pub_id: details.proxy_pub_id,
+            proxy_candidates: details.proxy_candidates,
+            failover: None,
+            bootstrap_timeout: details.bootstrap_timeout,
+            session: details.session,
             routing_msg_filter: RoutingMessageFilter::new(),
             timer: details.timer,
             msg_expiry_dur: details.msg_expiry_dur,
+            manager_key: None,
+            section_keys: BTreeMap::new(),
+            sealed_requests: LruCache::with_expiry_duration(details.msg_expiry_dur),
+            in_flight_requests: LruCache::with_expiry_duration(details.msg_expiry_dur),
+            watched_mdata: BTreeSet::new(),
+            watch_refresh_token,
+            retries: details.retry_policy.map(RequestRetries::new),
         };
 
         debug!("{} State changed to Client.", client);
 
         outbox.send_event(Event::Connected);
+        client.start_session();
         client
     }
 
+    // Resumes our session through our proxy, or asks for a new one if we don't have any yet.
+    fn start_session(&mut self) {
+        match self.session.clone() {
+            Some(token) => {
+                let proxy_pub_id = self.proxy_pub_id;
+                self.send_direct_message(&proxy_pub_id, DirectMessage::ResumeSession(token));
+            }
+            None => self.request_session(),
+        }
+    }
+
+    // Asks the section managing our account for a session token.
+    fn request_session(&mut self) {
+        let src = Authority::Client {
+            client_id: *self.full_id.public_id(),
+            proxy_node_name: *self.proxy_pub_id.name(),
+        };
+        let dst = Authority::ClientManager(*self.name());
+        let content = MessageContent::SessionRequest {
+            message_id: MessageId::new(),
+        };
+        if let Err(error) = self.send_routing_message(src, dst, content) {
+            debug!("{} Failed to request a session: {:?}", self, error);
+        }
+    }
+
+    fn handle_session_granted(&mut self, token: SessionToken, outbox: &mut dyn EventBox) {
+        match token.verify() {
+            Ok((client_id, _)) if client_id == *self.full_id.public_id() => {
+                debug!("{} Our client manager granted us a session.", self);
+                self.session = Some(token.clone());
+                outbox.send_event(Event::SessionStarted(token));
+            }
+            Ok(_) => debug!("{} Ignoring session token of another client.", self),
+            Err(error) => debug!("{} Invalid session token: {:?}", self, error),
+        }
+    }
+
     fn dispatch_routing_message(
         &mut self,
         routing_msg: RoutingMessage,
         outbox: &mut dyn EventBox,
     ) -> Transition {
+        if let MessageContent::UserMessage {
+            content: UserMessage::Response(ref response),
+            ..
+        } = routing_msg.content
+        {
+            let msg_id = *response.message_id();
+            let _ = self.sealed_requests.remove(&msg_id);
+            let _ = self.in_flight_requests.remove(&msg_id);
+            if !self.handle_response_retries(&msg_id) {
+                trace!("{} Dropping duplicate response {:?}.", self, msg_id);
+                return Transition::Stay;
+            }
+        }
+
         match routing_msg.content {
+            MessageContent::UserMessage {
+                content:
+                    UserMessage::Response(Response::MDataChanged {
+                        name, tag, actions, ..
+                    }),
+                ..
+            } => {
+                if self.watched_mdata.contains(&(name, tag)) {
+                    outbox.send_event(Event::MDataChanged { name, tag, actions });
+                } else {
+                    trace!("{} Got change of unwatched MData {:?}.", self, name);
+                }
+                Transition::Stay
+            }
             MessageContent::UserMessage { content, .. } => {
                 trace!(
                     "{} Got UserMessage {} from {:?} to {:?}.",
@@ -84,6 +209,14 @@ impl Client {
                 outbox.send_event(content.into_event(routing_msg.src, routing_msg.dst));
                 Transition::Stay
             }
+            MessageContent::SectionKeyResponse { .. } => {
+                // The key it carries was learned already, and the held requests sent.
+                Transition::Stay
+            }
+            MessageContent::DeliveryAck { msg_id } if routing_msg.src.is_multiple() => {
+                outbox.send_event(Event::MessageDelivered { msg_id });
+                Transition::Stay
+            }
             content => {
                 debug!(
                     "{} Unhandled routing message: {:?} from {:?} to {:?}",
@@ -94,6 +227,375 @@ impl Client {
         }
     }
 
+    // If `signed_msg` comes from the section managing our account and proves a newer key than the
+    // one we know, remembers it and notifies the user. The proof chain must include the key we
+    // know, so that a forged or unrelated chain can't replace our trust anchor. The first key we
+    // see is trusted as it was relayed by our proxy.
+    fn update_manager_key(&mut self, signed_msg: &SignedRoutingMessage, outbox: &mut dyn EventBox) {
+        let routing_msg = signed_msg.routing_message();
+        match routing_msg.src {
+            Authority::ClientManager(_) if self.in_authority(&routing_msg.dst) => (),
+            _ => return,
+        }
+        let proof_chain = match signed_msg.proof_chain() {
+            Some(proof_chain) => proof_chain,
+            None => return,
+        };
+        if let Err(error) = signed_msg.check_integrity() {
+            debug!(
+                "{} - Invalid message from our client manager: {:?}",
+                self, error
+            );
+            return;
+        }
+
+        let key_info = proof_chain.last_public_key_info();
+        if let Some(ref known) = self.manager_key {
+            if key_info.version() <= known.version() {
+                return;
+            }
+            if !proof_chain.all_key_infos().any(|info| info == known) {
+                debug!(
+                    "{} - Can't verify manager key {:?}: proof doesn't include {:?}.",
+                    self, key_info, known
+                );
+                return;
+            }
+        } else {
+            self.manager_key = Some(key_info.clone());
+            return;
+        }
+
+        let key = match serialisation::serialise(key_info.key()) {
+            Ok(bytes) => safe_crypto::hash(&bytes),
+            Err(error) => {
+                debug!("{} - Failed to serialise manager key: {:?}", self, error);
+                return;
+            }
+        };
+        debug!(
+            "{} - Our client manager's key changed: {:?}",
+            self, key_info
+        );
+        outbox.send_event(Event::ManagerSectionChanged {
+            prefix: *key_info.prefix(),
+            version: *key_info.version(),
+            key,
+        });
+        self.manager_key = Some(key_info.clone());
+    }
+
+    // Remembers the latest key of the section `signed_msg` comes from, so that requests to it can
+    // be sealed to that key. The proof chain must include a key we already trust, so that a relay
+    // can't make us seal requests to a key of its own. Returns the key if it is newer than any we
+    // knew for that part of the network.
+    fn update_section_keys(&mut self, signed_msg: &SignedRoutingMessage) -> Option<SectionKeyInfo> {
+        let proof_chain = signed_msg.proof_chain()?;
+        let key_info = proof_chain.last_public_key_info();
+        if !self.is_new_section_key(key_info) {
+            return None;
+        }
+        if !proof_chain
+            .all_key_infos()
+            .any(|info| self.is_trusted_section_key(info))
+        {
+            debug!(
+                "{} - Can't verify section key {:?}: proof doesn't include a key we trust.",
+                self, key_info
+            );
+            return None;
+        }
+        if signed_msg.check_integrity().is_err() {
+            return None;
+        }
+
+        let key_info = key_info.clone();
+        self.insert_section_key(key_info.clone());
+        Some(key_info)
+    }
+
+    // Returns the key `signed_msg` carries if it is a `SectionKeyResponse` from the section managing
+    // our account, proven by the manager key we trust, and newer than any we knew for that part of
+    // the network.
+    fn vouched_section_key(&mut self, signed_msg: &SignedRoutingMessage) -> Option<SectionKeyInfo> {
+        let routing_msg = signed_msg.routing_message();
+        let key_info = match (&routing_msg.content, routing_msg.src) {
+            (MessageContent::SectionKeyResponse { key_info, .. }, Authority::ClientManager(_))
+                if self.in_authority(&routing_msg.dst) =>
+            {
+                key_info
+            }
+            _ => return None,
+        };
+        if !self.is_new_section_key(key_info) {
+            return None;
+        }
+        let manager_key = self.manager_key.as_ref()?;
+        if !signed_msg
+            .proof_chain()?
+            .all_key_infos()
+            .any(|info| info == manager_key)
+        {
+            debug!(
+                "{} - Can't verify section key {:?}: not vouched for by our client manager.",
+                self, key_info
+            );
+            return None;
+        }
+        if signed_msg.check_integrity().is_err() {
+            return None;
+        }
+
+        let key_info = key_info.clone();
+        self.insert_section_key(key_info.clone());
+        Some(key_info)
+    }
+
+    fn is_new_section_key(&self, key_info: &SectionKeyInfo) -> bool {
+        !self.section_keys.iter().any(|(prefix, known)| {
+            prefix.is_compatible(key_info.prefix()) && known.version() >= key_info.version()
+        })
+    }
+
+    // Returns whether `key_info` is the key of our client manager, or the latest one we learned of
+    // its section.
+    fn is_trusted_section_key(&self, key_info: &SectionKeyInfo) -> bool {
+        self.manager_key.as_ref() == Some(key_info)
+            || self.section_keys.get(key_info.prefix()) == Some(key_info)
+    }
+
+    fn insert_section_key(&mut self, key_info: SectionKeyInfo) {
+        self.section_keys
+            .retain(|prefix, _| !prefix.is_compatible(key_info.prefix()));
+        let _ = self.section_keys.insert(*key_info.prefix(), key_info);
+    }
+
+    // Seals the unanswered requests to the section with the new key `key_info` again, as its new
+    // members couldn't open them.
+    fn reseal_requests(&mut self, key_info: &SectionKeyInfo) {
+        let resealed: Vec<_> = self
+            .sealed_requests
+            .peek_iter()
+            .filter(|(_, (dst, _, _))| key_info.prefix().matches(&dst.name()))
+            .map(|(_, request)| request.clone())
+            .collect();
+        for (dst, request, priority) in resealed {
+            let msg_id = *request.message_id();
+            debug!("{} Sealing request {:?} to {:?}.", self, msg_id, key_info);
+            if let Err(error) = self.send_request(dst, request, priority) {
+                debug!(
+                    "{} Failed to reseal request {:?}: {:?}",
+                    self, msg_id, error
+                );
+            }
+        }
+    }
+
+    // Bootstraps off the next proxy candidate. Returns `false` if there are none left.
+    fn fail_over(&mut self) -> bool {
+        if self.proxy_candidates.is_empty() {
+            return false;
+        }
+        let node_info = self.proxy_candidates.remove(0);
+        debug!(
+            "{} Failing over to the proxy candidate at {}.",
+            self, node_info.peer_addr
+        );
+
+        let message = match self.to_signed_direct_message(DirectMessage::BootstrapRequest) {
+            Ok(message) => message,
+            Err(error) => {
+                debug!("{} Failed to sign BootstrapRequest: {:?}", self, error);
+                return false;
+            }
+        };
+        let token = self.timer.schedule(self.bootstrap_timeout);
+        self.failover = Some((node_info.clone(), token));
+        self.send_message_to_initial_targets(vec![Peer::Node { node_info }], 1, message);
+        true
+    }
+
+    // Drops the candidate we were failing over to, and tries the next one. Terminates if there are
+    // none left.
+    fn fail_over_to_next(&mut self, outbox: &mut dyn EventBox) -> Transition {
+        if let Some((node_info, _)) = self.failover.take() {
+            self.network_service
+                .service_mut()
+                .disconnect_from(node_info.peer_addr);
+        }
+        if self.fail_over() {
+            Transition::Stay
+        } else {
+            info!("{} No proxy candidates left. Terminating.", self);
+            outbox.send_event(Event::Terminated);
+            Transition::Terminate
+        }
+    }
+
+    // Returns whether `pub_id` is the candidate we are failing over to.
+    fn is_failover_candidate(&self, pub_id: &PublicId) -> bool {
+        match (&self.failover, self.peer_map.get_connection_info(pub_id)) {
+            (Some((node_info, _)), Some(conn_info)) => node_info.peer_addr == conn_info.peer_addr(),
+            _ => false,
+        }
+    }
+
+    // Makes the candidate which accepted us our proxy, registers with it and resends the requests
+    // our old proxy may have lost.
+    fn handle_failed_over(&mut self, proxy_pub_id: PublicId, outbox: &mut dyn EventBox) {
+        debug!("{} Failed over to proxy {}.", self, proxy_pub_id);
+        self.failover = None;
+        self.proxy_pub_id = proxy_pub_id;
+        outbox.send_event(Event::Connected);
+        self.start_session();
+
+        let requests: Vec<_> = self
+            .in_flight_requests
+            .peek_iter()
+            .map(|(_, request)| request.clone())
+            .collect();
+        for (dst, request, priority) in requests {
+            let msg_id = *request.message_id();
+            debug!("{} Resending request {:?}.", self, msg_id);
+            if let Err(error) = self.send_request(dst, request, priority) {
+                debug!(
+                    "{} Failed to resend request {:?}: {:?}",
+                    self, msg_id, error
+                );
+            }
+        }
+    }
+
+    // Returns whether `request` may be sent again without the risk of it being applied twice.
+    fn is_safe_to_resend(&self, request: &Request) -> bool {
+        request.is_get()
+            || self
+                .retries
+                .as_ref()
+                .map_or(false, |retries| retries.policy().retry_mutations)
+    }
+
+    // Renews our watches, so that the members of the managing sections which joined since the
+    // last renewal know about them too.
+    fn refresh_mdata_watches(&mut self) {
+        let watched: Vec<_> = self.watched_mdata.iter().cloned().collect();
+        for (name, tag) in watched {
+            let request = Request::WatchMData {
+                name,
+                tag,
+                msg_id: MessageId::new(),
+            };
+            if let Err(error) =
+                self.send_request(Authority::NaeManager(name), request, CLIENT_GET_PRIORITY)
+            {
+                debug!("{} Failed to renew watch of {:?}: {:?}", self, name, error);
+            }
+        }
+    }
+
+    // Returns whether to deliver the response with the given message ID, i.e. it isn't a
+    // duplicate answer to a retried request.
+    fn handle_response_retries(&mut self, msg_id: &MessageId) -> bool {
+        self.retries
+            .as_mut()
+            .map_or(true, |retries| retries.handle_response(msg_id))
+    }
+
+    fn handle_retry_action(&mut self, action: Option<RetryAction>, outbox: &mut dyn EventBox) {
+        match action {
+            Some(RetryAction::Resend {
+                dst,
+                request,
+                priority,
+            }) => {
+                let msg_id = *request.message_id();
+                debug!("{} Retrying request {:?}.", self, msg_id);
+                if let Err(error) = self.send_request(dst, request, priority) {
+                    debug!("{} Failed to retry request {:?}: {:?}", self, msg_id, error);
+                    let timer = &self.timer;
+                    let action = self
+                        .retries
+                        .as_mut()
+                        .and_then(|retries| retries.handle_send_failure(&msg_id, timer));
+                    self.handle_retry_action(action, outbox);
+                }
+            }
+            Some(RetryAction::Fail { dst, msg_id }) => {
+                debug!("{} Giving up on request {:?}.", self, msg_id);
+                outbox.send_event(Event::RequestFailed { dst, msg_id });
+            }
+            None => (),
+        }
+    }
+
+    fn send_request(
+        &mut self,
+        dst: Authority<XorName>,
+        content: Request,
+        priority: u8,
+    ) -> Result<(), RoutingError> {
+        let src = Authority::Client {
+            client_id: *self.full_id.public_id(),
+            proxy_node_name: *self.proxy_pub_id.name(),
+        };
+        match self.seal_request(dst, content, priority)? {
+            Some(content) => {
+                self.send_user_message(src, dst, UserMessage::Request(content), priority)
+            }
+            None => self.request_section_key(src, dst),
+        }
+    }
+
+    // Seals requests carrying data to the latest key we know of the section managing `dst`. If we
+    // don't know any yet, holds the request back and returns `None`: it is sent once the section
+    // proved its key.
+    fn seal_request(
+        &mut self,
+        dst: Authority<XorName>,
+        request: Request,
+        priority: u8,
+    ) -> Result<Option<Request>, RoutingError> {
+        if !SealedRequest::should_seal(&request) {
+            return Ok(Some(request));
+        }
+        let sealed = match self
+            .section_keys
+            .iter()
+            .find(|(prefix, _)| prefix.matches(&dst.name()))
+        {
+            Some((_, key_info)) => Some(SealedRequest::seal(&request, key_info)?),
+            None => None,
+        };
+        let _ = self
+            .sealed_requests
+            .insert(*request.message_id(), (dst, request, priority));
+        Ok(sealed)
+    }
+
+    // Asks the section managing our account for the key of the section managing `dst`, so the
+    // requests held for it can be sealed.
+    fn request_section_key(
+        &mut self,
+        src: Authority<XorName>,
+        dst: Authority<XorName>,
+    ) -> Result<(), RoutingError> {
+        debug!(
+            "{} Don't know the key of {:?} yet. Requesting it.",
+            self, dst
+        );
+        let content = MessageContent::SectionKeyRequest {
+            name: dst.name(),
+            message_id: MessageId::new(),
+        };
+        let msg_expiry_dur = self.msg_expiry_dur;
+        self.send_routing_message_with_expiry(
+            src,
+            Authority::ClientManager(*self.name()),
+            content,
+            Some(Instant::now() + msg_expiry_dur),
+        )
+    }
+
     /// Sends the given message, possibly splitting it up into smaller parts.
     fn send_user_message(
         &mut self,
@@ -106,7 +608,11 @@ impl Client {
         self.send_routing_message_with_expiry(
             src,
             dst,
-            MessageContent::UserMessage { content, priority },
+            MessageContent::UserMessage {
+                content,
+                priority,
+                seq: None,
+            },
             Some(Instant::now() + msg_expiry_dur),
         )
     }
@@ -151,30 +657,85 @@ impl Base for Client {
         dst: Authority<XorName>,
         content: Request,
         priority: u8,
+        outbox: &mut dyn EventBox,
     ) -> Result<(), InterfaceError> {
-        let src = Authority::Client {
-            client_id: *self.full_id.public_id(),
-            proxy_node_name: *self.proxy_pub_id.name(),
+        if self.network_service.is_congested() {
+            debug!("{} Refusing {:?}: outgoing queue is full.", self, content);
+            return Err(InterfaceError::Busy);
+        }
+        match content {
+            Request::WatchMData { name, tag, .. } => {
+                let _ = self.watched_mdata.insert((name, tag));
+            }
+            Request::UnwatchMData { name, tag, .. } => {
+                let _ = self.watched_mdata.remove(&(name, tag));
+            }
+            _ => (),
+        }
+        let msg_id = *content.message_id();
+        let retried = self.retries.as_ref().map(|_| content.clone());
+        let resent = if self.is_safe_to_resend(&content) {
+            Some(content.clone())
+        } else {
+            None
         };
-        let user_msg = UserMessage::Request(content);
 
-        match self.send_user_message(src, dst, user_msg, priority) {
-            Err(RoutingError::Interface(err)) => Err(err),
-            Err(_) | Ok(_) => Ok(()),
+        let result = self.send_request(dst, content, priority);
+        if let Err(RoutingError::Interface(err)) = result {
+            return Err(err);
+        }
+
+        if let (Some(retries), Some(request)) = (self.retries.as_mut(), retried) {
+            retries.insert(dst, request, priority, &self.timer);
         }
+        if let Some(request) = resent {
+            let _ = self
+                .in_flight_requests
+                .insert(msg_id, (dst, request, priority));
+        }
+        if let Err(error) = result {
+            debug!("{} Failed to send request {:?}: {:?}", self, msg_id, error);
+            let timer = &self.timer;
+            let action = self
+                .retries
+                .as_mut()
+                .and_then(|retries| retries.handle_send_failure(&msg_id, timer));
+            self.handle_retry_action(action, outbox);
+        }
+        Ok(())
     }
 
-    fn handle_timeout(&mut self, _token: u64, _: &mut dyn EventBox) -> Transition {
+    fn handle_timeout(&mut self, token: u64, outbox: &mut dyn EventBox) -> Transition {
+        if token == self.watch_refresh_token {
+            self.refresh_mdata_watches();
+            self.watch_refresh_token = self.timer.schedule(MDATA_WATCH_REFRESH_INTERVAL);
+        } else if self
+            .failover
+            .as_ref()
+            .map_or(false, |(_, failover_token)| *failover_token == token)
+        {
+            debug!("{} Timeout when trying to fail over.", self);
+            return self.fail_over_to_next(outbox);
+        } else {
+            let timer = &self.timer;
+            let action = self
+                .retries
+                .as_mut()
+                .and_then(|retries| retries.handle_timeout(token, timer));
+            self.handle_retry_action(action, outbox);
+        }
         Transition::Stay
     }
 
     fn handle_peer_lost(&mut self, pub_id: PublicId, outbox: &mut dyn EventBox) -> Transition {
         debug!("{} - Lost peer {:?}", self, pub_id);
 
-        if self.proxy_pub_id == pub_id {
+        if self.proxy_pub_id == pub_id && self.failover.is_none() {
             debug!("{} - Lost bootstrap connection to {}.", self, pub_id);
-            outbox.send_event(Event::Terminated);
-            Transition::Terminate
+            self.fail_over_to_next(outbox)
+        } else if self.is_failover_candidate(&pub_id) {
+            debug!("{} - Lost connection to proxy candidate {}.", self, pub_id);
+            self.fail_over_to_next(outbox)
         } else {
             Transition::Stay
         }
@@ -183,10 +744,29 @@ impl Base for Client {
     fn handle_direct_message(
         &mut self,
         msg: DirectMessage,
-        _: PublicId,
-        _: &mut dyn EventBox,
+        pub_id: PublicId,
+        outbox: &mut dyn EventBox,
     ) -> Result<Transition, RoutingError> {
-        debug!("{} Unhandled direct message: {:?}", self, msg);
+        match msg {
+            DirectMessage::BootstrapResponse(Ok(())) if self.is_failover_candidate(&pub_id) => {
+                self.handle_failed_over(pub_id, outbox);
+            }
+            DirectMessage::BootstrapResponse(Err(error)) if self.is_failover_candidate(&pub_id) => {
+                info!("{} Proxy candidate {} refused us: {}", self, pub_id, error);
+                return Ok(self.fail_over_to_next(outbox));
+            }
+            DirectMessage::ResumeSessionResponse(true) if pub_id == self.proxy_pub_id => {
+                debug!("{} Resumed our session.", self);
+            }
+            DirectMessage::ResumeSessionResponse(false) if pub_id == self.proxy_pub_id => {
+                debug!(
+                    "{} Our session token was refused. Requesting a new one.",
+                    self
+                );
+                self.request_session();
+            }
+            msg => debug!("{} Unhandled direct message: {:?}", self, msg),
+        }
         Ok(Transition::Stay)
     }
 
@@ -195,10 +775,24 @@ impl Base for Client {
         msg: HopMessage,
         outbox: &mut dyn EventBox,
     ) -> Result<Transition, RoutingError> {
-        if let Some(routing_msg) = self.filter_hop_message(msg)? {
-            Ok(self.dispatch_routing_message(routing_msg, outbox))
-        } else {
-            Ok(Transition::Stay)
+        self.update_manager_key(&msg.content, outbox);
+        let new_key = self
+            .vouched_section_key(&msg.content)
+            .or_else(|| self.update_section_keys(&msg.content));
+        if let Some(key_info) = new_key {
+            self.reseal_requests(&key_info);
+        }
+        let session_token = match msg.content.routing_message().content {
+            MessageContent::SessionGranted { .. } => Some(SessionToken::new(msg.content.clone())),
+            _ => None,
+        };
+        match (self.filter_hop_message(msg)?, session_token) {
+            (Some(_), Some(token)) => {
+                self.handle_session_granted(token, outbox);
+                Ok(Transition::Stay)
+            }
+            (Some(routing_msg), None) => Ok(self.dispatch_routing_message(routing_msg, outbox)),
+            (None, _) => Ok(Transition::Stay),
         }
     }
 }
@@ -216,6 +810,21 @@ impl Bootstrapped for Client {
         &mut self.routing_msg_filter
     }
 
+    fn filter_outgoing_routing_msg(&mut self, msg: &RoutingMessage, pub_id: &PublicId) -> bool {
+        // A retry is identical to the original request, but mustn't be deduplicated.
+        let is_retry = match (&msg.content, &self.retries) {
+            (
+                MessageContent::UserMessage {
+                    content: UserMessage::Request(ref request),
+                    ..
+                },
+                Some(ref retries),
+            ) => retries.is_retrying(request.message_id()),
+            _ => false,
+        };
+        self.routing_msg_filter.filter_outgoing(msg, pub_id) && !is_retry
+    }
+
     fn timer(&mut self) -> &mut Timer {
         &mut self.timer
     }
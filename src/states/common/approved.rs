@@ -9,10 +9,13 @@
 use super::Relocated;
 use crate::{
     chain::{
-        Chain, ExpectCandidatePayload, NetworkEvent, OnlinePayload, Proof, ProofSet, SectionInfo,
-        SectionKeyInfo, SendAckMessagePayload,
+        AccountPayload, AuditPayload, Chain, ElderChangePayload, ExpectCandidatePayload,
+        Misbehaviour, NeighbourChange, NetworkEvent, OnlinePayload, Proof, ProofSet,
+        RefreshPayload, SectionInfo, SectionKeyInfo, SendAckMessagePayload, SequencePayload,
     },
+    crash_dump,
     error::RoutingError,
+    event::Event,
     id::PublicId,
     outbox::EventBox,
     parsec::{self, Block, Observation, ParsecMap},
@@ -89,6 +92,45 @@ pub trait Approved: Relocated {
     fn handle_purge_candidate_event(&mut self, old_public_id: PublicId)
         -> Result<(), RoutingError>;
 
+    /// Handles an accumulated `Relocate` event.
+    fn handle_relocate_event(&mut self, pub_id: PublicId) -> Result<(), RoutingError>;
+
+    /// Handles an accumulated `Promote` event.
+    fn handle_promote_event(&mut self, payload: ElderChangePayload, outbox: &mut dyn EventBox);
+
+    /// Handles an accumulated `Demote` event.
+    fn handle_demote_event(&mut self, payload: ElderChangePayload, outbox: &mut dyn EventBox);
+
+    /// Handles an accumulated `AddInfant` event.
+    fn handle_add_infant_event(
+        &mut self,
+        new_pub_id: PublicId,
+        client_auth: Authority<XorName>,
+        outbox: &mut dyn EventBox,
+    ) -> Result<(), RoutingError>;
+
+    /// Handles an accumulated `RemoveInfant` event.
+    fn handle_remove_infant_event(
+        &mut self,
+        pub_id: PublicId,
+        outbox: &mut dyn EventBox,
+    ) -> Result<(), RoutingError>;
+
+    /// Handles an accumulated `Refresh` event.
+    fn handle_refresh_event(&mut self, payload: RefreshPayload, outbox: &mut dyn EventBox);
+
+    /// Handles an accumulated `UpgradeSignal` event.
+    fn handle_upgrade_signal_event(&mut self, version: u16, activation_version: u64);
+
+    /// Handles an accumulated `AccountDebit` event.
+    fn handle_account_debit_event(&mut self, payload: AccountPayload);
+
+    /// Handles an accumulated `SequenceMessage` event.
+    fn handle_sequence_message_event(&mut self, payload: SequencePayload);
+
+    /// Handles an accumulated `FailedAudits` event.
+    fn handle_failed_audits_event(&mut self, payload: AuditPayload, outbox: &mut dyn EventBox);
+
     fn handle_parsec_request(
         &mut self,
         msg_version: u64,
@@ -168,7 +210,20 @@ pub trait Approved: Relocated {
                             proof.pub_id(),
                             event
                         );
-                        self.chain_mut().handle_opaque_event(event, proof)?;
+                        if proof.validate_signature(block.payload()) {
+                            self.chain_mut().handle_opaque_event(event, proof)?;
+                        } else {
+                            warn!(
+                                "{} Invalid signature by {} for {:?}",
+                                self,
+                                proof.pub_id(),
+                                event
+                            );
+                            self.chain_mut().record_misbehaviour(
+                                *proof.pub_id(),
+                                Misbehaviour::InvalidSignature,
+                            );
+                        }
                     }
                 }
                 Observation::Add {
@@ -212,42 +267,23 @@ pub trait Approved: Relocated {
     fn chain_poll(&mut self, outbox: &mut dyn EventBox) -> Result<Transition, RoutingError> {
         let mut our_pfx = *self.chain_mut().our_prefix();
         while let Some(event) = self.chain_mut().poll()? {
-            trace!("{} Handle accumulated event: {:?}", self, event);
+            let section_size = self.chain_mut().our_info().members().len();
+            let prefix_bit_count = self.chain_mut().our_prefix().bit_count();
+            let metrics = self.network_service().metrics();
+            metrics.inc_accumulated_events();
+            metrics.set_section(section_size, prefix_bit_count);
+            if crash_dump::is_enabled() {
+                crash_dump::record_log(format!("{} Handle accumulated event: {:?}", self, event));
+                crash_dump::record_chain(self.chain_mut());
+            }
 
-            match event {
-                NetworkEvent::AddElder(pub_id, client_auth) => {
-                    self.handle_add_elder_event(pub_id, client_auth, outbox)?;
-                }
-                NetworkEvent::RemoveElder(pub_id) => {
-                    self.handle_remove_elder_event(pub_id, outbox)?;
-                }
-                NetworkEvent::Online(info) => {
-                    self.handle_online_event(info)?;
-                }
-                NetworkEvent::Offline(pub_id) => {
-                    self.handle_offline_event(pub_id)?;
-                }
-                NetworkEvent::OurMerge => self.handle_our_merge_event()?,
-                NetworkEvent::NeighbourMerge(_) => self.handle_neighbour_merge_event()?,
-                NetworkEvent::SectionInfo(sec_info) => {
-                    match self.handle_section_info_event(sec_info, our_pfx, outbox)? {
-                        Transition::Stay => (),
-                        transition => return Ok(transition),
-                    }
-                }
-                NetworkEvent::TheirKeyInfo(key_info) => {
-                    self.handle_their_key_info_event(key_info)?
-                }
-                NetworkEvent::AckMessage(_payload) => {
-                    // Update their_knowledge is handled within the chain.
-                }
-                NetworkEvent::SendAckMessage(payload) => {
-                    self.handle_send_ack_message_event(payload)?
-                }
-                NetworkEvent::ExpectCandidate(vote) => self.handle_expect_candidate_event(vote)?,
-                NetworkEvent::PurgeCandidate(old_public_id) => {
-                    self.handle_purge_candidate_event(old_public_id)?
-                }
+            match in_span!(
+                "accumulated_event",
+                prefix = our_pfx;
+                self.handle_accumulated_event(event, our_pfx, outbox)
+            )? {
+                Transition::Stay => (),
+                transition => return Ok(transition),
             }
 
             our_pfx = *self.chain_mut().our_prefix();
@@ -255,6 +291,85 @@ pub trait Approved: Relocated {
 
         Ok(Transition::Stay)
     }
+
+    fn handle_accumulated_event(
+        &mut self,
+        event: NetworkEvent,
+        our_pfx: Prefix<XorName>,
+        outbox: &mut dyn EventBox,
+    ) -> Result<Transition, RoutingError> {
+        trace!("{} Handle accumulated event: {:?}", self, event);
+        match event {
+            NetworkEvent::AddElder(pub_id, client_auth) => {
+                self.handle_add_elder_event(pub_id, client_auth, outbox)?;
+            }
+            NetworkEvent::RemoveElder(pub_id) => {
+                self.handle_remove_elder_event(pub_id, outbox)?;
+            }
+            NetworkEvent::Online(info) => {
+                self.handle_online_event(info)?;
+            }
+            NetworkEvent::Offline(pub_id) => {
+                self.handle_offline_event(pub_id)?;
+            }
+            NetworkEvent::OurMerge => self.handle_our_merge_event()?,
+            NetworkEvent::NeighbourMerge(_) => self.handle_neighbour_merge_event()?,
+            NetworkEvent::SectionInfo(sec_info) => {
+                for evidence in self.chain_mut().take_new_forks() {
+                    self.send_event(Event::ForkDetected(evidence), outbox);
+                }
+                self.report_neighbour_changes(outbox);
+                return self.handle_section_info_event(sec_info, our_pfx, outbox);
+            }
+            NetworkEvent::TheirKeyInfo(key_info) => self.handle_their_key_info_event(key_info)?,
+            NetworkEvent::AckMessage(_payload) => {
+                // Update their_knowledge is handled within the chain.
+            }
+            NetworkEvent::SendAckMessage(payload) => self.handle_send_ack_message_event(payload)?,
+            NetworkEvent::ExpectCandidate(vote) => self.handle_expect_candidate_event(vote)?,
+            NetworkEvent::PurgeCandidate(old_public_id) => {
+                self.handle_purge_candidate_event(old_public_id)?
+            }
+            NetworkEvent::Relocate(payload) => self.handle_relocate_event(payload.pub_id)?,
+            NetworkEvent::Promote(payload) => self.handle_promote_event(payload, outbox),
+            NetworkEvent::Demote(payload) => self.handle_demote_event(payload, outbox),
+            NetworkEvent::AddInfant(pub_id, client_auth) => {
+                self.handle_add_infant_event(pub_id, client_auth, outbox)?;
+            }
+            NetworkEvent::RemoveInfant(pub_id) => {
+                self.handle_remove_infant_event(pub_id, outbox)?;
+            }
+            NetworkEvent::Refresh(payload) => self.handle_refresh_event(payload, outbox),
+            NetworkEvent::SetJoinDifficulty(_) | NetworkEvent::Allow(_) | NetworkEvent::Deny(_) => {
+                // The new difficulty or join filter is stored within the chain.
+            }
+            NetworkEvent::UpgradeSignal {
+                version,
+                activation_version,
+            } => self.handle_upgrade_signal_event(version, activation_version),
+            NetworkEvent::AccountCredit(_) => {
+                // The new balance is stored within the chain.
+            }
+            NetworkEvent::AccountDebit(payload) => self.handle_account_debit_event(payload),
+            NetworkEvent::SequenceMessage(payload) => self.handle_sequence_message_event(payload),
+            NetworkEvent::FailedAudits(payload) => self.handle_failed_audits_event(payload, outbox),
+        }
+
+        Ok(Transition::Stay)
+    }
+
+    /// Raises an event for every neighbour we started or stopped holding an inferred info for.
+    fn report_neighbour_changes(&mut self, outbox: &mut dyn EventBox) {
+        for change in self.chain_mut().take_neighbour_changes() {
+            let event = match change {
+                NeighbourChange::Inferred { prefix, from } => {
+                    Event::NeighbourInferred { prefix, from }
+                }
+                NeighbourChange::Converged(prefix) => Event::NeighbourConverged(prefix),
+            };
+            self.send_event(event, outbox);
+        }
+    }
 }
 
 fn to_proof_set(block: &Block) -> ProofSet {
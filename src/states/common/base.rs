@@ -8,11 +8,12 @@
 
 use crate::{
     action::Action,
+    codec::CodecError,
     error::{InterfaceError, RoutingError},
     id::{FullId, PublicId},
     messages::{
-        DirectMessage, HopMessage, Message, Request, SignedDirectMessage, SignedRoutingMessage,
-        UserMessage,
+        compat, Capabilities, DirectMessage, EncryptedHopMessage, HopMessage, Message, Request,
+        SignedDirectMessage, SignedRoutingMessage, UserMessage,
     },
     outbox::EventBox,
     peer_map::PeerMap,
@@ -23,7 +24,16 @@ use crate::{
     xor_name::XorName,
     ConnectionInfo, NetworkBytes, NetworkEvent, NetworkService,
 };
-use maidsafe_utilities::serialisation;
+#[cfg(feature = "node")]
+use crate::{
+    chain::{JoinerId, NetworkEpoch, PendingVote},
+    messages::AccountInfo,
+    routing_table::Prefix,
+    section_contacts::SectionContacts,
+    types::MessageId,
+};
+#[cfg(feature = "node")]
+use std::collections::{BTreeMap, BTreeSet};
 use std::{fmt::Display, net::SocketAddr};
 
 // Trait for all states.
@@ -65,7 +75,7 @@ pub trait Base: Display {
                 priority,
                 result_tx,
             } => {
-                let result = self.handle_client_send_request(dst, content, priority);
+                let result = self.handle_client_send_request(dst, content, priority, outbox);
                 let _ = result_tx.send(result);
             }
             Action::NodeSendMessage {
@@ -102,6 +112,7 @@ pub trait Base: Display {
         _dst: Authority<XorName>,
         _content: Request,
         _priority: u8,
+        _outbox: &mut dyn EventBox,
     ) -> Result<(), InterfaceError> {
         warn!(
             "{} - Cannot handle ClientSendRequest - invalid state.",
@@ -143,6 +154,21 @@ pub trait Base: Display {
     ) -> Transition {
         use crate::NetworkEvent::*;
 
+        match event {
+            BootstrappedTo { ref node } => self
+                .network_service_mut()
+                .bootstrap_contact_reached(&node.peer_addr, true),
+            ConnectedTo {
+                peer: ConnectionInfo::Node { ref node_info },
+            } => self
+                .network_service_mut()
+                .bootstrap_contact_reached(&node_info.peer_addr, false),
+            ConnectionFailure { peer_addr, .. } => self
+                .network_service_mut()
+                .bootstrap_contact_failed(&peer_addr),
+            _ => (),
+        }
+
         let transition = match event {
             BootstrappedTo { node } => self.handle_bootstrapped_to(node),
             BootstrapFailure => self.handle_bootstrap_failure(outbox),
@@ -186,6 +212,21 @@ pub trait Base: Display {
         conn_info: ConnectionInfo,
         _outbox: &mut dyn EventBox,
     ) -> Transition {
+        let peer_addr = conn_info.peer_addr();
+        if !self.peer_map().is_known(&peer_addr) {
+            let current_connections = self.peer_map().connection_count();
+            if !self
+                .network_service_mut()
+                .accept_connection(&conn_info, current_connections)
+            {
+                debug!("{} - Rejected connection from {}.", self, peer_addr);
+                self.network_service_mut()
+                    .service_mut()
+                    .disconnect_from(peer_addr);
+                return Transition::Stay;
+            }
+        }
+
         self.peer_map_mut().connect(conn_info);
         Transition::Stay
     }
@@ -196,6 +237,7 @@ pub trait Base: Display {
         outbox: &mut dyn EventBox,
     ) -> Transition {
         trace!("{} - ConnectionFailure from {}", self, peer_addr);
+        self.network_service_mut().peer_disconnected(peer_addr);
 
         if let Some(pub_id) = self.peer_map_mut().disconnect(peer_addr) {
             trace!("{} - ConnectionFailure from {}", self, pub_id);
@@ -217,6 +259,20 @@ pub trait Base: Display {
         match result {
             Ok(transition) => transition,
             Err(RoutingError::FilterCheckFailed) => Transition::Stay,
+            Err(RoutingError::UnsupportedProtocolVersion(version)) => {
+                warn!(
+                    "{} - Disconnecting from {}, which speaks unsupported protocol version {}.",
+                    self, src_addr, version
+                );
+                self.network_service_mut()
+                    .service_mut()
+                    .disconnect_from(src_addr);
+                if let Some(pub_id) = self.peer_map_mut().disconnect(src_addr) {
+                    self.handle_peer_lost(pub_id, outbox)
+                } else {
+                    Transition::Stay
+                }
+            }
             Err(err) => {
                 debug!("{} - {:?}", self, err);
                 Transition::Stay
@@ -232,14 +288,34 @@ pub trait Base: Display {
     ) -> Result<Transition, RoutingError> {
         match message {
             Message::Hop(msg) => self.handle_hop_message(msg, outbox),
+            Message::EncryptedHop(msg) => {
+                let msg = msg.open(self.full_id())?;
+                self.handle_hop_message(msg, outbox)
+            }
             Message::Direct(msg) => {
                 let (msg, pub_id) = msg.open()?;
                 self.peer_map_mut().identify(pub_id, src_addr);
+                self.announce_capabilities(&pub_id);
+                if let DirectMessage::Capabilities(capabilities) = msg {
+                    trace!("{} - Peer {} has {:?}", self, pub_id, capabilities);
+                    let _ = compat::negotiate(capabilities.protocol_versions())?;
+                    self.peer_map_mut().set_capabilities(pub_id, capabilities);
+                    return Ok(Transition::Stay);
+                }
                 self.handle_direct_message(msg, pub_id, outbox)
             }
         }
     }
 
+    // Sends our capabilities to the peer, unless we already did since we connected to it. Peers
+    // which don't know the `Capabilities` message drop it, so we keep using only the features all
+    // nodes support when talking to them.
+    fn announce_capabilities(&mut self, pub_id: &PublicId) {
+        if self.peer_map_mut().should_announce_capabilities(pub_id) {
+            self.send_direct_message(pub_id, DirectMessage::Capabilities(Capabilities::ours()));
+        }
+    }
+
     fn handle_unsent_message(
         &mut self,
         peer_addr: SocketAddr,
@@ -264,9 +340,7 @@ pub trait Base: Display {
             "{} Successfully sent message with ID {} to {:?}",
             self, token, peer_addr
         );
-        self.network_service_mut()
-            .targets_cache_mut()
-            .target_succeeded(token, peer_addr);
+        self.network_service_mut().message_sent(peer_addr, token);
         Transition::Stay
     }
 
@@ -282,23 +356,140 @@ pub trait Base: Display {
         self.full_id().public_id().name()
     }
 
-    fn our_connection_info(&mut self) -> Result<NodeInfo, RoutingError> {
+    fn our_endpoints(&mut self) -> Result<Vec<NodeInfo>, RoutingError> {
         self.network_service_mut()
             .service_mut()
-            .our_connection_info()
+            .our_endpoints()
             .map_err(|err| {
                 debug!(
                     "{} - Failed to retrieve our connection info: {:?}",
                     self, err
                 );
-                err.into()
+                err
             })
     }
 
+    #[cfg(feature = "node")]
     fn close_group(&self, _name: XorName, _count: usize) -> Option<Vec<XorName>> {
         None
     }
 
+    #[cfg(feature = "node")]
+    fn pending_votes(&self) -> Vec<PendingVote> {
+        Vec::new()
+    }
+
+    #[cfg(feature = "node")]
+    fn network_epoch(&self) -> Option<NetworkEpoch> {
+        None
+    }
+
+    #[cfg(feature = "node")]
+    fn peer_scores(&self) -> BTreeMap<PublicId, u64> {
+        BTreeMap::new()
+    }
+
+    #[cfg(feature = "node")]
+    fn account_info(&self, _account: &XorName) -> Option<AccountInfo> {
+        None
+    }
+
+    #[cfg(feature = "node")]
+    fn section_contacts(&self) -> Option<SectionContacts> {
+        None
+    }
+
+    #[cfg(feature = "node")]
+    fn request_membership_statement(&mut self) -> Result<(), InterfaceError> {
+        warn!(
+            "{} - Cannot request membership statement - invalid state.",
+            self
+        );
+        Err(InterfaceError::InvalidState)
+    }
+
+    #[cfg(feature = "node")]
+    fn shutdown(&mut self) -> Result<(), InterfaceError> {
+        warn!("{} - Cannot shut down gracefully - invalid state.", self);
+        Err(InterfaceError::InvalidState)
+    }
+
+    #[cfg(feature = "node")]
+    fn signal_upgrade(
+        &mut self,
+        _version: u16,
+        _activation_version: u64,
+    ) -> Result<(), InterfaceError> {
+        warn!("{} - Cannot signal an upgrade - invalid state.", self);
+        Err(InterfaceError::InvalidState)
+    }
+
+    #[cfg(feature = "node")]
+    fn credit_account(
+        &mut self,
+        _account: XorName,
+        _amount: u64,
+        _msg_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        warn!("{} - Cannot credit an account - invalid state.", self);
+        Err(InterfaceError::InvalidState)
+    }
+
+    #[cfg(feature = "node")]
+    fn vote_for_join_filter(&mut self, _id: JoinerId, _allow: bool) -> Result<(), InterfaceError> {
+        warn!(
+            "{} - Cannot vote for the join filter - invalid state.",
+            self
+        );
+        Err(InterfaceError::InvalidState)
+    }
+
+    #[cfg(feature = "node")]
+    fn start_handoff(
+        &mut self,
+        _range: Prefix<XorName>,
+        _to: Prefix<XorName>,
+        _handoff_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        warn!("{} - Cannot start handoff - invalid state.", self);
+        Err(InterfaceError::InvalidState)
+    }
+
+    #[cfg(feature = "node")]
+    fn confirm_handoff(&mut self, _handoff_id: MessageId) -> Result<(), InterfaceError> {
+        warn!("{} - Cannot confirm handoff - invalid state.", self);
+        Err(InterfaceError::InvalidState)
+    }
+
+    #[cfg(feature = "node")]
+    fn send_data_inventory(
+        &mut self,
+        _dst: XorName,
+        _names: BTreeSet<XorName>,
+    ) -> Result<(), InterfaceError> {
+        warn!("{} - Cannot send data inventory - invalid state.", self);
+        Err(InterfaceError::InvalidState)
+    }
+
+    #[cfg(feature = "node")]
+    fn request_republish(
+        &mut self,
+        _dst: XorName,
+        _names: BTreeSet<XorName>,
+    ) -> Result<(), InterfaceError> {
+        warn!("{} - Cannot request republish - invalid state.", self);
+        Err(InterfaceError::InvalidState)
+    }
+
+    #[cfg(feature = "node")]
+    fn report_failed_audits(
+        &mut self,
+        _offenders: BTreeSet<Authority<XorName>>,
+    ) -> Result<BTreeSet<Authority<XorName>>, InterfaceError> {
+        warn!("{} - Cannot report failed audits - invalid state.", self);
+        Err(InterfaceError::InvalidState)
+    }
+
     fn send_direct_message(&mut self, dst_id: &PublicId, content: DirectMessage) {
         let message = if let Ok(message) = self.to_signed_direct_message(content) {
             message
@@ -336,16 +527,50 @@ pub trait Base: Display {
             );
         }
 
+        let message = match message {
+            Message::Hop(msg) => match self.seal_hop_message(dst_targets, msg) {
+                Some(message) => message,
+                None => return,
+            },
+            message => message,
+        };
+
         self.send_message_to_initial_targets(conn_infos, dg_size, message);
     }
 
+    // Encrypts the hop message to the targets if they all support it, so that any of them can
+    // receive it if sending to others fails. Returns `None` if encryption failed.
+    fn seal_hop_message(&self, dst_targets: &[PublicId], msg: HopMessage) -> Option<Message> {
+        let recipients: Vec<_> = dst_targets
+            .iter()
+            .filter(|pub_id| self.peer_map().get_connection_info(pub_id).is_some())
+            .collect();
+        if recipients.is_empty()
+            || !recipients
+                .iter()
+                .all(|pub_id| self.peer_map().capabilities(pub_id).hop_encryption())
+        {
+            return Some(Message::Hop(msg));
+        }
+
+        match EncryptedHopMessage::new(&msg, self.full_id(), recipients) {
+            Ok(msg) => Some(Message::EncryptedHop(msg)),
+            Err(error) => {
+                error!("{} Failed to encrypt message {:?}: {:?}", self, msg, error);
+                None
+            }
+        }
+    }
+
     fn send_message_to_initial_targets(
         &mut self,
         conn_infos: Vec<ConnectionInfo>,
         dg_size: usize,
         message: Message,
     ) {
-        let bytes = match to_network_bytes(&message) {
+        let priority = message.priority();
+        let version = self.wire_version(&conn_infos);
+        let bytes = match to_network_bytes(&message, version) {
             Ok(bytes) => bytes,
             Err((error, message)) => {
                 error!(
@@ -360,7 +585,7 @@ pub trait Base: Display {
         };
 
         self.network_service_mut()
-            .send_message_to_initial_targets(conn_infos, dg_size, bytes);
+            .send_message_to_initial_targets(conn_infos, dg_size, bytes, priority);
     }
 
     // Create HopMessage containing the given signed message.
@@ -369,6 +594,25 @@ pub trait Base: Display {
         Ok(Message::Hop(hop_msg))
     }
 
+    // Returns the highest protocol version all the given peers support. Peers which haven't
+    // announced their capabilities yet are sent the oldest version we support, which all the peers
+    // we can talk to understand.
+    fn wire_version(&self, conn_infos: &[ConnectionInfo]) -> u16 {
+        conn_infos
+            .iter()
+            .map(|conn_info| {
+                self.peer_map()
+                    .get_id(&conn_info.peer_addr())
+                    .and_then(|pub_id| {
+                        compat::negotiate(self.peer_map().capabilities(pub_id).protocol_versions())
+                            .ok()
+                    })
+                    .unwrap_or(compat::MIN_PROTOCOL_VERSION)
+            })
+            .min()
+            .unwrap_or(compat::MIN_PROTOCOL_VERSION)
+    }
+
     fn to_signed_direct_message(&self, content: DirectMessage) -> Result<Message, RoutingError> {
         SignedDirectMessage::new(content, self.full_id())
             .map(Message::Direct)
@@ -381,21 +625,25 @@ pub trait Base: Display {
 
 pub fn to_network_bytes(
     message: &Message,
-) -> Result<NetworkBytes, (serialisation::SerialisationError, &Message)> {
+    version: u16,
+) -> Result<NetworkBytes, (CodecError, &Message)> {
     #[cfg(not(feature = "mock_serialise"))]
     let result = Ok(NetworkBytes::from(
-        serialisation::serialise(message).map_err(|err| (err, message))?,
+        compat::serialise(message, version).map_err(|err| (err, message))?,
     ));
 
     #[cfg(feature = "mock_serialise")]
-    let result = Ok(NetworkBytes::new(message.clone()));
+    let result = {
+        let _ = version;
+        Ok(NetworkBytes::new(message.clone()))
+    };
 
     result
 }
 
 pub fn from_network_bytes(data: NetworkBytes) -> Result<Message, RoutingError> {
     #[cfg(not(feature = "mock_serialise"))]
-    let result = serialisation::deserialise(&data[..]).map_err(RoutingError::SerialisationError);
+    let result = compat::deserialise(&data[..]).map(|(_, message)| message);
 
     #[cfg(feature = "mock_serialise")]
     let result = Ok((*data).clone());
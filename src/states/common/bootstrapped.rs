@@ -47,10 +47,30 @@ pub trait Bootstrapped: Base {
             src: src,
             dst: dst,
             content: content,
+            dst_min_version: None,
         };
         self.send_routing_message_impl(routing_msg, expires_at)
     }
 
+    /// Sends a message to the section `dst` which must only be delivered to members of its info
+    /// with version `dst_min_version` or newer.
+    #[cfg(feature = "node")]
+    fn send_routing_message_to_version(
+        &mut self,
+        src: Authority<XorName>,
+        dst: Authority<XorName>,
+        content: MessageContent,
+        dst_min_version: u64,
+    ) -> Result<()> {
+        let routing_msg = RoutingMessage {
+            src,
+            dst,
+            content,
+            dst_min_version: Some(dst_min_version),
+        };
+        self.send_routing_message_impl(routing_msg, None)
+    }
+
     fn send_routing_message(
         &mut self,
         src: Authority<XorName>,
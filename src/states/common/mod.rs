@@ -6,19 +6,23 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+#[cfg(feature = "node")]
 mod approved;
 mod base;
 mod bootstrapped;
 mod bootstrapped_not_established;
 pub mod proxied;
+#[cfg(feature = "node")]
 mod relocated;
+#[cfg(feature = "node")]
 mod relocated_not_established;
 
+#[cfg(feature = "node")]
+pub use self::{
+    approved::Approved, relocated::Relocated, relocated_not_established::RelocatedNotEstablished,
+};
 pub use self::{
-    approved::Approved,
     base::{from_network_bytes, Base},
     bootstrapped::Bootstrapped,
     bootstrapped_not_established::BootstrappedNotEstablished,
-    relocated::Relocated,
-    relocated_not_established::RelocatedNotEstablished,
 };
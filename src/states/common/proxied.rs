@@ -8,7 +8,9 @@
 
 //! Utilities for node states that are connected via proxy.
 
-use crate::{error::RoutingError, id::PublicId, peer_manager::PeerManager, xor_name::XorName};
+#[cfg(feature = "node")]
+use crate::peer_manager::PeerManager;
+use crate::{error::RoutingError, id::PublicId, xor_name::XorName};
 use std::fmt::Display;
 
 pub fn get_proxy_public_id<'a, T: Display>(
@@ -24,6 +26,7 @@ pub fn get_proxy_public_id<'a, T: Display>(
     }
 }
 
+#[cfg(feature = "node")]
 pub fn find_proxy_public_id<'a, T: Display>(
     label: &T,
     peer_mgr: &'a PeerManager,
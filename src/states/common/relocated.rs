@@ -49,12 +49,12 @@ pub trait Relocated: Bootstrapped {
             self.peer_mgr_mut().set_connecting(their_pub_id);
         }
 
-        let our_conn_info = self.our_connection_info()?;
+        let our_endpoints = self.our_endpoints()?;
         let shared_secret = self
             .full_id()
             .encrypting_private_key()
             .shared_secret(&their_pub_id.encrypting_public_key());
-        let encrypted_conn_info = shared_secret.encrypt(&our_conn_info).map_err(|err| {
+        let encrypted_conn_info = shared_secret.encrypt(&our_endpoints).map_err(|err| {
             debug!(
                 "{} - Failed to serialise our connection info for {:?}: {:?}.",
                 self, their_pub_id, err
@@ -99,9 +99,19 @@ pub trait Relocated: Bootstrapped {
             .full_id()
             .encrypting_private_key()
             .shared_secret(&their_pub_id.encrypting_public_key());
-        let their_conn_info: NodeInfo = shared_secret
+        let their_endpoints: Vec<NodeInfo> = shared_secret
             .decrypt(encrypted_their_conn_info)
             .map_err(RoutingError::Crypto)?;
+        let their_conn_info = match self.network_service_mut().select_endpoint(&their_endpoints) {
+            Some(conn_info) => conn_info,
+            None => {
+                debug!(
+                    "{} - None of the endpoints of {:?} is reachable: {:?}.",
+                    self, their_pub_id, their_endpoints
+                );
+                return Ok(());
+            }
+        };
 
         debug!(
             "{} - Received connection info from {:?}.",
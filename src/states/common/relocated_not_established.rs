@@ -64,6 +64,7 @@ pub trait RelocatedNotEstablished: Relocated {
                     },
                 src: ManagedNode(_),
                 dst: ManagedNode(_),
+                ..
             } => {
                 if self.our_prefix().matches(&src_name) {
                     self.handle_connection_request(
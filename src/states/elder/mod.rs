@@ -9,33 +9,51 @@
 #[cfg(all(test, feature = "mock_parsec"))]
 mod tests;
 
-use super::common::{Approved, Base, Bootstrapped, Relocated};
+use super::{
+    common::{Approved, Base, Bootstrapped, Relocated},
+    BootstrappingPeer, TargetState,
+};
+#[cfg(feature = "mock_base")]
+use crate::chain::StabilityGuard;
 use crate::{
+    action::Action,
     cache::Cache,
     chain::{
-        delivery_group_size, AckMessagePayload, Chain, ExpectCandidatePayload, GenesisPfxInfo,
-        NetworkEvent, OnlinePayload, PrefixChange, PrefixChangeOutcome, SectionInfo,
-        SectionKeyInfo, SendAckMessagePayload,
+        delivery_group_size, AccountPayload, AckMessagePayload, AuditPayload, Chain,
+        ElderChangePayload, ExpectCandidatePayload, GenesisPfxInfo, JoinerId, MembershipStatement,
+        Misbehaviour, NetworkEpoch, NetworkEvent, OnlinePayload, PendingVote, PrefixChange,
+        PrefixChangeOutcome, RefreshPayload, RelocatePayload, SectionInfo, SectionKeyInfo,
+        SectionProofChain, SendAckMessagePayload, SequencePayload,
     },
+    client_error::ClientError,
+    client_manager::ClientManager,
     config_handler,
-    error::{BootstrapResponseError, InterfaceError, RoutingError},
+    error::{BootstrapResponseError, InterfaceError, JoinDenialReason, RoutingError},
     event::Event,
     id::{FullId, PublicId},
+    join_rate::JoinRate,
+    message_sequencer::{MessageSequencer, OutOfOrder},
     messages::{
-        DirectMessage, HopMessage, MessageContent, RoutingMessage, SignedRoutingMessage,
-        UserMessage,
+        AccountInfo, DirectMessage, HopMessage, MessageContent, RelayedResponse, Request, Response,
+        RoutingMessage, SignedRoutingMessage, UserMessage, CLIENT_GET_PRIORITY,
     },
+    middleware::{Direction, MessageContext, Verdict},
+    network_params::NetworkParams,
     outbox::EventBox,
     parsec::{self, ParsecMap},
     peer_manager::{Peer, PeerManager, PeerState},
     peer_map::PeerMap,
     quic_p2p::NodeInfo,
+    rate_limiter::{RateLimiter, RateLimits, RequestKind},
+    refresh::{self, RepublishQueue, REPUBLISH_INTERVAL},
     routing_message_filter::{FilteringResult, RoutingMessageFilter},
     routing_table::Error as RoutingTableError,
     routing_table::{Authority, Prefix, Xorable, DEFAULT_PREFIX},
+    section_contacts::SectionContacts,
+    session::SessionToken,
     sha3::Digest256,
     signature_accumulator::SignatureAccumulator,
-    state_machine::Transition,
+    state_machine::{State, Transition},
     time::{Duration, Instant},
     timer::Timer,
     types::MessageId,
@@ -43,6 +61,7 @@ use crate::{
     xor_name::XorName,
     BlsPublicKeySet, ConnectionInfo, NetworkService,
 };
+use crossbeam_channel as mpmc;
 use itertools::Itertools;
 use log::LogLevel;
 use lru_time_cache::LruCache;
@@ -53,7 +72,7 @@ use safe_crypto::Signature;
 use std::net::SocketAddr;
 use std::{
     cmp,
-    collections::{BTreeSet, VecDeque},
+    collections::{BTreeMap, BTreeSet, VecDeque},
     fmt::{self, Display, Formatter},
     iter, mem,
     net::IpAddr,
@@ -62,10 +81,10 @@ use std::{
 /// Time after which a `Ticked` event is sent.
 const TICK_TIMEOUT: Duration = Duration::from_secs(15);
 const GOSSIP_TIMEOUT: Duration = Duration::from_secs(2);
+/// Interval between sending our section list to an elder of each neighbouring section.
+const SECTION_LIST_INTERVAL: Duration = Duration::from_secs(30);
 //const MAX_IDLE_ROUNDS: u64 = 100;
 //const TICK_TIMEOUT_SECS: u64 = 60;
-/// The number of required leading zero bits for the resource proof
-const RESOURCE_PROOF_DIFFICULTY: u8 = 0;
 /// The total size of the resource proof data.
 const RESOURCE_PROOF_TARGET_SIZE: usize = 250 * 1024 * 1024;
 /// Interval between displaying info about current candidate.
@@ -74,6 +93,22 @@ const CANDIDATE_STATUS_INTERVAL: Duration = Duration::from_secs(60);
 const CLIENT_BAN_DURATION: Duration = Duration::from_secs(2 * 60 * 60);
 /// Duration for which clients' IDs we disconnected from are retained.
 const DROPPED_CLIENT_TIMEOUT: Duration = Duration::from_secs(2 * 60 * 60);
+/// Maximum number of messages held back because we don't know the destination section's version
+/// they require yet.
+const MAX_HELD_MESSAGES: usize = 100;
+/// Duration after which a held message whose required section version we still don't know gets
+/// dropped.
+const HELD_MESSAGE_TIMEOUT: Duration = Duration::from_secs(60);
+/// Duration for which we wait for the other section to complete a handoff of responsibility.
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// Duration for which we wait for our section to accumulate our `Offline` vote when shutting down,
+/// before terminating anyway.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(60);
+/// Duration for which we resend a message proved from an older key if a section bounces it.
+const BOUNCE_RESEND_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// Duration for which we wait for our section to agree on the sequence number of a user message
+/// we are asked to send, or for the message of a sequence number it agreed on.
+const SEQUENCE_VOTE_TIMEOUT: Duration = Duration::from_secs(2 * 60);
 
 pub struct ElderDetails {
     pub cache: Box<dyn Cache>,
@@ -97,6 +132,16 @@ pub struct Elder {
     /// The queue of routing messages addressed to us. These do not themselves need forwarding,
     /// although they may wrap a message which needs forwarding.
     msg_queue: VecDeque<RoutingMessage>,
+    /// Messages requiring a newer version of their destination section than we know, together
+    /// with the time they were first held.
+    held_messages: VecDeque<(Instant, SignedRoutingMessage)>,
+    /// Restores the order of the sequenced user messages we receive.
+    msg_sequencer: MessageSequencer,
+    /// User messages from our section we voted to sequence, by hash, until the vote accumulates.
+    unsequenced_msgs:
+        LruCache<Digest256, (Authority<XorName>, Authority<XorName>, UserMessage, u8)>,
+    /// Sequence numbers our section agreed on for user messages we weren't asked to send yet.
+    sequenced_msgs: LruCache<Digest256, u64>,
     peer_map: PeerMap,
     peer_mgr: PeerManager,
     response_cache: Box<dyn Cache>,
@@ -118,17 +163,49 @@ pub struct Elder {
     /// already enqueued in the channel or added before Crust handled the disconnect request).  If a
     /// client then re-connects, its ID is removed from here when we add it to the `PeerManager`.
     dropped_clients: LruCache<PublicId, ()>,
+    /// `RelayedGet` requests of our clients whose response we are waiting for, by message ID, with
+    /// the client and the authority the GET is sent to.
+    relayed_gets: LruCache<MessageId, (Authority<XorName>, Authority<XorName>)>,
+    /// Handoffs of a range of names from our section to another one which wait for the other
+    /// section to confirm, by handoff ID, with the range and the other section's prefix.
+    outgoing_handoffs: LruCache<MessageId, (Prefix<XorName>, Prefix<XorName>)>,
+    /// Handoffs of a range of names from another section to ours which wait for the commit, by
+    /// handoff ID, with the range and the other section's prefix.
+    incoming_handoffs: LruCache<MessageId, (Prefix<XorName>, Prefix<XorName>)>,
+    /// Hashes of the proved messages we sent recently, with the first versions of the proof chains
+    /// we already resent each of them with after a bounce.
+    sent_proved_msgs: LruCache<Digest256, BTreeSet<u64>>,
+    /// Limits on the requests our clients send through us, unless disabled.
+    client_rate_limiter: Option<RateLimiter>,
+    /// Charges the mutation requests to the client accounts our section manages.
+    client_manager: ClientManager,
     /// Proxy client traffic handled
     proxy_load_amount: u64,
     /// Whether resource proof is disabled.
     disable_resource_proof: bool,
+    /// The recent joins to our section, which the resource proof difficulty adapts to.
+    join_rate: JoinRate,
     parsec_map: ParsecMap,
     gen_pfx_info: GenesisPfxInfo,
     gossip_timer_token: u64,
+    /// The timer token for gossiping our section list to our neighbours.
+    section_list_token: u64,
     chain: Chain,
     #[cfg(feature = "mock_base")]
     ignore_candidate_info_counter: u8,
     pfx_is_successfully_polled: bool,
+    /// Whether our section agreed to relocate us.
+    is_relocating: bool,
+    /// Our new ID and the section which accepted us, once we are relocated.
+    relocated_to: Option<(FullId, (Prefix<XorName>, BTreeSet<PublicId>))>,
+    /// The token of the shutdown timeout, once we voted ourselves offline to shut down.
+    shutdown_token: Option<u64>,
+    /// Whether our section accumulated our `Offline` vote, or gave up waiting for it.
+    shutdown_acknowledged: bool,
+    /// The data new members of our section asked us to republish, waiting to be passed on.
+    republish_queue: RepublishQueue,
+    /// The token of the timer passing on the next batch of `republish_queue`, if it is running.
+    republish_token: Option<u64>,
 }
 
 impl Elder {
@@ -136,7 +213,7 @@ impl Elder {
         cache: Box<dyn Cache>,
         network_service: NetworkService,
         full_id: FullId,
-        min_section_size: usize,
+        network_params: NetworkParams,
         timer: Timer,
         outbox: &mut dyn EventBox,
     ) -> Result<Self, RoutingError> {
@@ -149,7 +226,7 @@ impl Elder {
             latest_info: SectionInfo::default(),
         };
         let parsec_map = ParsecMap::new(full_id.clone(), &gen_pfx_info);
-        let chain = Chain::new(min_section_size, public_id, gen_pfx_info.clone());
+        let chain = Chain::new(network_params, public_id, gen_pfx_info.clone());
         let peer_map = PeerMap::new();
         let peer_mgr = PeerManager::new(dev_config.disable_client_rate_limiter);
 
@@ -190,12 +267,78 @@ impl Elder {
         Ok(elder)
     }
 
-    fn new(details: ElderDetails, is_first_node: bool) -> Self {
+    /// Leaves our section to rejoin the network with `new_full_id` in the section which accepted
+    /// us, after our section relocated us.
+    pub fn into_bootstrapping(
+        mut self,
+        new_full_id: FullId,
+        our_section: (Prefix<XorName>, BTreeSet<PublicId>),
+        action_sender: mpmc::Sender<Action>,
+        _outbox: &mut dyn EventBox,
+    ) -> State {
+        info!(
+            "{} Relocating to {:?} as {}.",
+            self,
+            our_section.0,
+            new_full_id.public_id()
+        );
+
+        // Disconnect from all currently connected peers.
+        for peer in self.peer_map.remove_all() {
+            self.network_service
+                .service_mut()
+                .disconnect_from(peer.peer_addr());
+        }
+
+        let network_params = *self.chain.network_params();
+        let target_state = TargetState::ProvingNode {
+            old_full_id: self.full_id,
+            our_section,
+        };
+
+        State::BootstrappingPeer(BootstrappingPeer::new(
+            action_sender,
+            self.response_cache,
+            target_state,
+            self.network_service,
+            new_full_id,
+            network_params,
+            self.timer,
+        ))
+    }
+
+    fn new(mut details: ElderDetails, is_first_node: bool) -> Self {
         let dev_config = config_handler::get_config().dev.unwrap_or_default();
+        details
+            .chain
+            .set_stability_guard(dev_config.stability_guard);
+        if let Some(vote_ttl_secs) = dev_config.vote_ttl_secs {
+            details
+                .chain
+                .set_vote_ttl(Duration::from_secs(vote_ttl_secs));
+        }
+        if let Some(threshold) = dev_config.misbehaviour_threshold {
+            details.chain.set_misbehaviour_threshold(threshold);
+        }
+        details
+            .chain
+            .set_relocation_interval(dev_config.relocation_interval);
+        details.chain.set_adult_age(dev_config.adult_age);
+        if let Some(bytes) = dev_config.message_filter_memory {
+            details.routing_msg_filter = RoutingMessageFilter::with_memory_budget(bytes);
+        }
+        let client_rate_limiter = if dev_config.disable_client_rate_limiter {
+            None
+        } else {
+            Some(RateLimiter::new(
+                dev_config.client_rate_limits.unwrap_or_default(),
+            ))
+        };
 
         let timer = details.timer;
         let tick_timer_token = timer.schedule(TICK_TIMEOUT);
         let gossip_timer_token = timer.schedule(GOSSIP_TIMEOUT);
+        let section_list_token = timer.schedule(SECTION_LIST_INTERVAL);
         let candidate_status_token = timer.schedule(CANDIDATE_STATUS_INTERVAL);
 
         Self {
@@ -203,6 +346,10 @@ impl Elder {
             full_id: details.full_id.clone(),
             is_first_node,
             msg_queue: details.msg_backlog.into_iter().collect(),
+            held_messages: VecDeque::new(),
+            msg_sequencer: MessageSequencer::new(),
+            unsequenced_msgs: LruCache::with_expiry_duration(SEQUENCE_VOTE_TIMEOUT),
+            sequenced_msgs: LruCache::with_expiry_duration(SEQUENCE_VOTE_TIMEOUT),
             peer_map: details.peer_map,
             peer_mgr: details.peer_mgr,
             response_cache: details.cache,
@@ -215,15 +362,31 @@ impl Elder {
             candidate_status_token,
             banned_client_ips: LruCache::with_expiry_duration(CLIENT_BAN_DURATION),
             dropped_clients: LruCache::with_expiry_duration(DROPPED_CLIENT_TIMEOUT),
+            relayed_gets: LruCache::with_expiry_duration(
+                details.chain.network_params().timeouts.relayed_get_response,
+            ),
+            outgoing_handoffs: LruCache::with_expiry_duration(HANDOFF_TIMEOUT),
+            incoming_handoffs: LruCache::with_expiry_duration(HANDOFF_TIMEOUT),
+            sent_proved_msgs: LruCache::with_expiry_duration(BOUNCE_RESEND_TIMEOUT),
+            client_rate_limiter,
+            client_manager: ClientManager::new(),
             proxy_load_amount: 0,
             disable_resource_proof: dev_config.disable_resource_proof,
+            join_rate: JoinRate::new(),
             parsec_map: details.parsec_map,
             gen_pfx_info: details.gen_pfx_info,
             gossip_timer_token,
+            section_list_token,
             chain: details.chain,
             #[cfg(feature = "mock_base")]
             ignore_candidate_info_counter: 0,
             pfx_is_successfully_polled: false,
+            is_relocating: false,
+            relocated_to: None,
+            shutdown_token: None,
+            shutdown_acknowledged: false,
+            republish_queue: RepublishQueue::new(),
+            republish_token: None,
         }
     }
 
@@ -286,6 +449,126 @@ impl Elder {
         Ok(())
     }
 
+    fn report_stalled_votes(&mut self, outbox: &mut dyn EventBox) {
+        for (event, have, need) in self.chain.stalled_votes() {
+            warn!(
+                "{} - Vote for {:?} stalled with {} of {} required votes.",
+                self, event, have, need
+            );
+            outbox.send_event(Event::VoteStalled {
+                event: event.descriptor(),
+                have,
+                need,
+            });
+        }
+    }
+
+    fn report_instability(&mut self, outbox: &mut dyn EventBox) {
+        if let Some((joined, size)) = self.chain.instability() {
+            warn!(
+                "{} - {} of {} section members joined recently. Only handling membership changes.",
+                self, joined, size
+            );
+            outbox.send_event(Event::SectionUnstable { joined, size });
+        }
+    }
+
+    fn purge_stale_votes(&mut self, outbox: &mut dyn EventBox) {
+        let count = self.chain.purge_stale_votes();
+        if count > 0 {
+            outbox.send_event(Event::StaleVotesPurged { count });
+        }
+    }
+
+    fn report_evicted_events(&mut self, outbox: &mut dyn EventBox) {
+        let count = self.chain.take_evicted_events();
+        if count > 0 {
+            warn!(
+                "{} - Dropped {} events voted for during the ongoing prefix change.",
+                self, count
+            );
+            outbox.send_event(Event::CachedEventsEvicted { count });
+        }
+    }
+
+    fn vote_out_malicious_peers(&mut self) {
+        for pub_id in self.chain.take_malicious_peers() {
+            warn!("{} - Voting {} offline for misbehaviour.", self, pub_id);
+            self.vote_for_event(NetworkEvent::Offline(pub_id));
+        }
+    }
+
+    fn vote_for_join_difficulty(&mut self) {
+        let section_size = self.chain.our_section().len();
+        if let Some(difficulty) = self
+            .join_rate
+            .vote(section_size, self.chain.join_difficulty())
+        {
+            info!(
+                "{} - Voting for a join difficulty of {} for {} members.",
+                self, difficulty, section_size
+            );
+            self.vote_for_event(NetworkEvent::SetJoinDifficulty(difficulty));
+        }
+    }
+
+    /// Returns `true` if `signed_msg` requires a newer version of its destination section than we
+    /// know of, in which case it is held back until we learn about it.
+    fn hold_if_dst_unknown(&mut self, signed_msg: &SignedRoutingMessage) -> bool {
+        let routing_msg = signed_msg.routing_message();
+        let min_version = match (routing_msg.dst, routing_msg.dst_min_version) {
+            (Authority::Section(_), Some(min_version)) => min_version,
+            _ => return false,
+        };
+        if self
+            .chain
+            .knows_section_version(&routing_msg.dst.name(), min_version)
+        {
+            return false;
+        }
+
+        if self.held_messages.len() >= MAX_HELD_MESSAGES {
+            if let Some((_, dropped)) = self.held_messages.pop_front() {
+                debug!(
+                    "{} Too many held messages - dropping {:?}",
+                    self,
+                    dropped.routing_message()
+                );
+            }
+        }
+        trace!(
+            "{} Holding {:?} until we know version {} of its destination.",
+            self,
+            routing_msg,
+            min_version
+        );
+        self.held_messages
+            .push_back((Instant::now(), signed_msg.clone()));
+        true
+    }
+
+    /// Sends on the held messages whose destination section version we now know, and drops those
+    /// held for longer than `HELD_MESSAGE_TIMEOUT`.
+    fn handle_held_messages(&mut self) {
+        let held_messages = mem::replace(&mut self.held_messages, VecDeque::new());
+        for (held_at, mut signed_msg) in held_messages {
+            if held_at.elapsed() > HELD_MESSAGE_TIMEOUT {
+                debug!(
+                    "{} Dropping {:?}: destination section version still unknown.",
+                    self,
+                    signed_msg.routing_message()
+                );
+            } else if self.hold_if_dst_unknown(&signed_msg) {
+                // Keep the original time, so the message still expires in time.
+                if let Some(entry) = self.held_messages.back_mut() {
+                    entry.0 = held_at;
+                }
+            } else if let Err(error) = self.send_signed_message(&mut signed_msg) {
+                debug!("{} Failed to send held message: {:?}", self, error);
+            }
+        }
+    }
+
     fn handle_routing_messages(&mut self, outbox: &mut dyn EventBox) {
         while let Some(routing_msg) = self.msg_queue.pop_front() {
             if self.in_authority(&routing_msg.dst) {
@@ -297,7 +580,10 @@ impl Elder {
     }
 
     fn public_key_set(&self) -> BlsPublicKeySet {
-        BlsPublicKeySet::from_section_info(self.chain.our_info().clone())
+        BlsPublicKeySet::from_section_info(
+            self.chain.our_info().clone(),
+            *self.chain.network_params(),
+        )
     }
 
     fn handle_parsec_poke(&mut self, msg_version: u64, pub_id: PublicId) {
@@ -383,7 +669,10 @@ impl Elder {
             completed_events,
         } = self.chain.finalise_prefix_change()?;
         self.gen_pfx_info = gen_pfx_info;
+        // Queued candidates are dropped with the `ExpectCandidate` votes in flight: they retry
+        // once they time out.
         self.chain.reset_candidate();
+        self.chain.clear_queued_candidates();
         self.peer_mgr.reset_candidate();
         self.init_parsec(); // We don't reset the chain on prefix change.
 
@@ -417,21 +706,44 @@ impl Elder {
                 // flight as well.
                 NetworkEvent::AddElder(_, _)
                 | NetworkEvent::RemoveElder(_)
+                | NetworkEvent::AddInfant(_, _)
+                | NetworkEvent::RemoveInfant(_)
                 | NetworkEvent::Online(_)
                 | NetworkEvent::ExpectCandidate(_)
                 | NetworkEvent::PurgeCandidate(_) => false,
 
+                // Drop: the relocation is voted for again after the next churn if still due, and
+                // elder changes are voted for with each new info of our section.
+                NetworkEvent::Relocate(_) | NetworkEvent::Promote(_) | NetworkEvent::Demote(_) => {
+                    false
+                }
+
+                // Drop: the members of the new sections are refreshed with their section infos,
+                // and the join difficulty is voted for again on the next tick if still needed.
+                NetworkEvent::Refresh(_) | NetworkEvent::SetJoinDifficulty(_) => false,
+
                 // Keep: Additional signatures for neighbours for sec-msg-relay.
                 NetworkEvent::SectionInfo(ref sec_info) => our_pfx.is_neighbour(sec_info.prefix()),
 
                 // Drop: condition may have changed.
                 NetworkEvent::OurMerge => false,
 
+                // Keep: Only if we still manage the account.
+                NetworkEvent::AccountCredit(ref payload)
+                | NetworkEvent::AccountDebit(ref payload) => our_pfx.matches(&payload.account),
+
+                // Keep: Only if the message is still sent by our section.
+                NetworkEvent::SequenceMessage(ref payload) => our_pfx.matches(&payload.src.name()),
+
                 // Keep: Still relevant after prefix change.
-                NetworkEvent::NeighbourMerge(_)
+                NetworkEvent::Allow(_)
+                | NetworkEvent::Deny(_)
+                | NetworkEvent::UpgradeSignal { .. }
+                | NetworkEvent::NeighbourMerge(_)
                 | NetworkEvent::TheirKeyInfo(_)
                 | NetworkEvent::AckMessage(_)
-                | NetworkEvent::SendAckMessage(_) => true,
+                | NetworkEvent::SendAckMessage(_)
+                | NetworkEvent::FailedAudits(_) => true,
             })
             .for_each(|event| {
                 self.vote_for_event(event.clone());
@@ -440,6 +752,29 @@ impl Elder {
         Ok(())
     }
 
+    fn vote_for_upgrade(&mut self, version: u16, activation_version: u64) {
+        if self.chain.is_new_upgrade(version) {
+            self.vote_for_event(NetworkEvent::UpgradeSignal {
+                version,
+                activation_version,
+            });
+        }
+    }
+
+    fn send_upgrade_signal(&mut self, version: u16, activation_version: u64) {
+        self.chain.other_prefixes().iter().for_each(|pfx| {
+            let src = Authority::Section(self.our_prefix().name());
+            let dst = Authority::PrefixSection(*pfx);
+            let content = MessageContent::UpgradeSignal {
+                version,
+                activation_version,
+            };
+            if let Err(err) = self.send_routing_message(src, dst, content) {
+                debug!("{} Failed to send UpgradeSignal: {:?}.", self, err);
+            }
+        });
+    }
+
     fn send_neighbour_infos(&mut self) {
         self.chain.other_prefixes().iter().for_each(|pfx| {
             let src = Authority::Section(self.our_prefix().name());
@@ -459,7 +794,7 @@ impl Elder {
     ) -> Result<(), RoutingError> {
         match self.peer_mgr.get_peer(pub_id).map(Peer::state) {
             Some(PeerState::Client { .. }) => {
-                if let DirectMessage::BootstrapRequest = *msg {
+                if let DirectMessage::BootstrapRequest | DirectMessage::ResumeSession(_) = *msg {
                     Ok(())
                 } else {
                     debug!(
@@ -503,6 +838,10 @@ impl Elder {
         let filter_res = self
             .routing_msg_filter
             .filter_incoming(signed_msg.routing_message());
+        let (filtered, false_positives) = self.routing_msg_filter.incoming_stats();
+        self.network_service()
+            .metrics()
+            .set_filter_stats(filtered, false_positives);
 
         if filter_res == FilteringResult::KnownMessage {
             debug!(
@@ -513,16 +852,20 @@ impl Elder {
             return Ok(());
         }
 
+        if self.refuse_if_rate_limited(signed_msg.routing_message())? {
+            return Ok(());
+        }
+
         if self.in_authority(&signed_msg.routing_message().dst) {
             // The message is addressed to our section. Verify its integrity and trust
             if !signed_msg.check_trust(&self.chain) {
-                log_or_panic!(
-                    LogLevel::Error,
+                debug!(
                     "{} Untrusted SignedRoutingMessage: {:?} --- {:?}",
                     self,
                     signed_msg,
                     self.chain.get_their_keys_info().collect::<Vec<_>>()
                 );
+                self.bounce_untrusted_message(signed_msg);
                 return Err(RoutingError::UntrustedMessage);
             }
             signed_msg.check_integrity()?;
@@ -536,6 +879,9 @@ impl Elder {
                 }
             }
             if filter_res == FilteringResult::NewMessage {
+                if self.forward_relayed_get_response(&signed_msg) {
+                    return Ok(());
+                }
                 // if addressed to us, then we just queue it and return
                 self.msg_queue.push_back(signed_msg.into_routing_message());
             }
@@ -559,7 +905,9 @@ impl Elder {
         outbox: &mut dyn EventBox,
     ) -> Result<(), RoutingError> {
         use crate::messages::MessageContent::*;
-        use crate::Authority::{Client, ManagedNode, PrefixSection, Section};
+        use crate::Authority::{
+            Client, ClientManager, ManagedNode, NaeManager, PrefixSection, Section,
+        };
 
         match routing_msg.content {
             UserMessage { .. } => (),
@@ -603,11 +951,15 @@ impl Elder {
                 dst @ ManagedNode(_),
             ) => self.handle_connection_request(&encrypted_conn_info, pub_id, src, dst, outbox),
             (NeighbourInfo(sec_info), Section(_), PrefixSection(_)) => {
-                self.handle_neighbour_info(sec_info)
+                self.handle_neighbour_info(sec_info, outbox)
             }
             (Merge(digest), PrefixSection(_), PrefixSection(_)) => self.handle_merge(digest),
-            (UserMessage { content, .. }, src, dst) => {
-                outbox.send_event(content.into_event(src, dst));
+            (UserMessage { content, seq, .. }, src, dst) => {
+                if self.refuse_if_low_balance(&content, src, dst)? {
+                    return Ok(());
+                }
+                self.note_relayed_get(&content, src, dst);
+                self.handle_user_message(content, seq, src, dst, outbox);
                 Ok(())
             }
             (
@@ -618,6 +970,80 @@ impl Elder {
                 Section(src),
                 Section(dst),
             ) => self.handle_ack_message(src_prefix, ack_version, src, dst),
+            (
+                RelocateResponse {
+                    target_interval,
+                    section,
+                    ..
+                },
+                Section(_),
+                ManagedNode(_),
+            ) => {
+                self.handle_relocate_response(target_interval, section);
+                Ok(())
+            }
+            (MembershipStatementRequest { message_id }, ManagedNode(src), Section(_)) => {
+                self.handle_membership_statement_request(src, message_id)
+            }
+            (MembershipStatementResponse { statement, .. }, Section(_), ManagedNode(dst)) => {
+                self.handle_membership_statement_response(statement, dst, outbox);
+                Ok(())
+            }
+            (HandoffPrepare { range, handoff_id }, PrefixSection(src), PrefixSection(_)) => {
+                self.handle_handoff_prepare(range, src, handoff_id, outbox);
+                Ok(())
+            }
+            (HandoffPrepared { range, handoff_id }, PrefixSection(src), PrefixSection(_)) => {
+                self.handle_handoff_prepared(range, src, handoff_id, outbox)
+            }
+            (HandoffCommit { range, handoff_id }, PrefixSection(src), PrefixSection(_)) => {
+                self.handle_handoff_commit(range, src, handoff_id, outbox);
+                Ok(())
+            }
+            (DeliveryAck { msg_id }, src, _) if src.is_multiple() => {
+                outbox.send_event(Event::MessageDelivered { msg_id });
+                Ok(())
+            }
+            (DataInventory { names }, ManagedNode(src), ManagedNode(_)) => {
+                self.handle_data_inventory(names, src, outbox);
+                Ok(())
+            }
+            (RepublishRequest { names }, ManagedNode(src), ManagedNode(_)) => {
+                self.handle_republish_request(names, src);
+                Ok(())
+            }
+            (
+                Bounce {
+                    message,
+                    proof_chain,
+                    their_version,
+                },
+                ManagedNode(src),
+                _,
+            ) => self.handle_bounce(*message, &proof_chain, their_version, src),
+            (
+                UpgradeSignal {
+                    version,
+                    activation_version,
+                },
+                Section(_),
+                PrefixSection(_),
+            ) => {
+                self.vote_for_upgrade(version, activation_version);
+                Ok(())
+            }
+            (SessionRequest { message_id }, src @ Client { .. }, ClientManager(dst_name)) => {
+                self.handle_session_request(src, dst_name, message_id)
+            }
+            (
+                SectionKeyRequest { name, message_id },
+                src @ Client { .. },
+                ClientManager(dst_name),
+            ) => self.handle_section_key_request(src, dst_name, name, message_id),
+            (FailedAudits { auditee }, Section(_), Section(_)) => {
+                self.handle_failed_audits_message(auditee);
+                Ok(())
+            }
             (content, src, dst) => {
                 debug!(
                     "{} Unhandled routing message {:?} from {:?} to {:?}",
@@ -628,6 +1054,266 @@ impl Elder {
         }
     }
 
+    fn handle_membership_statement_request(
+        &mut self,
+        src: XorName,
+        message_id: MessageId,
+    ) -> Result<(), RoutingError> {
+        let statement = match self
+            .chain
+            .our_info()
+            .members()
+            .iter()
+            .find(|pub_id| *pub_id.name() == src)
+            .and_then(|pub_id| self.chain.membership_statement(pub_id))
+        {
+            Some(statement) => statement,
+            None => {
+                debug!(
+                    "{} Not a member of our section: {}. Not sending membership statement.",
+                    self, src
+                );
+                return Ok(());
+            }
+        };
+
+        let src = Authority::Section(src);
+        let dst = Authority::ManagedNode(*statement.member.name());
+        let content = MessageContent::MembershipStatementResponse {
+            statement,
+            message_id,
+        };
+        self.send_routing_message(src, dst, content)
+    }
+
+    // Grants a session to a client whose account our section manages, holding it to the limits
+    // we hold our own clients to.
+    fn handle_session_request(
+        &mut self,
+        client: Authority<XorName>,
+        dst_name: XorName,
+        message_id: MessageId,
+    ) -> Result<(), RoutingError> {
+        match client {
+            Authority::Client { ref client_id, .. } if *client_id.name() == dst_name => (),
+            _ => {
+                debug!(
+                    "{} Session request from {:?} to another account - ignoring.",
+                    self, client
+                );
+                return Ok(());
+            }
+        }
+
+        let limits = self
+            .client_rate_limiter
+            .as_ref()
+            .map_or_else(RateLimits::default, |limiter| *limiter.limits());
+        let src = Authority::ClientManager(dst_name);
+        let content = MessageContent::SessionGranted { limits, message_id };
+        self.send_routing_message(src, client, content)
+    }
+
+    // Tells a client whose account our section manages the latest key we know of the section
+    // managing `name`. The client trusts our key, so our signature vouches for the one we send.
+    fn handle_section_key_request(
+        &mut self,
+        client: Authority<XorName>,
+        dst_name: XorName,
+        name: XorName,
+        message_id: MessageId,
+    ) -> Result<(), RoutingError> {
+        let key_info = match self
+            .chain()
+            .get_their_keys_info()
+            .find(|(prefix, _)| prefix.matches(&name))
+        {
+            Some((_, key_info)) => key_info.clone(),
+            None => {
+                debug!("{} Don't know the key of the section of {}.", self, name);
+                return Ok(());
+            }
+        };
+        let src = Authority::ClientManager(dst_name);
+        let content = MessageContent::SectionKeyResponse {
+            key_info,
+            message_id,
+        };
+        self.send_routing_message(src, client, content)
+    }
+
+    fn handle_membership_statement_response(
+        &mut self,
+        statement: MembershipStatement,
+        dst: XorName,
+        outbox: &mut dyn EventBox,
+    ) {
+        if dst != *self.name() || statement.member != *self.id() {
+            debug!(
+                "{} Ignoring membership statement not about us: {:?}",
+                self, statement
+            );
+            return;
+        }
+        outbox.send_event(Event::MembershipStatement(statement));
+    }
+
+    fn is_our_member(&self, name: &XorName) -> bool {
+        self.chain
+            .our_info()
+            .members()
+            .iter()
+            .any(|pub_id| pub_id.name() == name)
+    }
+
+    fn handle_data_inventory(
+        &mut self,
+        names: BTreeSet<XorName>,
+        src: XorName,
+        outbox: &mut dyn EventBox,
+    ) {
+        if !self.is_our_member(&src) {
+            debug!(
+                "{} Ignoring data inventory from {}, not a member of our section.",
+                self, src
+            );
+            return;
+        }
+        outbox.send_event(Event::DataInventory { src, names });
+    }
+
+    fn handle_republish_request(&mut self, names: BTreeSet<XorName>, src: XorName) {
+        if !self.is_our_member(&src) {
+            debug!(
+                "{} Ignoring republish request from {}, not a member of our section.",
+                self, src
+            );
+            return;
+        }
+        let dropped = self.republish_queue.push(src, names);
+        if dropped > 0 {
+            debug!(
+                "{} Too many pending republish requests - dropped {} names requested by {}.",
+                self, dropped, src
+            );
+        }
+        if self.republish_token.is_none() && !self.republish_queue.is_empty() {
+            self.republish_token = Some(self.timer.schedule(REPUBLISH_INTERVAL));
+        }
+    }
+
+    // Passes on the next batch of republish requests, and keeps the timer running while more are
+    // waiting.
+    fn release_republish_batch(&mut self, outbox: &mut dyn EventBox) {
+        for (dst, names) in self.republish_queue.pop_batch() {
+            outbox.send_event(Event::RepublishRequested { dst, names });
+        }
+        self.republish_token = if self.republish_queue.is_empty() {
+            None
+        } else {
+            Some(self.timer.schedule(REPUBLISH_INTERVAL))
+        };
+    }
+
+    // Sends `names` to `dst` in as many messages of the given kind as needed.
+    fn send_inventory_messages<F>(
+        &mut self,
+        dst: XorName,
+        names: BTreeSet<XorName>,
+        content: F,
+    ) -> Result<(), InterfaceError>
+    where
+        F: Fn(BTreeSet<XorName>) -> MessageContent,
+    {
+        let src = Authority::ManagedNode(*self.name());
+        let dst = Authority::ManagedNode(dst);
+        for chunk in refresh::inventory_chunks(names) {
+            match self.send_routing_message(src, dst, content(chunk)) {
+                Err(RoutingError::Interface(err)) => return Err(err),
+                Err(_) | Ok(()) => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_handoff_prepare(
+        &mut self,
+        range: Prefix<XorName>,
+        src: Prefix<XorName>,
+        handoff_id: MessageId,
+        outbox: &mut dyn EventBox,
+    ) {
+        if self.incoming_handoffs.contains_key(&handoff_id) {
+            return;
+        }
+        let _ = self.incoming_handoffs.insert(handoff_id, (range, src));
+        outbox.send_event(Event::HandoffPrepare {
+            range,
+            from: src,
+            handoff_id,
+        });
+    }
+
+    fn handle_handoff_prepared(
+        &mut self,
+        range: Prefix<XorName>,
+        src: Prefix<XorName>,
+        handoff_id: MessageId,
+        outbox: &mut dyn EventBox,
+    ) -> Result<(), RoutingError> {
+        let to = match self.outgoing_handoffs.get(&handoff_id) {
+            Some(&(our_range, to)) if our_range == range && to.is_compatible(&src) => to,
+            _ => {
+                debug!(
+                    "{} Ignoring confirmation of unknown handoff {:?} of {:?} by {:?}.",
+                    self, handoff_id, range, src
+                );
+                return Ok(());
+            }
+        };
+        let _ = self.outgoing_handoffs.remove(&handoff_id);
+
+        let from = *self.our_prefix();
+        outbox.send_event(Event::HandoffCommit {
+            range,
+            from,
+            to,
+            handoff_id,
+        });
+
+        let src = Authority::PrefixSection(from);
+        let dst = Authority::PrefixSection(to);
+        let content = MessageContent::HandoffCommit { range, handoff_id };
+        self.send_routing_message(src, dst, content)
+    }
+
+    fn handle_handoff_commit(
+        &mut self,
+        range: Prefix<XorName>,
+        src: Prefix<XorName>,
+        handoff_id: MessageId,
+        outbox: &mut dyn EventBox,
+    ) {
+        let from = match self.incoming_handoffs.get(&handoff_id) {
+            Some(&(our_range, from)) if our_range == range && from.is_compatible(&src) => from,
+            _ => {
+                debug!(
+                    "{} Ignoring commit of unknown handoff {:?} of {:?} by {:?}.",
+                    self, handoff_id, range, src
+                );
+                return;
+            }
+        };
+        let _ = self.incoming_handoffs.remove(&handoff_id);
+
+        outbox.send_event(Event::HandoffCommit {
+            range,
+            from,
+            to: *self.our_prefix(),
+            handoff_id,
+        });
+    }
+
     fn handle_ack_message(
         &mut self,
         src_prefix: Prefix<XorName>,
@@ -730,7 +1416,20 @@ impl Elder {
             proof,
             leading_zero_bytes,
         ) {
-            Err(error) => {
+            Err(RoutingError::FailedResourceProofValidation) => {
+                debug!(
+                    "{} Candidate {} failed our challenge. Denying it.",
+                    self, pub_id
+                );
+                self.send_direct_message(
+                    &pub_id,
+                    DirectMessage::JoinDenied {
+                        reason: JoinDenialReason::InvalidResourceProof,
+                        retry_after: None,
+                    },
+                );
+            }
+            Err(error) => {
                 debug!(
                     "{} Failed to verify candidate {}: {:?}",
                     self, pub_id, error
@@ -759,6 +1458,323 @@ impl Elder {
         }
     }
 
+    // Raises the events for user messages, restoring the order in which their source section sent
+    // them if they are sequenced.
+    fn handle_user_message(
+        &mut self,
+        content: UserMessage,
+        seq: Option<u64>,
+        src: Authority<XorName>,
+        dst: Authority<XorName>,
+        outbox: &mut dyn EventBox,
+    ) {
+        let seq = match seq {
+            Some(seq) => seq,
+            None => {
+                self.raise_user_message(content, src, dst, outbox);
+                return;
+            }
+        };
+
+        let (ready, out_of_order) = self.msg_sequencer.receive(src, dst, seq, content);
+        if let Some(OutOfOrder {
+            src,
+            dst,
+            expected,
+            received,
+        }) = out_of_order
+        {
+            debug!(
+                "{} Message {} from {:?} to {:?} out of order: expected {}.",
+                self, received, src, dst, expected
+            );
+            outbox.send_event(Event::OutOfOrderDetected {
+                src,
+                dst,
+                expected,
+                received,
+            });
+        }
+        for content in ready {
+            self.raise_user_message(content, src, dst, outbox);
+        }
+    }
+
+    // Passes the user message through the middleware pipeline, and raises it as an event unless it
+    // was dropped. This happens after sequencing, so a dropped message doesn't leave a gap.
+    fn raise_user_message(
+        &mut self,
+        content: UserMessage,
+        src: Authority<XorName>,
+        dst: Authority<XorName>,
+        outbox: &mut dyn EventBox,
+    ) {
+        let content = match content {
+            UserMessage::Request(Request::Sealed { request, msg_id }) => {
+                match request.open(&self.full_id, &msg_id) {
+                    Ok(request) => UserMessage::Request(request),
+                    Err(error) => {
+                        // It was sealed to a key we don't hold a share of. The client seals it
+                        // again once it learns our current key.
+                        debug!(
+                            "{} Can't open {:?} from {:?} to {:?}: {:?}",
+                            self, request, src, dst, error
+                        );
+                        return;
+                    }
+                }
+            }
+            content => content,
+        };
+        let verdict = self.network_service.process_user_message(&MessageContext {
+            direction: Direction::Incoming,
+            src,
+            dst,
+            content: &content,
+        });
+        if verdict == Verdict::Drop {
+            debug!(
+                "{} Middleware dropped {:?} from {:?} to {:?}",
+                self, content, src, dst
+            );
+            return;
+        }
+        if let (UserMessage::Request(_), Authority::Client { .. }) = (&content, src) {
+            self.network_service.metrics().inc_client_requests_served();
+        }
+        if let UserMessage::Request(ref request) = content {
+            self.send_delivery_ack(*request.message_id(), src, dst);
+        }
+        outbox.send_event(content.into_event(src, dst));
+    }
+
+    // Remembers a `RelayedGet` from one of our clients, so that the response to the GET the vault
+    // sends on the client's behalf is forwarded to the client rather than delivered to the vault.
+    fn note_relayed_get(
+        &mut self,
+        content: &UserMessage,
+        src: Authority<XorName>,
+        dst: Authority<XorName>,
+    ) {
+        if let (
+            UserMessage::Request(Request::RelayedGet {
+                dst: target @ Authority::NaeManager(_),
+                request,
+                msg_id,
+            }),
+            Authority::Client { .. },
+            Authority::ClientManager(_),
+        ) = (content, src, dst)
+        {
+            if !request.is_get() || request.message_id() != msg_id {
+                debug!("{} Invalid RelayedGet {:?} from {:?}", self, content, src);
+                return;
+            }
+            let _ = self.relayed_gets.insert(*msg_id, (src, *target));
+        }
+    }
+
+    // If `signed_msg` is the response to a GET relayed on behalf of one of our clients, sends it to
+    // the client with its section signature, so the client can verify where it came from. Only the
+    // member of our section closest to the client manager sends it, as the combined signatures
+    // differ between members and couldn't be accumulated.
+    fn forward_relayed_get_response(&mut self, signed_msg: &SignedRoutingMessage) -> bool {
+        let routing_msg = signed_msg.routing_message();
+        let msg_id = match routing_msg.content {
+            MessageContent::UserMessage {
+                content: UserMessage::Response(ref response),
+                ..
+            } => *response.message_id(),
+            _ => return false,
+        };
+        let client = match self.relayed_gets.get(&msg_id) {
+            Some(&(client, target)) if target == routing_msg.src => client,
+            _ => return false,
+        };
+        let _ = self.relayed_gets.remove(&msg_id);
+
+        let manager_name = routing_msg.dst.name();
+        let closest = self
+            .chain
+            .our_section()
+            .into_iter()
+            .min_by(|lhs, rhs| manager_name.cmp_distance(lhs, rhs));
+        if closest != Some(*self.name()) {
+            return true;
+        }
+
+        let response = Response::RelayedGet {
+            res: Ok(RelayedResponse::new(signed_msg.clone())),
+            msg_id,
+        };
+        let src = Authority::ManagedNode(*self.name());
+        if let Err(error) = self.send_user_message(
+            src,
+            client,
+            UserMessage::Response(response),
+            CLIENT_GET_PRIORITY,
+        ) {
+            debug!(
+                "{} Failed to forward relayed GET response {:?}: {:?}",
+                self, msg_id, error
+            );
+        }
+        true
+    }
+
+    /// Refuses the request with `ClientError::RateLimitExceeded` if it is from one of our clients
+    /// which exceeded its limit for this kind of request. Returns whether it was refused.
+    fn refuse_if_rate_limited(
+        &mut self,
+        routing_msg: &RoutingMessage,
+    ) -> Result<bool, RoutingError> {
+        let client_id = match routing_msg.src {
+            Authority::Client {
+                client_id,
+                proxy_node_name,
+            } if proxy_node_name == *self.name() => client_id,
+            _ => return Ok(false),
+        };
+        let request = match routing_msg.content {
+            MessageContent::UserMessage {
+                content: UserMessage::Request(ref request),
+                ..
+            } => request,
+            _ => return Ok(false),
+        };
+        let limiter = match self.client_rate_limiter {
+            Some(ref mut limiter) => limiter,
+            None => return Ok(false),
+        };
+        if limiter.try_acquire(client_id, RequestKind::of(request)) {
+            return Ok(false);
+        }
+
+        debug!(
+            "{} Client {} exceeded its rate limit - refusing {:?}",
+            self, client_id, request
+        );
+        if let Some(response) = request.error_response(ClientError::RateLimitExceeded) {
+            let priority = response.priority();
+            let src = Authority::ManagedNode(*self.name());
+            let msg = UserMessage::Response(response);
+            self.send_user_message(src, routing_msg.src, msg, priority)?;
+        }
+        Ok(true)
+    }
+
+    /// Charges a mutation request to the client account our section manages it for, and votes for
+    /// debiting the account. If the account can't afford it, refuses the request with
+    /// `ClientError::LowBalance` instead. Returns whether it was refused.
+    fn refuse_if_low_balance(
+        &mut self,
+        content: &UserMessage,
+        src: Authority<XorName>,
+        dst: Authority<XorName>,
+    ) -> Result<bool, RoutingError> {
+        let (request, account) = match (content, src, dst) {
+            (
+                UserMessage::Request(request),
+                Authority::Client { .. },
+                Authority::ClientManager(account),
+            ) => (request, account),
+            _ => return Ok(false),
+        };
+        let amount = ClientManager::cost(request);
+        if amount == 0 {
+            return Ok(false);
+        }
+
+        let msg_id = *request.message_id();
+        match self
+            .client_manager
+            .try_charge(self.chain.accounts(), account, msg_id, amount)
+        {
+            Ok(true) => {
+                self.vote_for_event(NetworkEvent::AccountDebit(AccountPayload {
+                    account,
+                    amount,
+                    msg_id,
+                }));
+                Ok(false)
+            }
+            Ok(false) => Ok(false),
+            Err(error) => {
+                debug!(
+                    "{} Account {} can't afford {:?} - refusing.",
+                    self, account, request
+                );
+                if let Some(response) = request.error_response(error) {
+                    let priority = response.priority();
+                    self.send_user_message(dst, src, UserMessage::Response(response), priority)?;
+                }
+                Ok(true)
+            }
+        }
+    }
+
+    // Holds the requests of our client `pub_id` to the limits granted by `token`, if it was issued
+    // to it by a section we trust. Returns whether the token was accepted.
+    fn accept_session_token(&mut self, pub_id: &PublicId, token: &SessionToken) -> bool {
+        let limits = match token.verify() {
+            Ok((client_id, limits)) if client_id == *pub_id => limits,
+            Ok((client_id, _)) => {
+                debug!(
+                    "{} Session token of {} presented by {} - refusing.",
+                    self, client_id, pub_id
+                );
+                return false;
+            }
+            Err(error) => {
+                debug!(
+                    "{} Invalid session token from {}: {:?}",
+                    self, pub_id, error
+                );
+                return false;
+            }
+        };
+        if !token.is_trusted(&self.chain) {
+            debug!(
+                "{} Session token of {} signed by a key we don't know - refusing.",
+                self, pub_id
+            );
+            return false;
+        }
+
+        if let Some(ref mut limiter) = self.client_rate_limiter {
+            limiter.set_client_limits(*pub_id, limits);
+        }
+        true
+    }
+
+    fn handle_resume_session(&mut self, pub_id: PublicId, token: &SessionToken) {
+        let resumed = self.accept_session_token(&pub_id, token);
+        if resumed {
+            debug!("{} Client {} resumed its session.", self, pub_id);
+        }
+        self.send_direct_message(&pub_id, DirectMessage::ResumeSessionResponse(resumed));
+    }
+
+    // Every member of the destination section sends the acknowledgement, so it reaches the sender
+    // signed by the section. Requests to single nodes aren't acknowledged.
+    fn send_delivery_ack(
+        &mut self,
+        msg_id: MessageId,
+        src: Authority<XorName>,
+        dst: Authority<XorName>,
+    ) {
+        if !dst.is_multiple() {
+            return;
+        }
+        let content = MessageContent::DeliveryAck { msg_id };
+        if let Err(error) = self.send_routing_message(dst, src, content) {
+            debug!(
+                "{} Failed to acknowledge delivery of {:?} to {:?}: {:?}",
+                self, msg_id, src, error
+            );
+        }
+    }
+
     fn respond_from_cache(&mut self, routing_msg: &RoutingMessage) -> Result<bool, RoutingError> {
         let content = if let MessageContent::UserMessage { ref content, .. } = routing_msg.content {
             if content.is_cacheable() {
@@ -772,7 +1788,11 @@ impl Elder {
 
         match content {
             UserMessage::Request(request) => {
-                if let Some(response) = self.response_cache.get(request) {
+                let cached = self.response_cache.get(request);
+                self.network_service()
+                    .metrics()
+                    .inc_relay_cache_lookups(cached.is_some());
+                if let Some(response) = cached {
                     debug!("{} Found cached response to {:?}", self, request);
 
                     let priority = response.priority();
@@ -819,6 +1839,19 @@ impl Elder {
             peer_type
         );
 
+        if self.shutdown_token.is_some() {
+            debug!(
+                "{} - Peer {:?} rejected: We are shutting down.",
+                self, pub_id
+            );
+            self.send_direct_message(
+                &pub_id,
+                DirectMessage::BootstrapResponse(Err(BootstrapResponseError::ShuttingDown)),
+            );
+            self.disconnect_peer(&pub_id);
+            return Ok(());
+        }
+
         if let Some(ip) = client_ip {
             // Check banned IPs.
             if self.banned_client_ips.contains_key(&ip) {
@@ -914,6 +1947,15 @@ impl Elder {
             return;
         }
 
+        if !self.is_joiner_allowed(old_pub_id, Some(new_pub_id)) {
+            warn!(
+                "{} Candidate {}->{} isn't allowed to join, so dropping it.",
+                self, old_pub_id, new_pub_id
+            );
+            self.disconnect_peer(new_pub_id);
+            return;
+        }
+
         let (difficulty, target_size) = if self.disable_resource_proof
             || self.is_peer_hard_coded(new_pub_id)
             || self.peer_mgr.is_or_was_joining_node(new_pub_id)
@@ -921,7 +1963,7 @@ impl Elder {
             (0, 1)
         } else {
             (
-                RESOURCE_PROOF_DIFFICULTY,
+                self.chain.join_difficulty(),
                 RESOURCE_PROOF_TARGET_SIZE / (self.chain.our_section().len() + 1),
             )
         };
@@ -991,6 +2033,22 @@ impl Elder {
         }
     }
 
+    // Checks the candidate's IDs, and its IP if we are connected to it, against the join filter
+    // our section agreed on.
+    fn is_joiner_allowed(&self, old_pub_id: &PublicId, new_pub_id: Option<&PublicId>) -> bool {
+        let ip = new_pub_id
+            .and_then(|pub_id| self.peer_map.get_connection_info(pub_id))
+            .map(|conn_info| match *conn_info {
+                ConnectionInfo::Client { peer_addr } => peer_addr.ip(),
+                ConnectionInfo::Node { ref node_info } => node_info.peer_addr.ip(),
+            });
+        let ids: Vec<_> = iter::once(JoinerId::Node(*old_pub_id))
+            .chain(new_pub_id.map(|pub_id| JoinerId::Node(*pub_id)))
+            .chain(ip.map(JoinerId::Ip))
+            .collect();
+        self.chain.join_filter().is_allowed(&ids)
+    }
+
     fn is_candidate_info_valid(
         &self,
         old_pub_id: &PublicId,
@@ -1015,6 +2073,17 @@ impl Elder {
             );
             return false;
         }
+        if new_pub_id.age() != old_pub_id.age().saturating_add(1) {
+            debug!(
+                "{} CandidateInfo from {}->{} has invalid age {} after {}.",
+                self,
+                old_pub_id,
+                new_pub_id,
+                new_pub_id.age(),
+                old_pub_id.age()
+            );
+            return false;
+        }
 
         true
     }
@@ -1038,6 +2107,22 @@ impl Elder {
             return Err(RoutingError::InvalidDestination);
         }
 
+        let old_client_auth = Authority::Client {
+            client_id: relocating_node_id,
+            proxy_node_name: proxy_name,
+        };
+        self.send_expect_candidate(relocating_node_id, old_client_auth, message_id)
+    }
+
+    // Sent by X; From X -> Y
+    // Context: `old_public_id` is relocated from our section, or joins the network through it.
+    fn send_expect_candidate(
+        &mut self,
+        old_public_id: PublicId,
+        old_client_auth: Authority<XorName>,
+        message_id: MessageId,
+    ) -> Result<(), RoutingError> {
+        let dst_name = *old_public_id.name();
         let close_section = self
             .chain
             .close_names(&dst_name)
@@ -1049,12 +2134,9 @@ impl Elder {
 
         // From X -> Y; Send to close section of the relocated name
         let request_content = MessageContent::ExpectCandidate {
-            old_public_id: relocating_node_id,
-            old_client_auth: Authority::Client {
-                client_id: relocating_node_id,
-                proxy_node_name: proxy_name,
-            },
-            message_id: message_id,
+            old_public_id,
+            old_client_auth,
+            message_id,
         };
 
         let src = Authority::Section(dst_name);
@@ -1062,6 +2144,74 @@ impl Elder {
         self.send_routing_message(src, dst, request_content)
     }
 
+    // Received by a relocated member of X; From Y -> X
+    fn handle_relocate_response(
+        &mut self,
+        target_interval: XorTargetInterval,
+        section: (Prefix<XorName>, BTreeSet<PublicId>),
+    ) {
+        if !self.is_relocating || self.relocated_to.is_some() {
+            debug!("{} Ignoring unexpected RelocateResponse.", self);
+            return;
+        }
+
+        let new_id = FullId::within_range(&target_interval.into())
+            .with_age(self.id().age().saturating_add(1));
+        if !section.0.matches(new_id.public_id().name()) {
+            log_or_panic!(
+                LogLevel::Error,
+                "{} Invalid name chosen for {:?}: {}",
+                self,
+                section.0,
+                new_id.public_id()
+            );
+        }
+        self.relocated_to = Some((new_id, section));
+    }
+
+    // Votes for a refresh of the members who joined our section with the given version of our
+    // section info.
+    fn vote_for_refresh(&mut self, version: u64) {
+        let new_members = self.chain.our_new_members();
+        if !new_members.is_empty() {
+            self.vote_for_event(NetworkEvent::Refresh(RefreshPayload {
+                version,
+                new_members,
+            }));
+        }
+    }
+
+    // Votes for the members who became elders, or stopped being elders, with the given version of
+    // our section info.
+    fn vote_for_elder_changes(&mut self, version: u64) {
+        let (promoted, demoted) = self.chain.our_elder_changes();
+        for pub_id in promoted {
+            self.vote_for_event(NetworkEvent::Promote(ElderChangePayload {
+                pub_id,
+                version,
+            }));
+        }
+        for pub_id in demoted {
+            self.vote_for_event(NetworkEvent::Demote(ElderChangePayload { pub_id, version }));
+        }
+    }
+
+    fn pending_transition(&mut self, outbox: &mut dyn EventBox) -> Transition {
+        if self.shutdown_acknowledged {
+            info!("{} Shutdown complete.", self);
+            outbox.send_event(Event::ShutdownComplete);
+            return Transition::Terminate;
+        }
+
+        match self.relocated_to.take() {
+            Some((new_id, our_section)) => Transition::IntoBootstrapping {
+                new_id,
+                our_section,
+            },
+            None => Transition::Stay,
+        }
+    }
+
     // Received by Y; From X -> Y
     // Context: a node is joining our section. Vote `ExpectCandidate`.
     fn handle_expect_candidate(
@@ -1166,27 +2316,203 @@ impl Elder {
         self.send_routing_message(src, dst, content)
     }
 
+    // Send JoinDenied to the candidate instead of a RelocateResponse.
+    fn send_join_denial(
+        &mut self,
+        vote: ExpectCandidatePayload,
+        reason: JoinDenialReason,
+        retry_after: Option<Duration>,
+    ) -> Result<(), RoutingError> {
+        let src = Authority::Section(vote.dst_name);
+        let dst = vote.old_client_auth;
+        let content = MessageContent::JoinDenied {
+            reason,
+            retry_after,
+            message_id: vote.message_id,
+        };
+
+        info!(
+            "{} Our section with {:?} denied candidate with old name {}: {}",
+            self,
+            self.our_prefix(),
+            vote.old_public_id,
+            reason
+        );
+
+        self.send_routing_message(src, dst, content)
+    }
+
     fn update_our_knowledge(&mut self, signed_msg: &SignedRoutingMessage) {
         if signed_msg.routing_message().src.is_client() {
             return;
         }
 
-        let key_info = if let Some(key_info) = signed_msg.source_section_key_info() {
-            key_info
-        } else {
-            return;
+        let key_info = if let Some(key_info) = signed_msg.source_section_key_info() {
+            key_info
+        } else {
+            return;
+        };
+
+        if self.chain.is_new_their_key(key_info) {
+            self.vote_for_event(NetworkEvent::TheirKeyInfo(key_info.clone()));
+        }
+    }
+
+    // Returns a message from a section whose proof chain we don't trust to that section, so it can
+    // resend it proved from a key we know. We forget the message, so the resent copy isn't
+    // filtered.
+    fn bounce_untrusted_message(&mut self, signed_msg: SignedRoutingMessage) {
+        let src_prefix = match signed_msg.proof_chain() {
+            Some(proof_chain) => *proof_chain.last_public_key_info().prefix(),
+            None => return,
+        };
+        self.routing_msg_filter
+            .forget_incoming(signed_msg.routing_message());
+
+        let (proof_chain, their_version) = self.chain.section_list_for(&src_prefix);
+        let src = Authority::ManagedNode(*self.name());
+        let dst = signed_msg.routing_message().src;
+        let content = MessageContent::Bounce {
+            message: Box::new(signed_msg),
+            proof_chain,
+            their_version,
+        };
+        if let Err(error) = self.send_routing_message(src, dst, content) {
+            debug!("{} Failed to bounce untrusted message: {:?}", self, error);
+        }
+    }
+
+    // Updates our knowledge of the bouncing section and resends the bounced message, proved from
+    // the key it knows, if we can prove it from an older key than before.
+    fn handle_bounce(
+        &mut self,
+        mut signed_msg: SignedRoutingMessage,
+        proof_chain: &SectionProofChain,
+        their_version: u64,
+        src: XorName,
+    ) -> Result<(), RoutingError> {
+        let src_prefix = *proof_chain.last_public_key_info().prefix();
+        if !src_prefix.matches(&src) {
+            return Err(RoutingError::InvalidMessage);
+        }
+        let hash = match msg_hash(signed_msg.routing_message()) {
+            Some(hash) if self.sent_proved_msgs.contains_key(&hash) => hash,
+            _ => {
+                debug!(
+                    "{} Ignoring bounce of a message we didn't send: {:?}",
+                    self, signed_msg
+                );
+                return Ok(());
+            }
+        };
+        for event in self
+            .chain
+            .section_list_votes(&src_prefix, proof_chain, their_version)
+        {
+            self.vote_for_event(event);
+        }
+
+        let old_first_version = match signed_msg.proof_chain() {
+            Some(old_proof_chain) => old_proof_chain.first_version(),
+            None => return Ok(()),
+        };
+        let new_proof_chain = self.chain.prove_from_version(their_version);
+        let new_first_version = new_proof_chain.first_version();
+        if new_first_version >= old_first_version || !signed_msg.reprove(new_proof_chain) {
+            debug!(
+                "{} Can't prove bounced message {:?} from version {}.",
+                self, signed_msg, their_version
+            );
+            return Ok(());
+        }
+
+        // Each member of the bouncing section may bounce it: only resend it once per proof.
+        if let Some(resent_versions) = self.sent_proved_msgs.get_mut(&hash) {
+            if !resent_versions.insert(new_first_version) {
+                return Ok(());
+            }
+        }
+
+        // The outgoing filter would drop the message, as we sent it before.
+        let (targets, dg_size) = self.get_targets(signed_msg.routing_message())?;
+        let message = self.to_hop_message(signed_msg)?;
+        self.send_message_to_targets(&targets, dg_size, message);
+        Ok(())
+    }
+
+    // Sends our section list to a random connected elder of each neighbouring section.
+    fn send_section_lists(&mut self) {
+        let mut rng = rand::thread_rng();
+        let targets: Vec<_> = self
+            .chain
+            .neighbour_infos()
+            .filter_map(|info| {
+                let connected: Vec<_> = info
+                    .members()
+                    .iter()
+                    .filter(|pub_id| self.peer_mgr.is_connected(pub_id))
+                    .cloned()
+                    .collect();
+                rng.choose(&connected)
+                    .map(|pub_id| (*pub_id, *info.prefix()))
+            })
+            .collect();
+
+        for (pub_id, prefix) in targets {
+            let (proof_chain, their_version) = self.chain.section_list_for(&prefix);
+            self.send_direct_message(
+                &pub_id,
+                DirectMessage::SectionList {
+                    proof_chain,
+                    their_version,
+                },
+            );
+        }
+    }
+
+    fn handle_section_list(
+        &mut self,
+        pub_id: PublicId,
+        proof_chain: &SectionProofChain,
+        their_version: u64,
+    ) {
+        let src_prefix = match self
+            .chain
+            .neighbour_infos()
+            .find(|info| info.members().contains(&pub_id))
+        {
+            Some(info) => *info.prefix(),
+            None => {
+                debug!(
+                    "{} Ignoring section list from {}, which isn't a neighbouring elder.",
+                    self, pub_id
+                );
+                return;
+            }
         };
 
-        let new_key_info = self.chain.get_their_keys_info().any(|(_, info)| {
-            *info.version() < *key_info.version() && info.prefix().is_compatible(key_info.prefix())
-        });
-
-        if new_key_info {
-            self.vote_for_event(NetworkEvent::TheirKeyInfo(key_info.clone()));
+        for event in self
+            .chain
+            .section_list_votes(&src_prefix, proof_chain, their_version)
+        {
+            self.vote_for_event(event);
         }
     }
 
-    fn handle_neighbour_info(&mut self, sec_info: SectionInfo) -> Result<(), RoutingError> {
+    fn handle_neighbour_info(
+        &mut self,
+        sec_info: SectionInfo,
+        outbox: &mut dyn EventBox,
+    ) -> Result<(), RoutingError> {
+        if let Some(infos) = self.chain.check_conflicting_info(&sec_info) {
+            let event = Event::ConflictingSectionInfo {
+                prefix: *sec_info.prefix(),
+                version: *sec_info.version(),
+                infos,
+            };
+            self.send_event(event, outbox);
+            return Ok(());
+        }
         if self.chain.is_new_neighbour(&sec_info) {
             self.vote_for_event(sec_info.into_network_event());
         }
@@ -1257,6 +2583,8 @@ impl Elder {
     }
 
     // ----- Send Functions -----------------------------------------------------------------------
+    // Sends a user message. One from our section is only sent once our section agreed on its
+    // sequence number, so all elders sign the same one.
     fn send_user_message(
         &mut self,
         src: Authority<XorName>,
@@ -1264,7 +2592,45 @@ impl Elder {
         content: UserMessage,
         priority: u8,
     ) -> Result<(), RoutingError> {
-        self.send_routing_message(src, dst, MessageContent::UserMessage { content, priority })
+        if !src.is_multiple() {
+            return self.send_routing_message(
+                src,
+                dst,
+                MessageContent::UserMessage {
+                    content,
+                    priority,
+                    seq: None,
+                },
+            );
+        }
+
+        let digest = safe_crypto::hash(&serialisation::serialise(&(
+            &src, &dst, &content, priority,
+        ))?);
+        if let Some(seq) = self.sequenced_msgs.remove(&digest) {
+            return self.send_routing_message(
+                src,
+                dst,
+                MessageContent::UserMessage {
+                    content,
+                    priority,
+                    seq: Some(seq),
+                },
+            );
+        }
+
+        if self
+            .unsequenced_msgs
+            .insert(digest, (src, dst, content, priority))
+            .is_none()
+        {
+            self.vote_for_event(NetworkEvent::SequenceMessage(SequencePayload {
+                src,
+                dst,
+                digest,
+            }));
+        }
+        Ok(())
     }
 
     // Send signed_msg on route. Hop is the name of the peer we received this from, or our name if
@@ -1273,6 +2639,10 @@ impl Elder {
         &mut self,
         signed_msg: &mut SignedRoutingMessage,
     ) -> Result<(), RoutingError> {
+        if self.hold_if_dst_unknown(signed_msg) {
+            return Ok(());
+        }
+
         let dst = signed_msg.routing_message().dst;
 
         if let Authority::Client { ref client_id, .. } = dst {
@@ -1302,6 +2672,15 @@ impl Elder {
 
         self.send_message_to_targets(&targets, dg_size, message);
 
+        // Remember it, so we can resend it if its destination bounces it.
+        if signed_msg.proof_chain().is_some() {
+            if let Some(hash) = msg_hash(signed_msg.routing_message()) {
+                if !self.sent_proved_msgs.contains_key(&hash) {
+                    let _ = self.sent_proved_msgs.insert(hash, BTreeSet::new());
+                }
+            }
+        }
+
         // we've seen this message - don't handle it again if someone else sends it to us
         let _ = self
             .routing_msg_filter
@@ -1322,6 +2701,9 @@ impl Elder {
             if self.filter_outgoing_routing_msg(signed_msg.routing_message(), pub_id) {
                 return Ok(());
             }
+            if let MessageContent::SessionGranted { .. } = signed_msg.routing_message().content {
+                let _ = self.accept_session_token(pub_id, &SessionToken::new(signed_msg.clone()));
+            }
 
             let message = self.to_hop_message(signed_msg.clone())?;
             self.send_message(pub_id, message);
@@ -1342,7 +2724,7 @@ impl Elder {
         use crate::Authority::*;
 
         let list: Vec<XorName> = match *src {
-            ClientManager(_) | NaeManager(_) | NodeManager(_) | Section(_) => self
+            ClientManager(_) | NaeManager(_) | NodeManager(_) | Group { .. } | Section(_) => self
                 .chain
                 .our_section()
                 .into_iter()
@@ -1394,7 +2776,11 @@ impl Elder {
                 .map(|pub_id| pub_id.name())
                 .chain(iter::once(self.name()))
                 .collect_vec();
-            let (targets, dg_size) = self.chain.targets(&routing_msg.dst, &conn_peers)?;
+            let (targets, dg_size) = self.chain.targets(
+                &routing_msg.dst,
+                &conn_peers,
+                routing_msg.content.delivery_scope(),
+            )?;
             Ok((
                 targets
                     .into_iter()
@@ -1494,11 +2880,15 @@ impl Elder {
     }
 
     fn remove_expired_peers(&mut self) {
-        if self.peer_mgr.expired_candidate_once() {
+        let candidate_timeout = self.chain.network_params().timeouts.candidate_approval;
+        if self.peer_mgr.expired_candidate_once(candidate_timeout) {
             if let Some(expired_id) = self.chain.candidate_old_public_id().cloned() {
                 self.vote_for_event(NetworkEvent::PurgeCandidate(expired_id));
             }
         }
+        for expired_id in self.chain.take_expired_queued_candidates() {
+            self.vote_for_event(NetworkEvent::PurgeCandidate(expired_id));
+        }
 
         for pub_id in self.peer_mgr.remove_expired_peers() {
             debug!("{} Disconnecting from timed out peer {:?}", self, pub_id);
@@ -1592,6 +2982,30 @@ impl Base for Elder {
         self.chain.min_sec_size()
     }
 
+    fn pending_votes(&self) -> Vec<PendingVote> {
+        self.chain.pending_votes()
+    }
+
+    fn network_epoch(&self) -> Option<NetworkEpoch> {
+        Some(self.chain.network_epoch())
+    }
+
+    fn peer_scores(&self) -> BTreeMap<PublicId, u64> {
+        self.chain.peer_scores().clone()
+    }
+
+    fn account_info(&self, account: &XorName) -> Option<AccountInfo> {
+        if self.chain.our_prefix().matches(account) {
+            Some(self.chain.accounts().get(account))
+        } else {
+            None
+        }
+    }
+
+    fn section_contacts(&self) -> Option<SectionContacts> {
+        Some(SectionContacts::new(self.chain.our_info(), &self.peer_map))
+    }
+
     fn peer_map(&self) -> &PeerMap {
         &self.peer_map
     }
@@ -1607,18 +3021,187 @@ impl Base for Elder {
         content: UserMessage,
         priority: u8,
     ) -> Result<(), InterfaceError> {
+        if self.network_service.is_congested() {
+            debug!(
+                "{} Refusing {:?} from {:?} to {:?}: outgoing queues are full.",
+                self, content, src, dst
+            );
+            return Err(InterfaceError::Busy);
+        }
+        let verdict = self.network_service.process_user_message(&MessageContext {
+            direction: Direction::Outgoing,
+            src,
+            dst,
+            content: &content,
+        });
+        if verdict == Verdict::Drop {
+            debug!(
+                "{} Middleware rejected {:?} from {:?} to {:?}",
+                self, content, src, dst
+            );
+            return Err(InterfaceError::Rejected);
+        }
         match self.send_user_message(src, dst, content, priority) {
             Err(RoutingError::Interface(err)) => Err(err),
             Err(_) | Ok(()) => Ok(()),
         }
     }
 
+    fn request_membership_statement(&mut self) -> Result<(), InterfaceError> {
+        let src = Authority::ManagedNode(*self.name());
+        let dst = Authority::Section(*self.name());
+        let content = MessageContent::MembershipStatementRequest {
+            message_id: MessageId::new(),
+        };
+        match self.send_routing_message(src, dst, content) {
+            Err(RoutingError::Interface(err)) => Err(err),
+            Err(_) | Ok(()) => Ok(()),
+        }
+    }
+
+    fn shutdown(&mut self) -> Result<(), InterfaceError> {
+        if self.shutdown_token.is_some() {
+            return Ok(());
+        }
+
+        info!("{} Shutting down - voting ourselves offline.", self);
+        let our_id = *self.id();
+        self.vote_for_event(NetworkEvent::Offline(our_id));
+        self.shutdown_token = Some(self.timer.schedule(SHUTDOWN_TIMEOUT));
+
+        // Disconnect our clients now, so they fail over to another proxy while our section can
+        // still answer the requests they resend.
+        let clients: Vec<_> = self
+            .peer_mgr
+            .connected_peers()
+            .filter(|(_, peer)| peer.is_client())
+            .map(|(pub_id, _)| *pub_id)
+            .collect();
+        for pub_id in clients {
+            debug!("{} Disconnecting client {} to fail it over.", self, pub_id);
+            self.disconnect_peer(&pub_id);
+        }
+        Ok(())
+    }
+
+    fn signal_upgrade(
+        &mut self,
+        version: u16,
+        activation_version: u64,
+    ) -> Result<(), InterfaceError> {
+        self.vote_for_upgrade(version, activation_version);
+        Ok(())
+    }
+
+    fn credit_account(
+        &mut self,
+        account: XorName,
+        amount: u64,
+        msg_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        self.vote_for_event(NetworkEvent::AccountCredit(AccountPayload {
+            account,
+            amount,
+            msg_id,
+        }));
+        Ok(())
+    }
+
+    fn vote_for_join_filter(&mut self, id: JoinerId, allow: bool) -> Result<(), InterfaceError> {
+        if allow {
+            self.vote_for_event(NetworkEvent::Allow(id));
+        } else {
+            self.vote_for_event(NetworkEvent::Deny(id));
+        }
+        Ok(())
+    }
+
+    fn start_handoff(
+        &mut self,
+        range: Prefix<XorName>,
+        to: Prefix<XorName>,
+        handoff_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        let _ = self.outgoing_handoffs.insert(handoff_id, (range, to));
+        let src = Authority::PrefixSection(*self.our_prefix());
+        let dst = Authority::PrefixSection(to);
+        let content = MessageContent::HandoffPrepare { range, handoff_id };
+        match self.send_routing_message(src, dst, content) {
+            Err(RoutingError::Interface(err)) => Err(err),
+            Err(_) | Ok(()) => Ok(()),
+        }
+    }
+
+    fn confirm_handoff(&mut self, handoff_id: MessageId) -> Result<(), InterfaceError> {
+        let (range, from) = match self.incoming_handoffs.get(&handoff_id) {
+            Some(&handoff) => handoff,
+            None => {
+                warn!(
+                    "{} - Cannot confirm handoff {:?} - not prepared by another section.",
+                    self, handoff_id
+                );
+                return Err(InterfaceError::InvalidState);
+            }
+        };
+        let src = Authority::PrefixSection(*self.our_prefix());
+        let dst = Authority::PrefixSection(from);
+        let content = MessageContent::HandoffPrepared { range, handoff_id };
+        match self.send_routing_message(src, dst, content) {
+            Err(RoutingError::Interface(err)) => Err(err),
+            Err(_) | Ok(()) => Ok(()),
+        }
+    }
+
+    fn send_data_inventory(
+        &mut self,
+        dst: XorName,
+        names: BTreeSet<XorName>,
+    ) -> Result<(), InterfaceError> {
+        self.send_inventory_messages(dst, names, |names| MessageContent::DataInventory { names })
+    }
+
+    fn request_republish(
+        &mut self,
+        dst: XorName,
+        names: BTreeSet<XorName>,
+    ) -> Result<(), InterfaceError> {
+        self.send_inventory_messages(dst, names, |names| MessageContent::RepublishRequest {
+            names,
+        })
+    }
+
+    fn report_failed_audits(
+        &mut self,
+        offenders: BTreeSet<Authority<XorName>>,
+    ) -> Result<BTreeSet<Authority<XorName>>, InterfaceError> {
+        let version = *self.chain.our_info().version();
+        for auditee in &offenders {
+            self.vote_for_event(NetworkEvent::FailedAudits(AuditPayload {
+                auditee: *auditee,
+                version,
+            }));
+        }
+        Ok(offenders)
+    }
+
     fn handle_timeout(&mut self, token: u64, outbox: &mut dyn EventBox) -> Transition {
         if self.tick_timer_token == token {
             self.tick_timer_token = self.timer.schedule(TICK_TIMEOUT);
             self.remove_expired_peers();
             self.proxy_load_amount = 0;
             self.update_peer_states(outbox);
+            self.report_stalled_votes(outbox);
+            self.report_instability(outbox);
+            self.purge_stale_votes(outbox);
+            self.report_evicted_events(outbox);
+            self.vote_out_malicious_peers();
+            self.vote_for_join_difficulty();
+            self.chain.compact_neighbour_infos();
+            self.report_neighbour_changes(outbox);
+            self.handle_held_messages();
+            for (src, dst, content) in self.msg_sequencer.release_expired() {
+                self.raise_user_message(content, src, dst, outbox);
+            }
             outbox.send_event(Event::TimerTicked);
         } else if self.candidate_status_token == token {
             self.candidate_status_token = self.timer.schedule(CANDIDATE_STATUS_INTERVAL);
@@ -1633,6 +3216,18 @@ impl Base for Elder {
             }
 
             self.send_parsec_gossip(None);
+        } else if self.section_list_token == token {
+            self.section_list_token = self.timer.schedule(SECTION_LIST_INTERVAL);
+            self.send_section_lists();
+        } else if self.republish_token == Some(token) {
+            self.release_republish_batch(outbox);
+        } else if self.shutdown_token == Some(token) {
+            warn!(
+                "{} Section didn't acknowledge our shutdown in time - terminating anyway.",
+                self
+            );
+            self.shutdown_acknowledged = true;
+            return self.pending_transition(outbox);
         }
 
         Transition::Stay
@@ -1640,7 +3235,7 @@ impl Base for Elder {
 
     fn finish_handle_action(&mut self, outbox: &mut dyn EventBox) -> Transition {
         self.handle_routing_messages(outbox);
-        Transition::Stay
+        self.pending_transition(outbox)
     }
 
     fn handle_bootstrapped_to(&mut self, node_info: NodeInfo) -> Transition {
@@ -1657,6 +3252,9 @@ impl Base for Elder {
         if self.peer_mgr.get_peer(&pub_id).is_none() {
             return Transition::Stay;
         }
+        if let Some(ref mut limiter) = self.client_rate_limiter {
+            limiter.remove_client(&pub_id);
+        }
 
         if self.dropped_peer(pub_id, outbox, true) {
             Transition::Stay
@@ -1667,7 +3265,7 @@ impl Base for Elder {
 
     fn finish_handle_network_event(&mut self, outbox: &mut dyn EventBox) -> Transition {
         self.handle_routing_messages(outbox);
-        Transition::Stay
+        self.pending_transition(outbox)
     }
 
     // Deconstruct a `DirectMessage` and handle or forward as appropriate.
@@ -1726,7 +3324,17 @@ impl Base for Elder {
             ParsecResponse(version, par_response) => {
                 return self.handle_parsec_response(version, par_response, pub_id, outbox);
             }
-            BootstrapResponse(_) | ResourceProof { .. } | ResourceProofResponseReceipt => {
+            SectionList {
+                proof_chain,
+                their_version,
+            } => self.handle_section_list(pub_id, &proof_chain, their_version),
+            ResumeSession(ref token) => self.handle_resume_session(pub_id, token),
+            BootstrapResponse(_)
+            | ResourceProof { .. }
+            | ResourceProofResponseReceipt
+            | Capabilities(_)
+            | JoinDenied { .. }
+            | ResumeSessionResponse(_) => {
                 debug!("{} Unhandled direct message: {:?}", self, msg);
             }
         }
@@ -1739,8 +3347,19 @@ impl Base for Elder {
         _: &mut dyn EventBox,
     ) -> Result<Transition, RoutingError> {
         let HopMessage { content, .. } = msg;
-        self.handle_signed_message(content)
-            .map(|()| Transition::Stay)
+        let routing_msg = content.routing_message();
+        let msg_id = routing_msg.content.message_id().cloned();
+        let (src, dst) = (routing_msg.src, routing_msg.dst);
+        let prefix = *self.our_prefix();
+        in_span!(
+            "handle_message",
+            msg_id = msg_id,
+            src = src,
+            dst = dst,
+            prefix = prefix;
+            self.handle_signed_message(content)
+        )
+        .map(|()| Transition::Stay)
     }
 }
 
@@ -1765,6 +3384,10 @@ impl Elder {
         self.next_relocation_dst = dst;
     }
 
+    pub fn set_stability_guard(&mut self, guard: Option<StabilityGuard>) {
+        self.chain.set_stability_guard(guard);
+    }
+
     pub fn set_next_relocation_interval(&mut self, interval: Option<XorTargetInterval>) {
         self.next_relocation_interval = interval;
     }
@@ -1958,16 +3581,29 @@ impl Approved for Elder {
     fn handle_online_event(&mut self, online_payload: OnlinePayload) -> Result<(), RoutingError> {
         if self.chain.try_accept_candidate_as_member(&online_payload) {
             self.peer_mgr.reset_candidate();
-            self.vote_for_event(NetworkEvent::AddElder(
-                online_payload.new_public_id,
-                online_payload.client_auth,
-            ));
+            self.join_rate.record_join();
+            let pub_id = online_payload.new_public_id;
+            let client_auth = online_payload.client_auth;
+            if self.chain.joins_as_infant(&pub_id) {
+                self.vote_for_event(NetworkEvent::AddInfant(pub_id, client_auth));
+            } else {
+                self.vote_for_event(NetworkEvent::AddElder(pub_id, client_auth));
+            }
         }
         Ok(())
     }
 
     fn handle_offline_event(&mut self, pub_id: PublicId) -> Result<(), RoutingError> {
-        self.vote_for_event(NetworkEvent::RemoveElder(pub_id));
+        if pub_id == *self.id() && self.shutdown_token.is_some() {
+            info!("{} Section acknowledged our shutdown.", self);
+            self.shutdown_acknowledged = true;
+        }
+
+        if self.chain.is_infant(&pub_id) {
+            self.vote_for_event(NetworkEvent::RemoveInfant(pub_id));
+        } else {
+            self.vote_for_event(NetworkEvent::RemoveElder(pub_id));
+        }
         Ok(())
     }
 
@@ -1987,12 +3623,35 @@ impl Approved for Elder {
             return self.forward_expect_candidate_to_prefix(vote, prefix);
         }
 
+        if !self.is_joiner_allowed(&vote.old_public_id, None) {
+            return self.send_join_denial(vote, JoinDenialReason::DenyListed, None);
+        }
+
         if let Some(target_interval) = self.accept_candidate_with_interval(&vote) {
             return self.send_relocate_response(vote, target_interval);
         }
 
-        // Nothing to do with this event.
-        Ok(())
+        if self
+            .chain
+            .matching_candidate_target_interval(&vote.old_public_id)
+            .is_some()
+            || self.chain.is_candidate_queued(&vote.old_public_id)
+        {
+            // Already accepted: a repeated vote for the candidate we're processing or queued.
+            return Ok(());
+        }
+
+        if self.chain.queue_candidate(vote.clone()) {
+            info!(
+                "{} Queued candidate with old name {} until the current one completes.",
+                self, vote.old_public_id
+            );
+            return Ok(());
+        }
+
+        // The current candidate is approved or purged by the time it expires at the latest.
+        let retry_after = self.chain.network_params().timeouts.candidate_approval;
+        self.send_join_denial(vote, JoinDenialReason::Throttled, Some(retry_after))
     }
 
     fn handle_purge_candidate_event(
@@ -2006,10 +3665,196 @@ impl Approved for Elder {
         {
             self.chain.reset_candidate();
             self.peer_mgr.reset_candidate();
+            return self.accept_queued_candidate();
+        }
+
+        if let Some(vote) = self.chain.remove_queued_candidate(&old_public_id) {
+            // It waited too long: it can retry once the queue had time to drain.
+            let retry_after = self.chain.network_params().timeouts.candidate_approval;
+            return self.send_join_denial(vote, JoinDenialReason::Throttled, Some(retry_after));
+        }
+        Ok(())
+    }
+
+    // Starts processing the next queued candidate, if the previous one completed.
+    fn accept_queued_candidate(&mut self) -> Result<(), RoutingError> {
+        let vote = match self.chain.take_next_candidate() {
+            Some(vote) => vote,
+            None => return Ok(()),
+        };
+        match self.accept_candidate_with_interval(&vote) {
+            Some(target_interval) => self.send_relocate_response(vote, target_interval),
+            None => Ok(()),
+        }
+    }
+
+    fn handle_relocate_event(&mut self, pub_id: PublicId) -> Result<(), RoutingError> {
+        if !self.chain.our_info().members().contains(&pub_id) {
+            return Ok(());
+        }
+
+        info!("{} Our section agreed to relocate {}.", self, pub_id);
+        if pub_id == *self.id() {
+            self.is_relocating = true;
+        }
+
+        // The node is addressed as itself: it keeps its ID until the other section accepts it.
+        let old_client_auth = Authority::ManagedNode(*pub_id.name());
+        let message_id = MessageId::from_relocated_node(*pub_id.name());
+        self.send_expect_candidate(pub_id, old_client_auth, message_id)
+    }
+
+    fn handle_promote_event(&mut self, payload: ElderChangePayload, outbox: &mut dyn EventBox) {
+        info!(
+            "{} {} became an elder with version {}.",
+            self, payload.pub_id, payload.version
+        );
+        if payload.pub_id == *self.id() {
+            self.send_event(Event::Promoted, outbox);
+        }
+    }
+
+    fn handle_demote_event(&mut self, payload: ElderChangePayload, outbox: &mut dyn EventBox) {
+        info!(
+            "{} {} stopped being an elder with version {}.",
+            self, payload.pub_id, payload.version
+        );
+        if payload.pub_id == *self.id() {
+            self.send_event(Event::Demoted, outbox);
+        }
+    }
+
+    fn handle_add_infant_event(
+        &mut self,
+        new_pub_id: PublicId,
+        client_auth: Authority<XorName>,
+        outbox: &mut dyn EventBox,
+    ) -> Result<(), RoutingError> {
+        info!("{} {} joined our section as an infant.", self, new_pub_id);
+        self.handle_candidate_approval(new_pub_id, client_auth, outbox)?;
+        self.accept_queued_candidate()
+    }
+
+    fn handle_remove_infant_event(
+        &mut self,
+        pub_id: PublicId,
+        outbox: &mut dyn EventBox,
+    ) -> Result<(), RoutingError> {
+        if let Some(&pub_id) = self.peer_mgr.get_pub_id(pub_id.name()) {
+            let _ = self.dropped_peer(pub_id, outbox, false);
+            self.disconnect_peer(&pub_id);
         }
         Ok(())
     }
 
+    fn handle_refresh_event(&mut self, payload: RefreshPayload, outbox: &mut dyn EventBox) {
+        // New members receive the inventories of the others instead.
+        if payload.new_members.contains(self.id()) {
+            return;
+        }
+        let new_members = payload
+            .new_members
+            .iter()
+            .map(PublicId::name)
+            .filter(|name| self.is_our_member(name))
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        if new_members.is_empty() {
+            return;
+        }
+        self.send_event(
+            Event::RefreshRequired {
+                prefix: *self.our_prefix(),
+                new_members,
+            },
+            outbox,
+        );
+    }
+
+    fn handle_account_debit_event(&mut self, payload: AccountPayload) {
+        self.client_manager
+            .settle(&payload.account, &payload.msg_id);
+    }
+
+    fn handle_sequence_message_event(&mut self, payload: SequencePayload) {
+        let seq = self.chain.msg_seq(&payload.src, &payload.dst);
+        let (src, dst, content, priority) = match self.unsequenced_msgs.remove(&payload.digest) {
+            Some(msg) => msg,
+            None => {
+                // We haven't been asked to send it yet; do so with this number once we are.
+                let _ = self.sequenced_msgs.insert(payload.digest, seq);
+                return;
+            }
+        };
+
+        let content = MessageContent::UserMessage {
+            content,
+            priority,
+            seq: Some(seq),
+        };
+        if let Err(error) = self.send_routing_message(src, dst, content) {
+            debug!(
+                "{} Failed to send sequenced user message: {:?}",
+                self, error
+            );
+        }
+    }
+
+    fn handle_failed_audits_event(&mut self, payload: AuditPayload, outbox: &mut dyn EventBox) {
+        let auditee = payload.auditee;
+        if !self.our_prefix().matches(&auditee.name()) {
+            // The auditee is managed elsewhere: let its own section agree on the failures.
+            let src = Authority::Section(self.our_prefix().name());
+            let dst = Authority::Section(auditee.name());
+            let content = MessageContent::FailedAudits { auditee };
+            if let Err(err) = self.send_routing_message(src, dst, content) {
+                debug!("{} Failed to send FailedAudits: {:?}.", self, err);
+            }
+            return;
+        }
+
+        match auditee {
+            Authority::ManagedNode(name) => {
+                let pub_id = self
+                    .chain
+                    .our_info()
+                    .members()
+                    .iter()
+                    .find(|pub_id| *pub_id.name() == name)
+                    .cloned();
+                if let Some(pub_id) = pub_id {
+                    self.chain
+                        .record_misbehaviour(pub_id, Misbehaviour::FailedAudits);
+                    self.vote_out_malicious_peers();
+                }
+            }
+            Authority::NaeManager(_) => {
+                warn!("{} Our section failed data audits as {:?}.", self, auditee);
+                outbox.send_event(Event::DataAuditFailed { auditee });
+            }
+            _ => (),
+        }
+    }
+
+    fn handle_failed_audits_message(&mut self, auditee: Authority<XorName>) {
+        if self.our_prefix().matches(&auditee.name()) {
+            let version = *self.chain.our_info().version();
+            self.vote_for_event(NetworkEvent::FailedAudits(AuditPayload {
+                auditee,
+                version,
+            }));
+        }
+    }
+
+    fn handle_upgrade_signal_event(&mut self, version: u16, activation_version: u64) {
+        info!(
+            "{} Our section agreed to upgrade to protocol v{} at section version {}.",
+            self, version, activation_version
+        );
+        // Neighbours which already know of this upgrade or a newer one ignore it.
+        self.send_upgrade_signal(version, activation_version);
+    }
+
     fn handle_section_info_event(
         &mut self,
         sec_info: SectionInfo,
@@ -2036,6 +3881,15 @@ impl Approved for Elder {
 
         if self_sec_update {
             self.chain.reset_candidate_if_member_of(sec_info.members());
+            self.accept_queued_candidate()?;
+            if let Some(pub_id) = self.chain.relocation_candidate() {
+                self.vote_for_event(NetworkEvent::Relocate(RelocatePayload {
+                    pub_id,
+                    version: *sec_info.version(),
+                }));
+            }
+            self.vote_for_elder_changes(*sec_info.version());
+            self.vote_for_refresh(*sec_info.version());
 
             // Vote to update our self messages proof
             self.vote_send_section_info_ack(SendAckMessagePayload {
@@ -2051,6 +3905,7 @@ impl Approved for Elder {
         }
 
         let _ = self.merge_if_necessary();
+        self.handle_held_messages();
 
         Ok(Transition::Stay)
     }
@@ -2077,7 +3932,8 @@ impl Approved for Elder {
             ack_version: ack_payload.ack_version,
         };
 
-        self.send_routing_message(src, dst, content)
+        // Only the members of the section info we acknowledge can make use of the ack.
+        self.send_routing_message_to_version(src, dst, content, ack_payload.ack_version)
     }
 }
 
@@ -2103,3 +3959,10 @@ fn create_first_section_info(public_id: PublicId) -> Result<SectionInfo, Routing
         err
     })
 }
+
+// Returns the hash identifying a routing message regardless of its proofs.
+fn msg_hash(routing_msg: &RoutingMessage) -> Option<Digest256> {
+    serialisation::serialise(routing_msg)
+        .ok()
+        .map(|bytes| safe_crypto::hash(&bytes))
+}
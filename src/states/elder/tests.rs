@@ -16,6 +16,7 @@
 use super::*;
 use crate::{
     cache::NullCache,
+    client_manager::DEFAULT_ACCOUNT_BALANCE,
     messages::DirectMessage,
     mock::Network,
     outbox::{EventBox, EventBuf},
@@ -47,7 +48,7 @@ impl CandidateInfo {
         Self {
             old_full_id: FullId::new(),
             old_proxy_id: FullId::new(),
-            new_full_id: FullId::new(),
+            new_full_id: FullId::new().with_age(1),
             new_proxy_id: FullId::new(),
             message_id: MessageId::new(),
         }
@@ -361,6 +362,7 @@ impl ElderUnderTest {
                 old_client_auth: payload.old_client_auth,
                 message_id: payload.message_id,
             },
+            dst_min_version: None,
         }
     }
 
@@ -379,8 +381,8 @@ impl ElderUnderTest {
                 .encrypting_private_key()
                 .shared_secret(their_pub_id.encrypting_public_key());
 
-            let conn_info = self.candidate_node_info();
-            let encrypted_conn_info = unwrap!(shared_secret.encrypt(&conn_info));
+            let endpoints = vec![self.candidate_node_info()];
+            let encrypted_conn_info = unwrap!(shared_secret.encrypt(&endpoints));
 
             MessageContent::ConnectionRequest {
                 encrypted_conn_info,
@@ -389,7 +391,12 @@ impl ElderUnderTest {
             }
         };
 
-        RoutingMessage { src, dst, content }
+        RoutingMessage {
+            src,
+            dst,
+            content,
+            dst_min_version: None,
+        }
     }
 
     fn candidate_info_message(&self) -> (DirectMessage, PublicId) {
@@ -468,7 +475,11 @@ fn new_elder_state(
     let public_id = *full_id.public_id();
 
     let parsec_map = ParsecMap::new(full_id.clone(), gen_pfx_info);
-    let chain = Chain::new(min_section_size, public_id, gen_pfx_info.clone());
+    let network_params = NetworkParams {
+        min_section_size,
+        ..NetworkParams::default()
+    };
+    let chain = Chain::new(network_params, public_id, gen_pfx_info.clone());
     let peer_map = PeerMap::new();
     let peer_mgr = PeerManager::new(false);
     let cache = Box::new(NullCache);
@@ -520,6 +531,7 @@ fn make_state_machine(
             )
         },
         config,
+        None,
         outbox,
     )
     .1
@@ -597,6 +609,157 @@ fn accumulate_purge_candidate() {
     assert!(!elder_test.is_candidate_a_valid_peer());
 }
 
+#[test]
+// Deny is consensused first: the candidate is refused
+fn accumulate_deny_then_expect_candidate() {
+    let mut elder_test = ElderUnderTest::new();
+    let denied = JoinerId::Node(*elder_test.candidate_info.old_full_id.public_id());
+    let _ = elder_test.n_vote_for_gossipped(ACCUMULATE_VOTE_COUNT, &[&NetworkEvent::Deny(denied)]);
+    assert!(elder_test
+        .elder_state()
+        .chain()
+        .join_filter()
+        .denied()
+        .contains(&denied));
+
+    elder_test.accumulate_expect_candidate(elder_test.expect_candidate_payload());
+
+    assert!(!elder_test.has_unpolled_observations());
+    assert!(!elder_test.has_resource_proof_candidate());
+}
+
+#[test]
+// Debits and credits are applied to the account once consensused.
+fn accumulate_account_debit_and_credit() {
+    let mut elder_test = ElderUnderTest::new();
+    let account = rand::random();
+    let debit = NetworkEvent::AccountDebit(AccountPayload {
+        account,
+        amount: DEFAULT_ACCOUNT_BALANCE,
+        msg_id: MessageId::new(),
+    });
+    let _ = elder_test.n_vote_for_gossipped(ACCUMULATE_VOTE_COUNT, &[&debit]);
+    let info = unwrap!(elder_test.elder_state().account_info(&account));
+    assert_eq!(info.mutations_available, 0);
+    assert_eq!(info.mutations_done, DEFAULT_ACCOUNT_BALANCE);
+
+    let credit = NetworkEvent::AccountCredit(AccountPayload {
+        account,
+        amount: 5,
+        msg_id: MessageId::new(),
+    });
+    let _ = elder_test.n_vote_for_gossipped(ACCUMULATE_VOTE_COUNT, &[&credit]);
+    let info = unwrap!(elder_test.elder_state().account_info(&account));
+    assert_eq!(info.mutations_available, 5);
+}
+
+#[test]
+// Section messages needing a newer version of their destination than we know are held, sent on once
+// we know it, and dropped if we don't learn of it in time.
+fn hold_and_release_section_messages() {
+    let mut elder_test = ElderUnderTest::new();
+    let version = *elder_test.section_info.version();
+    let message = |dst_min_version| {
+        SignedRoutingMessage::insecure(RoutingMessage {
+            src: Authority::Section(rand::random()),
+            dst: Authority::Section(rand::random()),
+            content: MessageContent::UpgradeSignal {
+                version: 1,
+                activation_version: dst_min_version,
+            },
+            dst_min_version: Some(dst_min_version),
+        })
+    };
+    let elder = elder_test.machine.elder_state_mut();
+
+    unwrap!(elder.send_signed_message(&mut message(version + 1)));
+    assert_eq!(elder.held_messages.len(), 1);
+
+    // A held message whose destination version we know by now is sent on, not handled by us.
+    elder
+        .held_messages
+        .push_back((Instant::now(), message(version)));
+    elder.handle_held_messages();
+    assert_eq!(elder.held_messages.len(), 1);
+    assert!(elder.msg_queue.is_empty());
+
+    Instant::advance_time(HELD_MESSAGE_TIMEOUT.as_secs() * 1000 + 1);
+    elder.handle_held_messages();
+    assert!(elder.held_messages.is_empty());
+}
+
+#[test]
+// The user messages of a route are numbered in the order their votes are consensused.
+fn accumulate_sequence_message() {
+    let mut elder_test = ElderUnderTest::new();
+    let src = Authority::NaeManager(rand::random());
+    let dst = Authority::ClientManager(rand::random());
+    let other_dst = Authority::ClientManager(rand::random());
+    let sequence =
+        |dst, digest| NetworkEvent::SequenceMessage(SequencePayload { src, dst, digest });
+
+    let _ =
+        elder_test.n_vote_for_gossipped(ACCUMULATE_VOTE_COUNT, &[&sequence(dst, rand::random())]);
+    let _ =
+        elder_test.n_vote_for_gossipped(ACCUMULATE_VOTE_COUNT, &[&sequence(dst, rand::random())]);
+    let _ = elder_test.n_vote_for_gossipped(
+        ACCUMULATE_VOTE_COUNT,
+        &[&sequence(other_dst, rand::random())],
+    );
+
+    let chain = elder_test.elder_state().chain();
+    assert_eq!(chain.msg_seq(&src, &dst), 1);
+    assert_eq!(chain.msg_seq(&src, &other_dst), 0);
+}
+
+#[test]
+// A data manager group of our section which the section agreed failed its audits is reported.
+fn accumulate_failed_audits_of_group() {
+    let mut elder_test = ElderUnderTest::new();
+    let auditee = Authority::NaeManager(rand::random());
+    let version = *elder_test.section_info.version();
+    let _ = elder_test.ev_buffer.take_all();
+
+    let _ = elder_test.n_vote_for_gossipped(
+        ACCUMULATE_VOTE_COUNT,
+        &[&NetworkEvent::FailedAudits(AuditPayload {
+            auditee,
+            version,
+        })],
+    );
+
+    assert!(elder_test
+        .ev_buffer
+        .take_all()
+        .contains(&Event::DataAuditFailed { auditee }));
+}
+
+#[test]
+// A second candidate is queued while the first one is processed, and accepted once it is purged.
+fn accumulate_expect_candidate_while_busy_queues_candidate() {
+    let mut elder_test = ElderUnderTest::new();
+    elder_test.accumulate_expect_candidate(elder_test.expect_candidate_payload());
+    let first_purge_payload = elder_test.purge_payload();
+
+    elder_test.candidate_info = CandidateInfo::new();
+    let second_payload = elder_test.expect_candidate_payload();
+    elder_test.accumulate_expect_candidate(second_payload.clone());
+    let chain = elder_test.elder_state().chain();
+    assert!(chain.is_candidate_queued(&second_payload.old_public_id));
+    assert!(chain
+        .matching_candidate_target_interval(&second_payload.old_public_id)
+        .is_none());
+
+    elder_test.accumulate_purge_candidate(first_purge_payload);
+
+    let chain = elder_test.elder_state().chain();
+    assert!(!chain.is_candidate_queued(&second_payload.old_public_id));
+    assert!(chain
+        .matching_candidate_target_interval(&second_payload.old_public_id)
+        .is_some());
+    assert!(elder_test.has_resource_proof_candidate());
+}
+
 #[test]
 // Candidate is only removed as candidate when its SectionInfo is consensused
 fn accumulate_online_candidate_only_do_not_remove_candidate() {
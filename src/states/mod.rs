@@ -6,21 +6,25 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+#[cfg(feature = "node")]
 mod adult;
 mod bootstrapping_peer;
+#[cfg(feature = "client")]
 mod client;
 pub mod common;
+#[cfg(feature = "node")]
 mod elder;
+#[cfg(feature = "node")]
 mod proving_node;
+#[cfg(feature = "node")]
 mod relocating_node;
 
+pub use self::bootstrapping_peer::{BootstrappingPeer, TargetState};
+#[cfg(feature = "client")]
+pub use self::client::Client;
+#[cfg(feature = "node")]
 pub use self::{
-    adult::Adult,
-    bootstrapping_peer::{BootstrappingPeer, TargetState},
-    client::Client,
-    elder::Elder,
-    proving_node::ProvingNode,
-    relocating_node::RelocatingNode,
+    adult::Adult, elder::Elder, proving_node::ProvingNode, relocating_node::RelocatingNode,
 };
 
 //
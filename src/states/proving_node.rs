@@ -17,10 +17,11 @@ use crate::{
     cache::Cache,
     chain::GenesisPfxInfo,
     config_handler,
-    error::RoutingError,
+    error::{JoinDenialReason, RoutingError},
     event::Event,
     id::{FullId, PublicId},
     messages::{DirectMessage, HopMessage, RoutingMessage},
+    network_params::NetworkParams,
     outbox::EventBox,
     peer_manager::{PeerManager, PeerState},
     peer_map::PeerMap,
@@ -50,7 +51,7 @@ pub struct ProvingNodeDetails {
     pub cache: Box<dyn Cache>,
     pub network_service: NetworkService,
     pub full_id: FullId,
-    pub min_section_size: usize,
+    pub network_params: NetworkParams,
     pub old_full_id: FullId,
     pub our_section: (Prefix<XorName>, BTreeSet<PublicId>),
     pub peer_map: PeerMap,
@@ -66,7 +67,7 @@ pub struct ProvingNode {
     event_backlog: Vec<Event>,
     full_id: FullId,
     joining_prefix: Prefix<XorName>,
-    min_section_size: usize,
+    network_params: NetworkParams,
     /// Routing messages addressed to us that we cannot handle until we are approved.
     msg_backlog: Vec<RoutingMessage>,
     /// ID from before relocating.
@@ -78,6 +79,12 @@ pub struct ProvingNode {
     timer: Timer,
     resource_proofing_status: BTreeMap<PublicId, bool>,
     resend_token: Option<u64>,
+    /// The number of members of the section we're joining.
+    challenger_count: usize,
+    /// Members of the section which refused to approve us.
+    join_denials: BTreeSet<PublicId>,
+    /// Set once the section told us to come back later: we restart when it fires.
+    restart_token: Option<u64>,
 }
 
 impl ProvingNode {
@@ -95,6 +102,7 @@ impl ProvingNode {
             details.action_sender,
             details.timer.clone(),
             challenger_count,
+            details.network_params.timeouts.node_approval,
         );
 
         let mut node = Self {
@@ -102,7 +110,7 @@ impl ProvingNode {
             network_service: details.network_service,
             event_backlog: Vec::new(),
             full_id: details.full_id,
-            min_section_size: details.min_section_size,
+            network_params: details.network_params,
             msg_backlog: Vec::new(),
             peer_map: details.peer_map,
             peer_mgr,
@@ -114,6 +122,9 @@ impl ProvingNode {
             resource_prover,
             resource_proofing_status: BTreeMap::new(),
             resend_token: None,
+            challenger_count,
+            join_denials: BTreeSet::new(),
+            restart_token: None,
         };
         node.init(details.our_section.1, &details.proxy_pub_id, outbox)?;
         Ok(node)
@@ -165,7 +176,7 @@ impl ProvingNode {
             event_backlog: self.event_backlog,
             full_id: self.full_id,
             gen_pfx_info,
-            min_section_size: self.min_section_size,
+            network_params: self.network_params,
             msg_backlog: self.msg_backlog,
             peer_map: self.peer_map,
             peer_mgr: self.peer_mgr,
@@ -187,6 +198,7 @@ impl ProvingNode {
                 content: NodeApproval(gen_info),
                 src: PrefixSection(_),
                 dst: Client { .. },
+                ..
             } => Ok(self.handle_node_approval(gen_info)),
             _ => {
                 self.handle_routing_message(msg, outbox)?;
@@ -211,6 +223,50 @@ impl ProvingNode {
         Transition::IntoAdult { gen_pfx_info }
     }
 
+    fn handle_join_denied(
+        &mut self,
+        pub_id: PublicId,
+        reason: JoinDenialReason,
+        retry_after: Option<Duration>,
+        outbox: &mut dyn EventBox,
+    ) -> Transition {
+        if !self.resource_proofing_status.contains_key(&pub_id) {
+            debug!("{} Ignoring JoinDenied from {}.", self, pub_id);
+            return Transition::Stay;
+        }
+
+        // A single member could be faulty, so only give up once a quorum refused us.
+        let _ = self.join_denials.insert(pub_id);
+        if !self
+            .network_params
+            .is_quorum(self.join_denials.len(), self.challenger_count)
+        {
+            return Transition::Stay;
+        }
+
+        if self.restart_token.is_some() {
+            return Transition::Stay;
+        }
+
+        info!(
+            "{} Our section with {:?} refused to approve us: {}",
+            self, self.joining_prefix, reason
+        );
+        outbox.send_event(Event::JoinDenied {
+            reason,
+            retry_after,
+        });
+
+        match retry_after {
+            // Our candidacy is gone, but we may try joining again once the section is ready.
+            Some(retry_after) => {
+                self.restart_token = Some(self.timer.schedule(retry_after));
+                Transition::Stay
+            }
+            None => Transition::Terminate,
+        }
+    }
+
     fn send_candidate_info(&mut self, pub_id: PublicId) {
         // We're not approved yet - we need to identify ourselves with our old and new IDs via
         // `CandidateInfo`. Serialise the old and new `PublicId`s and sign this using the old key.
@@ -312,7 +368,7 @@ impl Base for ProvingNode {
     }
 
     fn min_section_size(&self) -> usize {
-        self.min_section_size
+        self.network_params.min_section_size
     }
 
     fn peer_map(&self) -> &PeerMap {
@@ -328,6 +384,12 @@ impl Base for ProvingNode {
             return self.resend_info(outbox);
         }
 
+        if self.restart_token == Some(token) {
+            info!("{} Retrying to join the network.", self);
+            outbox.send_event(Event::RestartRequired);
+            return Transition::Terminate;
+        }
+
         let log_ident = self.log_ident();
         if let Some(transition) = self
             .resource_prover
@@ -355,7 +417,7 @@ impl Base for ProvingNode {
         &mut self,
         msg: DirectMessage,
         pub_id: PublicId,
-        _outbox: &mut dyn EventBox,
+        outbox: &mut dyn EventBox,
     ) -> Result<Transition, RoutingError> {
         self.check_direct_message_sender(&msg, &pub_id)?;
 
@@ -385,6 +447,12 @@ impl Base for ProvingNode {
                 }
             }
             BootstrapRequest => self.handle_bootstrap_request(pub_id),
+            JoinDenied {
+                reason,
+                retry_after,
+            } => {
+                return Ok(self.handle_join_denied(pub_id, reason, retry_after, outbox));
+            }
             _ => {
                 debug!("{} Unhandled direct message: {:?}", self, msg);
             }
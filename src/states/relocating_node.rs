@@ -13,13 +13,14 @@ use super::{
 use crate::{
     action::Action,
     cache::Cache,
-    error::RoutingError,
+    constants::RESOURCE_PROOF_DURATION,
+    error::{JoinDenialReason, RoutingError},
     event::Event,
     id::{FullId, PublicId},
     messages::{DirectMessage, HopMessage, MessageContent, RoutingMessage},
+    network_params::NetworkParams,
     outbox::EventBox,
     peer_map::PeerMap,
-    resource_prover::RESOURCE_PROOF_DURATION,
     routing_message_filter::RoutingMessageFilter,
     routing_table::{Authority, Prefix},
     state_machine::{State, Transition},
@@ -44,7 +45,7 @@ pub struct RelocatingNodeDetails {
     pub cache: Box<dyn Cache>,
     pub network_service: NetworkService,
     pub full_id: FullId,
-    pub min_section_size: usize,
+    pub network_params: NetworkParams,
     pub peer_map: PeerMap,
     pub proxy_pub_id: PublicId,
     pub timer: Timer,
@@ -56,13 +57,15 @@ pub struct RelocatingNode {
     full_id: FullId,
     /// Only held here to be passed eventually to the `Node` state.
     cache: Box<dyn Cache>,
-    min_section_size: usize,
+    network_params: NetworkParams,
     peer_map: PeerMap,
     proxy_pub_id: PublicId,
     /// The queue of routing messages addressed to us. These do not themselves need forwarding,
     /// although they may wrap a message which needs forwarding.
     routing_msg_filter: RoutingMessageFilter,
     relocation_timer_token: u64,
+    /// Set while we wait to ask again after a section told us to retry later.
+    retry_timer_token: Option<u64>,
     timer: Timer,
 }
 
@@ -74,11 +77,12 @@ impl RelocatingNode {
             network_service: details.network_service,
             full_id: details.full_id,
             cache: details.cache,
-            min_section_size: details.min_section_size,
+            network_params: details.network_params,
             peer_map: details.peer_map,
             proxy_pub_id: details.proxy_pub_id,
             routing_msg_filter: RoutingMessageFilter::new(),
             relocation_timer_token,
+            retry_timer_token: None,
             timer: details.timer,
         };
 
@@ -117,12 +121,16 @@ impl RelocatingNode {
             target_state,
             self.network_service,
             new_full_id,
-            self.min_section_size,
+            self.network_params,
             self.timer,
         ))
     }
 
-    fn dispatch_routing_message(&mut self, routing_msg: RoutingMessage) -> Transition {
+    fn dispatch_routing_message(
+        &mut self,
+        routing_msg: RoutingMessage,
+        outbox: &mut dyn EventBox,
+    ) -> Transition {
         use crate::messages::MessageContent::*;
         match routing_msg.content {
             Relocate { .. }
@@ -132,7 +140,22 @@ impl RelocatingNode {
             | Merge(..)
             | UserMessage { .. }
             | NodeApproval { .. }
-            | AckMessage { .. } => {
+            | AckMessage { .. }
+            | MembershipStatementRequest { .. }
+            | MembershipStatementResponse { .. }
+            | HandoffPrepare { .. }
+            | HandoffPrepared { .. }
+            | HandoffCommit { .. }
+            | DeliveryAck { .. }
+            | DataInventory { .. }
+            | RepublishRequest { .. }
+            | Bounce { .. }
+            | UpgradeSignal { .. }
+            | SessionRequest { .. }
+            | SessionGranted { .. }
+            | SectionKeyRequest { .. }
+            | SectionKeyResponse { .. }
+            | FailedAudits { .. } => {
                 warn!(
                     "{} Not joined yet. Not handling {:?} from {:?} to {:?}",
                     self, routing_msg.content, routing_msg.src, routing_msg.dst
@@ -145,6 +168,17 @@ impl RelocatingNode {
             } => {
                 return self.handle_relocate_response(target_interval, section);
             }
+            JoinDenied {
+                reason,
+                retry_after,
+                ..
+            } => {
+                outbox.send_event(Event::JoinDenied {
+                    reason,
+                    retry_after,
+                });
+                return self.handle_join_denied(reason, retry_after);
+            }
         }
         Transition::Stay
     }
@@ -173,7 +207,8 @@ impl RelocatingNode {
         section: (Prefix<XorName>, BTreeSet<PublicId>),
     ) -> Transition {
         let target_interval = target_interval.into();
-        let new_id = FullId::within_range(&target_interval);
+        let new_id = FullId::within_range(&target_interval)
+            .with_age(self.full_id.public_id().age().saturating_add(1));
         if !section.0.matches(new_id.public_id().name()) {
             log_or_panic!(
                 LogLevel::Error,
@@ -189,6 +224,33 @@ impl RelocatingNode {
         }
     }
 
+    fn handle_join_denied(
+        &mut self,
+        reason: JoinDenialReason,
+        retry_after: Option<Duration>,
+    ) -> Transition {
+        let retry_after = match retry_after {
+            Some(retry_after) => retry_after,
+            None => {
+                info!("{} - The network refused to relocate us: {}", self, reason);
+                return Transition::Terminate;
+            }
+        };
+
+        if self.retry_timer_token.is_some() {
+            return Transition::Stay;
+        }
+
+        info!(
+            "{} - The network can't relocate us yet: {}. Retrying in {:?}.",
+            self, reason, retry_after
+        );
+        self.retry_timer_token = Some(self.timer.schedule(retry_after));
+        // Our original deadline would otherwise expire before the retry had a chance.
+        self.relocation_timer_token = self.timer.schedule(retry_after + RELOCATE_TIMEOUT);
+        Transition::Stay
+    }
+
     #[cfg(feature = "mock_base")]
     pub fn get_timed_out_tokens(&mut self) -> Vec<u64> {
         self.timer.get_timed_out_tokens()
@@ -217,7 +279,7 @@ impl Base for RelocatingNode {
     }
 
     fn min_section_size(&self) -> usize {
-        self.min_section_size
+        self.network_params.min_section_size
     }
 
     fn peer_map(&self) -> &PeerMap {
@@ -237,6 +299,13 @@ impl Base for RelocatingNode {
             outbox.send_event(Event::RestartRequired);
             return Transition::Terminate;
         }
+
+        if self.retry_timer_token == Some(token) {
+            self.retry_timer_token = None;
+            if let Err(error) = self.relocate() {
+                error!("{} Failed to retry relocation: {:?}", self, error);
+            }
+        }
         Transition::Stay
     }
 
@@ -265,10 +334,10 @@ impl Base for RelocatingNode {
     fn handle_hop_message(
         &mut self,
         msg: HopMessage,
-        _: &mut dyn EventBox,
+        outbox: &mut dyn EventBox,
     ) -> Result<Transition, RoutingError> {
         if let Some(routing_msg) = self.filter_hop_message(msg)? {
-            Ok(self.dispatch_routing_message(routing_msg))
+            Ok(self.dispatch_routing_message(routing_msg, outbox))
         } else {
             Ok(Transition::Stay)
         }
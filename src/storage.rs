@@ -0,0 +1,401 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::error::RoutingError;
+use hex::{FromHex, ToHex};
+use maidsafe_utilities::serialisation;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+/// A change to a single entry, as part of a `WriteBatch`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum WriteOp {
+    /// Sets the value of `key` in `namespace`.
+    Put {
+        /// The namespace of the entry.
+        namespace: String,
+        /// The key of the entry.
+        key: Vec<u8>,
+        /// The new value.
+        value: Vec<u8>,
+    },
+    /// Removes `key` from `namespace`, if present.
+    Delete {
+        /// The namespace of the entry.
+        namespace: String,
+        /// The key of the entry.
+        key: Vec<u8>,
+    },
+}
+
+/// A list of changes which a `Storage` applies atomically: either all of them or none.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct WriteBatch {
+    ops: Vec<WriteOp>,
+}
+
+impl WriteBatch {
+    /// Returns an empty batch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds setting the value of `key` in `namespace`.
+    pub fn put(&mut self, namespace: &str, key: &[u8], value: Vec<u8>) -> &mut Self {
+        self.ops.push(WriteOp::Put {
+            namespace: namespace.to_string(),
+            key: key.to_vec(),
+            value,
+        });
+        self
+    }
+
+    /// Adds removing `key` from `namespace`.
+    pub fn delete(&mut self, namespace: &str, key: &[u8]) -> &mut Self {
+        self.ops.push(WriteOp::Delete {
+            namespace: namespace.to_string(),
+            key: key.to_vec(),
+        });
+        self
+    }
+
+    /// Returns the changes in the order they were added.
+    pub fn ops(&self) -> &[WriteOp] {
+        &self.ops
+    }
+
+    /// Returns `true` if the batch contains no changes.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+}
+
+/// A namespaced key-value store for everything routing persists.
+///
+/// Each user of the storage, e.g. the chain snapshots, keeps its entries in its own namespace.
+/// Embedders can implement this to keep routing's state in their existing database instead of the
+/// files written by `FileStorage`.
+pub trait Storage {
+    /// Returns the value of `key` in `namespace`, or `None` if there isn't any.
+    fn get(&self, namespace: &str, key: &[u8]) -> Result<Option<Vec<u8>>, RoutingError>;
+
+    /// Returns all keys in `namespace`, in ascending order.
+    fn keys(&self, namespace: &str) -> Result<Vec<Vec<u8>>, RoutingError>;
+
+    /// Applies all changes in `batch`, in order. If this fails or the process crashes meanwhile,
+    /// either all of them or none are visible afterwards.
+    fn write(&mut self, batch: WriteBatch) -> Result<(), RoutingError>;
+
+    /// Sets the value of `key` in `namespace`.
+    fn put(&mut self, namespace: &str, key: &[u8], value: Vec<u8>) -> Result<(), RoutingError> {
+        let mut batch = WriteBatch::new();
+        let _ = batch.put(namespace, key, value);
+        self.write(batch)
+    }
+
+    /// Removes `key` from `namespace`, if present.
+    fn delete(&mut self, namespace: &str, key: &[u8]) -> Result<(), RoutingError> {
+        let mut batch = WriteBatch::new();
+        let _ = batch.delete(namespace, key);
+        self.write(batch)
+    }
+}
+
+/// A `Storage` keeping everything in memory, e.g. for tests or nodes which don't need to survive
+/// restarts.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStorage {
+    namespaces: BTreeMap<String, BTreeMap<Vec<u8>, Vec<u8>>>,
+}
+
+impl MemoryStorage {
+    /// Returns an empty storage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, namespace: &str, key: &[u8]) -> Result<Option<Vec<u8>>, RoutingError> {
+        Ok(self
+            .namespaces
+            .get(namespace)
+            .and_then(|entries| entries.get(key))
+            .cloned())
+    }
+
+    fn keys(&self, namespace: &str) -> Result<Vec<Vec<u8>>, RoutingError> {
+        Ok(self
+            .namespaces
+            .get(namespace)
+            .map_or_else(Vec::new, |entries| entries.keys().cloned().collect()))
+    }
+
+    fn write(&mut self, batch: WriteBatch) -> Result<(), RoutingError> {
+        for op in batch.ops {
+            match op {
+                WriteOp::Put {
+                    namespace,
+                    key,
+                    value,
+                } => {
+                    let _ = self
+                        .namespaces
+                        .entry(namespace)
+                        .or_insert_with(BTreeMap::new)
+                        .insert(key, value);
+                }
+                WriteOp::Delete { namespace, key } => {
+                    if let Some(entries) = self.namespaces.get_mut(&namespace) {
+                        let _ = entries.remove(&key);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The file a batch is written to before it is committed.
+const PENDING_BATCH_FILE: &str = ".batch.tmp";
+/// The file a committed batch is kept in until all its changes are applied.
+const COMMITTED_BATCH_FILE: &str = ".batch";
+
+/// A `Storage` keeping each namespace in a directory, with one file per entry.
+///
+/// A batch is committed by writing it to a file in the root directory first. If the process
+/// crashes before all changes are applied, `FileStorage::open` finishes applying them.
+#[derive(Debug)]
+pub struct FileStorage {
+    root: PathBuf,
+}
+
+impl FileStorage {
+    /// Opens the storage in the directory `root`, creating it if necessary, and completes a batch
+    /// which was interrupted by a crash.
+    pub fn open<P: AsRef<Path>>(root: P) -> Result<Self, RoutingError> {
+        let storage = Self {
+            root: root.as_ref().to_path_buf(),
+        };
+        fs::create_dir_all(&storage.root)?;
+
+        remove_if_exists(&storage.root.join(PENDING_BATCH_FILE))?;
+        let committed = storage.root.join(COMMITTED_BATCH_FILE);
+        match fs::read(&committed) {
+            Ok(bytes) => {
+                let batch = serialisation::deserialise(&bytes)?;
+                storage.apply(&batch)?;
+                fs::remove_file(&committed)?;
+            }
+            Err(ref error) if error.kind() == ErrorKind::NotFound => (),
+            Err(error) => return Err(error.into()),
+        }
+
+        Ok(storage)
+    }
+
+    fn namespace_dir(&self, namespace: &str) -> Result<PathBuf, RoutingError> {
+        // Restricting the names keeps them valid on every file system, and distinct from the
+        // batch files.
+        let is_valid = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+        if namespace.is_empty() || !namespace.chars().all(is_valid) {
+            return Err(RoutingError::Io(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid storage namespace {:?}", namespace),
+            )));
+        }
+        Ok(self.root.join(namespace))
+    }
+
+    fn entry_path(&self, namespace: &str, key: &[u8]) -> Result<PathBuf, RoutingError> {
+        if key.is_empty() {
+            return Err(RoutingError::Io(io::Error::new(
+                ErrorKind::InvalidInput,
+                "empty storage key",
+            )));
+        }
+        Ok(self.namespace_dir(namespace)?.join(key.to_hex()))
+    }
+
+    fn apply(&self, batch: &WriteBatch) -> Result<(), RoutingError> {
+        for op in &batch.ops {
+            match *op {
+                WriteOp::Put {
+                    ref namespace,
+                    ref key,
+                    ref value,
+                } => {
+                    fs::create_dir_all(self.namespace_dir(namespace)?)?;
+                    let path = self.entry_path(namespace, key)?;
+                    let tmp_path = path.with_extension("tmp");
+                    fs::write(&tmp_path, value)?;
+                    fs::rename(&tmp_path, &path)?;
+                }
+                WriteOp::Delete {
+                    ref namespace,
+                    ref key,
+                } => remove_if_exists(&self.entry_path(namespace, key)?)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Storage for FileStorage {
+    fn get(&self, namespace: &str, key: &[u8]) -> Result<Option<Vec<u8>>, RoutingError> {
+        match fs::read(self.entry_path(namespace, key)?) {
+            Ok(value) => Ok(Some(value)),
+            Err(ref error) if error.kind() == ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    fn keys(&self, namespace: &str) -> Result<Vec<Vec<u8>>, RoutingError> {
+        let entries = match fs::read_dir(self.namespace_dir(namespace)?) {
+            Ok(entries) => entries,
+            Err(ref error) if error.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(error.into()),
+        };
+        let mut keys = Vec::new();
+        for entry in entries {
+            // Skips the leftovers of interrupted writes, whose names aren't valid hex.
+            if let Some(key) = entry?
+                .file_name()
+                .to_str()
+                .and_then(|name| Vec::from_hex(name).ok())
+            {
+                keys.push(key);
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+
+    fn write(&mut self, batch: WriteBatch) -> Result<(), RoutingError> {
+        // Validate all entries up front, so an invalid one can't fail a committed batch.
+        for op in &batch.ops {
+            match *op {
+                WriteOp::Put {
+                    ref namespace,
+                    ref key,
+                    ..
+                }
+                | WriteOp::Delete {
+                    ref namespace,
+                    ref key,
+                } => {
+                    let _ = self.entry_path(namespace, key)?;
+                }
+            }
+        }
+
+        let pending = self.root.join(PENDING_BATCH_FILE);
+        let committed = self.root.join(COMMITTED_BATCH_FILE);
+        fs::write(&pending, serialisation::serialise(&batch)?)?;
+        fs::rename(&pending, &committed)?;
+        self.apply(&batch)?;
+        fs::remove_file(&committed)?;
+        Ok(())
+    }
+}
+
+fn remove_if_exists(path: &Path) -> Result<(), RoutingError> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(ref error) if error.kind() == ErrorKind::NotFound => Ok(()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{self, Rng};
+    use std::env;
+    use unwrap::unwrap;
+
+    fn check_storage<S: Storage>(storage: &mut S) {
+        assert_eq!(unwrap!(storage.get("chain", b"snapshot")), None);
+        assert!(unwrap!(storage.keys("chain")).is_empty());
+
+        unwrap!(storage.put("chain", b"snapshot", vec![1, 2, 3]));
+        let mut batch = WriteBatch::new();
+        let _ = batch
+            .put("journal", &[2], vec![20])
+            .put("journal", &[1], vec![10])
+            .delete("chain", b"snapshot");
+        unwrap!(storage.write(batch));
+
+        assert_eq!(unwrap!(storage.get("chain", b"snapshot")), None);
+        assert_eq!(unwrap!(storage.get("journal", &[1])), Some(vec![10]));
+        assert_eq!(unwrap!(storage.keys("journal")), vec![vec![1], vec![2]]);
+
+        unwrap!(storage.delete("journal", &[1]));
+        assert_eq!(unwrap!(storage.keys("journal")), vec![vec![2]]);
+    }
+
+    fn temp_root() -> PathBuf {
+        env::temp_dir().join(format!(
+            "routing-storage-{}",
+            rand::thread_rng().gen::<u64>()
+        ))
+    }
+
+    #[test]
+    fn memory_storage() {
+        check_storage(&mut MemoryStorage::new());
+    }
+
+    #[test]
+    fn file_storage() {
+        let root = temp_root();
+        check_storage(&mut unwrap!(FileStorage::open(&root)));
+
+        // Entries survive reopening.
+        let storage = unwrap!(FileStorage::open(&root));
+        assert_eq!(unwrap!(storage.get("journal", &[2])), Some(vec![20]));
+        assert!(storage.get("../chain", &[1]).is_err());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn file_storage_completes_interrupted_batch() {
+        let root = temp_root();
+        let mut storage = unwrap!(FileStorage::open(&root));
+        unwrap!(storage.put("chain", b"old", vec![0]));
+
+        // A batch which was committed but not applied when the process crashed...
+        let mut committed = WriteBatch::new();
+        let _ = committed
+            .put("chain", b"new", vec![1])
+            .delete("chain", b"old");
+        unwrap!(fs::write(
+            root.join(COMMITTED_BATCH_FILE),
+            unwrap!(serialisation::serialise(&committed))
+        ));
+        // ...and one which wasn't committed yet.
+        let mut pending = WriteBatch::new();
+        let _ = pending.put("chain", b"newer", vec![2]);
+        unwrap!(fs::write(
+            root.join(PENDING_BATCH_FILE),
+            unwrap!(serialisation::serialise(&pending))
+        ));
+
+        let storage = unwrap!(FileStorage::open(&root));
+        assert_eq!(unwrap!(storage.keys("chain")), vec![b"new".to_vec()]);
+        assert!(!root.join(COMMITTED_BATCH_FILE).exists());
+        assert!(!root.join(PENDING_BATCH_FILE).exists());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,264 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+pub use crate::quic_p2p::{Event as NetworkEvent, NodeInfo, Peer as ConnectionInfo, Token};
+pub use crate::NetworkBytes;
+
+use crate::{error::RoutingError, quic_p2p::Builder, NetworkConfig};
+use crossbeam_channel::Sender;
+use std::net::SocketAddr;
+
+/// Creates the transport of a node or client, given the channel it reports `NetworkEvent`s
+/// through. See `NodeBuilder::transport`.
+pub type TransportFactory =
+    Box<dyn FnOnce(Sender<NetworkEvent>) -> Result<Box<dyn Transport>, RoutingError>>;
+
+/// The network transport routing runs over.
+///
+/// Routing only talks to the network through this trait, so it can run over TCP, uTP, QUIC or
+/// in-process channels alike. An implementation reports everything that happens on the network,
+/// including the outcome of the calls below, by sending `NetworkEvent`s through the channel it was
+/// created with. Messages are opaque to it, apart from the `Token` they are reported back with.
+///
+/// The crate ships implementations for quic-p2p, which is used by default, and for the in-process
+/// mock network of the `mock_base` feature.
+pub trait Transport {
+    /// Connects to one of the contacts the transport was configured with, and reports
+    /// `BootstrappedTo` or `BootstrapFailure`.
+    fn bootstrap(&mut self);
+
+    /// Connects to the node `peer_info`, and reports `ConnectedTo` or `ConnectionFailure`.
+    fn connect_to(&mut self, peer_info: NodeInfo);
+
+    /// Drops the connection to `peer_addr`.
+    fn disconnect_from(&mut self, peer_addr: SocketAddr);
+
+    /// Sends `msg` to `peer`, connecting to it first if necessary, and reports `SentUserMessage`
+    /// or `UnsentUserMessage` with `token`.
+    fn send(&mut self, peer: ConnectionInfo, msg: NetworkBytes, token: Token);
+
+    /// Starts listening for incoming connections if we aren't yet, and returns the info other
+    /// nodes connect to us with.
+    fn our_connection_info(&mut self) -> Result<NodeInfo, RoutingError>;
+
+    /// Returns the info of all endpoints we listen on, most preferred first. Transports listening
+    /// on more than one address family advertise one endpoint per family.
+    fn our_endpoints(&mut self) -> Result<Vec<NodeInfo>, RoutingError> {
+        Ok(vec![self.our_connection_info()?])
+    }
+
+    /// Returns whether `node_info` is one of the contacts the transport was configured with.
+    fn is_hard_coded_contact(&self, node_info: &NodeInfo) -> bool;
+}
+
+#[cfg(not(feature = "mock_base"))]
+impl Transport for crate::quic_p2p::QuicP2p {
+    fn bootstrap(&mut self) {
+        self.bootstrap()
+    }
+
+    fn connect_to(&mut self, peer_info: NodeInfo) {
+        self.connect_to(peer_info)
+    }
+
+    fn disconnect_from(&mut self, peer_addr: SocketAddr) {
+        self.disconnect_from(peer_addr)
+    }
+
+    fn send(&mut self, peer: ConnectionInfo, msg: NetworkBytes, token: Token) {
+        self.send(peer, msg, token)
+    }
+
+    fn our_connection_info(&mut self) -> Result<NodeInfo, RoutingError> {
+        Ok(self.our_connection_info()?)
+    }
+
+    fn is_hard_coded_contact(&self, node_info: &NodeInfo) -> bool {
+        self.is_hard_coded_contact(node_info)
+    }
+}
+
+/// Which address families a node connects to its peers over, and which it prefers for peers
+/// reachable over both.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AddressFamilyPolicy {
+    /// Use both, preferring IPv4.
+    PreferIpv4,
+    /// Use both, preferring IPv6.
+    PreferIpv6,
+    /// Only use IPv4.
+    Ipv4Only,
+    /// Only use IPv6.
+    Ipv6Only,
+}
+
+impl Default for AddressFamilyPolicy {
+    fn default() -> Self {
+        AddressFamilyPolicy::PreferIpv4
+    }
+}
+
+impl AddressFamilyPolicy {
+    /// Returns whether the policy allows connecting to `addr`.
+    pub fn allows(self, addr: &SocketAddr) -> bool {
+        match self {
+            AddressFamilyPolicy::PreferIpv4 | AddressFamilyPolicy::PreferIpv6 => true,
+            AddressFamilyPolicy::Ipv4Only => addr.is_ipv4(),
+            AddressFamilyPolicy::Ipv6Only => addr.is_ipv6(),
+        }
+    }
+
+    /// Returns whether the policy prefers IPv6 over IPv4.
+    pub fn prefers_ipv6(self) -> bool {
+        match self {
+            AddressFamilyPolicy::PreferIpv6 | AddressFamilyPolicy::Ipv6Only => true,
+            AddressFamilyPolicy::PreferIpv4 | AddressFamilyPolicy::Ipv4Only => false,
+        }
+    }
+
+    /// Picks the endpoint to connect to among those a peer advertised: the first one of the
+    /// preferred family, or of the other one if allowed, which `reachable` accepts.
+    pub fn select<'a, F>(self, endpoints: &'a [NodeInfo], reachable: F) -> Option<&'a NodeInfo>
+    where
+        F: Fn(&SocketAddr) -> bool,
+    {
+        endpoints
+            .iter()
+            .filter(|endpoint| self.allows(&endpoint.peer_addr) && reachable(&endpoint.peer_addr))
+            .min_by_key(|endpoint| endpoint.peer_addr.is_ipv6() != self.prefers_ipv6())
+    }
+}
+
+/// A transport listening on both IPv4 and IPv6, by running one transport per address family.
+///
+/// It bootstraps over the family its policy prefers, and talks to every peer over the transport
+/// of that peer's family. Both transports report through the same channel.
+pub struct DualStack {
+    ipv4: Box<dyn Transport>,
+    ipv6: Box<dyn Transport>,
+    policy: AddressFamilyPolicy,
+}
+
+impl DualStack {
+    /// Combines the IPv4 transport `ipv4` and the IPv6 transport `ipv6`.
+    pub fn new(
+        ipv4: Box<dyn Transport>,
+        ipv6: Box<dyn Transport>,
+        policy: AddressFamilyPolicy,
+    ) -> Self {
+        Self { ipv4, ipv6, policy }
+    }
+
+    /// Creates a dual-stack quic-p2p transport, binding the endpoints configured in `ipv4_config`
+    /// and `ipv6_config`.
+    pub fn quic_p2p(
+        event_tx: Sender<NetworkEvent>,
+        ipv4_config: NetworkConfig,
+        ipv6_config: NetworkConfig,
+        policy: AddressFamilyPolicy,
+    ) -> Result<Self, RoutingError> {
+        let ipv4 = Builder::new(event_tx.clone())
+            .with_config(ipv4_config)
+            .build()?;
+        let ipv6 = Builder::new(event_tx).with_config(ipv6_config).build()?;
+        Ok(Self::new(Box::new(ipv4), Box::new(ipv6), policy))
+    }
+
+    fn transport_for(&mut self, addr: &SocketAddr) -> &mut dyn Transport {
+        if addr.is_ipv6() {
+            &mut *self.ipv6
+        } else {
+            &mut *self.ipv4
+        }
+    }
+
+    fn preferred(&mut self) -> &mut dyn Transport {
+        if self.policy.prefers_ipv6() {
+            &mut *self.ipv6
+        } else {
+            &mut *self.ipv4
+        }
+    }
+}
+
+impl Transport for DualStack {
+    fn bootstrap(&mut self) {
+        self.preferred().bootstrap()
+    }
+
+    fn connect_to(&mut self, peer_info: NodeInfo) {
+        self.transport_for(&peer_info.peer_addr)
+            .connect_to(peer_info)
+    }
+
+    fn disconnect_from(&mut self, peer_addr: SocketAddr) {
+        self.transport_for(&peer_addr).disconnect_from(peer_addr)
+    }
+
+    fn send(&mut self, peer: ConnectionInfo, msg: NetworkBytes, token: Token) {
+        self.transport_for(&peer.peer_addr()).send(peer, msg, token)
+    }
+
+    fn our_connection_info(&mut self) -> Result<NodeInfo, RoutingError> {
+        self.preferred().our_connection_info()
+    }
+
+    fn our_endpoints(&mut self) -> Result<Vec<NodeInfo>, RoutingError> {
+        let mut endpoints = self.ipv4.our_endpoints()?;
+        endpoints.extend(self.ipv6.our_endpoints()?);
+        if self.policy.prefers_ipv6() {
+            endpoints.reverse();
+        }
+        Ok(endpoints)
+    }
+
+    fn is_hard_coded_contact(&self, node_info: &NodeInfo) -> bool {
+        self.ipv4.is_hard_coded_contact(node_info) || self.ipv6.is_hard_coded_contact(node_info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unwrap::unwrap;
+
+    fn endpoint(addr: &str) -> NodeInfo {
+        NodeInfo {
+            peer_addr: unwrap!(addr.parse()),
+            peer_cert_der: vec![],
+        }
+    }
+
+    #[test]
+    fn select_endpoint_by_family() {
+        let ipv4 = endpoint("192.0.2.1:5483");
+        let ipv6 = endpoint("[2001:db8::1]:5483");
+        let both = [ipv4.clone(), ipv6.clone()];
+        let any = |_: &SocketAddr| true;
+
+        assert_eq!(
+            AddressFamilyPolicy::PreferIpv4.select(&both, any),
+            Some(&ipv4)
+        );
+        assert_eq!(
+            AddressFamilyPolicy::PreferIpv6.select(&both, any),
+            Some(&ipv6)
+        );
+        // Peers reachable over one family only are still reached over it, if allowed.
+        assert_eq!(
+            AddressFamilyPolicy::PreferIpv6.select(&both[..1], any),
+            Some(&ipv4)
+        );
+        assert_eq!(AddressFamilyPolicy::Ipv6Only.select(&both[..1], any), None);
+        // Endpoints of families we don't listen on are skipped.
+        assert_eq!(
+            AddressFamilyPolicy::PreferIpv6.select(&both, |addr: &SocketAddr| addr.is_ipv4()),
+            Some(&ipv4)
+        );
+    }
+}
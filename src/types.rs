@@ -54,6 +54,12 @@ impl MessageId {
         MessageId(name)
     }
 
+    /// Generate a new `MessageId` with contents extracted from a node being relocated.
+    pub fn from_relocated_node(mut name: XorName) -> MessageId {
+        name.0[0] = b'R';
+        MessageId(name)
+    }
+
     /// Generate the reverse of the given `MessageId`.
     pub fn from_reverse(name: &MessageId) -> MessageId {
         let MessageId(XorName(mut name_mut)) = *name;
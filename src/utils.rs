@@ -6,13 +6,17 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{routing_table::Xorable, xor_name::XorName, Prefix};
+use crate::xor_name::XorName;
+#[cfg(feature = "node")]
+use crate::{routing_table::Xorable, Prefix};
+#[cfg(feature = "node")]
 use itertools::Itertools;
+#[cfg(feature = "node")]
 use safe_crypto;
+#[cfg(feature = "node")]
+use std::{collections::BTreeSet, iter};
 use std::{
-    collections::BTreeSet,
     fmt::{self, Display, Formatter},
-    iter,
     ops::RangeInclusive,
     time::Duration,
 };
@@ -98,6 +102,7 @@ impl Into<RangeInclusive<XorName>> for XorTargetInterval {
 /// In the case where only one close node is provided (in initial network setup scenario):
 ///
 /// [`current_name`, 1st closest node id]
+#[cfg(feature = "node")]
 pub fn calculate_relocation_dst(mut close_nodes: Vec<XorName>, current_name: &XorName) -> XorName {
     close_nodes.sort_by(|a, b| current_name.cmp_distance(a, b));
     let combined: Vec<u8> = iter::once(current_name)
@@ -109,6 +114,7 @@ pub fn calculate_relocation_dst(mut close_nodes: Vec<XorName>, current_name: &Xo
 }
 
 /// Calculate the interval for a node joining our section to generate a key for.
+#[cfg(feature = "node")]
 pub fn calculate_relocation_interval(
     prefix: &Prefix<XorName>,
     section: &BTreeSet<XorName>,
@@ -131,7 +137,7 @@ pub fn calculate_relocation_interval(
     XorTargetInterval(new_end - third_of_distance, new_end)
 }
 
-#[cfg(any(test, feature = "mock_base"))]
+#[cfg(all(feature = "node", any(test, feature = "mock_base")))]
 pub fn rand_index(exclusive_max: usize) -> usize {
     use maidsafe_utilities::SeededRng;
     use rand::Rng;
@@ -140,7 +146,7 @@ pub fn rand_index(exclusive_max: usize) -> usize {
     rng.gen::<usize>() % exclusive_max
 }
 
-#[cfg(all(not(test), not(feature = "mock_base")))]
+#[cfg(all(feature = "node", not(test), not(feature = "mock_base")))]
 pub fn rand_index(exclusive_max: usize) -> usize {
     ::rand::random::<usize>() % exclusive_max
 }
@@ -148,9 +154,13 @@ pub fn rand_index(exclusive_max: usize) -> usize {
 #[cfg(test)]
 mod tests {
     use super::DisplayDuration;
+    #[cfg(feature = "node")]
     use crate::routing_table::Xorable;
+    #[cfg(feature = "node")]
     use crate::xor_name::XorName;
+    #[cfg(feature = "node")]
     use rand;
+    #[cfg(feature = "node")]
     use safe_crypto;
     use std::time::Duration;
 
@@ -171,6 +181,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "node")]
     fn calculate_relocation_dst() {
         let original_name: XorName = rand::random();
 
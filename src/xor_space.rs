@@ -0,0 +1,185 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Containers ordered by the XOR metric, for use outside of the routing table, e.g. in client-side
+//! peer caches or data indexes.
+
+use crate::routing_table::Xorable;
+use crate::xor_name::{XorName, XOR_NAME_BITS};
+use itertools::Itertools;
+use std::collections::BTreeMap;
+
+/// Entries keyed by `XorName`, grouped into buckets by the length of the common prefix of their
+/// name with our own, holding at most `bucket_size` entries each.
+///
+/// Far buckets cover large parts of the name space, near ones small parts, so a full table knows
+/// many names close to ours and a few in every other region of the network.
+#[derive(Clone, Debug)]
+pub struct KBuckets<T> {
+    our_name: XorName,
+    bucket_size: usize,
+    buckets: Vec<BTreeMap<XorName, T>>,
+}
+
+impl<T> KBuckets<T> {
+    /// Creates an empty container centred on `our_name`, with at most `bucket_size` entries per
+    /// bucket.
+    pub fn new(our_name: XorName, bucket_size: usize) -> Self {
+        KBuckets {
+            our_name,
+            bucket_size,
+            buckets: (0..XOR_NAME_BITS).map(|_| BTreeMap::new()).collect(),
+        }
+    }
+
+    /// Returns the name the buckets are centred on.
+    pub fn our_name(&self) -> &XorName {
+        &self.our_name
+    }
+
+    /// Returns the index of the bucket `name` belongs to, i.e. the length of its common prefix
+    /// with our name, or `None` if it is our name.
+    pub fn bucket_index(&self, name: &XorName) -> Option<usize> {
+        if *name == self.our_name {
+            None
+        } else {
+            Some(self.our_name.common_prefix(name))
+        }
+    }
+
+    /// Inserts an entry. Returns the value previously stored for `name`, if any, or gives `value`
+    /// back as an error if `name` is our name or its bucket is full. In that case the caller can
+    /// decide to `remove` another entry of the bucket, e.g. an unresponsive peer, and try again.
+    pub fn insert(&mut self, name: XorName, value: T) -> Result<Option<T>, T> {
+        let index = match self.bucket_index(&name) {
+            Some(index) => index,
+            None => return Err(value),
+        };
+        let bucket = &mut self.buckets[index];
+        if bucket.len() >= self.bucket_size && !bucket.contains_key(&name) {
+            return Err(value);
+        }
+        Ok(bucket.insert(name, value))
+    }
+
+    /// Removes the entry for `name`, returning its value.
+    pub fn remove(&mut self, name: &XorName) -> Option<T> {
+        let index = self.bucket_index(name)?;
+        self.buckets[index].remove(name)
+    }
+
+    /// Returns the value stored for `name`.
+    pub fn get(&self, name: &XorName) -> Option<&T> {
+        let index = self.bucket_index(name)?;
+        self.buckets[index].get(name)
+    }
+
+    /// Returns whether there is an entry for `name`.
+    pub fn contains(&self, name: &XorName) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Returns whether the bucket `name` would be stored in is full.
+    pub fn is_bucket_full(&self, name: &XorName) -> bool {
+        self.bucket_index(name)
+            .map_or(true, |index| self.buckets[index].len() >= self.bucket_size)
+    }
+
+    /// Returns the entries of the bucket with the given index.
+    pub fn bucket(&self, index: usize) -> impl Iterator<Item = (&XorName, &T)> {
+        self.buckets.get(index).into_iter().flat_map(BTreeMap::iter)
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.buckets.iter().map(BTreeMap::len).sum()
+    }
+
+    /// Returns whether there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.buckets.iter().all(BTreeMap::is_empty)
+    }
+
+    /// Returns all entries, closest to `target` first.
+    pub fn iter_by_distance<'a>(
+        &'a self,
+        target: &XorName,
+    ) -> impl Iterator<Item = (&'a XorName, &'a T)> {
+        let target = *target;
+        self.buckets
+            .iter()
+            .flat_map(BTreeMap::iter)
+            .sorted_by(|(lhs, _), (rhs, _)| target.cmp_distance(lhs, rhs))
+    }
+
+    /// Returns the `count` entries closest to `target`, closest first.
+    pub fn closest_n(&self, target: &XorName, count: usize) -> Vec<(&XorName, &T)> {
+        self.iter_by_distance(target).take(count).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+
+    #[test]
+    fn buckets_are_bounded() {
+        let our_name: XorName = rand::random();
+        let mut buckets = KBuckets::new(our_name, 2);
+        assert_eq!(buckets.insert(our_name, 0), Err(0));
+
+        // Names differing from ours in the first bit all go to bucket 0.
+        let far_names: Vec<XorName> = (0..3)
+            .map(|i| {
+                our_name
+                    .with_flipped_bit(0)
+                    .with_bit(XOR_NAME_BITS - 1, i % 2 == 0)
+                    .with_bit(XOR_NAME_BITS - 2, i / 2 == 0)
+            })
+            .collect();
+        assert_eq!(buckets.insert(far_names[0], 1), Ok(None));
+        assert_eq!(buckets.insert(far_names[1], 2), Ok(None));
+        assert!(buckets.is_bucket_full(&far_names[2]));
+        assert_eq!(buckets.insert(far_names[2], 3), Err(3));
+        assert_eq!(buckets.insert(far_names[1], 4), Ok(Some(2)));
+
+        let near_name = our_name.with_flipped_bit(XOR_NAME_BITS - 1);
+        assert_eq!(buckets.bucket_index(&near_name), Some(XOR_NAME_BITS - 1));
+        assert_eq!(buckets.insert(near_name, 5), Ok(None));
+
+        assert_eq!(buckets.len(), 3);
+        assert_eq!(buckets.bucket(0).count(), 2);
+        assert_eq!(buckets.remove(&far_names[0]), Some(1));
+        assert_eq!(buckets.insert(far_names[2], 3), Ok(None));
+        assert_eq!(buckets.get(&far_names[2]), Some(&3));
+    }
+
+    #[test]
+    fn closest_entries() {
+        let our_name: XorName = rand::random();
+        let mut buckets = KBuckets::new(our_name, 100);
+        let mut names = Vec::new();
+        for _ in 0..50 {
+            let name: XorName = rand::random();
+            if buckets.insert(name, ()).is_ok() {
+                names.push(name);
+            }
+        }
+
+        let target: XorName = rand::random();
+        names.sort_by(|lhs, rhs| target.cmp_distance(lhs, rhs));
+        let closest: Vec<_> = buckets
+            .closest_n(&target, 10)
+            .into_iter()
+            .map(|(name, _)| *name)
+            .collect();
+        assert_eq!(closest, names[..10].to_vec());
+        assert_eq!(buckets.iter_by_distance(&target).count(), names.len());
+    }
+}
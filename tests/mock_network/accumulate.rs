@@ -10,8 +10,7 @@ use super::{
     create_connected_nodes, gen_immutable_data, poll_all, sort_nodes_by_distance_to, TestNode,
 };
 use routing::{
-    delivery_group_size, mock::Network, Authority, Event, EventStream, MessageId, Response,
-    XorName, QUORUM_DENOMINATOR, QUORUM_NUMERATOR,
+    delivery_group_size, mock::Network, Authority, Event, EventStream, MessageId, Response, XorName,
 };
 use std::cmp::min;
 
@@ -35,9 +34,7 @@ fn messages_accumulate_with_quorum() {
     };
 
     let dst = Authority::ManagedNode(nodes[0].name()); // The closest node.
-                                                       // The smallest number such that
-                                                       // `quorum * QUORUM_DENOMINATOR > section_size * QUORUM_NUMERATOR`:
-    let quorum = 1 + (section_size * QUORUM_NUMERATOR) / QUORUM_DENOMINATOR;
+    let quorum = nodes[0].chain().network_params().quorum_size(section_size);
 
     // Send a message from the section `src` to the node `dst`.
     // Only the `quorum`-th sender should cause accumulation and a
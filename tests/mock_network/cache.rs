@@ -139,3 +139,79 @@ fn response_caching() {
         expect_no_event!(node);
     }
 }
+
+// Lets the nodes managing `data` respond to the `GetIData` request with the given message ID.
+fn respond_to_get(nodes: &mut [TestNode], data: &ImmutableData, message_id: MessageId) {
+    for node in nodes {
+        while let Ok(event) = node.try_next_ev() {
+            if let Event::RequestReceived {
+                request: Request::GetIData { name, msg_id },
+                src,
+                dst,
+            } = event
+            {
+                if name == *data.name() && msg_id == message_id {
+                    unwrap!(node
+                        .inner
+                        .send_get_idata_response(dst, src, Ok(data.clone()), msg_id));
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn cached_responses_take_fewer_hops() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes_until_split(&network, vec![1, 1], true);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+
+    let data = gen_immutable_data_not_in_first_node_section(&mut rng, &nodes);
+    let dst = Authority::NaeManager(*data.name());
+
+    // The first request travels all the way to the data's managers and back.
+    let sent_before = network.sent_messages();
+    let message_id = MessageId::new();
+    unwrap!(clients[0].inner.get_idata(dst, *data.name(), message_id));
+    let _ = poll_all(&mut nodes, &mut clients);
+    respond_to_get(&mut nodes, &data, message_id);
+    let _ = poll_all(&mut nodes, &mut clients);
+    expect_any_event!(
+        clients[0],
+        Event::ResponseReceived {
+            response: Response::GetIData { res: Ok(ref res_data), .. },
+            ..
+        } if *res_data == data
+    );
+    let uncached_hops = network.sent_messages() - sent_before;
+    assert_eq!(nodes[0].inner.metrics().relay_cache_hits(), 0);
+    assert!(nodes[0].inner.metrics().relay_cache_misses() > 0);
+
+    while let Ok(_) = clients[0].inner.try_next_ev() {}
+    FakeClock::advance_time(1000);
+
+    // The second one is answered by the proxy node.
+    let sent_before = network.sent_messages();
+    let message_id = MessageId::new();
+    unwrap!(clients[0].inner.get_idata(dst, *data.name(), message_id));
+    let _ = poll_all(&mut nodes, &mut clients);
+    expect_any_event!(
+        clients[0],
+        Event::ResponseReceived {
+            response: Response::GetIData { res: Ok(ref res_data), .. },
+            ..
+        } if *res_data == data
+    );
+    let cached_hops = network.sent_messages() - sent_before;
+    assert_eq!(nodes[0].inner.metrics().relay_cache_hits(), 1);
+    assert!(
+        cached_hops < uncached_hops,
+        "{} hops with cache, {} without",
+        cached_hops,
+        uncached_hops
+    );
+}
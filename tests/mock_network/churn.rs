@@ -15,7 +15,7 @@ use itertools::Itertools;
 use rand::Rng;
 use routing::{
     mock::Network, Authority, Event, EventStream, ImmutableData, MessageId, NetworkConfig, Request,
-    Response, XorName, XorTargetInterval, QUORUM_DENOMINATOR, QUORUM_NUMERATOR,
+    Response, XorName, XorTargetInterval,
 };
 use std::{
     cmp,
@@ -222,8 +222,10 @@ fn random_churn<R: Rng>(
     }
 
     // Use min_sec_size rather than section size to prevent collapsing any groups.
-    let max_drop = (nodes[0].chain().min_sec_size() - 1) * (QUORUM_DENOMINATOR - QUORUM_NUMERATOR)
-        / QUORUM_DENOMINATOR;
+    let params = *nodes[0].chain().network_params();
+    let max_drop = (nodes[0].chain().min_sec_size() - 1)
+        * (params.quorum_denominator - params.quorum_numerator)
+        / params.quorum_denominator;
     assert!(max_drop > 0);
     let dropped_nodes = drop_random_nodes(rng, nodes, Some(max_drop));
     warn!("Dropping nodes: {:?}", dropped_nodes);
@@ -268,8 +270,9 @@ impl ExpectedPuts {
             sent_count += 1;
         }
         if src.is_multiple() {
+            let params = nodes[0].chain().network_params();
             assert!(
-                sent_count * QUORUM_DENOMINATOR > min_section_size * QUORUM_NUMERATOR,
+                params.is_quorum(sent_count, min_section_size),
                 "sent_count: {}. min_section_size: {}",
                 sent_count,
                 min_section_size
@@ -391,13 +394,14 @@ impl ExpectedPuts {
                 }
             }
         }
+        let params = *nodes[0].chain().network_params();
         for key in self.messages {
             // All received messages for single nodes were removed: if any are left, they failed.
             assert!(key.3.is_multiple(), "Failed to receive request {:?}", key);
             let section_size = section_sizes[&key.3];
             let count = section_msgs_received.remove(&key).unwrap_or(0);
             assert!(
-                count * QUORUM_DENOMINATOR > section_size * QUORUM_NUMERATOR,
+                params.is_quorum(count, section_size),
                 "Only received {} out of {} messages {:?}.",
                 count,
                 section_size,
@@ -0,0 +1,234 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! A simulator driving long random sequences of churn events from a single seed.
+//!
+//! Every step either adds a node, drops some nodes or adds a node relocated to a chosen section,
+//! then lets the network settle and checks the chains of all nodes. The seed and the failing step
+//! are printed if a run panics, so it can be reproduced by passing the seed in the
+//! `ROUTING_CHURN_SEED` environment variable, e.g. `ROUTING_CHURN_SEED=1,2,3,4`.
+
+use super::{
+    clear_relocation_overrides, create_connected_nodes, current_sections, gen_range,
+    poll_and_resend, remove_nodes_which_failed_to_connect, verify_invariant_for_all_nodes,
+    TestNode,
+};
+use maidsafe_utilities::SeededRng;
+use rand::{self, Rng};
+use routing::{mock::Network, NetworkConfig, Prefix, XorName, XorTargetInterval};
+use std::{collections::BTreeSet, env, thread};
+
+/// The environment variable holding the seed to replay, as four comma-separated numbers.
+const CHURN_SEED_ENV_VAR: &str = "ROUTING_CHURN_SEED";
+const MIN_SECTION_SIZE: usize = 4;
+/// The number of churn steps per run.
+const STEPS: usize = 40;
+/// Above this many nodes, only drops are simulated.
+const MAX_NODES: usize = 40;
+
+#[derive(Clone, Debug)]
+enum Step {
+    /// A node joins via a random proxy.
+    Join,
+    /// Up to this many nodes of one section leave.
+    Drop(usize),
+    /// A node joins and is relocated to the section with the given prefix.
+    Relocate(Prefix<XorName>),
+}
+
+/// Prints the seed of the run, and the step it was at, if the run panics.
+struct SeedPrinter {
+    seed: [u32; 4],
+    step: Option<(usize, Step)>,
+}
+
+impl Drop for SeedPrinter {
+    fn drop(&mut self) {
+        if !thread::panicking() {
+            return;
+        }
+        let seed: Vec<_> = self.seed.iter().map(u32::to_string).collect();
+        match self.step {
+            Some((index, ref step)) => println!(
+                "Churn simulation failed at step {} ({:?}). Replay it with {}={}",
+                index,
+                step,
+                CHURN_SEED_ENV_VAR,
+                seed.join(",")
+            ),
+            None => println!(
+                "Churn simulation failed during setup. Replay it with {}={}",
+                CHURN_SEED_ENV_VAR,
+                seed.join(",")
+            ),
+        }
+    }
+}
+
+fn seed() -> [u32; 4] {
+    if let Some(value) = env::var_os(CHURN_SEED_ENV_VAR) {
+        let value = unwrap!(
+            value.into_string(),
+            "{} isn't valid unicode",
+            CHURN_SEED_ENV_VAR
+        );
+        let parts: Vec<u32> = value
+            .split(',')
+            .map(|part| unwrap!(part.trim().parse(), "Invalid seed {:?}", value))
+            .collect();
+        assert_eq!(parts.len(), 4, "Invalid seed {:?}", value);
+        [parts[0], parts[1], parts[2], parts[3]]
+    } else {
+        rand::thread_rng().gen()
+    }
+}
+
+fn choose_step(rng: &mut SeededRng, nodes: &[TestNode]) -> Step {
+    if nodes.len() >= MAX_NODES {
+        return Step::Drop(gen_range(rng, 1, 3));
+    }
+    let sections: Vec<_> = current_sections(nodes).into_iter().collect();
+    match gen_range(rng, 0, 10) {
+        0..=4 => Step::Join,
+        5..=7 => Step::Drop(gen_range(rng, 1, 3)),
+        _ => Step::Relocate(*unwrap!(rng.choose(&sections))),
+    }
+}
+
+// Adds a node bootstrapping off a random proxy, relocated to `dst` if given. Drops it again if
+// it failed to join.
+fn add_node(
+    rng: &mut SeededRng,
+    network: &Network,
+    nodes: &mut Vec<TestNode>,
+    dst: Option<Prefix<XorName>>,
+) {
+    if let Some(dst) = dst {
+        let relocation_dst = dst.substituted_in(rng.gen());
+        for node in nodes.iter_mut() {
+            node.inner.set_next_relocation_dst(Some(relocation_dst));
+            if *node.our_prefix() == dst {
+                node.inner
+                    .set_next_relocation_interval(Some(XorTargetInterval::new(
+                        dst.range_inclusive(),
+                    )));
+            }
+        }
+    }
+
+    let proxy = gen_range(rng, 0, nodes.len());
+    let config = NetworkConfig::node().with_hard_coded_contact(nodes[proxy].endpoint());
+    nodes.push(TestNode::builder(network).network_config(config).create());
+    poll_and_resend(nodes, &mut []);
+    clear_relocation_overrides(nodes);
+    let _ = remove_nodes_which_failed_to_connect(nodes, 1);
+}
+
+// Drops up to `count` random nodes of one section, leaving it a quorum of its members and at least
+// `MIN_SECTION_SIZE` of them. Never drops the first node.
+fn drop_nodes(rng: &mut SeededRng, nodes: &mut Vec<TestNode>, count: usize) {
+    let index = gen_range(rng, 1, nodes.len());
+    let prefix = *nodes[index].our_prefix();
+    let size = nodes[index].chain().our_info().members().len();
+    let params = *nodes[index].chain().network_params();
+
+    let mut dropped = 0;
+    while dropped < count
+        && size - dropped > MIN_SECTION_SIZE
+        && params.is_quorum(size - dropped - 1, size)
+    {
+        let candidates: Vec<_> = (1..nodes.len())
+            .filter(|&index| *nodes[index].our_prefix() == prefix)
+            .collect();
+        let index = match rng.choose(&candidates) {
+            Some(&index) => index,
+            None => break,
+        };
+        drop(nodes.remove(index));
+        dropped += 1;
+    }
+    verify_quorum_safety(nodes);
+    poll_and_resend(nodes, &mut []);
+}
+
+// Panics unless, according to every node, a quorum of its section's members is still alive.
+fn verify_quorum_safety(nodes: &[TestNode]) {
+    let alive: BTreeSet<_> = nodes.iter().map(TestNode::name).collect();
+    for node in nodes {
+        let info = node.chain().our_info();
+        let live_members = info
+            .members()
+            .iter()
+            .filter(|pub_id| alive.contains(pub_id.name()))
+            .count();
+        assert!(
+            node.chain()
+                .network_params()
+                .is_quorum(live_members, info.members().len()),
+            "{}: only {} of the {} members of {:?} are alive.",
+            node.name(),
+            live_members,
+            info.members().len(),
+            info.prefix()
+        );
+    }
+}
+
+// Panics unless all nodes agree on consistent chains with valid histories, no chain has
+// compatible prefixes and every section keeps a quorum.
+fn verify_step(network: &Network, nodes: &mut [TestNode]) {
+    verify_invariant_for_all_nodes(network, nodes);
+    for node in nodes.iter() {
+        let chain = node.chain();
+        assert!(
+            chain.validate_our_history(),
+            "{}: our section's history is invalid.",
+            node.name()
+        );
+
+        let mut prefixes = vec![*chain.our_prefix()];
+        for info in chain.neighbour_infos() {
+            if let Some(prefix) = prefixes.iter().find(|pfx| pfx.is_compatible(info.prefix())) {
+                panic!(
+                    "{}: found compatible prefixes {:?} and {:?}.",
+                    node.name(),
+                    prefix,
+                    info.prefix()
+                );
+            }
+            prefixes.push(*info.prefix());
+        }
+    }
+    verify_quorum_safety(nodes);
+}
+
+fn simulate(seed: [u32; 4]) {
+    let mut printer = SeedPrinter { seed, step: None };
+    let network = Network::new(MIN_SECTION_SIZE, Some(seed));
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, 2 * MIN_SECTION_SIZE).0;
+    verify_step(&network, &mut nodes);
+
+    for index in 0..STEPS {
+        let step = choose_step(&mut rng, &nodes);
+        printer.step = Some((index, step.clone()));
+        match step {
+            Step::Join => add_node(&mut rng, &network, &mut nodes, None),
+            Step::Drop(count) => drop_nodes(&mut rng, &mut nodes, count),
+            Step::Relocate(dst) => add_node(&mut rng, &network, &mut nodes, Some(dst)),
+        }
+        verify_step(&network, &mut nodes);
+    }
+
+    network.shutdown_and_verify(nodes.into_iter().map(|node| node.inner), Vec::new());
+}
+
+#[test]
+fn random_churn_from_seed() {
+    simulate(seed())
+}
@@ -0,0 +1,224 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Replays churn captured from a real network against the mock network.
+//!
+//! A trace is a JSON document listing joins and leaves with the time they happened at, in
+//! milliseconds since the start of the capture:
+//!
+//! ```json
+//! { "events": [
+//!     { "time_ms": 0, "action": "join", "node": "a" },
+//!     { "time_ms": 95000, "action": "leave", "node": "a" }
+//! ] }
+//! ```
+//!
+//! `node` is any label identifying the node in the capture. Since names are assigned by the
+//! network, only leaves of nodes which joined during the replay can be applied; others are skipped.
+
+use super::{create_connected_nodes, poll_and_resend, verify_invariant_for_all_nodes, TestNode};
+use fake_clock::FakeClock;
+use routing::{mock::Network, Event, EventStream, NetworkConfig, XorName};
+use serde_derive::Deserialize;
+use std::{
+    collections::{BTreeSet, HashMap},
+    env, fs,
+};
+
+/// Environment variable holding the path of a trace to replay in `replay_trace_from_env`.
+const TRACE_PATH_ENV_VAR: &str = "ROUTING_CHURN_TRACE";
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceAction {
+    Join,
+    Leave,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TraceEvent {
+    pub time_ms: u64,
+    pub action: TraceAction,
+    pub node: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChurnTrace {
+    pub events: Vec<TraceEvent>,
+}
+
+impl ChurnTrace {
+    /// Parses a trace, ordering its events by time.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let mut trace: ChurnTrace = serde_json::from_str(json)?;
+        trace.events.sort_by_key(|event| event.time_ms);
+        Ok(trace)
+    }
+}
+
+/// What happened to the events of a replayed trace.
+#[derive(Debug, Default)]
+pub struct ReplayStats {
+    pub joined: usize,
+    pub failed_joins: usize,
+    pub left: usize,
+    pub skipped: usize,
+}
+
+/// Applies the events of `trace` to `nodes` one at a time, advancing the fake clock by the time
+/// between them and verifying the network invariant after each.
+pub fn replay_churn_trace(
+    network: &Network,
+    nodes: &mut Vec<TestNode>,
+    trace: &ChurnTrace,
+) -> ReplayStats {
+    let mut stats = ReplayStats::default();
+    let mut names: HashMap<&str, XorName> = HashMap::new();
+    let mut last_time_ms = trace.events.first().map_or(0, |event| event.time_ms);
+
+    for event in &trace.events {
+        FakeClock::advance_time(event.time_ms - last_time_ms);
+        last_time_ms = event.time_ms;
+
+        match event.action {
+            TraceAction::Join => {
+                if names.contains_key(event.node.as_str()) {
+                    stats.skipped += 1;
+                    continue;
+                }
+                match join_node(network, nodes) {
+                    Some(name) => {
+                        let _ = names.insert(event.node.as_str(), name);
+                        stats.joined += 1;
+                    }
+                    None => stats.failed_joins += 1,
+                }
+            }
+            TraceAction::Leave => {
+                let index = names
+                    .remove(event.node.as_str())
+                    .and_then(|name| nodes.iter().position(|node| node.name() == name));
+                match index {
+                    Some(index) if nodes.len() > 1 => {
+                        drop(nodes.remove(index));
+                        poll_and_resend(nodes, &mut []);
+                        stats.left += 1;
+                    }
+                    _ => stats.skipped += 1,
+                }
+            }
+        }
+
+        verify_invariant_for_all_nodes(network, nodes);
+    }
+
+    stats
+}
+
+/// Asserts that every section holds at least `min_section_size` nodes, and that each node's
+/// section info lists exactly the nodes of its prefix.
+fn verify_section_sizes(network: &Network, nodes: &[TestNode]) {
+    for node in nodes {
+        let members: BTreeSet<_> = nodes
+            .iter()
+            .filter(|other| node.our_prefix().matches(&other.name()))
+            .map(TestNode::id)
+            .collect();
+        assert_eq!(
+            *node.chain().our_info().members(),
+            members,
+            "{}: section info doesn't match the nodes of {:?}",
+            node.name(),
+            node.our_prefix()
+        );
+        assert!(
+            members.len() >= network.min_section_size(),
+            "{:?} has only {} members",
+            node.our_prefix(),
+            members.len()
+        );
+    }
+}
+
+/// Adds a node bootstrapping off the first one. Returns its name if it got accepted.
+fn join_node(network: &Network, nodes: &mut Vec<TestNode>) -> Option<XorName> {
+    let network_config = NetworkConfig::node().with_hard_coded_contact(nodes[0].endpoint());
+    nodes.push(
+        TestNode::builder(network)
+            .network_config(network_config)
+            .create(),
+    );
+    poll_and_resend(nodes, &mut []);
+
+    let mut node = unwrap!(nodes.pop());
+    while let Ok(event) = node.try_next_ev() {
+        if let Event::Connected = event {
+            let name = node.name();
+            nodes.push(node);
+            return Some(name);
+        }
+    }
+
+    drop(node);
+    poll_and_resend(nodes, &mut []);
+    None
+}
+
+const SAMPLE_TRACE: &str = r#"{ "events": [
+    { "time_ms": 0, "action": "join", "node": "n1" },
+    { "time_ms": 4200, "action": "join", "node": "n2" },
+    { "time_ms": 4900, "action": "join", "node": "n3" },
+    { "time_ms": 61000, "action": "leave", "node": "n2" },
+    { "time_ms": 30500, "action": "join", "node": "n4" },
+    { "time_ms": 75000, "action": "leave", "node": "unknown" },
+    { "time_ms": 90000, "action": "leave", "node": "n1" },
+    { "time_ms": 120000, "action": "join", "node": "n5" }
+] }"#;
+
+#[test]
+fn replay_sample_trace() {
+    let trace = unwrap!(ChurnTrace::from_json(SAMPLE_TRACE));
+    assert!(trace
+        .events
+        .windows(2)
+        .all(|pair| pair[0].time_ms <= pair[1].time_ms));
+
+    let min_section_size = 5;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+
+    let stats = replay_churn_trace(&network, &mut nodes, &trace);
+    assert_eq!(stats.joined, 5);
+    assert_eq!(stats.failed_joins, 0);
+    assert_eq!(stats.left, 2);
+    assert_eq!(stats.skipped, 1);
+
+    assert_eq!(nodes.len(), min_section_size + 1 + 5 - 2);
+    verify_section_sizes(&network, &nodes);
+}
+
+/// Replays the trace at the path given in `ROUTING_CHURN_TRACE`, to evaluate changes against
+/// churn captured from a real network.
+#[test]
+#[ignore]
+fn replay_trace_from_env() {
+    let path = unwrap!(
+        env::var(TRACE_PATH_ENV_VAR),
+        "{} not set",
+        TRACE_PATH_ENV_VAR
+    );
+    let trace = unwrap!(ChurnTrace::from_json(&unwrap!(fs::read_to_string(&path))));
+
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, min_section_size);
+
+    let stats = replay_churn_trace(&network, &mut nodes, &trace);
+    info!("Replayed {}: {:?}", path, stats);
+    verify_section_sizes(&network, &nodes);
+}
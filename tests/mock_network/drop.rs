@@ -7,10 +7,11 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
-    create_connected_nodes, poll_all, poll_and_resend, verify_invariant_for_all_nodes, TestNode,
+    create_connected_nodes, poll_all, poll_and_resend, verify_invariant_for_all_nodes, TestClient,
+    TestNode,
 };
 use rand::Rng;
-use routing::{mock::Network, Event, EventStream};
+use routing::{mock::Network, Event, EventStream, NetworkConfig};
 
 // Drop node at index and verify its own section receives NodeLost.
 fn drop_node(nodes: &mut Vec<TestNode>, index: usize) {
@@ -48,6 +49,62 @@ fn node_drops() {
     verify_invariant_for_all_nodes(&network, &mut nodes);
 }
 
+#[test]
+fn node_shuts_down() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, min_section_size + 2);
+
+    unwrap!(nodes[0].inner.shutdown());
+    poll_and_resend(&mut nodes, &mut []);
+    expect_any_event!(nodes[0], Event::ShutdownComplete);
+
+    let node = nodes.remove(0);
+    let name = node.name();
+    drop(node);
+    poll_and_resend(&mut nodes, &mut []);
+    for node in nodes.iter_mut() {
+        assert!(!node.inner.chain().map_or(false, |chain| chain
+            .our_info()
+            .members()
+            .iter()
+            .any(|pub_id| *pub_id.name() == name)));
+    }
+    verify_invariant_for_all_nodes(&network, &mut nodes);
+}
+
+#[test]
+fn shutting_down_proxy_fails_its_clients_over() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, min_section_size + 2);
+    let network_config = NetworkConfig::client()
+        .with_hard_coded_contacts(vec![nodes[0].endpoint(), nodes[1].endpoint()]);
+    let client_endpoint = network.gen_addr();
+    let mut clients = vec![TestClient::new(
+        &network,
+        Some(network_config),
+        Some(client_endpoint),
+    )];
+    let _ = poll_all(&mut nodes, &mut clients);
+    expect_next_event!(clients[0], Event::Connected);
+
+    let proxy_index = unwrap!(
+        (0..2).find(|&index| network.is_connected(&client_endpoint, &nodes[index].endpoint()))
+    );
+    let other_index = 1 - proxy_index;
+
+    // The client moves to the other contact before the proxy is gone.
+    unwrap!(nodes[proxy_index].inner.shutdown());
+    let _ = poll_all(&mut nodes, &mut clients);
+    expect_any_event!(clients[0], Event::Connected);
+    assert!(network.is_connected(&client_endpoint, &nodes[other_index].endpoint()));
+    assert!(!network.is_connected(&client_endpoint, &nodes[proxy_index].endpoint()));
+
+    poll_and_resend(&mut nodes, &mut clients);
+    expect_any_event!(nodes[proxy_index], Event::ShutdownComplete);
+}
+
 #[test]
 fn node_restart() {
     // Idea of test: if a node disconnects from all other nodes, it should restart
@@ -52,7 +52,17 @@ fn merge(prefix_lengths: Vec<usize>) {
         for node in &mut *nodes {
             while let Ok(event) = node.try_next_ev() {
                 match event {
-                    Event::NodeAdded(..) | Event::NodeLost(..) | Event::TimerTicked => (),
+                    Event::NodeAdded(..)
+                    | Event::NodeLost(..)
+                    | Event::VoteStalled { .. }
+                    | Event::StaleVotesPurged { .. }
+                    | Event::CachedEventsEvicted { .. }
+                    | Event::Promoted
+                    | Event::Demoted
+                    | Event::RefreshRequired { .. }
+                    | Event::OutOfOrderDetected { .. }
+                    | Event::MessageDelivered { .. }
+                    | Event::TimerTicked => (),
                     Event::SectionMerged(prefix) => {
                         if prefix.is_empty() {
                             merge_events_missing -= 1;
@@ -9,8 +9,12 @@
 mod accumulate;
 mod cache;
 mod churn;
+mod churn_sim;
+mod churn_trace;
 mod drop;
 mod merge;
+#[cfg(feature = "model_check")]
+mod model_check;
 mod requests;
 mod utils;
 
@@ -26,8 +30,14 @@ use fake_clock::FakeClock;
 use itertools::Itertools;
 use rand::Rng;
 use routing::{
-    mock::Network, test_consts, Event, EventStream, NetworkConfig, Prefix, XorName,
-    XorTargetInterval,
+    mock::Network, test_consts, Event, EventStream, MessageId, NetworkConfig, Prefix,
+    StabilityGuard, XorName, XorTargetInterval,
+};
+use std::{
+    cell::RefCell,
+    collections::BTreeSet,
+    io::{self, Write},
+    rc::Rc,
 };
 
 pub const MIN_SECTION_SIZE: usize = 3;
@@ -51,6 +61,7 @@ fn test_nodes(percentage_size: usize) {
     let network = Network::new(MIN_SECTION_SIZE, None);
     let mut nodes = create_connected_nodes(&network, size);
     verify_invariant_for_all_nodes(&network, &mut nodes);
+    network.shutdown_and_verify(nodes.0.into_iter().map(|node| node.inner), Vec::new());
 }
 
 fn nodes_with_prefix_mut<'a>(
@@ -128,6 +139,30 @@ fn candidate_timeout_resource_proof() {
     );
 }
 
+#[test]
+fn simultaneous_candidates_are_queued_and_approved() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE);
+
+    // The section only processes one candidate at a time. The others wait in its queue rather
+    // than being told to come back later.
+    let candidate_count = 3;
+    for _ in 0..candidate_count {
+        let network_config = NetworkConfig::node().with_hard_coded_contact(nodes[0].endpoint());
+        nodes.push(
+            TestNode::builder(&network)
+                .network_config(network_config)
+                .create(),
+        );
+    }
+    poll_and_resend(&mut nodes, &mut []);
+
+    for node in &mut nodes[MIN_SECTION_SIZE..] {
+        assert!(node.inner.is_node(), "{} wasn't approved", node.name());
+        expect_next_event!(node, Event::Connected);
+    }
+}
+
 #[test]
 fn single_section() {
     let sec_size = 10;
@@ -136,6 +171,45 @@ fn single_section() {
     verify_invariant_for_all_nodes(&network, &mut nodes);
 }
 
+/// Collects the network trace in memory.
+#[derive(Clone, Default)]
+struct TraceBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for TraceBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn visualiser_trace() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let buffer = TraceBuffer::default();
+    network.set_trace_output(buffer.clone());
+    let nodes = create_connected_nodes(&network, MIN_SECTION_SIZE);
+
+    let trace = unwrap!(String::from_utf8(buffer.0.borrow().clone()));
+    assert!(trace.starts_with("ROUND 0 "));
+
+    // Once connected, all nodes are members of the single section with the empty prefix.
+    let last_round = unwrap!(trace.rsplit("ROUND ").next());
+    let sections: Vec<_> = last_round
+        .lines()
+        .filter(|line| line.starts_with("SECTION "))
+        .collect();
+    assert_eq!(sections.len(), 1);
+    assert!(sections[0].starts_with("SECTION _ "));
+    for node in &nodes {
+        assert!(sections[0].contains(&format!(" {}", node.name())));
+        assert!(last_round.contains(&format!("NODE {} _ ", node.name())));
+    }
+    assert!(last_round.ends_with("END\n"));
+}
+
 #[test]
 fn less_than_section_size_nodes() {
     test_nodes(80)
@@ -155,7 +229,11 @@ fn more_than_section_size_nodes() {
 fn client_connects_to_nodes() {
     let network = Network::new(MIN_SECTION_SIZE, None);
     let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE + 1);
-    let _ = create_connected_clients(&network, &mut nodes, 1);
+    let clients = create_connected_clients(&network, &mut nodes, 1);
+    network.shutdown_and_verify(
+        nodes.0.into_iter().map(|node| node.inner),
+        clients.into_iter().map(|client| client.inner),
+    );
 }
 
 #[test]
@@ -248,8 +326,8 @@ struct SimultaneousJoiningNode {
 
 // Proceed with testing joining nodes at the same time with the given configuration.
 fn simultaneous_joining_nodes(
-    network: Network,
-    mut nodes: Nodes,
+    network: &Network,
+    nodes: &mut Nodes,
     nodes_to_add_setup: &[SimultaneousJoiningNode],
 ) {
     //
@@ -257,20 +335,20 @@ fn simultaneous_joining_nodes(
     // Setup nodes so relocation will happen as specified by nodes_to_add_setup.
     //
     let mut rng = network.new_rng();
-    rng.shuffle(&mut nodes);
+    rng.shuffle(nodes);
 
     let mut nodes_to_add = Vec::new();
     for setup in nodes_to_add_setup {
         // Set the specified relocation destination on the nodes of the given prefixes
         let relocation_dst = setup.dst_section_prefix.substituted_in(rng.gen());
-        nodes_with_prefix_mut(&mut nodes, &setup.src_section_prefix)
+        nodes_with_prefix_mut(nodes, &setup.src_section_prefix)
             .for_each(|node| node.inner.set_next_relocation_dst(Some(relocation_dst)));
 
         // Set the specified relocation interval on the nodes of the given prefixes
         let relocation_interval = setup
             .dst_relocation_interval_prefix
             .map(|prefix| XorTargetInterval::new(prefix.range_inclusive()));
-        nodes_with_prefix_mut(&mut nodes, &setup.dst_section_prefix).for_each(|node| {
+        nodes_with_prefix_mut(nodes, &setup.dst_section_prefix).for_each(|node| {
             node.inner
                 .set_next_relocation_interval(relocation_interval.clone())
         });
@@ -282,13 +360,13 @@ fn simultaneous_joining_nodes(
                 // Get random bootstrap node from within proxy_prefix
                 let config = {
                     let mut compatible_proxies =
-                        nodes_with_prefix_mut(&mut nodes, &setup.proxy_prefix).collect_vec();
+                        nodes_with_prefix_mut(nodes, &setup.proxy_prefix).collect_vec();
                     rng.shuffle(&mut compatible_proxies);
 
                     NetworkConfig::node().with_hard_coded_contact(unwrap!(nodes.first()).endpoint())
                 };
 
-                let node = TestNode::builder(&network).network_config(config).create();
+                let node = TestNode::builder(network).network_config(config).create();
                 if setup.src_section_prefix.matches(&node.name()) {
                     break node;
                 }
@@ -302,7 +380,7 @@ fn simultaneous_joining_nodes(
     // Add new nodes and process until complete
     //
     nodes.extend(nodes_to_add.into_iter());
-    poll_and_resend(&mut nodes, &mut []);
+    poll_and_resend(nodes, &mut []);
 
     //
     // Assert
@@ -318,14 +396,14 @@ fn simultaneous_joining_nodes(
         "Should be full node: {:?}",
         non_full_nodes
     );
-    verify_invariant_for_all_nodes(&network, &mut nodes);
+    verify_invariant_for_all_nodes(network, nodes);
 }
 
 #[test]
 fn simultaneous_joining_nodes_two_sections() {
     // Create a network with two sections:
     let network = Network::new(MIN_SECTION_SIZE, None);
-    let nodes = create_connected_nodes_until_split(&network, vec![1, 1], false);
+    let mut nodes = create_connected_nodes_until_split(&network, vec![1, 1], false);
 
     let prefix_0 = Prefix::default().pushed(false);
     let prefix_1 = Prefix::default().pushed(true);
@@ -345,14 +423,14 @@ fn simultaneous_joining_nodes_two_sections() {
             proxy_prefix: prefix_0,
         },
     ];
-    simultaneous_joining_nodes(network, nodes, &nodes_to_add_setup);
+    simultaneous_joining_nodes(&network, &mut nodes, &nodes_to_add_setup);
 }
 
 #[test]
 fn simultaneous_joining_nodes_two_sections_switch_section() {
     // Create a network with two sections:
     let network = Network::new(MIN_SECTION_SIZE, None);
-    let nodes = create_connected_nodes_until_split(&network, vec![1, 1], false);
+    let mut nodes = create_connected_nodes_until_split(&network, vec![1, 1], false);
 
     let prefix_0 = Prefix::default().pushed(false);
     let prefix_1 = Prefix::default().pushed(true);
@@ -372,7 +450,7 @@ fn simultaneous_joining_nodes_two_sections_switch_section() {
             proxy_prefix: prefix_0,
         },
     ];
-    simultaneous_joining_nodes(network, nodes, &nodes_to_add_setup);
+    simultaneous_joining_nodes(&network, &mut nodes, &nodes_to_add_setup);
 }
 
 #[test]
@@ -423,7 +501,59 @@ fn simultaneous_joining_nodes_three_section_with_one_ready_to_split() {
             proxy_prefix: long_prefix_1.with_flipped_bit(0).with_flipped_bit(1),
         },
     ];
-    simultaneous_joining_nodes(network, nodes, &nodes_to_add_setup);
+    simultaneous_joining_nodes(&network, &mut nodes, &nodes_to_add_setup);
+}
+
+#[test]
+fn simultaneous_split_of_neighbouring_sections() {
+    // Create a network with two sections:
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut nodes = create_connected_nodes_until_split(&network, vec![1, 1], false);
+
+    let prefix_0 = Prefix::default().pushed(false);
+    let prefix_1 = Prefix::default().pushed(true);
+
+    // Setup the network so both sections will split with one more node each.
+    let prefixes_to_add = add_connected_nodes_until_one_away_from_split(
+        &network,
+        &mut nodes,
+        &[prefix_0, prefix_1],
+        false,
+    );
+
+    // Add the node triggering the split to each section in the same round, so both sections
+    // learn about the other's split while still splitting themselves.
+    let nodes_to_add_setup = [prefix_0, prefix_1]
+        .iter()
+        .zip(prefixes_to_add)
+        .map(|(prefix, prefix_to_add)| SimultaneousJoiningNode {
+            dst_section_prefix: *prefix,
+            src_section_prefix: *prefix,
+            dst_relocation_interval_prefix: Some(prefix_to_add),
+            proxy_prefix: prefix_0,
+        })
+        .collect_vec();
+    simultaneous_joining_nodes(&network, &mut nodes, &nodes_to_add_setup);
+
+    // Both sections have split and everyone's neighbour infos converged to the new sections.
+    let expected_sections: BTreeSet<_> = [prefix_0, prefix_1]
+        .iter()
+        .flat_map(|prefix| vec![prefix.pushed(false), prefix.pushed(true)])
+        .collect();
+    assert_eq!(current_sections(&nodes), expected_sections);
+    for node in nodes.iter() {
+        let our_prefix = *node.chain().our_prefix();
+        let neighbours: BTreeSet<_> = node
+            .chain()
+            .neighbour_infos()
+            .map(|info| *info.prefix())
+            .collect();
+        let expected_neighbours: BTreeSet<_> = current_sections(&nodes)
+            .into_iter()
+            .filter(|prefix| our_prefix.is_neighbour(prefix))
+            .collect();
+        assert_eq!(neighbours, expected_neighbours, "{}", node.name());
+    }
 }
 
 #[test]
@@ -463,3 +593,159 @@ fn check_section_info_ack() {
     let expected_all: Vec<_> = nodes.iter().map(|node| node.id()).collect();
     assert_eq!(node_with_sibling_knowledge, expected_all);
 }
+
+#[test]
+fn handoff_between_sections() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut nodes = create_connected_nodes_until_split(&network, vec![1, 1], false);
+    let from: Prefix<XorName> = unwrap!("0".parse());
+    let to: Prefix<XorName> = unwrap!("1".parse());
+    let range: Prefix<XorName> = unwrap!("10".parse());
+    let handoff_id = MessageId::new();
+
+    // Section `1` can't confirm a handoff nobody prepared.
+    for node in nodes_with_prefix_mut(&mut nodes, &to) {
+        assert!(node.inner.confirm_handoff(handoff_id).is_err());
+    }
+
+    for node in nodes_with_prefix_mut(&mut nodes, &from) {
+        unwrap!(node.inner.start_handoff(range, to, handoff_id));
+    }
+    let _ = poll_all(&mut nodes, &mut []);
+    for node in nodes_with_prefix_mut(&mut nodes, &to) {
+        expect_any_event!(
+            node,
+            Event::HandoffPrepare {
+                range: event_range,
+                from: event_from,
+                handoff_id: event_id,
+            } if event_range == range && event_from == from && event_id == handoff_id
+        );
+        unwrap!(node.inner.confirm_handoff(handoff_id));
+    }
+
+    let _ = poll_all(&mut nodes, &mut []);
+    for node in nodes.iter_mut() {
+        expect_any_event!(
+            node,
+            Event::HandoffCommit {
+                range: event_range,
+                from: event_from,
+                to: event_to,
+                handoff_id: event_id,
+            } if event_range == range
+                && event_from == from
+                && event_to == to
+                && event_id == handoff_id
+        );
+    }
+}
+
+#[test]
+fn refresh_new_member() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE);
+    let names: BTreeSet<XorName> = (0..10).map(|_| rand::random()).collect();
+
+    let config = NetworkConfig::node().with_hard_coded_contact(nodes[0].endpoint());
+    nodes.push(TestNode::builder(&network).network_config(config).create());
+    poll_and_resend(&mut nodes, &mut []);
+    let new_name = nodes[MIN_SECTION_SIZE].name();
+
+    for node in nodes.iter_mut().take(MIN_SECTION_SIZE) {
+        expect_any_event!(
+            node,
+            Event::RefreshRequired { ref new_members, .. } if new_members.contains(&new_name)
+        );
+    }
+
+    // The first node offers its data to the new member, which asks for all of it.
+    let holder = nodes[0].name();
+    unwrap!(nodes[0].inner.send_data_inventory(new_name, names.clone()));
+    let _ = poll_all(&mut nodes, &mut []);
+    expect_any_event!(
+        nodes[MIN_SECTION_SIZE],
+        Event::DataInventory { src, names: ref event_names }
+            if src == holder && *event_names == names
+    );
+    unwrap!(nodes[MIN_SECTION_SIZE]
+        .inner
+        .request_republish(holder, names.clone()));
+    let _ = poll_all(&mut nodes, &mut []);
+
+    // The request is passed on with the next batch.
+    FakeClock::advance_time(1001);
+    let _ = poll_all(&mut nodes, &mut []);
+    expect_any_event!(
+        nodes[0],
+        Event::RepublishRequested { dst, names: ref event_names }
+            if dst == new_name && *event_names == names
+    );
+}
+
+#[test]
+fn section_unstable_after_rapid_churn() {
+    let min_section_size = 8;
+    let initial_count = 5;
+    let joining_count = 3;
+    let guard = StabilityGuard {
+        window: joining_count as u64,
+        max_joined_numerator: 1,
+        max_joined_denominator: 4,
+        hold_secs: 60 * 60,
+    };
+    let hold_millis = guard.hold_secs * 1000 + 1;
+
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, initial_count);
+    for node in nodes.iter_mut() {
+        node.inner.set_stability_guard(Some(guard));
+    }
+
+    // After a quiet period, the section is considered stable regardless of how it was formed.
+    FakeClock::advance_time(hold_millis);
+    poll_and_resend(&mut nodes, &mut []);
+    for node in nodes.iter_mut() {
+        while node.inner.try_next_ev().is_ok() {}
+        assert_eq!(unwrap!(node.inner.chain()).instability(), None);
+    }
+
+    // A burst of joining nodes makes up more than the allowed fraction of the section.
+    for _ in 0..joining_count {
+        let config = NetworkConfig::node().with_hard_coded_contact(nodes[0].endpoint());
+        nodes.push(TestNode::builder(&network).network_config(config).create());
+        poll_and_resend(&mut nodes, &mut []);
+    }
+    verify_invariant_for_all_nodes(&network, &mut nodes);
+
+    for node in nodes.iter_mut().take(initial_count) {
+        assert_eq!(
+            unwrap!(node.inner.chain()).instability(),
+            Some((joining_count, min_section_size))
+        );
+        expect_any_event!(
+            node,
+            Event::SectionUnstable { joined, size }
+                if joined == joining_count && size == min_section_size
+        );
+    }
+
+    // Once the section stopped changing for long enough, the guard is lifted.
+    FakeClock::advance_time(hold_millis);
+    poll_and_resend(&mut nodes, &mut []);
+    for node in nodes.iter_mut().take(initial_count) {
+        assert_eq!(unwrap!(node.inner.chain()).instability(), None);
+        while node.inner.try_next_ev().is_ok() {}
+    }
+
+    // Wait for the next tick, which no longer reports the section as unstable.
+    FakeClock::advance_time(20 * 1000);
+    let _ = poll_all(&mut nodes, &mut []);
+    for node in nodes.iter_mut().take(initial_count) {
+        while let Ok(event) = node.inner.try_next_ev() {
+            if let Event::SectionUnstable { .. } = event {
+                panic!("{} still reports an unstable section.", node.name());
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! A harness checking small networks under many different orders of message delivery.
+//!
+//! Each run rebuilds the same network from a fixed seed and triggers a churn event. The queued
+//! messages are then delivered one at a time, in the order picked by the `Strategy`, and the chain
+//! of every node is checked after each delivery. Nodes can't be cloned, so instead of branching off
+//! a saved state, every run replays its choices from the start.
+//!
+//! Deliveries to different nodes commute. So when exploring exhaustively, of two such deliveries
+//! which were both possible before either of them happened, only the order delivering to the lower
+//! address first is explored.
+
+use super::{
+    create_connected_nodes, poll_and_resend, verify_invariant_for_all_nodes, Nodes, TestNode,
+    MIN_SECTION_SIZE,
+};
+use fake_clock::FakeClock;
+use maidsafe_utilities::SeededRng;
+use rand::Rng;
+use routing::{mock::Network, verify_chain_invariant, EventStream, NetworkConfig};
+use std::{cmp, iter, net::SocketAddr};
+
+/// The seed all runs rebuild the network from.
+const SEED: [u32; 4] = [2019, 7, 13, 42];
+/// The number of clock advances in a row without any message to deliver after which a run ends.
+const MAX_IDLE_ADVANCES: usize = 10;
+/// The number of deliveries after which a run is cut short.
+const MAX_STEPS: usize = 5000;
+
+type Delivery = (SocketAddr, SocketAddr);
+
+/// How the order of deliveries is chosen.
+enum Strategy {
+    /// Explores every order of the first `depth` deliveries, up to `max_runs` runs. Later
+    /// deliveries happen in a fixed order.
+    Exhaustive { depth: usize, max_runs: usize },
+    /// Performs `runs` runs, each delivering in a random order.
+    Random { runs: usize },
+}
+
+/// Runs `scenario` under the orders of delivery picked by `strategy`. The scenario sets up the
+/// network and triggers a churn event, leaving the resulting messages undelivered. Returns the
+/// number of runs performed.
+fn explore<S>(strategy: Strategy, scenario: S) -> usize
+where
+    S: Fn(&Network) -> Nodes,
+{
+    match strategy {
+        Strategy::Exhaustive { depth, max_runs } => {
+            // The choices of the current run, and the number of options each had.
+            let mut choices: Vec<usize> = Vec::new();
+            for runs in 1..=max_runs {
+                let options = run(&scenario, &choices, depth, &mut |_| 0);
+                choices.resize(options.len(), 0);
+                // Move on to the next unexplored order: the last branching step with an option
+                // left takes its next one, and the steps after it start over.
+                while let Some(choice) = choices.pop() {
+                    let step = choices.len();
+                    if step < depth && choice + 1 < options[step] {
+                        choices.push(choice + 1);
+                        break;
+                    }
+                }
+                if choices.is_empty() {
+                    return runs;
+                }
+            }
+            max_runs
+        }
+        Strategy::Random { runs } => {
+            let mut rng = SeededRng::new();
+            println!("Delivery order {:?}", rng);
+            for _ in 0..runs {
+                let _ = run(&scenario, &[], 0, &mut |count| rng.gen_range(0, count));
+            }
+            runs
+        }
+    }
+}
+
+// Runs `scenario` once, taking the choices in `prefix` first and the ones returned by `choose`
+// for the number of options afterwards. Only the first `reduce_depth` steps skip orders that are
+// equivalent to explored ones. Returns the number of options at each step.
+fn run<S, C>(scenario: &S, prefix: &[usize], reduce_depth: usize, choose: &mut C) -> Vec<usize>
+where
+    S: Fn(&Network) -> Nodes,
+    C: FnMut(usize) -> usize,
+{
+    let network = Network::new(MIN_SECTION_SIZE, Some(SEED));
+    let mut nodes = scenario(&network);
+
+    let mut options_per_step = Vec::new();
+    let mut previous: Option<(Delivery, Vec<Delivery>)> = None;
+    let mut idle_advances = 0;
+    while options_per_step.len() < MAX_STEPS && idle_advances < MAX_IDLE_ADVANCES {
+        let pending = network.pending_connections();
+        if pending.is_empty() {
+            // Let timers fire, e.g. for gossip or resource proofs.
+            FakeClock::advance_time(1001);
+            poll_nodes(&mut nodes);
+            idle_advances += 1;
+            previous = None;
+            continue;
+        }
+        idle_advances = 0;
+
+        let step = options_per_step.len();
+        let options = if step < reduce_depth {
+            reduce(&pending, previous.as_ref())
+        } else {
+            pending.clone()
+        };
+        // If the run diverged from the one the prefix was recorded in, stay within the options.
+        let choice = prefix
+            .get(step)
+            .cloned()
+            .unwrap_or_else(|| choose(options.len()));
+        let delivery = options[cmp::min(choice, options.len() - 1)];
+        options_per_step.push(options.len());
+
+        let _ = network.deliver_next(delivery.0, delivery.1);
+        poll_nodes(&mut nodes);
+        // Until the network goes quiet, nodes may know different versions of a section, so only
+        // each chain by itself has to be consistent.
+        for chain in nodes.iter().filter_map(|node| node.inner.chain()) {
+            verify_chain_invariant(iter::once(chain), MIN_SECTION_SIZE);
+        }
+        previous = Some((delivery, pending));
+    }
+
+    poll_and_resend(&mut nodes, &mut []);
+    verify_invariant_for_all_nodes(&network, &mut nodes);
+    options_per_step
+}
+
+// Returns the deliveries in `pending` worth exploring after `previous`, the last delivery and the
+// deliveries pending before it. A delivery to a lower address than the last one, which was already
+// pending before it, leads to a state we reach by delivering it first instead.
+fn reduce(pending: &[Delivery], previous: Option<&(Delivery, Vec<Delivery>)>) -> Vec<Delivery> {
+    let (last, pending_before) = match previous {
+        Some(previous) => previous,
+        None => return pending.to_vec(),
+    };
+    let key = |addr: &SocketAddr| (addr.ip(), addr.port());
+    let reduced: Vec<_> = pending
+        .iter()
+        .filter(|delivery| key(&delivery.1) >= key(&last.1) || !pending_before.contains(delivery))
+        .cloned()
+        .collect();
+    if reduced.is_empty() {
+        // Everything left was explored in another order; finish this run without branching.
+        pending.to_vec()
+    } else {
+        reduced
+    }
+}
+
+fn poll_nodes(nodes: &mut [TestNode]) {
+    while nodes.iter_mut().any(TestNode::poll) {}
+}
+
+fn add_node(network: &Network, nodes: &mut Nodes) {
+    let network_config = NetworkConfig::node().with_hard_coded_contact(nodes[0].endpoint());
+    nodes.push(
+        TestNode::builder(network)
+            .network_config(network_config)
+            .create(),
+    );
+}
+
+#[test]
+fn node_joins_under_all_orders() {
+    let runs = explore(
+        Strategy::Exhaustive {
+            depth: 6,
+            max_runs: 200,
+        },
+        |network| {
+            let mut nodes = create_connected_nodes(network, MIN_SECTION_SIZE);
+            add_node(network, &mut nodes);
+            nodes
+        },
+    );
+    assert!(runs > 1);
+}
+
+#[test]
+fn node_leaves_under_random_orders() {
+    let _ = explore(Strategy::Random { runs: 20 }, |network| {
+        let mut nodes = create_connected_nodes(network, MIN_SECTION_SIZE + 2);
+        drop(nodes.remove(1));
+        nodes
+    });
+}
+
+#[test]
+fn churn_under_random_orders() {
+    let _ = explore(Strategy::Random { runs: 20 }, |network| {
+        let mut nodes = create_connected_nodes(network, MIN_SECTION_SIZE + 2);
+        drop(nodes.remove(1));
+        add_node(network, &mut nodes);
+        nodes
+    });
+}
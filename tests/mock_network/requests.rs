@@ -6,19 +6,35 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{create_connected_clients, create_connected_nodes, gen_immutable_data, poll_all};
+use super::{
+    add_connected_nodes_until_split, create_connected_clients, create_connected_nodes,
+    current_sections, gen_bytes, gen_immutable_data, poll_all, TestClient, TestNode,
+    MIN_SECTION_SIZE,
+};
+use fake_clock::FakeClock;
+use rand::Rng;
 use routing::{
-    mock::Network, Authority, ClientError, Event, EventStream, MessageId, Request, Response,
-    QUORUM_DENOMINATOR, QUORUM_NUMERATOR,
+    constants,
+    mock::{replay::Capture, Network},
+    Authority, ClientConfig, ClientError, DataMap, DataMapAssembler, EntryAction, EntryActions,
+    Event, EventStream, ImmutableData, MessageId, NetworkConfig, Request, Response, RetryPolicy,
+    SectionKeyInfo, XorName, MAX_CHUNK_SIZE,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    time::Duration,
 };
 
 #[test]
 fn successful_put_request() {
     let min_section_size = 8;
-    let quorum = 1 + (min_section_size * QUORUM_NUMERATOR) / QUORUM_DENOMINATOR;
     let network = Network::new(min_section_size, None);
     let mut rng = network.new_rng();
     let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let quorum = nodes[0]
+        .chain()
+        .network_params()
+        .quorum_size(min_section_size);
     let mut clients = create_connected_clients(&network, &mut nodes, 1);
 
     let dst = Authority::ClientManager(clients[0].name());
@@ -61,10 +77,13 @@ fn successful_put_request() {
 #[test]
 fn successful_get_request() {
     let min_section_size = 8;
-    let quorum = 1 + (min_section_size * QUORUM_NUMERATOR) / QUORUM_DENOMINATOR;
     let network = Network::new(min_section_size, None);
     let mut rng = network.new_rng();
     let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let quorum = nodes[0]
+        .chain()
+        .network_params()
+        .quorum_size(min_section_size);
     let mut clients = create_connected_clients(&network, &mut nodes, 1);
 
     let data = gen_immutable_data(&mut rng, 1024);
@@ -145,10 +164,13 @@ fn successful_get_request() {
 #[test]
 fn failed_get_request() {
     let min_section_size = 8;
-    let quorum = 1 + (min_section_size * QUORUM_NUMERATOR) / QUORUM_DENOMINATOR;
     let network = Network::new(min_section_size, None);
     let mut rng = network.new_rng();
     let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let quorum = nodes[0]
+        .chain()
+        .network_params()
+        .quorum_size(min_section_size);
     let mut clients = create_connected_clients(&network, &mut nodes, 1);
 
     let data = gen_immutable_data(&mut rng, 1024);
@@ -225,3 +247,569 @@ fn failed_get_request() {
 
     assert_eq!(response_received_count, 1);
 }
+
+#[test]
+fn successful_relayed_get_request() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+
+    let data = gen_immutable_data(&mut rng, 1024);
+    let manager = Authority::ClientManager(clients[0].name());
+    let target = Authority::NaeManager(*data.name());
+    let message_id = MessageId::new();
+    let request = Request::GetIData {
+        name: *data.name(),
+        msg_id: message_id,
+    };
+
+    assert!(clients[0]
+        .inner
+        .relayed_get(manager, target, request)
+        .is_ok());
+
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    // The client managers send the GET on the client's behalf.
+    for node in nodes.iter_mut().filter(|n| n.is_recipient(&manager)) {
+        loop {
+            match node.try_next_ev() {
+                Ok(Event::RequestReceived {
+                    request: Request::RelayedGet { dst, msg_id, .. },
+                    dst: req_dst,
+                    ..
+                }) => {
+                    assert_eq!(dst, target);
+                    assert_eq!(msg_id, message_id);
+                    unwrap!(node
+                        .inner
+                        .send_get_idata_request(req_dst, dst, *data.name(), msg_id));
+                    break;
+                }
+                Ok(_) => (),
+                _ => panic!("Event::RequestReceived not received"),
+            }
+        }
+    }
+
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    for node in nodes.iter_mut().filter(|n| n.is_recipient(&target)) {
+        loop {
+            match node.try_next_ev() {
+                Ok(Event::RequestReceived {
+                    request: Request::GetIData { msg_id, .. },
+                    src,
+                    dst,
+                }) => {
+                    assert_eq!(src, manager);
+                    unwrap!(node
+                        .inner
+                        .send_get_idata_response(dst, src, Ok(data.clone()), msg_id));
+                    break;
+                }
+                Ok(_) => (),
+                _ => panic!("Event::RequestReceived not received"),
+            }
+        }
+    }
+
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    let target_chain = unwrap!(nodes.iter().find(|n| n.is_recipient(&target))).chain();
+    let trusted_key =
+        SectionKeyInfo::from_section_info(target_chain.our_info(), *target_chain.network_params());
+
+    let mut response_received_count = 0;
+    while let Ok(event) = clients[0].inner.try_next_ev() {
+        if let Event::ResponseReceived {
+            response:
+                Response::RelayedGet {
+                    res: Ok(relayed),
+                    msg_id,
+                },
+            ..
+        } = event
+        {
+            response_received_count += 1;
+            assert_eq!(msg_id, message_id);
+            match unwrap!(relayed.verify(&target, &trusted_key)) {
+                Response::GetIData {
+                    res: Ok(res_data), ..
+                } => assert_eq!(res_data, data),
+                response => panic!("Unexpected response {:?}", response),
+            }
+            assert!(relayed.verify(&manager, &trusted_key).is_err());
+        }
+    }
+
+    assert_eq!(response_received_count, 1);
+}
+
+// Handles the requests for `data` every node received, standing in for the vaults: `PutIData`
+// stores it, `GetIData` responds with it if stored. Returns the names of the nodes that received
+// such a request.
+fn handle_data_requests(
+    nodes: &mut [TestNode],
+    data: &ImmutableData,
+    stored: &mut HashMap<XorName, ImmutableData>,
+) -> BTreeSet<XorName> {
+    let mut recipients = BTreeSet::new();
+    for node in nodes.iter_mut() {
+        while let Ok(event) = node.try_next_ev() {
+            match event {
+                Event::RequestReceived {
+                    request: Request::PutIData { data: req_data, .. },
+                    ..
+                } => {
+                    if req_data == *data {
+                        let _ = recipients.insert(node.name());
+                        let _ = stored.insert(*req_data.name(), req_data);
+                    }
+                }
+                Event::RequestReceived {
+                    request: Request::GetIData { name, msg_id },
+                    src,
+                    dst,
+                } => {
+                    if name == *data.name() {
+                        let _ = recipients.insert(node.name());
+                        let res = stored.get(&name).cloned().ok_or(ClientError::NoSuchData);
+                        unwrap!(node.inner.send_get_idata_response(dst, src, res, msg_id));
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+    recipients
+}
+
+#[test]
+fn data_round_trip_across_split() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+
+    // Store the data with the single section.
+    let data = gen_immutable_data(&mut rng, 1024);
+    let dst = Authority::NaeManager(*data.name());
+    let mut stored = HashMap::new();
+    unwrap!(clients[0]
+        .inner
+        .put_idata(dst, data.clone(), MessageId::new()));
+    let _ = poll_all(&mut nodes, &mut clients);
+    let recipients = handle_data_requests(&mut nodes, &data, &mut stored);
+    let params = *nodes[0].chain().network_params();
+    assert!(recipients.len() >= params.quorum_size(MIN_SECTION_SIZE));
+
+    // Split the section the data is stored in. The vaults would relocate the data to its new
+    // managers; `stored` stands in for that.
+    add_connected_nodes_until_split(&network, &mut nodes, vec![1, 1], false);
+    let new_prefix = unwrap!(current_sections(&nodes)
+        .into_iter()
+        .find(|pfx| pfx.matches(data.name())));
+    let new_managers: BTreeSet<_> = nodes
+        .iter()
+        .map(TestNode::name)
+        .filter(|name| new_prefix.matches(name))
+        .collect();
+
+    // The request must reach the child section, and only it.
+    let message_id = MessageId::new();
+    unwrap!(clients[0].inner.get_idata(dst, *data.name(), message_id));
+    let _ = poll_all(&mut nodes, &mut clients);
+    let recipients = handle_data_requests(&mut nodes, &data, &mut stored);
+    assert!(
+        recipients.is_subset(&new_managers),
+        "GET delivered outside of {:?}: {:?}",
+        new_prefix,
+        recipients
+    );
+    assert!(recipients.len() >= params.quorum_size(new_managers.len()));
+
+    let _ = poll_all(&mut nodes, &mut clients);
+    let mut response_received_count = 0;
+    while let Ok(event) = clients[0].inner.try_next_ev() {
+        if let Event::ResponseReceived {
+            response: Response::GetIData { res, msg_id },
+            ..
+        } = event
+        {
+            assert_eq!(msg_id, message_id);
+            assert_eq!(unwrap!(res), data);
+            response_received_count += 1;
+        }
+    }
+    assert_eq!(response_received_count, 1);
+}
+
+#[test]
+fn replayed_request_is_not_handled_twice() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+    let addr = nodes[0].endpoint();
+
+    // Record what the node receives while handling a request.
+    network.capture_messages_to(addr);
+    let data = gen_immutable_data(&mut rng, 1024);
+    let dst = Authority::NaeManager(*data.name());
+    unwrap!(clients[0]
+        .inner
+        .put_idata(dst, data.clone(), MessageId::new()));
+    let _ = poll_all(&mut nodes, &mut clients);
+    let capture = network.take_capture(&addr);
+    assert!(!capture.is_empty());
+    let capture = unwrap!(Capture::from_bytes(&unwrap!(capture.to_bytes())));
+
+    expect_any_event!(
+        nodes[0],
+        Event::RequestReceived {
+            request: Request::PutIData { .. },
+            ..
+        }
+    );
+    while nodes[0].inner.try_next_ev().is_ok() {}
+
+    // Delivering the same messages again must not raise the request again.
+    unwrap!(network.replay(&addr, &capture));
+    let _ = poll_all(&mut nodes, &mut clients);
+    while let Ok(event) = nodes[0].inner.try_next_ev() {
+        if let Event::RequestReceived { request, .. } = event {
+            panic!("Replayed request handled again: {:?}", request);
+        }
+    }
+}
+
+#[test]
+fn large_get_request() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE + 1);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+
+    let content = gen_bytes(&mut rng, 2 * MAX_CHUNK_SIZE + 1024);
+    let (data_map, chunks) = DataMap::split(&content);
+    assert_eq!(chunks.len(), 3);
+    let chunks: HashMap<_, _> = chunks
+        .into_iter()
+        .map(|chunk| (*chunk.name(), chunk))
+        .collect();
+
+    let mut assembler = DataMapAssembler::new(data_map);
+    let mut assembled = Vec::new();
+    let mut requests = unwrap!(clients[0].inner.get_large_idata(&assembler));
+    assert_eq!(requests.len(), 3);
+
+    while !requests.is_empty() {
+        let _ = poll_all(&mut nodes, &mut clients);
+        for node in &mut nodes {
+            while let Ok(event) = node.try_next_ev() {
+                if let Event::RequestReceived {
+                    request: Request::GetIData { name, msg_id },
+                    src,
+                    dst,
+                } = event
+                {
+                    unwrap!(node.inner.send_get_idata_response(
+                        dst,
+                        src,
+                        Ok(chunks[&name].clone()),
+                        msg_id,
+                    ));
+                }
+            }
+        }
+
+        let _ = poll_all(&mut nodes, &mut clients);
+        while let Ok(event) = clients[0].inner.try_next_ev() {
+            if let Event::ResponseReceived {
+                response:
+                    Response::GetIData {
+                        res: Ok(chunk),
+                        msg_id,
+                    },
+                ..
+            } = event
+            {
+                let before = requests.len();
+                requests.retain(|&(name, id)| name != *chunk.name() || id != msg_id);
+                assert_eq!(requests.len() + 1, before);
+                assert!(!unwrap!(assembler.add_chunk(&chunk)));
+                assembled.extend(assembler.take_ready());
+            }
+        }
+    }
+
+    assert!(assembler.is_complete());
+    assert_eq!(assembled, content);
+}
+
+// Returns the source of the `WatchMData` requests for `name` received by the members of `dst`.
+fn watch_requests(
+    nodes: &mut [TestNode],
+    dst: &Authority<XorName>,
+    name: XorName,
+) -> Vec<Authority<XorName>> {
+    let mut watchers = Vec::new();
+    for node in nodes.iter_mut().filter(|node| node.is_recipient(dst)) {
+        while let Ok(event) = node.try_next_ev() {
+            if let Event::RequestReceived {
+                request: Request::WatchMData { name: req_name, .. },
+                src,
+                ..
+            } = event
+            {
+                assert_eq!(req_name, name);
+                watchers.push(src);
+            }
+        }
+    }
+    watchers
+}
+
+#[test]
+fn watch_mdata() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE + 1);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+
+    let name: XorName = rng.gen();
+    let tag = 10_000;
+    let dst = Authority::NaeManager(name);
+    unwrap!(clients[0].inner.watch_mdata(name, tag, MessageId::new()));
+    let _ = poll_all(&mut nodes, &mut clients);
+    let watchers = watch_requests(&mut nodes, &dst, name);
+    assert!(!watchers.is_empty());
+
+    let actions: BTreeMap<_, EntryAction> = EntryActions::new()
+        .insert(b"key".to_vec(), b"value".to_vec(), 0)
+        .into();
+    let msg_id = MessageId::new();
+    for node in nodes.iter_mut().filter(|node| node.is_recipient(&dst)) {
+        unwrap!(node.inner.send_mdata_changed_notification(
+            dst,
+            watchers[0],
+            name,
+            tag,
+            actions.clone(),
+            msg_id,
+        ));
+    }
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    let mut changes = 0;
+    while let Ok(event) = clients[0].inner.try_next_ev() {
+        if let Event::MDataChanged {
+            name: changed_name,
+            tag: changed_tag,
+            actions: ref changed_actions,
+        } = event
+        {
+            assert_eq!((changed_name, changed_tag), (name, tag));
+            assert_eq!(*changed_actions, actions);
+            changes += 1;
+        }
+    }
+    assert_eq!(changes, 1);
+
+    // The watch is renewed periodically, so new members of the section learn about it.
+    FakeClock::advance_time(constants::MDATA_WATCH_REFRESH_INTERVAL.as_secs() * 1000 + 1);
+    let _ = poll_all(&mut nodes, &mut clients);
+    assert!(!watch_requests(&mut nodes, &dst, name).is_empty());
+
+    // Once unwatched, it isn't renewed anymore.
+    unwrap!(clients[0].inner.unwatch_mdata(name, tag, MessageId::new()));
+    let _ = poll_all(&mut nodes, &mut clients);
+    FakeClock::advance_time(constants::MDATA_WATCH_REFRESH_INTERVAL.as_secs() * 1000 + 1);
+    let _ = poll_all(&mut nodes, &mut clients);
+    assert!(watch_requests(&mut nodes, &dst, name).is_empty());
+}
+
+#[test]
+fn unanswered_request_is_retried_until_it_fails() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE);
+    let network_config = NetworkConfig::client().with_hard_coded_contact(nodes[0].endpoint());
+    let policy = RetryPolicy {
+        max_attempts: 3,
+        response_timeout: Duration::from_secs(10),
+        initial_backoff: Duration::from_secs(1),
+        max_backoff: Duration::from_secs(1),
+        retry_mutations: false,
+    };
+    let client_config = ClientConfig::builder().retry_policy(policy).build();
+    let mut clients = vec![TestClient::with_config(
+        &network,
+        Some(network_config),
+        client_config,
+    )];
+    let _ = poll_all(&mut nodes, &mut clients);
+    expect_next_event!(clients[0], Event::Connected);
+
+    // Nodes don't answer requests for data by themselves, so the client has to give up.
+    let name: XorName = rand::random();
+    let msg_id = MessageId::new();
+    unwrap!(clients[0]
+        .inner
+        .get_idata(Authority::NaeManager(name), name, msg_id));
+
+    // Three attempts of 10 seconds each, with backoffs of a second between them.
+    for _ in 0..6 {
+        let _ = poll_all(&mut nodes, &mut clients);
+        FakeClock::advance_time(11 * 1000);
+    }
+    let _ = poll_all(&mut nodes, &mut clients);
+    expect_any_event!(
+        clients[0],
+        Event::RequestFailed { msg_id: failed_id, .. } if failed_id == msg_id
+    );
+}
+
+#[test]
+fn put_request_sealed_to_fetched_section_key() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE + 1);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+
+    // The client doesn't know the key of the section managing the data yet, so it holds the put
+    // back until its client manager, whose key it trusts, told it that key. The put is then sealed
+    // to that key, and opened by the section before it is delivered.
+    let data = gen_immutable_data(&mut rng, 1024);
+    let dst = Authority::NaeManager(*data.name());
+    let msg_id = MessageId::new();
+    unwrap!(clients[0].inner.put_idata(dst, data.clone(), msg_id));
+    let _ = poll_all(&mut nodes, &mut clients);
+    let mut received = 0;
+    for node in nodes.iter_mut().filter(|node| node.is_recipient(&dst)) {
+        while let Ok(event) = node.try_next_ev() {
+            match event {
+                Event::RequestReceived {
+                    request:
+                        Request::PutIData {
+                            data: ref req_data,
+                            msg_id: req_msg_id,
+                        },
+                    ..
+                } => {
+                    assert_eq!((req_data, req_msg_id), (&data, msg_id));
+                    received += 1;
+                }
+                Event::RequestReceived {
+                    request: Request::Sealed { .. },
+                    ..
+                } => panic!("Sealed request delivered to the vault"),
+                _ => (),
+            }
+        }
+    }
+    let params = nodes[0].chain().network_params();
+    assert!(received >= params.quorum_size(MIN_SECTION_SIZE));
+}
+
+#[test]
+fn delivery_acknowledged_to_client() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+
+    let dst = Authority::ClientManager(clients[0].name());
+    let data = gen_immutable_data(&mut rng, 1024);
+    let msg_id = MessageId::new();
+    unwrap!(clients[0].inner.put_idata(dst, data, msg_id));
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    expect_any_event!(
+        clients[0],
+        Event::MessageDelivered { msg_id: acked_id } if acked_id == msg_id
+    );
+}
+
+#[test]
+fn client_resumes_session() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    let token = loop {
+        match clients[0].inner.try_next_ev() {
+            Ok(Event::SessionStarted(token)) => break token,
+            Ok(_) => (),
+            other => panic!("Expected Ok(Event::SessionStarted), got {:?}", other),
+        }
+    };
+    assert_eq!(token.client_id(), Some(clients[0].full_id.public_id()));
+
+    // After a restart, the client resumes its session through another proxy without being issued
+    // a new token.
+    let full_id = clients[0].full_id.clone();
+    drop(clients);
+    let _ = poll_all(&mut nodes, &mut []);
+
+    let network_config = NetworkConfig::client().with_hard_coded_contact(nodes[1].endpoint());
+    let mut clients = vec![TestClient::resume(
+        &network,
+        Some(network_config),
+        full_id,
+        token,
+    )];
+    let _ = poll_all(&mut nodes, &mut clients);
+    expect_next_event!(clients[0], Event::Connected);
+    expect_no_event!(clients[0]);
+}
+
+#[test]
+fn client_fails_over_to_another_proxy() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE + 1);
+    let network_config = NetworkConfig::client()
+        .with_hard_coded_contacts(vec![nodes[0].endpoint(), nodes[1].endpoint()]);
+    let client_endpoint = network.gen_addr();
+    let mut clients = vec![TestClient::new(
+        &network,
+        Some(network_config),
+        Some(client_endpoint),
+    )];
+    let _ = poll_all(&mut nodes, &mut clients);
+    expect_next_event!(clients[0], Event::Connected);
+
+    // Send a request, and lose the proxy before it could relay it.
+    let proxy_index = unwrap!(
+        (0..2).find(|&index| network.is_connected(&client_endpoint, &nodes[index].endpoint()))
+    );
+    let name: XorName = rand::random();
+    let dst = Authority::NaeManager(name);
+    let msg_id = MessageId::new();
+    unwrap!(clients[0].inner.get_idata(dst, name, msg_id));
+    drop(nodes.remove(proxy_index));
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    // The client reconnects through the other contact, which relays the request again.
+    expect_any_event!(clients[0], Event::Connected);
+    let mut request_received_count = 0;
+    for node in nodes.iter_mut().filter(|node| node.is_recipient(&dst)) {
+        while let Ok(event) = node.try_next_ev() {
+            match event {
+                Event::RequestReceived {
+                    request:
+                        Request::GetIData {
+                            msg_id: req_message_id,
+                            ..
+                        },
+                    ..
+                } if req_message_id == msg_id => request_received_count += 1,
+                _ => (),
+            }
+        }
+    }
+    assert!(request_received_count > 0);
+}
@@ -12,14 +12,12 @@ use itertools::Itertools;
 use rand::Rng;
 use routing::{
     mock::Network, test_consts::CONNECTING_PEER_TIMEOUT_SECS, verify_chain_invariant, Authority,
-    Cache, Chain, Client, Config, DevConfig, Event, EventStream, FullId, ImmutableData,
-    NetworkConfig, Node, NullCache, Prefix, PublicId, Request, Response, XorName,
-    XorTargetInterval, Xorable,
+    Chain, Client, ClientConfig, Config, DevConfig, Event, EventStream, FullId, ImmutableData,
+    NetworkConfig, Node, Prefix, PublicId, SessionToken, XorName, XorTargetInterval, Xorable,
 };
 use std::{
-    cell::RefCell,
     cmp,
-    collections::{BTreeSet, HashMap},
+    collections::BTreeSet,
     iter,
     net::SocketAddr,
     ops::{Deref, DerefMut},
@@ -36,6 +34,9 @@ const MAX_POLL_CALLS: usize = 1000;
 // Duration clients expect a response by.
 const CLIENT_MSG_EXPIRY_DUR_SECS: u64 = 90;
 
+// Number of immutable data chunks cached by nodes created with `TestNodeBuilder::cache(true)`.
+const TEST_CACHE_SIZE: usize = 100;
+
 // ----- Typs -----
 type PrefixAndSize = (Prefix<XorName>, usize);
 
@@ -67,6 +68,7 @@ fn create_config(network: &Network) -> Config {
             min_section_size: Some(network.min_section_size()),
             ..DevConfig::default()
         }),
+        ..Config::default()
     }
 }
 
@@ -119,7 +121,7 @@ impl TestNode {
             first_node: false,
             network_config: None,
             endpoint: None,
-            cache: Box::new(NullCache),
+            relay_cache_size: None,
         }
     }
 
@@ -128,16 +130,16 @@ impl TestNode {
         first_node: bool,
         network_config: Option<NetworkConfig>,
         endpoint: Option<SocketAddr>,
-        cache: Box<dyn Cache>,
+        relay_cache_size: Option<usize>,
     ) -> Self {
         let endpoint = endpoint.unwrap_or_else(|| network.gen_addr());
         network.set_next_addr(endpoint);
 
-        let config = create_config(network);
-        let builder = Node::builder()
-            .cache(cache)
-            .first(first_node)
-            .config(config);
+        let config = Config {
+            relay_cache_size,
+            ..create_config(network)
+        };
+        let builder = Node::builder().first(first_node).config(config);
         let builder = if let Some(network_config) = network_config {
             builder.network_config(network_config)
         } else {
@@ -207,7 +209,7 @@ pub struct TestNodeBuilder<'a> {
     first_node: bool,
     network_config: Option<NetworkConfig>,
     endpoint: Option<SocketAddr>,
-    cache: Box<dyn Cache>,
+    relay_cache_size: Option<usize>,
 }
 
 impl<'a> TestNodeBuilder<'a> {
@@ -227,10 +229,10 @@ impl<'a> TestNodeBuilder<'a> {
     }
 
     pub fn cache(mut self, use_cache: bool) -> Self {
-        self.cache = if use_cache {
-            Box::new(TestCache::new())
+        self.relay_cache_size = if use_cache {
+            Some(TEST_CACHE_SIZE)
         } else {
-            Box::new(NullCache)
+            None
         };
 
         self
@@ -242,7 +244,7 @@ impl<'a> TestNodeBuilder<'a> {
             self.first_node,
             self.network_config,
             self.endpoint,
-            self.cache,
+            self.relay_cache_size,
         )
     }
 }
@@ -297,38 +299,51 @@ impl TestClient {
         }
     }
 
-    pub fn name(&self) -> XorName {
-        *unwrap!(self.inner.id()).name()
-    }
-}
-
-// -----  TestCache  -----
+    pub fn with_config(
+        network: &Network,
+        network_config: Option<NetworkConfig>,
+        client_config: ClientConfig,
+    ) -> Self {
+        let full_id = FullId::new();
+        network.set_next_addr(network.gen_addr());
 
-#[derive(Default)]
-pub struct TestCache(RefCell<HashMap<XorName, ImmutableData>>);
+        let client = unwrap!(Client::with_config(
+            Some(full_id.clone()),
+            network_config,
+            create_config(network),
+            client_config,
+        ));
 
-impl TestCache {
-    pub fn new() -> Self {
-        TestCache(RefCell::new(HashMap::new()))
+        TestClient {
+            inner: client,
+            full_id: full_id,
+        }
     }
-}
 
-impl Cache for TestCache {
-    fn get(&self, request: &Request) -> Option<Response> {
-        if let Request::GetIData { ref name, msg_id } = *request {
-            self.0.borrow().get(name).map(|data| Response::GetIData {
-                res: Ok(data.clone()),
-                msg_id: msg_id,
-            })
-        } else {
-            None
+    pub fn resume(
+        network: &Network,
+        network_config: Option<NetworkConfig>,
+        full_id: FullId,
+        token: SessionToken,
+    ) -> Self {
+        network.set_next_addr(network.gen_addr());
+
+        let client = unwrap!(Client::resume(
+            full_id.clone(),
+            token,
+            network_config,
+            create_config(network),
+            ClientConfig::default(),
+        ));
+
+        TestClient {
+            inner: client,
+            full_id: full_id,
         }
     }
 
-    fn put(&self, response: Response) {
-        if let Response::GetIData { res: Ok(data), .. } = response {
-            let _ = self.0.borrow_mut().insert(*data.name(), data);
-        }
+    pub fn name(&self) -> XorName {
+        *unwrap!(self.inner.id()).name()
     }
 }
 
@@ -366,6 +381,7 @@ pub fn poll_all_until(
             handled_message = nodes.iter_mut().any(TestNode::poll);
         }
         handled_message = clients.iter_mut().any(|c| c.inner.poll()) || handled_message;
+        network.trace_round(nodes.iter().map(|node| &node.inner));
 
         // check if there were any outgoing messages which could be due to timeouts
         // that were handled via cur iter poll.
@@ -488,6 +504,14 @@ pub fn create_connected_nodes_with_cache(network: &Network, size: usize, use_cac
                 Event::NodeLost(..)
                 | Event::SectionSplit(..)
                 | Event::RestartRequired
+                | Event::VoteStalled { .. }
+                | Event::StaleVotesPurged { .. }
+                | Event::CachedEventsEvicted { .. }
+                | Event::Promoted
+                | Event::Demoted
+                | Event::RefreshRequired { .. }
+                | Event::OutOfOrderDetected { .. }
+                | Event::MessageDelivered { .. }
                 | Event::TimerTicked => (),
                 event => panic!("Got unexpected event: {:?}", event),
             }
@@ -604,6 +628,14 @@ pub fn add_connected_nodes_until_split(
         Event::NodeAdded(..)
         | Event::NodeLost(..)
         | Event::TimerTicked
+        | Event::VoteStalled { .. }
+        | Event::StaleVotesPurged { .. }
+        | Event::CachedEventsEvicted { .. }
+        | Event::Promoted
+        | Event::Demoted
+        | Event::RefreshRequired { .. }
+        | Event::OutOfOrderDetected { .. }
+        | Event::MessageDelivered { .. }
         | Event::SectionSplit(..) => (),
         event => panic!("Got unexpected event: {:?}", event),
     });